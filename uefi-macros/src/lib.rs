@@ -7,11 +7,11 @@ extern crate proc_macro;
 use proc_macro::TokenStream;
 
 use proc_macro2::TokenStream as TokenStream2;
-use quote::{TokenStreamExt, quote, quote_spanned};
+use quote::{TokenStreamExt, format_ident, quote, quote_spanned};
 use syn::spanned::Spanned;
 use syn::{
-    Error, Expr, ExprLit, ExprPath, ItemFn, ItemStruct, Lit, Visibility, parse_macro_input,
-    parse_quote, parse_quote_spanned,
+    Data, DataStruct, DeriveInput, Error, Expr, ExprLit, ExprPath, Fields, ItemFn, ItemStruct, Lit,
+    Visibility, parse_macro_input, parse_quote, parse_quote_spanned,
 };
 
 macro_rules! err {
@@ -180,6 +180,15 @@ pub fn entry(args: TokenStream, input: TokenStream) -> TokenStream {
         },
     );
 
+    // Create a guard after that, which is dropped after all locals of the
+    // entry function when it returns, to run the end-of-program checks.
+    f.block.stmts.insert(
+        1,
+        parse_quote! {
+            let _uefi_entry_guard = ::uefi::helpers::EntryGuard;
+        },
+    );
+
     // Set the required ABI.
     f.sig.abi = Some(parse_quote_spanned!(signature_span=> extern "efiapi"));
 
@@ -218,3 +227,124 @@ pub fn entry(args: TokenStream, input: TokenStream) -> TokenStream {
     };
     result.into()
 }
+
+/// Derive macro for the `Varstore` trait, for using a struct as the
+/// varstore of HII forms.
+///
+/// The struct must be `#[repr(C)]`, must not be generic, and must have named
+/// fields whose types implement `VarstoreValue`. Padding between fields is a
+/// compile-time error.
+///
+/// The macro generates a struct named like the varstore with a `Fields`
+/// suffix, with a `VarstoreField` of the same name and visibility for each
+/// field. It is available as `FIELDS` constant of the `Varstore` trait.
+///
+/// # Example
+///
+/// ```
+/// use uefi::proto::hii::varstore::Varstore;
+///
+/// #[derive(Varstore)]
+/// #[repr(C)]
+/// struct Config {
+///     enabled: u8,
+///     level: u8,
+///     timeout: u16,
+/// }
+///
+/// assert_eq!(Config::FIELDS.level.offset(), 1);
+/// ```
+#[proc_macro_derive(Varstore)]
+pub fn derive_varstore(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let ident = &input.ident;
+    let vis = &input.vis;
+
+    let mut is_repr_c = false;
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("repr"))
+    {
+        // Errors are ignored here; the compiler reports invalid reprs.
+        let _ = attr.parse_nested_meta(|meta| {
+            is_repr_c |= meta.path.is_ident("C");
+            Ok(())
+        });
+    }
+    if !is_repr_c {
+        return err!(ident, "Varstore must be #[repr(C)]").into();
+    }
+    if !input.generics.params.is_empty() {
+        return err!(input.generics.params, "Varstore must not be generic").into();
+    }
+    let fields = match &input.data {
+        Data::Struct(DataStruct {
+            fields: Fields::Named(fields),
+            ..
+        }) => &fields.named,
+        _ => return err!(ident, "Varstore must be a struct with named fields").into(),
+    };
+
+    let fields_ident = format_ident!("{}Fields", ident);
+    let field_defs = fields.iter().map(|field| {
+        let vis = &field.vis;
+        let name = &field.ident;
+        let ty = &field.ty;
+        quote!(#vis #name: ::uefi::proto::hii::varstore::VarstoreField<#ty>)
+    });
+    let field_values = fields.iter().map(|field| {
+        let name = &field.ident;
+        quote! {
+            #name: ::uefi::proto::hii::varstore::VarstoreField::new(
+                ::core::mem::offset_of!(#ident, #name)
+            )
+        }
+    });
+    let field_checks = fields.iter().map(|field| {
+        let ty = &field.ty;
+        quote_spanned!(ty.span()=> check::<#ty>();)
+    });
+    let field_sizes = fields.iter().map(|field| {
+        let ty = &field.ty;
+        quote!(::core::mem::size_of::<#ty>())
+    });
+
+    quote! {
+        #[doc = concat!("Fields of the [`", stringify!(#ident), "`] varstore.")]
+        #[derive(Clone, Copy, Debug)]
+        #vis struct #fields_ident {
+            #(#[allow(missing_docs)] #field_defs,)*
+        }
+
+        const _: () = {
+            // All fields must implement `VarstoreValue`.
+            #[allow(dead_code)]
+            fn check_fields() {
+                fn check<T: ::uefi::proto::hii::varstore::VarstoreValue>() {}
+                #(#field_checks)*
+            }
+            ::core::assert!(
+                ::core::mem::size_of::<#ident>() == 0 #(+ #field_sizes)*,
+                "Varstore must not contain padding"
+            );
+        };
+
+        // SAFETY: the fields implement `VarstoreValue` and there is no
+        // padding, as checked above.
+        unsafe impl ::uefi::proto::hii::varstore::Varstore for #ident {
+            type Fields = #fields_ident;
+
+            const FIELDS: #fields_ident = #fields_ident {
+                #(#field_values,)*
+            };
+
+            const SIZE: u16 = {
+                let size = ::core::mem::size_of::<#ident>();
+                ::core::assert!(size <= u16::MAX as usize, "Varstore too large");
+                size as u16
+            };
+        }
+    }
+    .into()
+}
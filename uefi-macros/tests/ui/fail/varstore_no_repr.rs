@@ -0,0 +1,8 @@
+use uefi::proto::hii::varstore::Varstore;
+
+#[derive(Varstore)]
+struct Config {
+    enabled: u8,
+}
+
+fn main() {}
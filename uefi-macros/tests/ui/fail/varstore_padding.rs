@@ -0,0 +1,10 @@
+use uefi::proto::hii::varstore::Varstore;
+
+#[derive(Varstore)]
+#[repr(C)]
+struct Config {
+    enabled: u8,
+    timeout: u16,
+}
+
+fn main() {}
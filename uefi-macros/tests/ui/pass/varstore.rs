@@ -0,0 +1,14 @@
+use uefi::proto::hii::varstore::Varstore;
+
+#[derive(Varstore)]
+#[repr(C)]
+pub struct Config {
+    pub enabled: u8,
+    pub level: u8,
+    pub timeout: u16,
+}
+
+const _: () = assert!(Config::FIELDS.timeout.offset() == 2);
+
+// trybuild requires a `main` function.
+fn main() {}
@@ -5,7 +5,8 @@
 //! Capsules are used to pass information to the firmware, for example to
 //! trigger a firmware update.
 
-use crate::{Guid, PhysicalAddress};
+use crate::time::Time;
+use crate::{Char16, Guid, PhysicalAddress, Status, guid, newtype_enum};
 use bitflags::bitflags;
 
 /// Descriptor that defines a scatter-gather list for passing a set of capsules
@@ -125,3 +126,337 @@ pub struct CapsuleHeader {
     /// Size in bytes of the entire capsule, including the header.
     pub capsule_image_size: u32,
 }
+
+impl CapsuleHeader {
+    /// GUID of capsules for the Firmware Management Protocol, starting with a
+    /// [`FirmwareManagementCapsuleHeader`] after the capsule header
+    /// (`EFI_FIRMWARE_MANAGEMENT_CAPSULE_ID_GUID`).
+    ///
+    /// [`FirmwareManagementCapsuleHeader`]: crate::protocol::firmware_management::FirmwareManagementCapsuleHeader
+    pub const FIRMWARE_MANAGEMENT_GUID: Guid = guid!("6dcbd5ed-e82d-4c44-bda1-7194199ad92a");
+
+    /// GUID of memory range capsules, see [`MemoryRangeCapsule`]
+    /// (`EFI_MEMORY_RANGE_CAPSULE_GUID`).
+    pub const MEMORY_RANGE_GUID: Guid = guid!("0de9f0ec-88b6-428f-977a-258f1d0e5e72");
+
+    /// GUID of JSON capsules, see [`JsonCapsuleHeader`]
+    /// (`EFI_JSON_CAPSULE_ID_GUID`).
+    pub const JSON_GUID: Guid = guid!("67d6f4cd-d6b8-4573-bf4a-de5e252d61ae");
+}
+
+/// Range of physical memory (`EFI_MEMORY_RANGE`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(C)]
+pub struct MemoryRange {
+    /// Start address of the range.
+    pub address: PhysicalAddress,
+
+    /// Size of the range in bytes.
+    pub length: u64,
+}
+
+/// Capsule asking the firmware to preserve memory ranges across a warm reset,
+/// e.g. to collect a crash dump (`EFI_MEMORY_RANGE_CAPSULE`).
+///
+/// The header's `capsule_guid` is [`CapsuleHeader::MEMORY_RANGE_GUID`], and
+/// its flags must include [`CapsuleFlags::PERSIST_ACROSS_RESET`].
+#[derive(Debug)]
+#[repr(C)]
+pub struct MemoryRangeCapsule {
+    /// Common capsule header.
+    pub header: CapsuleHeader,
+
+    /// Memory type, of type `MemoryType`, the firmware should report the
+    /// preserved ranges as after the reset. Must be in the OS-defined range
+    /// `0x8000_0000..=0xffff_ffff`.
+    pub os_requested_memory_type: u32,
+
+    /// Number of entries in `memory_ranges`.
+    pub number_of_memory_ranges: u64,
+
+    /// Ranges to preserve.
+    pub memory_ranges: [MemoryRange; 0],
+}
+
+/// Result of a memory range capsule, found in the configuration table after
+/// the reset under [`CapsuleHeader::MEMORY_RANGE_GUID`]
+/// (`EFI_MEMORY_RANGE_CAPSULE_RESULT`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(C)]
+pub struct MemoryRangeCapsuleResult {
+    /// Amount of memory in bytes the firmware needs, which the preserved
+    /// ranges must leave available.
+    pub firmware_memory_requirement: u64,
+
+    /// Number of ranges that were preserved.
+    pub number_of_memory_ranges: u64,
+}
+
+/// Header of the `Capsule####` variables reporting the result of processing
+/// a capsule (`EFI_CAPSULE_RESULT_VARIABLE_HEADER`).
+///
+/// The variables are in the [`CapsuleResultVariableHeader::VENDOR_GUID`]
+/// namespace. The header is followed by data specific to `capsule_guid`, such
+/// as a [`CapsuleResultVariableFmp`].
+#[derive(Debug)]
+#[repr(C)]
+pub struct CapsuleResultVariableHeader {
+    /// Size of the variable in bytes, including the header.
+    pub variable_total_size: u32,
+
+    /// Reserved, must be zero.
+    pub reserved: u32,
+
+    /// GUID of the processed capsule.
+    pub capsule_guid: Guid,
+
+    /// Time the capsule was processed.
+    pub capsule_processed: Time,
+
+    /// Result of processing the capsule.
+    pub capsule_status: Status,
+}
+
+impl CapsuleResultVariableHeader {
+    /// Vendor GUID of the `Capsule####`, `CapsuleMax`, and `CapsuleLast`
+    /// variables (`EFI_CAPSULE_REPORT_GUID`).
+    pub const VENDOR_GUID: Guid = guid!("39b68c46-f7fb-441b-b6ec-16b0f69821f3");
+}
+
+/// Capsule result data for Firmware Management Protocol capsules
+/// (`EFI_CAPSULE_RESULT_VARIABLE_FMP`).
+///
+/// The structure is followed by two null-terminated UCS-2 strings: the file
+/// name of the capsule, and the device path of the updated device in text
+/// form.
+#[derive(Debug)]
+#[repr(C)]
+pub struct CapsuleResultVariableFmp {
+    /// Version of this structure, currently [`Self::VERSION`].
+    pub version: u16,
+
+    /// Index of the payload within the capsule.
+    pub payload_index: u8,
+
+    /// `update_image_index` of the payload's image header.
+    pub update_image_index: u8,
+
+    /// `update_image_type_id` of the payload's image header.
+    pub update_image_type_id: Guid,
+
+    /// File name and target device path strings.
+    pub capsule_file_name_and_target: [Char16; 0],
+}
+
+impl CapsuleResultVariableFmp {
+    /// Current version of the structure.
+    pub const VERSION: u16 = 1;
+}
+
+/// Header of a JSON capsule's payload, following the [`CapsuleHeader`]
+/// (`EFI_JSON_CAPSULE_HEADER`).
+#[derive(Debug)]
+#[repr(C)]
+pub struct JsonCapsuleHeader {
+    /// Version of this structure, currently [`Self::VERSION`].
+    pub version: u32,
+
+    /// Vendor-defined identifier of the capsule, reported back in the
+    /// [`CapsuleResultVariableJson`].
+    pub capsule_id: u32,
+
+    /// Size of `payload` in bytes.
+    pub payload_length: u32,
+
+    /// JSON payload.
+    pub payload: [u8; 0],
+}
+
+impl JsonCapsuleHeader {
+    /// Current version of the structure.
+    pub const VERSION: u32 = 1;
+
+    /// GUID of the configuration table entry holding the JSON payloads of
+    /// capsules processed with [`CapsuleFlags::POPULATE_SYSTEM_TABLE`], in a
+    /// [`JsonCapsuleConfigData`] (`EFI_JSON_CAPSULE_DATA_TABLE_GUID`).
+    pub const DATA_TABLE_GUID: Guid = guid!("35e7a725-8dd2-4cac-8011-33cda8109056");
+
+    /// GUID of the configuration table entry holding JSON configuration data
+    /// provided by the platform, in a [`JsonCapsuleConfigData`]
+    /// (`EFI_JSON_CONFIG_DATA_TABLE_GUID`).
+    pub const CONFIG_DATA_TABLE_GUID: Guid = guid!("87367f87-1119-41ce-aaec-8be01101f558");
+
+    /// GUID of the configuration table entry holding the results of
+    /// processed JSON capsules, in a [`JsonCapsuleConfigData`]
+    /// (`EFI_JSON_CAPSULE_RESULT_TABLE_GUID`).
+    pub const RESULT_TABLE_GUID: Guid = guid!("dbc461c3-b3de-422a-b9b4-9886fd49a1e5");
+}
+
+/// List of JSON data items found in the configuration table
+/// (`EFI_JSON_CAPSULE_CONFIG_DATA`).
+#[derive(Debug)]
+#[repr(C)]
+pub struct JsonCapsuleConfigData {
+    /// Version of this structure, currently [`Self::VERSION`].
+    pub version: u32,
+
+    /// Size of the structure in bytes, including this header.
+    pub total_length: u32,
+
+    /// Items, each with its own length.
+    pub config_data_list: [JsonConfigDataItem; 0],
+}
+
+impl JsonCapsuleConfigData {
+    /// Current version of the structure.
+    pub const VERSION: u32 = 1;
+}
+
+/// Item of a [`JsonCapsuleConfigData`] (`EFI_JSON_CONFIG_DATA_ITEM`).
+#[derive(Debug)]
+#[repr(C)]
+pub struct JsonConfigDataItem {
+    /// Size of `config_data` in bytes.
+    pub config_data_length: u32,
+
+    /// JSON data.
+    pub config_data: [u8; 0],
+}
+
+/// Capsule result data for JSON capsules
+/// (`EFI_CAPSULE_RESULT_VARIABLE_JSON`).
+#[derive(Debug)]
+#[repr(C)]
+pub struct CapsuleResultVariableJson {
+    /// Version of this structure, currently [`Self::VERSION`].
+    pub version: u32,
+
+    /// `capsule_id` of the processed capsule's [`JsonCapsuleHeader`].
+    pub capsule_id: u32,
+
+    /// Size of `resp` in bytes.
+    pub resp_length: u32,
+
+    /// JSON response of the firmware.
+    pub resp: [u8; 0],
+}
+
+impl CapsuleResultVariableJson {
+    /// Current version of the structure.
+    pub const VERSION: u32 = 1;
+}
+
+/// Header of the EFI System Resource Table (`EFI_SYSTEM_RESOURCE_TABLE`),
+/// listing the firmware resources that can be updated with capsules.
+///
+/// The table is installed in the configuration table under `ESRT_GUID`.
+#[derive(Debug)]
+#[repr(C)]
+pub struct SystemResourceTable {
+    /// Number of entries in `entries`.
+    pub fw_resource_count: u32,
+
+    /// Number of entries that fit in the allocated table.
+    pub fw_resource_count_max: u32,
+
+    /// Version of the table format, currently [`Self::VERSION`].
+    pub fw_resource_version: u64,
+
+    /// Resource entries.
+    pub entries: [SystemResourceEntry; 0],
+}
+
+impl SystemResourceTable {
+    /// Current version of the table format.
+    pub const VERSION: u64 = 1;
+}
+
+/// Entry of the [`SystemResourceTable`] (`EFI_SYSTEM_RESOURCE_ENTRY`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[repr(C)]
+pub struct SystemResourceEntry {
+    /// GUID identifying the firmware component, used as the capsule GUID to
+    /// update it.
+    pub fw_class: Guid,
+
+    /// Type of the firmware component.
+    pub fw_type: SystemResourceType,
+
+    /// Current version of the firmware.
+    pub fw_version: u32,
+
+    /// Lowest version the firmware can be updated or rolled back to.
+    pub lowest_supported_fw_version: u32,
+
+    /// Flags to use in the [`CapsuleHeader`] of capsules updating this
+    /// firmware.
+    pub capsule_flags: u32,
+
+    /// Version of the last attempted update.
+    pub last_attempt_version: u32,
+
+    /// Result of the last attempted update.
+    pub last_attempt_status: LastAttemptStatus,
+}
+
+newtype_enum! {
+    /// Type of a firmware component in the [`SystemResourceTable`].
+    #[derive(Default)]
+    pub enum SystemResourceType: u32 => {
+        /// Unknown firmware type.
+        UNKNOWN = 0,
+        /// System firmware.
+        SYSTEM_FIRMWARE = 1,
+        /// Device firmware.
+        DEVICE_FIRMWARE = 2,
+        /// UEFI driver.
+        UEFI_DRIVER = 3,
+    }
+}
+
+newtype_enum! {
+    /// Result of the last firmware update attempt
+    /// (`LAST_ATTEMPT_STATUS_*`).
+    ///
+    /// Values in `0x1000..=0x4000` are vendor-specific errors.
+    #[derive(Default)]
+    pub enum LastAttemptStatus: u32 => {
+        /// The update succeeded.
+        SUCCESS = 0,
+        /// The update failed for an unspecified reason.
+        ERROR_UNSUCCESSFUL = 1,
+        /// Not enough resources were available.
+        ERROR_INSUFFICIENT_RESOURCES = 2,
+        /// The image version was incorrect.
+        ERROR_INCORRECT_VERSION = 3,
+        /// The image format was invalid.
+        ERROR_INVALID_FORMAT = 4,
+        /// The image failed authentication.
+        ERROR_AUTH_ERROR = 5,
+        /// The update was not attempted because of insufficient AC power.
+        ERROR_PWR_EVT_AC = 6,
+        /// The update was not attempted because of insufficient battery
+        /// power.
+        ERROR_PWR_EVT_BATT = 7,
+        /// The image's dependencies were not satisfied.
+        ERROR_UNSATISFIED_DEPENDENCIES = 8,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn abi() {
+        assert_eq!(size_of::<CapsuleHeader>(), 28);
+        assert_eq!(size_of::<MemoryRangeCapsule>(), 40);
+        assert_eq!(size_of::<CapsuleResultVariableHeader>(), 48);
+        assert_eq!(size_of::<CapsuleResultVariableFmp>(), 20);
+        assert_eq!(size_of::<JsonCapsuleHeader>(), 12);
+        assert_eq!(size_of::<JsonCapsuleConfigData>(), 8);
+        assert_eq!(size_of::<CapsuleResultVariableJson>(), 12);
+        assert_eq!(size_of::<SystemResourceTable>(), 16);
+        assert_eq!(size_of::<SystemResourceEntry>(), 40);
+    }
+}
@@ -38,6 +38,7 @@ pub use status::Status;
 pub use uguid::{Guid, guid};
 
 use core::ffi::c_void;
+use core::fmt::{self, Display, Formatter};
 
 /// Handle to an event structure.
 pub type Event = *mut c_void;
@@ -84,8 +85,44 @@ impl Boolean {
 
     /// [`Boolean`] representing `false`.
     pub const FALSE: Self = Self(0);
+
+    /// Returns whether the value is 0 or 1, the only values the
+    /// specification allows.
+    #[must_use]
+    pub const fn is_valid(self) -> bool {
+        matches!(self.0, 0 | 1)
+    }
+
+    /// Converts to a `bool`, rejecting values other than 0 or 1.
+    ///
+    /// Use this instead of the [`From`] conversion, which treats any
+    /// non-zero value as `true`, to detect malformed firmware data.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`InvalidBooleanError`] if the value is neither 0 nor 1.
+    pub const fn try_into_bool(self) -> Result<bool, InvalidBooleanError> {
+        match self.0 {
+            0 => Ok(false),
+            1 => Ok(true),
+            value => Err(InvalidBooleanError(value)),
+        }
+    }
 }
 
+/// Error returned by [`Boolean::try_into_bool`] for values other than 0 or
+/// 1. Contains the invalid value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidBooleanError(pub u8);
+
+impl Display for InvalidBooleanError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid boolean value {:#04x}", self.0)
+    }
+}
+
+impl core::error::Error for InvalidBooleanError {}
+
 impl From<bool> for Boolean {
     fn from(value: bool) -> Self {
         match value {
@@ -127,4 +164,16 @@ mod tests {
         assert!(bool::from(Boolean(0b11111110)));
         assert!(bool::from(Boolean(0b11111111)));
     }
+
+    #[test]
+    fn test_boolean_validation() {
+        assert_eq!(Boolean::FALSE.try_into_bool(), Ok(false));
+        assert_eq!(Boolean::TRUE.try_into_bool(), Ok(true));
+        assert!(Boolean::TRUE.is_valid());
+        assert!(!Boolean(2).is_valid());
+        assert_eq!(
+            Boolean(0xff).try_into_bool(),
+            Err(InvalidBooleanError(0xff))
+        );
+    }
 }
@@ -0,0 +1,177 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Table of the protocol GUIDs defined in this crate.
+//!
+//! [`PROTOCOLS`] lists every protocol GUID exported from [`protocol`],
+//! together with the protocol's name in the specification and the
+//! specification that defines it. Tools such as handle dumpers and fuzzers
+//! can use it to name the protocols they find, or to enumerate which
+//! protocols this crate covers.
+//!
+//! [`protocol`]: crate::protocol
+
+use crate::Guid;
+use crate::table::Revision;
+
+/// Specification that defines a protocol.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Spec {
+    /// The UEFI Specification, with the revision that introduced the
+    /// protocol.
+    Uefi(Revision),
+    /// The UEFI Platform Initialization (PI) Specification.
+    PlatformInit,
+    /// The UEFI Shell Specification.
+    Shell,
+    /// A TCG EFI Protocol Specification.
+    Tcg,
+    /// EDK II, which defines the protocol outside of any specification.
+    Edk2,
+}
+
+/// A protocol GUID defined in this crate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ProtocolInfo {
+    /// Name of the protocol in its specification, e.g.
+    /// `EFI_BLOCK_IO_PROTOCOL`.
+    pub name: &'static str,
+    /// Path of the GUID constant, relative to [`protocol`].
+    ///
+    /// [`protocol`]: crate::protocol
+    pub path: &'static str,
+    /// The protocol GUID.
+    pub guid: Guid,
+    /// Specification that defines the protocol.
+    pub spec: Spec,
+}
+
+/// Builds the table, taking each GUID and its path from the same tokens so
+/// that they can't get out of sync.
+macro_rules! protocols {
+    ($($name:literal => $module:ident $(:: $rest:ident)*, $spec:expr;)*) => {
+        &[$(ProtocolInfo {
+            name: $name,
+            path: concat!(stringify!($module) $(, "::", stringify!($rest))*),
+            guid: super::$module $(:: $rest)*,
+            spec: $spec,
+        }),*]
+    };
+}
+
+/// All protocol GUIDs defined in this crate, sorted by path.
+pub const PROTOCOLS: &[ProtocolInfo] = protocols! {
+    "EFI_ACPI_TABLE_PROTOCOL" => acpi::AcpiTableProtocol::GUID, Spec::Uefi(Revision::EFI_2_30);
+    "EFI_ATA_PASS_THRU_PROTOCOL" => ata::AtaPassThruProtocol::GUID, Spec::Uefi(Revision::EFI_2_30);
+    "EFI_BLOCK_IO_PROTOCOL" => block::BlockIoProtocol::GUID, Spec::Uefi(Revision::EFI_1_10);
+    "EFI_ABSOLUTE_POINTER_PROTOCOL" => console::AbsolutePointerProtocol::GUID, Spec::Uefi(Revision::EFI_2_30);
+    "EFI_EDID_ACTIVE_PROTOCOL" => console::EdidActiveProtocol::GUID, Spec::Uefi(Revision::EFI_2_00);
+    "EFI_EDID_DISCOVERED_PROTOCOL" => console::EdidDiscoveredProtocol::GUID, Spec::Uefi(Revision::EFI_2_00);
+    "EFI_EDID_OVERRIDE_PROTOCOL" => console::EdidOverrideProtocol::GUID, Spec::Uefi(Revision::EFI_2_00);
+    "EFI_GRAPHICS_OUTPUT_PROTOCOL" => console::GraphicsOutputProtocol::GUID, Spec::Uefi(Revision::EFI_2_00);
+    "EFI_SIMPLE_POINTER_PROTOCOL" => console::SimplePointerProtocol::GUID, Spec::Uefi(Revision::EFI_1_10);
+    "EFI_SIMPLE_TEXT_INPUT_EX_PROTOCOL" => console::SimpleTextInputExProtocol::GUID, Spec::Uefi(Revision::EFI_2_10);
+    "EFI_SIMPLE_TEXT_INPUT_PROTOCOL" => console::SimpleTextInputProtocol::GUID, Spec::Uefi(Revision::EFI_1_10);
+    "EFI_SIMPLE_TEXT_OUTPUT_PROTOCOL" => console::SimpleTextOutputProtocol::GUID, Spec::Uefi(Revision::EFI_1_10);
+    "EFI_SERIAL_IO_PROTOCOL" => console::serial::SerialIoProtocol::GUID, Spec::Uefi(Revision::EFI_1_10);
+    "EFI_DEVICE_PATH_FROM_TEXT_PROTOCOL" => device_path::DevicePathFromTextProtocol::GUID, Spec::Uefi(Revision::EFI_2_00);
+    "EFI_DEVICE_PATH_PROTOCOL" => device_path::DevicePathProtocol::GUID, Spec::Uefi(Revision::EFI_1_10);
+    "EFI_DEVICE_PATH_TO_TEXT_PROTOCOL" => device_path::DevicePathToTextProtocol::GUID, Spec::Uefi(Revision::EFI_2_00);
+    "EFI_DEVICE_PATH_UTILITIES_PROTOCOL" => device_path::DevicePathUtilitiesProtocol::GUID, Spec::Uefi(Revision::EFI_2_00);
+    "EFI_DISK_INFO_PROTOCOL" => disk::DiskInfoProtocol::GUID, Spec::PlatformInit;
+    "EFI_DISK_IO2_PROTOCOL" => disk::DiskIo2Protocol::GUID, Spec::Uefi(Revision::EFI_2_31);
+    "EFI_DISK_IO_PROTOCOL" => disk::DiskIoProtocol::GUID, Spec::Uefi(Revision::EFI_1_10);
+    "EFI_COMPONENT_NAME_PROTOCOL" => driver::ComponentName2Protocol::DEPRECATED_COMPONENT_NAME_GUID, Spec::Uefi(Revision::EFI_1_10);
+    "EFI_COMPONENT_NAME2_PROTOCOL" => driver::ComponentName2Protocol::GUID, Spec::Uefi(Revision::EFI_2_00);
+    "EFI_DRIVER_BINDING_PROTOCOL" => driver::DriverBindingProtocol::GUID, Spec::Uefi(Revision::EFI_1_10);
+    "EFI_DRIVER_FAMILY_OVERRIDE_PROTOCOL" => driver::DriverFamilyOverrideProtocol::GUID, Spec::Uefi(Revision::EFI_2_10);
+    "EFI_DRIVER_SUPPORTED_EFI_VERSION_PROTOCOL" => driver::DriverSupportedEfiVersionProtocol::GUID, Spec::Uefi(Revision::EFI_2_00);
+    "EFI_PLATFORM_TO_DRIVER_CONFIGURATION_PROTOCOL" => driver::PlatformToDriverConfigurationProtocol::GUID, Spec::Uefi(Revision::EFI_2_10);
+    "EFI_SIMPLE_FILE_SYSTEM_PROTOCOL" => file_system::SimpleFileSystemProtocol::GUID, Spec::Uefi(Revision::EFI_1_10);
+    "EFI_FIRMWARE_MANAGEMENT_PROTOCOL" => firmware_management::FirmwareManagementProtocol::GUID, Spec::Uefi(Revision::EFI_2_30);
+    "EFI_FIRMWARE_VOLUME2_PROTOCOL" => firmware_volume::FirmwareVolume2Protocol::GUID, Spec::PlatformInit;
+    "EFI_FIRMWARE_VOLUME_BLOCK2_PROTOCOL" => firmware_volume::FirmwareVolumeBlock2Protocol::GUID, Spec::PlatformInit;
+    "EFI_CONFIG_KEYWORD_HANDLER_PROTOCOL" => hii::config::ConfigKeywordHandlerProtocol::GUID, Spec::Uefi(Revision::EFI_2_50);
+    "EFI_HII_CONFIG_ACCESS_PROTOCOL" => hii::config::HiiConfigAccessProtocol::GUID, Spec::Uefi(Revision::EFI_2_10);
+    "EFI_HII_CONFIG_ROUTING_PROTOCOL" => hii::config::HiiConfigRoutingProtocol::GUID, Spec::Uefi(Revision::EFI_2_10);
+    "EFI_HII_DATABASE_PROTOCOL" => hii::database::HiiDatabaseProtocol::GUID, Spec::Uefi(Revision::EFI_2_10);
+    "EFI_HII_FONT_EX_PROTOCOL" => hii::font::HiiFontExProtocol::GUID, Spec::Uefi(Revision::EFI_2_60);
+    "EFI_HII_FONT_PROTOCOL" => hii::font::HiiFontProtocol::GUID, Spec::Uefi(Revision::EFI_2_10);
+    "EFI_FORM_BROWSER2_PROTOCOL" => hii::form_browser::FormBrowser2Protocol::GUID, Spec::Uefi(Revision::EFI_2_10);
+    "EFI_HII_IMAGE_EX_PROTOCOL" => hii::image::HiiImageExProtocol::GUID, Spec::Uefi(Revision::EFI_2_60);
+    "EFI_HII_IMAGE_PROTOCOL" => hii::image::HiiImageProtocol::GUID, Spec::Uefi(Revision::EFI_2_10);
+    "EFI_HII_POPUP_PROTOCOL" => hii::popup::HiiPopupProtocol::GUID, Spec::Uefi(Revision::EFI_2_70);
+    "EFI_HII_STRING_PROTOCOL" => hii::string::HiiStringProtocol::GUID, Spec::Uefi(Revision::EFI_2_10);
+    "EDKII_IOMMU_PROTOCOL" => iommu::EdkiiIommuProtocol::GUID, Spec::Edk2;
+    "EFI_LOADED_IMAGE_PROTOCOL" => loaded_image::LoadedImageProtocol::GUID, Spec::Uefi(Revision::EFI_1_10);
+    "EFI_LOAD_FILE2_PROTOCOL" => media::LoadFile2Protocol::GUID, Spec::Uefi(Revision::EFI_2_10);
+    "EFI_LOAD_FILE_PROTOCOL" => media::LoadFileProtocol::GUID, Spec::Uefi(Revision::EFI_1_10);
+    "EFI_STORAGE_SECURITY_COMMAND_PROTOCOL" => media::StorageSecurityCommandProtocol::GUID, Spec::Uefi(Revision::EFI_2_30);
+    "EFI_MEMORY_ATTRIBUTE_PROTOCOL" => memory_protection::MemoryAttributeProtocol::GUID, Spec::Uefi(Revision::EFI_2_100);
+    "EFI_RESET_NOTIFICATION_PROTOCOL" => misc::ResetNotificationProtocol::GUID, Spec::Uefi(Revision::EFI_2_70);
+    "EFI_TIMESTAMP_PROTOCOL" => misc::TimestampProtocol::GUID, Spec::Uefi(Revision::EFI_2_40);
+    "EFI_DHCP4_PROTOCOL" => network::dhcp4::Dhcp4Protocol::GUID, Spec::Uefi(Revision::EFI_2_00);
+    "EFI_DHCP4_SERVICE_BINDING_PROTOCOL" => network::dhcp4::Dhcp4Protocol::SERVICE_BINDING_GUID, Spec::Uefi(Revision::EFI_2_00);
+    "EFI_HTTP_PROTOCOL" => network::http::HttpProtocol::GUID, Spec::Uefi(Revision::EFI_2_50);
+    "EFI_HTTP_SERVICE_BINDING_PROTOCOL" => network::http::HttpProtocol::SERVICE_BINDING_GUID, Spec::Uefi(Revision::EFI_2_50);
+    "EFI_IP4_CONFIG2_PROTOCOL" => network::ip4_config2::Ip4Config2Protocol::GUID, Spec::Uefi(Revision::EFI_2_50);
+    "EFI_PXE_BASE_CODE_PROTOCOL" => network::pxe::PxeBaseCodeProtocol::GUID, Spec::Uefi(Revision::EFI_1_10);
+    "EFI_SIMPLE_NETWORK_PROTOCOL" => network::snp::SimpleNetworkProtocol::GUID, Spec::Uefi(Revision::EFI_1_10);
+    "EFI_TCP4_PROTOCOL" => network::tcp4::Tcp4Protocol::GUID, Spec::Uefi(Revision::EFI_2_00);
+    "EFI_TCP4_SERVICE_BINDING_PROTOCOL" => network::tcp4::Tcp4Protocol::SERVICE_BINDING_GUID, Spec::Uefi(Revision::EFI_2_00);
+    "EFI_TLS_CONFIGURATION_PROTOCOL" => network::tls::TlsConfigurationProtocol::GUID, Spec::Uefi(Revision::EFI_2_50);
+    "EFI_TLS_SERVICE_BINDING_PROTOCOL" => network::tls::TlsConfigurationProtocol::SERVICE_BINDING_GUID, Spec::Uefi(Revision::EFI_2_50);
+    "EFI_NVM_EXPRESS_PASS_THRU_PROTOCOL" => nvme::NvmExpressPassThruProtocol::GUID, Spec::Uefi(Revision::EFI_2_50);
+    "EFI_PCI_HOT_PLUG_INIT_PROTOCOL" => pci::hot_plug::PciHotPlugInitProtocol::GUID, Spec::PlatformInit;
+    "EFI_PCI_IO_PROTOCOL" => pci::io::PciIoProtocol::GUID, Spec::Uefi(Revision::EFI_1_10);
+    "EFI_PCI_ROOT_BRIDGE_IO_PROTOCOL" => pci::root_bridge::PciRootBridgeIoProtocol::GUID, Spec::Uefi(Revision::EFI_1_10);
+    "EFI_RNG_PROTOCOL" => rng::RngProtocol::GUID, Spec::Uefi(Revision::EFI_2_40);
+    "EFI_EXT_SCSI_PASS_THRU_PROTOCOL" => scsi::ExtScsiPassThruProtocol::GUID, Spec::Uefi(Revision::EFI_2_00);
+    "EFI_SCSI_IO_PROTOCOL" => scsi::ScsiIoProtocol::GUID, Spec::Uefi(Revision::EFI_2_00);
+    "EFI_SHELL_PROTOCOL" => shell::ShellProtocol::GUID, Spec::Shell;
+    "EFI_SHELL_PARAMETERS_PROTOCOL" => shell_params::ShellParametersProtocol::GUID, Spec::Shell;
+    "EFI_UNICODE_COLLATION2_PROTOCOL" => string::UnicodeCollationProtocol::GUID, Spec::Uefi(Revision::EFI_2_00);
+    "EFI_TCG_PROTOCOL" => tcg::v1::TcgProtocol::GUID, Spec::Tcg;
+    "EFI_TCG2_PROTOCOL" => tcg::v2::Tcg2Protocol::GUID, Spec::Tcg;
+    "EFI_USB2_HC_PROTOCOL" => usb::host_controller::Usb2HostControllerProtocol::GUID, Spec::Uefi(Revision::EFI_2_00);
+    "EFI_USB_IO_PROTOCOL" => usb::io::UsbIoProtocol::GUID, Spec::Uefi(Revision::EFI_1_10);
+};
+
+/// Returns the protocol with the given GUID, if it is defined in this
+/// crate.
+#[must_use]
+pub fn find_by_guid(guid: &Guid) -> Option<&'static ProtocolInfo> {
+    PROTOCOLS.iter().find(|protocol| protocol.guid == *guid)
+}
+
+/// Returns the protocol with the given specification name, e.g.
+/// `EFI_BLOCK_IO_PROTOCOL`, if it is defined in this crate.
+#[must_use]
+pub fn find_by_name(name: &str) -> Option<&'static ProtocolInfo> {
+    PROTOCOLS.iter().find(|protocol| protocol.name == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::block::BlockIoProtocol;
+
+    #[test]
+    fn test_unique() {
+        for (i, a) in PROTOCOLS.iter().enumerate() {
+            for b in &PROTOCOLS[i + 1..] {
+                assert_ne!(a.guid, b.guid, "{} and {}", a.name, b.name);
+                assert_ne!(a.name, b.name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_find() {
+        let block_io = find_by_guid(&BlockIoProtocol::GUID).unwrap();
+        assert_eq!(block_io.name, "EFI_BLOCK_IO_PROTOCOL");
+        assert_eq!(block_io.path, "block::BlockIoProtocol::GUID");
+        assert_eq!(block_io.spec, Spec::Uefi(Revision::EFI_1_10));
+        assert_eq!(find_by_name("EFI_BLOCK_IO_PROTOCOL"), Some(block_io));
+        assert!(find_by_name("EFI_BLOCK_IO2_PROTOCOL").is_none());
+    }
+}
@@ -1,7 +1,9 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::protocol::device_path::DevicePathProtocol;
-use crate::{Guid, Handle, Status, guid};
+use crate::table::Revision;
+use crate::{Guid, Handle, Status, guid, newtype_enum};
+use core::ffi::c_void;
 
 #[derive(Debug)]
 #[repr(C)]
@@ -67,3 +69,88 @@ pub struct ServiceBindingProtocol {
         unsafe extern "efiapi" fn(this: *mut Self, child_handle: *mut Handle) -> Status,
     pub destroy_child: unsafe extern "efiapi" fn(this: *mut Self, child_handle: Handle) -> Status,
 }
+
+newtype_enum! {
+    /// Action required for a configuration block to take effect, reported by
+    /// a driver to the platform in
+    /// [`PlatformToDriverConfigurationProtocol::response`].
+    ///
+    /// The corresponding C type is `EFI_PLATFORM_CONFIGURATION_ACTION`.
+    pub enum PlatformConfigurationAction: u32 => {
+        NONE = 0,
+        STOP_CONTROLLER = 1,
+        RESTART_CONTROLLER = 2,
+        RESTART_PLATFORM = 3,
+        NVRAM_FAILED = 4,
+        UNSUPPORTED_GUID = 5,
+    }
+}
+
+#[derive(Debug)]
+#[repr(C)]
+pub struct PlatformToDriverConfigurationProtocol {
+    pub query: unsafe extern "efiapi" fn(
+        this: *const Self,
+        controller_handle: Handle,
+        child_handle: Handle,
+        instance: *const usize,
+        parameter_type_guid: *mut *const Guid,
+        parameter_block: *mut *mut c_void,
+        parameter_block_size: *mut usize,
+    ) -> Status,
+    pub response: unsafe extern "efiapi" fn(
+        this: *const Self,
+        controller_handle: Handle,
+        child_handle: Handle,
+        instance: *const usize,
+        parameter_type_guid: *const Guid,
+        parameter_block: *const c_void,
+        parameter_block_size: usize,
+        configuration_action: PlatformConfigurationAction,
+    ) -> Status,
+}
+
+impl PlatformToDriverConfigurationProtocol {
+    pub const GUID: Guid = guid!("642cd590-8059-4c0a-a958-c5ec07d23c4b");
+
+    /// Parameter type GUID of configuration blocks in the
+    /// [`ConfigureClpParameterBlock`] format.
+    pub const CLP_GUID: Guid = guid!("345ecc0e-0cb6-4b75-bb57-1b129c47333e");
+}
+
+/// Configuration block containing a DMTF SM CLP command.
+///
+/// The corresponding C type is `EFI_CONFIGURE_CLP_PARAMETER_BLK`.
+#[derive(Debug)]
+#[repr(C)]
+pub struct ConfigureClpParameterBlock {
+    pub clp_command: *const u8,
+    pub clp_command_length: u32,
+    pub clp_return_string: *mut u8,
+    pub clp_return_string_length: u32,
+    pub clp_cmd_status: u8,
+    pub clp_error_value: u8,
+    pub clp_msg_code: u16,
+}
+
+#[derive(Debug)]
+#[repr(C)]
+pub struct DriverFamilyOverrideProtocol {
+    pub get_version: unsafe extern "efiapi" fn(this: *const Self) -> u32,
+}
+
+impl DriverFamilyOverrideProtocol {
+    pub const GUID: Guid = guid!("b1ee129e-da36-4181-91f8-04a4923766a7");
+}
+
+#[derive(Debug)]
+#[repr(C)]
+pub struct DriverSupportedEfiVersionProtocol {
+    /// Size of the protocol structure in bytes.
+    pub length: u32,
+    pub firmware_version: Revision,
+}
+
+impl DriverSupportedEfiVersionProtocol {
+    pub const GUID: Guid = guid!("5c198761-16a8-4e69-972c-89d67954f81d");
+}
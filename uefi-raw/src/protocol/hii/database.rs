@@ -6,8 +6,8 @@ use super::{HiiHandle, HiiPackageHeader, HiiPackageListHeader, KeyDescriptor};
 use crate::{Guid, Handle, Status, guid, newtype_enum};
 
 /// EFI_HII_KEYBOARD_LAYOUT
-#[derive(Debug)]
-#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+#[repr(C, packed)]
 pub struct HiiKeyboardLayout {
     pub layout_length: u16,
     pub guid: Guid,
@@ -16,6 +16,15 @@ pub struct HiiKeyboardLayout {
     pub descriptors: [KeyDescriptor; 0],
 }
 
+/// EFI_HII_KEYBOARD_PACKAGE_HDR
+#[derive(Clone, Copy, Debug)]
+#[repr(C, packed)]
+pub struct HiiKeyboardPackageHeader {
+    pub header: HiiPackageHeader,
+    pub layout_count: u16,
+    pub layouts: [HiiKeyboardLayout; 0],
+}
+
 newtype_enum! {
     /// EFI_HII_DATABASE_NOTIFY_TYPE
     pub enum HiiDatabaseNotifyType: usize => {
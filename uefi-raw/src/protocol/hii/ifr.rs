@@ -0,0 +1,853 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Internal Forms Representation (IFR) opcodes, as stored in HII form
+//! packages.
+//!
+//! An IFR buffer is a sequence of opcodes, each starting with an
+//! [`IfrOpHeader`]. All structures are byte-packed.
+
+use super::{FormId, ImageId, QuestionId, StringId, VarstoreId};
+use crate::{Guid, guid, newtype_enum};
+use bitflags::bitflags;
+use core::fmt::{self, Debug, Formatter};
+
+newtype_enum! {
+    /// IFR opcode, as stored in [`IfrOpHeader`].
+    pub enum IfrOpCode: u8 => {
+        FORM = 0x01,
+        SUBTITLE = 0x02,
+        TEXT = 0x03,
+        IMAGE = 0x04,
+        ONE_OF = 0x05,
+        CHECKBOX = 0x06,
+        NUMERIC = 0x07,
+        PASSWORD = 0x08,
+        ONE_OF_OPTION = 0x09,
+        SUPPRESS_IF = 0x0A,
+        LOCKED = 0x0B,
+        ACTION = 0x0C,
+        RESET_BUTTON = 0x0D,
+        FORM_SET = 0x0E,
+        REF = 0x0F,
+        NO_SUBMIT_IF = 0x10,
+        INCONSISTENT_IF = 0x11,
+        EQ_ID_VAL = 0x12,
+        EQ_ID_ID = 0x13,
+        EQ_ID_VAL_LIST = 0x14,
+        AND = 0x15,
+        OR = 0x16,
+        NOT = 0x17,
+        RULE = 0x18,
+        GRAY_OUT_IF = 0x19,
+        DATE = 0x1A,
+        TIME = 0x1B,
+        STRING = 0x1C,
+        REFRESH = 0x1D,
+        DISABLE_IF = 0x1E,
+        ANIMATION = 0x1F,
+        TO_LOWER = 0x20,
+        TO_UPPER = 0x21,
+        MAP = 0x22,
+        ORDERED_LIST = 0x23,
+        VARSTORE = 0x24,
+        VARSTORE_NAME_VALUE = 0x25,
+        VARSTORE_EFI = 0x26,
+        VARSTORE_DEVICE = 0x27,
+        VERSION = 0x28,
+        END = 0x29,
+        MATCH = 0x2A,
+        GET = 0x2B,
+        SET = 0x2C,
+        READ = 0x2D,
+        WRITE = 0x2E,
+        EQUAL = 0x2F,
+        NOT_EQUAL = 0x30,
+        GREATER_THAN = 0x31,
+        GREATER_EQUAL = 0x32,
+        LESS_THAN = 0x33,
+        LESS_EQUAL = 0x34,
+        BITWISE_AND = 0x35,
+        BITWISE_OR = 0x36,
+        BITWISE_NOT = 0x37,
+        SHIFT_LEFT = 0x38,
+        SHIFT_RIGHT = 0x39,
+        ADD = 0x3A,
+        SUBTRACT = 0x3B,
+        MULTIPLY = 0x3C,
+        DIVIDE = 0x3D,
+        MODULO = 0x3E,
+        RULE_REF = 0x3F,
+        QUESTION_REF1 = 0x40,
+        QUESTION_REF2 = 0x41,
+        UINT8 = 0x42,
+        UINT16 = 0x43,
+        UINT32 = 0x44,
+        UINT64 = 0x45,
+        TRUE = 0x46,
+        FALSE = 0x47,
+        TO_UINT = 0x48,
+        TO_STRING = 0x49,
+        TO_BOOLEAN = 0x4A,
+        MID = 0x4B,
+        FIND = 0x4C,
+        TOKEN = 0x4D,
+        STRING_REF1 = 0x4E,
+        STRING_REF2 = 0x4F,
+        CONDITIONAL = 0x50,
+        QUESTION_REF3 = 0x51,
+        ZERO = 0x52,
+        ONE = 0x53,
+        ONES = 0x54,
+        UNDEFINED = 0x55,
+        LENGTH = 0x56,
+        DUP = 0x57,
+        THIS = 0x58,
+        SPAN = 0x59,
+        VALUE = 0x5A,
+        DEFAULT = 0x5B,
+        DEFAULTSTORE = 0x5C,
+        FORM_MAP = 0x5D,
+        CATENATE = 0x5E,
+        GUID = 0x5F,
+        SECURITY = 0x60,
+        MODAL_TAG = 0x61,
+        REFRESH_ID = 0x62,
+        WARNING_IF = 0x63,
+        MATCH2 = 0x64,
+    }
+}
+
+newtype_enum! {
+    /// Type of an IFR value, e.g. in [`IfrDefault`] and [`IfrOneOfOption`].
+    pub enum IfrType: u8 => {
+        NUM_SIZE_8 = 0x00,
+        NUM_SIZE_16 = 0x01,
+        NUM_SIZE_32 = 0x02,
+        NUM_SIZE_64 = 0x03,
+        BOOLEAN = 0x04,
+        TIME = 0x05,
+        DATE = 0x06,
+        STRING = 0x07,
+        OTHER = 0x08,
+        UNDEFINED = 0x09,
+        ACTION = 0x0A,
+        BUFFER = 0x0B,
+        REF = 0x0C,
+    }
+}
+
+/// EFI_IFR_OP_HEADER
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrOpHeader {
+    pub op_code: IfrOpCode,
+    /// Bits 0..7: length of the opcode in bytes, including the header.
+    /// Bit 7: whether the opcode opens a scope, which is closed by a
+    /// matching [`IfrOpCode::END`].
+    pub length_and_scope: u8,
+}
+
+impl IfrOpHeader {
+    /// Creates an opcode header. Only the lower 7 bits of `length` are used.
+    #[must_use]
+    pub const fn new(op_code: IfrOpCode, length: u8, scope: bool) -> Self {
+        Self {
+            op_code,
+            length_and_scope: (length & 0x7f) | ((scope as u8) << 7),
+        }
+    }
+
+    /// Length of the opcode in bytes, including the header.
+    #[must_use]
+    pub const fn length(&self) -> u8 {
+        self.length_and_scope & 0x7f
+    }
+
+    /// Whether the opcode opens a scope.
+    #[must_use]
+    pub const fn scope(&self) -> bool {
+        self.length_and_scope & 0x80 != 0
+    }
+}
+
+/// EFI_IFR_STATEMENT_HEADER
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrStatementHeader {
+    pub prompt: StringId,
+    pub help: StringId,
+}
+
+bitflags! {
+    /// Flags of an [`IfrQuestionHeader`].
+    #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    #[repr(transparent)]
+    pub struct IfrQuestionFlags: u8 {
+        const READ_ONLY = 0x01;
+        const CALLBACK = 0x04;
+        const RESET_REQUIRED = 0x10;
+        const REST_STYLE = 0x20;
+        const RECONNECT_REQUIRED = 0x40;
+        const OPTIONS_ONLY = 0x80;
+    }
+}
+
+/// EFI_IFR_QUESTION_HEADER
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrQuestionHeader {
+    pub header: IfrStatementHeader,
+    pub question_id: QuestionId,
+    pub var_store_id: VarstoreId,
+    /// Byte offset of the question in a buffer varstore, or the string ID
+    /// of the variable name in a name/value varstore.
+    pub var_store_info: u16,
+    pub flags: IfrQuestionFlags,
+}
+
+/// EFI_IFR_FORM_SET
+///
+/// Followed by `flags & 0x3` class GUIDs.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrFormSet {
+    pub header: IfrOpHeader,
+    pub guid: Guid,
+    pub form_set_title: StringId,
+    pub help: StringId,
+    pub flags: u8,
+}
+
+impl IfrFormSet {
+    /// Class GUID of formsets shown in the platform setup.
+    pub const PLATFORM_SETUP_FORMSET_GUID: Guid = guid!("93039971-8545-4b04-b45e-32eb8326040e");
+}
+
+/// EFI_IFR_FORM
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrForm {
+    pub header: IfrOpHeader,
+    pub form_id: FormId,
+    pub form_title: StringId,
+}
+
+/// EFI_IFR_SUBTITLE
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrSubtitle {
+    pub header: IfrOpHeader,
+    pub statement: IfrStatementHeader,
+    pub flags: u8,
+}
+
+impl IfrSubtitle {
+    /// The subtitle is displayed in a horizontal line.
+    pub const FLAG_HORIZONTAL: u8 = 0x01;
+}
+
+/// EFI_IFR_TEXT
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrText {
+    pub header: IfrOpHeader,
+    pub statement: IfrStatementHeader,
+    pub text_two: StringId,
+}
+
+/// EFI_IFR_IMAGE
+///
+/// Sets the image of the enclosing statement or form.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrImage {
+    pub header: IfrOpHeader,
+    pub id: ImageId,
+}
+
+/// EFI_IFR_REF
+///
+/// Longer variants of the opcode (`EFI_IFR_REF2` to `EFI_IFR_REF5`) add a
+/// question ID, formset GUID, and device path.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrRef {
+    pub header: IfrOpHeader,
+    pub question: IfrQuestionHeader,
+    pub form_id: FormId,
+}
+
+/// EFI_IFR_ACTION
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrAction {
+    pub header: IfrOpHeader,
+    pub question: IfrQuestionHeader,
+    pub question_config: StringId,
+}
+
+bitflags! {
+    /// Flags of an [`IfrCheckbox`].
+    #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    #[repr(transparent)]
+    pub struct IfrCheckboxFlags: u8 {
+        const DEFAULT = 0x01;
+        const DEFAULT_MFG = 0x02;
+    }
+}
+
+/// EFI_IFR_CHECKBOX
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrCheckbox {
+    pub header: IfrOpHeader,
+    pub question: IfrQuestionHeader,
+    pub flags: IfrCheckboxFlags,
+}
+
+bitflags! {
+    /// Flags of an [`IfrNumeric`] or [`IfrOneOf`].
+    ///
+    /// The lower two bits select the size of the value, the next two bits
+    /// how it is displayed.
+    #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    #[repr(transparent)]
+    pub struct IfrNumericFlags: u8 {
+        const SIZE_1 = 0x00;
+        const SIZE_2 = 0x01;
+        const SIZE_4 = 0x02;
+        const SIZE_8 = 0x03;
+        const DISPLAY_INT_DEC = 0x00;
+        const DISPLAY_UINT_DEC = 0x10;
+        const DISPLAY_UINT_HEX = 0x20;
+    }
+}
+
+impl IfrNumericFlags {
+    /// Mask of the size bits.
+    pub const SIZE_MASK: u8 = 0x03;
+    /// Mask of the display bits.
+    pub const DISPLAY_MASK: u8 = 0x30;
+
+    /// Returns the size of the values in bytes: 1, 2, 4, or 8.
+    #[must_use]
+    pub const fn value_size(self) -> usize {
+        1 << (self.bits() & Self::SIZE_MASK)
+    }
+}
+
+macro_rules! min_max_step {
+    ($name:ident, $ty:ty, $variant:literal) => {
+        #[doc = concat!("The `", $variant, "` variant of [`IfrNumericData`].")]
+        #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+        #[repr(C, packed)]
+        pub struct $name {
+            pub min_value: $ty,
+            pub max_value: $ty,
+            pub step: $ty,
+        }
+    };
+}
+
+min_max_step!(IfrMinMaxStep8, u8, "u8");
+min_max_step!(IfrMinMaxStep16, u16, "u16");
+min_max_step!(IfrMinMaxStep32, u32, "u32");
+min_max_step!(IfrMinMaxStep64, u64, "u64");
+
+/// MINMAXSTEP_DATA
+///
+/// The minimum, maximum, and step values following an [`IfrNumeric`] or
+/// [`IfrOneOf`]. The variant is selected by the size bits of the opcode's
+/// [`IfrNumericFlags`]; only the bytes of that variant are part of the
+/// opcode.
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub union IfrNumericData {
+    pub u8: IfrMinMaxStep8,
+    pub u16: IfrMinMaxStep16,
+    pub u32: IfrMinMaxStep32,
+    pub u64: IfrMinMaxStep64,
+}
+
+impl IfrNumericData {
+    /// Creates the data for values of the size selected by `flags`. The
+    /// values are truncated to that size, and the unused bytes are zero.
+    #[must_use]
+    pub const fn new(flags: IfrNumericFlags, min: u64, max: u64, step: u64) -> Self {
+        let mut data = Self {
+            u64: IfrMinMaxStep64 {
+                min_value: 0,
+                max_value: 0,
+                step: 0,
+            },
+        };
+        match flags.value_size() {
+            1 => {
+                data.u8 = IfrMinMaxStep8 {
+                    min_value: min as u8,
+                    max_value: max as u8,
+                    step: step as u8,
+                }
+            }
+            2 => {
+                data.u16 = IfrMinMaxStep16 {
+                    min_value: min as u16,
+                    max_value: max as u16,
+                    step: step as u16,
+                }
+            }
+            4 => {
+                data.u32 = IfrMinMaxStep32 {
+                    min_value: min as u32,
+                    max_value: max as u32,
+                    step: step as u32,
+                }
+            }
+            _ => {
+                data.u64 = IfrMinMaxStep64 {
+                    min_value: min,
+                    max_value: max,
+                    step,
+                }
+            }
+        }
+        data
+    }
+
+    /// Returns the minimum, maximum, and step values of the variant
+    /// selected by `flags`, zero-extended to 64 bits.
+    ///
+    /// # Safety
+    ///
+    /// The bytes of the selected variant must be initialized, e.g. because
+    /// the data was created with [`Self::new`] or that variant.
+    #[must_use]
+    pub const unsafe fn min_max_step(&self, flags: IfrNumericFlags) -> IfrMinMaxStep64 {
+        unsafe {
+            match flags.value_size() {
+                1 => IfrMinMaxStep64 {
+                    min_value: self.u8.min_value as u64,
+                    max_value: self.u8.max_value as u64,
+                    step: self.u8.step as u64,
+                },
+                2 => IfrMinMaxStep64 {
+                    min_value: self.u16.min_value as u64,
+                    max_value: self.u16.max_value as u64,
+                    step: self.u16.step as u64,
+                },
+                4 => IfrMinMaxStep64 {
+                    min_value: self.u32.min_value as u64,
+                    max_value: self.u32.max_value as u64,
+                    step: self.u32.step as u64,
+                },
+                _ => self.u64,
+            }
+        }
+    }
+}
+
+impl Debug for IfrNumericData {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        // This is a union type, so we can't access the internal data.
+        f.debug_struct("IfrNumericData").finish()
+    }
+}
+
+/// EFI_IFR_NUMERIC
+///
+/// Followed by the minimum, maximum, and step values, each of the size
+/// given by the flags. See [`IfrNumericData`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrNumeric {
+    pub header: IfrOpHeader,
+    pub question: IfrQuestionHeader,
+    pub flags: IfrNumericFlags,
+}
+
+/// EFI_IFR_ONE_OF
+///
+/// Followed by the minimum, maximum, and step values, each of the size
+/// given by the flags. The options are [`IfrOneOfOption`] opcodes in the
+/// scope of this opcode.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrOneOf {
+    pub header: IfrOpHeader,
+    pub question: IfrQuestionHeader,
+    pub flags: IfrNumericFlags,
+}
+
+bitflags! {
+    /// Flags of an [`IfrOneOfOption`].
+    #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    #[repr(transparent)]
+    pub struct IfrOneOfOptionFlags: u8 {
+        const DEFAULT = 0x10;
+        const DEFAULT_MFG = 0x20;
+    }
+}
+
+/// EFI_IFR_ONE_OF_OPTION
+///
+/// Followed by the value of the option, of the size given by `type`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrOneOfOption {
+    pub header: IfrOpHeader,
+    pub option: StringId,
+    pub flags: IfrOneOfOptionFlags,
+    pub ty: IfrType,
+}
+
+/// EFI_IFR_STRING
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrString {
+    pub header: IfrOpHeader,
+    pub question: IfrQuestionHeader,
+    pub min_size: u8,
+    pub max_size: u8,
+    pub flags: u8,
+}
+
+/// EFI_IFR_PASSWORD
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrPassword {
+    pub header: IfrOpHeader,
+    pub question: IfrQuestionHeader,
+    pub min_size: u16,
+    pub max_size: u16,
+}
+
+/// EFI_IFR_ORDERED_LIST
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrOrderedList {
+    pub header: IfrOpHeader,
+    pub question: IfrQuestionHeader,
+    pub max_containers: u8,
+    pub flags: u8,
+}
+
+/// EFI_IFR_DATE
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrDate {
+    pub header: IfrOpHeader,
+    pub question: IfrQuestionHeader,
+    pub flags: u8,
+}
+
+/// EFI_IFR_TIME
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrTime {
+    pub header: IfrOpHeader,
+    pub question: IfrQuestionHeader,
+    pub flags: u8,
+}
+
+/// EFI_IFR_VARSTORE
+///
+/// Followed by the null-terminated ASCII name of the varstore.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrVarStore {
+    pub header: IfrOpHeader,
+    pub guid: Guid,
+    pub var_store_id: VarstoreId,
+    pub size: u16,
+}
+
+/// EFI_IFR_VARSTORE_EFI
+///
+/// Followed by the null-terminated ASCII name of the variable.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrVarStoreEfi {
+    pub header: IfrOpHeader,
+    pub var_store_id: VarstoreId,
+    pub guid: Guid,
+    pub attributes: u32,
+    pub size: u16,
+}
+
+/// EFI_IFR_VARSTORE_NAME_VALUE
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrVarStoreNameValue {
+    pub header: IfrOpHeader,
+    pub var_store_id: VarstoreId,
+    pub guid: Guid,
+}
+
+/// EFI_IFR_DEFAULTSTORE
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrDefaultStore {
+    pub header: IfrOpHeader,
+    pub default_name: StringId,
+    pub default_id: u16,
+}
+
+impl IfrDefaultStore {
+    /// ID of the standard default store.
+    pub const STANDARD: u16 = 0x0000;
+    /// ID of the manufacturing default store.
+    pub const MANUFACTURING: u16 = 0x0001;
+}
+
+/// EFI_IFR_DEFAULT
+///
+/// Followed by the default value, of the size given by `type`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrDefault {
+    pub header: IfrOpHeader,
+    pub default_id: u16,
+    pub ty: IfrType,
+}
+
+/// EFI_IFR_GUID
+///
+/// Followed by data specific to the GUID.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrGuid {
+    pub header: IfrOpHeader,
+    pub guid: Guid,
+}
+
+impl IfrGuid {
+    /// EFI_IFR_TIANO_GUID: GUID of the EDK2 extension opcodes, such as
+    /// [`IfrGuidLabel`].
+    pub const TIANO_GUID: Guid = guid!("0f0b1735-87a0-4193-b266-538c38af48ce");
+}
+
+newtype_enum! {
+    /// Extension opcode of the [`IfrGuid::TIANO_GUID`] opcodes, following
+    /// the GUID (`EFI_IFR_EXTEND_OP_*`).
+    pub enum IfrTianoOpCode: u8 => {
+        LABEL = 0x00,
+        BANNER = 0x01,
+        TIMEOUT = 0x02,
+        CLASS = 0x03,
+        SUBCLASS = 0x04,
+    }
+}
+
+/// EFI_IFR_GUID_LABEL
+///
+/// Marks a position in a form, where content can be inserted at runtime.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrGuidLabel {
+    pub header: IfrOpHeader,
+    pub guid: Guid,
+    pub extend_op_code: IfrTianoOpCode,
+    pub number: u16,
+}
+
+newtype_enum! {
+    /// Alignment of an [`IfrGuidBanner`].
+    pub enum IfrBannerAlignment: u8 => {
+        LEFT = 0,
+        CENTER = 1,
+        RIGHT = 2,
+    }
+}
+
+/// EFI_IFR_GUID_BANNER
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrGuidBanner {
+    pub header: IfrOpHeader,
+    pub guid: Guid,
+    pub extend_op_code: IfrTianoOpCode,
+    pub title: StringId,
+    /// One-based line number of the banner.
+    pub line_number: u16,
+    pub alignment: IfrBannerAlignment,
+}
+
+/// EFI_IFR_GUID_TIMEOUT
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrGuidTimeout {
+    pub header: IfrOpHeader,
+    pub guid: Guid,
+    pub extend_op_code: IfrTianoOpCode,
+    /// Timeout in seconds.
+    pub timeout: u16,
+}
+
+bitflags! {
+    /// Device classes of an [`IfrGuidClass`] (`EFI_*_CLASS`). A formset
+    /// without classes has the non-device class.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+    #[repr(transparent)]
+    pub struct IfrDeviceClass: u16 {
+        const DISK = 0x01;
+        const VIDEO = 0x02;
+        const NETWORK = 0x04;
+        const INPUT = 0x08;
+        const ON_BOARD = 0x10;
+        const OTHER = 0x20;
+    }
+}
+
+/// EFI_IFR_GUID_CLASS
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrGuidClass {
+    pub header: IfrOpHeader,
+    pub guid: Guid,
+    pub extend_op_code: IfrTianoOpCode,
+    pub class: IfrDeviceClass,
+}
+
+newtype_enum! {
+    /// Subclass of an [`IfrGuidSubclass`] (`EFI_*_SUBCLASS`).
+    pub enum IfrSubclass: u16 => {
+        SETUP_APPLICATION = 0x00,
+        GENERAL_APPLICATION = 0x01,
+        FRONT_PAGE = 0x02,
+        SINGLE_USE = 0x03,
+    }
+}
+
+/// EFI_IFR_GUID_SUBCLASS
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrGuidSubclass {
+    pub header: IfrOpHeader,
+    pub guid: Guid,
+    pub extend_op_code: IfrTianoOpCode,
+    pub subclass: IfrSubclass,
+}
+
+/// EFI_IFR_END
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrEnd {
+    pub header: IfrOpHeader,
+}
+
+/// EFI_IFR_EQ_ID_VAL
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrEqIdVal {
+    pub header: IfrOpHeader,
+    pub question_id: QuestionId,
+    pub value: u16,
+}
+
+/// EFI_IFR_EQ_ID_ID
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrEqIdId {
+    pub header: IfrOpHeader,
+    pub question_id_1: QuestionId,
+    pub question_id_2: QuestionId,
+}
+
+/// EFI_IFR_EQ_ID_VAL_LIST
+///
+/// Followed by `list_length` 16-bit values.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrEqIdValList {
+    pub header: IfrOpHeader,
+    pub question_id: QuestionId,
+    pub list_length: u16,
+}
+
+/// EFI_IFR_QUESTION_REF1
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrQuestionRef1 {
+    pub header: IfrOpHeader,
+    pub question_id: QuestionId,
+}
+
+/// EFI_IFR_RULE
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrRule {
+    pub header: IfrOpHeader,
+    pub rule_id: u8,
+}
+
+/// EFI_IFR_RULE_REF
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrRuleRef {
+    pub header: IfrOpHeader,
+    pub rule_id: u8,
+}
+
+/// EFI_IFR_STRING_REF1
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrStringRef1 {
+    pub header: IfrOpHeader,
+    pub string_id: StringId,
+}
+
+/// EFI_IFR_UINT8
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrUint8 {
+    pub header: IfrOpHeader,
+    pub value: u8,
+}
+
+/// EFI_IFR_UINT16
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrUint16 {
+    pub header: IfrOpHeader,
+    pub value: u16,
+}
+
+/// EFI_IFR_UINT32
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrUint32 {
+    pub header: IfrOpHeader,
+    pub value: u32,
+}
+
+/// EFI_IFR_UINT64
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrUint64 {
+    pub header: IfrOpHeader,
+    pub value: u64,
+}
+
+/// EFI_IFR_TO_STRING
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrToString {
+    pub header: IfrOpHeader,
+    pub format: u8,
+}
+
+/// EFI_IFR_FIND
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrFind {
+    pub header: IfrOpHeader,
+    pub format: u8,
+}
+
+/// EFI_IFR_SPAN
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct IfrSpan {
+    pub header: IfrOpHeader,
+    pub flags: u8,
+}
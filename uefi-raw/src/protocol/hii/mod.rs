@@ -6,11 +6,13 @@ pub mod config;
 pub mod database;
 pub mod font;
 pub mod form_browser;
+pub mod ifr;
 pub mod image;
 pub mod popup;
 pub mod string;
 
 use crate::{Char16, Guid, newtype_enum};
+use bitflags::bitflags;
 
 pub type HiiHandle = *mut core::ffi::c_void;
 
@@ -21,14 +23,67 @@ pub type FormId = u16;
 pub type VarstoreId = u16;
 pub type AnimationId = u16;
 
+newtype_enum! {
+    /// HII package types, as stored in [`HiiPackageHeader`].
+    pub enum HiiPackageType: u8 => {
+        ALL = 0x00,
+        TYPE_GUID = 0x01,
+        FORMS = 0x02,
+        STRINGS = 0x04,
+        FONTS = 0x05,
+        IMAGES = 0x06,
+        SIMPLE_FONTS = 0x07,
+        DEVICE_PATH = 0x08,
+        KEYBOARD_LAYOUT = 0x09,
+        ANIMATIONS = 0x0A,
+        END = 0xDF,
+        TYPE_SYSTEM_BEGIN = 0xE0,
+        TYPE_SYSTEM_END = 0xFF,
+    }
+}
+
 /// EFI_HII_PACKAGE_HEADER
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 #[repr(C)]
 pub struct HiiPackageHeader {
     pub length_and_type: u32,
     pub data: [u8; 0],
 }
 
+impl HiiPackageHeader {
+    /// Creates a package header. Only the lower 24 bits of `length` are
+    /// used.
+    #[must_use]
+    pub const fn new(package_type: HiiPackageType, length: u32) -> Self {
+        Self {
+            length_and_type: (length & 0x00ff_ffff) | ((package_type.0 as u32) << 24),
+            data: [],
+        }
+    }
+
+    /// Length of the package in bytes, including the header.
+    #[must_use]
+    pub const fn length(&self) -> u32 {
+        self.length_and_type & 0x00ff_ffff
+    }
+
+    /// Type of the package.
+    #[must_use]
+    pub const fn package_type(&self) -> HiiPackageType {
+        HiiPackageType((self.length_and_type >> 24) as u8)
+    }
+}
+
+/// EFI_HII_GUID_PACKAGE_HDR
+///
+/// Followed by data specific to the GUID.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct HiiGuidPackageHeader {
+    pub header: HiiPackageHeader,
+    pub guid: Guid,
+}
+
 /// EFI_HII_PACKAGE_LIST_HEADER
 #[derive(Debug)]
 #[repr(C)]
@@ -207,9 +262,25 @@ newtype_enum! {
     }
 }
 
+bitflags! {
+    /// Flags for [`KeyDescriptor::affected_attribute`].
+    #[repr(transparent)]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+    pub struct KeyAffectedAttributes: u16 {
+        /// The key is affected by the shift keys.
+        const STANDARD_SHIFT = 0x0001;
+
+        /// The key is affected by caps lock.
+        const CAPS_LOCK = 0x0002;
+
+        /// The key is affected by num lock.
+        const NUM_LOCK = 0x0004;
+    }
+}
+
 /// EFI_KEY_DESCRIPTOR
-#[derive(Debug)]
-#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+#[repr(C, packed)]
 pub struct KeyDescriptor {
     pub key: Key,
     pub unicode: Char16,
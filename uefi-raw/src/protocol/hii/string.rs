@@ -3,8 +3,42 @@
 //! Bindings for HII String protocols and data types
 
 use super::font::FontInfo;
-use super::{HiiHandle, StringId};
-use crate::{Char8, Char16, Guid, Status, guid};
+use super::{HiiHandle, HiiPackageHeader, StringId};
+use crate::{Char8, Char16, Guid, Status, guid, newtype_enum};
+
+/// EFI_HII_STRING_PACKAGE_HDR
+#[derive(Debug)]
+#[repr(C, packed)]
+pub struct HiiStringPackageHeader {
+    pub header: HiiPackageHeader,
+    pub header_size: u32,
+    pub string_info_offset: u32,
+    pub language_window: [Char16; 16],
+    pub language_name: StringId,
+    pub language: [Char8; 0],
+}
+
+newtype_enum! {
+    /// String information block types (`EFI_HII_SIBT_*`).
+    pub enum StringBlockType: u8 => {
+        END = 0x00,
+        STRING_SCSU = 0x10,
+        STRING_SCSU_FONT = 0x11,
+        STRINGS_SCSU = 0x12,
+        STRINGS_SCSU_FONT = 0x13,
+        STRING_UCS2 = 0x14,
+        STRING_UCS2_FONT = 0x15,
+        STRINGS_UCS2 = 0x16,
+        STRINGS_UCS2_FONT = 0x17,
+        DUPLICATE = 0x20,
+        SKIP2 = 0x21,
+        SKIP1 = 0x22,
+        EXT1 = 0x30,
+        EXT2 = 0x31,
+        EXT4 = 0x32,
+        FONT = 0x40,
+    }
+}
 
 /// EFI_HII_STRING_PROTOCOL
 #[derive(Debug)]
@@ -24,7 +58,7 @@ pub struct HiiStringProtocol {
         language: *const Char8,
         package_list: HiiHandle,
         string_id: StringId,
-        string: *mut *mut Char16,
+        string: *mut Char16,
         string_size: *mut usize,
         string_font_info: *mut *mut FontInfo,
     ) -> Status,
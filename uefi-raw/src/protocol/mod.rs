@@ -26,6 +26,7 @@
 pub mod acpi;
 pub mod ata;
 pub mod block;
+pub mod catalog;
 pub mod console;
 pub mod device_path;
 pub mod disk;
@@ -0,0 +1,79 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! PCI Hot Plug Init protocol, defined in the Platform Initialization (PI)
+//! specification.
+
+use crate::protocol::device_path::DevicePathProtocol;
+use crate::{Event, Status, newtype_enum};
+use bitflags::bitflags;
+use core::ffi::c_void;
+use uguid::{Guid, guid};
+
+/// EFI_HPC_LOCATION: location of a root hot plug controller (HPC).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C)]
+pub struct HpcLocation {
+    /// Device path of the controller.
+    pub hpc_device_path: *const DevicePathProtocol,
+    /// Device path of the bus the controller manages.
+    pub hpb_device_path: *const DevicePathProtocol,
+}
+
+bitflags! {
+    /// EFI_HPC_STATE: state of a root hot plug controller.
+    #[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    #[repr(transparent)]
+    pub struct HpcState: u16 {
+        /// The controller is initialized.
+        const INITIALIZED = 0x01;
+        /// The controller is enabled.
+        const ENABLED = 0x02;
+    }
+}
+
+newtype_enum! {
+    /// EFI_HPC_PADDING_ATTRIBUTES: how the padding returned by
+    /// `get_resource_padding` is applied.
+    pub enum HpcPaddingAttributes: u32 => {
+        /// The padding is applied to the bus behind the controller.
+        PCI_BUS = 0,
+        /// The padding is applied to the root bridge.
+        PCI_ROOT_BRIDGE = 1,
+    }
+}
+
+/// EFI_PCI_HOT_PLUG_INIT_PROTOCOL
+///
+/// The resource padding returned by `get_resource_padding` is a list of
+/// [`QwordAddressSpaceDescriptor`]s terminated by an [`EndTagDescriptor`].
+///
+/// [`QwordAddressSpaceDescriptor`]: super::resource::QwordAddressSpaceDescriptor
+/// [`EndTagDescriptor`]: super::resource::EndTagDescriptor
+#[derive(Debug)]
+#[repr(C)]
+pub struct PciHotPlugInitProtocol {
+    pub get_root_hpc_list: unsafe extern "efiapi" fn(
+        this: *const Self,
+        hpc_count: *mut usize,
+        hpc_list: *mut *const HpcLocation,
+    ) -> Status,
+    pub initialize_root_hpc: unsafe extern "efiapi" fn(
+        this: *const Self,
+        hpc_device_path: *const DevicePathProtocol,
+        hpc_pci_address: u64,
+        event: Event,
+        hpc_state: *mut HpcState,
+    ) -> Status,
+    pub get_resource_padding: unsafe extern "efiapi" fn(
+        this: *const Self,
+        hpc_device_path: *const DevicePathProtocol,
+        hpc_pci_address: u64,
+        hpc_state: *mut HpcState,
+        padding: *mut *const c_void,
+        attributes: *mut HpcPaddingAttributes,
+    ) -> Status,
+}
+
+impl PciHotPlugInitProtocol {
+    pub const GUID: Guid = guid!("aa0e8bc1-dabc-46b0-a844-37b8169b2bea");
+}
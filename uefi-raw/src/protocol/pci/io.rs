@@ -0,0 +1,179 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::table::boot::{AllocateType, MemoryType};
+use crate::{PhysicalAddress, Status, newtype_enum};
+use core::ffi::c_void;
+use uguid::{Guid, guid};
+
+newtype_enum! {
+    /// Corresponds to the `EFI_PCI_IO_PROTOCOL_WIDTH` enum.
+    pub enum PciIoProtocolWidth: u32 => {
+        UINT8 = 0,
+        UINT16 = 1,
+        UINT32 = 2,
+        UINT64 = 3,
+        FIFO_UINT8 = 4,
+        FIFO_UINT16 = 5,
+        FIFO_UINT32 = 6,
+        FIFO_UINT64 = 7,
+        FILL_UINT8 = 8,
+        FILL_UINT16 = 9,
+        FILL_UINT32 = 10,
+        FILL_UINT64 = 11,
+        MAXIMUM = 12,
+    }
+}
+
+newtype_enum! {
+    /// Corresponds to the `EFI_PCI_IO_PROTOCOL_OPERATION` enum.
+    pub enum PciIoProtocolOperation: u32 => {
+        BUS_MASTER_READ = 0,
+        BUS_MASTER_WRITE = 1,
+        BUS_MASTER_COMMON_BUFFER = 2,
+        MAXIMUM = 3,
+    }
+}
+
+newtype_enum! {
+    /// Corresponds to the `EFI_PCI_IO_PROTOCOL_ATTRIBUTE_OPERATION` enum.
+    pub enum PciIoProtocolAttributeOperation: u32 => {
+        GET = 0,
+        SET = 1,
+        ENABLE = 2,
+        DISABLE = 3,
+        SUPPORTED = 4,
+        MAXIMUM = 5,
+    }
+}
+
+#[derive(Debug)]
+#[repr(C)]
+pub struct PciIoAccess {
+    pub read: unsafe extern "efiapi" fn(
+        this: *mut PciIoProtocol,
+        width: PciIoProtocolWidth,
+        bar_index: u8,
+        offset: u64,
+        count: usize,
+        buffer: *mut c_void,
+    ) -> Status,
+    pub write: unsafe extern "efiapi" fn(
+        this: *mut PciIoProtocol,
+        width: PciIoProtocolWidth,
+        bar_index: u8,
+        offset: u64,
+        count: usize,
+        buffer: *const c_void,
+    ) -> Status,
+}
+
+#[derive(Debug)]
+#[repr(C)]
+pub struct PciIoConfigAccess {
+    pub read: unsafe extern "efiapi" fn(
+        this: *mut PciIoProtocol,
+        width: PciIoProtocolWidth,
+        offset: u32,
+        count: usize,
+        buffer: *mut c_void,
+    ) -> Status,
+    pub write: unsafe extern "efiapi" fn(
+        this: *mut PciIoProtocol,
+        width: PciIoProtocolWidth,
+        offset: u32,
+        count: usize,
+        buffer: *const c_void,
+    ) -> Status,
+}
+
+#[derive(Debug)]
+#[repr(C)]
+pub struct PciIoProtocol {
+    pub poll_mem: unsafe extern "efiapi" fn(
+        this: *mut Self,
+        width: PciIoProtocolWidth,
+        bar_index: u8,
+        offset: u64,
+        mask: u64,
+        value: u64,
+        delay: u64,
+        result: *mut u64,
+    ) -> Status,
+    pub poll_io: unsafe extern "efiapi" fn(
+        this: *mut Self,
+        width: PciIoProtocolWidth,
+        bar_index: u8,
+        offset: u64,
+        mask: u64,
+        value: u64,
+        delay: u64,
+        result: *mut u64,
+    ) -> Status,
+    pub mem: PciIoAccess,
+    pub io: PciIoAccess,
+    pub pci: PciIoConfigAccess,
+    pub copy_mem: unsafe extern "efiapi" fn(
+        this: *mut Self,
+        width: PciIoProtocolWidth,
+        dest_bar_index: u8,
+        dest_offset: u64,
+        src_bar_index: u8,
+        src_offset: u64,
+        count: usize,
+    ) -> Status,
+    pub map: unsafe extern "efiapi" fn(
+        this: *const Self,
+        operation: PciIoProtocolOperation,
+        host_addr: *const c_void,
+        num_bytes: *mut usize,
+        device_addr: *mut PhysicalAddress,
+        mapping: *mut *mut c_void,
+    ) -> Status,
+    pub unmap: unsafe extern "efiapi" fn(this: *const Self, mapping: *const c_void) -> Status,
+    pub allocate_buffer: unsafe extern "efiapi" fn(
+        this: *const Self,
+        alloc_ty: AllocateType,
+        memory_ty: MemoryType,
+        pages: usize,
+        host_addr: *mut *const c_void,
+        attributes: u64,
+    ) -> Status,
+    pub free_buffer: unsafe extern "efiapi" fn(
+        this: *const Self,
+        pages: usize,
+        host_addr: *const c_void,
+    ) -> Status,
+    pub flush: unsafe extern "efiapi" fn(this: *mut Self) -> Status,
+    pub get_location: unsafe extern "efiapi" fn(
+        this: *const Self,
+        segment_number: *mut usize,
+        bus_number: *mut usize,
+        device_number: *mut usize,
+        function_number: *mut usize,
+    ) -> Status,
+    pub attributes: unsafe extern "efiapi" fn(
+        this: *mut Self,
+        operation: PciIoProtocolAttributeOperation,
+        attributes: u64,
+        result: *mut u64,
+    ) -> Status,
+    pub get_bar_attributes: unsafe extern "efiapi" fn(
+        this: *const Self,
+        bar_index: u8,
+        supports: *mut u64,
+        resources: *mut *const c_void,
+    ) -> Status,
+    pub set_bar_attributes: unsafe extern "efiapi" fn(
+        this: *mut Self,
+        attributes: u64,
+        bar_index: u8,
+        offset: *mut u64,
+        length: *mut u64,
+    ) -> Status,
+    pub rom_size: u64,
+    pub rom_image: *const c_void,
+}
+
+impl PciIoProtocol {
+    pub const GUID: Guid = guid!("4cf5b200-68b8-4ca5-9eec-b23e3f50029a");
+}
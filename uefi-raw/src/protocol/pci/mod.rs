@@ -1,3 +1,6 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+pub mod hot_plug;
+pub mod io;
+pub mod resource;
 pub mod root_bridge;
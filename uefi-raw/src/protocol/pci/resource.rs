@@ -0,0 +1,81 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! ACPI resource descriptors used to describe PCI resources, e.g. by
+//! [`PciRootBridgeIoProtocol::configuration`] and the resource padding of
+//! [`PciHotPlugInitProtocol::get_resource_padding`].
+//!
+//! [`PciRootBridgeIoProtocol::configuration`]: super::root_bridge::PciRootBridgeIoProtocol::configuration
+//! [`PciHotPlugInitProtocol::get_resource_padding`]: super::hot_plug::PciHotPlugInitProtocol::get_resource_padding
+
+use crate::newtype_enum;
+
+newtype_enum! {
+    /// Resource type of a [`QwordAddressSpaceDescriptor`].
+    pub enum ResourceType: u8 => {
+        MEMORY = 0,
+        IO = 1,
+        BUS = 2,
+    }
+}
+
+/// ACPI QWORD address space descriptor.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct QwordAddressSpaceDescriptor {
+    /// Always [`Self::TAG`].
+    pub tag: u8,
+    /// Length of the descriptor after this field, always [`Self::LENGTH`].
+    pub length: u16,
+    pub resource_type: ResourceType,
+    /// General flags.
+    pub general_flags: u8,
+    /// Flags specific to the resource type, e.g. the prefetchable bit for
+    /// memory.
+    pub type_specific_flags: u8,
+    /// Alignment of the range. In resource padding, the alignment is
+    /// `address_range_maximum + 1`.
+    pub address_space_granularity: u64,
+    pub address_range_minimum: u64,
+    pub address_range_maximum: u64,
+    pub address_translation_offset: u64,
+    pub address_length: u64,
+}
+
+impl QwordAddressSpaceDescriptor {
+    /// Tag of a QWORD address space descriptor.
+    pub const TAG: u8 = 0x8a;
+    /// Value of the `length` field.
+    pub const LENGTH: u16 = 0x2b;
+
+    /// Type-specific flag of memory ranges: the memory is prefetchable.
+    pub const MEMORY_PREFETCHABLE: u8 = 0x06;
+}
+
+/// ACPI end tag descriptor, terminating a list of resource descriptors.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct EndTagDescriptor {
+    /// Always [`Self::TAG`].
+    pub tag: u8,
+    /// Checksum of the descriptors, or zero if not used.
+    pub checksum: u8,
+}
+
+impl EndTagDescriptor {
+    /// Tag of an end tag descriptor.
+    pub const TAG: u8 = 0x79;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn abi() {
+        assert_eq!(
+            size_of::<QwordAddressSpaceDescriptor>(),
+            3 + usize::from(QwordAddressSpaceDescriptor::LENGTH)
+        );
+        assert_eq!(size_of::<EndTagDescriptor>(), 2);
+    }
+}
@@ -5,7 +5,8 @@
 use crate::protocol::device_path::DevicePathProtocol;
 use crate::table::Header;
 use crate::{
-    Boolean, Char16, Event, Guid, Handle, PhysicalAddress, Status, VirtualAddress, newtype_enum,
+    Boolean, Char16, Event, Guid, Handle, PhysicalAddress, Status, VirtualAddress, guid,
+    newtype_enum,
 };
 use bitflags::bitflags;
 use core::ffi::c_void;
@@ -287,6 +288,45 @@ pub enum InterfaceType: u32 => {
     NATIVE_INTERFACE = 0,
 }}
 
+newtype_enum! {
+    /// GUID identifying an event group. When an event in a group is
+    /// signaled, all events in that group are signaled. The UEFI
+    /// specification defines groups that the firmware signals at specific
+    /// points of the boot process; vendors may define their own.
+    pub enum EventGroup: Guid => {
+        /// Signaled when `ExitBootServices` is called, after the
+        /// `BEFORE_EXIT_BOOT_SERVICES` group.
+        EXIT_BOOT_SERVICES = guid!("27abf055-b1b8-4c26-8048-748f37baa2df"),
+
+        /// Signaled when `ExitBootServices` is called, before the
+        /// `EXIT_BOOT_SERVICES` group.
+        BEFORE_EXIT_BOOT_SERVICES = guid!("8be0e274-3970-4b44-80c5-1ab9502f3bfc"),
+
+        /// Signaled when `SetVirtualAddressMap` is called.
+        VIRTUAL_ADDRESS_CHANGE = guid!("13fa7698-c831-49c7-87ea-8f43fcc25196"),
+
+        /// Signaled when the memory map changes due to allocations or
+        /// frees of memory.
+        MEMORY_MAP_CHANGE = guid!("78bee926-692f-48fd-9edb-01422ef0d7ab"),
+
+        /// Signaled by the boot manager right before it attempts to boot
+        /// a boot option.
+        READY_TO_BOOT = guid!("7ce88fb3-4bd7-4679-87a8-a8d8dee50d2b"),
+
+        /// Signaled by the boot manager right after the `READY_TO_BOOT`
+        /// group has been signaled.
+        AFTER_READY_TO_BOOT = guid!("3a2a00ad-98b9-4cdf-a478-702777f1c10b"),
+
+        /// Signaled when `ResetSystem` is called, before the platform is
+        /// reset.
+        RESET_SYSTEM = guid!("62da6a56-13fb-485a-a8da-a3dd7912cb6b"),
+
+        /// Signaled by the HII database when the current keyboard layout
+        /// changes.
+        SET_KEYBOARD_LAYOUT = guid!("14982a4f-b0ed-45b8-a811-5a7a9bc232df"),
+    }
+}
+
 /// Raw event notification function.
 pub type EventNotifyFn = unsafe extern "efiapi" fn(event: Event, context: *mut c_void);
 
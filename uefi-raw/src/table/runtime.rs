@@ -159,6 +159,100 @@ bitflags! {
     }
 }
 
+bitflags! {
+    /// Flags of the `OsIndications` and `OsIndicationsSupported` global
+    /// variables, requesting features of the firmware on the next boot.
+    #[repr(transparent)]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct OsIndications: u64 {
+        /// Stop in the firmware user interface on the next boot.
+        const BOOT_TO_FW_UI = 0x01;
+
+        /// Revocation entries with timestamps are supported in `dbx`.
+        const TIMESTAMP_REVOCATION = 0x02;
+
+        /// Process capsules on the EFI System Partition on the next boot.
+        const FILE_CAPSULE_DELIVERY_SUPPORTED = 0x04;
+
+        /// Firmware management protocol capsules are supported.
+        const FMP_CAPSULE_SUPPORTED = 0x08;
+
+        /// Capsule results are reported in `Capsule####` variables.
+        const CAPSULE_RESULT_VAR_SUPPORTED = 0x10;
+
+        /// Start OS-defined recovery on the next boot.
+        const START_OS_RECOVERY = 0x20;
+
+        /// Start platform-defined recovery on the next boot.
+        const START_PLATFORM_RECOVERY = 0x40;
+
+        /// Refresh the JSON configuration data on the next boot.
+        const JSON_CONFIG_DATA_REFRESH = 0x80;
+    }
+}
+
+/// Table describing which runtime services remain callable after exiting
+/// boot services, installed in the configuration table under
+/// [`RtPropertiesTable::GUID`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(C)]
+pub struct RtPropertiesTable {
+    /// Version of the table, currently [`RtPropertiesTable::VERSION`].
+    pub version: u16,
+
+    /// Size in bytes of the table.
+    pub length: u16,
+
+    /// Runtime services that are still supported after exiting boot
+    /// services.
+    pub runtime_services_supported: RuntimeServicesSupported,
+}
+
+impl RtPropertiesTable {
+    /// Configuration table GUID of the table.
+    pub const GUID: Guid = guid!("eb66918a-7eef-402a-842e-931d21c38ae9");
+
+    /// Current version of the table.
+    pub const VERSION: u16 = 1;
+}
+
+bitflags! {
+    /// Runtime services that are supported after exiting boot services,
+    /// as reported by the [`RtPropertiesTable`].
+    #[repr(transparent)]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct RuntimeServicesSupported: u32 {
+        /// `GetTime`.
+        const GET_TIME = 0x0001;
+        /// `SetTime`.
+        const SET_TIME = 0x0002;
+        /// `GetWakeupTime`.
+        const GET_WAKEUP_TIME = 0x0004;
+        /// `SetWakeupTime`.
+        const SET_WAKEUP_TIME = 0x0008;
+        /// `GetVariable`.
+        const GET_VARIABLE = 0x0010;
+        /// `GetNextVariableName`.
+        const GET_NEXT_VARIABLE_NAME = 0x0020;
+        /// `SetVariable`.
+        const SET_VARIABLE = 0x0040;
+        /// `SetVirtualAddressMap`.
+        const SET_VIRTUAL_ADDRESS_MAP = 0x0080;
+        /// `ConvertPointer`.
+        const CONVERT_POINTER = 0x0100;
+        /// `GetNextHighMonotonicCount`.
+        const GET_NEXT_HIGH_MONOTONIC_COUNT = 0x0200;
+        /// `ResetSystem`.
+        const RESET_SYSTEM = 0x0400;
+        /// `UpdateCapsule`.
+        const UPDATE_CAPSULE = 0x0800;
+        /// `QueryCapsuleCapabilities`.
+        const QUERY_CAPSULE_CAPABILITIES = 0x1000;
+        /// `QueryVariableInfo`.
+        const QUERY_VARIABLE_INFO = 0x2000;
+    }
+}
+
 newtype_enum! {
     /// Variable vendor GUID. This serves as a namespace for variables to
     /// avoid naming conflicts between vendors. The UEFI specification
@@ -6,6 +6,7 @@ pub fn test() {
     bootservices::allocate_pages();
     bootservices::allocate_pool();
     bootservices::memory_map();
+    bootservices::copy_set_mem();
 
     global::alloc_vec();
     global::alloc_alignment();
@@ -50,6 +51,45 @@ mod bootservices {
         unsafe { boot::free_pool(ptr) }.unwrap();
     }
 
+    /// Tests the `copy_mem` and `set_mem` boot services, and compares their
+    /// speed with the `core` equivalents.
+    pub fn copy_set_mem() {
+        let mut buffer = [0u8; 16];
+        boot::set_mem(&mut buffer, 0xaa);
+        assert_eq!(buffer, [0xaa; 16]);
+
+        let src: [u8; 16] = core::array::from_fn(|i| i as u8);
+        boot::copy_mem(&mut buffer, &src);
+        assert_eq!(buffer, src);
+
+        // Overlapping copies in both directions.
+        boot::copy_mem_within(&mut buffer, 0..8, 4);
+        assert_eq!(buffer[..12], [0, 1, 2, 3, 0, 1, 2, 3, 4, 5, 6, 7]);
+        boot::copy_mem_within(&mut buffer, 8..16, 6);
+        assert_eq!(buffer[6..14], [4, 5, 6, 7, 12, 13, 14, 15]);
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            use core::arch::x86_64::_rdtsc;
+
+            let len = 1 << 20;
+            let mut dest = alloc::vec![0u8; len];
+            let src = alloc::vec![0x55u8; len];
+            let cycles = |f: &mut dyn FnMut()| {
+                let start = unsafe { _rdtsc() };
+                f();
+                let end = unsafe { _rdtsc() };
+                end - start
+            };
+            let firmware = cycles(&mut || boot::copy_mem(&mut dest, &src));
+            let core = cycles(&mut || dest.copy_from_slice(&src));
+            info!("Copying 1 MiB: CopyMem {firmware} cycles, core {core} cycles");
+            let firmware = cycles(&mut || boot::set_mem(&mut dest, 0));
+            let core = cycles(&mut || dest.fill(0));
+            info!("Filling 1 MiB: SetMem {firmware} cycles, core {core} cycles");
+        }
+    }
+
     /// Tests getting the memory map and performing a few sanity checks on it.
     pub fn memory_map() {
         info!("Testing memory map functions");
@@ -86,6 +126,14 @@ mod bootservices {
         }
         let page_count = first_desc.page_count;
         assert!(page_count != 0, "Memory map entry has size zero");
+
+        // Refreshing reuses the buffer unless the map outgrew it.
+        let capacity = memory_map.capacity();
+        let ptr = boot::allocate_pool(MemoryType::LOADER_DATA, 10).unwrap();
+        memory_map.refresh().unwrap();
+        assert!(!memory_map.is_empty());
+        assert!(memory_map.capacity() >= capacity);
+        unsafe { boot::free_pool(ptr) }.unwrap();
     }
 }
 
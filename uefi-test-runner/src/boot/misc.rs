@@ -2,22 +2,32 @@
 
 use core::ffi::c_void;
 use core::ptr::{self, NonNull};
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::time::Duration;
 
 use uefi::boot::{
-    EventType, OpenProtocolAttributes, OpenProtocolParams, SearchType, TimerTrigger, Tpl,
+    EventGroup, EventType, OpenProtocolAttributes, OpenProtocolParams, SearchType, TimerTrigger,
+    Tpl,
 };
 use uefi::mem::memory_map::MemoryType;
 use uefi::proto::unsafe_protocol;
-use uefi::{Event, Guid, Identify, boot, guid, system};
+use uefi::sync::OnceCell;
+use uefi::table::hook::Hook;
+use uefi::time::{self, DelayMethod};
+use uefi::trace::{self, EventKind};
+use uefi::{Event, Guid, Identify, Status, boot, guid, system};
 
 pub fn test() {
     test_tpl();
     info!("Testing timer...");
     test_timer();
+    test_delay();
+    test_trace();
     info!("Testing events...");
     test_check_event();
     test_callback_with_ctx();
     test_signal_event();
+    test_event_group();
     info!("Testing watchdog...");
     test_watchdog();
     info!("Testing protocol handler services...");
@@ -28,6 +38,8 @@ pub fn test() {
     test_install_configuration_table();
     info!("Testing crc32...");
     test_calculate_crc32();
+    info!("Testing boot services hooks...");
+    test_hook();
 }
 
 fn test_tpl() {
@@ -36,6 +48,62 @@ fn test_tpl() {
     let _guard = unsafe { boot::raise_tpl(Tpl::NOTIFY) };
 }
 
+fn test_delay() {
+    assert_eq!(time::current_tpl(), Tpl::APPLICATION);
+    {
+        let _guard = unsafe { boot::raise_tpl(Tpl::NOTIFY) };
+        assert_eq!(time::current_tpl(), Tpl::NOTIFY);
+    }
+
+    let long = Duration::from_millis(20);
+    assert_eq!(
+        time::delay_method(long, Tpl::APPLICATION),
+        DelayMethod::TimerEvent
+    );
+    time::delay(long);
+    time::delay(Duration::from_micros(50));
+
+    if let Some(frequency) = time::calibrate() {
+        info!("TSC frequency: {frequency} Hz");
+        assert_eq!(
+            time::delay_method(long, Tpl::HIGH_LEVEL),
+            DelayMethod::BusyWait
+        );
+        let _guard = unsafe { boot::raise_tpl(Tpl::HIGH_LEVEL) };
+        time::delay(Duration::from_millis(1));
+    }
+}
+
+fn test_trace() {
+    let Ok(clock) = trace::Clock::detect() else {
+        info!("No clock available for tracing");
+        return;
+    };
+    trace::start(clock, 3);
+    assert!(trace::is_enabled());
+    {
+        let _span = trace::span("stall");
+        boot::stall(Duration::from_millis(1));
+    }
+    trace::counter("count", 1);
+    trace::instant("mark");
+    trace::instant("dropped");
+    let trace = trace::stop().unwrap();
+    assert!(!trace::is_enabled());
+
+    assert_eq!(trace.events().len(), 3);
+    assert_eq!(trace.dropped(), 1);
+    let EventKind::Span { duration } = trace.events()[0].kind else {
+        panic!("not a span");
+    };
+    assert!(duration >= trace.frequency() / 1000);
+    assert!(
+        trace
+            .to_chrome_json()
+            .starts_with(r#"{"traceEvents":[{"name":"stall""#)
+    );
+}
+
 fn test_check_event() {
     extern "efiapi" fn callback(_event: Event, _ctx: Option<NonNull<c_void>>) {
         info!("Callback triggered by check_event");
@@ -95,6 +163,30 @@ fn test_callback_with_ctx() {
     assert_eq!(data, 456);
 }
 
+fn test_event_group() {
+    static CALLED: AtomicUsize = AtomicUsize::new(0);
+
+    fn callback() {
+        CALLED.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // Signaling a group signals every event in it.
+    let group = EventGroup(guid!("5a1e9ab3-2f0c-4d7e-9b41-6c3f8e2d7a10"));
+    let first = boot::on_event_group(group, callback).unwrap();
+    let second = boot::on_event_group(group, callback).unwrap();
+    boot::signal_event(&first).unwrap();
+    assert_eq!(CALLED.load(Ordering::Relaxed), 2);
+    boot::signal_event_group(group).unwrap();
+    assert_eq!(CALLED.load(Ordering::Relaxed), 4);
+    boot::close_event(first).unwrap();
+    boot::close_event(second).unwrap();
+
+    // Only register for the real group; signaling it would start booting.
+    let event = boot::on_ready_to_boot(callback).unwrap();
+    boot::close_event(event).unwrap();
+    assert_eq!(CALLED.load(Ordering::Relaxed), 4);
+}
+
 fn test_signal_event() {
     let mut data = 123u32;
 
@@ -257,3 +349,25 @@ fn test_calculate_crc32() {
 
     assert_eq!(crc, 0xcfc96a3e);
 }
+
+fn test_hook() {
+    type StallFn = unsafe extern "efiapi" fn(usize) -> Status;
+    static ORIGINAL: OnceCell<StallFn> = OnceCell::new();
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    unsafe extern "efiapi" fn stall(microseconds: usize) -> Status {
+        CALLS.fetch_add(1, Ordering::Relaxed);
+        unsafe { ORIGINAL.get().unwrap()(microseconds) }
+    }
+
+    let hook = unsafe { Hook::<StallFn>::install(|bs| &mut bs.stall, stall) };
+    let _ = ORIGINAL.set(hook.original());
+    assert!(hook.is_active());
+
+    boot::stall(Duration::from_micros(1));
+    assert_eq!(CALLS.load(Ordering::Relaxed), 1);
+
+    hook.unhook();
+    boot::stall(Duration::from_micros(1));
+    assert_eq!(CALLS.load(Ordering::Relaxed), 1);
+}
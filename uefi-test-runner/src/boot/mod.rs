@@ -7,7 +7,9 @@ use uefi::proto::BootPolicy;
 use uefi::proto::console::text::Output;
 use uefi::proto::device_path::media::FilePath;
 use uefi::proto::device_path::{DevicePath, LoadedImageDevicePath};
-use uefi::{CString16, Identify, boot};
+use uefi::quirks::{self, QuirkEntry, Quirks};
+use uefi::security::iommu::{Dmar, Ivrs};
+use uefi::{CString16, Identify, boot, labels, probe, security};
 
 mod memory;
 mod misc;
@@ -17,9 +19,91 @@ pub fn test() {
     memory::test();
     misc::test();
     test_locate_handles();
+    test_probe();
+    test_security_audit();
+    test_labels();
+    test_quirks();
     test_load_image();
 }
 
+fn test_probe() {
+    info!("Testing the `probe::report` function");
+
+    let report = probe::report();
+    assert!(report.supports::<Output>());
+    assert!(report.supports::<LoadedImageDevicePath>());
+    assert!(report.present().count() > 0);
+    debug!("{report}");
+}
+
+fn test_security_audit() {
+    info!("Testing the `security::audit` function");
+
+    let report = security::audit();
+    // The test runner is not signed, so it only runs if Secure Boot does not
+    // verify images.
+    assert!(!report.secure_boot.is_enforced());
+    if let Some(tpm) = report.tpm {
+        assert!(tpm.supported_pcr_banks.contains(tpm.active_pcr_banks));
+    }
+    info!("{report}");
+
+    let dmar = Dmar::find();
+    assert_eq!(report.dma_protection.dmar, dmar.is_some());
+    if let Some(dmar) = dmar {
+        debug!("{dmar:?}");
+    }
+    let ivrs = Ivrs::find();
+    assert_eq!(report.dma_protection.ivrs, ivrs.is_some());
+    if let Some(ivrs) = ivrs {
+        debug!("{ivrs:?}");
+    }
+}
+
+fn test_labels() {
+    info!("Testing the `labels` module");
+
+    // Handles with a device path get a label.
+    let handle = boot::find_handles::<DevicePath>().unwrap()[0];
+    let label = labels::label(handle).expect("no label for device");
+    assert_eq!(labels::get(handle), Some(label.clone()));
+    assert!(format!("{handle:?}").contains(&label));
+
+    // Labels can be set explicitly.
+    labels::set(handle, "test device");
+    assert!(format!("{handle:?}").contains("test device"));
+
+    labels::clear();
+    assert_eq!(labels::get(handle), None);
+    labels::label_all();
+    assert!(labels::get(handle).is_some());
+    labels::clear();
+}
+
+fn test_quirks() {
+    info!("Testing the `quirks` module");
+
+    // OVMF provides SMBIOS tables when running in QEMU.
+    let system = quirks::system_id().expect("no SMBIOS system information");
+    info!("System: {system}");
+    assert_eq!(system.manufacturer, "QEMU");
+    assert!(!quirks::is_active(Quirks::VARIABLE_NAME_SIZE_NOT_UPDATED));
+
+    quirks::register(QuirkEntry {
+        manufacturer: "qemu",
+        product: "",
+        quirks: Quirks::VARIABLE_NAME_SIZE_NOT_UPDATED,
+    });
+    assert!(quirks::is_active(Quirks::VARIABLE_NAME_SIZE_NOT_UPDATED));
+    quirks::disable(Quirks::VARIABLE_NAME_SIZE_NOT_UPDATED);
+    assert!(!quirks::is_active(Quirks::VARIABLE_NAME_SIZE_NOT_UPDATED));
+
+    quirks::enable(Quirks::BROKEN_TIMER_EVENTS);
+    assert_eq!(quirks::active(), Quirks::BROKEN_TIMER_EVENTS);
+    quirks::disable(Quirks::BROKEN_TIMER_EVENTS);
+    assert_eq!(quirks::active(), Quirks::empty());
+}
+
 fn test_locate_handles() {
     info!("Testing the `locate_handle_buffer`/`find_handles` functions");
 
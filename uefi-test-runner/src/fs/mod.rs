@@ -4,8 +4,9 @@
 
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
+use core::time::Duration;
 use uefi::boot::ScopedProtocol;
-use uefi::fs::{FileSystem, IoError, IoErrorContext, PathBuf};
+use uefi::fs::{DirChange, FileSystem, IoError, IoErrorContext, OpenOptions, PathBuf};
 use uefi::proto::media::fs::SimpleFileSystem;
 use uefi::{Status, cstr16, fs};
 
@@ -60,6 +61,9 @@ pub fn test(sfs: ScopedProtocol<SimpleFileSystem>) -> Result<(), fs::Error> {
     test_copy_error(&mut fs)?;
     test_copy_success(&mut fs)?;
     test_copy_success_chunks(&mut fs)?;
+    test_open_options(&mut fs)?;
+    test_write_atomic(&mut fs)?;
+    test_watch(&mut fs)?;
 
     Ok(())
 }
@@ -170,3 +174,94 @@ fn test_copy_success_chunks(fs: &mut FileSystem) -> Result<(), fs::Error> {
 
     Ok(())
 }
+
+fn test_open_options(fs: &mut FileSystem) -> Result<(), fs::Error> {
+    let path = cstr16!("open_options");
+
+    // A new file can be created only once.
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(fs, path)?;
+    file.write(b"hello").unwrap();
+    drop(file);
+    let err = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(fs, path)
+        .unwrap_err();
+    assert_eq!(err.kind(), uefi::ErrorKind::AlreadyExists);
+    assert!(matches!(
+        err,
+        fs::Error::Io(IoError {
+            context: IoErrorContext::AlreadyExists,
+            ..
+        })
+    ));
+
+    // Appending writes after the existing data.
+    let mut file = OpenOptions::new().append(true).open(fs, path)?;
+    file.write(b" world").unwrap();
+    drop(file);
+    assert_eq!(fs.read(path)?, b"hello world");
+
+    // Truncating removes the existing data.
+    let mut file = OpenOptions::new()
+        .write(true)
+        .truncate(true)
+        .open(fs, path)?;
+    file.write(b"bye").unwrap();
+    drop(file);
+    assert_eq!(fs.read(path)?, b"bye");
+
+    // Truncating requires write access.
+    let err = OpenOptions::new()
+        .read(true)
+        .truncate(true)
+        .open(fs, path)
+        .unwrap_err();
+    assert_eq!(err.kind(), uefi::ErrorKind::InvalidInput);
+
+    fs.remove_file(path)
+}
+
+fn test_write_atomic(fs: &mut FileSystem) -> Result<(), fs::Error> {
+    fs.create_dir(cstr16!("atomic_dir"))?;
+    let path = cstr16!("atomic_dir\\config.txt");
+
+    // Writing creates the file, and writing again replaces it.
+    fs.write_atomic(path, b"first")?;
+    assert_eq!(fs.read(path)?, b"first");
+    fs.write_atomic(path, b"second version")?;
+    assert_eq!(fs.read(path)?, b"second version");
+
+    // The temporary file has been renamed.
+    assert!(!fs.try_exists(cstr16!("atomic_dir\\config.txt.tmp"))?);
+
+    fs.remove_dir_all(cstr16!("atomic_dir"))
+}
+
+fn test_watch(fs: &mut FileSystem) -> Result<(), fs::Error> {
+    fs.create_dir(cstr16!("watch_dir"))?;
+    fs.write(cstr16!("watch_dir\\old"), b"old")?;
+    let mut watcher = fs.watch(cstr16!("watch_dir"), Duration::from_millis(10))?;
+    assert_eq!(watcher.poll(fs)?, []);
+
+    // The timer picks up a new file.
+    fs.write(cstr16!("watch_dir\\new"), b"new")?;
+    assert_eq!(watcher.wait(fs)?, [DirChange::Added(cstr16!("new").into())]);
+
+    fs.write(cstr16!("watch_dir\\new"), b"newer")?;
+    fs.remove_file(cstr16!("watch_dir\\old"))?;
+    assert_eq!(
+        watcher.poll(fs)?,
+        [
+            DirChange::Modified(cstr16!("new").into()),
+            DirChange::Removed(cstr16!("old").into()),
+        ]
+    );
+    assert_eq!(watcher.poll(fs)?, []);
+
+    drop(watcher);
+    fs.remove_dir_all(cstr16!("watch_dir"))
+}
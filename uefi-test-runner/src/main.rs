@@ -41,6 +41,9 @@ fn efi_main() -> Status {
     // Check the `uefi::system` module.
     check_system();
 
+    // Check the logger configuration.
+    check_logger();
+
     // Try retrieving a handle to the file system the image was booted from.
     uefi::boot::get_image_file_system(uefi::boot::image_handle())
         .expect("Failed to retrieve boot file system");
@@ -59,6 +62,28 @@ fn efi_main() -> Status {
     shutdown();
 }
 
+fn check_logger() {
+    use uefi::proto::console::text::Color;
+
+    let logger = uefi::helpers::logger();
+    assert!(matches!(
+        logger.level_color(log::Level::Error),
+        Some(Color::LightRed)
+    ));
+    logger.set_level_color(log::Level::Info, Some(Color::LightGreen));
+    info!("Colored info message");
+    logger.set_level_color(log::Level::Info, None);
+    assert!(logger.level_color(log::Level::Info).is_none());
+
+    match logger.enable_timestamps() {
+        Ok(()) => {
+            info!("Message with timestamp");
+            logger.disable_timestamps();
+        }
+        Err(err) => info!("Log timestamps are not supported: {err}"),
+    }
+}
+
 fn check_revision(rev: uefi::table::Revision) {
     assert_eq!(system::uefi_revision(), rev);
 
@@ -193,6 +218,11 @@ fn send_request_to_host(request: HostRequest) {
 }
 
 fn shutdown() -> ! {
+    // Report what the tests left behind. Some resources are kept alive on
+    // purpose, so the result is only logged.
+    let leaks = uefi::helpers::check_leaks().expect("Failed to check for leaks");
+    info!("Leak check: {leaks}");
+
     // Get our text output back.
     system::with_stdout(|stdout| stdout.reset(false).unwrap());
 
@@ -2,13 +2,17 @@
 
 use uefi::boot;
 use uefi::boot::{OpenProtocolAttributes, OpenProtocolParams};
+use uefi::disk::smart::{self, DiskInterface};
 use uefi::proto::ata::AtaRequestBuilder;
 use uefi::proto::ata::pass_thru::AtaPassThru;
+use uefi::proto::media::block::BlockIO;
 
 pub fn test() {
     info!("Running ATA PassThru tests");
 
     assert!(is_testdrive_present());
+    test_identify();
+    test_health();
 }
 
 const ATACMD_IDENTIFY: u8 = 0xEC;
@@ -57,3 +61,36 @@ fn is_testdrive_present() -> bool {
 
     false
 }
+
+fn test_identify() {
+    let handle = boot::find_handles::<AtaPassThru>().unwrap()[0];
+    let params = OpenProtocolParams {
+        handle,
+        agent: boot::image_handle(),
+        controller: None,
+    };
+    let ata_pt = unsafe {
+        // don't open exclusive! That would break other tests
+        boot::open_protocol::<AtaPassThru>(params, OpenProtocolAttributes::GetProtocol).unwrap()
+    };
+    let identify = ata_pt
+        .iter_devices()
+        .filter_map(|mut device| device.identify().ok())
+        .inspect(|identify| info!("Found ATA device: {identify:?}"))
+        .find(|identify| identify.serial_number() == "AtaPassThru")
+        .unwrap();
+    assert!(identify.is_checksum_valid());
+    assert_ne!(identify.sector_count(), 0);
+}
+
+fn test_health() {
+    let report = boot::find_handles::<BlockIO>()
+        .unwrap()
+        .into_iter()
+        .filter_map(|handle| smart::health(handle).ok())
+        .find(|report| report.serial_number == "AtaPassThru")
+        .unwrap();
+    assert_eq!(report.interface, DiskInterface::Ata);
+    info!("{report}");
+    assert!(!report.attributes.is_empty());
+}
@@ -2,7 +2,10 @@
 
 use crate::{HostRequest, send_request_to_host};
 use uefi::boot::{self, OpenProtocolAttributes, OpenProtocolParams};
-use uefi::proto::console::gop::{BltOp, BltPixel, FrameBuffer, GraphicsOutput, PixelFormat};
+use uefi::proto::console::gop::{
+    BltOp, BltPixel, BltRegion, Bmp, Canvas, EdidActive, FrameBuffer, FrameBufferCanvas,
+    FrameBufferSurface, GraphicsOutput, PixelFormat, Rect,
+};
 
 pub unsafe fn test() {
     info!("Running graphics output protocol test");
@@ -31,6 +34,10 @@ pub unsafe fn test() {
     if cfg!(not(target_arch = "aarch64")) {
         send_request_to_host(HostRequest::Screenshot("gop_test"));
     }
+
+    test_surface(gop);
+    test_screenshot(gop);
+    test_edid(gop);
 }
 
 // Set a larger graphics mode.
@@ -106,3 +113,81 @@ fn draw_fb(gop: &mut GraphicsOutput) {
     fill_rectangle((50, 30), (150, 600), [250, 128, 64]);
     fill_rectangle((400, 120), (750, 450), [16, 128, 255]);
 }
+
+// Copy the screen to a surface and flush it back, which leaves the screen
+// unchanged.
+fn test_surface(gop: &mut GraphicsOutput) {
+    let mut surface = FrameBufferSurface::new(gop);
+    assert_eq!((surface.width(), surface.height()), (1024, 768));
+
+    let dims = (surface.width(), surface.height());
+    gop.blt(BltOp::VideoToBltBuffer {
+        buffer: surface.pixels_mut(),
+        src: (0, 0),
+        dest: BltRegion::Full,
+        dims,
+    })
+    .expect("Failed to read the screen");
+    let pixel = surface.pixel(100, 100).unwrap();
+    surface.flush(gop).expect("Failed to flush surface");
+    assert!(surface.dirty_rects().is_empty());
+
+    surface.fill_rect(Rect::new(0, 0, 16, 16), surface.pixel(0, 0).unwrap());
+    surface.set_pixel(100, 100, pixel);
+    assert_eq!(surface.dirty_rects().len(), 2);
+    match gop.current_mode_info().pixel_format() {
+        PixelFormat::BltOnly => surface.flush(gop),
+        _ => surface.flush_to_frame_buffer(gop),
+    }
+    .expect("Failed to flush surface");
+    assert!(surface.dirty_rects().is_empty());
+
+    let mut read_back = [BltPixel::new(0, 0, 0)];
+    gop.blt(BltOp::VideoToBltBuffer {
+        buffer: &mut read_back,
+        src: (100, 100),
+        dest: BltRegion::Full,
+        dims: (1, 1),
+    })
+    .expect("Failed to read the screen");
+    assert_eq!(read_back[0], pixel);
+
+    // Drawing a pixel with its current color directly to the frame buffer.
+    if let Ok(mut canvas) = FrameBufferCanvas::new(gop) {
+        assert_eq!(canvas.size(), (1024, 768));
+        canvas.set_pixel(100, 100, pixel);
+    }
+}
+
+// Capture the screen and check that it survives a round trip through BMP.
+fn test_screenshot(gop: &mut GraphicsOutput) {
+    let image = gop.screenshot().expect("Failed to capture the screen");
+    assert_eq!((image.width(), image.height()), (1024, 768));
+
+    let bmp = image.to_bmp();
+    let decoded = Bmp::parse(&bmp).expect("Failed to parse the screenshot");
+    assert_eq!(decoded.to_image(), image);
+}
+
+// Parse the EDID of the display, if the video driver provides one.
+fn test_edid(gop: &GraphicsOutput) {
+    let Ok(handle) = boot::get_handle_for_protocol::<EdidActive>() else {
+        info!("No EDID available");
+        return;
+    };
+    let edid_active = boot::open_protocol_exclusive::<EdidActive>(handle).unwrap();
+    match edid_active.edid() {
+        Ok(edid) => {
+            info!(
+                "EDID: {:?} {:?}, preferred resolution {:?}",
+                core::str::from_utf8(&edid.manufacturer_id()),
+                edid.monitor_name(),
+                edid.preferred_resolution()
+            );
+            if let Some(mode) = gop.mode_for_edid(&edid) {
+                assert_eq!(Some(mode.info().resolution()), edid.preferred_resolution());
+            }
+        }
+        Err(err) => info!("Invalid EDID: {err}"),
+    }
+}
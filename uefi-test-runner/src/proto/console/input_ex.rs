@@ -0,0 +1,38 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use core::sync::atomic::{AtomicBool, Ordering};
+use uefi::boot::{self, OpenProtocolAttributes, OpenProtocolParams};
+use uefi::proto::console::text::{InputEx, Key, KeyShiftState, KeyStroke, ScanCode};
+
+pub fn test() {
+    info!("Running simple text input ex protocol test");
+    let handle = boot::get_handle_for_protocol::<InputEx>().expect("missing InputEx protocol");
+    let mut input = unsafe {
+        boot::open_protocol::<InputEx>(
+            OpenProtocolParams {
+                handle,
+                agent: boot::image_handle(),
+                controller: None,
+            },
+            // Don't open in exclusive mode, which would disconnect the
+            // keyboard from the console.
+            OpenProtocolAttributes::GetProtocol,
+        )
+        .unwrap()
+    };
+
+    // No key is pressed during the test.
+    assert_eq!(input.read_key_stroke_ex().unwrap(), None);
+    assert!(input.wait_for_key_event().is_some());
+
+    static CALLED: AtomicBool = AtomicBool::new(false);
+    let key = KeyStroke::new(
+        Key::Special(ScanCode::FUNCTION_12),
+        KeyShiftState::LEFT_CONTROL_PRESSED,
+    );
+    let notify = input
+        .register_key_notify(key, |_| CALLED.store(true, Ordering::Relaxed))
+        .unwrap();
+    input.unregister_key_notify(notify).unwrap();
+    assert!(!CALLED.load(Ordering::Relaxed));
+}
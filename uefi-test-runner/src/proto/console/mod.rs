@@ -1,20 +1,52 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use core::ptr;
+use core::task::{Context, Waker};
 use uefi::prelude::*;
+use uefi::proto::console::text::KeyStream;
 
 pub fn test() {
     info!("Testing console protocols");
 
     system::with_stdout(stdout::test);
+    test_replace_console();
+    test_key_stream();
 
     unsafe {
         serial::test();
         gop::test();
     }
     pointer::test();
+    input_ex::test();
+}
+
+// Temporarily point stderr at stdout.
+fn test_replace_console() {
+    let stdout = system::with_stdout(ptr::from_mut);
+    let stderr = system::with_stderr(ptr::from_mut);
+    {
+        let guard = unsafe { system::replace_stderr(boot::image_handle(), stdout) };
+        assert_eq!(guard.console(), system::Console::Stderr);
+        assert!(guard.previous_handle().is_some());
+        assert_eq!(system::with_stderr(ptr::from_mut), stdout);
+        system::with_stderr(|stderr| stderr.output_string(cstr16!("stderr -> stdout\r\n")))
+            .unwrap();
+    }
+    assert_eq!(system::with_stderr(ptr::from_mut), stderr);
+}
+
+fn test_key_stream() {
+    info!("Testing KeyStream");
+    system::with_stdin(|input| {
+        let mut stream = KeyStream::new(input).unwrap();
+        // No key is pressed during the test.
+        let mut cx = Context::from_waker(Waker::noop());
+        assert!(stream.poll_next_key(&mut cx).is_pending());
+    });
 }
 
 mod gop;
+mod input_ex;
 mod pointer;
 mod serial;
 mod stdout;
@@ -1,7 +1,9 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use alloc::vec::Vec;
+use core::fmt::Write;
 use uefi::prelude::*;
-use uefi::proto::console::text::{Color, Output};
+use uefi::proto::console::text::{Color, Output, Terminal};
 
 pub fn test(stdout: &mut Output) {
     info!("Running text output protocol test");
@@ -10,6 +12,8 @@ pub fn test(stdout: &mut Output) {
     change_text_mode(stdout);
     change_color(stdout);
     center_text(stdout);
+    save_state(stdout);
+    ansi_terminal(stdout);
 
     // Print all modes.
     for (index, mode) in stdout.modes().enumerate() {
@@ -31,12 +35,15 @@ fn get_current_mode(stdout: &mut Output) {
     info!("UEFI standard output current mode: {current_mode:?}");
 }
 
-// Switch to the maximum supported text mode.
+// Switch to the largest supported text mode.
 fn change_text_mode(stdout: &mut Output) {
-    let best_mode = stdout.modes().last().unwrap();
-    stdout
-        .set_mode(best_mode)
-        .expect("Failed to change text mode");
+    let modes = stdout.mode_list();
+    assert_eq!(modes, stdout.modes().collect::<Vec<_>>());
+    assert_eq!(stdout.query_mode(0).unwrap().columns(), 80);
+
+    let best_mode = stdout.set_best_mode().expect("Failed to change text mode");
+    assert!(modes.iter().all(|mode| mode.area() <= best_mode.area()));
+    assert_eq!(stdout.current_mode().unwrap(), Some(best_mode));
 }
 
 // Set a new color, and paint the background with it.
@@ -68,3 +75,37 @@ fn center_text(stdout: &mut Output) {
             _ => panic!("Failed to hide cursor"),
         });
 }
+
+// Print somewhere else, and check that the cursor and colors are restored.
+fn save_state(stdout: &mut Output) {
+    let position = stdout.cursor_position();
+    let info = stdout.mode_info();
+    assert_eq!(info.cursor_position(), position);
+    assert_eq!(info.cursor_visible(), stdout.cursor_visible());
+    {
+        let mut guard = stdout.save_cursor();
+        guard
+            .set_cursor_position(0, 1)
+            .expect("Failed to move cursor");
+        guard
+            .with_colors(Color::Yellow, Color::Black, |stdout| {
+                stdout.output_string(cstr16!("status"))
+            })
+            .expect("Failed to change console color")
+            .expect("Failed to print");
+        assert_eq!(guard.cursor_position(), (6, 1));
+    }
+    assert_eq!(stdout.cursor_position(), position);
+    assert_eq!(stdout.mode_info(), info);
+}
+
+// Move the cursor and change colors with escape sequences.
+fn ansi_terminal(stdout: &mut Output) {
+    let mut terminal = Terminal::new(stdout);
+    write!(terminal, "\x1b[3;5H\x1b[1;33mansi\x1b[0m\x1b[2D").unwrap();
+    terminal.write_bytes(b"\x1b[1B\x1b[").unwrap();
+    terminal.write_bytes(b"K").unwrap();
+    terminal.flush().unwrap();
+    drop(terminal);
+    assert_eq!(stdout.cursor_position(), (6, 3));
+}
@@ -0,0 +1,660 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! End-to-end test of the HII support, modeled after EDK2's DriverSample.
+//!
+//! A driver handle with a device path publishes a formset whose varstore is
+//! provided by the driver's own HII Config Access protocol. The
+//! configuration is then read and written through the HII Config Routing
+//! protocol, which locates the driver by the device path in the
+//! configuration header. The driver code doubles as a template for drivers
+//! publishing their own setup forms.
+
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::cell::Cell;
+use core::ptr;
+use core::str::FromStr;
+use uefi::boot;
+use uefi::proto::console::gop::BltPixel;
+use uefi::proto::console::text::{Key, ScanCode};
+use uefi::proto::device_path::DevicePath;
+use uefi::proto::device_path::build::{self, DevicePathBuilder};
+use uefi::proto::hii::browser::{Browser, BrowserExit, FirmwareVarstores};
+use uefi::proto::hii::config::HiiConfigAccess;
+use uefi::proto::hii::config_access::{
+    BrowserAction, BrowserActionRequest, CallbackValue, ConfigAccessHandler, ConfigAccessProvider,
+};
+use uefi::proto::hii::config_routing::HiiConfigRouting;
+use uefi::proto::hii::config_str::{
+    ConfigHdr, ConfigRequest, ConfigurationString, ConfigurationStringElement,
+    MultiConfigurationStringIter,
+};
+use uefi::proto::hii::database::HiiDatabase;
+use uefi::proto::hii::disasm::{Disassembly, HiiStrings};
+use uefi::proto::hii::font::{HiiFont, HiiOutFlags, TextStyle};
+use uefi::proto::hii::form::{FormSetBuilder, IfrQuestionFlags, NumericSize, Question};
+use uefi::proto::hii::image::{HiiDrawFlags, HiiImage, ImageInputFlags};
+use uefi::proto::hii::keyboard::{
+    self, KeyAffectedAttributes, KeyDescriptor, KeyState, KeyboardLayout, Modifier,
+};
+use uefi::proto::hii::package::{Package, PackageList, PackageListBuilder, StringTable};
+use uefi::proto::hii::popup::{HiiPopup, HiiPopupStyle, HiiPopupType};
+use uefi::proto::hii::settings::Settings;
+use uefi::proto::hii::string::HiiString;
+use uefi::proto::hii::varstore::Varstore;
+use uefi::proto::hii::{BltImage, HiiHandle, QuestionId, StringId};
+use uefi::{CStr16, CString16, Char16, Guid, Handle, cstr8, cstr16, guid};
+use uefi_raw::Status;
+use uefi_raw::protocol::device_path::DevicePathProtocol;
+use uefi_raw::protocol::hii::HiiPackageType;
+use uefi_raw::protocol::hii::config::HiiConfigAccessProtocol;
+use uefi_raw::protocol::hii::popup::HiiPopupProtocol;
+
+const FORMSET_GUID: Guid = guid!("a04a27f4-df00-4d42-b552-39511302113d");
+const VARSTORE_GUID: Guid = guid!("a04a27f4-df00-4d42-b552-39511302113e");
+const VARSTORE_NAME: &str = "SampleData";
+const VARSTORE_ID: u16 = 1;
+const QUESTION_LEVEL: QuestionId = 0x1001;
+
+/// The varstore of the sample formset.
+#[derive(Clone, Copy, Debug, Varstore)]
+#[repr(C)]
+struct SampleConfig {
+    enabled: u8,
+    mode: u8,
+    level: u16,
+}
+
+const DEFAULT_CONFIG: SampleConfig = SampleConfig {
+    enabled: 1,
+    mode: 0,
+    level: 10,
+};
+
+/// Sample driver implementing the HII Config Access protocol for its
+/// varstore.
+struct SampleDriver {
+    device_path: Box<DevicePath>,
+    config: Cell<SampleConfig>,
+    last_callback: Cell<Option<(BrowserAction, QuestionId)>>,
+}
+
+impl SampleDriver {
+    fn new(device_path: Box<DevicePath>) -> Self {
+        Self {
+            device_path,
+            config: Cell::new(DEFAULT_CONFIG),
+            last_callback: Cell::new(None),
+        }
+    }
+
+    /// Returns the `<ConfigHdr>` of the varstore of the driver.
+    fn header(&self) -> ConfigHdr {
+        config_header(&self.device_path)
+    }
+}
+
+impl ConfigAccessHandler for SampleDriver {
+    fn extract_config(&self, request: Option<&CStr16>) -> uefi::Result<CString16> {
+        // Without request, the whole varstore is returned.
+        let mut blocks = Vec::new();
+        if let Some(request) = request {
+            let request = request.to_string();
+            if !self.header().matches(&request) {
+                return Err(Status::NOT_FOUND.into());
+            }
+            let request =
+                ConfigRequest::from_str(&request).map_err(|_| Status::INVALID_PARAMETER)?;
+            for block in request.blocks {
+                blocks.push((block.offset as usize, block.width as usize));
+            }
+        }
+        if blocks.is_empty() {
+            blocks.push((0, usize::from(SampleConfig::SIZE)));
+        }
+
+        let config = self.config.get();
+        let bytes = config.as_bytes();
+        let mut elements = Vec::new();
+        for (offset, width) in blocks {
+            let value = bytes
+                .get(offset..offset + width)
+                .ok_or(Status::INVALID_PARAMETER)?;
+            elements.push(ConfigurationStringElement {
+                offset: offset as u64,
+                width: width as u64,
+                value: value.to_vec(),
+            });
+        }
+        let ConfigHdr {
+            guid,
+            name,
+            device_path,
+        } = self.header();
+        let response = ConfigurationString {
+            guid,
+            name,
+            device_path,
+            alt_cfg_id: None,
+            elements,
+            name_values: Vec::new(),
+        };
+        Ok(CString16::try_from(response.to_string().as_str()).unwrap())
+    }
+
+    fn route_config(&self, configuration: &CStr16) -> uefi::Result {
+        let configuration = configuration.to_string();
+        if !self.header().matches(&configuration) {
+            return Err(Status::NOT_FOUND.into());
+        }
+        let parsed =
+            ConfigurationString::from_str(&configuration).map_err(|_| Status::INVALID_PARAMETER)?;
+
+        let mut config = self.config.get();
+        let bytes = config.as_bytes_mut();
+        for element in parsed.elements {
+            let offset = element.offset as usize;
+            let target = bytes
+                .get_mut(offset..offset + element.value.len())
+                .ok_or(Status::INVALID_PARAMETER)?;
+            target.copy_from_slice(&element.value);
+        }
+        self.config.set(config);
+        Ok(())
+    }
+
+    fn callback(
+        &self,
+        action: BrowserAction,
+        question_id: QuestionId,
+        _value: Option<&mut CallbackValue>,
+    ) -> uefi::Result<BrowserActionRequest> {
+        self.last_callback.set(Some((action, question_id)));
+        match (action, question_id) {
+            (BrowserAction::CHANGED, QUESTION_LEVEL) => Ok(BrowserActionRequest::NONE),
+            _ => Err(Status::UNSUPPORTED.into()),
+        }
+    }
+}
+
+/// Builds the package list of the sample formset.
+fn package_list() -> Vec<u8> {
+    const STRINGS: &str = "\
+[en-US]
+TITLE = Sample settings
+HELP = Settings of the sample driver
+MAIN = Main
+ENABLE = Enable
+LEVEL = Level
+MODE = Mode
+FAST = Fast
+QUIET = Quiet
+";
+    let table = StringTable::parse(STRINGS).unwrap();
+    let string = |key: &str| -> StringId { table.id(key).unwrap() };
+    let fields = SampleConfig::FIELDS;
+
+    let ifr = FormSetBuilder::new(FORMSET_GUID, string("TITLE"), string("HELP"))
+        .varstore(
+            VARSTORE_ID,
+            VARSTORE_GUID,
+            cstr8!("SampleData"),
+            SampleConfig::SIZE,
+        )
+        .form(1, string("MAIN"), |form| {
+            form.checkbox(
+                Question::new(0x1000, string("ENABLE"), string("ENABLE"))
+                    .field(VARSTORE_ID, fields.enabled),
+                DEFAULT_CONFIG.enabled != 0,
+            )
+            .numeric(
+                Question::new(QUESTION_LEVEL, string("LEVEL"), string("LEVEL"))
+                    .field(VARSTORE_ID, fields.level)
+                    .flags(IfrQuestionFlags::CALLBACK),
+                NumericSize::U16,
+                0..=100,
+                1,
+                DEFAULT_CONFIG.level.into(),
+            )
+            .one_of(
+                Question::new(0x1002, string("MODE"), string("MODE"))
+                    .field(VARSTORE_ID, fields.mode),
+                NumericSize::U8,
+                &[(string("FAST"), 0), (string("QUIET"), 1)],
+                DEFAULT_CONFIG.mode.into(),
+            );
+        })
+        .build();
+
+    PackageListBuilder::new(FORMSET_GUID)
+        .string_table(&table)
+        .forms(&ifr)
+        .build()
+}
+
+/// Creates the device path of the sample driver handle.
+fn device_path() -> Box<DevicePath> {
+    let mut v = Vec::new();
+    DevicePathBuilder::with_vec(&mut v)
+        .push(&build::hardware::Vendor {
+            vendor_guid: FORMSET_GUID,
+            vendor_defined_data: &[],
+        })
+        .unwrap()
+        .finalize()
+        .unwrap()
+        .to_boxed()
+}
+
+/// Returns the `<ConfigHdr>` of the sample varstore.
+fn config_header(device_path: &DevicePath) -> ConfigHdr {
+    ConfigHdr {
+        guid: VARSTORE_GUID,
+        name: VARSTORE_NAME.to_string(),
+        device_path: device_path.to_boxed(),
+    }
+}
+
+/// Returns the value of the `level` field in the configuration response
+/// `response` for the sample varstore.
+fn level_in_response(response: &str) -> u16 {
+    let offset = u64::from(SampleConfig::FIELDS.level.offset());
+    let config = MultiConfigurationStringIter::new(response)
+        .map(Result::unwrap)
+        .find(|config| config.guid == VARSTORE_GUID && config.alt_cfg_id.is_none())
+        .expect("sample varstore missing in response");
+    let element = config
+        .elements
+        .iter()
+        .find(|element| element.offset <= offset && offset + 2 <= element.offset + element.width)
+        .expect("level missing in response");
+    let start = (offset - element.offset) as usize;
+    u16::from_le_bytes([element.value[start], element.value[start + 1]])
+}
+
+pub fn test() {
+    info!("Running HII config routing test");
+
+    let driver: &'static ConfigAccessProvider<SampleDriver> = Box::leak(Box::new(
+        ConfigAccessProvider::new(SampleDriver::new(device_path())),
+    ));
+    let handle = install(driver);
+
+    let db_handle = boot::get_handle_for_protocol::<HiiDatabase>().unwrap();
+    let db = boot::open_protocol_exclusive::<HiiDatabase>(db_handle).unwrap();
+    let hii_handle = db.new_package_list(&package_list(), Some(handle)).unwrap();
+
+    // The package list can be found and exported again.
+    let forms = db.list_package_lists(HiiPackageType::FORMS, None).unwrap();
+    assert!(forms.contains(&hii_handle));
+    let export = db.export_package_lists(Some(hii_handle)).unwrap();
+    let list = PackageList::parse(&export).unwrap();
+    assert_eq!(list.guid(), FORMSET_GUID);
+    assert_eq!(list.bytes().len(), export.len());
+    let packages = list.packages().collect::<Result<Vec<_>, _>>().unwrap();
+    // Strings can be resolved without the HII String protocol.
+    let strings = packages
+        .iter()
+        .find_map(|package| match package {
+            Package::Strings(strings) if strings.language() == cstr8!("en-US") => Some(strings),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(
+        strings.string(1).unwrap(),
+        CString16::try_from("Sample settings").unwrap()
+    );
+    let ifr = packages
+        .iter()
+        .find_map(|package| match package {
+            Package::Forms(ifr) => Some(*ifr),
+            _ => None,
+        })
+        .unwrap();
+
+    // The forms can be disassembled, with strings from the HII String
+    // protocol.
+    let hii_string = boot::open_protocol_exclusive::<HiiString>(
+        boot::get_handle_for_protocol::<HiiString>().unwrap(),
+    )
+    .unwrap();
+    let hii_strings = HiiStrings::new(&hii_string, hii_handle, cstr8!("en-US"));
+    let text = Disassembly::new(ifr).strings(&hii_strings).to_string();
+    debug!("Forms:\n{text}");
+    assert!(text.starts_with("formset guid = a04a27f4-df00-4d42-b552-39511302113d"));
+    assert!(text.contains("title = STRING_TOKEN(0x1) \"Sample settings\""));
+    assert!(text.contains("checkbox questionid = 0x1000"));
+    assert!(text.trim_end().ends_with("endformset;"));
+    drop(hii_string);
+    // The database adds the device path of the driver handle.
+    assert!(packages.iter().any(|package| matches!(
+        package,
+        Package::DevicePath(path) if **path == *driver.handler().device_path
+    )));
+
+    let routing_handle = boot::get_handle_for_protocol::<HiiConfigRouting>().unwrap();
+    let routing = boot::open_protocol_exclusive::<HiiConfigRouting>(routing_handle).unwrap();
+
+    // Read the configuration through the routing protocol.
+    let header = config_header(&driver.handler().device_path);
+    let request = CString16::try_from(header.request().to_string().as_str()).unwrap();
+    let response = routing.extract_config(&request).unwrap();
+    assert_eq!(level_in_response(&response), DEFAULT_CONFIG.level);
+
+    // Change the level through the routing protocol.
+    let level = SampleConfig::FIELDS.level;
+    let config = format!(
+        "{header}&OFFSET={}&WIDTH={}&VALUE={}",
+        ConfigurationString::encode_number_to_hex(level.offset().into(), 2),
+        ConfigurationString::encode_number_to_hex(level.size().into(), 2),
+        ConfigurationString::encode_number_to_hex(42, 2),
+    );
+    routing
+        .route_config(&CString16::try_from(config.as_str()).unwrap())
+        .unwrap();
+    assert_eq!(driver.handler().config.get().level, 42);
+    let response = routing.extract_config(&request).unwrap();
+    assert_eq!(level_in_response(&response), 42);
+
+    // Convert between the configuration string and the varstore buffer.
+    let mut block = [0; SampleConfig::SIZE as usize];
+    let response = CString16::try_from(response.as_str()).unwrap();
+    routing.config_to_block(&response, &mut block).unwrap();
+    let start = usize::from(level.offset());
+    assert_eq!(u16::from_le_bytes([block[start], block[start + 1]]), 42);
+    let block_request = header
+        .request()
+        .with_block(level.offset().into(), level.size().into())
+        .to_string();
+    let block_request = CString16::try_from(block_request.as_str()).unwrap();
+    let config = routing.block_to_config(&block_request, &block).unwrap();
+    assert_eq!(level_in_response(&config), 42);
+
+    // The export of all configurations includes the sample varstore.
+    let export = routing.export().unwrap();
+    assert!(
+        export
+            .to_ascii_lowercase()
+            .contains(&ConfigurationString::encode_guid_to_hex(&VARSTORE_GUID))
+    );
+
+    // Call the callback like a form browser does after changing the level.
+    let config_access = boot::open_protocol_exclusive::<HiiConfigAccess>(handle).unwrap();
+    let mut action_request = BrowserActionRequest::EXIT;
+    let status = unsafe {
+        let protocol: *const HiiConfigAccessProtocol = ptr::from_ref(&*config_access).cast();
+        ((*protocol).callback)(
+            protocol,
+            BrowserAction::CHANGED,
+            QUESTION_LEVEL,
+            0,
+            ptr::null_mut(),
+            &mut action_request,
+        )
+    };
+    assert_eq!(status, Status::SUCCESS);
+    assert_eq!(action_request, BrowserActionRequest::NONE);
+    assert_eq!(
+        driver.handler().last_callback.get(),
+        Some((BrowserAction::CHANGED, QUESTION_LEVEL))
+    );
+    drop(config_access);
+
+    test_font(hii_handle);
+    test_image(hii_handle);
+    test_keyboard_layout(&db);
+
+    // Cleanup.
+    drop(routing);
+    test_browser(driver, &db.export_package_lists(Some(hii_handle)).unwrap());
+    db.remove_package_list(hii_handle).unwrap();
+    uninstall(handle, driver);
+    drop(db);
+
+    test_popup();
+    test_string_table();
+}
+
+/// Registers strings built in code and reads them back.
+fn test_string_table() {
+    info!("Running HII string table test");
+
+    let mut table = StringTable::default();
+    let title = table.add(
+        "TITLE",
+        &[
+            ("en-US", cstr16!("Settings")),
+            ("de-DE", cstr16!("Einstellungen")),
+        ],
+    );
+    let help = table.add("HELP", &[("en-US", cstr16!("Help"))]);
+    let hii_handle = table
+        .register(guid!("5d0c8b1e-7a2f-4c63-9e41-3b8f6a2d7c90"))
+        .unwrap();
+
+    let hii_string = boot::open_protocol_exclusive::<HiiString>(
+        boot::get_handle_for_protocol::<HiiString>().unwrap(),
+    )
+    .unwrap();
+    let get = |language, id| hii_string.get_string(language, hii_handle, id).unwrap();
+    assert_eq!(get(cstr8!("de-DE"), title), cstr16!("Einstellungen"));
+    assert_eq!(get(cstr8!("en-US"), help), cstr16!("Help"));
+    // Untranslated strings fall back to the first language.
+    assert_eq!(get(cstr8!("de-DE"), help), cstr16!("Help"));
+    drop(hii_string);
+
+    let db = boot::open_protocol_exclusive::<HiiDatabase>(
+        boot::get_handle_for_protocol::<HiiDatabase>().unwrap(),
+    )
+    .unwrap();
+    db.remove_package_list(hii_handle).unwrap();
+}
+
+/// Checks that a popup with a temporary message cleans up after itself. An
+/// invalid popup type is rejected without waiting for user input.
+fn test_popup() {
+    info!("Running HII popup test");
+
+    let Ok(popup_handle) = boot::get_handle_for_protocol::<HiiPopup>() else {
+        info!("HII Popup protocol is not supported");
+        return;
+    };
+    let popup = boot::open_protocol_exclusive::<HiiPopup>(popup_handle).unwrap();
+    assert_eq!(popup.revision(), HiiPopupProtocol::REVISION);
+
+    let package_lists = || {
+        let db = boot::open_protocol_exclusive::<HiiDatabase>(
+            boot::get_handle_for_protocol::<HiiDatabase>().unwrap(),
+        )
+        .unwrap();
+        db.list_package_lists(HiiPackageType::ALL, None).unwrap()
+    };
+    let before = package_lists();
+    let status = popup
+        .show_popup(
+            HiiPopupStyle::INFO,
+            HiiPopupType(0xff),
+            cstr16!("uefi-rs popup test").into(),
+        )
+        .unwrap_err()
+        .status();
+    assert_eq!(status, Status::INVALID_PARAMETER);
+    assert_eq!(package_lists(), before);
+}
+
+/// Registers a keyboard layout and makes it the current layout.
+fn test_keyboard_layout(db: &HiiDatabase) {
+    info!("Running HII keyboard layout test");
+
+    let guid = guid!("a04a27f4-df00-4d42-b552-39511302113f");
+    let descriptor = KeyDescriptor {
+        key: keyboard::Key::C1,
+        unicode: u16::from(b'y'),
+        shifted_unicode: u16::from(b'Y'),
+        alt_gr_unicode: 0,
+        shifted_alt_gr_unicode: 0,
+        modifier: Modifier::NULL.0,
+        affected_attribute: (KeyAffectedAttributes::STANDARD_SHIFT
+            | KeyAffectedAttributes::CAPS_LOCK)
+            .bits(),
+    };
+    let layout =
+        KeyboardLayout::new(guid, [descriptor]).with_description("en-US", cstr16!("Sample layout"));
+    let package_list = PackageListBuilder::new(guid)
+        .keyboard_layouts(&[layout])
+        .build();
+    let hii_handle = db.new_package_list(&package_list, None).unwrap();
+
+    assert!(db.keyboard_layouts().unwrap().contains(&guid));
+    let original = db.keyboard_layout(None).ok().map(|layout| layout.guid());
+    db.set_keyboard_layout(&guid).unwrap();
+    let current = db.keyboard_layout(None).unwrap();
+    assert_eq!(current.guid(), guid);
+    assert_eq!(current.description("en-US"), Some(cstr16!("Sample layout")));
+    assert_eq!(
+        current.translate(keyboard::Key::C1, KeyState::default()),
+        Some('y')
+    );
+
+    if let Some(original) = original {
+        db.set_keyboard_layout(&original).unwrap();
+    }
+    db.remove_package_list(hii_handle).unwrap();
+}
+
+/// Edits the sample varstore with the forms browser, through the routing
+/// protocol and the driver's HII Config Access protocol.
+fn test_browser(driver: &'static ConfigAccessProvider<SampleDriver>, export: &[u8]) {
+    info!("Running HII forms browser test");
+
+    let settings = Settings::parse(export, "en-US").unwrap();
+    let formset = &settings.formsets[0];
+    let access = FirmwareVarstores::new(&driver.handler().device_path);
+    let mut browser = Browser::new(formset, access);
+    let level = &formset.forms[0].questions[1];
+    assert_eq!(level.prompt.as_deref(), Some("Level"));
+    let setting = settings.find("Level").next().unwrap();
+    assert_eq!(setting.question, level);
+    assert_eq!(setting.varstore.name, "SampleData");
+    debug!("{settings}");
+    assert_eq!(browser.value(level), Some(42));
+
+    // Select the level, type a new value, and save.
+    browser.handle_key(Key::Special(ScanCode::DOWN));
+    assert_eq!(browser.selected(), Some(level));
+    for c in ['7', '\r'] {
+        assert_eq!(
+            browser.handle_key(Key::Printable(Char16::try_from(c).unwrap())),
+            None
+        );
+    }
+    assert_eq!(browser.value(level), Some(7));
+    assert!(browser.is_modified());
+    assert_eq!(
+        browser.handle_key(Key::Special(ScanCode::FUNCTION_10)),
+        Some(BrowserExit::Saved)
+    );
+    assert_eq!(driver.handler().config.get().level, 7);
+}
+
+fn test_font(hii_handle: HiiHandle) {
+    info!("Running HII font test");
+
+    let font = boot::open_protocol_exclusive::<HiiFont>(
+        boot::get_handle_for_protocol::<HiiFont>().unwrap(),
+    )
+    .unwrap();
+    let style = TextStyle::default();
+
+    let image = font
+        .string_to_image(cstr16!("Hi"), &style, HiiOutFlags::empty())
+        .unwrap();
+    assert!(image.width() > 0 && image.height() > 0);
+
+    // String 1 is the formset title, "Sample settings".
+    let title = font
+        .string_id_to_image(
+            hii_handle,
+            1,
+            Some(cstr8!("en-US")),
+            &style,
+            HiiOutFlags::empty(),
+        )
+        .unwrap();
+    assert!(title.width() > image.width());
+    assert_eq!(title.height(), image.height());
+
+    let glyph = font.glyph(Char16::try_from('H').unwrap(), &style).unwrap();
+    assert!(glyph.image.width() > 0 && glyph.image.height() > 0);
+
+    let system_font = font.system_font().unwrap();
+    debug!("System font: {system_font:?}");
+    let fonts = font.fonts().unwrap();
+    debug!("Found {} fonts", fonts.len());
+}
+
+fn test_image(hii_handle: HiiHandle) {
+    info!("Running HII image test");
+
+    let hii_image = boot::open_protocol_exclusive::<HiiImage>(
+        boot::get_handle_for_protocol::<HiiImage>().unwrap(),
+    )
+    .unwrap();
+
+    let red = BltPixel::new(0xff, 0, 0);
+    let blue = BltPixel::new(0, 0, 0xff);
+    let mut image = BltImage::new(3, 2, red);
+    image.pixels_mut()[4] = blue;
+
+    let id = hii_image
+        .new_image(hii_handle, &image, ImageInputFlags::empty())
+        .unwrap();
+    let stored = hii_image.get_image(hii_handle, id).unwrap();
+    assert_eq!(stored.image, image);
+    assert_eq!(stored.flags, ImageInputFlags::empty());
+
+    let drawn = hii_image
+        .draw_image_id(hii_handle, id, HiiDrawFlags::empty())
+        .unwrap();
+    assert_eq!(drawn, image);
+
+    let mut canvas = BltImage::new(4, 4, BltPixel::new(0, 0, 0));
+    hii_image
+        .draw_image_id_into(hii_handle, id, HiiDrawFlags::empty(), &mut canvas, (2, 1))
+        .unwrap();
+    assert_eq!(canvas.pixel(2, 1), Some(red));
+    assert_eq!(canvas.pixel(3, 2), Some(blue));
+    assert_eq!(canvas.pixel(1, 1), Some(BltPixel::new(0, 0, 0)));
+
+    image.pixels_mut()[0] = blue;
+    hii_image
+        .set_image(hii_handle, id, &image, ImageInputFlags::empty())
+        .unwrap();
+    assert_eq!(hii_image.get_image(hii_handle, id).unwrap().image, image);
+}
+
+/// Installs the device path and the HII Config Access protocol of `driver`
+/// on a new handle.
+fn install(driver: &'static ConfigAccessProvider<SampleDriver>) -> Handle {
+    let device_path: *const DevicePathProtocol = driver.handler().device_path.as_ffi_ptr().cast();
+    let handle = unsafe {
+        boot::install_protocol_interface(None, &DevicePathProtocol::GUID, device_path.cast())
+    }
+    .unwrap();
+    driver.install(Some(handle)).unwrap()
+}
+
+/// Uninstalls the protocols installed by [`install`].
+fn uninstall(handle: Handle, driver: &'static ConfigAccessProvider<SampleDriver>) {
+    driver.uninstall(handle).unwrap();
+    unsafe {
+        boot::uninstall_protocol_interface(
+            handle,
+            &DevicePathProtocol::GUID,
+            driver.handler().device_path.as_ffi_ptr().cast(),
+        )
+    }
+    .unwrap();
+}
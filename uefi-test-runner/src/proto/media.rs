@@ -10,9 +10,10 @@ use uefi::data_types::Align;
 use uefi::prelude::*;
 use uefi::proto::media::block::BlockIO;
 use uefi::proto::media::disk::{DiskIo, DiskIo2, DiskIo2Token};
-use uefi::proto::media::disk_info::{DiskInfo, DiskInfoInterface};
+use uefi::proto::media::disk_info::{DiskInfo, DiskInfoInterface, InquiryData};
 use uefi::proto::media::file::{
     Directory, File, FileAttribute, FileInfo, FileMode, FileSystemInfo, FileSystemVolumeLabel,
+    RegularFile, SeekFrom,
 };
 use uefi::proto::media::fs::SimpleFileSystem;
 use uefi::proto::media::partition::{MbrOsType, PartitionInfo};
@@ -171,6 +172,12 @@ fn test_existing_file(directory: &mut Directory) {
     let boxed_info = file.get_boxed_info::<FileInfo>().unwrap();
     assert_eq!(*info, *boxed_info);
 
+    // Check that `file_info` returns the same info.
+    let mut view_buffer = vec![0; 128];
+    let view = file.file_info(&mut view_buffer).unwrap();
+    assert_eq!(*view, *info);
+    assert_eq!(view.info().file_name(), cstr16!("test_input.txt"));
+
     // Delete the file.
     file.delete().unwrap();
 
@@ -201,6 +208,104 @@ fn test_create_file(directory: &mut Directory) {
     file.write(b"test output data").unwrap();
 }
 
+/// Test seeking in the file created by `test_create_file`, including
+/// positions beyond 4 GiB.
+fn test_seek(directory: &mut Directory) {
+    info!("Testing file seeking");
+
+    let mut file = directory
+        .open(
+            cstr16!("new_test_file.txt"),
+            FileMode::Read,
+            FileAttribute::empty(),
+        )
+        .expect("failed to open file")
+        .into_regular_file()
+        .expect("not a regular file");
+
+    assert_eq!(file.seek(SeekFrom::End(-4)).unwrap(), 12);
+    let mut buf = [0; 4];
+    assert_eq!(file.read(&mut buf).unwrap(), 4);
+    assert_eq!(&buf, b"data");
+    assert_eq!(file.seek(SeekFrom::Current(-8)).unwrap(), 8);
+
+    // Positions past the end of the file, and past 4 GiB, are allowed.
+    let large = 5 << 30;
+    assert_eq!(file.seek(SeekFrom::Start(large)).unwrap(), large);
+    assert_eq!(file.get_position().unwrap(), large);
+    assert_eq!(file.read(&mut buf).unwrap(), 0);
+    assert_eq!(file.seek(SeekFrom::Current(-(5 << 30))).unwrap(), 0);
+
+    assert_eq!(
+        file.seek(SeekFrom::Current(-1)).unwrap_err().status(),
+        Status::INVALID_PARAMETER
+    );
+
+    // Seeking to the end with the sentinel returns the actual position.
+    assert_eq!(
+        file.seek(SeekFrom::Start(RegularFile::END_OF_FILE))
+            .unwrap(),
+        16
+    );
+
+    // Negative offsets past 4 GiB, and invalid positions relative to the
+    // end, which leave the position unchanged.
+    assert_eq!(file.seek(SeekFrom::End(large as i64)).unwrap(), large + 16);
+    assert_eq!(
+        file.seek(SeekFrom::Current(-(4 << 30))).unwrap(),
+        (1 << 30) + 16
+    );
+    assert_eq!(
+        file.seek(SeekFrom::End(-17)).unwrap_err().status(),
+        Status::INVALID_PARAMETER
+    );
+    assert_eq!(file.get_position().unwrap(), (1 << 30) + 16);
+}
+
+/// Test writing past the end of a file, and past 4 GiB.
+///
+/// Files larger than 4 GiB can't be tested on the test disk: it uses FAT, the
+/// only file system the firmware is required to support, which limits files
+/// to 4 GiB - 1 bytes and doesn't support sparse files, so even a file close
+/// to the limit doesn't fit on the 10 MiB disk. Instead, check that a write
+/// past the end grows the file, and that a write past 4 GiB fails without
+/// changing it.
+fn test_sparse_write(directory: &mut Directory) {
+    info!("Testing writes past the end of a file");
+
+    let mut file = directory
+        .open(
+            cstr16!("sparse_test_file.bin"),
+            FileMode::CreateReadWrite,
+            FileAttribute::empty(),
+        )
+        .expect("failed to create file")
+        .into_regular_file()
+        .expect("not a regular file");
+
+    file.write(b"head").unwrap();
+    let offset = 64 * 1024;
+    assert_eq!(file.seek(SeekFrom::Start(offset)).unwrap(), offset);
+    file.write(b"tail").unwrap();
+    assert_eq!(file.seek(SeekFrom::End(0)).unwrap(), offset + 4);
+
+    let mut buf = [0; 4];
+    assert_eq!(file.seek(SeekFrom::Start(0)).unwrap(), 0);
+    assert_eq!(file.read(&mut buf).unwrap(), 4);
+    assert_eq!(&buf, b"head");
+    assert_eq!(file.seek(SeekFrom::Start(offset)).unwrap(), offset);
+    assert_eq!(file.read(&mut buf).unwrap(), 4);
+    assert_eq!(&buf, b"tail");
+
+    let large = 5 << 30;
+    assert_eq!(file.seek(SeekFrom::Start(large)).unwrap(), large);
+    assert!(file.write(b"data").is_err());
+    let info = file.get_boxed_info::<FileInfo>().unwrap();
+    assert_eq!(info.file_size(), offset + 4);
+
+    file.delete().unwrap();
+}
+
 /// Test directory creation by
 /// - creating a new directory
 /// - creating a file in that directory
@@ -367,9 +472,9 @@ fn test_disk_info() {
         };
         // SCSI Spec states: The standard INQUIRY data (see table 59) shall contain at least 36 bytes
         assert!(len >= 36);
-        let vendor_id = core::str::from_utf8(&inquiry_bfr[8..16]).unwrap().trim();
-        let product_id = core::str::from_utf8(&inquiry_bfr[16..32]).unwrap().trim();
-        if vendor_id == "uefi-rs" && product_id == "ExtScsiPassThru" {
+        let inquiry = disk_info.inquiry_data().unwrap();
+        assert_eq!(inquiry, InquiryData::parse(&inquiry_bfr[..len]).unwrap());
+        if inquiry.vendor_id() == "uefi-rs" && inquiry.product_id() == "ExtScsiPassThru" {
             info!("Found Testdisk at Handle: {handle:?}");
             found_drive = true;
         }
@@ -473,6 +578,8 @@ pub fn test() {
         test_delete_warning(&mut root_directory);
         test_existing_file(&mut root_directory);
         test_create_file(&mut root_directory);
+        test_seek(&mut root_directory);
+        test_sparse_write(&mut root_directory);
         test_create_directory(&mut root_directory);
 
         test_partition_info(handle);
@@ -16,6 +16,7 @@ pub fn test() {
     debug::test();
     device_path::test();
     driver::test();
+    hii::test();
     load::test();
     loaded_image::test();
     media::test();
@@ -80,6 +81,7 @@ mod console;
 mod debug;
 mod device_path;
 mod driver;
+mod hii;
 mod load;
 mod loaded_image;
 mod media;
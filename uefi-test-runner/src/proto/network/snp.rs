@@ -7,7 +7,7 @@ use smoltcp::wire::{
 };
 use uefi::boot::ScopedProtocol;
 use uefi::proto::network::EfiMacAddr;
-use uefi::proto::network::snp::{InterruptStatus, ReceiveFlags, SimpleNetwork};
+use uefi::proto::network::snp::{InterruptStatus, ReceiveFlags, ResetLevel, SimpleNetwork};
 use uefi::{Status, boot};
 use uefi_raw::protocol::network::snp::NetworkState;
 
@@ -254,6 +254,13 @@ pub fn test() {
         }
     }
 
+    let link = simple_network.link_status().unwrap();
+    info!("Link up: {link:?}");
+
+    // Like statistics, resetting may be unsupported.
+    let res = simple_network.reset_with_level(ResetLevel::Quick);
+    assert!(res == Ok(()) || res == Err(Status::UNSUPPORTED.into()));
+
     // Workaround for OVMF firmware. `stop()` works in CI on x86_64, but not
     // x86 or aarch64.
     if simple_network.mode().state == NetworkState::STARTED {
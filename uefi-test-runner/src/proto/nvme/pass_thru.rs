@@ -2,6 +2,7 @@
 
 use core::time::Duration;
 use uefi::boot;
+use uefi::disk::smart::{self, DiskInterface};
 use uefi::proto::device_path::DevicePath;
 use uefi::proto::device_path::text::{AllowShortcuts, DisplayOnly};
 use uefi::proto::media::block::BlockIO;
@@ -12,6 +13,7 @@ pub fn test() {
     info!("Running NVMe PassThru tests");
 
     assert!(has_nvme_drive());
+    test_identify();
 }
 
 fn has_nvme_drive() -> bool {
@@ -51,3 +53,42 @@ fn has_nvme_drive() -> bool {
 
     false
 }
+
+fn test_identify() {
+    let block_io_handles = boot::find_handles::<BlockIO>().unwrap();
+    for handle in block_io_handles {
+        let Ok(device_path) = boot::open_protocol_exclusive::<DevicePath>(handle) else {
+            continue;
+        };
+        let mut device_path = &*device_path;
+
+        let Ok(nvme_pt_handle) = boot::locate_device_path::<NvmePassThru>(&mut device_path) else {
+            continue;
+        };
+        let nvme_pt = boot::open_protocol_exclusive::<NvmePassThru>(nvme_pt_handle).unwrap();
+        let mut nvme_ctrl = nvme_pt.controller();
+
+        let Ok(controller) = nvme_ctrl.identify_controller() else {
+            continue;
+        };
+        let serial = controller.serial_number();
+        if serial != "uefi-rsNvmePassThru" {
+            continue;
+        }
+
+        let mut namespace = nvme_pt.iter_namespaces().next().unwrap();
+        let namespace = namespace.identify().unwrap();
+        info!("First namespace: {namespace:?}");
+        assert_ne!(namespace.size(), 0);
+        assert!(namespace.block_size().is_some());
+
+        let report = smart::health(handle).unwrap();
+        info!("{report}");
+        assert_eq!(report.interface, DiskInterface::Nvme);
+        assert_eq!(report.serial_number, serial);
+        assert!(report.temperature.is_some());
+        return;
+    }
+
+    panic!("NVMe test drive not found");
+}
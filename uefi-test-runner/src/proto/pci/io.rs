@@ -0,0 +1,59 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use uefi::boot::{self, OpenProtocolAttributes, OpenProtocolParams};
+use uefi::proto::pci::dma::{DmaAddressing, DmaMapping};
+use uefi::proto::pci::io::PciIo;
+
+pub fn test() {
+    let handles = boot::find_handles::<PciIo>().unwrap();
+    assert!(!handles.is_empty());
+
+    for (i, handle) in handles.into_iter().enumerate() {
+        let params = OpenProtocolParams {
+            handle,
+            agent: boot::image_handle(),
+            controller: None,
+        };
+        // Don't open exclusive, that would disconnect the device drivers.
+        let pci_io =
+            unsafe { boot::open_protocol::<PciIo>(params, OpenProtocolAttributes::GetProtocol) }
+                .unwrap();
+        let location = pci_io.location().unwrap();
+        if i == 0 {
+            test_dma(&pci_io);
+        }
+        let Some(rom) = pci_io.option_rom() else {
+            continue;
+        };
+        info!("Option ROM of PCI device {location:?}: {rom:?}");
+        let mut images = rom.images();
+        let first = images.next().unwrap();
+        assert_eq!(first.offset(), 0);
+        let end = images.fold(first, |_, image| image).offset();
+        assert!(end < rom.bytes().len());
+        for efi in rom.efi_images() {
+            let pe = efi.pe_image().unwrap();
+            info!("EFI driver: {efi:?}, {} bytes", pe.len());
+        }
+    }
+}
+
+fn test_dma(pci_io: &PciIo) {
+    let mut buffer = pci_io.dma_buffer(100, DmaAddressing::Below4GiB).unwrap();
+    assert_eq!(buffer.len(), 100);
+    assert!(buffer.as_slice().iter().all(|&b| b == 0));
+    assert!(buffer.device_address() + 100 <= 1 << 32);
+    buffer.as_mut_slice().fill(0xaa);
+    buffer.flush().unwrap();
+    assert!(buffer.as_slice().iter().all(|&b| b == 0xaa));
+
+    let data = [0x55; 64];
+    let mapping = DmaMapping::to_device(pci_io, &data).unwrap();
+    assert_eq!(mapping.len(), 64);
+    mapping.unmap().unwrap();
+
+    let mut data = [0; 64];
+    let mapping = DmaMapping::from_device(pci_io, &mut data).unwrap();
+    assert_eq!(mapping.len(), 64);
+    drop(mapping);
+}
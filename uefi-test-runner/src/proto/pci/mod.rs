@@ -1,7 +1,9 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+pub mod io;
 pub mod root_bridge;
 
 pub fn test() {
+    io::test();
     root_bridge::test();
 }
@@ -5,6 +5,7 @@ use core::ptr;
 use core::sync::atomic::{AtomicUsize, Ordering};
 use core::time::Duration;
 use uefi::proto::pi::mp::MpServices;
+use uefi::sync::{ApContext, Barrier, SpinLock};
 use uefi::{Status, boot};
 
 /// Number of cores qemu is configured to have
@@ -28,6 +29,7 @@ pub fn test() {
     test_get_processor_info(mp_support);
     test_startup_all_aps(mp_support);
     test_startup_this_ap(mp_support);
+    test_run_on_aps(mp_support);
     test_enable_disable_ap(mp_support);
     test_switch_bsp_and_who_am_i(mp_support);
 }
@@ -121,6 +123,29 @@ fn test_startup_this_ap(mps: &MpServices) {
     }
 }
 
+fn test_run_on_aps(mps: &MpServices) {
+    // All APs must pass the barrier together before updating the total
+    let barrier = Barrier::new(NUM_CPUS - 1);
+    let total = SpinLock::new(0);
+    mps.run_on_aps(
+        false,
+        &|_: &ApContext| {
+            barrier.wait();
+            *total.lock() += 1;
+        },
+        None,
+    )
+    .unwrap();
+    assert_eq!(total.into_inner(), NUM_CPUS - 1);
+
+    let visited = SpinLock::new([false; NUM_CPUS]);
+    for i in 1..NUM_CPUS {
+        mps.run_on_ap(i, &|_: &ApContext| visited.lock()[i] = true, None)
+            .unwrap();
+    }
+    assert_eq!(visited.into_inner()[1..], [true; NUM_CPUS - 1]);
+}
+
 fn test_enable_disable_ap(mps: &MpServices) {
     // Disable second CPU
     mps.enable_disable_ap(1, false, None).unwrap();
@@ -2,12 +2,22 @@
 
 mod vars;
 
-use uefi::runtime::{self, Daylight, Time, TimeParams};
+use uefi::Status;
+use uefi::runtime::capsule::{self, JsonCapsuleHeader};
+use uefi::runtime::{self, Daylight, RuntimePostEbs, Time, TimeParams};
 
 pub fn test() {
     info!("Testing runtime services");
     vars::test();
     test_time();
+    test_capsule_result();
+    test_os_indications();
+}
+
+fn test_os_indications() {
+    // Only the capabilities are checked, requesting them would reboot.
+    let supported = runtime::os_indications_supported().unwrap();
+    info!("OsIndicationsSupported: {supported:?}");
 }
 
 fn test_time() {
@@ -36,4 +46,20 @@ fn test_time() {
     let now = runtime::get_time().unwrap();
     info!("After setting time: {now}");
     assert_eq!(now.year(), 2020);
+
+    // Before exiting boot services, the handle uses the table of the system
+    // table.
+    let rt = RuntimePostEbs::current().unwrap();
+    assert_eq!(rt.get_time().unwrap().year(), 2020);
+}
+
+fn test_capsule_result() {
+    // No capsule is processed in the test VM, but reading the results must
+    // fail cleanly.
+    match capsule::last_capsule_result() {
+        Ok(result) => info!("Last capsule result: {result:?}"),
+        Err(err) => assert_eq!(err.status(), Status::NOT_FOUND),
+    }
+    let data = capsule::json_config_data(&JsonCapsuleHeader::CONFIG_DATA_TABLE_GUID);
+    info!("JSON configuration data: {data:?}");
 }
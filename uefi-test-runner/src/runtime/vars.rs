@@ -31,7 +31,7 @@ fn test_variables() {
     let mut buf = [0u8; 0];
     assert_eq!(
         runtime::get_variable(NAME, VENDOR, &mut buf).unwrap_err(),
-        Error::new(Status::BUFFER_TOO_SMALL, Some(9))
+        Error::new(Status::BUFFER_TOO_SMALL, Some(9)).into()
     );
 
     // Test `get_variable`.
@@ -15,6 +15,8 @@
 //! Other methods for opening protocols:
 //!
 //! * [`open_protocol`]
+//! * [`open_protocol_by_driver`] and [`open_protocol_by_child_controller`],
+//!   for drivers following the UEFI driver model
 //! * [`get_image_file_system`]
 //!
 //! For protocol definitions, see the [`proto`] module.
@@ -22,7 +24,7 @@
 //! [`proto`]: crate::proto
 
 pub use uefi_raw::table::boot::{
-    EventType, MemoryAttribute, MemoryDescriptor, MemoryType, PAGE_SIZE, Tpl,
+    EventGroup, EventType, MemoryAttribute, MemoryDescriptor, MemoryType, PAGE_SIZE, Tpl,
 };
 
 use crate::data_types::PhysicalAddress;
@@ -40,14 +42,14 @@ use crate::util::opt_nonnull_to_ptr;
 use crate::{Char16, Error, Event, Guid, Handle, Result, Status, StatusExt, table};
 use core::ffi::c_void;
 use core::mem::MaybeUninit;
-use core::ops::{Deref, DerefMut};
+use core::ops::{Deref, DerefMut, Range};
 use core::ptr::{self, NonNull};
 use core::sync::atomic::{AtomicPtr, Ordering};
 use core::time::Duration;
 use core::{mem, slice};
 use uefi_raw::table::boot::{AllocateType as RawAllocateType, InterfaceType, TimerDelay};
 #[cfg(feature = "alloc")]
-use {alloc::vec::Vec, uefi::ResultExt};
+use {alloc::boxed::Box, alloc::vec::Vec, core::sync::atomic::AtomicBool, uefi::ResultExt};
 
 /// Global image handle. This is only set by [`set_image_handle`], and it is
 /// only read by [`image_handle`].
@@ -145,10 +147,30 @@ pub unsafe fn raise_tpl(tpl: Tpl) -> TplGuard {
 /// * [`Status::INVALID_PARAMETER`]: `mem_ty` is [`MemoryType::PERSISTENT_MEMORY`],
 ///   [`MemoryType::UNACCEPTED`], or in the range <code>[MemoryType::MAX]..=0x6fff_ffff</code>.
 /// * [`Status::NOT_FOUND`]: the requested pages could not be found.
+#[cfg_attr(feature = "alloc_trace", track_caller)]
 pub fn allocate_pages(
     allocation_type: AllocateType,
     memory_type: MemoryType,
     count: usize,
+) -> Result<NonNull<u8>> {
+    let ptr = allocate_pages_untraced(allocation_type, memory_type, count)?;
+    #[cfg(feature = "alloc_trace")]
+    crate::mem::trace::report(
+        crate::mem::trace::AllocEvent::AllocatePages {
+            ptr,
+            count,
+            memory_type,
+        },
+        core::panic::Location::caller(),
+    );
+    Ok(ptr)
+}
+
+/// Implementation of [`allocate_pages`], without reporting the allocation.
+fn allocate_pages_untraced(
+    allocation_type: AllocateType,
+    memory_type: MemoryType,
+    count: usize,
 ) -> Result<NonNull<u8>> {
     let bt = boot_services_raw_panicking();
     let bt = unsafe { bt.as_ref() };
@@ -200,12 +222,19 @@ pub fn allocate_pages(
 ///
 /// * [`Status::NOT_FOUND`]: `ptr` was not allocated by [`allocate_pages`].
 /// * [`Status::INVALID_PARAMETER`]: `ptr` is not page aligned or is otherwise invalid.
+#[cfg_attr(feature = "alloc_trace", track_caller)]
 pub unsafe fn free_pages(ptr: NonNull<u8>, count: usize) -> Result {
     let bt = boot_services_raw_panicking();
     let bt = unsafe { bt.as_ref() };
 
     let addr = ptr.as_ptr() as PhysicalAddress;
-    unsafe { (bt.free_pages)(addr, count) }.to_result()
+    unsafe { (bt.free_pages)(addr, count) }.to_result()?;
+    #[cfg(feature = "alloc_trace")]
+    crate::mem::trace::report(
+        crate::mem::trace::AllocEvent::FreePages { ptr, count },
+        core::panic::Location::caller(),
+    );
+    Ok(())
 }
 
 /// Allocates a consecutive region of bytes using the UEFI allocator.
@@ -230,6 +259,7 @@ pub unsafe fn free_pages(ptr: NonNull<u8>, count: usize) -> Result {
 /// * [`Status::OUT_OF_RESOURCES`]: allocation failed.
 /// * [`Status::INVALID_PARAMETER`]: `mem_ty` is [`MemoryType::PERSISTENT_MEMORY`],
 ///   [`MemoryType::UNACCEPTED`], or in the range <code>[MemoryType::MAX]..=0x6fff_ffff</code>.
+#[cfg_attr(feature = "alloc_trace", track_caller)]
 pub fn allocate_pool(memory_type: MemoryType, size: usize) -> Result<NonNull<u8>> {
     let bt = boot_services_raw_panicking();
     let bt = unsafe { bt.as_ref() };
@@ -238,7 +268,17 @@ pub fn allocate_pool(memory_type: MemoryType, size: usize) -> Result<NonNull<u8>
     let ptr = unsafe { (bt.allocate_pool)(memory_type, size, &mut buffer) }
         .to_result_with_val(|| buffer)?;
 
-    NonNull::new(ptr).ok_or_else(|| Status::OUT_OF_RESOURCES.into())
+    let ptr = NonNull::new(ptr).ok_or(Status::OUT_OF_RESOURCES)?;
+    #[cfg(feature = "alloc_trace")]
+    crate::mem::trace::report(
+        crate::mem::trace::AllocEvent::AllocatePool {
+            ptr,
+            size,
+            memory_type,
+        },
+        core::panic::Location::caller(),
+    );
+    Ok(ptr)
 }
 
 /// Frees memory allocated by [`allocate_pool`].
@@ -251,11 +291,89 @@ pub fn allocate_pool(memory_type: MemoryType, size: usize) -> Result<NonNull<u8>
 /// # Errors
 ///
 /// * [`Status::INVALID_PARAMETER`]: `ptr` is invalid.
+#[cfg_attr(feature = "alloc_trace", track_caller)]
 pub unsafe fn free_pool(ptr: NonNull<u8>) -> Result {
     let bt = boot_services_raw_panicking();
     let bt = unsafe { bt.as_ref() };
 
-    unsafe { (bt.free_pool)(ptr.as_ptr()) }.to_result()
+    unsafe { (bt.free_pool)(ptr.as_ptr()) }.to_result()?;
+    #[cfg(feature = "alloc_trace")]
+    crate::mem::trace::report(
+        crate::mem::trace::AllocEvent::FreePool { ptr },
+        core::panic::Location::caller(),
+    );
+    Ok(())
+}
+
+/// Copies `src` to `dest` with the firmware's `CopyMem` service.
+///
+/// Firmware may provide an implementation optimized for the platform, which
+/// can be faster than [`<[u8]>::copy_from_slice`] on some systems. Use the
+/// latter when in doubt, since it avoids the call into firmware.
+///
+/// # Panics
+///
+/// Panics if `dest` and `src` have different lengths.
+///
+/// [`<[u8]>::copy_from_slice`]: slice::copy_from_slice
+pub fn copy_mem(dest: &mut [u8], src: &[u8]) {
+    assert_eq!(
+        dest.len(),
+        src.len(),
+        "source and destination lengths differ"
+    );
+    // SAFETY: both slices are valid for `len` bytes.
+    unsafe { copy_mem_raw(dest.as_mut_ptr(), src.as_ptr(), src.len()) }
+}
+
+/// Copies the bytes in `src` to `dest` within `buffer` with the firmware's
+/// `CopyMem` service. The ranges may overlap.
+///
+/// This is the firmware equivalent of [`<[u8]>::copy_within`].
+///
+/// # Panics
+///
+/// Panics if either range exceeds the end of `buffer`, or if the end of
+/// `src` is before the start.
+///
+/// [`<[u8]>::copy_within`]: slice::copy_within
+pub fn copy_mem_within(buffer: &mut [u8], src: Range<usize>, dest: usize) {
+    assert!(src.start <= src.end, "source range is reversed");
+    assert!(src.end <= buffer.len(), "source range out of bounds");
+    let len = src.end - src.start;
+    assert!(dest <= buffer.len() - len, "destination out of bounds");
+    let base = buffer.as_mut_ptr();
+    // SAFETY: both ranges are within `buffer`.
+    unsafe { copy_mem_raw(base.add(dest), base.add(src.start), len) }
+}
+
+/// Copies `len` bytes from `src` to `dest` with the firmware's `CopyMem`
+/// service.
+///
+/// Unlike [`ptr::copy_nonoverlapping`], the regions may overlap: the UEFI
+/// specification requires `CopyMem` to behave like [`ptr::copy`].
+///
+/// # Safety
+///
+/// `src` must be valid for reads and `dest` valid for writes of `len` bytes.
+pub unsafe fn copy_mem_raw(dest: *mut u8, src: *const u8, len: usize) {
+    let bt = boot_services_raw_panicking();
+    let bt = unsafe { bt.as_ref() };
+
+    unsafe { (bt.copy_mem)(dest, src, len) }
+}
+
+/// Fills `buffer` with `value` using the firmware's `SetMem` service.
+///
+/// Firmware may provide an implementation optimized for the platform, which
+/// can be faster than [`<[u8]>::fill`] on some systems.
+///
+/// [`<[u8]>::fill`]: slice::fill
+pub fn set_mem(buffer: &mut [u8], value: u8) {
+    let bt = boot_services_raw_panicking();
+    let bt = unsafe { bt.as_ref() };
+
+    unsafe { (bt.set_mem)(buffer.as_mut_ptr(), buffer.len(), value) }
 }
 
 /// Queries the `get_memory_map` function of UEFI to retrieve the current
@@ -402,6 +520,7 @@ pub(crate) fn get_memory_map(buf: &mut [u8]) -> Result<MemoryMapMeta> {
 ///
 /// * [`Status::INVALID_PARAMETER`]: an invalid combination of parameters was provided.
 /// * [`Status::OUT_OF_RESOURCES`]: the event could not be allocated.
+#[cfg_attr(feature = "leak_check", track_caller)]
 pub unsafe fn create_event(
     event_ty: EventType,
     notify_tpl: Tpl,
@@ -422,10 +541,11 @@ pub unsafe fn create_event(
 
     // Now we're ready to call UEFI
     unsafe { (bt.create_event)(event_ty, notify_tpl, notify_fn, notify_ctx, &mut event) }
-        .to_result_with_val(
-            // OK to unwrap: event is non-null for Status::SUCCESS.
-            || unsafe { Event::from_ptr(event) }.unwrap(),
-        )
+        .to_result()?;
+    #[cfg(feature = "leak_check")]
+    crate::helpers::leak_check::record_event_created(event, core::panic::Location::caller());
+    // OK to unwrap: event is non-null for Status::SUCCESS.
+    Ok(unsafe { Event::from_ptr(event) }.unwrap())
 }
 
 /// Creates an event in an event group.
@@ -469,6 +589,7 @@ pub unsafe fn create_event(
 ///
 /// * [`Status::INVALID_PARAMETER`]: an invalid combination of parameters was provided.
 /// * [`Status::OUT_OF_RESOURCES`]: the event could not be allocated.
+#[cfg_attr(feature = "leak_check", track_caller)]
 pub unsafe fn create_event_ex(
     event_type: EventType,
     notify_tpl: Tpl,
@@ -500,10 +621,115 @@ pub unsafe fn create_event_ex(
             &mut event,
         )
     }
-    .to_result_with_val(
-        // OK to unwrap: event is non-null for Status::SUCCESS.
-        || unsafe { Event::from_ptr(event) }.unwrap(),
-    )
+    .to_result()?;
+    #[cfg(feature = "leak_check")]
+    crate::helpers::leak_check::record_event_created(event, core::panic::Location::caller());
+    // OK to unwrap: event is non-null for Status::SUCCESS.
+    Ok(unsafe { Event::from_ptr(event) }.unwrap())
+}
+
+/// Creates a [`NOTIFY_SIGNAL`] event in the event group `group`.
+///
+/// This is a shorthand for [`create_event_ex`] for the common case of
+/// reacting to one of the [`EventGroup`]s signaled by the firmware.
+///
+/// # Safety
+///
+/// The caller must ensure that `notify_fn` and `notify_ctx` remain valid
+/// for as long as the event exists.
+///
+/// # Errors
+///
+/// See [`create_event_ex`].
+///
+/// [`NOTIFY_SIGNAL`]: EventType::NOTIFY_SIGNAL
+#[cfg_attr(feature = "leak_check", track_caller)]
+pub unsafe fn create_event_in_group(
+    group: EventGroup,
+    notify_tpl: Tpl,
+    notify_fn: EventNotifyFn,
+    notify_ctx: Option<NonNull<c_void>>,
+) -> Result<Event> {
+    let mut guid = group.0;
+    unsafe {
+        create_event_ex(
+            EventType::NOTIFY_SIGNAL,
+            notify_tpl,
+            Some(notify_fn),
+            notify_ctx,
+            Some(NonNull::from(&mut guid)),
+        )
+    }
+}
+
+/// Calls `callback` at [`Tpl::CALLBACK`] whenever the event group `group`
+/// is signaled.
+///
+/// The returned event keeps the callback registered; pass it to
+/// [`close_event`] to unregister it.
+///
+/// # Errors
+///
+/// See [`create_event_ex`].
+#[cfg_attr(feature = "leak_check", track_caller)]
+pub fn on_event_group(group: EventGroup, callback: fn()) -> Result<Event> {
+    extern "efiapi" fn trampoline(_event: Event, ctx: Option<NonNull<c_void>>) {
+        // Safety: the context was created from a `fn()` below.
+        let callback: fn() = unsafe { mem::transmute(ctx.unwrap().as_ptr()) };
+        callback();
+    }
+
+    let ctx = NonNull::new(callback as *mut c_void);
+    // Safety: the trampoline and the callback are both `'static`.
+    unsafe { create_event_in_group(group, Tpl::CALLBACK, trampoline, ctx) }
+}
+
+/// Calls `callback` when the boot manager is about to boot a boot option.
+///
+/// This is [`on_event_group`] for [`EventGroup::READY_TO_BOOT`]. Drivers use
+/// it for work that must happen as late as possible before an OS loader
+/// runs, such as locking configuration.
+///
+/// # Errors
+///
+/// See [`create_event_ex`].
+#[cfg_attr(feature = "leak_check", track_caller)]
+pub fn on_ready_to_boot(callback: fn()) -> Result<Event> {
+    on_event_group(EventGroup::READY_TO_BOOT, callback)
+}
+
+/// Signals all events in the event group `group`.
+///
+/// A temporary event is added to the group, signaled, and closed again.
+///
+/// # Errors
+///
+/// See [`create_event_ex`].
+pub fn signal_event_group(group: EventGroup) -> Result {
+    const extern "efiapi" fn empty(_event: Event, _ctx: Option<NonNull<c_void>>) {}
+
+    // Safety: the notification function is `'static` and takes no context.
+    let event = unsafe { create_event_in_group(group, Tpl::CALLBACK, empty, None) }?;
+    let status = signal_event(&event);
+    close_event(event)?;
+    status
+}
+
+/// Signals the event groups that a firmware boot manager signals right
+/// before it boots a boot option: [`EventGroup::READY_TO_BOOT`] followed by
+/// [`EventGroup::AFTER_READY_TO_BOOT`].
+///
+/// The end-of-DXE group has already been signaled by the platform by the
+/// time any boot option can run, so it is not signaled again.
+///
+/// See [`start_boot_option`] for the complete boot sequence.
+///
+/// # Errors
+///
+/// See [`create_event_ex`].
+pub fn signal_ready_to_boot() -> Result {
+    signal_event_group(EventGroup::READY_TO_BOOT)?;
+    signal_event_group(EventGroup::AFTER_READY_TO_BOOT)
 }
 
 /// Checks to see if an event is signaled, without blocking execution to wait for it.
@@ -572,7 +798,10 @@ pub fn close_event(event: Event) -> Result {
     let bt = boot_services_raw_panicking();
     let bt = unsafe { bt.as_ref() };
 
-    unsafe { (bt.close_event)(event.as_ptr()) }.to_result()
+    unsafe { (bt.close_event)(event.as_ptr()) }.to_result()?;
+    #[cfg(feature = "leak_check")]
+    crate::helpers::leak_check::record_event_closed(event.as_ptr());
+    Ok(())
 }
 
 /// Sets the trigger for an event of type [`TIMER`].
@@ -713,6 +942,99 @@ pub fn disconnect_controller(
     .to_result_with_err(|_| ())
 }
 
+/// Connects all drivers to `controller`, like [`connect_controller`] with no
+/// driver image and no remaining device path.
+///
+/// If `recursive` is true, drivers are also connected to all child
+/// controllers created in the process.
+///
+/// # Errors
+///
+/// See [`connect_controller`].
+pub fn connect(controller: Handle, recursive: bool) -> Result {
+    connect_controller(controller, None, None, recursive)
+}
+
+/// Disconnects all drivers from `controller`, including the drivers of all
+/// its child controllers.
+///
+/// # Errors
+///
+/// See [`disconnect_controller`].
+pub fn disconnect(controller: Handle) -> Result {
+    disconnect_controller(controller, None, None)
+}
+
+/// Recursively connects drivers to all handles in the system.
+///
+/// This is needed to make devices available when the firmware only connected
+/// the devices needed to boot, or after loading and starting a driver image.
+/// Errors for individual handles are ignored, since most handles have no
+/// driver that supports them.
+///
+/// # Errors
+///
+/// * [`Status::OUT_OF_RESOURCES`]: not enough memory to list all handles.
+pub fn connect_all() -> Result {
+    for handle in locate_handle_buffer(SearchType::AllHandles)?.iter() {
+        let _ = connect(*handle, true);
+    }
+    Ok(())
+}
+
+/// Connects drivers to all handles whose device path starts with `prefix`,
+/// and returns the number of handles that were connected.
+///
+/// If `reconnect` is true, drivers are disconnected from each matching
+/// handle first, which makes them rescan the device, e.g. after a driver was
+/// updated or after the partitions of a disk were modified.
+///
+/// Errors for individual handles are ignored.
+///
+/// # Errors
+///
+/// * [`Status::NOT_FOUND`]: no handle supports the device path protocol.
+/// * [`Status::OUT_OF_RESOURCES`]: not enough memory to list all handles.
+pub fn connect_by_device_path(prefix: &DevicePath, reconnect: bool) -> Result<usize> {
+    let handles = locate_handle_buffer(SearchType::from_proto::<DevicePath>())?;
+    let mut count = 0;
+    for handle in handles.iter() {
+        // Safety: the protocol is only used for the comparison below, while
+        // nothing can uninstall it. It is not opened exclusively since that
+        // would disconnect the drivers that opened it by driver.
+        let matches = unsafe {
+            open_protocol::<DevicePath>(
+                OpenProtocolParams {
+                    handle: *handle,
+                    agent: image_handle(),
+                    controller: None,
+                },
+                OpenProtocolAttributes::GetProtocol,
+            )
+        }
+        .ok()
+        .and_then(|path| path.get().map(|path| device_path_starts_with(path, prefix)))
+        .unwrap_or(false);
+        if !matches {
+            continue;
+        }
+
+        if reconnect {
+            let _ = disconnect(*handle);
+        }
+        if connect(*handle, true).is_ok() {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Returns whether `path` starts with the nodes of `prefix`.
+fn device_path_starts_with(path: &DevicePath, prefix: &DevicePath) -> bool {
+    let mut nodes = path.node_iter();
+    prefix.node_iter().all(|node| nodes.next() == Some(node))
+}
+
 /// Installs a protocol interface on a device handle.
 ///
 /// When a protocol interface is installed, firmware will call all functions
@@ -857,6 +1179,32 @@ pub fn protocols_per_handle(handle: Handle) -> Result<ProtocolsPerHandle> {
         })
 }
 
+/// Calls `f` with each agent that has `protocol` open on `handle`.
+#[cfg(feature = "leak_check")]
+pub(crate) fn open_protocol_information(
+    handle: Handle,
+    protocol: &Guid,
+    mut f: impl FnMut(&uefi_raw::table::boot::OpenProtocolInformationEntry),
+) -> Result {
+    let bt = boot_services_raw_panicking();
+    let bt = unsafe { bt.as_ref() };
+
+    let mut entries = ptr::null();
+    let mut count = 0;
+    unsafe { (bt.open_protocol_information)(handle.as_ptr(), protocol, &mut entries, &mut count) }
+        .to_result()?;
+    if entries.is_null() {
+        return Ok(());
+    }
+    // SAFETY: the firmware returns an array of `count` entries.
+    for entry in unsafe { slice::from_raw_parts(entries, count) } {
+        f(entry);
+    }
+    // The buffer is freed directly, so that it isn't reported as a
+    // deallocation.
+    unsafe { (bt.free_pool)(entries.cast_mut().cast()) }.to_result()
+}
+
 /// Locates the handle of a device on the [`DevicePath`] that supports the
 /// specified [`Protocol`].
 ///
@@ -1103,6 +1451,7 @@ pub unsafe fn open_protocol<P: ProtocolPointer + ?Sized>(
         ScopedProtocol {
             interface,
             open_params: params,
+            attributes,
         }
     })
 }
@@ -1135,6 +1484,108 @@ pub fn open_protocol_exclusive<P: ProtocolPointer + ?Sized>(
     }
 }
 
+/// Opens a [`Protocol`] interface on a controller handle on behalf of a
+/// driver, as done in the `Start` function of a driver binding.
+///
+/// The protocol is opened with [`OpenProtocolAttributes::ByDriver`], using
+/// `driver` (the handle with the driver binding protocol) as the agent and
+/// `controller` as both the handle and the controller. While it is open, no
+/// other driver can open the protocol by driver, and the firmware stops the
+/// driver through its binding before uninstalling the interface.
+///
+/// The protocol is closed when the [`ScopedProtocol`] is dropped. To keep it
+/// open after `Start` returns, use [`ScopedProtocol::leak`] and close it in
+/// the driver's `Stop` function with [`close_protocol`].
+///
+/// # Errors
+///
+/// * [`Status::UNSUPPORTED`]: the handle does not support the protocol.
+/// * [`Status::ALREADY_STARTED`]: the protocol is already open by `driver`.
+/// * [`Status::ACCESS_DENIED`]: the protocol is already open by another
+///   driver or exclusively.
+pub fn open_protocol_by_driver<P: ProtocolPointer + ?Sized>(
+    controller: Handle,
+    driver: Handle,
+) -> Result<ScopedProtocol<P>> {
+    // Safety: the firmware tracks the open protocol and stops the driver
+    // before the interface can be uninstalled.
+    unsafe {
+        open_protocol::<P>(
+            OpenProtocolParams {
+                handle: controller,
+                agent: driver,
+                controller: Some(controller),
+            },
+            OpenProtocolAttributes::ByDriver,
+        )
+    }
+}
+
+/// Opens a [`Protocol`] interface on a controller handle on behalf of a child
+/// controller created by a bus driver.
+///
+/// The protocol is opened with [`OpenProtocolAttributes::ByChildController`],
+/// using `driver` (the handle with the driver binding protocol) as the agent
+/// and `child` as the controller. This records the parent-child relationship
+/// in the handle database, so that disconnecting `controller` stops the child
+/// first.
+///
+/// The protocol is closed when the [`ScopedProtocol`] is dropped. To keep it
+/// open while the child exists, use [`ScopedProtocol::leak`] and close it
+/// before destroying the child with [`close_protocol`].
+///
+/// # Errors
+///
+/// * [`Status::UNSUPPORTED`]: the handle does not support the protocol.
+/// * [`Status::INVALID_PARAMETER`]: one of the handles is invalid.
+pub fn open_protocol_by_child_controller<P: ProtocolPointer + ?Sized>(
+    controller: Handle,
+    driver: Handle,
+    child: Handle,
+) -> Result<ScopedProtocol<P>> {
+    // Safety: the firmware tracks the open protocol and stops the child
+    // before the interface can be uninstalled.
+    unsafe {
+        open_protocol::<P>(
+            OpenProtocolParams {
+                handle: controller,
+                agent: driver,
+                controller: Some(child),
+            },
+            OpenProtocolAttributes::ByChildController,
+        )
+    }
+}
+
+/// Closes a [`Protocol`] interface that was opened with `params`.
+///
+/// This is only needed for protocols kept open with [`ScopedProtocol::leak`];
+/// otherwise, dropping the [`ScopedProtocol`] closes the protocol.
+///
+/// # Safety
+///
+/// No [`ScopedProtocol`] opened with the same `params` may exist, and no
+/// references to the protocol interface may be used after it is closed.
+///
+/// # Errors
+///
+/// * [`Status::INVALID_PARAMETER`]: one of the handles is invalid.
+/// * [`Status::NOT_FOUND`]: the protocol is not open with `params`.
+pub unsafe fn close_protocol<P: ProtocolPointer + ?Sized>(params: OpenProtocolParams) -> Result {
+    let bt = boot_services_raw_panicking();
+    let bt = unsafe { bt.as_ref() };
+
+    unsafe {
+        (bt.close_protocol)(
+            params.handle.as_ptr(),
+            &P::GUID,
+            params.agent.as_ptr(),
+            Handle::opt_to_ptr(params.controller),
+        )
+    }
+    .to_result()
+}
+
 /// Tests whether a handle supports a [`Protocol`].
 ///
 /// Returns `Ok(true)` if the handle supports the protocol, `Ok(false)` if not.
@@ -1250,6 +1701,27 @@ pub fn start_image(image_handle: Handle) -> Result {
     }
 }
 
+/// Boots an image the way a firmware boot manager boots a boot option.
+///
+/// This signals the ready-to-boot event groups with
+/// [`signal_ready_to_boot`], loads the image from `source`, arms the
+/// five-minute watchdog timer required by the UEFI specification, and
+/// starts the image. If the image returns, the watchdog timer is disabled
+/// again and the result of [`start_image`] is returned.
+///
+/// # Errors
+///
+/// See [`signal_ready_to_boot`], [`load_image`], and [`start_image`].
+pub fn start_boot_option(source: LoadImageSource) -> Result {
+    signal_ready_to_boot()?;
+
+    let image = load_image(image_handle(), source)?;
+    set_watchdog_timer(5 * 60, 0, None)?;
+    let status = start_image(image);
+    set_watchdog_timer(0, 0, None)?;
+    status
+}
+
 /// Exits the UEFI application and returns control to the UEFI component
 /// that started the UEFI application.
 ///
@@ -1365,6 +1837,7 @@ pub unsafe fn exit_boot_services(custom_memory_type: Option<MemoryType>) -> Memo
     // https://elixir.bootlin.com/linux/v6.13.7/source/drivers/firmware/efi/libstub/mem.c#L24
     let memory_type = custom_memory_type.unwrap_or(MemoryType::LOADER_DATA);
     crate::helpers::exit();
+    let runtime_services = crate::runtime::availability::read_rt_properties();
 
     let mut buf = MemoryMapBackingMemory::new(memory_type).expect("Failed to allocate memory");
 
@@ -1376,6 +1849,7 @@ pub unsafe fn exit_boot_services(custom_memory_type: Option<MemoryType>) -> Memo
     for _ in 0..2 {
         match unsafe { get_memory_map_and_exit_boot_services(buf.as_mut_slice()) } {
             Ok(memory_map) => {
+                crate::runtime::availability::mark_boot_services_exited(runtime_services);
                 return MemoryMapOwned::from_initialized_mem(buf, memory_map);
             }
             Err(err) => {
@@ -1570,6 +2044,165 @@ impl Deref for HandleBuffer {
     }
 }
 
+/// A cache of the handles supporting each protocol, for code that looks up
+/// the same protocols repeatedly.
+///
+/// Locating handles requires a pool allocation and a search of the handle
+/// database, which is slow on some firmware. The cache performs the search
+/// once per protocol and keeps the result until an interface for the
+/// protocol is installed or reinstalled, which it is notified about through
+/// [`register_protocol_notify`].
+///
+/// Firmware does not notify about uninstalled interfaces, so a cached handle
+/// may no longer support the protocol. Opening the protocol on such a handle
+/// fails with [`Status::UNSUPPORTED`]; call [`invalidate`] in that case.
+///
+/// # Example
+///
+/// ```no_run
+/// use uefi::boot::{self, HandleCache};
+/// use uefi::proto::console::serial::Serial;
+/// use uefi::ResultExt;
+///
+/// # fn example() -> uefi::Result {
+/// let mut cache = HandleCache::new();
+/// for _ in 0..100 {
+///     let handle = cache.handle_for_protocol::<Serial>()?;
+///     let mut serial = boot::open_protocol_exclusive::<Serial>(handle)?;
+///     serial.write(b"hello").discard_errdata()?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`invalidate`]: Self::invalidate
+#[cfg(feature = "alloc")]
+#[derive(Debug, Default)]
+pub struct HandleCache {
+    entries: Vec<HandleCacheEntry>,
+}
+
+/// Cached handles for one protocol.
+#[cfg(feature = "alloc")]
+#[derive(Debug)]
+struct HandleCacheEntry {
+    guid: &'static Guid,
+    handles: Vec<Handle>,
+    /// Cleared by [`HandleCache::notify`] when the handles are outdated.
+    /// Boxed so that the address passed to the event stays stable.
+    valid: Box<AtomicBool>,
+    /// Event registered for protocol notifications.
+    event: Event,
+}
+
+#[cfg(feature = "alloc")]
+impl HandleCache {
+    /// Creates an empty cache.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Marks an entry as outdated.
+    unsafe extern "efiapi" fn notify(_event: Event, context: Option<NonNull<c_void>>) {
+        if let Some(valid) = context {
+            let valid = unsafe { valid.cast::<AtomicBool>().as_ref() };
+            valid.store(false, Ordering::Release);
+        }
+    }
+
+    /// Returns the handles supporting the protocol identified by `guid`,
+    /// which may be empty.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::OUT_OF_RESOURCES`]: out of memory.
+    pub fn handles(&mut self, guid: &'static Guid) -> Result<&[Handle]> {
+        let index = match self.entries.iter().position(|e| e.guid == guid) {
+            Some(index) => index,
+            None => {
+                let valid = Box::new(AtomicBool::new(false));
+                let event = unsafe {
+                    create_event(
+                        EventType::NOTIFY_SIGNAL,
+                        Tpl::CALLBACK,
+                        Some(Self::notify),
+                        NonNull::new(ptr::from_ref(&*valid).cast_mut().cast()),
+                    )?
+                };
+                let entry = HandleCacheEntry {
+                    guid,
+                    handles: Vec::new(),
+                    valid,
+                    event,
+                };
+                // If registering fails, dropping `entry` closes the event.
+                register_protocol_notify(guid, &entry.event)?;
+                self.entries.push(entry);
+                self.entries.len() - 1
+            }
+        };
+
+        let entry = &mut self.entries[index];
+        if !entry.valid.swap(true, Ordering::AcqRel) {
+            // Mark the entry as valid before searching, so that an interface
+            // installed in the meantime invalidates it again.
+            entry.handles.clear();
+            match locate_handle_buffer(SearchType::ByProtocol(guid)) {
+                Ok(buffer) => entry.handles.extend_from_slice(&buffer),
+                Err(err) if err.status() == Status::NOT_FOUND => {}
+                Err(err) => {
+                    entry.valid.store(false, Ordering::Release);
+                    return Err(err);
+                }
+            }
+        }
+        Ok(&entry.handles)
+    }
+
+    /// Returns the handles supporting protocol `P`, which may be empty.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::OUT_OF_RESOURCES`]: out of memory.
+    pub fn handles_for_protocol<P: ProtocolPointer + ?Sized>(&mut self) -> Result<&[Handle]> {
+        self.handles(&P::GUID)
+    }
+
+    /// Returns an arbitrary handle supporting protocol `P`, like
+    /// [`get_handle_for_protocol`].
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::NOT_FOUND`]: no matching handle.
+    /// * [`Status::OUT_OF_RESOURCES`]: out of memory.
+    pub fn handle_for_protocol<P: ProtocolPointer + ?Sized>(&mut self) -> Result<Handle> {
+        self.handles_for_protocol::<P>()?
+            .first()
+            .copied()
+            .ok_or_else(|| Status::NOT_FOUND.into())
+    }
+
+    /// Marks all cached handles as outdated, so that they are searched again
+    /// on the next lookup.
+    pub fn invalidate(&mut self) {
+        for entry in &self.entries {
+            entry.valid.store(false, Ordering::Release);
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Drop for HandleCacheEntry {
+    fn drop(&mut self) {
+        // Closing the event also unregisters it from protocol notifications,
+        // so `valid` is not accessed anymore.
+        let _ = close_event(unsafe { self.event.unsafe_clone() });
+    }
+}
+
 /// An open [`Protocol`] interface. Automatically closes the protocol
 /// interface on drop.
 ///
@@ -1589,6 +2222,7 @@ pub struct ScopedProtocol<P: Protocol + ?Sized> {
     /// The protocol interface.
     interface: Option<NonNull<P>>,
     open_params: OpenProtocolParams,
+    attributes: OpenProtocolAttributes,
 }
 
 impl<P: Protocol + ?Sized> ScopedProtocol<P> {
@@ -1597,6 +2231,26 @@ impl<P: Protocol + ?Sized> ScopedProtocol<P> {
     pub const fn open_params(&self) -> OpenProtocolParams {
         self.open_params
     }
+
+    /// Returns the [`OpenProtocolAttributes`] used to open the
+    /// [`ScopedProtocol`].
+    #[must_use]
+    pub const fn attributes(&self) -> OpenProtocolAttributes {
+        self.attributes
+    }
+
+    /// Consumes the `ScopedProtocol` without closing the protocol, and
+    /// returns the parameters needed to close it later with
+    /// [`close_protocol`].
+    ///
+    /// This is used by drivers, which open protocols in the `Start` function
+    /// of their driver binding and close them in the `Stop` function.
+    #[must_use]
+    pub const fn leak(self) -> OpenProtocolParams {
+        let params = self.open_params;
+        mem::forget(self);
+        params
+    }
 }
 
 impl<P: Protocol + ?Sized> Drop for ScopedProtocol<P> {
@@ -1681,9 +2335,9 @@ impl Drop for TplGuard {
 }
 
 // OpenProtocolAttributes is safe to model as a regular enum because it
-// is only used as an input. The attributes are bitflags, but all valid
-// combinations are listed in the spec and only ByDriver and Exclusive
-// can actually be combined.
+// is never read back from the firmware. The attributes are bitflags, but
+// all valid combinations are listed in the spec and only ByDriver and
+// Exclusive can actually be combined.
 //
 // Some values intentionally excluded:
 //
@@ -1697,7 +2351,7 @@ impl Drop for TplGuard {
 
 /// Attributes for [`open_protocol`].
 #[repr(u32)]
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum OpenProtocolAttributes {
     /// Used by drivers to get a protocol interface for a handle. The
     /// driver will not be informed if the interface is uninstalled or
@@ -0,0 +1,322 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Allocation-free parser for INI-style configuration files.
+//!
+//! The supported syntax is intentionally small:
+//!
+//! ```ini
+//! ; Comments start with `;` or `#`.
+//! timeout = 5
+//!
+//! [entry]
+//! title = "Linux"   ; inline comments must be preceded by whitespace
+//! path = \EFI\linux\vmlinuz.efi
+//! ```
+//!
+//! - Keys before the first section header belong to the global section,
+//!   which is named `""`.
+//! - Leading and trailing whitespace around keys and values is removed.
+//! - Values may be surrounded by double quotes to preserve whitespace or
+//!   comment characters. No escape sequences are supported.
+//! - Section and key names are case-sensitive.
+
+use super::{ParseError, ParseErrorKind};
+
+/// A single `key = value` entry of an INI file.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Entry<'a> {
+    /// Name of the section the entry belongs to. This is `""` for entries
+    /// before the first section header.
+    pub section: &'a str,
+
+    /// The entry's key.
+    pub key: &'a str,
+
+    /// The entry's value, with surrounding quotes removed.
+    pub value: &'a str,
+
+    /// One-based line number of the entry.
+    pub line: usize,
+}
+
+/// An INI-style configuration file.
+///
+/// This is a thin wrapper around the text of the file. Parsing happens
+/// lazily when iterating over [`entries`] or looking up values.
+///
+/// [`entries`]: Self::entries
+#[derive(Clone, Copy, Debug)]
+pub struct Ini<'a> {
+    text: &'a str,
+}
+
+impl<'a> Ini<'a> {
+    /// Creates a new parser for `text`.
+    #[must_use]
+    pub const fn new(text: &'a str) -> Self {
+        Self { text }
+    }
+
+    /// Returns an iterator over all entries of the file.
+    #[must_use]
+    pub fn entries(&self) -> Entries<'a> {
+        Entries {
+            lines: self.text.lines().enumerate(),
+            section: "",
+        }
+    }
+
+    /// Checks that the whole file is well-formed.
+    ///
+    /// Lookup methods such as [`get`] skip malformed lines, so call this
+    /// first to report syntax errors to the user.
+    ///
+    /// [`get`]: Self::get
+    pub fn validate(&self) -> Result<(), ParseError> {
+        self.entries().try_for_each(|entry| entry.map(|_| ()))
+    }
+
+    /// Returns the value of `key` in `section`, or `None` if there is no
+    /// such entry. Use `""` as section name for the global section.
+    ///
+    /// If a key appears multiple times in a section, the last value wins.
+    #[must_use]
+    pub fn get(&self, section: &str, key: &str) -> Option<&'a str> {
+        self.entries()
+            .flatten()
+            .filter(|entry| entry.section == section && entry.key == key)
+            .last()
+            .map(|entry| entry.value)
+    }
+
+    /// Returns the value of `key` in `section` interpreted as boolean.
+    ///
+    /// The values `true`, `yes`, `on`, and `1` are `true`; `false`, `no`,
+    /// `off`, and `0` are `false` (ignoring ASCII case). Any other value
+    /// results in `None`.
+    #[must_use]
+    pub fn get_bool(&self, section: &str, key: &str) -> Option<bool> {
+        let value = self.get(section, key)?;
+        if ["true", "yes", "on", "1"]
+            .iter()
+            .any(|s| value.eq_ignore_ascii_case(s))
+        {
+            Some(true)
+        } else if ["false", "no", "off", "0"]
+            .iter()
+            .any(|s| value.eq_ignore_ascii_case(s))
+        {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the value of `key` in `section` interpreted as unsigned
+    /// integer. Decimal and `0x`-prefixed hexadecimal values are supported.
+    #[must_use]
+    pub fn get_u64(&self, section: &str, key: &str) -> Option<u64> {
+        let value = self.get(section, key)?;
+        match value
+            .strip_prefix("0x")
+            .or_else(|| value.strip_prefix("0X"))
+        {
+            Some(hex) => u64::from_str_radix(hex, 16).ok(),
+            None => value.parse().ok(),
+        }
+    }
+
+    /// Returns an iterator over the names of all sections, in the order
+    /// they appear. The global section is not included.
+    pub fn sections(&self) -> impl Iterator<Item = &'a str> + 'a {
+        self.text
+            .lines()
+            .map(strip_comment)
+            .filter_map(|line| line.strip_prefix('[')?.strip_suffix(']'))
+            .map(str::trim)
+    }
+}
+
+/// Iterator over the entries of an [`Ini`] file.
+///
+/// Malformed lines are reported as errors; iteration can continue past them.
+#[derive(Clone, Debug)]
+pub struct Entries<'a> {
+    lines: core::iter::Enumerate<core::str::Lines<'a>>,
+    section: &'a str,
+}
+
+impl<'a> Iterator for Entries<'a> {
+    type Item = Result<Entry<'a>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (index, line) = self.lines.next()?;
+            let line_number = index + 1;
+            let err = |kind| {
+                Some(Err(ParseError {
+                    line: line_number,
+                    kind,
+                }))
+            };
+
+            let line = strip_comment(line);
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(header) = line.strip_prefix('[') {
+                let Some(name) = header.strip_suffix(']') else {
+                    return err(ParseErrorKind::UnterminatedHeader);
+                };
+                let name = name.trim();
+                if name.is_empty() || name.contains(['[', ']']) {
+                    return err(ParseErrorKind::InvalidHeader);
+                }
+                self.section = name;
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                return err(ParseErrorKind::MissingDelimiter);
+            };
+            let key = key.trim();
+            if key.is_empty() {
+                return err(ParseErrorKind::InvalidKey);
+            }
+            let value = match unquote(value.trim()) {
+                Ok(value) => value,
+                Err(kind) => return err(kind),
+            };
+
+            return Some(Ok(Entry {
+                section: self.section,
+                key,
+                value,
+                line: line_number,
+            }));
+        }
+    }
+}
+
+/// Removes comments and surrounding whitespace from a line.
+///
+/// A comment either fills the whole line or starts with whitespace followed
+/// by `;` or `#`. Comment characters within double quotes are preserved.
+fn strip_comment(line: &str) -> &str {
+    let line = line.trim();
+    if line.starts_with([';', '#']) {
+        return "";
+    }
+
+    let mut in_quotes = false;
+    let mut prev_is_whitespace = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ';' | '#' if !in_quotes && prev_is_whitespace => return line[..i].trim_end(),
+            _ => {}
+        }
+        prev_is_whitespace = c.is_whitespace();
+    }
+    line
+}
+
+/// Removes the surrounding double quotes of a value, if present.
+fn unquote(value: &str) -> Result<&str, ParseErrorKind> {
+    match value.strip_prefix('"') {
+        Some(rest) => match rest.strip_suffix('"') {
+            Some(inner) if !inner.contains('"') => Ok(inner),
+            Some(_) => Err(ParseErrorKind::TrailingCharacters),
+            None if rest.contains('"') => Err(ParseErrorKind::TrailingCharacters),
+            None => Err(ParseErrorKind::UnterminatedString),
+        },
+        None => Ok(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "\
+; global settings
+timeout = 5
+verbose = Yes
+
+[linux]
+title = \"Linux  ; rolling\"   # inline comment
+path = \\EFI\\linux\\vmlinuz.efi
+timeout=0x10
+
+[ windows ]
+path=\\EFI\\Microsoft\\Boot\\bootmgfw.efi
+path = \\EFI\\Microsoft\\Boot\\bootmgr.efi
+";
+
+    #[test]
+    fn test_entries() {
+        let ini = Ini::new(CONFIG);
+        let mut entries = [None; 8];
+        for (slot, entry) in entries.iter_mut().zip(ini.entries()) {
+            *slot = Some(entry.unwrap());
+        }
+        let entries = entries.map(Option::unwrap_or_default);
+        assert_eq!(ini.entries().count(), 7);
+        assert_eq!(
+            entries[0],
+            Entry {
+                section: "",
+                key: "timeout",
+                value: "5",
+                line: 2,
+            }
+        );
+        assert_eq!(entries[2].section, "linux");
+        assert_eq!(entries[2].value, "Linux  ; rolling");
+        assert_eq!(entries[5].section, "windows");
+        assert!(ini.validate().is_ok());
+    }
+
+    #[test]
+    fn test_get() {
+        let ini = Ini::new(CONFIG);
+        assert_eq!(ini.get("", "timeout"), Some("5"));
+        assert_eq!(ini.get("linux", "path"), Some("\\EFI\\linux\\vmlinuz.efi"));
+        assert_eq!(
+            ini.get("windows", "path"),
+            Some("\\EFI\\Microsoft\\Boot\\bootmgr.efi")
+        );
+        assert_eq!(ini.get("windows", "title"), None);
+        assert_eq!(ini.get("missing", "path"), None);
+
+        assert_eq!(ini.get_u64("", "timeout"), Some(5));
+        assert_eq!(ini.get_u64("linux", "timeout"), Some(16));
+        assert_eq!(ini.get_u64("linux", "title"), None);
+        assert_eq!(ini.get_bool("", "verbose"), Some(true));
+        assert_eq!(ini.get_bool("", "timeout"), None);
+    }
+
+    #[test]
+    fn test_sections() {
+        let ini = Ini::new(CONFIG);
+        assert!(ini.sections().eq(["linux", "windows"]));
+    }
+
+    #[test]
+    fn test_errors() {
+        let check = |text, line, kind| {
+            assert_eq!(Ini::new(text).validate(), Err(ParseError { line, kind }));
+        };
+        check("[section", 1, ParseErrorKind::UnterminatedHeader);
+        check("a = b\n[ ]", 2, ParseErrorKind::InvalidHeader);
+        check("\n\nkey", 3, ParseErrorKind::MissingDelimiter);
+        check(" = value", 1, ParseErrorKind::InvalidKey);
+        check("a = \"value", 1, ParseErrorKind::UnterminatedString);
+        check("a = \"value\" x", 1, ParseErrorKind::TrailingCharacters);
+
+        // Malformed lines are skipped by lookups.
+        let ini = Ini::new("garbage\na = b");
+        assert_eq!(ini.get("", "a"), Some("b"));
+    }
+}
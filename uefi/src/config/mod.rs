@@ -0,0 +1,316 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Parsing helpers for small configuration files.
+//!
+//! Boot managers and similar tools typically read a configuration file from
+//! the EFI System Partition (ESP). This module provides the building blocks
+//! for doing so without pulling in large dependencies:
+//!
+//! - [`decode`] turns the raw file contents into a `&str`. Files on the ESP
+//!   are commonly either UTF-8 or UCS-2 (little endian, usually with a byte
+//!   order mark, as written by the UEFI Shell's `edit` command). The encoding
+//!   is detected with [`detect_encoding`].
+//! - [`ini`] is an allocation-free parser for INI-style files.
+//! - [`toml`] is a parser for a subset of TOML. It requires the `alloc`
+//!   feature.
+//!
+//! # Example
+//!
+//! ```
+//! use uefi::config::{decode, ini::Ini};
+//!
+//! let raw = b"timeout = 5\n[entry]\ntitle = \"Linux\"\n";
+//! let mut buf = [0; 64];
+//! let text = decode(raw, &mut buf).unwrap();
+//!
+//! let ini = Ini::new(text);
+//! assert_eq!(ini.get_u64("", "timeout"), Some(5));
+//! assert_eq!(ini.get("entry", "title"), Some("Linux"));
+//! ```
+
+pub mod ini;
+#[cfg(feature = "alloc")]
+pub mod toml;
+
+use core::fmt::{self, Display, Formatter};
+
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+
+/// UTF-8 byte order mark.
+const UTF8_BOM: [u8; 3] = [0xef, 0xbb, 0xbf];
+
+/// UCS-2 little endian byte order mark.
+const UCS2_LE_BOM: [u8; 2] = [0xff, 0xfe];
+
+/// UCS-2 big endian byte order mark.
+const UCS2_BE_BOM: [u8; 2] = [0xfe, 0xff];
+
+/// Text encoding of a configuration file.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Encoding {
+    /// UTF-8, with or without a byte order mark.
+    Utf8,
+
+    /// Little endian UCS-2, with or without a byte order mark.
+    Ucs2,
+
+    /// Big endian UCS-2. This is only detected by its byte order mark and
+    /// is not supported by [`decode`].
+    Ucs2BigEndian,
+}
+
+/// Detects the text encoding of `bytes`.
+///
+/// A byte order mark takes precedence. Without one, the data is treated as
+/// UCS-2 if the first character looks like a little endian ASCII character
+/// (a non-zero byte followed by a zero byte), and as UTF-8 otherwise.
+#[must_use]
+pub fn detect_encoding(bytes: &[u8]) -> Encoding {
+    if bytes.starts_with(&UTF8_BOM) {
+        Encoding::Utf8
+    } else if bytes.starts_with(&UCS2_LE_BOM) {
+        Encoding::Ucs2
+    } else if bytes.starts_with(&UCS2_BE_BOM) {
+        Encoding::Ucs2BigEndian
+    } else if bytes.len() >= 2 && bytes.len() % 2 == 0 && bytes[0] != 0 && bytes[1] == 0 {
+        Encoding::Ucs2
+    } else {
+        Encoding::Utf8
+    }
+}
+
+/// Error returned by [`decode`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DecodeError {
+    /// The data is encoded with an unsupported encoding.
+    UnsupportedEncoding(Encoding),
+
+    /// An invalid character was encountered at the given byte offset of the
+    /// input.
+    InvalidChar(usize),
+
+    /// UCS-2 input has an odd number of bytes.
+    TruncatedInput,
+
+    /// The output buffer is not big enough to hold the decoded text.
+    BufferTooSmall,
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedEncoding(encoding) => write!(f, "unsupported encoding: {encoding:?}"),
+            Self::InvalidChar(offset) => write!(f, "invalid character at offset {offset}"),
+            Self::TruncatedInput => write!(f, "UCS-2 input has an odd number of bytes"),
+            Self::BufferTooSmall => write!(f, "buffer too small"),
+        }
+    }
+}
+
+impl core::error::Error for DecodeError {}
+
+/// Decodes the raw contents of a configuration file into a `&str`.
+///
+/// The encoding is detected with [`detect_encoding`], and a leading byte
+/// order mark is removed. UTF-8 input is borrowed from `bytes` directly,
+/// without touching `buf`. UCS-2 input is converted to UTF-8 and written to
+/// `buf`, which must be large enough to hold the result. A trailing null
+/// character, if present, ends the text.
+pub fn decode<'a>(bytes: &'a [u8], buf: &'a mut [u8]) -> Result<&'a str, DecodeError> {
+    match detect_encoding(bytes) {
+        Encoding::Utf8 => decode_utf8(bytes),
+        Encoding::Ucs2 => {
+            let mut len = 0;
+            for c in ucs2_chars(bytes) {
+                let (_, c) = c?;
+                let encoded_len = c.len_utf8();
+                let dst = buf
+                    .get_mut(len..len + encoded_len)
+                    .ok_or(DecodeError::BufferTooSmall)?;
+                c.encode_utf8(dst);
+                len += encoded_len;
+            }
+            // OK to unwrap: the buffer only contains `char`s encoded as UTF-8.
+            Ok(core::str::from_utf8(&buf[..len]).unwrap())
+        }
+        encoding => Err(DecodeError::UnsupportedEncoding(encoding)),
+    }
+}
+
+/// Decodes the raw contents of a configuration file into a [`String`].
+///
+/// This is the same as [`decode`], but allocates the output.
+#[cfg(feature = "alloc")]
+pub fn decode_to_string(bytes: &[u8]) -> Result<String, DecodeError> {
+    match detect_encoding(bytes) {
+        Encoding::Utf8 => decode_utf8(bytes).map(String::from),
+        Encoding::Ucs2 => ucs2_chars(bytes).map(|c| c.map(|(_, c)| c)).collect(),
+        encoding => Err(DecodeError::UnsupportedEncoding(encoding)),
+    }
+}
+
+fn decode_utf8(bytes: &[u8]) -> Result<&str, DecodeError> {
+    let bytes = bytes.strip_prefix(&UTF8_BOM).unwrap_or(bytes);
+    let bytes = match bytes.iter().position(|b| *b == 0) {
+        Some(nul) => &bytes[..nul],
+        None => bytes,
+    };
+    core::str::from_utf8(bytes).map_err(|e| DecodeError::InvalidChar(e.valid_up_to()))
+}
+
+/// Iterates over the characters of little endian UCS-2 input, along with
+/// their byte offset. Stops at the first null character; an odd trailing
+/// byte is only reported as [`DecodeError::TruncatedInput`] if there is
+/// none.
+fn ucs2_chars(bytes: &[u8]) -> impl Iterator<Item = Result<(usize, char), DecodeError>> + '_ {
+    let start = if bytes.starts_with(&UCS2_LE_BOM) {
+        UCS2_LE_BOM.len()
+    } else {
+        0
+    };
+    let truncated = (bytes.len() - start) % 2 != 0;
+    let mut pairs = bytes[start..].chunks_exact(2).enumerate();
+    let mut done = false;
+
+    core::iter::from_fn(move || {
+        if done {
+            return None;
+        }
+        let Some((i, pair)) = pairs.next() else {
+            done = true;
+            return truncated.then_some(Err(DecodeError::TruncatedInput));
+        };
+        let offset = start + i * 2;
+        let c = u16::from_le_bytes([pair[0], pair[1]]);
+        match char::from_u32(u32::from(c)) {
+            Some('\0') => {
+                done = true;
+                None
+            }
+            Some(c) => Some(Ok((offset, c))),
+            None => Some(Err(DecodeError::InvalidChar(offset))),
+        }
+    })
+}
+
+/// Error returned when a configuration file cannot be parsed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ParseError {
+    /// One-based line number where the error occurred.
+    pub line: usize,
+
+    /// The kind of error.
+    pub kind: ParseErrorKind,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.kind)
+    }
+}
+
+impl core::error::Error for ParseError {}
+
+/// The kind of a [`ParseError`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ParseErrorKind {
+    /// A section or table header is missing its closing `]`.
+    UnterminatedHeader,
+
+    /// A section or table header has an empty or invalid name.
+    InvalidHeader,
+
+    /// A line is neither a header nor a `key = value` pair.
+    MissingDelimiter,
+
+    /// A key is empty or contains invalid characters.
+    InvalidKey,
+
+    /// A quoted string is missing its closing quote.
+    UnterminatedString,
+
+    /// A string contains an invalid escape sequence.
+    InvalidEscape,
+
+    /// A value could not be parsed.
+    InvalidValue,
+
+    /// Unexpected characters follow a value.
+    TrailingCharacters,
+
+    /// A key or table was defined more than once.
+    DuplicateKey,
+
+    /// Arrays are nested deeper than the parser allows.
+    NestingTooDeep,
+}
+
+impl Display for ParseErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::UnterminatedHeader => "unterminated header",
+            Self::InvalidHeader => "invalid header",
+            Self::MissingDelimiter => "missing `=` delimiter",
+            Self::InvalidKey => "invalid key",
+            Self::UnterminatedString => "unterminated string",
+            Self::InvalidEscape => "invalid escape sequence",
+            Self::InvalidValue => "invalid value",
+            Self::TrailingCharacters => "trailing characters after value",
+            Self::DuplicateKey => "duplicate key",
+            Self::NestingTooDeep => "arrays nested too deeply",
+        };
+        f.write_str(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_encoding() {
+        assert_eq!(detect_encoding(b""), Encoding::Utf8);
+        assert_eq!(detect_encoding(b"a = b"), Encoding::Utf8);
+        assert_eq!(detect_encoding(b"\xef\xbb\xbfa = b"), Encoding::Utf8);
+        assert_eq!(detect_encoding(b"\xff\xfea\0"), Encoding::Ucs2);
+        assert_eq!(detect_encoding(b"a\0=\0"), Encoding::Ucs2);
+        assert_eq!(detect_encoding(b"\xfe\xff\0a"), Encoding::Ucs2BigEndian);
+    }
+
+    #[test]
+    fn test_decode() {
+        let mut buf = [0; 16];
+
+        assert_eq!(decode(b"\xef\xbb\xbfa=b", &mut buf), Ok("a=b"));
+        assert_eq!(decode(b"a=b\0garbage", &mut buf), Ok("a=b"));
+        assert_eq!(decode(b"a\xff", &mut buf), Err(DecodeError::InvalidChar(1)));
+
+        assert_eq!(decode(b"\xff\xfea\0=\0\xe4\0", &mut buf), Ok("a=ä"));
+        assert_eq!(decode(b"a\0=\0b\0\0\0c\0", &mut buf), Ok("a=b"));
+        assert_eq!(
+            decode(b"a\0\x00\xd8", &mut buf),
+            Err(DecodeError::InvalidChar(2))
+        );
+        assert_eq!(
+            decode(b"\xff\xfea\0b", &mut buf),
+            Err(DecodeError::TruncatedInput)
+        );
+        assert_eq!(decode(b"a\0\0\0x", &mut buf), Ok("a"));
+        assert_eq!(
+            decode(b"a\0b\0c\0", &mut [0; 2]),
+            Err(DecodeError::BufferTooSmall)
+        );
+        assert_eq!(
+            decode(b"\xfe\xff\0a", &mut buf),
+            Err(DecodeError::UnsupportedEncoding(Encoding::Ucs2BigEndian))
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_decode_to_string() {
+        assert_eq!(decode_to_string(b"a=b").unwrap(), "a=b");
+        assert_eq!(decode_to_string(b"\xff\xfea\0=\0b\0").unwrap(), "a=b");
+    }
+}
@@ -0,0 +1,583 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Parser for a subset of [TOML].
+//!
+//! The following subset of TOML is supported, which covers typical boot
+//! configuration files:
+//!
+//! - Tables (`[a.b]`) and arrays of tables (`[[entry]]`).
+//! - Bare, quoted, and dotted keys.
+//! - Basic strings (with escape sequences) and literal strings.
+//! - Integers in decimal, hexadecimal (`0x`), octal (`0o`), and binary (`0b`)
+//!   notation, with optional `_` separators.
+//! - Booleans.
+//! - Arrays of the above, written on a single line, nested at most
+//!   [`MAX_DEPTH`] levels deep.
+//!
+//! Not supported are floats, date-times, inline tables, multi-line strings,
+//! and arrays spanning multiple lines; these result in a [`ParseError`].
+//!
+//! # Example
+//!
+//! ```
+//! use uefi::config::toml::{self, Value};
+//!
+//! let config = toml::parse(
+//!     r#"
+//! default = "linux"
+//!
+//! [[entry]]
+//! name = "linux"
+//! args = ["quiet", "splash"]
+//! "#,
+//! )
+//! .unwrap();
+//!
+//! assert_eq!(config["default"].as_str(), Some("linux"));
+//! assert_eq!(
+//!     toml::get(&config, "entry").and_then(Value::as_array).map(|a| a.len()),
+//!     Some(1)
+//! );
+//! ```
+//!
+//! [TOML]: https://toml.io
+
+use super::{ParseError, ParseErrorKind};
+use alloc::collections::btree_map::{BTreeMap, Entry};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Maximum nesting depth of arrays. Deeper nesting is rejected, as the
+/// parser recurses for each level and UEFI stacks are small.
+pub const MAX_DEPTH: usize = 32;
+
+/// A TOML table, mapping keys to values.
+pub type Table = BTreeMap<String, Value>;
+
+/// A TOML value.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Value {
+    /// A string.
+    String(String),
+
+    /// A signed 64-bit integer.
+    Integer(i64),
+
+    /// A boolean.
+    Boolean(bool),
+
+    /// An array of values.
+    Array(Vec<Self>),
+
+    /// A table.
+    Table(Table),
+}
+
+impl Value {
+    /// Returns the string if this value is a string.
+    #[must_use]
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns the integer if this value is an integer.
+    #[must_use]
+    pub const fn as_integer(&self) -> Option<i64> {
+        match self {
+            Self::Integer(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// Returns the boolean if this value is a boolean.
+    #[must_use]
+    pub const fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements if this value is an array.
+    #[must_use]
+    pub fn as_array(&self) -> Option<&[Self]> {
+        match self {
+            Self::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Returns the table if this value is a table.
+    #[must_use]
+    pub const fn as_table(&self) -> Option<&Table> {
+        match self {
+            Self::Table(t) => Some(t),
+            _ => None,
+        }
+    }
+}
+
+/// Looks up a value by its dotted `path`, e.g. `"boot.timeout"`.
+///
+/// Path segments are split at every `.`; quoted keys containing dots can
+/// not be looked up this way.
+#[must_use]
+pub fn get<'a>(table: &'a Table, path: &str) -> Option<&'a Value> {
+    let mut segments = path.split('.');
+    let mut value = table.get(segments.next()?)?;
+    for segment in segments {
+        value = value.as_table()?.get(segment)?;
+    }
+    Some(value)
+}
+
+/// Parses `text` into a [`Table`].
+pub fn parse(text: &str) -> Result<Table, ParseError> {
+    let mut root = Table::new();
+    let mut current: Vec<String> = Vec::new();
+    let mut defined_tables: Vec<Vec<String>> = Vec::new();
+
+    for (index, line) in text.lines().enumerate() {
+        parse_line(line, &mut root, &mut current, &mut defined_tables).map_err(|kind| {
+            ParseError {
+                line: index + 1,
+                kind,
+            }
+        })?;
+    }
+
+    Ok(root)
+}
+
+fn parse_line(
+    line: &str,
+    root: &mut Table,
+    current: &mut Vec<String>,
+    defined_tables: &mut Vec<Vec<String>>,
+) -> Result<(), ParseErrorKind> {
+    let mut cursor = Cursor { s: line, depth: 0 };
+    cursor.skip_whitespace();
+    if cursor.at_end() {
+        return Ok(());
+    }
+
+    if cursor.eat("[[") {
+        let path = cursor.key()?;
+        if !cursor.eat("]]") {
+            return Err(ParseErrorKind::UnterminatedHeader);
+        }
+        cursor.end()?;
+
+        let (last, parents) = path.split_last().ok_or(ParseErrorKind::InvalidHeader)?;
+        let parent = table_at(root, parents)?;
+        match parent.entry(last.clone()) {
+            Entry::Vacant(entry) => {
+                entry.insert(Value::Array(Vec::from([Value::Table(Table::new())])));
+            }
+            Entry::Occupied(mut entry) => match entry.get_mut() {
+                Value::Array(array) if array.iter().all(|v| v.as_table().is_some()) => {
+                    array.push(Value::Table(Table::new()));
+                }
+                _ => return Err(ParseErrorKind::DuplicateKey),
+            },
+        }
+        // Tables defined below the previous element belong to it, and may
+        // be defined again for the new one.
+        defined_tables.retain(|table| !table.starts_with(&path));
+        *current = path;
+    } else if cursor.eat("[") {
+        let path = cursor.key()?;
+        if !cursor.eat("]") {
+            return Err(ParseErrorKind::UnterminatedHeader);
+        }
+        cursor.end()?;
+
+        if defined_tables.contains(&path) {
+            return Err(ParseErrorKind::DuplicateKey);
+        }
+        table_at(root, &path)?;
+        defined_tables.push(path.clone());
+        *current = path;
+    } else {
+        let key = cursor.key()?;
+        if !cursor.eat("=") {
+            return Err(ParseErrorKind::MissingDelimiter);
+        }
+        cursor.skip_whitespace();
+        let value = cursor.value()?;
+        cursor.end()?;
+
+        let (last, parents) = key.split_last().ok_or(ParseErrorKind::InvalidKey)?;
+        let table = table_at(table_at(root, current)?, parents)?;
+        match table.entry(last.clone()) {
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+            }
+            Entry::Occupied(_) => return Err(ParseErrorKind::DuplicateKey),
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the table at `path`, creating intermediate tables as needed. If
+/// a path segment refers to an array of tables, its last element is used.
+fn table_at<'a>(
+    mut table: &'a mut Table,
+    path: &[String],
+) -> Result<&'a mut Table, ParseErrorKind> {
+    for segment in path {
+        let value = table
+            .entry(segment.clone())
+            .or_insert_with(|| Value::Table(Table::new()));
+        table = match value {
+            Value::Table(t) => t,
+            Value::Array(array) => match array.last_mut() {
+                Some(Value::Table(t)) => t,
+                _ => return Err(ParseErrorKind::DuplicateKey),
+            },
+            _ => return Err(ParseErrorKind::DuplicateKey),
+        };
+    }
+    Ok(table)
+}
+
+/// Simple cursor over the remaining text of a line.
+struct Cursor<'a> {
+    s: &'a str,
+    /// Number of arrays currently being parsed.
+    depth: usize,
+}
+
+impl Cursor<'_> {
+    fn skip_whitespace(&mut self) {
+        self.s = self.s.trim_start_matches([' ', '\t']);
+    }
+
+    fn at_end(&self) -> bool {
+        self.s.is_empty() || self.s.starts_with('#')
+    }
+
+    /// Checks that only whitespace or a comment is left.
+    fn end(&mut self) -> Result<(), ParseErrorKind> {
+        self.skip_whitespace();
+        if self.at_end() {
+            Ok(())
+        } else {
+            Err(ParseErrorKind::TrailingCharacters)
+        }
+    }
+
+    fn eat(&mut self, prefix: &str) -> bool {
+        match self.s.strip_prefix(prefix) {
+            Some(rest) => {
+                self.s = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn next_char(&mut self) -> Option<char> {
+        let mut chars = self.s.chars();
+        let c = chars.next()?;
+        self.s = chars.as_str();
+        Some(c)
+    }
+
+    /// Parses a possibly dotted key, skipping whitespace on both sides.
+    fn key(&mut self) -> Result<Vec<String>, ParseErrorKind> {
+        let mut segments = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let segment = if self.eat("\"") {
+                self.basic_string()?
+            } else if self.eat("'") {
+                self.literal_string()?
+            } else {
+                let end = self
+                    .s
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+                    .unwrap_or(self.s.len());
+                if end == 0 {
+                    return Err(ParseErrorKind::InvalidKey);
+                }
+                let (segment, rest) = self.s.split_at(end);
+                self.s = rest;
+                String::from(segment)
+            };
+            segments.push(segment);
+            self.skip_whitespace();
+            if !self.eat(".") {
+                return Ok(segments);
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, ParseErrorKind> {
+        if self.s.starts_with("\"\"\"") || self.s.starts_with("'''") {
+            Err(ParseErrorKind::InvalidValue)
+        } else if self.eat("\"") {
+            self.basic_string().map(Value::String)
+        } else if self.eat("'") {
+            self.literal_string().map(Value::String)
+        } else if self.eat("[") {
+            self.array().map(Value::Array)
+        } else if self.eat("true") {
+            Ok(Value::Boolean(true))
+        } else if self.eat("false") {
+            Ok(Value::Boolean(false))
+        } else {
+            self.integer().map(Value::Integer)
+        }
+    }
+
+    /// Parses the rest of an array after the opening `[`.
+    fn array(&mut self) -> Result<Vec<Value>, ParseErrorKind> {
+        if self.depth == MAX_DEPTH {
+            return Err(ParseErrorKind::NestingTooDeep);
+        }
+        self.depth += 1;
+        let values = self.array_values();
+        self.depth -= 1;
+        values
+    }
+
+    fn array_values(&mut self) -> Result<Vec<Value>, ParseErrorKind> {
+        let mut values = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.eat("]") {
+                return Ok(values);
+            }
+            if self.at_end() {
+                return Err(ParseErrorKind::InvalidValue);
+            }
+            values.push(self.value()?);
+            self.skip_whitespace();
+            if !self.eat(",") && !self.s.starts_with(']') {
+                return Err(ParseErrorKind::InvalidValue);
+            }
+        }
+    }
+
+    fn integer(&mut self) -> Result<i64, ParseErrorKind> {
+        let end = self
+            .s
+            .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '+' | '-')))
+            .unwrap_or(self.s.len());
+        let (token, rest) = self.s.split_at(end);
+        self.s = rest;
+
+        if token.is_empty()
+            || token.starts_with('_')
+            || token.ends_with('_')
+            || token.contains("__")
+        {
+            return Err(ParseErrorKind::InvalidValue);
+        }
+        let (negative, digits) = match token.as_bytes()[0] {
+            b'-' => (true, &token[1..]),
+            b'+' => (false, &token[1..]),
+            _ => (false, token),
+        };
+        let (radix, digits) = match digits.get(..2) {
+            Some("0x") if !negative => (16, &digits[2..]),
+            Some("0o") if !negative => (8, &digits[2..]),
+            Some("0b") if !negative => (2, &digits[2..]),
+            _ => (10, digits),
+        };
+        if digits.is_empty() || digits.starts_with(['+', '-']) {
+            return Err(ParseErrorKind::InvalidValue);
+        }
+
+        let mut value: i64 = 0;
+        for c in digits.chars().filter(|c| *c != '_') {
+            let digit = c.to_digit(radix).ok_or(ParseErrorKind::InvalidValue)?;
+            value = value
+                .checked_mul(i64::from(radix))
+                .and_then(|v| {
+                    if negative {
+                        v.checked_sub(i64::from(digit))
+                    } else {
+                        v.checked_add(i64::from(digit))
+                    }
+                })
+                .ok_or(ParseErrorKind::InvalidValue)?;
+        }
+        Ok(value)
+    }
+
+    /// Parses the rest of a basic string after the opening `"`.
+    fn basic_string(&mut self) -> Result<String, ParseErrorKind> {
+        let mut s = String::new();
+        loop {
+            match self.next_char().ok_or(ParseErrorKind::UnterminatedString)? {
+                '"' => return Ok(s),
+                '\\' => {
+                    let c = match self.next_char().ok_or(ParseErrorKind::UnterminatedString)? {
+                        'b' => '\u{8}',
+                        't' => '\t',
+                        'n' => '\n',
+                        'f' => '\u{c}',
+                        'r' => '\r',
+                        '"' => '"',
+                        '\\' => '\\',
+                        'u' => self.unicode_escape(4)?,
+                        'U' => self.unicode_escape(8)?,
+                        _ => return Err(ParseErrorKind::InvalidEscape),
+                    };
+                    s.push(c);
+                }
+                c => s.push(c),
+            }
+        }
+    }
+
+    fn unicode_escape(&mut self, len: usize) -> Result<char, ParseErrorKind> {
+        let hex = self.s.get(..len).ok_or(ParseErrorKind::InvalidEscape)?;
+        self.s = &self.s[len..];
+        u32::from_str_radix(hex, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or(ParseErrorKind::InvalidEscape)
+    }
+
+    /// Parses the rest of a literal string after the opening `'`.
+    fn literal_string(&mut self) -> Result<String, ParseErrorKind> {
+        let (s, rest) = self
+            .s
+            .split_once('\'')
+            .ok_or(ParseErrorKind::UnterminatedString)?;
+        self.s = rest;
+        Ok(String::from(s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_parse() {
+        let config = parse(
+            r#"
+# Boot configuration
+timeout = 0x1_0
+verbose = true
+title = "Boot \"menu\"\t\u00e4"
+path = 'C:\EFI\boot'
+
+[console]
+mode.columns = 80
+mode.rows = -25 # comment
+
+[[entry]]
+name = "linux"
+args = ["quiet", 'splash', [1, 2]]
+
+[[entry]]
+name = "windows"
+args = []
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config["timeout"], Value::Integer(16));
+        assert_eq!(config["verbose"].as_bool(), Some(true));
+        assert_eq!(config["title"].as_str(), Some("Boot \"menu\"\tä"));
+        assert_eq!(config["path"].as_str(), Some("C:\\EFI\\boot"));
+        assert_eq!(
+            get(&config, "console.mode.columns").and_then(Value::as_integer),
+            Some(80)
+        );
+        assert_eq!(
+            get(&config, "console.mode.rows").and_then(Value::as_integer),
+            Some(-25)
+        );
+
+        let entries = config["entry"].as_array().unwrap();
+        assert_eq!(entries.len(), 2);
+        let linux = entries[0].as_table().unwrap();
+        assert_eq!(linux["name"].as_str(), Some("linux"));
+        assert_eq!(
+            linux["args"],
+            Value::Array(vec![
+                Value::String("quiet".into()),
+                Value::String("splash".into()),
+                Value::Array(vec![Value::Integer(1), Value::Integer(2)]),
+            ])
+        );
+        let windows = entries[1].as_table().unwrap();
+        assert_eq!(windows["args"], Value::Array(vec![]));
+    }
+
+    #[test]
+    fn test_errors() {
+        let check = |text, line, kind| {
+            assert_eq!(parse(text), Err(ParseError { line, kind }));
+        };
+        check("a = 1\na = 2", 2, ParseErrorKind::DuplicateKey);
+        check("[a]\n[a]", 2, ParseErrorKind::DuplicateKey);
+        check("a = 1\n[a]", 2, ParseErrorKind::DuplicateKey);
+        check("[a", 1, ParseErrorKind::UnterminatedHeader);
+        check("a", 1, ParseErrorKind::MissingDelimiter);
+        check("= 1", 1, ParseErrorKind::InvalidKey);
+        check("a = \"b", 1, ParseErrorKind::UnterminatedString);
+        check("a = \"\\q\"", 1, ParseErrorKind::InvalidEscape);
+        check("a = 1.5", 1, ParseErrorKind::TrailingCharacters);
+        check("a = 1_", 1, ParseErrorKind::InvalidValue);
+        check("a = 0xg", 1, ParseErrorKind::InvalidValue);
+        check("a = [1,", 1, ParseErrorKind::InvalidValue);
+        check("a = 99999999999999999999", 1, ParseErrorKind::InvalidValue);
+        check("a = true false", 1, ParseErrorKind::TrailingCharacters);
+        check("[[a]]\n[a.b]\n[a.b]", 3, ParseErrorKind::DuplicateKey);
+    }
+
+    #[test]
+    fn test_array_of_tables_subtables() {
+        let config =
+            parse("[[entry]]\n[entry.opts]\na = 1\n[[entry]]\n[entry.opts]\na = 2").unwrap();
+        let entries = config["entry"].as_array().unwrap();
+        assert_eq!(entries.len(), 2);
+        for (entry, a) in entries.iter().zip([1, 2]) {
+            let opts = entry.as_table().unwrap()["opts"].as_table().unwrap();
+            assert_eq!(opts["a"].as_integer(), Some(a));
+        }
+    }
+
+    #[test]
+    fn test_nesting_limit() {
+        let nested = |depth| {
+            let mut text = String::from("a = ");
+            text.extend(core::iter::repeat_n('[', depth));
+            text.extend(core::iter::repeat_n(']', depth));
+            text
+        };
+        assert!(parse(&nested(MAX_DEPTH)).is_ok());
+        assert_eq!(
+            parse(&nested(MAX_DEPTH + 1)),
+            Err(ParseError {
+                line: 1,
+                kind: ParseErrorKind::NestingTooDeep
+            })
+        );
+        // Unterminated arrays must not overflow the stack either.
+        let text = String::from("a = ") + &"[".repeat(100_000);
+        assert_eq!(
+            parse(&text).unwrap_err().kind,
+            ParseErrorKind::NestingTooDeep
+        );
+    }
+
+    #[test]
+    fn test_integer_limits() {
+        let config = parse("min = -9223372036854775808\nmax = 9223372036854775807").unwrap();
+        assert_eq!(config["min"].as_integer(), Some(i64::MIN));
+        assert_eq!(config["max"].as_integer(), Some(i64::MAX));
+    }
+}
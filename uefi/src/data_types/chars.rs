@@ -24,6 +24,14 @@ impl core::error::Error for CharConversionError {}
 #[repr(transparent)]
 pub struct Char8(u8);
 
+impl Char8 {
+    /// Checks if the value is within the ASCII range.
+    #[must_use]
+    pub const fn is_ascii(&self) -> bool {
+        self.0.is_ascii()
+    }
+}
+
 impl TryFrom<char> for Char8 {
     type Error = CharConversionError;
 
@@ -71,6 +79,16 @@ impl PartialEq<char> for Char8 {
     }
 }
 
+impl TryFrom<Char16> for Char8 {
+    type Error = CharConversionError;
+
+    fn try_from(value: Char16) -> Result<Self, Self::Error> {
+        u8::try_from(value.0)
+            .map(Char8)
+            .map_err(|_| CharConversionError)
+    }
+}
+
 /// Latin-1 version of the NUL character
 pub const NUL_8: Char8 = Char8(0);
 
@@ -80,6 +98,10 @@ pub const NUL_8: Char8 = Char8(0);
 pub struct Char16(u16);
 
 impl Char16 {
+    /// The replacement character `U+FFFD`, used in place of invalid
+    /// characters.
+    pub const REPLACEMENT_CHARACTER: Self = Self(0xfffd);
+
     /// Creates a UCS-2 character from a Rust character without checks.
     ///
     /// # Safety
@@ -94,6 +116,28 @@ impl Char16 {
     pub const fn is_ascii(&self) -> bool {
         self.0 <= 127
     }
+
+    /// Checks if `value` is a UTF-16 surrogate, which is not a valid UCS-2
+    /// character.
+    #[must_use]
+    pub const fn is_surrogate(value: u16) -> bool {
+        matches!(value, 0xd800..=0xdfff)
+    }
+
+    /// Converts a character provided by the firmware, replacing surrogates
+    /// with [`REPLACEMENT_CHARACTER`].
+    ///
+    /// Use [`TryFrom<u16>`] instead to reject them.
+    ///
+    /// [`REPLACEMENT_CHARACTER`]: Self::REPLACEMENT_CHARACTER
+    #[must_use]
+    pub const fn from_u16_lossy(value: u16) -> Self {
+        if Self::is_surrogate(value) {
+            Self::REPLACEMENT_CHARACTER
+        } else {
+            Self(value)
+        }
+    }
 }
 
 impl TryFrom<char> for Char16 {
@@ -127,6 +171,13 @@ impl TryFrom<u16> for Char16 {
     }
 }
 
+impl From<Char8> for Char16 {
+    fn from(char: Char8) -> Self {
+        // Latin-1 is the first block of UCS-2.
+        Self(char.0.into())
+    }
+}
+
 impl From<Char16> for u16 {
     fn from(char: Char16) -> Self {
         char.0
@@ -184,4 +235,23 @@ mod tests {
         assert_eq!(Char8(0x41), primitive_char);
         assert_eq!(Char16(0x41), primitive_char);
     }
+
+    #[test]
+    fn test_char_validation() {
+        assert!(Char8(b'a').is_ascii());
+        assert!(!Char8(0xe9).is_ascii());
+        assert_eq!(Char16::from(Char8(0xe9)), 'é');
+        assert_eq!(Char8::try_from(Char16(0xe9)).unwrap(), Char8(0xe9));
+        assert!(Char8::try_from(Char16(0x100)).is_err());
+
+        assert!(Char16::is_surrogate(0xd800));
+        assert!(Char16::is_surrogate(0xdfff));
+        assert!(!Char16::is_surrogate(0xe000));
+        assert!(Char16::try_from(0xdc00).is_err());
+        assert_eq!(
+            Char16::from_u16_lossy(0xdc00),
+            Char16::REPLACEMENT_CHARACTER
+        );
+        assert_eq!(Char16::from_u16_lossy(0x41), 'A');
+    }
 }
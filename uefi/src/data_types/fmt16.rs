@@ -0,0 +1,235 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Formatting of UCS-2 strings without allocating.
+
+use super::chars::{Char16, NUL_16};
+use super::strs::CStr16;
+use core::fmt::{self, Display, Formatter, Write};
+
+/// Adapter to [`Display`] a sequence of [`Char16`]s.
+///
+/// Unlike converting to a [`String`] first, this doesn't allocate. Width,
+/// fill, alignment, and precision (the maximum number of characters) are
+/// supported, as for [`str`]. Characters that are not valid Unicode scalar
+/// values, i.e. surrogates, are displayed as
+/// [`REPLACEMENT_CHARACTER`][char::REPLACEMENT_CHARACTER].
+///
+/// Returned by [`CStr16::display`], or created from any cloneable iterator:
+///
+/// ```
+/// use uefi::data_types::DisplayChars;
+/// use uefi::cstr16;
+///
+/// let chars = cstr16!("firmware").iter().copied().take(4);
+/// assert_eq!(format!("[{:>6}]", DisplayChars::new(chars)), "[  firm]");
+/// ```
+///
+/// [`String`]: https://doc.rust-lang.org/alloc/string/struct.String.html
+#[derive(Clone, Debug)]
+pub struct DisplayChars<I> {
+    chars: I,
+}
+
+impl<I: Iterator<Item = Char16> + Clone> DisplayChars<I> {
+    /// Creates an adapter displaying the characters of `chars`.
+    pub const fn new(chars: I) -> Self {
+        Self { chars }
+    }
+
+    fn chars(&self) -> impl Iterator<Item = char> + use<I> {
+        self.chars
+            .clone()
+            .map(|c| char::from_u32(u16::from(c).into()).unwrap_or(char::REPLACEMENT_CHARACTER))
+    }
+}
+
+impl<I: Iterator<Item = Char16> + Clone> Display for DisplayChars<I> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let max = f.precision().unwrap_or(usize::MAX);
+        let Some(width) = f.width() else {
+            return self.chars().take(max).try_for_each(|c| f.write_char(c));
+        };
+
+        let len = self.chars().take(max).count();
+        let padding = width.saturating_sub(len);
+        let (before, after) = match f.align() {
+            Some(fmt::Alignment::Right) => (padding, 0),
+            Some(fmt::Alignment::Center) => (padding / 2, padding - padding / 2),
+            Some(fmt::Alignment::Left) | None => (0, padding),
+        };
+        let fill = f.fill();
+        for _ in 0..before {
+            f.write_char(fill)?;
+        }
+        for c in self.chars().take(len) {
+            f.write_char(c)?;
+        }
+        for _ in 0..after {
+            f.write_char(fill)?;
+        }
+        Ok(())
+    }
+}
+
+/// A [`fmt::Write`] sink encoding to a UCS-2 buffer.
+///
+/// The buffer is kept null-terminated, so that the text written so far can
+/// be passed to the firmware with [`as_cstr16`] at any time, e.g. to build
+/// a string for [`Output::output_string`] with [`write!`] in a no-alloc
+/// build:
+///
+/// ```
+/// use core::fmt::Write;
+/// use uefi::data_types::Ucs2Writer;
+/// use uefi::cstr16;
+///
+/// let mut buf = [0; 16];
+/// let mut writer = Ucs2Writer::new(&mut buf);
+/// write!(writer, "Boot{:04X}", 3).unwrap();
+/// assert_eq!(writer.as_cstr16(), cstr16!("Boot0003"));
+/// ```
+///
+/// Writing fails with [`fmt::Error`] if the buffer is full, or on a
+/// character that is not representable in UCS-2, or a null character. The
+/// characters before it are kept.
+///
+/// [`as_cstr16`]: Self::as_cstr16
+/// [`Output::output_string`]: crate::proto::console::text::Output::output_string
+#[derive(Debug)]
+pub struct Ucs2Writer<'a> {
+    buf: &'a mut [u16],
+    len: usize,
+}
+
+impl<'a> Ucs2Writer<'a> {
+    /// Creates a writer that encodes into `buf`, which must have room for
+    /// the trailing null character.
+    pub const fn new(buf: &'a mut [u16]) -> Self {
+        if let Some(first) = buf.first_mut() {
+            *first = 0;
+        }
+        Self { buf, len: 0 }
+    }
+
+    /// Returns the number of characters written.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether nothing has been written.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the maximum number of characters the buffer can hold.
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        self.buf.len().saturating_sub(1)
+    }
+
+    /// Discards the characters written.
+    pub const fn clear(&mut self) {
+        self.len = 0;
+        if let Some(first) = self.buf.first_mut() {
+            *first = 0;
+        }
+    }
+
+    /// Returns the characters written as a string.
+    #[must_use]
+    pub fn as_cstr16(&self) -> &CStr16 {
+        let Some(chars) = self.buf.get(..=self.len) else {
+            // SAFETY: a single null character.
+            return unsafe { CStr16::from_char16_with_nul_unchecked(&[NUL_16]) };
+        };
+        // SAFETY: only valid non-null characters are written, and the
+        // buffer is kept null-terminated.
+        unsafe { CStr16::from_u16_with_nul_unchecked(chars) }
+    }
+
+    /// Consumes the writer, returning the characters written as a string
+    /// borrowing the buffer.
+    #[must_use]
+    pub fn into_cstr16(self) -> &'a CStr16 {
+        let Some(chars) = self.buf.get(..=self.len) else {
+            // SAFETY: a single null character.
+            return unsafe { CStr16::from_char16_with_nul_unchecked(&[NUL_16]) };
+        };
+        // SAFETY: see `as_cstr16`.
+        unsafe { CStr16::from_u16_with_nul_unchecked(chars) }
+    }
+}
+
+impl Write for Ucs2Writer<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        s.chars().try_for_each(|c| self.write_char(c))
+    }
+
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        let c = Char16::try_from(c).map_err(|_| fmt::Error)?;
+        if c == NUL_16 || self.len + 1 >= self.buf.len() {
+            return Err(fmt::Error);
+        }
+        self.buf[self.len] = c.into();
+        self.len += 1;
+        self.buf[self.len] = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cstr16;
+    use alloc::format;
+
+    #[test]
+    fn test_display_chars() {
+        let s = cstr16!("abc");
+        assert_eq!(format!("{}", s.display()), "abc");
+        assert_eq!(format!("{:5}|", s.display()), "abc  |");
+        assert_eq!(format!("{:>5}", s.display()), "  abc");
+        assert_eq!(format!("{:*^7}", s.display()), "**abc**");
+        assert_eq!(format!("{:.2}", s.display()), "ab");
+        assert_eq!(format!("{:>4.2}", s.display()), "  ab");
+        assert_eq!(format!("{:>4}", s), " abc");
+
+        let surrogate = unsafe { Char16::from_u16_unchecked(0xd800) };
+        let chars = [Char16::try_from('x').unwrap(), surrogate];
+        assert_eq!(
+            format!("{}", DisplayChars::new(chars.iter().copied())),
+            "x\u{fffd}"
+        );
+    }
+
+    #[test]
+    fn test_ucs2_writer() {
+        let mut buf = [0xffff; 6];
+        let mut writer = Ucs2Writer::new(&mut buf);
+        assert_eq!(writer.as_cstr16(), cstr16!(""));
+        assert_eq!(writer.capacity(), 5);
+
+        write!(writer, "a{}", 12).unwrap();
+        assert_eq!(writer.as_cstr16(), cstr16!("a12"));
+
+        // Characters outside the BMP and nulls are rejected.
+        assert!(writer.write_char('😀').is_err());
+        assert!(writer.write_char('\0').is_err());
+
+        // The buffer is full after two more characters.
+        assert!(writer.write_str("éxy").is_err());
+        assert_eq!(writer.len(), 5);
+        assert_eq!(writer.as_cstr16(), cstr16!("a12éx"));
+
+        writer.clear();
+        assert!(writer.is_empty());
+        writer.write_str("ok").unwrap();
+        assert_eq!(writer.into_cstr16(), cstr16!("ok"));
+
+        let mut empty = Ucs2Writer::new(&mut []);
+        assert!(empty.write_char('a').is_err());
+        assert_eq!(empty.as_cstr16(), cstr16!(""));
+    }
+}
@@ -5,15 +5,33 @@
 //! This module defines the basic data types that are used throughout uefi-rs
 
 use core::ffi::c_void;
+use core::fmt::{self, Debug, Formatter};
 use core::ptr::{self, NonNull};
 
 /// Opaque handle to an UEFI entity (protocol, image...), guaranteed to be non-null.
 ///
 /// If you need to have a nullable handle (for a custom UEFI FFI for example) use `Option<Handle>`.
-#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
+///
+/// If a label was registered for the handle with the [`labels`] module, the
+/// [`Debug`] output includes it.
+///
+/// [`labels`]: crate::labels
+#[derive(Clone, Copy, Hash, Eq, PartialEq, Ord, PartialOrd)]
 #[repr(transparent)]
 pub struct Handle(NonNull<c_void>);
 
+impl Debug for Handle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut tuple = f.debug_tuple("Handle");
+        tuple.field(&self.0);
+        #[cfg(feature = "alloc")]
+        crate::labels::with_label(*self, |label| {
+            tuple.field(&label);
+        });
+        tuple.finish()
+    }
+}
+
 impl Handle {
     /// Creates a new [`Handle`].
     ///
@@ -163,6 +181,9 @@ pub use chars::{Char8, Char16};
 #[macro_use]
 mod opaque;
 
+mod fmt16;
+pub use fmt16::{DisplayChars, Ucs2Writer};
+
 mod strs;
 pub use strs::{
     CStr8, CStr16, EqStrUntilNul, FromSliceWithNulError, FromStrWithBufError, PoolString,
@@ -4,6 +4,7 @@ use uefi_raw::Status;
 
 use super::UnalignedSlice;
 use super::chars::{Char8, Char16, NUL_8, NUL_16};
+use super::fmt16::DisplayChars;
 use crate::mem::PoolAllocation;
 use crate::polyfill::maybe_uninit_slice_assume_init_ref;
 use core::borrow::Borrow;
@@ -364,6 +365,34 @@ impl CStr16 {
         unsafe { Self::from_u16_with_nul_unchecked(slice::from_raw_parts(ptr, len + 1)) }
     }
 
+    /// Wraps a raw UEFI string like [`from_ptr`], but checks that it only
+    /// contains valid UCS-2 characters. Use this for strings provided by the
+    /// firmware.
+    ///
+    /// # Safety
+    ///
+    /// The same as for [`from_ptr`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FromSliceWithNulError::InvalidChar`] with the index of the
+    /// first surrogate in the string.
+    ///
+    /// [`from_ptr`]: Self::from_ptr
+    pub unsafe fn from_ptr_checked<'ptr>(
+        ptr: *const Char16,
+    ) -> Result<&'ptr Self, FromSliceWithNulError> {
+        let s = unsafe { Self::from_ptr(ptr) };
+        match s
+            .to_u16_slice()
+            .iter()
+            .position(|&c| Char16::is_surrogate(c))
+        {
+            Some(pos) => Err(FromSliceWithNulError::InvalidChar(pos)),
+            None => Ok(s),
+        }
+    }
+
     /// Creates a `&CStr16` from a u16 slice, stopping at the first nul character.
     ///
     /// # Errors
@@ -600,6 +629,16 @@ impl CStr16 {
         self.0.iter().all(|c| c.is_ascii())
     }
 
+    /// Returns an adapter to [`Display`] this string without allocating.
+    ///
+    /// This is the same as displaying the string itself, but the adapter
+    /// can be stored, and its type is shared with other sequences of
+    /// [`Char16`]s. See [`DisplayChars`].
+    #[must_use]
+    pub fn display(&self) -> DisplayChars<core::iter::Copied<slice::Iter<'_, Char16>>> {
+        DisplayChars::new(self.as_slice().iter().copied())
+    }
+
     /// Writes each [`Char16`] as a [`char`] (4 bytes long in Rust language) into the buffer.
     /// It is up to the implementer of [`core::fmt::Write`] to convert the char to a string
     /// with proper encoding/charset. For example, in the case of [`alloc::string::String`]
@@ -682,7 +721,7 @@ impl AsRef<Self> for CStr16 {
 }
 
 /// An iterator over the [`Char16`]s in a [`CStr16`].
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct CStr16Iter<'a> {
     inner: &'a CStr16,
     pos: usize,
@@ -709,10 +748,7 @@ impl fmt::Debug for CStr16 {
 
 impl fmt::Display for CStr16 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        for c in self.iter() {
-            <Char16 as fmt::Display>::fmt(c, f)?;
-        }
-        Ok(())
+        self.display().fmt(f)
     }
 }
 
@@ -872,6 +908,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_cstr16_from_ptr_checked() {
+        let valid: [u16; 3] = [97, 98, 0];
+        let s = unsafe { CStr16::from_ptr_checked(valid.as_ptr().cast()) };
+        assert_eq!(s, Ok(cstr16!("ab")));
+
+        let invalid: [u16; 3] = [97, 0xd800, 0];
+        let s = unsafe { CStr16::from_ptr_checked(invalid.as_ptr().cast()) };
+        assert_eq!(s, Err(FromSliceWithNulError::InvalidChar(1)));
+    }
+
     #[test]
     fn test_cstr16_from_char16_until_nul() {
         // Invalid: empty input.
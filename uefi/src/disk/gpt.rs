@@ -0,0 +1,356 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Parsing of GUID Partition Tables.
+//!
+//! A GPT disk has a header at LBA 1 and a backup header at its last LBA.
+//! Each header points to an array of [`GptPartitionEntry`], and protects
+//! itself and the array with CRC32 checksums. [`GptHeader::parse`] and
+//! [`GptHeader::entries`] validate both, and only operate on bytes, so they
+//! also work on disk images on the host:
+//!
+//! ```no_run
+//! use uefi::disk::gpt::GptHeader;
+//! use uefi::proto::media::partition::GptPartitionType;
+//!
+//! # fn read_blocks(lba: u64, count: usize) -> Vec<u8> { unimplemented!() }
+//! let block_size = 512;
+//! let header = GptHeader::parse(&read_blocks(1, 1)).unwrap();
+//! let array = read_blocks(header.partition_entry_lba, header.entry_array_blocks(block_size));
+//! for entry in header.entries(&array).unwrap() {
+//!     if { entry.partition_type_guid } != GptPartitionType::UNUSED_ENTRY {
+//!         let (start, end) = (entry.starting_lba, entry.ending_lba);
+//!         println!("{start}..={end}");
+//!     }
+//! }
+//! ```
+
+use crate::Guid;
+use crate::proto::media::partition::GptPartitionEntry;
+use crate::util::usize_from_u32;
+use core::fmt::{self, Display, Formatter};
+use core::ptr;
+use core::slice::ChunksExact;
+
+/// Signature of a GPT header.
+const SIGNATURE: [u8; 8] = *b"EFI PART";
+
+/// Size of the fields of the header defined by the UEFI specification.
+const HEADER_SIZE: usize = 92;
+
+/// Offset of the `HeaderCRC32` field of the header.
+const HEADER_CRC32_OFFSET: usize = 16;
+
+/// Error returned when parsing an invalid GPT header or partition entry
+/// array.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GptError {
+    /// The header doesn't start with the `EFI PART` signature.
+    InvalidSignature,
+    /// The `HeaderSize` field of the header is too small, or larger than
+    /// the block it was read from.
+    InvalidHeaderSize(u32),
+    /// The CRC32 of the header doesn't match its `HeaderCRC32` field.
+    HeaderCrcMismatch,
+    /// The `SizeOfPartitionEntry` field of the header isn't a power of two
+    /// of at least 128.
+    InvalidEntrySize(u32),
+    /// The partition entry array is smaller than described by the header.
+    EntriesTruncated,
+    /// The CRC32 of the partition entry array doesn't match the
+    /// `PartitionEntryArrayCRC32` field of the header.
+    EntriesCrcMismatch,
+}
+
+impl Display for GptError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidSignature => write!(f, "invalid GPT header signature"),
+            Self::InvalidHeaderSize(size) => write!(f, "invalid GPT header size {size}"),
+            Self::HeaderCrcMismatch => write!(f, "GPT header CRC32 mismatch"),
+            Self::InvalidEntrySize(size) => write!(f, "invalid GPT partition entry size {size}"),
+            Self::EntriesTruncated => write!(f, "truncated GPT partition entry array"),
+            Self::EntriesCrcMismatch => write!(f, "GPT partition entry array CRC32 mismatch"),
+        }
+    }
+}
+
+impl core::error::Error for GptError {}
+
+/// A validated GPT header.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct GptHeader {
+    /// Revision of the header format, `0x0001_0000` for UEFI 2.x.
+    pub revision: u32,
+    /// LBA of this header.
+    pub my_lba: u64,
+    /// LBA of the other header, the backup header for the primary one and
+    /// vice versa.
+    pub alternate_lba: u64,
+    /// First LBA usable by partitions.
+    pub first_usable_lba: u64,
+    /// Last LBA usable by partitions.
+    pub last_usable_lba: u64,
+    /// GUID identifying the disk.
+    pub disk_guid: Guid,
+    /// First LBA of the partition entry array.
+    pub partition_entry_lba: u64,
+    /// Number of entries of the partition entry array.
+    pub number_of_partition_entries: u32,
+    /// Size in bytes of each entry of the partition entry array.
+    pub size_of_partition_entry: u32,
+    /// CRC32 of the partition entry array.
+    pub partition_entry_array_crc32: u32,
+}
+
+impl GptHeader {
+    /// Parses the header at the start of `block`, the whole block holding
+    /// the header.
+    ///
+    /// # Errors
+    ///
+    /// * [`GptError::InvalidSignature`]: `block` doesn't start with a GPT
+    ///   header.
+    /// * [`GptError::InvalidHeaderSize`]: the size of the header is invalid.
+    /// * [`GptError::HeaderCrcMismatch`]: the header is corrupted.
+    pub fn parse(block: &[u8]) -> Result<Self, GptError> {
+        if !block.starts_with(&SIGNATURE) || block.len() < HEADER_CRC32_OFFSET {
+            return Err(GptError::InvalidSignature);
+        }
+        let u32_at =
+            |offset: usize| u32::from_le_bytes(block[offset..offset + 4].try_into().unwrap());
+        let u64_at =
+            |offset: usize| u64::from_le_bytes(block[offset..offset + 8].try_into().unwrap());
+
+        let header_size = u32_at(12);
+        let size = usize::try_from(header_size).unwrap_or(usize::MAX);
+        if size < HEADER_SIZE || size > block.len() {
+            return Err(GptError::InvalidHeaderSize(header_size));
+        }
+        // The CRC32 covers the header with its `HeaderCRC32` field zeroed.
+        let crc = Crc32::new()
+            .update(&block[..HEADER_CRC32_OFFSET])
+            .update(&[0; 4])
+            .update(&block[HEADER_CRC32_OFFSET + 4..size])
+            .finish();
+        if crc != u32_at(HEADER_CRC32_OFFSET) {
+            return Err(GptError::HeaderCrcMismatch);
+        }
+
+        Ok(Self {
+            revision: u32_at(8),
+            my_lba: u64_at(24),
+            alternate_lba: u64_at(32),
+            first_usable_lba: u64_at(40),
+            last_usable_lba: u64_at(48),
+            disk_guid: Guid::from_bytes(block[56..72].try_into().unwrap()),
+            partition_entry_lba: u64_at(72),
+            number_of_partition_entries: u32_at(80),
+            size_of_partition_entry: u32_at(84),
+            partition_entry_array_crc32: u32_at(88),
+        })
+    }
+
+    /// Returns the size in bytes of the partition entry array.
+    #[must_use]
+    pub const fn entry_array_size(&self) -> u64 {
+        self.number_of_partition_entries as u64 * self.size_of_partition_entry as u64
+    }
+
+    /// Returns the number of blocks of `block_size` bytes holding the
+    /// partition entry array.
+    #[must_use]
+    pub fn entry_array_blocks(&self, block_size: usize) -> usize {
+        let size = usize::try_from(self.entry_array_size()).unwrap_or(usize::MAX);
+        size.div_ceil(block_size)
+    }
+
+    /// Returns an iterator over the partition entry array at the start of
+    /// `array`, including unused entries, which have the
+    /// [`UNUSED_ENTRY`] type.
+    ///
+    /// # Errors
+    ///
+    /// * [`GptError::InvalidEntrySize`]: the size of the entries is invalid.
+    /// * [`GptError::EntriesTruncated`]: `array` is smaller than the
+    ///   partition entry array.
+    /// * [`GptError::EntriesCrcMismatch`]: the partition entry array is
+    ///   corrupted.
+    ///
+    /// [`UNUSED_ENTRY`]: crate::proto::media::partition::GptPartitionType::UNUSED_ENTRY
+    pub fn entries<'a>(&self, array: &'a [u8]) -> Result<GptEntries<'a>, GptError> {
+        let entry_size = self.size_of_partition_entry;
+        if entry_size < 128 || !entry_size.is_power_of_two() {
+            return Err(GptError::InvalidEntrySize(entry_size));
+        }
+        let array = usize::try_from(self.entry_array_size())
+            .ok()
+            .and_then(|size| array.get(..size))
+            .ok_or(GptError::EntriesTruncated)?;
+        if Crc32::new().update(array).finish() != self.partition_entry_array_crc32 {
+            return Err(GptError::EntriesCrcMismatch);
+        }
+        Ok(GptEntries {
+            chunks: array.chunks_exact(usize_from_u32(entry_size)),
+        })
+    }
+}
+
+/// Iterator over the entries of a partition entry array, returned by
+/// [`GptHeader::entries`].
+#[derive(Clone, Debug)]
+pub struct GptEntries<'a> {
+    chunks: ChunksExact<'a, u8>,
+}
+
+impl Iterator for GptEntries<'_> {
+    type Item = GptPartitionEntry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let chunk = self.chunks.next()?;
+        // SAFETY: entries are at least 128 bytes, the size of
+        // `GptPartitionEntry`, which is packed and valid for any bytes.
+        Some(unsafe { ptr::read_unaligned(chunk.as_ptr().cast()) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.chunks.size_hint()
+    }
+}
+
+impl ExactSizeIterator for GptEntries<'_> {}
+
+/// CRC32 as used by UEFI (ISO-HDLC, reflected polynomial `0xedb88320`).
+#[derive(Clone, Copy, Debug)]
+struct Crc32(u32);
+
+impl Crc32 {
+    const fn new() -> Self {
+        Self(!0)
+    }
+
+    fn update(mut self, data: &[u8]) -> Self {
+        for byte in data {
+            self.0 ^= u32::from(*byte);
+            for _ in 0..8 {
+                let mask = (self.0 & 1).wrapping_neg();
+                self.0 = (self.0 >> 1) ^ (0xedb8_8320 & mask);
+            }
+        }
+        self
+    }
+
+    const fn finish(self) -> u32 {
+        !self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guid;
+    use crate::proto::media::partition::GptPartitionType;
+
+    const DISK_GUID: Guid = guid!("a6e8ca5e-0e8f-4b6a-9a3c-6f2bb7f4e2d1");
+
+    /// Returns a block of 512 bytes with a header for `array`, holding
+    /// `count` entries of `size` bytes.
+    fn header(array: &[u8], count: u32, size: u32) -> [u8; 512] {
+        let mut block = [0; 512];
+        block[..8].copy_from_slice(&SIGNATURE);
+        block[8..12].copy_from_slice(&0x0001_0000u32.to_le_bytes());
+        block[12..16].copy_from_slice(&(HEADER_SIZE as u32).to_le_bytes());
+        block[24..32].copy_from_slice(&1u64.to_le_bytes());
+        block[32..40].copy_from_slice(&2047u64.to_le_bytes());
+        block[40..48].copy_from_slice(&34u64.to_le_bytes());
+        block[48..56].copy_from_slice(&2014u64.to_le_bytes());
+        block[56..72].copy_from_slice(&DISK_GUID.to_bytes());
+        block[72..80].copy_from_slice(&2u64.to_le_bytes());
+        block[80..84].copy_from_slice(&count.to_le_bytes());
+        block[84..88].copy_from_slice(&size.to_le_bytes());
+        let crc = Crc32::new().update(array).finish();
+        block[88..92].copy_from_slice(&crc.to_le_bytes());
+        let crc = Crc32::new().update(&block[..HEADER_SIZE]).finish();
+        block[16..20].copy_from_slice(&crc.to_le_bytes());
+        block
+    }
+
+    /// Returns a partition entry array of 4 entries of 128 bytes, with an
+    /// ESP as the second entry.
+    fn array() -> [u8; 512] {
+        let mut array = [0; 512];
+        let entry = &mut array[128..256];
+        entry[..16].copy_from_slice(&GptPartitionType::EFI_SYSTEM_PARTITION.0.to_bytes());
+        entry[16..32].copy_from_slice(&DISK_GUID.to_bytes());
+        entry[32..40].copy_from_slice(&34u64.to_le_bytes());
+        entry[40..48].copy_from_slice(&1057u64.to_le_bytes());
+        array
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(Crc32::new().update(b"123456789").finish(), 0xcbf4_3926);
+        assert_eq!(Crc32::new().update(b"").finish(), 0);
+    }
+
+    #[test]
+    fn test_parse() {
+        let array = array();
+        let header = GptHeader::parse(&header(&array, 4, 128)).unwrap();
+        assert_eq!(header.my_lba, 1);
+        assert_eq!(header.alternate_lba, 2047);
+        assert_eq!(header.disk_guid, DISK_GUID);
+        assert_eq!(header.entry_array_blocks(512), 1);
+
+        let entries = header.entries(&array).unwrap();
+        assert_eq!(entries.len(), 4);
+        let unused = GptPartitionType::UNUSED_ENTRY;
+        assert_eq!(
+            entries
+                .clone()
+                .filter(|entry| { entry.partition_type_guid } != unused)
+                .count(),
+            1
+        );
+        let esp = entries.clone().nth(1).unwrap();
+        assert_eq!(
+            { esp.partition_type_guid },
+            GptPartitionType::EFI_SYSTEM_PARTITION
+        );
+        assert_eq!(esp.num_blocks(), Some(1024));
+    }
+
+    #[test]
+    fn test_invalid() {
+        let array = array();
+        let block = header(&array, 4, 128);
+
+        assert_eq!(GptHeader::parse(&[0; 512]), Err(GptError::InvalidSignature));
+        assert_eq!(
+            GptHeader::parse(&block[..HEADER_SIZE - 1]),
+            Err(GptError::InvalidHeaderSize(HEADER_SIZE as u32))
+        );
+        let mut corrupted = block;
+        corrupted[40] ^= 1;
+        assert_eq!(
+            GptHeader::parse(&corrupted),
+            Err(GptError::HeaderCrcMismatch)
+        );
+
+        let header = GptHeader::parse(&block).unwrap();
+        assert_eq!(
+            header.entries(&array[..511]).err(),
+            Some(GptError::EntriesTruncated)
+        );
+        let mut corrupted = array;
+        corrupted[200] ^= 1;
+        assert_eq!(
+            header.entries(&corrupted).err(),
+            Some(GptError::EntriesCrcMismatch)
+        );
+        let header = GptHeader::parse(&self::header(&array, 4, 100)).unwrap();
+        assert_eq!(
+            header.entries(&array).err(),
+            Some(GptError::InvalidEntrySize(100))
+        );
+    }
+}
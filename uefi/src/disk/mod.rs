@@ -0,0 +1,8 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Higher-level helpers for disks, built on the storage pass-thru protocols,
+//! and parsers for on-disk structures.
+
+pub mod gpt;
+#[cfg(feature = "alloc")]
+pub mod smart;
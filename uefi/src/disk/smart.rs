@@ -0,0 +1,500 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Disk health screening with SMART data.
+//!
+//! ATA and NVMe devices both report health information, but in different
+//! formats: ATA devices report vendor-specific attributes compared against
+//! thresholds, while NVMe controllers report a fixed log page with critical
+//! warning flags. [`health`] reads whichever applies to a disk, and
+//! normalizes it into a [`HealthReport`]:
+//!
+//! ```no_run
+//! use uefi::boot;
+//! use uefi::disk::smart::{self, HealthStatus};
+//! use uefi::proto::media::block::BlockIO;
+//!
+//! for handle in boot::find_handles::<BlockIO>().unwrap() {
+//!     if let Ok(report) = smart::health(handle) {
+//!         if report.status != HealthStatus::Good {
+//!             log::warn!("{report}");
+//!         }
+//!     }
+//! }
+//! ```
+
+use crate::boot::{self, OpenProtocolAttributes, OpenProtocolParams, ScopedProtocol};
+use crate::proto::ProtocolPointer;
+use crate::proto::ata::identify::{AtaFeatures, AtaIdentifyData};
+use crate::proto::ata::pass_thru::AtaPassThru;
+use crate::proto::ata::smart::AtaSmartData;
+use crate::proto::device_path::{DevicePath, DevicePathNode};
+use crate::proto::nvme::identify::NvmeControllerData;
+use crate::proto::nvme::pass_thru::NvmePassThru;
+use crate::proto::nvme::smart::{NvmeCriticalWarning, NvmeSmartLog};
+use crate::{Handle, Result, Status};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+
+/// IDs of ATA attributes counting defective sectors.
+const ATA_SECTOR_ATTRIBUTES: [u8; 3] = [5, 197, 198];
+
+/// The interface a disk is connected through.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DiskInterface {
+    /// ATA, e.g. a SATA drive.
+    Ata,
+    /// NVM Express.
+    Nvme,
+}
+
+/// Overall health of a disk.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HealthStatus {
+    /// No problems are reported.
+    Good,
+    /// The disk is worn or has defects, but is not expected to fail soon.
+    Degraded,
+    /// The disk reports a critical warning, and may fail soon.
+    Failing,
+}
+
+bitflags::bitflags! {
+    /// Critical warnings reported by a disk.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct CriticalWarnings: u8 {
+        /// The spare capacity is below its threshold.
+        const SPARE_LOW = 1 << 0;
+        /// The temperature is outside of its thresholds.
+        const TEMPERATURE = 1 << 1;
+        /// Reliability is degraded. For ATA devices, this means a
+        /// pre-failure attribute reached its threshold, or the last
+        /// self-test failed.
+        const RELIABILITY_DEGRADED = 1 << 2;
+        /// The media is read-only.
+        const READ_ONLY = 1 << 3;
+        /// The volatile memory backup device has failed.
+        const BACKUP_FAILED = 1 << 4;
+    }
+}
+
+/// A health attribute of a disk.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HealthAttribute {
+    /// ATA attribute ID, or `None` for NVMe devices.
+    pub id: Option<u8>,
+    /// Name of the attribute.
+    pub name: &'static str,
+    /// Normalized value, where lower values are worse, if the attribute
+    /// has one.
+    pub value: Option<u8>,
+    /// Worst normalized value seen, if reported.
+    pub worst: Option<u8>,
+    /// Threshold of the normalized value, if reported.
+    pub threshold: Option<u8>,
+    /// Raw value.
+    pub raw: u128,
+    /// Whether the attribute is at or beyond its threshold.
+    pub is_failing: bool,
+}
+
+impl HealthAttribute {
+    const fn nvme(name: &'static str, raw: u128) -> Self {
+        Self {
+            id: None,
+            name,
+            value: None,
+            worst: None,
+            threshold: None,
+            raw,
+            is_failing: false,
+        }
+    }
+}
+
+/// Normalized health information of a disk, returned by [`health`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HealthReport {
+    /// Interface of the disk.
+    pub interface: DiskInterface,
+    /// Model number.
+    pub model: String,
+    /// Serial number.
+    pub serial_number: String,
+    /// Firmware revision.
+    pub firmware_revision: String,
+    /// Overall health.
+    pub status: HealthStatus,
+    /// Critical warnings.
+    pub warnings: CriticalWarnings,
+    /// Current temperature in degrees Celsius, if reported.
+    pub temperature: Option<i16>,
+    /// Number of power-on hours, if reported.
+    pub power_on_hours: Option<u64>,
+    /// Number of power cycles, if reported.
+    pub power_cycles: Option<u64>,
+    /// Estimate of the life used in percent, if reported.
+    pub percentage_used: Option<u8>,
+    /// All attributes reported by the disk.
+    pub attributes: Vec<HealthAttribute>,
+}
+
+impl HealthReport {
+    /// Creates a report from the identify data and SMART data of an ATA
+    /// device.
+    #[must_use]
+    pub fn from_ata(identify: &AtaIdentifyData, smart: &AtaSmartData) -> Self {
+        let mut warnings = CriticalWarnings::empty();
+        let mut degraded = false;
+        let mut attributes = Vec::new();
+        for attribute in smart.attributes() {
+            if attribute.is_failing() {
+                if attribute.is_prefailure() {
+                    warnings |= CriticalWarnings::RELIABILITY_DEGRADED;
+                }
+                if matches!(attribute.id, 190 | 194) {
+                    warnings |= CriticalWarnings::TEMPERATURE;
+                }
+                degraded = true;
+            }
+            if ATA_SECTOR_ATTRIBUTES.contains(&attribute.id) && attribute.raw_value() != 0 {
+                degraded = true;
+            }
+            attributes.push(HealthAttribute {
+                id: Some(attribute.id),
+                name: attribute.name().unwrap_or("Unknown"),
+                value: Some(attribute.value),
+                worst: Some(attribute.worst),
+                threshold: attribute.threshold,
+                raw: u128::from(attribute.raw_value()),
+                is_failing: attribute.is_failing(),
+            });
+        }
+        if smart.is_self_test_failed() {
+            warnings |= CriticalWarnings::RELIABILITY_DEGRADED;
+        }
+
+        // Some devices store additional data in the upper bytes of these.
+        let counter = |id| {
+            smart
+                .attribute(id)
+                .map(|attribute| attribute.raw_value() & 0xffff_ffff)
+        };
+        Self {
+            interface: DiskInterface::Ata,
+            model: identify.model_number().to_string(),
+            serial_number: identify.serial_number().to_string(),
+            firmware_revision: identify.firmware_revision().to_string(),
+            status: status(warnings, degraded),
+            warnings,
+            temperature: smart.temperature().map(i16::from),
+            power_on_hours: counter(9),
+            power_cycles: counter(12),
+            percentage_used: None,
+            attributes,
+        }
+    }
+
+    /// Creates a report from the Identify Controller data and SMART / Health
+    /// Information log page of an NVMe controller.
+    #[must_use]
+    pub fn from_nvme(controller: &NvmeControllerData, log: &NvmeSmartLog) -> Self {
+        let critical = log.critical_warning();
+        let mut warnings = CriticalWarnings::empty();
+        for (nvme, warning) in [
+            (NvmeCriticalWarning::SPARE, CriticalWarnings::SPARE_LOW),
+            (
+                NvmeCriticalWarning::TEMPERATURE,
+                CriticalWarnings::TEMPERATURE,
+            ),
+            (
+                NvmeCriticalWarning::RELIABILITY,
+                CriticalWarnings::RELIABILITY_DEGRADED,
+            ),
+            (NvmeCriticalWarning::READ_ONLY, CriticalWarnings::READ_ONLY),
+            (
+                NvmeCriticalWarning::PMR_READ_ONLY,
+                CriticalWarnings::READ_ONLY,
+            ),
+            (
+                NvmeCriticalWarning::VOLATILE_BACKUP,
+                CriticalWarnings::BACKUP_FAILED,
+            ),
+        ] {
+            if critical.contains(nvme) {
+                warnings |= warning;
+            }
+        }
+        let degraded = log.percentage_used() >= 100 || log.media_errors() != 0;
+
+        let attributes = Vec::from([
+            HealthAttribute {
+                value: Some(log.available_spare()),
+                threshold: Some(log.available_spare_threshold()),
+                is_failing: log.available_spare() < log.available_spare_threshold(),
+                ..HealthAttribute::nvme("Available Spare", u128::from(log.available_spare()))
+            },
+            HealthAttribute {
+                is_failing: log.percentage_used() >= 100,
+                ..HealthAttribute::nvme("Percentage Used", u128::from(log.percentage_used()))
+            },
+            HealthAttribute::nvme("Data Units Read", log.data_units_read()),
+            HealthAttribute::nvme("Data Units Written", log.data_units_written()),
+            HealthAttribute::nvme("Host Read Commands", log.host_read_commands()),
+            HealthAttribute::nvme("Host Write Commands", log.host_write_commands()),
+            HealthAttribute::nvme("Controller Busy Time", log.controller_busy_time()),
+            HealthAttribute::nvme("Power Cycles", log.power_cycles()),
+            HealthAttribute::nvme("Power-On Hours", log.power_on_hours()),
+            HealthAttribute::nvme("Unsafe Shutdowns", log.unsafe_shutdowns()),
+            HealthAttribute::nvme("Media Errors", log.media_errors()),
+            HealthAttribute::nvme("Error Log Entries", log.error_log_entries()),
+        ]);
+
+        let saturate = |value: u128| u64::try_from(value).unwrap_or(u64::MAX);
+        Self {
+            interface: DiskInterface::Nvme,
+            model: controller.model_number().to_string(),
+            serial_number: controller.serial_number().to_string(),
+            firmware_revision: controller.firmware_revision().to_string(),
+            status: status(warnings, degraded),
+            warnings,
+            temperature: Some(log.composite_temperature())
+                .filter(|&kelvin| kelvin != 0)
+                .map(|kelvin| kelvin as i16 - 273),
+            power_on_hours: Some(saturate(log.power_on_hours())),
+            power_cycles: Some(saturate(log.power_cycles())),
+            percentage_used: Some(log.percentage_used()),
+            attributes,
+        }
+    }
+}
+
+impl Display for HealthReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:?} {} (serial {}, firmware {}): {:?}",
+            self.interface, self.model, self.serial_number, self.firmware_revision, self.status
+        )?;
+        if !self.warnings.is_empty() {
+            writeln!(f, "  warnings: {:?}", self.warnings)?;
+        }
+        if let Some(temperature) = self.temperature {
+            writeln!(f, "  temperature: {temperature} °C")?;
+        }
+        for attribute in &self.attributes {
+            write!(f, "  {:<32} {}", attribute.name, attribute.raw)?;
+            if let Some(value) = attribute.value {
+                write!(f, " (value {value}")?;
+                if let Some(threshold) = attribute.threshold {
+                    write!(f, ", threshold {threshold}")?;
+                }
+                write!(f, ")")?;
+            }
+            if attribute.is_failing {
+                write!(f, " FAILING")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+const fn status(warnings: CriticalWarnings, degraded: bool) -> HealthStatus {
+    if !warnings.is_empty() {
+        HealthStatus::Failing
+    } else if degraded {
+        HealthStatus::Degraded
+    } else {
+        HealthStatus::Good
+    }
+}
+
+/// Reads the health information of the disk on `handle`, which must have a
+/// device path leading through an [`AtaPassThru`] or [`NvmePassThru`]
+/// controller, e.g. a handle with a [`BlockIO`] protocol for a whole disk.
+///
+/// For NVMe devices, the controller-wide information is returned.
+///
+/// # Errors
+/// - [`Status::UNSUPPORTED`] The disk is not connected through a supported
+///   controller, or it doesn't support SMART or has it disabled.
+/// - [`Status::NOT_FOUND`] The device is not found on its controller.
+/// - See [`AtaDevice::identify`], [`AtaDevice::smart_data`],
+///   [`NvmeNamespace::identify_controller`], and
+///   [`NvmeNamespace::smart_log`] for other errors.
+///
+/// [`BlockIO`]: crate::proto::media::block::BlockIO
+/// [`AtaDevice::identify`]: crate::proto::ata::pass_thru::AtaDevice::identify
+/// [`AtaDevice::smart_data`]: crate::proto::ata::pass_thru::AtaDevice::smart_data
+/// [`NvmeNamespace::identify_controller`]: crate::proto::nvme::pass_thru::NvmeNamespace::identify_controller
+/// [`NvmeNamespace::smart_log`]: crate::proto::nvme::pass_thru::NvmeNamespace::smart_log
+pub fn health(handle: Handle) -> Result<HealthReport> {
+    let device_path = get_protocol::<DevicePath>(handle)?;
+
+    let mut path = &*device_path;
+    if let Ok(controller) = boot::locate_device_path::<AtaPassThru>(&mut path) {
+        let node = first_node(path)?;
+        let ata = get_protocol::<AtaPassThru>(controller)?;
+        let mut device = ata
+            .iter_devices()
+            .find(|device| device.path_node().is_ok_and(|n| *n == *node))
+            .ok_or(Status::NOT_FOUND)?;
+        let identify = device.identify()?;
+        if !identify.features().contains(AtaFeatures::SMART_ENABLED) {
+            return Err(Status::UNSUPPORTED.into());
+        }
+        let smart = device.smart_data()?;
+        return Ok(HealthReport::from_ata(&identify, &smart));
+    }
+
+    let mut path = &*device_path;
+    let controller =
+        boot::locate_device_path::<NvmePassThru>(&mut path).map_err(|_| Status::UNSUPPORTED)?;
+    let node = first_node(path)?;
+    let nvme = get_protocol::<NvmePassThru>(controller)?;
+    if !nvme
+        .iter_namespaces()
+        .any(|namespace| namespace.path_node().is_ok_and(|n| *n == *node))
+    {
+        return Err(Status::NOT_FOUND.into());
+    }
+    let mut controller = nvme.controller();
+    let identify = controller.identify_controller()?;
+    let log = controller.smart_log()?;
+    Ok(HealthReport::from_nvme(&identify, &log))
+}
+
+/// Returns the node of the device below its controller.
+fn first_node(path: &DevicePath) -> Result<&DevicePathNode> {
+    path.node_iter()
+        .next()
+        .ok_or_else(|| Status::NOT_FOUND.into())
+}
+
+/// Opens `P` on `handle` without affecting drivers managing it.
+fn get_protocol<P: ProtocolPointer + ?Sized>(handle: Handle) -> Result<ScopedProtocol<P>> {
+    // SAFETY: the protocol is only used briefly, while the handle is not
+    // expected to go away.
+    unsafe {
+        boot::open_protocol::<P>(
+            OpenProtocolParams {
+                handle,
+                agent: boot::image_handle(),
+                controller: None,
+            },
+            OpenProtocolAttributes::GetProtocol,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn ata_identify() -> AtaIdentifyData {
+        let mut bytes = [0u8; 512];
+        bytes[54..58].copy_from_slice(b"EQUM");
+        bytes[164..166].copy_from_slice(&(0x4000u16 | 1).to_le_bytes());
+        bytes[170..172].copy_from_slice(&(0x4000u16 | 1).to_le_bytes());
+        AtaIdentifyData::parse(&bytes).unwrap()
+    }
+
+    fn ata_smart(entries: &[[u8; 11]], thresholds: &[[u8; 2]]) -> AtaSmartData {
+        let mut data = [0u8; 512];
+        let mut threshold_data = [0u8; 512];
+        for (i, entry) in entries.iter().enumerate() {
+            data[2 + i * 12..13 + i * 12].copy_from_slice(entry);
+        }
+        for (i, entry) in thresholds.iter().enumerate() {
+            threshold_data[2 + i * 12..4 + i * 12].copy_from_slice(entry);
+        }
+        AtaSmartData::parse(&data, Some(&threshold_data)).unwrap()
+    }
+
+    #[test]
+    fn test_ata_report() {
+        let identify = ata_identify();
+        assert_eq!(identify.model_number(), "QEMU");
+
+        let smart = ata_smart(
+            &[
+                [5, 0x33, 0, 100, 100, 0, 0, 0, 0, 0, 0],
+                [9, 0x32, 0, 99, 99, 0x10, 0, 0, 0, 0x20, 0],
+                [12, 0x32, 0, 99, 99, 7, 0, 0, 0, 0, 0],
+                [194, 0x22, 0, 70, 60, 30, 0, 15, 0, 45, 0],
+            ],
+            &[[5, 36], [194, 0]],
+        );
+        let report = HealthReport::from_ata(&identify, &smart);
+        assert_eq!(report.interface, DiskInterface::Ata);
+        assert_eq!(report.model, "QEMU");
+        assert_eq!(report.status, HealthStatus::Good);
+        assert!(report.warnings.is_empty());
+        assert_eq!(report.temperature, Some(30));
+        assert_eq!(report.power_on_hours, Some(0x10));
+        assert_eq!(report.power_cycles, Some(7));
+        assert_eq!(report.percentage_used, None);
+        assert_eq!(report.attributes.len(), 4);
+        assert_eq!(report.attributes[0].name, "Reallocated Sector Count");
+        assert_eq!(report.attributes[0].threshold, Some(36));
+
+        // Reallocated sectors degrade the disk.
+        let smart = ata_smart(&[[5, 0x33, 0, 100, 100, 8, 0, 0, 0, 0, 0]], &[[5, 36]]);
+        let report = HealthReport::from_ata(&identify, &smart);
+        assert_eq!(report.status, HealthStatus::Degraded);
+        assert!(report.warnings.is_empty());
+
+        // A failing pre-failure attribute is critical.
+        let smart = ata_smart(&[[5, 0x33, 0, 30, 30, 200, 0, 0, 0, 0, 0]], &[[5, 36]]);
+        let report = HealthReport::from_ata(&identify, &smart);
+        assert_eq!(report.status, HealthStatus::Failing);
+        assert_eq!(report.warnings, CriticalWarnings::RELIABILITY_DEGRADED);
+        assert!(report.attributes[0].is_failing);
+    }
+
+    #[test]
+    fn test_nvme_report() {
+        let mut bytes = vec![0u8; 4096];
+        bytes[4..24].copy_from_slice(b"uefi-rsNvmePassThru ");
+        bytes[24..33].copy_from_slice(b"QEMU NVMe");
+        let controller = NvmeControllerData::parse(&bytes).unwrap();
+
+        let mut bytes = [0u8; 512];
+        bytes[1..3].copy_from_slice(&313u16.to_le_bytes());
+        bytes[3] = 100;
+        bytes[4] = 10;
+        bytes[5] = 3;
+        bytes[112] = 12;
+        bytes[128] = 40;
+        let report = HealthReport::from_nvme(&controller, &NvmeSmartLog::parse(&bytes).unwrap());
+        assert_eq!(report.interface, DiskInterface::Nvme);
+        assert_eq!(report.serial_number, "uefi-rsNvmePassThru");
+        assert_eq!(report.status, HealthStatus::Good);
+        assert_eq!(report.temperature, Some(40));
+        assert_eq!(report.power_on_hours, Some(40));
+        assert_eq!(report.power_cycles, Some(12));
+        assert_eq!(report.percentage_used, Some(3));
+        assert!(!report.attributes[0].is_failing);
+
+        // Spare below threshold, and read-only media.
+        bytes[0] = 0x09;
+        bytes[3] = 5;
+        let report = HealthReport::from_nvme(&controller, &NvmeSmartLog::parse(&bytes).unwrap());
+        assert_eq!(report.status, HealthStatus::Failing);
+        assert_eq!(
+            report.warnings,
+            CriticalWarnings::SPARE_LOW | CriticalWarnings::READ_ONLY
+        );
+        assert!(report.attributes[0].is_failing);
+
+        // Worn out.
+        bytes[0] = 0;
+        bytes[3] = 100;
+        bytes[5] = 110;
+        let report = HealthReport::from_nvme(&controller, &NvmeSmartLog::parse(&bytes).unwrap());
+        assert_eq!(report.status, HealthStatus::Degraded);
+        assert_eq!(report.temperature, Some(40));
+    }
+}
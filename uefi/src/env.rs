@@ -0,0 +1,270 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Environment variables.
+//!
+//! This module provides a single API to read and write environment-style
+//! variables, typically used for tool configuration. Depending on the
+//! [`Backend`], variables are stored in one of two places:
+//!
+//! - When running under the UEFI Shell, the shell's environment is used via
+//!   the [`Shell`] protocol. Variables set this way are visible to the `set`
+//!   shell command and to other shell applications.
+//! - Otherwise, variables are stored as UEFI variables in the
+//!   [`SHELL_VARIABLE_VENDOR`] namespace. This is the namespace in which the
+//!   EDK2 shell persists its non-volatile variables, so non-volatile values
+//!   written without a shell are picked up by a shell started later.
+//!
+//! Use [`set_scoped`] to temporarily override a variable, for example while
+//! launching a child image; the previous state is restored when the returned
+//! [`ScopedVar`] is dropped.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use uefi::{cstr16, env};
+//!
+//! # fn example() -> uefi::Result {
+//! let verbose = env::var(cstr16!("verbose")).is_some();
+//!
+//! {
+//!     let _guard = env::set_scoped(cstr16!("path"), cstr16!("fs0:\\tools"))?;
+//!     // Launch a child image that reads `path` here.
+//! }
+//! // `path` has its previous value again.
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::boot::{self, ScopedProtocol};
+use crate::proto::shell::Shell;
+use crate::runtime::{self, VariableAttributes, VariableVendor};
+use crate::{CStr16, CString16, Result, Status, cstr16, guid};
+use alloc::vec::Vec;
+
+/// Vendor GUID under which variables are stored when no shell is available.
+///
+/// This matches the GUID used by the EDK2 shell for non-volatile shell
+/// variables (`gShellVariableGuid`).
+pub const SHELL_VARIABLE_VENDOR: VariableVendor =
+    VariableVendor(guid!("158def5a-f656-449c-b56e-3e2bf5baebcb"));
+
+/// Storage used for environment variables.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Backend {
+    /// Variables are stored in the environment of the UEFI Shell.
+    Shell,
+
+    /// Variables are stored as UEFI variables under
+    /// [`SHELL_VARIABLE_VENDOR`].
+    Variables,
+}
+
+/// Returns the backend used by the functions in this module.
+///
+/// [`Backend::Shell`] is used if the [`Shell`] protocol is available.
+#[must_use]
+pub fn backend() -> Backend {
+    if open_shell().is_some() {
+        Backend::Shell
+    } else {
+        Backend::Variables
+    }
+}
+
+fn open_shell() -> Option<ScopedProtocol<Shell>> {
+    let handle = boot::get_handle_for_protocol::<Shell>().ok()?;
+    boot::open_protocol_exclusive::<Shell>(handle).ok()
+}
+
+fn attributes(volatile: bool) -> VariableAttributes {
+    if volatile {
+        VariableAttributes::BOOTSERVICE_ACCESS
+    } else {
+        VariableAttributes::NON_VOLATILE | VariableAttributes::BOOTSERVICE_ACCESS
+    }
+}
+
+/// Converts the data of a UEFI variable holding a UCS-2 string into a
+/// [`CString16`]. The trailing null character is optional.
+fn string_from_variable_data(data: &[u8]) -> Option<CString16> {
+    if data.len() % 2 != 0 {
+        return None;
+    }
+    let mut chars: Vec<u16> = data
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect();
+    if chars.last() != Some(&0) {
+        chars.push(0);
+    }
+    CString16::try_from(chars).ok()
+}
+
+/// Gets the value and volatility of a variable.
+fn var_with_volatility(name: &CStr16) -> Option<(CString16, bool)> {
+    match open_shell() {
+        Some(shell) => match shell.var_ex(name) {
+            Ok((value, attributes)) => {
+                let volatile = !attributes.contains(VariableAttributes::NON_VOLATILE);
+                Some((value.into(), volatile))
+            }
+            // Shells older than 2.2 don't report the volatility.
+            Err(err) if err.status() == Status::UNSUPPORTED => {
+                shell.var(name).map(|value| (value.into(), true))
+            }
+            Err(_) => None,
+        },
+        None => {
+            let (data, attributes) =
+                runtime::get_variable_boxed(name, &SHELL_VARIABLE_VENDOR).ok()?;
+            let volatile = !attributes.contains(VariableAttributes::NON_VOLATILE);
+            Some((string_from_variable_data(&data)?, volatile))
+        }
+    }
+}
+
+/// Gets the value of the environment variable `name`.
+///
+/// Returns `None` if the variable does not exist or its value is not a
+/// valid string.
+#[must_use]
+pub fn var(name: &CStr16) -> Option<CString16> {
+    var_with_volatility(name).map(|(value, _)| value)
+}
+
+/// Sets the environment variable `name` to `value`.
+///
+/// Volatile variables are lost on reset. Non-volatile variables persist,
+/// either in the shell's variable store or in the UEFI variable store.
+///
+/// # Errors
+///
+/// * [`Status::INVALID_PARAMETER`]: `name` is empty.
+/// * [`Status::OUT_OF_RESOURCES`]: not enough storage is available to hold
+///   the variable.
+/// * [`Status::WRITE_PROTECTED`]: the variable is read-only.
+pub fn set_var(name: &CStr16, value: &CStr16, volatile: bool) -> Result {
+    if name.is_empty() {
+        return Err(Status::INVALID_PARAMETER.into());
+    }
+
+    match open_shell() {
+        Some(shell) => shell.set_var(name, value, volatile),
+        None => runtime::set_variable(
+            name,
+            &SHELL_VARIABLE_VENDOR,
+            attributes(volatile),
+            value.as_bytes(),
+        )
+        .map_err(Into::into),
+    }
+}
+
+/// Removes the environment variable `name`.
+///
+/// Removing a variable that does not exist is not an error.
+///
+/// # Errors
+///
+/// * [`Status::WRITE_PROTECTED`]: the variable is read-only.
+pub fn remove_var(name: &CStr16) -> Result {
+    match open_shell() {
+        // The shell deletes a variable when setting it to an empty value.
+        Some(shell) => shell.set_var(name, cstr16!(""), true),
+        None => match runtime::delete_variable(name, &SHELL_VARIABLE_VENDOR) {
+            Err(err) if err.status() == Status::NOT_FOUND => Ok(()),
+            result => result.map_err(Into::into),
+        },
+    }
+}
+
+/// Returns the names and values of all environment variables.
+///
+/// Variables whose value is not a valid string are skipped.
+#[must_use]
+pub fn vars() -> Vec<(CString16, CString16)> {
+    match open_shell() {
+        Some(shell) => shell
+            .vars()
+            .filter_map(|(name, value)| Some((name.into(), value?.into())))
+            .collect(),
+        None => runtime::variable_keys()
+            .filter_map(|key| key.ok())
+            .filter(|key| key.vendor == SHELL_VARIABLE_VENDOR)
+            .filter_map(|key| {
+                let value = var(&key.name)?;
+                Some((key.name, value))
+            })
+            .collect(),
+    }
+}
+
+/// Sets the volatile environment variable `name` to `value` until the
+/// returned guard is dropped.
+///
+/// When the [`ScopedVar`] is dropped, the variable is restored to its
+/// previous value and volatility, or removed if it did not exist before.
+/// Shells older than version 2.2 don't report the volatility of variables,
+/// so the previous value is restored as a volatile variable.
+///
+/// # Errors
+///
+/// See [`set_var`].
+pub fn set_scoped(name: &CStr16, value: &CStr16) -> Result<ScopedVar> {
+    let previous = var_with_volatility(name);
+    set_var(name, value, true)?;
+    Ok(ScopedVar {
+        name: name.into(),
+        previous,
+    })
+}
+
+/// Guard returned by [`set_scoped`] that restores an environment variable
+/// when dropped.
+#[derive(Debug)]
+pub struct ScopedVar {
+    name: CString16,
+    previous: Option<(CString16, bool)>,
+}
+
+impl ScopedVar {
+    /// Returns the name of the overridden variable.
+    #[must_use]
+    pub fn name(&self) -> &CStr16 {
+        &self.name
+    }
+
+    /// Returns the value the variable had before it was overridden.
+    #[must_use]
+    pub fn previous(&self) -> Option<&CStr16> {
+        self.previous.as_ref().map(|(value, _)| value.as_ref())
+    }
+}
+
+impl Drop for ScopedVar {
+    fn drop(&mut self) {
+        // Errors are ignored since they can't be propagated from `drop`.
+        let _ = match &self.previous {
+            Some((value, volatile)) => set_var(&self.name, value, *volatile),
+            None => remove_var(&self.name),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_string_from_variable_data() {
+        let expected = CString16::try_from("ab").unwrap();
+        assert_eq!(
+            string_from_variable_data(b"a\0b\0\0\0"),
+            Some(expected.clone())
+        );
+        assert_eq!(string_from_variable_data(b"a\0b\0"), Some(expected));
+        assert_eq!(string_from_variable_data(b""), Some(CString16::new()));
+        assert_eq!(string_from_variable_data(b"a\0b"), None);
+        assert_eq!(string_from_variable_data(b"a\0\0\0b\0"), None);
+    }
+}
@@ -1,5 +1,6 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use crate::ErrorKind;
 use crate::fs::{PathBuf, PathError};
 use alloc::string::FromUtf8Error;
 use core::fmt::{self, Debug, Display, Formatter};
@@ -17,6 +18,29 @@ pub enum Error {
     Utf8Encoding(FromUtf8Error),
 }
 
+impl Error {
+    /// Returns the general category of the error.
+    #[must_use]
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Io(IoError {
+                context: IoErrorContext::AlreadyExists,
+                ..
+            }) => ErrorKind::AlreadyExists,
+            Self::Io(err) => err.uefi_error.kind(),
+            Self::Path(_) => ErrorKind::InvalidInput,
+            Self::Utf8Encoding(_) => ErrorKind::InvalidData,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<Error> for std::io::Error {
+    fn from(err: Error) -> Self {
+        Self::new(err.kind().into(), err)
+    }
+}
+
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
@@ -70,6 +94,8 @@ pub enum IoErrorContext {
     /// The path exists but does not correspond to a file when a file was
     /// expected.
     NotAFile,
+    /// The path exists but a new file was expected.
+    AlreadyExists,
 }
 
 impl Display for IoErrorContext {
@@ -85,6 +111,7 @@ impl Display for IoErrorContext {
             Self::WriteFailure => "failed to write file",
             Self::NotADirectory => "expected a directory",
             Self::NotAFile => "expected a file",
+            Self::AlreadyExists => "file already exists",
         };
         write!(f, "{s}")
     }
@@ -2,14 +2,16 @@
 
 //! Module for [`FileSystem`].
 
-use crate::Status;
 use crate::fs::*;
+use crate::runtime::Time;
+use crate::{CStr16, CString16, Status, cstr16};
 use alloc::boxed::Box;
 use alloc::string::String;
 use alloc::vec;
 use alloc::vec::Vec;
-use core::fmt;
 use core::fmt::{Debug, Formatter};
+use core::time::Duration;
+use core::{fmt, mem};
 use uefi::boot::ScopedProtocol;
 
 /// Return type for public [`FileSystem`] operations.
@@ -135,7 +137,7 @@ impl FileSystem {
                 })
             })?;
 
-            remaining_size -= u64::try_from(num_bytes_read).unwrap();
+            remaining_size = remaining_size.saturating_sub(u64::try_from(num_bytes_read).unwrap());
         }
 
         dest.flush().map_err(|err| {
@@ -220,7 +222,16 @@ impl FileSystem {
             })
         })?;
 
-        let mut vec = vec![0; info.file_size() as usize];
+        // The file size is 64-bit, and may not fit in memory on 32-bit
+        // targets.
+        let size = usize::try_from(info.file_size()).map_err(|_| {
+            Error::Io(IoError {
+                path: path.to_path_buf(),
+                context: IoErrorContext::ReadFailure,
+                uefi_error: Status::OUT_OF_RESOURCES.into(),
+            })
+        })?;
+        let mut vec = vec![0; size];
         let read_bytes = file.read(vec.as_mut_slice()).map_err(|err| {
             Error::Io(IoError {
                 path: path.to_path_buf(),
@@ -230,13 +241,83 @@ impl FileSystem {
         })?;
 
         // we read the whole file at once!
-        if read_bytes != info.file_size() as usize {
-            log::error!("Did only read {}/{} bytes", info.file_size(), read_bytes);
+        if read_bytes != size {
+            log::error!("Did only read {}/{} bytes", read_bytes, size);
         }
 
         Ok(vec)
     }
 
+    /// Opens the regular file at `path` with the given [`OpenOptions`].
+    ///
+    /// This gives access to the underlying [`UefiRegularFile`] for reading
+    /// and writing at arbitrary positions.
+    ///
+    /// # Errors
+    ///
+    /// * [`IoErrorContext::OpenError`] with [`Status::INVALID_PARAMETER`]:
+    ///   the options are inconsistent, e.g. truncating without write access.
+    /// * [`IoErrorContext::AlreadyExists`] with [`Status::ACCESS_DENIED`]:
+    ///   `create_new` is set and the file exists. The error is classified as
+    ///   [`ErrorKind::AlreadyExists`].
+    /// * [`IoErrorContext::NotAFile`]: the path is a directory.
+    /// * [`IoErrorContext::WriteFailure`]: the file could not be truncated.
+    ///
+    /// UEFI has no exclusive create mode, so `create_new` checks whether the
+    /// file exists before creating it. This is not atomic: a file created in
+    /// between by someone else is opened instead.
+    ///
+    /// [`ErrorKind::AlreadyExists`]: crate::ErrorKind::AlreadyExists
+    pub fn open_file(
+        &mut self,
+        path: impl AsRef<Path>,
+        options: &OpenOptions,
+    ) -> FileSystemResult<UefiRegularFile> {
+        let path = path.as_ref();
+        let io_error = |context, uefi_error| {
+            Error::Io(IoError {
+                path: path.to_path_buf(),
+                context,
+                uefi_error,
+            })
+        };
+
+        if !options.is_valid() {
+            return Err(io_error(
+                IoErrorContext::OpenError,
+                Status::INVALID_PARAMETER.into(),
+            ));
+        }
+        if options.create_new && self.try_exists(path)? {
+            return Err(io_error(
+                IoErrorContext::AlreadyExists,
+                Status::ACCESS_DENIED.into(),
+            ));
+        }
+
+        let mode = if options.create || options.create_new {
+            UefiFileMode::CreateReadWrite
+        } else if options.write || options.append {
+            UefiFileMode::ReadWrite
+        } else {
+            UefiFileMode::Read
+        };
+        let mut file = self
+            .open(path, mode, false)?
+            .into_regular_file()
+            .ok_or_else(|| io_error(IoErrorContext::NotAFile, Status::INVALID_PARAMETER.into()))?;
+
+        if options.truncate {
+            file.set_len(0)
+                .map_err(|err| io_error(IoErrorContext::WriteFailure, err))?;
+        }
+        if options.append {
+            file.set_position(UefiRegularFile::END_OF_FILE)
+                .map_err(|err| io_error(IoErrorContext::OpenError, err))?;
+        }
+        Ok(file)
+    }
+
     /// Returns an iterator over the entries within a directory.
     pub fn read_dir(&mut self, path: impl AsRef<Path>) -> FileSystemResult<UefiDirectoryIter> {
         let path = path.as_ref();
@@ -394,6 +475,72 @@ impl FileSystem {
         Ok(())
     }
 
+    /// Starts watching the directory at `path` for added, removed, and
+    /// modified entries, which are checked for every `interval`. See
+    /// [`DirWatcher`].
+    pub fn watch(
+        &mut self,
+        path: impl AsRef<Path>,
+        interval: Duration,
+    ) -> FileSystemResult<DirWatcher> {
+        DirWatcher::new(self, path.as_ref(), interval)
+    }
+
+    /// Writes a slice as the entire contents of a file, such that the file
+    /// contains either its old or its new contents if the system loses power
+    /// in between.
+    ///
+    /// The content is first written to a temporary file with `.tmp` appended
+    /// to the name, and flushed to the device. The original file is then
+    /// deleted, and the temporary file is renamed to the original name with
+    /// `SetInfo()`, which changes only the directory entry.
+    ///
+    /// UEFI has no atomic replace operation, so there is a short window
+    /// between deleting the original file and renaming the temporary file.
+    /// If the file is missing after a power loss, the temporary file holds
+    /// its complete new contents. A leftover temporary file next to an
+    /// existing file is incomplete, and is overwritten by the next call.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::write`] and [`Self::remove_file`]. Renaming the temporary
+    /// file fails with [`IoErrorContext::WriteFailure`].
+    pub fn write_atomic(
+        &mut self,
+        path: impl AsRef<Path>,
+        content: impl AsRef<[u8]>,
+    ) -> FileSystemResult<()> {
+        let path = path.as_ref();
+        validate_path(path)?;
+        let name = path.components().last().ok_or_else(|| {
+            Error::Io(IoError {
+                path: path.to_path_buf(),
+                context: IoErrorContext::NotAFile,
+                uefi_error: Status::INVALID_PARAMETER.into(),
+            })
+        })?;
+
+        let mut tmp_path = CString16::from(path.to_cstr16());
+        tmp_path.push_str(cstr16!(".tmp"));
+        let tmp_path = PathBuf::from(tmp_path);
+
+        self.write(&tmp_path, content)?;
+        if self.try_exists(path)? {
+            self.remove_file(path)?;
+        }
+
+        let mut file = self.open_file(&tmp_path, OpenOptions::new().write(true))?;
+        set_file_name(&mut file, &name)
+            .and_then(|()| file.flush())
+            .map_err(|err| {
+                Error::Io(IoError {
+                    path: tmp_path.clone(),
+                    context: IoErrorContext::WriteFailure,
+                    uefi_error: err,
+                })
+            })
+    }
+
     /// Opens a fresh handle to the root directory of the volume.
     fn open_root(&mut self) -> FileSystemResult<UefiDirectoryHandle> {
         self.0.open_volume().map_err(|err| {
@@ -441,6 +588,26 @@ impl FileSystem {
     }
 }
 
+/// Renames `file` to `name` within its directory.
+fn set_file_name(file: &mut UefiRegularFile, name: &CStr16) -> crate::Result {
+    let info = file.get_boxed_info::<UefiFileInfo>()?;
+    // Room for the new name, and for aligning the buffer.
+    let size = mem::size_of_val(&*info) + mem::size_of_val(name.as_slice_with_nul()) + 8;
+    let mut storage = vec![0; size];
+    let new_info = UefiFileInfo::new(
+        &mut storage,
+        info.file_size(),
+        info.physical_size(),
+        Time::invalid(),
+        Time::invalid(),
+        Time::invalid(),
+        info.attribute(),
+        name,
+    )
+    .map_err(|_| Status::BUFFER_TOO_SMALL)?;
+    file.set_info(new_info)
+}
+
 impl Debug for FileSystem {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         let ptr: *const _ = &self.0;
@@ -2,6 +2,10 @@
 
 mod error;
 mod fs;
+mod open_options;
+mod watch;
 
 pub use error::*;
 pub use fs::*;
+pub use open_options::*;
+pub use watch::*;
@@ -0,0 +1,144 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Module for [`OpenOptions`].
+
+use super::{FileSystem, FileSystemResult};
+use crate::fs::Path;
+use crate::proto::media::file::RegularFile;
+
+/// Options for opening a file with [`FileSystem::open_file`], close to
+/// `std::fs::OpenOptions`.
+///
+/// UEFI only knows three open modes: read, read/write, and create. The
+/// options are mapped to these as follows:
+///
+/// - [`write`], [`append`], and [`truncate`] open the file for reading and
+///   writing, as UEFI has no write-only mode.
+/// - [`create`] and [`create_new`] open the file in create mode.
+/// - [`create_new`] checks for an existing file before opening it. This is
+///   not atomic: a file created in between by someone else is opened.
+/// - [`truncate`] sets the file size to zero with `SetInfo()` after opening.
+/// - [`append`] moves the position to the end of the file after opening.
+///   UEFI has no append mode, so seeking elsewhere before writing overwrites
+///   existing data.
+///
+/// ```no_run
+/// use uefi::cstr16;
+/// use uefi::fs::{FileSystem, OpenOptions};
+///
+/// fn log_line(fs: &mut FileSystem, line: &[u8]) {
+///     let mut file = OpenOptions::new()
+///         .append(true)
+///         .create(true)
+///         .open(fs, cstr16!("\\log.txt"))
+///         .expect("failed to open log");
+///     file.write(line).expect("failed to write log");
+/// }
+/// ```
+///
+/// [`write`]: Self::write
+/// [`append`]: Self::append
+/// [`truncate`]: Self::truncate
+/// [`create`]: Self::create
+/// [`create_new`]: Self::create_new
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct OpenOptions {
+    pub(super) read: bool,
+    pub(super) write: bool,
+    pub(super) append: bool,
+    pub(super) truncate: bool,
+    pub(super) create: bool,
+    pub(super) create_new: bool,
+}
+
+impl OpenOptions {
+    /// Creates a blank set of options, with all options set to `false`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the option for read access.
+    pub const fn read(&mut self, read: bool) -> &mut Self {
+        self.read = read;
+        self
+    }
+
+    /// Sets the option for write access.
+    pub const fn write(&mut self, write: bool) -> &mut Self {
+        self.write = write;
+        self
+    }
+
+    /// Sets the option for appending to the end of the file. Implies write
+    /// access.
+    pub const fn append(&mut self, append: bool) -> &mut Self {
+        self.append = append;
+        self
+    }
+
+    /// Sets the option for truncating the file to zero bytes. Requires
+    /// write access.
+    pub const fn truncate(&mut self, truncate: bool) -> &mut Self {
+        self.truncate = truncate;
+        self
+    }
+
+    /// Sets the option to create the file if it doesn't exist. Requires
+    /// write or append access.
+    pub const fn create(&mut self, create: bool) -> &mut Self {
+        self.create = create;
+        self
+    }
+
+    /// Sets the option to create a new file, failing if it already exists.
+    /// Requires write or append access.
+    ///
+    /// The check for an existing file is not atomic; see
+    /// [`FileSystem::open_file`].
+    pub const fn create_new(&mut self, create_new: bool) -> &mut Self {
+        self.create_new = create_new;
+        self
+    }
+
+    /// Opens the file at `path` on `fs` with these options. See
+    /// [`FileSystem::open_file`].
+    pub fn open(
+        &self,
+        fs: &mut FileSystem,
+        path: impl AsRef<Path>,
+    ) -> FileSystemResult<RegularFile> {
+        fs.open_file(path, self)
+    }
+
+    /// Returns whether the options are consistent: creating and truncating
+    /// require write access, and appending excludes truncating.
+    pub(super) const fn is_valid(&self) -> bool {
+        let write = self.write || self.append;
+        (self.read || write)
+            && (write || !(self.truncate || self.create || self.create_new))
+            && !(self.append && self.truncate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid() {
+        assert!(!OpenOptions::new().is_valid());
+        assert!(OpenOptions::new().read(true).is_valid());
+        assert!(OpenOptions::new().append(true).is_valid());
+        assert!(
+            OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .is_valid()
+        );
+        assert!(!OpenOptions::new().read(true).truncate(true).is_valid());
+        assert!(!OpenOptions::new().read(true).create_new(true).is_valid());
+        assert!(!OpenOptions::new().append(true).truncate(true).is_valid());
+    }
+}
@@ -0,0 +1,200 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Module for [`DirWatcher`].
+
+use super::{Error, FileSystem, FileSystemResult, IoError, IoErrorContext};
+use crate::Event;
+use crate::boot::{self, EventType, TimerTrigger, Tpl};
+use crate::fs::{COMMON_SKIP_DIRS, Path, PathBuf};
+use crate::proto::media::file::FileAttribute;
+use crate::runtime::Time;
+use crate::{CString16, ResultExt};
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::time::Duration;
+
+/// A change to an entry of a directory watched by a [`DirWatcher`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DirChange {
+    /// The entry was created.
+    Added(CString16),
+    /// The entry was deleted.
+    Removed(CString16),
+    /// The size, modification time, or attributes of the entry changed.
+    Modified(CString16),
+}
+
+impl DirChange {
+    /// Returns the name of the changed entry within the watched directory.
+    #[must_use]
+    pub const fn name(&self) -> &CString16 {
+        match self {
+            Self::Added(name) | Self::Removed(name) | Self::Modified(name) => name,
+        }
+    }
+}
+
+/// Metadata of a directory entry that is compared between snapshots.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct EntryState {
+    size: u64,
+    modification_time: Time,
+    attribute: FileAttribute,
+}
+
+/// Polls a directory for added, removed, and modified entries. Created with
+/// [`FileSystem::watch`].
+///
+/// UEFI has no file change notifications, so the watcher keeps a snapshot of
+/// the metadata of the directory's entries, and compares it with the current
+/// contents of the directory at an interval driven by a periodic timer event.
+/// Only the direct entries of the directory are compared, not the contents of
+/// subdirectories.
+///
+/// A change is detected if the size, modification time, or attributes of an
+/// entry differ. Rewriting a file with contents of the same size within the
+/// timestamp resolution of the file system, e.g. two seconds on FAT, is not
+/// detected.
+///
+/// ```no_run
+/// use core::time::Duration;
+/// use uefi::cstr16;
+/// use uefi::fs::{DirChange, FileSystem, FileSystemResult};
+///
+/// fn wait_for_driver(fs: &mut FileSystem) -> FileSystemResult<()> {
+///     let mut watcher = fs.watch(cstr16!("\\drivers"), Duration::from_millis(500))?;
+///     loop {
+///         for change in watcher.wait(fs)? {
+///             if let DirChange::Added(name) = change {
+///                 if name == cstr16!("driver.efi") {
+///                     return Ok(());
+///                 }
+///             }
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct DirWatcher {
+    path: PathBuf,
+    timer: Event,
+    entries: BTreeMap<CString16, EntryState>,
+}
+
+impl DirWatcher {
+    /// Takes the first snapshot of the directory at `path`, and starts the
+    /// timer.
+    pub(super) fn new(
+        fs: &mut FileSystem,
+        path: &Path,
+        interval: Duration,
+    ) -> FileSystemResult<Self> {
+        let entries = snapshot(fs, path)?;
+        let ticks = u64::try_from(interval.as_nanos() / 100).unwrap_or(u64::MAX);
+        // SAFETY: the event has no notification function.
+        let timer = unsafe { boot::create_event(EventType::TIMER, Tpl::APPLICATION, None, None) }
+            .and_then(
+                |timer| match boot::set_timer(&timer, TimerTrigger::Periodic(ticks)) {
+                    Ok(()) => Ok(timer),
+                    Err(err) => {
+                        let _ = boot::close_event(timer);
+                        Err(err)
+                    }
+                },
+            )
+            .map_err(|err| read_error(path, err))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            timer,
+            entries,
+        })
+    }
+
+    /// Returns the path of the watched directory.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Compares the directory with the last snapshot right away, and returns
+    /// the changes since, sorted by name. An empty vector means that nothing
+    /// changed.
+    pub fn poll(&mut self, fs: &mut FileSystem) -> FileSystemResult<Vec<DirChange>> {
+        let entries = snapshot(fs, &self.path)?;
+        let mut changes = Vec::new();
+        for (name, state) in &entries {
+            match self.entries.get(name) {
+                None => changes.push(DirChange::Added(name.clone())),
+                Some(old_state) if old_state != state => {
+                    changes.push(DirChange::Modified(name.clone()));
+                }
+                Some(_) => {}
+            }
+        }
+        changes.extend(
+            self.entries
+                .keys()
+                .filter(|name| !entries.contains_key(*name))
+                .map(|name| DirChange::Removed(name.clone())),
+        );
+        changes.sort_by(|a, b| a.name().cmp(b.name()));
+        self.entries = entries;
+        Ok(changes)
+    }
+
+    /// Waits for the directory to change, and returns the changes. The
+    /// directory is compared with the last snapshot each time the timer
+    /// fires.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`IoErrorContext::ReadFailure`] if the directory can't be
+    /// read, or waiting for the timer fails. The latter happens if this is
+    /// called at a TPL other than [`Tpl::APPLICATION`].
+    pub fn wait(&mut self, fs: &mut FileSystem) -> FileSystemResult<Vec<DirChange>> {
+        // SAFETY: the event is only closed when the watcher is dropped.
+        let mut events = [unsafe { self.timer.unsafe_clone() }];
+        loop {
+            boot::wait_for_event(&mut events)
+                .discard_errdata()
+                .map_err(|err| read_error(&self.path, err))?;
+            let changes = self.poll(fs)?;
+            if !changes.is_empty() {
+                return Ok(changes);
+            }
+        }
+    }
+}
+
+impl Drop for DirWatcher {
+    fn drop(&mut self) {
+        // SAFETY: the event is not used after this.
+        let _ = boot::close_event(unsafe { self.timer.unsafe_clone() });
+    }
+}
+
+fn read_error(path: &Path, err: crate::Error) -> Error {
+    Error::Io(IoError {
+        path: path.to_path_buf(),
+        context: IoErrorContext::ReadFailure,
+        uefi_error: err,
+    })
+}
+
+/// Reads the metadata of all entries of the directory at `path`.
+fn snapshot(fs: &mut FileSystem, path: &Path) -> FileSystemResult<BTreeMap<CString16, EntryState>> {
+    let mut entries = BTreeMap::new();
+    for info in fs.read_dir(path)? {
+        let info = info.map_err(|err| read_error(path, err))?;
+        if COMMON_SKIP_DIRS.contains(&info.file_name()) {
+            continue;
+        }
+        let state = EntryState {
+            size: info.file_size(),
+            modification_time: *info.modification_time(),
+            attribute: info.attribute(),
+        };
+        entries.insert(CString16::from(info.file_name()), state);
+    }
+    Ok(entries)
+}
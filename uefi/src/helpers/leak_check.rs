@@ -0,0 +1,322 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Detection of resources leaked by the application.
+//!
+//! With the `leak_check` feature, allocations made through the boot
+//! services wrappers of this crate (see [`mem::trace`]) and events created
+//! through [`boot`] are recorded until they are freed or closed.
+//! [`check_leaks`] reports the ones still alive, together with the
+//! protocols the image still has open, which it gets from the firmware.
+//! The [`entry`] macro calls it when the entry function returns.
+//!
+//! Resources are recorded in fixed-size tables, so that recording doesn't
+//! allocate. Resources that don't fit, or that are created while another
+//! one is being recorded, e.g. in an event notification function, are
+//! counted in [`LeakReport::untracked`].
+//!
+//! [`entry`]: crate::entry
+//! [`mem::trace`]: crate::mem::trace
+
+use crate::Result;
+use crate::boot::{self, SearchType};
+use crate::mem::trace::AllocEvent;
+use crate::sync::SpinLock;
+use core::ffi::c_void;
+use core::fmt::{self, Display, Formatter};
+use core::panic::Location;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// Number of live allocations that can be tracked.
+const MAX_ALLOCATIONS: usize = 4096;
+
+/// Number of live events that can be tracked.
+const MAX_EVENTS: usize = 256;
+
+/// `EFI_OPEN_PROTOCOL_BY_HANDLE_PROTOCOL`, used by `HandleProtocol`.
+const BY_HANDLE_PROTOCOL: u32 = 0x01;
+
+/// `EFI_OPEN_PROTOCOL_TEST_PROTOCOL`, which doesn't actually open anything.
+const TEST_PROTOCOL: u32 = 0x04;
+
+/// Key of a slot that held an entry that was removed.
+const REMOVED: usize = usize::MAX;
+
+/// A live resource.
+#[derive(Clone, Copy, Debug)]
+struct Entry {
+    /// Address of the resource, 0 if the slot is empty, or [`REMOVED`].
+    key: usize,
+    /// Size in bytes of an allocation.
+    size: usize,
+    location: Option<&'static Location<'static>>,
+}
+
+impl Entry {
+    const EMPTY: Self = Self {
+        key: 0,
+        size: 0,
+        location: None,
+    };
+
+    /// Returns whether the slot holds an entry.
+    const fn is_live(&self) -> bool {
+        !matches!(self.key, 0 | REMOVED)
+    }
+}
+
+/// Hash table of live resources, with linear probing.
+#[derive(Debug)]
+struct Table<const N: usize> {
+    entries: [Entry; N],
+}
+
+impl<const N: usize> Table<N> {
+    const fn new() -> Self {
+        Self {
+            entries: [Entry::EMPTY; N],
+        }
+    }
+
+    /// Returns the slots to probe for `key`.
+    fn probe(key: usize) -> impl Iterator<Item = usize> {
+        // Resources are at least 8-byte aligned, so ignore the low bits.
+        let start = (key >> 3).wrapping_mul(0x9e37_79b9) % N;
+        (start..N).chain(0..start)
+    }
+
+    /// Inserts an entry, returning `false` if the table is full.
+    fn insert(&mut self, entry: Entry) -> bool {
+        for i in Self::probe(entry.key) {
+            if !self.entries[i].is_live() {
+                self.entries[i] = entry;
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Removes the entry for `key`, if any.
+    fn remove(&mut self, key: usize) {
+        for i in Self::probe(key) {
+            match self.entries[i].key {
+                0 => return,
+                k if k == key => {
+                    self.entries[i].key = REMOVED;
+                    return;
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+static ALLOCATIONS: SpinLock<Table<MAX_ALLOCATIONS>> = SpinLock::new(Table::new());
+static EVENTS: SpinLock<Table<MAX_EVENTS>> = SpinLock::new(Table::new());
+
+/// Number of resources that could not be recorded.
+static UNTRACKED: AtomicUsize = AtomicUsize::new(0);
+
+/// Runs `f` on `table`, unless it is locked by the code this interrupted.
+fn with_table<const N: usize>(table: &SpinLock<Table<N>>, f: impl FnOnce(&mut Table<N>) -> bool) {
+    let recorded = table.try_lock().is_some_and(|mut table| f(&mut table));
+    if !recorded {
+        UNTRACKED.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Records an allocation or deallocation.
+pub(crate) fn record_alloc(event: &AllocEvent, location: &'static Location<'static>) {
+    let (key, size) = match *event {
+        AllocEvent::AllocatePool { ptr, size, .. } => (ptr.as_ptr() as usize, size),
+        AllocEvent::AllocatePages { ptr, count, .. } => {
+            (ptr.as_ptr() as usize, count * boot::PAGE_SIZE)
+        }
+        AllocEvent::FreePool { ptr } | AllocEvent::FreePages { ptr, .. } => {
+            // Buffers allocated by the firmware are freed as well; they are
+            // simply not found.
+            with_table(&ALLOCATIONS, |table| {
+                table.remove(ptr.as_ptr() as usize);
+                true
+            });
+            return;
+        }
+    };
+    with_table(&ALLOCATIONS, |table| {
+        table.insert(Entry {
+            key,
+            size,
+            location: Some(location),
+        })
+    });
+}
+
+/// Records the creation of an event.
+pub(crate) fn record_event_created(event: *mut c_void, location: &'static Location<'static>) {
+    with_table(&EVENTS, |table| {
+        table.insert(Entry {
+            key: event as usize,
+            size: 0,
+            location: Some(location),
+        })
+    });
+}
+
+/// Records the closing of an event.
+pub(crate) fn record_event_closed(event: *mut c_void) {
+    with_table(&EVENTS, |table| {
+        table.remove(event as usize);
+        true
+    });
+}
+
+/// Logs the entries of `table`, returning their number and total size.
+fn log_entries<const N: usize>(table: &SpinLock<Table<N>>, kind: &str) -> (usize, usize) {
+    let (mut count, mut size) = (0, 0);
+    // Entries are copied out a few at a time, so that the table is not
+    // locked while logging, which may allocate.
+    let mut chunk = [Entry::EMPTY; 16];
+    let mut slot = 0;
+    while slot < N {
+        let mut n = 0;
+        {
+            let table = table.lock();
+            while slot < N && n < chunk.len() {
+                let entry = table.entries[slot];
+                if entry.is_live() {
+                    chunk[n] = entry;
+                    n += 1;
+                }
+                slot += 1;
+            }
+        }
+        for entry in &chunk[..n] {
+            match entry.location {
+                Some(location) => log::warn!(
+                    "{kind} at {:#x} created at {location} is still alive",
+                    entry.key
+                ),
+                None => log::warn!("{kind} at {:#x} is still alive", entry.key),
+            }
+            count += 1;
+            size += entry.size;
+        }
+    }
+    (count, size)
+}
+
+/// Resources found by [`check_leaks`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct LeakReport {
+    /// Number of protocols opened by the image that are still open.
+    pub open_protocols: usize,
+    /// Number of events that were not closed.
+    pub events: usize,
+    /// Number of allocations that were not freed.
+    pub allocations: usize,
+    /// Total size in bytes of the allocations that were not freed.
+    pub allocated_bytes: usize,
+    /// Number of allocations and events that could not be tracked.
+    pub untracked: usize,
+}
+
+impl LeakReport {
+    /// Returns whether no leaks were found.
+    #[must_use]
+    pub const fn is_clean(&self) -> bool {
+        self.open_protocols == 0 && self.events == 0 && self.allocations == 0
+    }
+}
+
+impl Display for LeakReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} open protocols, {} events, {} allocations ({} bytes)",
+            self.open_protocols, self.events, self.allocations, self.allocated_bytes
+        )?;
+        if self.untracked > 0 {
+            write!(f, ", {} untracked", self.untracked)?;
+        }
+        Ok(())
+    }
+}
+
+/// Logs the resources the image has not released, and returns a summary.
+///
+/// Allocations and events are only found if they were made through the
+/// functions of this crate, e.g. by the [`Allocator`]. Protocols opened
+/// through the `HandleProtocol` boot service are not reported, since it
+/// never closes them.
+///
+/// # Errors
+///
+/// Returns an error if the open protocols could not be listed.
+///
+/// [`Allocator`]: crate::allocator::Allocator
+pub fn check_leaks() -> Result<LeakReport> {
+    let mut report = LeakReport {
+        untracked: UNTRACKED.load(Ordering::Relaxed),
+        ..LeakReport::default()
+    };
+
+    let image = boot::image_handle();
+    let handles = boot::locate_handle_buffer(SearchType::AllHandles)?;
+    for handle in handles.iter() {
+        let Ok(protocols) = boot::protocols_per_handle(*handle) else {
+            continue;
+        };
+        for protocol in protocols.iter() {
+            let _ = boot::open_protocol_information(*handle, protocol, |entry| {
+                let ignored = entry.attributes & (BY_HANDLE_PROTOCOL | TEST_PROTOCOL) != 0;
+                if entry.agent_handle == image.as_ptr() && !ignored {
+                    log::warn!(
+                        "Protocol {protocol} on handle {handle:?} is still open (attributes {:#x})",
+                        entry.attributes
+                    );
+                    report.open_protocols += 1;
+                }
+            });
+        }
+    }
+
+    (report.events, _) = log_entries(&EVENTS, "Event");
+    (report.allocations, report.allocated_bytes) = log_entries(&ALLOCATIONS, "Allocation");
+
+    if report.is_clean() {
+        log::info!("No leaks found");
+    } else {
+        log::warn!("Leaks found: {report}");
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table() {
+        let mut table = Table::<4>::new();
+        let entry = |key| Entry {
+            key,
+            size: key,
+            location: None,
+        };
+        for key in [8, 16, 24, 32] {
+            assert!(table.insert(entry(key)));
+        }
+        assert!(!table.insert(entry(40)));
+        table.remove(16);
+        table.remove(48);
+        let live = |table: &Table<4>| table.entries.iter().filter(|e| e.is_live()).count();
+        assert_eq!(live(&table), 3);
+        assert!(table.insert(entry(40)));
+        table.remove(40);
+        table.remove(8);
+        assert_eq!(live(&table), 2);
+        // Entries behind removed ones are still found.
+        table.remove(24);
+        table.remove(32);
+        assert_eq!(live(&table), 0);
+    }
+}
@@ -13,12 +13,19 @@
 //!
 //! The last part also means that some Unicode characters might not be
 //! supported by the UEFI console. Don't expect emoji output support.
+//!
+//! Records are colored by level on the text console, and can be prefixed
+//! with a timestamp; see [`Logger::set_level_color`] and
+//! [`Logger::enable_timestamps`].
 
-use crate::proto::console::text::Output;
-use crate::system;
+use crate::proto::console::text::{Color, Output};
+use crate::proto::misc::Timestamp;
+use crate::{Result, Status, boot, system};
+use core::ffi::c_void;
 use core::fmt::{self, Write};
 use core::ptr;
-use core::sync::atomic::{AtomicPtr, Ordering};
+use core::sync::atomic::{AtomicPtr, AtomicU8, AtomicU64, Ordering};
+use log::Level;
 
 /// Global logger object
 static LOGGER: Logger = Logger::new();
@@ -44,6 +51,38 @@ pub fn disable() {
     LOGGER.disable();
 }
 
+/// Returns the global logger, e.g. to configure its colors.
+#[must_use]
+pub fn logger() -> &'static Logger {
+    &LOGGER
+}
+
+/// Console colors, indexed by their value.
+const COLORS: [Color; 16] = [
+    Color::Black,
+    Color::Blue,
+    Color::Green,
+    Color::Cyan,
+    Color::Red,
+    Color::Magenta,
+    Color::Brown,
+    Color::LightGray,
+    Color::DarkGray,
+    Color::LightBlue,
+    Color::LightGreen,
+    Color::LightCyan,
+    Color::LightRed,
+    Color::LightMagenta,
+    Color::Yellow,
+    Color::White,
+];
+
+/// Value of a level color meaning that the color is not changed.
+const NO_COLOR: u8 = u8::MAX;
+
+/// Function reading the timestamp counter of the Timestamp protocol.
+type TimestampFn = unsafe extern "efiapi" fn() -> u64;
+
 /// Writer to the QEMU debugcon device and the debug-console of
 /// cloud-hypervisor.
 ///
@@ -86,6 +125,13 @@ impl core::fmt::Write for DebugconWriter {
 #[derive(Debug)]
 pub struct Logger {
     writer: AtomicPtr<Output>,
+    /// Text color of each level, indexed by the level minus one, or
+    /// [`NO_COLOR`].
+    colors: [AtomicU8; 5],
+    /// Function of the Timestamp protocol, null if timestamps are disabled.
+    timestamp: AtomicPtr<c_void>,
+    timestamp_frequency: AtomicU64,
+    timestamp_start: AtomicU64,
 }
 
 impl Logger {
@@ -93,11 +139,24 @@ impl Logger {
     ///
     /// The logger is initially disabled. Call [`set_output`] to enable it.
     ///
+    /// Errors are shown in light red and warnings in yellow, and other
+    /// levels in the current color of the console.
+    ///
     /// [`set_output`]: Self::set_output
     #[must_use]
     pub const fn new() -> Self {
         Self {
             writer: AtomicPtr::new(ptr::null_mut()),
+            colors: [
+                AtomicU8::new(Color::LightRed as u8),
+                AtomicU8::new(Color::Yellow as u8),
+                AtomicU8::new(NO_COLOR),
+                AtomicU8::new(NO_COLOR),
+                AtomicU8::new(NO_COLOR),
+            ],
+            timestamp: AtomicPtr::new(ptr::null_mut()),
+            timestamp_frequency: AtomicU64::new(0),
+            timestamp_start: AtomicU64::new(0),
         }
     }
 
@@ -130,6 +189,76 @@ impl Logger {
     pub fn disable(&self) {
         unsafe { self.set_output(ptr::null_mut()) }
     }
+
+    /// Sets the text color of records of `level` on the text console, or
+    /// `None` to use the current color of the console.
+    pub fn set_level_color(&self, level: Level, color: Option<Color>) {
+        let color = color.map_or(NO_COLOR, |color| color as u8);
+        self.colors[level as usize - 1].store(color, Ordering::Relaxed);
+    }
+
+    /// Returns the text color of records of `level`.
+    #[must_use]
+    pub fn level_color(&self, level: Level) -> Option<Color> {
+        let color = self.colors[level as usize - 1].load(Ordering::Relaxed);
+        COLORS.get(usize::from(color)).copied()
+    }
+
+    /// Prefixes records with the time in seconds since this was called,
+    /// read from the [`Timestamp`] protocol.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::NOT_FOUND`]: the protocol is not available.
+    /// * [`Status::UNSUPPORTED`]: the counter frequency is unknown.
+    pub fn enable_timestamps(&self) -> Result {
+        let handle = boot::get_handle_for_protocol::<Timestamp>()?;
+        let timestamp = boot::open_protocol_exclusive::<Timestamp>(handle)?;
+        let frequency = timestamp.get_properties()?.frequency;
+        if frequency == 0 {
+            return Err(Status::UNSUPPORTED.into());
+        }
+        // SAFETY: `Timestamp` is a transparent wrapper of the raw protocol.
+        let get_timestamp = unsafe {
+            (*ptr::from_ref(&*timestamp).cast::<uefi_raw::protocol::misc::TimestampProtocol>())
+                .get_timestamp
+        };
+        self.timestamp_frequency.store(frequency, Ordering::Relaxed);
+        self.timestamp_start
+            .store(timestamp.get_timestamp(), Ordering::Relaxed);
+        // The function is used after closing the protocol, which is fine as
+        // long as the driver providing it isn't unloaded.
+        self.timestamp
+            .store(get_timestamp as *mut c_void, Ordering::Release);
+        Ok(())
+    }
+
+    /// Stops prefixing records with a timestamp.
+    pub fn disable_timestamps(&self) {
+        self.timestamp.store(ptr::null_mut(), Ordering::Release);
+    }
+
+    /// Returns the time since timestamps were enabled, in microseconds.
+    fn timestamp_micros(&self) -> Option<u64> {
+        let get_timestamp = self.timestamp.load(Ordering::Acquire);
+        if get_timestamp.is_null() {
+            return None;
+        }
+        // SAFETY: the pointer was stored by `enable_timestamps`.
+        let get_timestamp =
+            unsafe { core::mem::transmute::<*mut c_void, TimestampFn>(get_timestamp) };
+        // SAFETY: the function comes from the Timestamp protocol.
+        let ticks =
+            unsafe { get_timestamp() }.wrapping_sub(self.timestamp_start.load(Ordering::Relaxed));
+        let frequency = self.timestamp_frequency.load(Ordering::Relaxed);
+        u64::try_from(u128::from(ticks) * 1_000_000 / u128::from(frequency)).ok()
+    }
+}
+
+impl Default for Logger {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl log::Log for Logger {
@@ -140,17 +269,30 @@ impl log::Log for Logger {
     }
 
     fn log(&self, record: &log::Record) {
+        let timestamp = self.timestamp_micros();
         if let Some(writer) = unsafe { self.output().as_mut() } {
+            let write = |writer: &mut Output| {
+                DecoratedLog::write(
+                    writer,
+                    record.level(),
+                    record.args(),
+                    record.file().unwrap_or("<unknown file>"),
+                    record.line().unwrap_or(0),
+                    timestamp,
+                )
+            };
             // Ignore all errors. Since we're in the logger implementation we
             // can't log the error. We also don't want to panic, since logging
             // is generally not critical functionality.
-            let _ = DecoratedLog::write(
-                writer,
-                record.level(),
-                record.args(),
-                record.file().unwrap_or("<unknown file>"),
-                record.line().unwrap_or(0),
-            );
+            let _ = match self.level_color(record.level()) {
+                Some(color) => {
+                    let background = COLORS[(writer.mode_info().attribute() >> 4) & 0x07];
+                    writer
+                        .with_colors(color, background, write)
+                        .unwrap_or(Err(fmt::Error))
+                }
+                None => write(writer),
+            };
         }
 
         #[cfg(all(
@@ -167,6 +309,7 @@ impl log::Log for Logger {
                 record.args(),
                 record.file().unwrap_or("<unknown file>"),
                 record.line().unwrap_or(0),
+                timestamp,
             );
         }
     }
@@ -197,6 +340,8 @@ struct DecoratedLog<'writer, 'a, W: fmt::Write> {
     at_line_start: bool,
     file: &'a str,
     line: u32,
+    /// Microseconds to print before the log level, if any.
+    timestamp: Option<u64>,
 }
 
 impl<'writer, 'a, W: fmt::Write> DecoratedLog<'writer, 'a, W> {
@@ -207,6 +352,7 @@ impl<'writer, 'a, W: fmt::Write> DecoratedLog<'writer, 'a, W> {
         args: &fmt::Arguments,
         file: &'a str,
         line: u32,
+        timestamp: Option<u64>,
     ) -> fmt::Result {
         let mut decorated_writer = Self {
             writer,
@@ -214,6 +360,7 @@ impl<'writer, 'a, W: fmt::Write> DecoratedLog<'writer, 'a, W> {
             at_line_start: true,
             file,
             line,
+            timestamp,
         };
         writeln!(decorated_writer, "{}", *args)
     }
@@ -229,6 +376,14 @@ impl<W: fmt::Write> fmt::Write for DecoratedLog<'_, '_, W> {
         // beginning of a line of output.
         let first = lines.next().unwrap_or("");
         if self.at_line_start {
+            if let Some(micros) = self.timestamp {
+                write!(
+                    self.writer,
+                    "[{:>5}.{:06}] ",
+                    micros / 1_000_000,
+                    micros % 1_000_000
+                )?;
+            }
             write!(
                 self.writer,
                 "[{:>5}]: {:>12}@{:03}: ",
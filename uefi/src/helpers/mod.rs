@@ -26,9 +26,15 @@ pub use println::_print;
 
 #[cfg(feature = "global_allocator")]
 mod global_allocator;
+#[cfg(feature = "leak_check")]
+pub(crate) mod leak_check;
+#[cfg(feature = "leak_check")]
+pub use leak_check::{LeakReport, check_leaks};
 #[cfg(feature = "logger")]
 mod logger;
-#[cfg(feature = "panic_handler")]
+#[cfg(feature = "logger")]
+pub use logger::{Logger, logger};
+#[cfg(all(feature = "panic_handler", not(feature = "std")))]
 mod panic_handler;
 mod println;
 
@@ -55,6 +61,25 @@ pub fn init() -> Result<()> {
     Ok(())
 }
 
+/// Guard created by the [`entry`] macro at the start of the entry function.
+///
+/// It is dropped when the entry function returns, after all of its locals,
+/// and runs the end-of-program checks enabled by crate features.
+///
+/// [`entry`]: crate::entry
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct EntryGuard;
+
+impl Drop for EntryGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "leak_check")]
+        if crate::boot::are_boot_services_active() {
+            let _ = check_leaks();
+        }
+    }
+}
+
 #[allow(clippy::missing_const_for_fn)]
 pub(crate) fn exit() {
     #[cfg(feature = "logger")]
@@ -0,0 +1,295 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Localization using HII string packages.
+//!
+//! A [`Catalog`] registers translated strings with the HII database, one
+//! string package per language, and looks them up in the language that best
+//! matches the platform language (the `PlatformLang` UEFI variable). Strings
+//! are identified by their [`StringId`], which is the one-based index of the
+//! string in each language's string list.
+//!
+//! A catalog can be installed globally with [`set_global`], after which the
+//! [`t!`] macro can be used to look up strings.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use uefi::l10n::{self, Catalog};
+//! use uefi::proto::hii::StringId;
+//! use uefi::{cstr8, cstr16, guid, t};
+//!
+//! const GREETING: StringId = 1;
+//! const FAREWELL: StringId = 2;
+//!
+//! # fn example() -> uefi::Result {
+//! let catalog = Catalog::new(
+//!     guid!("3ab3bb4a-f3a2-4ba4-a431-3fc9dc69e9ff"),
+//!     &[
+//!         (cstr8!("en-US"), &[cstr16!("Hello"), cstr16!("Goodbye")]),
+//!         (cstr8!("de-DE"), &[cstr16!("Hallo"), cstr16!("Tschüss")]),
+//!     ],
+//! )?;
+//! l10n::set_global(catalog);
+//!
+//! uefi::println!("{}", t!(GREETING));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`t!`]: crate::t
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+use crate::boot;
+use crate::proto::hii::database::HiiDatabase;
+use crate::proto::hii::package::PackageListBuilder;
+use crate::proto::hii::string::HiiString;
+use crate::proto::hii::{HiiHandle, StringId};
+use crate::runtime::{self, VariableVendor};
+use crate::{CStr8, CStr16, CString16, Guid, Result, Status, cstr16};
+
+/// The globally installed catalog, see [`set_global`].
+static GLOBAL_CATALOG: AtomicPtr<Catalog> = AtomicPtr::new(ptr::null_mut());
+
+/// A set of translated strings registered with the HII database.
+///
+/// The package list is removed from the HII database when the catalog is
+/// dropped.
+#[derive(Debug)]
+pub struct Catalog {
+    handle: HiiHandle,
+    /// Selected language, including a trailing null byte.
+    language: Vec<u8>,
+}
+
+impl Catalog {
+    /// Registers `translations`, a list of `(language, strings)` pairs, as a
+    /// new HII package list identified by `guid`.
+    ///
+    /// Languages are RFC 4646 language codes such as `en-US`. Each language
+    /// must provide the same strings in the same order; the string with
+    /// index `i` gets the [`StringId`] `i + 1`.
+    ///
+    /// The language used for lookups is selected with [`select_language`]
+    /// based on the platform language.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::INVALID_PARAMETER`]: `translations` is empty.
+    /// * [`Status::NOT_FOUND`]: the HII Database or HII String protocol is
+    ///   not available.
+    /// * [`Status::OUT_OF_RESOURCES`]: the package list could not be added.
+    pub fn new(guid: Guid, translations: &[(&CStr8, &[&CStr16])]) -> Result<Self> {
+        if translations.is_empty() {
+            return Err(Status::INVALID_PARAMETER.into());
+        }
+
+        let package_list = translations
+            .iter()
+            .fold(
+                PackageListBuilder::new(guid),
+                |builder, (language, strings)| builder.strings(language, strings),
+            )
+            .build();
+
+        let db_handle = boot::get_handle_for_protocol::<HiiDatabase>()?;
+        let db = boot::open_protocol_exclusive::<HiiDatabase>(db_handle)?;
+        let handle = db.new_package_list(&package_list, None)?;
+
+        let mut catalog = Self {
+            handle,
+            language: Vec::new(),
+        };
+        let supported = with_hii_string(|hii_string| hii_string.languages(handle))?;
+        let language =
+            select_language(platform_language().as_deref(), &supported).ok_or(Status::NOT_FOUND)?;
+        catalog.set_language_unchecked(language);
+        Ok(catalog)
+    }
+
+    /// Returns the HII handle of the registered package list.
+    #[must_use]
+    pub const fn handle(&self) -> HiiHandle {
+        self.handle
+    }
+
+    /// Returns the language used for lookups.
+    #[must_use]
+    pub fn language(&self) -> &str {
+        // OK to unwrap: the language is always set from a `&str` and
+        // ends with a null byte.
+        core::str::from_utf8(&self.language[..self.language.len() - 1]).unwrap()
+    }
+
+    /// Changes the language used for lookups.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::UNSUPPORTED`]: the catalog has no strings for `language`.
+    pub fn set_language(&mut self, language: &str) -> Result {
+        let supported = with_hii_string(|hii_string| hii_string.languages(self.handle))?;
+        if !supported
+            .split(';')
+            .any(|l| l.eq_ignore_ascii_case(language))
+        {
+            return Err(Status::UNSUPPORTED.into());
+        }
+        self.set_language_unchecked(language);
+        Ok(())
+    }
+
+    fn set_language_unchecked(&mut self, language: &str) {
+        self.language.clear();
+        self.language.extend_from_slice(language.as_bytes());
+        self.language.push(0);
+    }
+
+    /// Returns the string with ID `id` in the selected language, or `None`
+    /// if there is no such string.
+    #[must_use]
+    pub fn get(&self, id: StringId) -> Option<CString16> {
+        // OK to unwrap: the language is ASCII and ends with a null byte.
+        let language = CStr8::from_bytes_with_nul(&self.language).unwrap();
+        with_hii_string(|hii_string| hii_string.get_string(language, self.handle, id)).ok()
+    }
+}
+
+impl Drop for Catalog {
+    fn drop(&mut self) {
+        // Ignore errors since they can't be propagated from `drop`.
+        if let Ok(handle) = boot::get_handle_for_protocol::<HiiDatabase>() {
+            if let Ok(db) = boot::open_protocol_exclusive::<HiiDatabase>(handle) {
+                let _ = db.remove_package_list(self.handle);
+            }
+        }
+    }
+}
+
+fn with_hii_string<T>(f: impl FnOnce(&HiiString) -> Result<T>) -> Result<T> {
+    let handle = boot::get_handle_for_protocol::<HiiString>()?;
+    let hii_string = boot::open_protocol_exclusive::<HiiString>(handle)?;
+    f(&hii_string)
+}
+
+/// Returns the current platform language from the `PlatformLang` variable,
+/// e.g. `en-US`.
+#[must_use]
+pub fn platform_language() -> Option<String> {
+    let (data, _) =
+        runtime::get_variable_boxed(cstr16!("PlatformLang"), &VariableVendor::GLOBAL_VARIABLE)
+            .ok()?;
+    let language: String = data
+        .iter()
+        .take_while(|b| **b != 0)
+        .map(|b| char::from(*b))
+        .collect();
+    (!language.is_empty()).then_some(language)
+}
+
+/// Selects the best match for `preferred` from `supported`, a
+/// semicolon-separated list of RFC 4646 language codes.
+///
+/// The first of the following rules that matches determines the result:
+/// 1. A supported language equal to `preferred`, ignoring ASCII case.
+/// 2. A supported language with the same primary language subtag, e.g.
+///    `en-GB` for a preferred language of `en-US`.
+/// 3. The first supported language.
+///
+/// Returns `None` if `supported` is empty.
+#[must_use]
+pub fn select_language<'a>(preferred: Option<&str>, supported: &'a str) -> Option<&'a str> {
+    let mut languages = supported.split(';').filter(|l| !l.is_empty());
+    let first = languages.clone().next()?;
+    let Some(preferred) = preferred else {
+        return Some(first);
+    };
+
+    let primary = |language: &str| -> String {
+        language
+            .split('-')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase()
+    };
+
+    if let Some(exact) = languages
+        .clone()
+        .find(|l| l.eq_ignore_ascii_case(preferred))
+    {
+        return Some(exact);
+    }
+    let preferred_primary = primary(preferred);
+    Some(
+        languages
+            .find(|l| primary(l) == preferred_primary)
+            .unwrap_or(first),
+    )
+}
+
+/// Installs `catalog` as the global catalog used by [`translate`] and the
+/// [`t!`] macro.
+///
+/// The catalog is leaked and stays registered with the HII database until
+/// the image exits. If a global catalog was already installed, it is
+/// replaced; the previous catalog is leaked as well since references to it
+/// may still exist.
+///
+/// [`t!`]: crate::t
+pub fn set_global(catalog: Catalog) {
+    GLOBAL_CATALOG.store(Box::into_raw(Box::new(catalog)), Ordering::Release);
+}
+
+/// Returns the global catalog installed with [`set_global`].
+#[must_use]
+pub fn global() -> Option<&'static Catalog> {
+    // SAFETY: the pointer is either null or was created by `Box::into_raw`
+    // in `set_global` and is never freed.
+    unsafe { GLOBAL_CATALOG.load(Ordering::Acquire).as_ref() }
+}
+
+/// Looks up the string with ID `id` in the global catalog.
+///
+/// If no global catalog is installed or the string does not exist, an empty
+/// string is returned.
+#[must_use]
+pub fn translate(id: StringId) -> CString16 {
+    global()
+        .and_then(|catalog| catalog.get(id))
+        .unwrap_or_default()
+}
+
+/// Looks up a translated string in the global [`Catalog`].
+///
+/// This is shorthand for [`l10n::translate`]. The result is a
+/// [`CString16`], which is empty if the string is not available.
+///
+/// [`Catalog`]: crate::l10n::Catalog
+/// [`CString16`]: crate::CString16
+/// [`l10n::translate`]: crate::l10n::translate
+#[macro_export]
+macro_rules! t {
+    ($id:expr) => {
+        $crate::l10n::translate($id)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_language() {
+        let supported = "en-US;de-DE;fr";
+        assert_eq!(select_language(Some("de-DE"), supported), Some("de-DE"));
+        assert_eq!(select_language(Some("DE-de"), supported), Some("de-DE"));
+        assert_eq!(select_language(Some("de-AT"), supported), Some("de-DE"));
+        assert_eq!(select_language(Some("fr-CA"), supported), Some("fr"));
+        assert_eq!(select_language(Some("ja-JP"), supported), Some("en-US"));
+        assert_eq!(select_language(None, supported), Some("en-US"));
+        assert_eq!(select_language(Some("en-US"), ""), None);
+    }
+}
@@ -0,0 +1,259 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Human-readable labels for handles.
+//!
+//! Handles are opaque pointers, so logs mentioning many of them are hard to
+//! follow. This module keeps a registry of labels for handles, which the
+//! [`Debug`] implementation of [`Handle`] prints next to the pointer:
+//!
+//! ```text
+//! Handle(0x7e5c5f98, "PciRoot(0x0)/Pci(0x1,0x1)/Ata(0x0)")
+//! ```
+//!
+//! Labels are either set explicitly with [`set`], or derived from the
+//! handle's protocols with [`label`] or [`label_all`]. The registry is empty
+//! until one of these is called, so handles are printed as before by
+//! default.
+//!
+//! Labels are keyed by the address of the handle, and are not removed when
+//! the handle is deleted. Since firmware may reuse the address for a new
+//! handle, which would then be printed with the stale label, [`remove`] the
+//! label after uninstalling the last protocol of a labeled handle.
+//!
+//! ```no_run
+//! use uefi::{boot, labels};
+//!
+//! labels::set(boot::image_handle(), "my app");
+//! labels::label_all();
+//! log::debug!("image handle: {:?}", boot::image_handle());
+//! ```
+//!
+//! [`Debug`]: core::fmt::Debug
+
+use crate::boot::{
+    self, OpenProtocolAttributes, OpenProtocolParams, ScopedProtocol, SearchType, Tpl, TplGuard,
+};
+use crate::proto::ProtocolPointer;
+use crate::proto::device_path::DevicePath;
+use crate::proto::device_path::text::{AllowShortcuts, DisplayOnly};
+use crate::proto::driver::ComponentName2;
+use crate::sync::{SpinLock, SpinLockGuard};
+use crate::{Handle, Identify};
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use core::ops::{Deref, DerefMut};
+
+/// Labels by handle address. `Handle` is not `Send`, so it can't be the key.
+static LABELS: SpinLock<BTreeMap<usize, String>> = SpinLock::new(BTreeMap::new());
+
+fn key(handle: Handle) -> usize {
+    handle.as_ptr() as usize
+}
+
+/// The locked registry.
+///
+/// While boot services are active, the lock is held at [`Tpl::NOTIFY`], so
+/// that a notification function calling into this module can't spin forever
+/// on a lock held by the code it interrupted.
+struct Locked {
+    // Dropped before `_tpl`, so the TPL is restored after the lock is
+    // released.
+    labels: SpinLockGuard<'static, BTreeMap<usize, String>>,
+    _tpl: Option<TplGuard>,
+}
+
+impl Deref for Locked {
+    type Target = BTreeMap<usize, String>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.labels
+    }
+}
+
+impl DerefMut for Locked {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.labels
+    }
+}
+
+fn lock() -> Locked {
+    // SAFETY: the TPL is restored when the guard is dropped. Allocating,
+    // which the registry does, requires a TPL of at most `NOTIFY` anyway.
+    let tpl = boot::are_boot_services_active().then(|| unsafe { boot::raise_tpl(Tpl::NOTIFY) });
+    Locked {
+        labels: LABELS.lock(),
+        _tpl: tpl,
+    }
+}
+
+/// Sets the label of `handle`, replacing any previous label.
+pub fn set(handle: Handle, label: impl Into<String>) {
+    lock().insert(key(handle), label.into());
+}
+
+/// Removes the label of `handle`, and returns it.
+#[must_use]
+pub fn remove(handle: Handle) -> Option<String> {
+    lock().remove(&key(handle))
+}
+
+/// Removes all labels.
+pub fn clear() {
+    lock().clear();
+}
+
+/// Returns the label of `handle`, or `None` if it has none.
+#[must_use]
+pub fn get(handle: Handle) -> Option<String> {
+    lock().get(&key(handle)).cloned()
+}
+
+/// Calls `f` with the label of `handle` if it has one, without waiting for
+/// the registry. Used by the [`Debug`] implementation of [`Handle`], which may
+/// run in a notification function interrupting an update of the registry.
+///
+/// [`Debug`]: core::fmt::Debug
+pub(crate) fn with_label<R>(handle: Handle, f: impl FnOnce(&str) -> R) -> Option<R> {
+    let labels = LABELS.try_lock()?;
+    labels.get(&key(handle)).map(|label| f(label))
+}
+
+/// Returns the label of `handle`, deriving and registering it with
+/// [`derive()`] if it has none yet.
+#[must_use]
+pub fn label(handle: Handle) -> Option<String> {
+    if let Some(label) = get(handle) {
+        return Some(label);
+    }
+    let label = derive(handle)?;
+    set(handle, label.clone());
+    Some(label)
+}
+
+/// Derives and registers labels for all handles in the handle database that
+/// have none yet.
+pub fn label_all() {
+    if let Ok(handles) = boot::locate_handle_buffer(SearchType::AllHandles) {
+        for &handle in handles.iter() {
+            let _ = label(handle);
+        }
+    }
+}
+
+/// Derives a label for `handle` from its protocols, without registering it.
+/// In order of preference, the label is:
+///
+/// * the name of the controller, as reported by the [`ComponentName2`]
+///   protocol of the driver managing it,
+/// * the name of the driver, if the handle has a [`ComponentName2`]
+///   protocol itself,
+/// * the text representation of the [`DevicePath`] on the handle.
+///
+/// English names are used if available. Returns `None` if none of these
+/// exist.
+#[must_use]
+pub fn derive(handle: Handle) -> Option<String> {
+    controller_name(handle)
+        .or_else(|| driver_name(handle))
+        .or_else(|| device_path_text(handle))
+}
+
+/// Opens `P` on `handle` without affecting drivers managing it.
+fn get_protocol<P: ProtocolPointer + ?Sized>(handle: Handle) -> Option<ScopedProtocol<P>> {
+    // SAFETY: the protocol is only used briefly, while the handle is not
+    // expected to go away.
+    unsafe {
+        boot::open_protocol::<P>(
+            OpenProtocolParams {
+                handle,
+                agent: boot::image_handle(),
+                controller: None,
+            },
+            OpenProtocolAttributes::GetProtocol,
+        )
+    }
+    .ok()
+}
+
+/// Returns the language to request names of `component_name` in: English,
+/// such as `en` or `en-US`, if supported, the first supported language
+/// otherwise.
+fn name_language(component_name: &ComponentName2) -> Option<&str> {
+    let mut languages = component_name.supported_languages().ok()?;
+    let first = languages.next()?;
+    Some(
+        core::iter::once(first)
+            .chain(languages)
+            .find(|language| is_english(language))
+            .unwrap_or(first),
+    )
+}
+
+/// Returns whether the RFC 4646 `language` is English.
+fn is_english(language: &str) -> bool {
+    let primary = language.split('-').next().unwrap_or_default();
+    primary.eq_ignore_ascii_case("en")
+}
+
+fn controller_name(handle: Handle) -> Option<String> {
+    let drivers = boot::locate_handle_buffer(SearchType::ByProtocol(&ComponentName2::GUID)).ok()?;
+    drivers.iter().find_map(|&driver| {
+        let component_name = get_protocol::<ComponentName2>(driver)?;
+        let language = name_language(&component_name)?;
+        let name = component_name
+            .controller_name(handle, None, language)
+            .ok()?;
+        Some(name.to_string())
+    })
+}
+
+fn driver_name(handle: Handle) -> Option<String> {
+    let component_name = get_protocol::<ComponentName2>(handle)?;
+    let language = name_language(&component_name)?;
+    let name = component_name.driver_name(language).ok()?;
+    Some(name.to_string())
+}
+
+fn device_path_text(handle: Handle) -> Option<String> {
+    let device_path = get_protocol::<DevicePath>(handle)?;
+    let text = device_path
+        .to_string(DisplayOnly(true), AllowShortcuts(true))
+        .ok()?;
+    Some(text.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::format;
+    use core::ffi::c_void;
+    use core::ptr;
+
+    #[test]
+    fn test_labels() {
+        let mut dummy = 0u8;
+        let handle =
+            unsafe { Handle::from_ptr(ptr::from_mut(&mut dummy).cast::<c_void>()) }.unwrap();
+        let unlabeled = format!("{handle:?}");
+
+        set(handle, "disk");
+        assert_eq!(get(handle).as_deref(), Some("disk"));
+        assert_eq!(
+            format!("{handle:?}"),
+            format!("Handle({:?}, \"disk\")", handle.as_ptr())
+        );
+
+        assert_eq!(remove(handle).as_deref(), Some("disk"));
+        assert_eq!(get(handle), None);
+        assert_eq!(format!("{handle:?}"), unlabeled);
+    }
+
+    #[test]
+    fn test_is_english() {
+        assert!(is_english("en"));
+        assert!(is_english("en-US"));
+        assert!(is_english("EN-gb"));
+        assert!(!is_english("eng"));
+        assert!(!is_english("fr-FR"));
+    }
+}
@@ -130,12 +130,29 @@
 //!   a global allocator; you can use the `global_allocator` feature or
 //!   provide your own. This is independent of internal direct usages of the
 //!   UEFI boot service allocator which may happen anyway, where necessary.
+//! - `std`: Enable conversions to types of the Rust standard library, such
+//!   as from [`ErrorKind`] to `std::io::ErrorKind`. Implies `alloc`. This is
+//!   meant for host tools sharing code with UEFI applications, e.g. to build
+//!   device paths or inspect disk images offline; the protocol-independent
+//!   parts of this crate also build for the host, such as [`CString16`],
+//!   [`proto::device_path::build`], [`config`], the GPT parser in
+//!   [`disk::gpt`], the SMBIOS parser in [`smbios`], and
+//!   [`runtime::LoadOption`]. `std` provides the panic handler, so the
+//!   `panic_handler` feature has no effect with it. Don't enable this
+//!   together with `global_allocator`.
 //! - `global_allocator`: Set [`allocator::Allocator`] as the global Rust
 //!   allocator. This is a simple allocator that relies on the UEFI pool
 //!   allocator. You can choose to provide your own allocator instead of
 //!   using this feature, or no allocator at all if you don't need to
 //!   dynamically allocate any memory. Note that even without that feature,
 //!   some code might use the internal UEFI allocator.
+//! - `alloc_trace`: Report every allocation and deallocation made through
+//!   the boot services wrappers of this crate to a hook registered with
+//!   `mem::trace::set_hook`. Useful for hunting memory leaks.
+//! - `leak_check`: Track the allocations and events made through this crate,
+//!   and log the ones still alive, as well as the protocols still open, when
+//!   the [`entry`] function returns. Implies `alloc_trace`. See
+//!   `helpers::check_leaks`.
 //! - `logger`: Logging implementation for the standard [`log`] crate
 //!   that prints output to the UEFI console. No buffering is done; this
 //!   is not a high-performance logger.
@@ -244,6 +261,8 @@
 
 #[cfg(feature = "alloc")]
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 // allow referring to self as ::uefi for macros to work universally (from this crate and from others)
 // see https://github.com/rust-lang/rust/issues/54647
 extern crate self as uefi;
@@ -254,15 +273,33 @@ extern crate uefi_raw;
 pub mod data_types;
 pub mod allocator;
 pub mod boot;
+pub mod config;
+pub mod disk;
+#[cfg(feature = "alloc")]
+pub mod env;
 #[cfg(feature = "alloc")]
 pub mod fs;
 pub mod helpers;
+#[cfg(feature = "alloc")]
+pub mod l10n;
+#[cfg(feature = "alloc")]
+pub mod labels;
 pub mod mem;
 pub mod prelude;
+#[cfg(feature = "alloc")]
+pub mod probe;
 pub mod proto;
+#[cfg(feature = "alloc")]
+pub mod quirks;
 pub mod runtime;
+pub mod security;
+pub mod smbios;
+pub mod sync;
 pub mod system;
 pub mod table;
+pub mod time;
+#[cfg(feature = "alloc")]
+pub mod trace;
 
 pub(crate) mod polyfill;
 
@@ -273,7 +310,7 @@ mod util;
 #[cfg(feature = "alloc")]
 pub use data_types::CString16;
 pub use data_types::{CStr8, CStr16, Char8, Char16, Event, Guid, Handle, Identify};
-pub use result::{Error, Result, ResultExt, Status, StatusExt};
+pub use result::{Error, ErrorKind, Result, ResultExt, Status, StatusExt};
 /// Re-export ucs2_cstr so that it can be used in the implementation of the
 /// cstr16 macro. It is hidden since it's not intended to be used directly.
 #[doc(hidden)]
@@ -89,6 +89,38 @@ pub trait MemoryMap: Debug + Index<usize, Output = MemoryDescriptor> {
         }
         true
     }
+
+    /// Compares this map with a newer map `new` of the same system, and
+    /// returns the ranges whose description differs, sorted by physical
+    /// address.
+    ///
+    /// The comparison is done per page rather than per descriptor, so
+    /// regions that were merged or split without changing their type or
+    /// attributes are not reported. Neither map needs to be sorted.
+    ///
+    /// This is useful to debug allocations between two points in time, e.g.
+    /// to verify that the memory map didn't change before exiting boot
+    /// services.
+    ///
+    /// ```no_run
+    /// use uefi::boot::{self, MemoryType};
+    /// use uefi::mem::memory_map::MemoryMap;
+    ///
+    /// let before = boot::memory_map(MemoryType::LOADER_DATA).unwrap();
+    /// // ...
+    /// let after = boot::memory_map(MemoryType::LOADER_DATA).unwrap();
+    /// for change in before.diff(&after) {
+    ///     log::info!("{change}");
+    /// }
+    /// ```
+    #[cfg(feature = "alloc")]
+    #[must_use]
+    fn diff(&self, new: &dyn MemoryMap) -> alloc::vec::Vec<MemoryMapChange>
+    where
+        Self: Sized,
+    {
+        diff::diff(self, new)
+    }
 }
 
 /// Extension to [`MemoryMap`] that adds mutable operations. This also includes
@@ -0,0 +1,248 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Module for [`MemoryMapChange`] and comparing memory maps.
+
+use super::*;
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+use uefi_raw::PhysicalAddress;
+use uefi_raw::table::boot::PAGE_SIZE;
+
+/// A difference between two memory maps, as reported by [`MemoryMap::diff`].
+///
+/// The descriptors only cover the affected range, so a single descriptor of
+/// one map may be reported as several changes, e.g. if a part of a
+/// conventional memory region was allocated.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MemoryMapChange {
+    /// The range is only described by the new map.
+    Added(MemoryDescriptor),
+    /// The range is only described by the old map.
+    Removed(MemoryDescriptor),
+    /// The range is described by both maps, but with a different type,
+    /// attributes, or virtual address.
+    Changed {
+        /// The range as described by the old map.
+        old: MemoryDescriptor,
+        /// The range as described by the new map.
+        new: MemoryDescriptor,
+    },
+}
+
+impl MemoryMapChange {
+    /// Returns the physical start address of the affected range.
+    #[must_use]
+    pub const fn phys_start(&self) -> PhysicalAddress {
+        match self {
+            Self::Added(desc) | Self::Removed(desc) | Self::Changed { new: desc, .. } => {
+                desc.phys_start
+            }
+        }
+    }
+
+    /// Returns the number of pages in the affected range.
+    #[must_use]
+    pub const fn page_count(&self) -> u64 {
+        match self {
+            Self::Added(desc) | Self::Removed(desc) | Self::Changed { new: desc, .. } => {
+                desc.page_count
+            }
+        }
+    }
+}
+
+impl Display for MemoryMapChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let start = self.phys_start();
+        let end = start + self.page_count() * PAGE_SIZE as u64;
+        write!(f, "{start:#018x}-{end:#018x} ")?;
+        match self {
+            Self::Added(desc) => write!(f, "added {:?} {:?}", desc.ty, desc.att),
+            Self::Removed(desc) => write!(f, "removed {:?} {:?}", desc.ty, desc.att),
+            Self::Changed { old, new } => write!(
+                f,
+                "changed {:?} {:?} -> {:?} {:?}",
+                old.ty, old.att, new.ty, new.att
+            ),
+        }
+    }
+}
+
+/// Returns the end address of `desc`, exclusive.
+const fn end(desc: &MemoryDescriptor) -> PhysicalAddress {
+    desc.phys_start
+        .saturating_add(desc.page_count.saturating_mul(PAGE_SIZE as u64))
+}
+
+/// Returns the descriptors of `map` sorted by physical address, without
+/// empty descriptors.
+fn sorted_entries(map: &dyn MemoryMap) -> Vec<MemoryDescriptor> {
+    let mut entries: Vec<_> = map
+        .entries()
+        .filter(|d| d.page_count != 0)
+        .copied()
+        .collect();
+    entries.sort_unstable_by_key(|d| d.phys_start);
+    entries
+}
+
+/// Returns the index of the descriptor containing `addr`, if any.
+fn find(entries: &[MemoryDescriptor], addr: PhysicalAddress) -> Option<usize> {
+    let i = entries
+        .partition_point(|d| d.phys_start <= addr)
+        .checked_sub(1)?;
+    (addr < end(&entries[i])).then_some(i)
+}
+
+/// Returns the part of `desc` in the range `start..end`. A virtual start
+/// address of zero means the range isn't mapped and is kept as is.
+const fn slice(
+    desc: &MemoryDescriptor,
+    start: PhysicalAddress,
+    end: PhysicalAddress,
+) -> MemoryDescriptor {
+    let virt_start = if desc.virt_start == 0 {
+        0
+    } else {
+        desc.virt_start + (start - desc.phys_start)
+    };
+    MemoryDescriptor {
+        phys_start: start,
+        virt_start,
+        page_count: (end - start) / PAGE_SIZE as u64,
+        ..*desc
+    }
+}
+
+/// Implementation of [`MemoryMap::diff`].
+pub(super) fn diff(old: &dyn MemoryMap, new: &dyn MemoryMap) -> Vec<MemoryMapChange> {
+    let old = sorted_entries(old);
+    let new = sorted_entries(new);
+
+    // Split the address space at every boundary of both maps. Within each
+    // resulting range, both maps are described by at most one descriptor.
+    let mut bounds: Vec<_> = old
+        .iter()
+        .chain(&new)
+        .flat_map(|d| [d.phys_start, end(d)])
+        .collect();
+    bounds.sort_unstable();
+    bounds.dedup();
+
+    // Adjacent ranges described by the same pair of descriptors are merged
+    // into a single change.
+    let mut changes = Vec::new();
+    let mut current: Option<(Option<usize>, Option<usize>, PhysicalAddress)> = None;
+    let mut flush = |pair: (Option<usize>, Option<usize>), start, end| {
+        let change = match pair {
+            (None, None) => None,
+            (Some(o), None) => Some(MemoryMapChange::Removed(slice(&old[o], start, end))),
+            (None, Some(n)) => Some(MemoryMapChange::Added(slice(&new[n], start, end))),
+            (Some(o), Some(n)) => {
+                let old = slice(&old[o], start, end);
+                let new = slice(&new[n], start, end);
+                (old != new).then_some(MemoryMapChange::Changed { old, new })
+            }
+        };
+        changes.extend(change);
+    };
+    for &addr in &bounds {
+        let pair = (find(&old, addr), find(&new, addr));
+        match current {
+            Some((o, n, _)) if (o, n) == pair => {}
+            Some((o, n, start)) => {
+                flush((o, n), start, addr);
+                current = Some((pair.0, pair.1, addr));
+            }
+            None => current = Some((pair.0, pair.1, addr)),
+        }
+    }
+    // The last boundary is the end of the highest descriptor, so nothing is
+    // left to flush.
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: u64 = PAGE_SIZE as u64;
+
+    fn desc(ty: MemoryType, phys_start: u64, page_count: u64) -> MemoryDescriptor {
+        MemoryDescriptor {
+            ty,
+            phys_start,
+            virt_start: 0,
+            page_count,
+            att: MemoryAttribute::WRITE_BACK,
+        }
+    }
+
+    fn map(entries: &[MemoryDescriptor]) -> MemoryMapRef<'_> {
+        let len = size_of_val(entries);
+        let buffer = unsafe { core::slice::from_raw_parts(entries.as_ptr().cast::<u8>(), len) };
+        MemoryMapRef::new(
+            buffer,
+            MemoryMapMeta {
+                map_size: len,
+                desc_size: size_of::<MemoryDescriptor>(),
+                map_key: MemoryMapKey(0),
+                desc_version: MemoryDescriptor::VERSION,
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_diff_identical() {
+        let entries = [
+            desc(MemoryType::CONVENTIONAL, 0, 16),
+            desc(MemoryType::LOADER_DATA, 16 * PAGE, 4),
+        ];
+        let a = map(&entries);
+        assert_eq!(a.diff(&a), []);
+    }
+
+    #[test]
+    fn test_diff_allocation() {
+        // Four pages at the end of a conventional region were allocated, and
+        // a reserved region was removed.
+        let old = [
+            desc(MemoryType::RESERVED, 0x100 * PAGE, 1),
+            desc(MemoryType::CONVENTIONAL, 0, 16),
+        ];
+        let new = [
+            desc(MemoryType::CONVENTIONAL, 0, 12),
+            desc(MemoryType::LOADER_DATA, 12 * PAGE, 2),
+            desc(MemoryType::BOOT_SERVICES_DATA, 14 * PAGE, 2),
+            desc(MemoryType::MMIO, 0x200 * PAGE, 2),
+        ];
+        assert_eq!(
+            map(&old).diff(&map(&new)),
+            [
+                MemoryMapChange::Changed {
+                    old: desc(MemoryType::CONVENTIONAL, 12 * PAGE, 2),
+                    new: desc(MemoryType::LOADER_DATA, 12 * PAGE, 2),
+                },
+                MemoryMapChange::Changed {
+                    old: desc(MemoryType::CONVENTIONAL, 14 * PAGE, 2),
+                    new: desc(MemoryType::BOOT_SERVICES_DATA, 14 * PAGE, 2),
+                },
+                MemoryMapChange::Removed(desc(MemoryType::RESERVED, 0x100 * PAGE, 1)),
+                MemoryMapChange::Added(desc(MemoryType::MMIO, 0x200 * PAGE, 2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_merged_regions() {
+        // Adjacent regions of the same type were merged; this is not a
+        // change.
+        let old = [
+            desc(MemoryType::CONVENTIONAL, 0, 4),
+            desc(MemoryType::CONVENTIONAL, 4 * PAGE, 4),
+        ];
+        let new = [desc(MemoryType::CONVENTIONAL, 0, 8)];
+        assert_eq!(map(&old).diff(&map(&new)), []);
+    }
+}
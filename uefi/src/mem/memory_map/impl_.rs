@@ -4,7 +4,7 @@
 //! as well as relevant helper types, such as [`MemoryMapBackingMemory`].
 
 use super::*;
-use crate::boot;
+use crate::{Status, boot};
 use core::fmt::{Debug, Display, Formatter};
 use core::ops::{Index, IndexMut};
 use core::ptr;
@@ -270,7 +270,11 @@ impl IndexMut<usize> for MemoryMapRefMut<'_> {
 /// [`boot::get_memory_map`]: crate::boot::get_memory_map
 #[derive(Debug)]
 #[allow(clippy::len_without_is_empty)] // this type is never empty
-pub(crate) struct MemoryMapBackingMemory(NonNull<[u8]>);
+pub(crate) struct MemoryMapBackingMemory {
+    buf: NonNull<[u8]>,
+    /// Memory type of the allocation, to allocate a replacement with.
+    memory_type: MemoryType,
+}
 
 impl MemoryMapBackingMemory {
     /// Constructs a new [`MemoryMapBackingMemory`].
@@ -290,16 +294,16 @@ impl MemoryMapBackingMemory {
         // If this panics, the UEFI implementation is broken.
         assert_eq!(memory_map_meta.map_size % memory_map_meta.desc_size, 0);
 
-        unsafe { Ok(Self::from_raw(ptr, len)) }
+        unsafe { Ok(Self::from_raw(ptr, len, memory_type)) }
     }
 
-    unsafe fn from_raw(ptr: *mut u8, len: usize) -> Self {
+    unsafe fn from_raw(ptr: *mut u8, len: usize, memory_type: MemoryType) -> Self {
         assert_eq!(ptr.align_offset(align_of::<MemoryDescriptor>()), 0);
 
         let ptr = NonNull::new(ptr).expect("UEFI should never return a null ptr. An error should have been reflected via an Err earlier.");
-        let slice = NonNull::slice_from_raw_parts(ptr, len);
+        let buf = NonNull::slice_from_raw_parts(ptr, len);
 
-        Self(slice)
+        Self { buf, memory_type }
     }
 
     /// INTERNAL, for unit tests.
@@ -309,7 +313,7 @@ impl MemoryMapBackingMemory {
     #[cfg(test)]
     pub(crate) fn from_slice(buffer: &mut [u8]) -> Self {
         let len = buffer.len();
-        unsafe { Self::from_raw(buffer.as_mut_ptr(), len) }
+        unsafe { Self::from_raw(buffer.as_mut_ptr(), len, MemoryType::LOADER_DATA) }
     }
 
     /// Returns a "safe" best-effort size hint for the memory map size with
@@ -330,13 +334,13 @@ impl MemoryMapBackingMemory {
     /// Returns a slice to the underlying memory.
     #[must_use]
     pub const fn as_slice(&self) -> &[u8] {
-        unsafe { self.0.as_ref() }
+        unsafe { self.buf.as_ref() }
     }
 
     /// Returns a mutable slice to the underlying memory.
     #[must_use]
     pub const fn as_mut_slice(&mut self) -> &mut [u8] {
-        unsafe { self.0.as_mut() }
+        unsafe { self.buf.as_mut() }
     }
 }
 
@@ -344,7 +348,7 @@ impl MemoryMapBackingMemory {
 impl Drop for MemoryMapBackingMemory {
     fn drop(&mut self) {
         if boot::are_boot_services_active() {
-            let res = unsafe { boot::free_pool(self.0.cast()) };
+            let res = unsafe { boot::free_pool(self.buf.cast()) };
             if let Err(e) = res {
                 log::error!("Failed to deallocate memory map: {e:?}");
             }
@@ -374,6 +378,43 @@ impl MemoryMapOwned {
         let len = meta.entry_count();
         Self { buf, meta, len }
     }
+
+    /// Retrieves the current memory map into the existing buffer.
+    ///
+    /// This avoids allocating a new buffer for each call when polling the
+    /// memory map frequently. The buffer is only replaced by a larger one if
+    /// the memory map grew beyond it; like the initial buffer, the new one
+    /// has room for a few additional entries.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::OUT_OF_RESOURCES`]: a larger buffer could not be
+    ///   allocated. The map is left unchanged.
+    ///
+    /// [`Status::OUT_OF_RESOURCES`]: crate::Status::OUT_OF_RESOURCES
+    pub fn refresh(&mut self) -> crate::Result {
+        loop {
+            match boot::get_memory_map(self.buf.as_mut_slice()) {
+                Ok(meta) => {
+                    assert!(meta.desc_size >= size_of::<MemoryDescriptor>());
+                    self.meta = meta;
+                    self.len = meta.entry_count();
+                    return Ok(());
+                }
+                Err(err) if err.status() == Status::BUFFER_TOO_SMALL => {
+                    self.buf = MemoryMapBackingMemory::new(self.buf.memory_type)?;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Returns the size of the buffer backing the memory map in bytes,
+    /// which may be larger than the map itself.
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        self.buf.as_slice().len()
+    }
 }
 
 impl MemoryMap for MemoryMapOwned {
@@ -26,6 +26,7 @@
 //! - the trait implementations [`MemoryMapOwned`], [`MemoryMapRef`], and
 //!   [`MemoryMapRefMut`],
 //! - the iterator [`MemoryMapIter`]
+//! - [`MemoryMapChange`], as returned by [`MemoryMap::diff`],
 //! - various associated helper types, such as [`MemoryMapKey`] and
 //!   [`MemoryMapMeta`],
 //! - re-exports [`MemoryDescriptor`], [`MemoryType`], and [`MemoryAttribute`].
@@ -34,10 +35,14 @@
 //! [`boot::memory_map`]: crate::boot::memory_map
 
 mod api;
+#[cfg(feature = "alloc")]
+mod diff;
 mod impl_;
 mod iter;
 
 pub use api::*;
+#[cfg(feature = "alloc")]
+pub use diff::MemoryMapChange;
 pub use impl_::*;
 pub use iter::*;
 pub use uefi_raw::table::boot::{MemoryAttribute, MemoryDescriptor, MemoryType};
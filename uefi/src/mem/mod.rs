@@ -7,6 +7,8 @@ use crate::boot;
 use core::ptr::NonNull;
 
 pub mod memory_map;
+#[cfg(feature = "alloc_trace")]
+pub mod trace;
 
 #[cfg(feature = "alloc")]
 pub(crate) mod util;
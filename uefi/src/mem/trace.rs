@@ -0,0 +1,118 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Tracing of memory allocations.
+//!
+//! With the `alloc_trace` crate feature, every allocation and deallocation
+//! made through [`boot::allocate_pool`], [`boot::free_pool`],
+//! [`boot::allocate_pages`], and [`boot::free_pages`] is reported to the
+//! hook registered with [`set_hook`]. This includes allocations made by the
+//! [`Allocator`] and by this crate internally, but not allocations made by
+//! other code calling the boot services directly.
+//!
+//! Together with the location of the caller, this can be used to find
+//! leaks in long-running applications:
+//!
+//! ```no_run
+//! use core::panic::Location;
+//! use uefi::mem::trace::{self, AllocEvent};
+//!
+//! fn hook(event: &AllocEvent, location: &'static Location<'static>) {
+//!     log::trace!("{location}: {event:?}");
+//! }
+//!
+//! trace::set_hook(Some(hook));
+//! ```
+//!
+//! [`Allocator`]: crate::allocator::Allocator
+//! [`boot::allocate_pool`]: crate::boot::allocate_pool
+//! [`boot::free_pool`]: crate::boot::free_pool
+//! [`boot::allocate_pages`]: crate::boot::allocate_pages
+//! [`boot::free_pages`]: crate::boot::free_pages
+
+use crate::mem::memory_map::MemoryType;
+use core::mem;
+use core::panic::Location;
+use core::ptr::{self, NonNull};
+use core::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+
+/// A successful allocation or deallocation.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AllocEvent {
+    /// Memory was allocated with [`boot::allocate_pool`].
+    ///
+    /// [`boot::allocate_pool`]: crate::boot::allocate_pool
+    AllocatePool {
+        /// Start of the allocation.
+        ptr: NonNull<u8>,
+        /// Size of the allocation in bytes.
+        size: usize,
+        /// Memory type of the allocation.
+        memory_type: MemoryType,
+    },
+    /// Memory was freed with [`boot::free_pool`].
+    ///
+    /// [`boot::free_pool`]: crate::boot::free_pool
+    FreePool {
+        /// Start of the allocation.
+        ptr: NonNull<u8>,
+    },
+    /// Pages were allocated with [`boot::allocate_pages`].
+    ///
+    /// [`boot::allocate_pages`]: crate::boot::allocate_pages
+    AllocatePages {
+        /// Start of the allocation.
+        ptr: NonNull<u8>,
+        /// Number of pages.
+        count: usize,
+        /// Memory type of the allocation.
+        memory_type: MemoryType,
+    },
+    /// Pages were freed with [`boot::free_pages`].
+    ///
+    /// [`boot::free_pages`]: crate::boot::free_pages
+    FreePages {
+        /// Start of the allocation.
+        ptr: NonNull<u8>,
+        /// Number of pages.
+        count: usize,
+    },
+}
+
+/// Function called for each [`AllocEvent`], along with the location of the
+/// code that called the allocation function.
+pub type AllocHook = fn(&AllocEvent, &'static Location<'static>);
+
+/// The registered hook, null if none.
+static HOOK: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+
+/// Set while the hook is running, to avoid reporting allocations made by the
+/// hook itself.
+static IN_HOOK: AtomicBool = AtomicBool::new(false);
+
+/// Registers `hook` to be called for each allocation and deallocation, or
+/// removes the hook if `None`. Returns the previously registered hook.
+///
+/// Allocations made while the hook is running, including those made by the
+/// hook itself, are not reported.
+pub fn set_hook(hook: Option<AllocHook>) -> Option<AllocHook> {
+    let new = hook.map_or(ptr::null_mut(), |hook| hook as *mut ());
+    let prev = HOOK.swap(new, Ordering::AcqRel);
+    // SAFETY: the pointer is either null or was stored above from an
+    // `AllocHook`.
+    (!prev.is_null()).then(|| unsafe { mem::transmute::<*mut (), AllocHook>(prev) })
+}
+
+/// Reports `event` to the registered hook, if any.
+pub(crate) fn report(event: AllocEvent, location: &'static Location<'static>) {
+    #[cfg(feature = "leak_check")]
+    crate::helpers::leak_check::record_alloc(&event, location);
+
+    let hook = HOOK.load(Ordering::Acquire);
+    if hook.is_null() || IN_HOOK.swap(true, Ordering::Acquire) {
+        return;
+    }
+    // SAFETY: see `set_hook`.
+    let hook = unsafe { mem::transmute::<*mut (), AllocHook>(hook) };
+    hook(&event, location);
+    IN_HOOK.store(false, Ordering::Release);
+}
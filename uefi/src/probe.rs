@@ -0,0 +1,219 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Probing the capabilities of the firmware.
+//!
+//! Firmware implementations differ widely in which protocols they provide:
+//! a minimal hypervisor firmware may lack HII or networking entirely. Rather
+//! than failing deep inside a wrapper, applications can call [`report`] once
+//! at startup to find out which of the protocols wrapped by this crate are
+//! present, and adapt or print a clear diagnostic:
+//!
+//! ```no_run
+//! use uefi::probe;
+//! use uefi::proto::console::gop::GraphicsOutput;
+//!
+//! let report = probe::report();
+//! if !report.supports::<GraphicsOutput>() {
+//!     log::warn!("No graphics output, falling back to text mode");
+//! }
+//! log::debug!("{report}");
+//! ```
+
+use crate::data_types::Identify;
+use crate::proto;
+use crate::table::Revision;
+use crate::{CStr16, Guid, boot, system};
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+
+/// Presence of a protocol.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ProtocolCapability {
+    /// Name of the protocol's type in this crate, including its module
+    /// relative to [`proto`].
+    pub name: &'static str,
+    /// GUID of the protocol.
+    pub guid: Guid,
+    /// Number of handles the protocol is installed on.
+    pub handles: usize,
+}
+
+impl ProtocolCapability {
+    /// Returns whether the protocol is installed on any handle.
+    #[must_use]
+    pub const fn is_present(&self) -> bool {
+        self.handles > 0
+    }
+}
+
+/// The capabilities of the firmware, returned by [`report`].
+#[derive(Clone, Debug)]
+pub struct CapabilityReport {
+    /// Revision of the UEFI specification implemented by the firmware.
+    pub uefi_revision: Revision,
+    /// Firmware vendor string.
+    pub firmware_vendor: &'static CStr16,
+    /// Vendor-specific firmware revision.
+    pub firmware_revision: u32,
+    /// Presence of each protocol wrapped by this crate.
+    pub protocols: Vec<ProtocolCapability>,
+}
+
+impl CapabilityReport {
+    /// Returns the capability for the protocol with `guid`, or `None` if it
+    /// is not wrapped by this crate.
+    #[must_use]
+    pub fn get(&self, guid: &Guid) -> Option<&ProtocolCapability> {
+        self.protocols
+            .iter()
+            .find(|protocol| protocol.guid == *guid)
+    }
+
+    /// Returns whether the protocol `P` is installed on any handle.
+    ///
+    /// Protocols not wrapped by this crate are always reported as missing;
+    /// use [`boot::locate_handle_buffer`] for those.
+    #[must_use]
+    pub fn supports<P: Identify + ?Sized>(&self) -> bool {
+        self.get(&P::GUID)
+            .is_some_and(ProtocolCapability::is_present)
+    }
+
+    /// Returns the protocols installed on at least one handle.
+    pub fn present(&self) -> impl Iterator<Item = &ProtocolCapability> {
+        self.protocols
+            .iter()
+            .filter(|protocol| protocol.is_present())
+    }
+
+    /// Returns the protocols not installed on any handle.
+    pub fn missing(&self) -> impl Iterator<Item = &ProtocolCapability> {
+        self.protocols
+            .iter()
+            .filter(|protocol| !protocol.is_present())
+    }
+}
+
+impl Display for CapabilityReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "UEFI {}, firmware {} revision {:#x}",
+            self.uefi_revision, self.firmware_vendor, self.firmware_revision
+        )?;
+        for protocol in &self.protocols {
+            if protocol.is_present() {
+                writeln!(f, "  {:<36} {} handle(s)", protocol.name, protocol.handles)?;
+            } else {
+                writeln!(f, "  {:<36} missing", protocol.name)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+macro_rules! protocols {
+    ($($path:ident $(:: $rest:ident)*),* $(,)?) => {
+        &[$((
+            stringify!($path $(:: $rest)*),
+            <proto::$path $(:: $rest)* as Identify>::GUID,
+        )),*]
+    };
+}
+
+/// Names and GUIDs of the protocols wrapped by this crate.
+const PROTOCOLS: &[(&str, Guid)] = protocols![
+    acpi::AcpiTable,
+    ata::pass_thru::AtaPassThru,
+    console::gop::GraphicsOutput,
+    console::pointer::Pointer,
+    console::serial::Serial,
+    console::text::Input,
+    console::text::InputEx,
+    console::text::Output,
+    debug::DebugPort,
+    debug::DebugSupport,
+    device_path::DevicePath,
+    device_path::LoadedImageDevicePath,
+    device_path::text::DevicePathFromText,
+    device_path::text::DevicePathToText,
+    device_path::util::DevicePathUtilities,
+    driver::DriverFamilyOverride,
+    driver::DriverSupportedEfiVersion,
+    driver::PlatformToDriverConfiguration,
+    hii::config::ConfigKeywordHandler,
+    hii::config::HiiConfigAccess,
+    hii::config_routing::HiiConfigRouting,
+    hii::database::HiiDatabase,
+    hii::font::HiiFont,
+    hii::image::HiiImage,
+    hii::popup::HiiPopup,
+    hii::form_browser::FormBrowser2,
+    hii::string::HiiString,
+    loaded_image::LoadedImage,
+    media::block::BlockIO,
+    media::disk::DiskIo,
+    media::disk::DiskIo2,
+    media::disk_info::DiskInfo,
+    media::fs::SimpleFileSystem,
+    media::load_file::LoadFile,
+    media::load_file::LoadFile2,
+    media::partition::PartitionInfo,
+    misc::ResetNotification,
+    misc::Timestamp,
+    network::http::Http,
+    network::http::HttpBinding,
+    network::ip4config2::Ip4Config2,
+    network::pxe::BaseCode,
+    network::snp::SimpleNetwork,
+    nvme::pass_thru::NvmePassThru,
+    pci::io::PciIo,
+    pci::root_bridge::PciRootBridgeIo,
+    pi::mp::MpServices,
+    rng::Rng,
+    scsi::pass_thru::ExtScsiPassThru,
+    security::MemoryProtection,
+    shell::Shell,
+    shell_params::ShellParameters,
+    shim::ShimLock,
+    string::unicode_collation::UnicodeCollation,
+    tcg::v1::Tcg,
+    tcg::v2::Tcg,
+    usb::io::UsbIo,
+];
+
+/// Checks which of the protocols wrapped by this crate are installed, and
+/// returns them along with the firmware and UEFI revisions.
+///
+/// Protocols are only located, not opened, so probing doesn't affect drivers
+/// managing them.
+#[must_use]
+pub fn report() -> CapabilityReport {
+    let protocols = PROTOCOLS
+        .iter()
+        .map(|&(name, guid)| ProtocolCapability {
+            name,
+            guid,
+            handles: boot::locate_handle_buffer(boot::SearchType::ByProtocol(&guid))
+                .map_or(0, |handles| handles.len()),
+        })
+        .collect();
+    CapabilityReport {
+        uefi_revision: system::uefi_revision(),
+        firmware_vendor: system::firmware_vendor(),
+        firmware_revision: system::firmware_revision(),
+        protocols,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::collections::BTreeSet;
+
+    #[test]
+    fn unique_guids() {
+        let guids: BTreeSet<_> = PROTOCOLS.iter().map(|(_, guid)| *guid).collect();
+        assert_eq!(guids.len(), PROTOCOLS.len());
+    }
+}
@@ -0,0 +1,346 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! ATA IDENTIFY DEVICE data.
+
+use crate::util::trim_ascii_field;
+use core::fmt::{self, Debug, Formatter};
+
+/// The ATA IDENTIFY DEVICE command.
+pub const ATA_CMD_IDENTIFY_DEVICE: u8 = 0xec;
+
+bitflags::bitflags! {
+    /// Features supported by an ATA device, from its [`AtaIdentifyData`].
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct AtaFeatures: u32 {
+        /// LBA addressing is supported.
+        const LBA = 1 << 0;
+        /// DMA transfers are supported.
+        const DMA = 1 << 1;
+        /// 48-bit LBA addressing is supported.
+        const LBA48 = 1 << 2;
+        /// The SMART feature set is supported.
+        const SMART = 1 << 3;
+        /// The SMART feature set is enabled.
+        const SMART_ENABLED = 1 << 4;
+        /// SMART self-tests are supported.
+        const SMART_SELF_TEST = 1 << 5;
+        /// The Security feature set is supported.
+        const SECURITY = 1 << 6;
+        /// The volatile write cache is supported.
+        const WRITE_CACHE = 1 << 7;
+        /// Native Command Queuing is supported (SATA).
+        const NCQ = 1 << 8;
+        /// The TRIM function of DATA SET MANAGEMENT is supported.
+        const TRIM = 1 << 9;
+    }
+}
+
+/// Decoded ATA IDENTIFY DEVICE data, returned by
+/// [`AtaDevice::identify`] or [`DiskInfo::ata_identify_data`].
+///
+/// [`AtaDevice::identify`]: super::pass_thru::AtaDevice::identify
+/// [`DiskInfo::ata_identify_data`]: crate::proto::media::disk_info::DiskInfo::ata_identify_data
+#[derive(Clone)]
+pub struct AtaIdentifyData {
+    words: [u16; 256],
+    serial_number: [u8; 20],
+    firmware_revision: [u8; 8],
+    model_number: [u8; 40],
+}
+
+impl AtaIdentifyData {
+    /// Size of the IDENTIFY DEVICE data in bytes.
+    pub const SIZE: usize = 512;
+
+    /// Decodes the IDENTIFY DEVICE data returned by a device. Returns `None`
+    /// if there are less than [`Self::SIZE`] bytes.
+    #[must_use]
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let bytes = bytes.get(..Self::SIZE)?;
+        let mut words = [0; 256];
+        for (word, chunk) in words.iter_mut().zip(bytes.chunks_exact(2)) {
+            *word = u16::from_le_bytes([chunk[0], chunk[1]]);
+        }
+        Some(Self {
+            serial_number: ata_string(&words[10..20]),
+            firmware_revision: ata_string(&words[23..27]),
+            model_number: ata_string(&words[27..47]),
+            words,
+        })
+    }
+
+    /// Returns the 256 words of the data.
+    #[must_use]
+    pub const fn words(&self) -> &[u16; 256] {
+        &self.words
+    }
+
+    /// Returns the serial number, without padding.
+    #[must_use]
+    pub fn serial_number(&self) -> &str {
+        trim_ascii_field(&self.serial_number)
+    }
+
+    /// Returns the firmware revision, without padding.
+    #[must_use]
+    pub fn firmware_revision(&self) -> &str {
+        trim_ascii_field(&self.firmware_revision)
+    }
+
+    /// Returns the model number, without padding.
+    #[must_use]
+    pub fn model_number(&self) -> &str {
+        trim_ascii_field(&self.model_number)
+    }
+
+    /// Returns whether the medium is removable.
+    #[must_use]
+    pub const fn is_removable(&self) -> bool {
+        self.words[0] & (1 << 7) != 0
+    }
+
+    /// Returns the features supported by the device.
+    #[must_use]
+    pub fn features(&self) -> AtaFeatures {
+        let w = &self.words;
+        let bit = |word: usize, bit: u32| w[word] & (1 << bit) != 0;
+        // Words 82 to 87 are only valid if bit 14 is set and bit 15 is
+        // clear.
+        let valid = |word: usize| w[word] & 0xc000 == 0x4000;
+        let command_set = |word: usize, b: u32| valid(word) && bit(word, b);
+
+        let mut features = AtaFeatures::empty();
+        features.set(AtaFeatures::LBA, bit(49, 9));
+        features.set(AtaFeatures::DMA, bit(49, 8));
+        features.set(AtaFeatures::LBA48, command_set(83, 10));
+        features.set(AtaFeatures::SMART, command_set(82, 0));
+        features.set(AtaFeatures::SMART_ENABLED, command_set(85, 0));
+        features.set(AtaFeatures::SMART_SELF_TEST, command_set(84, 1));
+        features.set(AtaFeatures::SECURITY, command_set(82, 1));
+        features.set(AtaFeatures::WRITE_CACHE, command_set(82, 5));
+        // Word 76 is reserved for parallel ATA devices, which report 0 or
+        // 0xffff.
+        features.set(AtaFeatures::NCQ, w[76] != 0xffff && bit(76, 8));
+        features.set(AtaFeatures::TRIM, bit(169, 0));
+        features
+    }
+
+    /// Returns the number of user addressable logical sectors.
+    #[must_use]
+    pub fn sector_count(&self) -> u64 {
+        let w = &self.words;
+        if self.features().contains(AtaFeatures::LBA48) {
+            let count = w[100..104]
+                .iter()
+                .rev()
+                .fold(0, |count, &word| (count << 16) | u64::from(word));
+            if count != 0 {
+                return count;
+            }
+        }
+        (u64::from(w[61]) << 16) | u64::from(w[60])
+    }
+
+    /// Returns the size of a logical sector in bytes, or `None` if the
+    /// reported size doesn't fit in a `u32`.
+    #[must_use]
+    pub fn logical_sector_size(&self) -> Option<u32> {
+        let w = &self.words;
+        if self.sector_size_valid() && w[106] & (1 << 12) != 0 {
+            let words = (u32::from(w[118]) << 16) | u32::from(w[117]);
+            if words != 0 {
+                return words.checked_mul(2);
+            }
+        }
+        Some(512)
+    }
+
+    /// Returns the size of a physical sector in bytes, or `None` if the
+    /// reported size doesn't fit in a `u32`.
+    #[must_use]
+    pub fn physical_sector_size(&self) -> Option<u32> {
+        let w = &self.words;
+        let logical = self.logical_sector_size()?;
+        if self.sector_size_valid() && w[106] & (1 << 13) != 0 {
+            logical.checked_mul(1 << (w[106] & 0xf))
+        } else {
+            Some(logical)
+        }
+    }
+
+    /// Returns the capacity of the device in bytes, or `None` if it doesn't
+    /// fit in a `u64`.
+    #[must_use]
+    pub fn capacity(&self) -> Option<u64> {
+        self.sector_count()
+            .checked_mul(u64::from(self.logical_sector_size()?))
+    }
+
+    /// Returns the nominal media rotation rate in revolutions per minute,
+    /// `Some(0)` for solid state devices, or `None` if it is not reported.
+    #[must_use]
+    pub const fn rotation_rate(&self) -> Option<u16> {
+        match self.words[217] {
+            1 => Some(0),
+            rpm @ 0x0401..=0xfffe => Some(rpm),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the checksum in word 255 is valid. Data without
+    /// checksum is considered valid.
+    #[must_use]
+    pub fn is_checksum_valid(&self) -> bool {
+        if self.words[255] & 0xff != 0xa5 {
+            return true;
+        }
+        let sum = self
+            .words
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .fold(0u8, u8::wrapping_add);
+        sum == 0
+    }
+
+    /// Returns whether word 106 is valid.
+    const fn sector_size_valid(&self) -> bool {
+        self.words[106] & 0xc000 == 0x4000
+    }
+}
+
+impl Debug for AtaIdentifyData {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AtaIdentifyData")
+            .field("model_number", &self.model_number())
+            .field("serial_number", &self.serial_number())
+            .field("firmware_revision", &self.firmware_revision())
+            .field("sector_count", &self.sector_count())
+            .field("logical_sector_size", &self.logical_sector_size())
+            .field("features", &self.features())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Decodes an ATA string, which stores two characters per word with the
+/// first in the high byte.
+fn ata_string<const N: usize>(words: &[u16]) -> [u8; N] {
+    let mut bytes = [0; N];
+    for (chunk, word) in bytes.chunks_exact_mut(2).zip(words) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::format;
+
+    /// Encodes `s` as an ATA string in `words`.
+    fn set_string(words: &mut [u16], s: &str) {
+        let mut bytes = s.as_bytes().to_vec();
+        bytes.resize(words.len() * 2, b' ');
+        for (word, chunk) in words.iter_mut().zip(bytes.chunks_exact(2)) {
+            *word = u16::from_be_bytes([chunk[0], chunk[1]]);
+        }
+    }
+
+    fn identify_bytes() -> [u8; 512] {
+        let mut words = [0u16; 256];
+        words[0] = 0x0040;
+        set_string(&mut words[10..20], "AtaPassThru");
+        set_string(&mut words[23..27], "1.0");
+        set_string(&mut words[27..47], "QEMU HARDDISK");
+        words[49] = (1 << 9) | (1 << 8);
+        words[60] = 0xffff;
+        words[61] = 0x0fff;
+        words[76] = 1 << 8;
+        words[82] = 0x4000 | (1 << 5) | 1;
+        words[83] = 0x4000 | (1 << 10);
+        words[84] = 0x4000 | (1 << 1);
+        words[85] = 0x4000 | 1;
+        // 2^32 sectors.
+        words[100..104].copy_from_slice(&[0, 0, 1, 0]);
+        // 4 KiB physical sectors.
+        words[106] = 0x4000 | (1 << 13) | 3;
+        words[169] = 1;
+        words[217] = 1;
+        words[255] = 0xa5;
+
+        let mut bytes = [0; 512];
+        for (chunk, word) in bytes.chunks_exact_mut(2).zip(words) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        let sum = bytes.iter().fold(0u8, |sum, &b| sum.wrapping_add(b));
+        bytes[511] = 0u8.wrapping_sub(sum);
+        bytes
+    }
+
+    #[test]
+    fn test_identify() {
+        let bytes = identify_bytes();
+        let data = AtaIdentifyData::parse(&bytes).unwrap();
+        assert_eq!(data.serial_number(), "AtaPassThru");
+        assert_eq!(data.firmware_revision(), "1.0");
+        assert_eq!(data.model_number(), "QEMU HARDDISK");
+        assert!(!data.is_removable());
+        assert_eq!(
+            data.features(),
+            AtaFeatures::LBA
+                | AtaFeatures::DMA
+                | AtaFeatures::LBA48
+                | AtaFeatures::SMART
+                | AtaFeatures::SMART_ENABLED
+                | AtaFeatures::SMART_SELF_TEST
+                | AtaFeatures::WRITE_CACHE
+                | AtaFeatures::NCQ
+                | AtaFeatures::TRIM
+        );
+        assert_eq!(data.sector_count(), 1 << 32);
+        assert_eq!(data.logical_sector_size(), Some(512));
+        assert_eq!(data.physical_sector_size(), Some(4096));
+        assert_eq!(data.capacity(), Some(512 << 32));
+        assert_eq!(data.rotation_rate(), Some(0));
+        assert!(data.is_checksum_valid());
+
+        let mut corrupted = bytes;
+        corrupted[100] ^= 1;
+        assert!(
+            !AtaIdentifyData::parse(&corrupted)
+                .unwrap()
+                .is_checksum_valid()
+        );
+        assert!(AtaIdentifyData::parse(&bytes[..511]).is_none());
+    }
+
+    #[test]
+    fn test_identify_lba28() {
+        let mut bytes = identify_bytes();
+        // Clear 48-bit support.
+        bytes[167] = 0x40;
+        bytes[166] = 0;
+        let data = AtaIdentifyData::parse(&bytes).unwrap();
+        assert!(!data.features().contains(AtaFeatures::LBA48));
+        assert_eq!(data.sector_count(), 0x0fff_ffff);
+    }
+
+    #[test]
+    fn test_identify_sizes_overflow() {
+        let mut bytes = identify_bytes();
+        // Logical sectors of 2^32 bytes.
+        bytes[212..214].copy_from_slice(&(0x4000u16 | (1 << 13) | (1 << 12) | 3).to_le_bytes());
+        bytes[234..238].copy_from_slice(&[0, 0, 0, 0x80]);
+        let data = AtaIdentifyData::parse(&bytes).unwrap();
+        assert_eq!(data.logical_sector_size(), None);
+        assert_eq!(data.physical_sector_size(), None);
+        assert_eq!(data.capacity(), None);
+        assert!(format!("{data:?}").contains("logical_sector_size: None"));
+
+        // 2^64 - 1 sectors of 512 bytes.
+        let mut bytes = identify_bytes();
+        bytes[200..208].fill(0xff);
+        let data = AtaIdentifyData::parse(&bytes).unwrap();
+        assert_eq!(data.sector_count(), u64::MAX);
+        assert_eq!(data.capacity(), None);
+    }
+}
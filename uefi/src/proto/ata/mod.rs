@@ -12,7 +12,9 @@ use uefi_raw::protocol::ata::{
     AtaCommandBlock, AtaPassThruCommandPacket, AtaPassThruLength, AtaStatusBlock,
 };
 
+pub mod identify;
 pub mod pass_thru;
+pub mod smart;
 
 /// Represents the protocol for ATA Pass Thru command handling.
 ///
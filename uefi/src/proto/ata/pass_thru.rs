@@ -2,13 +2,19 @@
 
 //! ATA Pass Thru Protocol.
 
-use super::{AtaRequest, AtaResponse};
+use super::identify::{ATA_CMD_IDENTIFY_DEVICE, AtaIdentifyData};
+use super::smart::{
+    ATA_CMD_SMART, ATA_SMART_READ_DATA, ATA_SMART_READ_THRESHOLDS, ATA_SMART_SIGNATURE,
+    AtaSmartData,
+};
+use super::{AtaRequest, AtaRequestBuilder, AtaResponse};
 use crate::StatusExt;
 use crate::mem::{AlignedBuffer, PoolAllocation};
 use crate::proto::device_path::PoolDevicePathNode;
 use core::alloc::LayoutError;
 use core::cell::UnsafeCell;
 use core::ptr::{self, NonNull};
+use core::time::Duration;
 use uefi_macros::unsafe_protocol;
 use uefi_raw::Status;
 use uefi_raw::protocol::ata::AtaPassThruProtocol;
@@ -164,6 +170,79 @@ impl AtaDevice<'_> {
         }
     }
 
+    /// Sends an ATA IDENTIFY DEVICE command to the device, and decodes the
+    /// returned data.
+    ///
+    /// This can also be used to probe whether a device is connected.
+    ///
+    /// # Errors
+    /// - [`Status::OUT_OF_RESOURCES`] The I/O buffers could not be allocated.
+    /// - [`Status::DEVICE_ERROR`] The device returned less than [`AtaIdentifyData::SIZE`] bytes.
+    /// - See [`AtaDevice::execute_command`] for other errors, e.g. if no device is connected.
+    pub fn identify(&mut self) -> crate::Result<AtaIdentifyData> {
+        self.read_pio(
+            |io_align| AtaRequestBuilder::read_pio(io_align, ATA_CMD_IDENTIFY_DEVICE),
+            AtaIdentifyData::SIZE,
+            AtaIdentifyData::parse,
+        )
+    }
+
+    /// Reads the SMART data and attribute thresholds of the device. The
+    /// thresholds are omitted if the device does not return them.
+    ///
+    /// The SMART feature set must be enabled, see [`AtaFeatures::SMART_ENABLED`].
+    ///
+    /// # Errors
+    /// - [`Status::OUT_OF_RESOURCES`] The I/O buffers could not be allocated.
+    /// - [`Status::DEVICE_ERROR`] The device returned less than [`AtaSmartData::SIZE`] bytes.
+    /// - See [`AtaDevice::execute_command`] for other errors, e.g. if SMART is disabled.
+    ///
+    /// [`AtaFeatures::SMART_ENABLED`]: super::identify::AtaFeatures::SMART_ENABLED
+    pub fn smart_data(&mut self) -> crate::Result<AtaSmartData> {
+        let data = self.read_smart(ATA_SMART_READ_DATA)?;
+        let thresholds = self.read_smart(ATA_SMART_READ_THRESHOLDS).ok();
+        AtaSmartData::parse(&data, thresholds.as_ref().map(|t| t.as_slice()))
+            .ok_or_else(|| Status::DEVICE_ERROR.into())
+    }
+
+    /// Sends the SMART subcommand `feature`, which reads one sector.
+    fn read_smart(&mut self, feature: u8) -> crate::Result<[u8; AtaSmartData::SIZE]> {
+        let (mid, high) = ATA_SMART_SIGNATURE;
+        self.read_pio(
+            |io_align| {
+                Ok(AtaRequestBuilder::read_pio(io_align, ATA_CMD_SMART)?
+                    .with_features(feature)
+                    .with_sector_count(1)
+                    .with_cylinder(mid, high))
+            },
+            AtaSmartData::SIZE,
+            |bytes| bytes.get(..AtaSmartData::SIZE)?.try_into().ok(),
+        )
+    }
+
+    /// Sends the PIO data-in command created by `builder`, reading `len`
+    /// bytes, and decodes the returned data with `parse`.
+    fn read_pio<T>(
+        &mut self,
+        builder: impl FnOnce(u32) -> Result<AtaRequestBuilder<'static>, LayoutError>,
+        len: usize,
+        parse: impl FnOnce(&[u8]) -> Option<T>,
+    ) -> crate::Result<T> {
+        let io_align = unsafe { (*(*self.proto.get()).mode).io_align.max(1) };
+        let request = builder(io_align)
+            .and_then(|builder| builder.with_read_buffer(len))
+            .map_err(|_| Status::OUT_OF_RESOURCES)?
+            .with_timeout(Duration::from_secs(3))
+            .build();
+        let response = self
+            .execute_command(request)
+            .map_err(|err| err.to_err_without_payload())?;
+        response
+            .read_buffer()
+            .and_then(parse)
+            .ok_or_else(|| Status::DEVICE_ERROR.into())
+    }
+
     /// Executes a command on the device.
     ///
     /// # Arguments
@@ -0,0 +1,274 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! ATA SMART (Self-Monitoring, Analysis and Reporting Technology) data.
+
+use core::fmt::{self, Debug, Formatter};
+
+/// The ATA SMART command. The subcommand is passed in the features
+/// register.
+pub const ATA_CMD_SMART: u8 = 0xb0;
+
+/// The SMART READ DATA subcommand.
+pub const ATA_SMART_READ_DATA: u8 = 0xd0;
+
+/// The SMART READ ATTRIBUTE THRESHOLDS subcommand. This is obsolete since
+/// ATA-8, but still implemented by most devices.
+pub const ATA_SMART_READ_THRESHOLDS: u8 = 0xd1;
+
+/// Value of the LBA mid and LBA high registers for SMART commands.
+pub const ATA_SMART_SIGNATURE: (u8, u8) = (0x4f, 0xc2);
+
+/// Number of attribute entries in the SMART data.
+const ATTRIBUTE_COUNT: usize = 30;
+
+/// Size of an attribute entry in the SMART data.
+const ATTRIBUTE_SIZE: usize = 12;
+
+/// A vendor-specific SMART attribute, from [`AtaSmartData::attributes`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct AtaSmartAttribute {
+    /// Attribute ID. The meaning of most IDs is de facto standardized, see
+    /// [`Self::name`].
+    pub id: u8,
+    /// Status flags.
+    pub flags: u16,
+    /// Normalized value, usually between 1 and 253. Lower values are worse.
+    pub value: u8,
+    /// Worst normalized value seen.
+    pub worst: u8,
+    /// Vendor-specific raw value.
+    pub raw: [u8; 6],
+    /// Threshold of the normalized value, or `None` if the thresholds could
+    /// not be read.
+    pub threshold: Option<u8>,
+}
+
+impl AtaSmartAttribute {
+    /// Returns whether the attribute predicts an imminent failure when it
+    /// reaches its threshold. Other attributes indicate wear.
+    #[must_use]
+    pub const fn is_prefailure(&self) -> bool {
+        self.flags & 1 != 0
+    }
+
+    /// Returns the raw value as an integer. Most devices store counters in
+    /// the lower bytes.
+    #[must_use]
+    pub fn raw_value(&self) -> u64 {
+        let mut bytes = [0; 8];
+        bytes[..6].copy_from_slice(&self.raw);
+        u64::from_le_bytes(bytes)
+    }
+
+    /// Returns whether the normalized value is at or below its threshold.
+    /// A threshold of zero means the attribute never fails.
+    #[must_use]
+    pub fn is_failing(&self) -> bool {
+        self.threshold
+            .is_some_and(|threshold| threshold != 0 && self.value <= threshold)
+    }
+
+    /// Returns the commonly used name of the attribute, or `None` if its ID
+    /// is not widely known.
+    #[must_use]
+    pub const fn name(&self) -> Option<&'static str> {
+        Some(match self.id {
+            1 => "Raw Read Error Rate",
+            3 => "Spin Up Time",
+            4 => "Start/Stop Count",
+            5 => "Reallocated Sector Count",
+            7 => "Seek Error Rate",
+            9 => "Power-On Hours",
+            10 => "Spin Retry Count",
+            12 => "Power Cycle Count",
+            177 => "Wear Leveling Count",
+            183 => "Runtime Bad Block",
+            184 => "End-to-End Error",
+            187 => "Reported Uncorrectable Errors",
+            188 => "Command Timeout",
+            190 => "Airflow Temperature",
+            194 => "Temperature",
+            196 => "Reallocation Event Count",
+            197 => "Current Pending Sector Count",
+            198 => "Offline Uncorrectable",
+            199 => "UDMA CRC Error Count",
+            231 => "SSD Life Left",
+            241 => "Total LBAs Written",
+            242 => "Total LBAs Read",
+            _ => return None,
+        })
+    }
+}
+
+/// SMART data, returned by [`AtaDevice::smart_data`].
+///
+/// [`AtaDevice::smart_data`]: super::pass_thru::AtaDevice::smart_data
+#[derive(Clone)]
+pub struct AtaSmartData {
+    data: [u8; Self::SIZE],
+    thresholds: Option<[u8; Self::SIZE]>,
+}
+
+impl AtaSmartData {
+    /// Size of the SMART data and of the thresholds in bytes.
+    pub const SIZE: usize = 512;
+
+    /// Decodes the data returned by SMART READ DATA, and optionally the
+    /// thresholds returned by SMART READ ATTRIBUTE THRESHOLDS. Returns `None`
+    /// if `data` has less than [`Self::SIZE`] bytes. Thresholds with less
+    /// than [`Self::SIZE`] bytes are ignored.
+    #[must_use]
+    pub fn parse(data: &[u8], thresholds: Option<&[u8]>) -> Option<Self> {
+        Some(Self {
+            data: data.get(..Self::SIZE)?.try_into().ok()?,
+            thresholds: thresholds
+                .and_then(|thresholds| thresholds.get(..Self::SIZE)?.try_into().ok()),
+        })
+    }
+
+    /// Returns the raw SMART data.
+    #[must_use]
+    pub const fn bytes(&self) -> &[u8; Self::SIZE] {
+        &self.data
+    }
+
+    /// Returns the raw thresholds, if they were read.
+    #[must_use]
+    pub const fn threshold_bytes(&self) -> Option<&[u8; Self::SIZE]> {
+        self.thresholds.as_ref()
+    }
+
+    /// Returns the attributes reported by the device.
+    pub fn attributes(&self) -> impl Iterator<Item = AtaSmartAttribute> + '_ {
+        entries(&self.data).map(|entry| {
+            let id = entry[0];
+            AtaSmartAttribute {
+                id,
+                flags: u16::from_le_bytes([entry[1], entry[2]]),
+                value: entry[3],
+                worst: entry[4],
+                raw: entry[5..11].try_into().unwrap(),
+                threshold: self.threshold(id),
+            }
+        })
+    }
+
+    /// Returns the attribute with `id`, or `None` if it is not reported.
+    #[must_use]
+    pub fn attribute(&self, id: u8) -> Option<AtaSmartAttribute> {
+        self.attributes().find(|attribute| attribute.id == id)
+    }
+
+    /// Returns the current temperature in degrees Celsius, or `None` if it
+    /// is not reported.
+    #[must_use]
+    pub fn temperature(&self) -> Option<u8> {
+        // The lowest raw byte holds the current temperature; drives may
+        // store minimum and maximum values in the other bytes.
+        self.attribute(194)
+            .or_else(|| self.attribute(190))
+            .map(|attribute| attribute.raw[0])
+    }
+
+    /// Returns the status of the last or current self-test: 0 if it
+    /// completed without error or was never run, 1 and 2 if it was
+    /// interrupted, 3 to 8 if it failed, and 15 if it is in progress.
+    #[must_use]
+    pub const fn self_test_status(&self) -> u8 {
+        self.data[363] >> 4
+    }
+
+    /// Returns whether the last self-test failed.
+    #[must_use]
+    pub const fn is_self_test_failed(&self) -> bool {
+        matches!(self.self_test_status(), 3..=8)
+    }
+
+    /// Returns whether the checksum of the data is valid.
+    #[must_use]
+    pub fn is_checksum_valid(&self) -> bool {
+        self.data.iter().fold(0u8, |sum, &b| sum.wrapping_add(b)) == 0
+    }
+
+    fn threshold(&self, id: u8) -> Option<u8> {
+        entries(self.thresholds.as_ref()?)
+            .find(|entry| entry[0] == id)
+            .map(|entry| entry[1])
+    }
+}
+
+impl Debug for AtaSmartData {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AtaSmartData")
+            .field("temperature", &self.temperature())
+            .field("self_test_status", &self.self_test_status())
+            .field("attributes", &self.attributes().count())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Returns the used attribute (or threshold) entries, which follow the
+/// 2-byte revision number.
+fn entries(bytes: &[u8; AtaSmartData::SIZE]) -> impl Iterator<Item = &[u8]> {
+    bytes[2..2 + ATTRIBUTE_COUNT * ATTRIBUTE_SIZE]
+        .chunks_exact(ATTRIBUTE_SIZE)
+        .filter(|entry| entry[0] != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set_entry(bytes: &mut [u8], index: usize, entry: &[u8]) {
+        let offset = 2 + index * ATTRIBUTE_SIZE;
+        bytes[offset..offset + entry.len()].copy_from_slice(entry);
+    }
+
+    #[test]
+    fn test_smart_data() {
+        let mut data = [0u8; 512];
+        let mut thresholds = [0u8; 512];
+        // Reallocated Sector Count: pre-failure, value 100, 3 sectors.
+        set_entry(&mut data, 0, &[5, 0x33, 0, 100, 100, 3, 0, 0, 0, 0, 0]);
+        // Power-On Hours: 0x1234 hours.
+        set_entry(&mut data, 1, &[9, 0x32, 0, 98, 98, 0x34, 0x12, 0, 0, 0, 0]);
+        // Temperature: 35 °C, with min/max in the other bytes.
+        set_entry(&mut data, 2, &[194, 0x22, 0, 65, 50, 35, 0, 20, 0, 50, 0]);
+        // Spin Up Time: pre-failure, below threshold.
+        set_entry(&mut data, 4, &[3, 0x27, 0, 20, 20, 0, 0, 0, 0, 0, 0]);
+        data[363] = 0x70;
+        let sum = data.iter().fold(0u8, |sum, &b| sum.wrapping_add(b));
+        data[511] = 0u8.wrapping_sub(sum);
+
+        // Thresholds are listed in a different order.
+        set_entry(&mut thresholds, 0, &[3, 21]);
+        set_entry(&mut thresholds, 1, &[5, 36]);
+
+        let smart = AtaSmartData::parse(&data, Some(&thresholds)).unwrap();
+        assert!(smart.is_checksum_valid());
+        assert_eq!(smart.attributes().count(), 4);
+        assert_eq!(smart.temperature(), Some(35));
+        assert_eq!(smart.self_test_status(), 7);
+        assert!(smart.is_self_test_failed());
+
+        let reallocated = smart.attribute(5).unwrap();
+        assert_eq!(reallocated.name(), Some("Reallocated Sector Count"));
+        assert!(reallocated.is_prefailure());
+        assert_eq!(reallocated.raw_value(), 3);
+        assert_eq!(reallocated.threshold, Some(36));
+        assert!(!reallocated.is_failing());
+
+        let power_on = smart.attribute(9).unwrap();
+        assert!(!power_on.is_prefailure());
+        assert_eq!(power_on.raw_value(), 0x1234);
+        assert_eq!(power_on.threshold, None);
+
+        assert!(smart.attribute(3).unwrap().is_failing());
+        assert!(smart.attribute(1).is_none());
+
+        let smart = AtaSmartData::parse(&data, Some(&thresholds[..100])).unwrap();
+        assert!(smart.threshold_bytes().is_none());
+        assert!(!smart.attribute(3).unwrap().is_failing());
+        assert!(AtaSmartData::parse(&data[..511], None).is_none());
+    }
+}
@@ -0,0 +1,590 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Decoding and encoding of BMP images.
+
+use super::{BltPixel, Canvas};
+use core::fmt::{self, Display, Formatter};
+
+#[cfg(feature = "alloc")]
+use {
+    super::{BltOp, BltRegion, GraphicsOutput},
+    crate::proto::hii::BltImage,
+    crate::{Result, Status},
+    alloc::vec::Vec,
+};
+
+/// Size of the file header in bytes.
+const FILE_HEADER_SIZE: usize = 14;
+/// Size of the `BITMAPCOREHEADER` in bytes.
+const CORE_HEADER_SIZE: usize = 12;
+/// Size of the `BITMAPINFOHEADER` in bytes.
+const INFO_HEADER_SIZE: usize = 40;
+
+/// `BI_RGB` compression: uncompressed.
+const BI_RGB: u32 = 0;
+/// `BI_BITFIELDS` compression: uncompressed, with color masks.
+const BI_BITFIELDS: u32 = 3;
+/// `BI_ALPHABITFIELDS` compression: uncompressed, with color and alpha
+/// masks.
+const BI_ALPHABITFIELDS: u32 = 6;
+
+/// Number of pixels decoded at once by [`Bmp::draw`].
+const CHUNK: usize = 64;
+
+/// Error returned when parsing a BMP image.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BmpError {
+    /// The data doesn't start with the `BM` signature.
+    InvalidSignature,
+    /// The header contains invalid values.
+    InvalidHeader,
+    /// The image uses a feature that is not supported, e.g. compression.
+    Unsupported,
+    /// The data ends before the pixels described by the header.
+    Truncated,
+}
+
+impl Display for BmpError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidSignature => write!(f, "not a BMP image"),
+            Self::InvalidHeader => write!(f, "invalid BMP header"),
+            Self::Unsupported => write!(f, "unsupported BMP format"),
+            Self::Truncated => write!(f, "BMP data is truncated"),
+        }
+    }
+}
+
+impl core::error::Error for BmpError {}
+
+/// How pixels are stored.
+#[derive(Clone, Copy, Debug)]
+enum Format<'a> {
+    /// 1, 4, or 8 bits per pixel, indexing the palette.
+    Indexed {
+        bits: usize,
+        palette: &'a [u8],
+        entry_size: usize,
+    },
+    /// 24 bits per pixel, in blue, green, red order.
+    Bgr,
+    /// 16 or 32 bits per pixel, with the red, green, and blue masks.
+    Masks { bits: usize, masks: [u32; 3] },
+}
+
+/// Extracts the channel selected by `mask` from `value`, scaled to 8 bits.
+const fn channel(value: u32, mask: u32) -> u8 {
+    if mask == 0 {
+        return 0;
+    }
+    let bits = mask.count_ones();
+    let value = (value & mask) >> mask.trailing_zeros();
+    if bits >= 8 {
+        (value >> (bits - 8)) as u8
+    } else {
+        (value * 0xff / ((1 << bits) - 1)) as u8
+    }
+}
+
+/// Returns whether the bits set in `mask` are contiguous, as [`channel`]
+/// requires.
+const fn is_contiguous(mask: u32) -> bool {
+    if mask == 0 {
+        return true;
+    }
+    let shifted = mask >> mask.trailing_zeros();
+    shifted & shifted.wrapping_add(1) == 0
+}
+
+/// Reads a little-endian `u16` at `offset`.
+fn u16_at(data: &[u8], offset: usize) -> core::result::Result<u16, BmpError> {
+    let bytes = data.get(offset..offset + 2).ok_or(BmpError::Truncated)?;
+    Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+/// Reads a little-endian `u32` at `offset`.
+fn u32_at(data: &[u8], offset: usize) -> core::result::Result<u32, BmpError> {
+    let bytes = data.get(offset..offset + 4).ok_or(BmpError::Truncated)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// A BMP image, the format of firmware logos and boot splash screens.
+///
+/// Uncompressed images with 1, 4, 8, 16, 24, or 32 bits per pixel are
+/// supported, including color masks (`BI_BITFIELDS`) whose bits are
+/// contiguous. Transparency is ignored. The image is decoded on the fly from
+/// the borrowed data, so drawing it with [`draw`] doesn't allocate.
+///
+/// # Example
+///
+/// ```no_run
+/// use uefi::proto::console::gop::GraphicsOutput;
+///
+/// # fn example(gop: &mut GraphicsOutput) -> uefi::Result {
+/// static LOGO: &[u8] = &[/* include_bytes!("logo.bmp") */];
+/// gop.draw_bmp(LOGO, (100, 100))?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`draw`]: Self::draw
+#[derive(Clone, Copy, Debug)]
+pub struct Bmp<'a> {
+    /// The pixel array.
+    pixels: &'a [u8],
+    width: usize,
+    height: usize,
+    /// Whether the first row of the pixel array is the top row.
+    top_down: bool,
+    /// Size of a row of the pixel array in bytes.
+    stride: usize,
+    format: Format<'a>,
+}
+
+impl<'a> Bmp<'a> {
+    /// Parses the headers of a BMP file.
+    ///
+    /// # Errors
+    ///
+    /// See [`BmpError`].
+    pub fn parse(data: &'a [u8]) -> core::result::Result<Self, BmpError> {
+        if !data.starts_with(b"BM") {
+            return Err(BmpError::InvalidSignature);
+        }
+        let pixel_offset = u32_at(data, 10)? as usize;
+        let dib = FILE_HEADER_SIZE;
+        let header_size = u32_at(data, dib)? as usize;
+
+        let (width, height, planes, bits, compression, colors_used) = match header_size {
+            CORE_HEADER_SIZE => (
+                i64::from(u16_at(data, dib + 4)?),
+                i64::from(u16_at(data, dib + 6)?),
+                u16_at(data, dib + 8)?,
+                u16_at(data, dib + 10)?,
+                BI_RGB,
+                0,
+            ),
+            INFO_HEADER_SIZE.. => (
+                i64::from(u32_at(data, dib + 4)? as i32),
+                i64::from(u32_at(data, dib + 8)? as i32),
+                u16_at(data, dib + 12)?,
+                u16_at(data, dib + 14)?,
+                u32_at(data, dib + 16)?,
+                u32_at(data, dib + 32)? as usize,
+            ),
+            _ => return Err(BmpError::Unsupported),
+        };
+        if width <= 0 || height == 0 || planes != 1 {
+            return Err(BmpError::InvalidHeader);
+        }
+        let width = usize::try_from(width).map_err(|_| BmpError::InvalidHeader)?;
+        let top_down = height < 0;
+        let height = usize::try_from(height.unsigned_abs()).map_err(|_| BmpError::InvalidHeader)?;
+        let bits = usize::from(bits);
+
+        let mut palette_offset = dib + header_size;
+        let format = match (bits, compression) {
+            (1 | 4 | 8, BI_RGB) => {
+                let entry_size = if header_size == CORE_HEADER_SIZE {
+                    3
+                } else {
+                    4
+                };
+                let count = match colors_used {
+                    0 => 1 << bits,
+                    count => count.min(1 << bits),
+                };
+                // Some files omit unused palette entries.
+                let palette_end = pixel_offset.min(palette_offset + count * entry_size);
+                let palette = data
+                    .get(palette_offset..palette_end)
+                    .ok_or(BmpError::InvalidHeader)?;
+                Format::Indexed {
+                    bits,
+                    palette,
+                    entry_size,
+                }
+            }
+            (24, BI_RGB) => Format::Bgr,
+            (16, BI_RGB) => Format::Masks {
+                bits,
+                masks: [0x7c00, 0x03e0, 0x001f],
+            },
+            (32, BI_RGB) => Format::Masks {
+                bits,
+                masks: [0x00ff_0000, 0x0000_ff00, 0x0000_00ff],
+            },
+            (16 | 32, BI_BITFIELDS | BI_ALPHABITFIELDS) => {
+                // The masks follow a `BITMAPINFOHEADER`, and are part of the
+                // later versions of the header.
+                let masks = [
+                    u32_at(data, dib + INFO_HEADER_SIZE)?,
+                    u32_at(data, dib + INFO_HEADER_SIZE + 4)?,
+                    u32_at(data, dib + INFO_HEADER_SIZE + 8)?,
+                ];
+                if !masks.iter().all(|mask| is_contiguous(*mask)) {
+                    return Err(BmpError::Unsupported);
+                }
+                if header_size == INFO_HEADER_SIZE {
+                    palette_offset += if compression == BI_BITFIELDS { 12 } else { 16 };
+                }
+                Format::Masks { bits, masks }
+            }
+            (1 | 4 | 8 | 16 | 24 | 32, _) => return Err(BmpError::Unsupported),
+            _ => return Err(BmpError::InvalidHeader),
+        };
+        if pixel_offset < palette_offset {
+            return Err(BmpError::InvalidHeader);
+        }
+
+        let stride = width
+            .checked_mul(bits)
+            .map(|row_bits| row_bits.div_ceil(32) * 4)
+            .ok_or(BmpError::InvalidHeader)?;
+        let len = stride.checked_mul(height).ok_or(BmpError::InvalidHeader)?;
+        let pixels = data
+            .get(pixel_offset..)
+            .and_then(|pixels| pixels.get(..len))
+            .ok_or(BmpError::Truncated)?;
+        Ok(Self {
+            pixels,
+            width,
+            height,
+            top_down,
+            stride,
+            format,
+        })
+    }
+
+    /// Returns the width in pixels.
+    #[must_use]
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the height in pixels.
+    #[must_use]
+    pub const fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the bytes of row `y`, counted from the top.
+    fn row(&self, y: usize) -> &'a [u8] {
+        let row = if self.top_down {
+            y
+        } else {
+            self.height - 1 - y
+        };
+        &self.pixels[row * self.stride..][..self.stride]
+    }
+
+    /// Decodes pixel `x` of `row`.
+    fn decode(&self, row: &[u8], x: usize) -> BltPixel {
+        match self.format {
+            Format::Indexed {
+                bits,
+                palette,
+                entry_size,
+            } => {
+                let bit = x * bits;
+                let index = usize::from(row[bit / 8] >> (8 - bits - bit % 8)) & ((1 << bits) - 1);
+                match palette.get(index * entry_size..index * entry_size + 3) {
+                    Some(&[blue, green, red]) => BltPixel::new(red, green, blue),
+                    _ => BltPixel::new(0, 0, 0),
+                }
+            }
+            Format::Bgr => BltPixel::new(row[x * 3 + 2], row[x * 3 + 1], row[x * 3]),
+            Format::Masks { bits, masks } => {
+                let bytes = &row[x * bits / 8..];
+                let value = if bits == 16 {
+                    u32::from(u16::from_le_bytes([bytes[0], bytes[1]]))
+                } else {
+                    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+                };
+                BltPixel::new(
+                    channel(value, masks[0]),
+                    channel(value, masks[1]),
+                    channel(value, masks[2]),
+                )
+            }
+        }
+    }
+
+    /// Returns the pixel at (`x`, `y`), or `None` if it is outside of the
+    /// image.
+    #[must_use]
+    pub fn pixel(&self, x: usize, y: usize) -> Option<BltPixel> {
+        (x < self.width && y < self.height).then(|| self.decode(self.row(y), x))
+    }
+
+    /// Decodes row `y`, counted from the top, into `out`, starting at column
+    /// `x`. Decodes as many pixels as fit into `out`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `y` is not less than the height.
+    pub fn decode_row(&self, x: usize, y: usize, out: &mut [BltPixel]) {
+        let row = self.row(y);
+        for (x, pixel) in (x..self.width).zip(out) {
+            *pixel = self.decode(row, x);
+        }
+    }
+
+    /// Draws the image with its top-left corner at `pos`.
+    pub fn draw<C: Canvas + ?Sized>(&self, canvas: &mut C, pos: (usize, usize)) {
+        let (canvas_width, canvas_height) = canvas.size();
+        let width = self.width.min(canvas_width.saturating_sub(pos.0));
+        let height = self.height.min(canvas_height.saturating_sub(pos.1));
+        let mut chunk = [BltPixel::new(0, 0, 0); CHUNK];
+        for y in 0..height {
+            for x in (0..width).step_by(CHUNK) {
+                let len = CHUNK.min(width - x);
+                self.decode_row(x, y, &mut chunk[..len]);
+                canvas.draw_span(pos.0 + x, pos.1 + y, &chunk[..len]);
+            }
+        }
+    }
+
+    /// Decodes the whole image.
+    #[cfg(feature = "alloc")]
+    #[must_use]
+    pub fn to_image(&self) -> BltImage {
+        let mut image = BltImage::new(self.width, self.height, BltPixel::new(0, 0, 0));
+        for (y, row) in image.pixels_mut().chunks_exact_mut(self.width).enumerate() {
+            self.decode_row(0, y, row);
+        }
+        image
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl GraphicsOutput {
+    /// Decodes the BMP image `bmp` and draws it with its top-left corner at
+    /// `dest`. See [`Bmp`] for the supported formats.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::UNSUPPORTED`]: `bmp` is not a valid BMP image, or uses
+    ///   an unsupported format.
+    /// * [`Status::INVALID_PARAMETER`]: the image doesn't fit on the screen
+    ///   at `dest`.
+    /// * [`Status::DEVICE_ERROR`]: the blit failed.
+    pub fn draw_bmp(&mut self, bmp: &[u8], dest: (usize, usize)) -> Result {
+        let image = Bmp::parse(bmp).map_err(|_| Status::UNSUPPORTED)?.to_image();
+        self.blt(image.to_video(dest))
+    }
+
+    /// Captures the whole screen in the current mode.
+    ///
+    /// The pixels are read with [`blt`], so this also works in
+    /// [`PixelFormat::BltOnly`] modes. Use [`BltImage::to_bmp`] to save the
+    /// capture to a file.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use uefi::fs::FileSystem;
+    /// use uefi::proto::console::gop::GraphicsOutput;
+    /// use uefi::{boot, cstr16};
+    ///
+    /// # fn example(gop: &mut GraphicsOutput) -> uefi::Result {
+    /// let image = gop.screenshot()?;
+    /// let mut fs = FileSystem::new(boot::get_image_file_system(boot::image_handle())?);
+    /// fs.write(cstr16!("screenshot.bmp"), image.to_bmp())
+    ///     .map_err(|_| uefi::Status::DEVICE_ERROR)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::DEVICE_ERROR`]: the blit failed.
+    ///
+    /// [`blt`]: Self::blt
+    /// [`PixelFormat::BltOnly`]: super::PixelFormat::BltOnly
+    pub fn screenshot(&mut self) -> Result<BltImage> {
+        let (width, height) = self.current_mode_info().resolution();
+        let mut image = BltImage::new(width, height, BltPixel::new(0, 0, 0));
+        self.blt(BltOp::VideoToBltBuffer {
+            buffer: image.pixels_mut(),
+            src: (0, 0),
+            dest: BltRegion::Full,
+            dims: (width, height),
+        })?;
+        Ok(image)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl BltImage {
+    /// Encodes the image as an uncompressed 24-bit BMP file, which can be
+    /// decoded with [`Bmp::parse`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file would be larger than 4 GiB.
+    #[must_use]
+    pub fn to_bmp(&self) -> Vec<u8> {
+        let (width, height) = (self.width(), self.height());
+        let stride = (width * 3).next_multiple_of(4);
+        let pixel_offset = FILE_HEADER_SIZE + INFO_HEADER_SIZE;
+        let file_size = u32::try_from(pixel_offset + stride * height)
+            .ok()
+            .filter(|_| i32::try_from(width).is_ok() && i32::try_from(height).is_ok())
+            .expect("image is too large for a BMP file");
+
+        let mut data = Vec::with_capacity(file_size as usize);
+        // File header.
+        data.extend_from_slice(b"BM");
+        data.extend_from_slice(&file_size.to_le_bytes());
+        data.extend_from_slice(&[0; 4]);
+        data.extend_from_slice(&(pixel_offset as u32).to_le_bytes());
+        // `BITMAPINFOHEADER` of a bottom-up image.
+        data.extend_from_slice(&(INFO_HEADER_SIZE as u32).to_le_bytes());
+        data.extend_from_slice(&(width as u32).to_le_bytes());
+        data.extend_from_slice(&(height as u32).to_le_bytes());
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&24u16.to_le_bytes());
+        data.extend_from_slice(&BI_RGB.to_le_bytes());
+        data.extend_from_slice(&((stride * height) as u32).to_le_bytes());
+        // 96 DPI in pixels per meter, and no palette.
+        data.extend_from_slice(&3780u32.to_le_bytes());
+        data.extend_from_slice(&3780u32.to_le_bytes());
+        data.extend_from_slice(&[0; 8]);
+
+        if width > 0 {
+            for row in self.pixels().chunks_exact(width).rev() {
+                for pixel in row {
+                    data.extend_from_slice(&[pixel.blue, pixel.green, pixel.red]);
+                }
+                data.resize(data.len() + stride - width * 3, 0);
+            }
+        }
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::console::gop::PixelBuffer;
+    use alloc::vec::Vec;
+
+    const RED: BltPixel = BltPixel::new(0xff, 0, 0);
+    const WHITE: BltPixel = BltPixel::new(0xff, 0xff, 0xff);
+    const BLACK: BltPixel = BltPixel::new(0, 0, 0);
+
+    /// Builds a BMP file with a `BITMAPINFOHEADER`.
+    fn bmp(
+        width: i32,
+        height: i32,
+        bits: u16,
+        compression: u32,
+        extra: &[u8],
+        rows: &[u8],
+    ) -> Vec<u8> {
+        let pixel_offset = 14 + 40 + extra.len();
+        let mut data = Vec::new();
+        data.extend_from_slice(b"BM");
+        data.extend_from_slice(&((pixel_offset + rows.len()) as u32).to_le_bytes());
+        data.extend_from_slice(&[0; 4]);
+        data.extend_from_slice(&(pixel_offset as u32).to_le_bytes());
+        data.extend_from_slice(&40u32.to_le_bytes());
+        data.extend_from_slice(&width.to_le_bytes());
+        data.extend_from_slice(&height.to_le_bytes());
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&bits.to_le_bytes());
+        data.extend_from_slice(&compression.to_le_bytes());
+        data.extend_from_slice(&[0; 20]);
+        data.extend_from_slice(extra);
+        data.extend_from_slice(rows);
+        data
+    }
+
+    #[test]
+    fn test_24bit() {
+        // Bottom-up 2x2, with rows padded to 8 bytes.
+        let rows = [
+            0, 0, 0xff, 0xff, 0xff, 0xff, 0, 0, // bottom: red, white
+            0, 0, 0, 0, 0, 0xff, 0, 0, // top: black, red
+        ];
+        let data = bmp(2, 2, 24, BI_RGB, &[], &rows);
+        let image = Bmp::parse(&data).unwrap();
+        assert_eq!((image.width(), image.height()), (2, 2));
+        assert_eq!(image.pixel(0, 0), Some(BLACK));
+        assert_eq!(image.pixel(1, 0), Some(RED));
+        assert_eq!(image.pixel(0, 1), Some(RED));
+        assert_eq!(image.pixel(1, 1), Some(WHITE));
+        assert_eq!(image.pixel(2, 1), None);
+        assert_eq!(image.to_image().pixels(), [BLACK, RED, RED, WHITE]);
+
+        // Drawing is clipped to the canvas.
+        let mut pixels = [WHITE; 4];
+        let mut canvas = PixelBuffer::new(&mut pixels, 2);
+        image.draw(&mut canvas, (1, 1));
+        assert_eq!(pixels, [WHITE, WHITE, WHITE, BLACK]);
+
+        assert_eq!(
+            Bmp::parse(&data[..data.len() - 1]).unwrap_err(),
+            BmpError::Truncated
+        );
+        assert_eq!(
+            Bmp::parse(&data[1..]).unwrap_err(),
+            BmpError::InvalidSignature
+        );
+    }
+
+    #[test]
+    fn test_indexed() {
+        // Top-down 3x1 with a 2-color palette.
+        let palette = [0, 0, 0, 0, 0, 0, 0xff, 0];
+        let data = bmp(3, -1, 1, BI_RGB, &palette, &[0b1010_0000, 0, 0, 0]);
+        let image = Bmp::parse(&data).unwrap();
+        assert_eq!(image.to_image().pixels(), [RED, BLACK, RED]);
+
+        // 4 bits per pixel, with an index outside of the palette.
+        let data = bmp(2, 1, 4, BI_RGB, &palette, &[0x13, 0, 0, 0]);
+        let image = Bmp::parse(&data).unwrap();
+        assert_eq!(image.to_image().pixels(), [RED, BLACK]);
+    }
+
+    #[test]
+    fn test_masks() {
+        // RGB565.
+        let masks = [0x00, 0xf8, 0, 0, 0xe0, 0x07, 0, 0, 0x1f, 0, 0, 0];
+        let data = bmp(2, 1, 16, BI_BITFIELDS, &masks, &[0x00, 0xf8, 0xff, 0xff]);
+        let image = Bmp::parse(&data).unwrap();
+        assert_eq!(image.to_image().pixels(), [RED, WHITE]);
+
+        // Default masks of 32 bits per pixel.
+        let data = bmp(1, 1, 32, BI_RGB, &[], &[0x56, 0x34, 0x12, 0]);
+        let image = Bmp::parse(&data).unwrap();
+        assert_eq!(image.pixel(0, 0), Some(BltPixel::new(0x12, 0x34, 0x56)));
+
+        // Masks with gaps are not supported.
+        let masks = [1, 0, 0, 0x80, 0, 0xff, 0, 0, 0xff, 0, 0, 0];
+        let data = bmp(1, 1, 32, BI_BITFIELDS, &masks, &[0xff; 4]);
+        assert_eq!(Bmp::parse(&data).unwrap_err(), BmpError::Unsupported);
+
+        // Run-length encoding is not supported.
+        let data = bmp(1, 1, 8, 1, &[0; 1024], &[0; 4]);
+        assert_eq!(Bmp::parse(&data).unwrap_err(), BmpError::Unsupported);
+    }
+
+    #[test]
+    fn test_to_bmp() {
+        let mut image = BltImage::new(3, 2, WHITE);
+        image.pixels_mut()[0] = RED;
+        image.pixels_mut()[5] = BltPixel::new(0x12, 0x34, 0x56);
+        let data = image.to_bmp();
+        // Rows of 9 bytes are padded to 12.
+        assert_eq!(data.len(), 14 + 40 + 2 * 12);
+        assert_eq!(&data[60..63], [0x56, 0x34, 0x12]);
+
+        let decoded = Bmp::parse(&data).unwrap();
+        assert_eq!(decoded.to_image(), image);
+
+        let empty = BltImage::new(0, 0, WHITE).to_bmp();
+        assert_eq!(empty.len(), 54);
+    }
+}
@@ -0,0 +1,538 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Drawing primitives.
+
+use super::{BltPixel, FrameBuffer, GraphicsOutput, PixelBitmask, PixelFormat};
+use crate::{Result, Status};
+
+/// A rectangle on the screen, defined by its top-left corner and its size.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Rect {
+    /// X coordinate of the left edge.
+    pub x: usize,
+    /// Y coordinate of the top edge.
+    pub y: usize,
+    /// Width in pixels.
+    pub width: usize,
+    /// Height in pixels.
+    pub height: usize,
+}
+
+impl Rect {
+    /// Creates a rectangle.
+    #[must_use]
+    pub const fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Returns whether the rectangle has no pixels.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    /// Returns the number of pixels in the rectangle.
+    #[must_use]
+    pub const fn area(&self) -> usize {
+        self.width * self.height
+    }
+
+    /// Returns the X coordinate one past the right edge.
+    #[must_use]
+    pub const fn right(&self) -> usize {
+        self.x + self.width
+    }
+
+    /// Returns the Y coordinate one past the bottom edge.
+    #[must_use]
+    pub const fn bottom(&self) -> usize {
+        self.y + self.height
+    }
+
+    /// Returns whether the two rectangles have at least one pixel in common.
+    #[must_use]
+    pub const fn intersects(&self, other: &Self) -> bool {
+        !self.is_empty()
+            && !other.is_empty()
+            && self.x < other.right()
+            && other.x < self.right()
+            && self.y < other.bottom()
+            && other.y < self.bottom()
+    }
+
+    /// Returns the smallest rectangle containing both rectangles.
+    #[must_use]
+    pub fn union(&self, other: &Self) -> Self {
+        if self.is_empty() {
+            return *other;
+        }
+        if other.is_empty() {
+            return *self;
+        }
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        Self::new(
+            x,
+            y,
+            self.right().max(other.right()) - x,
+            self.bottom().max(other.bottom()) - y,
+        )
+    }
+
+    /// Returns the rectangle covered by both rectangles, which is empty if
+    /// they don't intersect.
+    #[must_use]
+    pub fn intersection(&self, other: &Self) -> Self {
+        if !self.intersects(other) {
+            return Self::default();
+        }
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        Self::new(
+            x,
+            y,
+            self.right().min(other.right()) - x,
+            self.bottom().min(other.bottom()) - y,
+        )
+    }
+
+    /// Returns whether the union of the two rectangles consists exactly of
+    /// their pixels, i.e. they can be replaced by their union.
+    #[cfg(feature = "alloc")]
+    pub(super) fn union_is_exact(&self, other: &Self) -> bool {
+        let union = self.union(other);
+        union.area() == self.area() + other.area() - self.intersection(other).area()
+    }
+}
+
+/// A surface that can be drawn on with [`BltPixel`]s.
+///
+/// Implementors provide the size and the drawing of horizontal spans of
+/// pixels; lines, rectangles, and circles are built on top of them. All
+/// drawing is clipped to the surface, so shapes may extend past its edges.
+/// Shapes that can start left of or above the surface take signed
+/// coordinates.
+///
+/// Implemented for [`PixelBuffer`], [`FrameBufferCanvas`], and
+/// `FrameBufferSurface`.
+///
+/// # Example
+///
+/// ```
+/// use uefi::proto::console::gop::{BltPixel, Canvas, PixelBuffer, Rect};
+///
+/// let white = BltPixel::new(0xff, 0xff, 0xff);
+/// let mut pixels = [BltPixel::new(0, 0, 0); 64 * 32];
+/// let mut canvas = PixelBuffer::new(&mut pixels, 64);
+/// canvas.draw_rect(Rect::new(0, 0, 64, 32), white);
+/// canvas.line((2, 2), (61, 29), white);
+/// canvas.fill_circle((32, 16), 8, white);
+/// ```
+pub trait Canvas {
+    /// Returns the width and height in pixels.
+    fn size(&self) -> (usize, usize);
+
+    /// Fills `len` pixels of row `y` with `color`, starting at column `x`.
+    fn fill_span(&mut self, x: usize, y: usize, len: usize, color: BltPixel);
+
+    /// Copies `pixels` to row `y`, starting at column `x`.
+    fn draw_span(&mut self, x: usize, y: usize, pixels: &[BltPixel]);
+
+    /// Returns the rectangle covering the whole surface.
+    fn bounds(&self) -> Rect {
+        let (width, height) = self.size();
+        Rect::new(0, 0, width, height)
+    }
+
+    /// Sets the pixel at (`x`, `y`).
+    fn set_pixel(&mut self, x: usize, y: usize, color: BltPixel) {
+        self.fill_span(x, y, 1, color);
+    }
+
+    /// Draws a horizontal line of `len` pixels, starting at (`x`, `y`).
+    fn horizontal_line(&mut self, x: usize, y: usize, len: usize, color: BltPixel) {
+        self.fill_span(x, y, len, color);
+    }
+
+    /// Draws a vertical line of `len` pixels, starting at (`x`, `y`).
+    fn vertical_line(&mut self, x: usize, y: usize, len: usize, color: BltPixel) {
+        let len = len.min(self.size().1.saturating_sub(y));
+        for y in y..y + len {
+            self.fill_span(x, y, 1, color);
+        }
+    }
+
+    /// Fills `rect` with `color`.
+    fn fill_rect(&mut self, rect: Rect, color: BltPixel) {
+        let rect = rect.intersection(&self.bounds());
+        for y in rect.y..rect.bottom() {
+            self.fill_span(rect.x, y, rect.width, color);
+        }
+    }
+
+    /// Draws the one pixel wide outline of `rect`.
+    fn draw_rect(&mut self, rect: Rect, color: BltPixel) {
+        if rect.is_empty() {
+            return;
+        }
+        self.horizontal_line(rect.x, rect.y, rect.width, color);
+        self.horizontal_line(rect.x, rect.bottom() - 1, rect.width, color);
+        self.vertical_line(rect.x, rect.y, rect.height, color);
+        self.vertical_line(rect.right() - 1, rect.y, rect.height, color);
+    }
+
+    /// Draws a line from `from` to `to`, both included.
+    fn line(&mut self, from: (isize, isize), to: (isize, isize), color: BltPixel) {
+        // Bresenham's algorithm, for all octants.
+        let dx = (to.0 - from.0).abs();
+        let dy = -(to.1 - from.1).abs();
+        let step_x = if from.0 < to.0 { 1 } else { -1 };
+        let step_y = if from.1 < to.1 { 1 } else { -1 };
+        let (mut x, mut y) = from;
+        let mut err = dx + dy;
+        loop {
+            plot(self, x, y, color);
+            if (x, y) == to {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += step_x;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += step_y;
+            }
+        }
+    }
+
+    /// Draws the one pixel wide outline of the circle of `radius` around
+    /// `center`.
+    fn circle(&mut self, center: (isize, isize), radius: usize, color: BltPixel) {
+        let (cx, cy) = center;
+        for_each_octant_point(radius, |x, y| {
+            for (px, py) in [
+                (cx + x, cy + y),
+                (cx - x, cy + y),
+                (cx + x, cy - y),
+                (cx - x, cy - y),
+                (cx + y, cy + x),
+                (cx - y, cy + x),
+                (cx + y, cy - x),
+                (cx - y, cy - x),
+            ] {
+                plot(self, px, py, color);
+            }
+        });
+    }
+
+    /// Fills the circle of `radius` around `center`.
+    fn fill_circle(&mut self, center: (isize, isize), radius: usize, color: BltPixel) {
+        let (cx, cy) = center;
+        for_each_octant_point(radius, |x, y| {
+            span(self, cx - x, cx + x, cy + y, color);
+            span(self, cx - x, cx + x, cy - y, color);
+            span(self, cx - y, cx + y, cy + x, color);
+            span(self, cx - y, cx + y, cy - x, color);
+        });
+    }
+}
+
+/// Sets the pixel at signed coordinates, if it is on `canvas`.
+fn plot<C: Canvas + ?Sized>(canvas: &mut C, x: isize, y: isize, color: BltPixel) {
+    if let (Ok(x), Ok(y)) = (usize::try_from(x), usize::try_from(y)) {
+        canvas.fill_span(x, y, 1, color);
+    }
+}
+
+/// Fills row `y` from `x0` to `x1`, both included, at signed coordinates.
+fn span<C: Canvas + ?Sized>(canvas: &mut C, x0: isize, x1: isize, y: isize, color: BltPixel) {
+    let (Ok(y), Ok(x1)) = (usize::try_from(y), usize::try_from(x1)) else {
+        return;
+    };
+    let x0 = usize::try_from(x0).unwrap_or(0);
+    if x0 <= x1 {
+        canvas.fill_span(x0, y, x1 - x0 + 1, color);
+    }
+}
+
+/// Calls `f` with the points of the first octant of a circle of `radius`
+/// around the origin, using the midpoint circle algorithm. Each point
+/// (x, y) has x >= y >= 0.
+fn for_each_octant_point(radius: usize, mut f: impl FnMut(isize, isize)) {
+    let Ok(mut x) = isize::try_from(radius) else {
+        return;
+    };
+    let mut y = 0;
+    let mut err = 1 - x;
+    while x >= y {
+        f(x, y);
+        y += 1;
+        if err < 0 {
+            err += 2 * y + 1;
+        } else {
+            x -= 1;
+            err += 2 * (y - x) + 1;
+        }
+    }
+}
+
+/// A [`Canvas`] drawing into a buffer of [`BltPixel`]s, such as one used
+/// with [`BltOp::BufferToVideo`].
+///
+/// [`BltOp::BufferToVideo`]: super::BltOp::BufferToVideo
+#[derive(Debug)]
+pub struct PixelBuffer<'a> {
+    pixels: &'a mut [BltPixel],
+    width: usize,
+}
+
+impl<'a> PixelBuffer<'a> {
+    /// Creates a canvas from `pixels`, in rows of `width` pixels. An
+    /// incomplete last row is not drawn on.
+    pub const fn new(pixels: &'a mut [BltPixel], width: usize) -> Self {
+        Self { pixels, width }
+    }
+
+    /// Returns the pixels.
+    #[must_use]
+    pub const fn pixels(&self) -> &[BltPixel] {
+        self.pixels
+    }
+
+    /// Returns the pixels of row `y` starting at column `x`, up to `len`
+    /// pixels, clipped to the buffer.
+    fn row_mut(&mut self, x: usize, y: usize, len: usize) -> &mut [BltPixel] {
+        let (width, height) = self.size();
+        if x >= width || y >= height {
+            return &mut [];
+        }
+        let start = y * width + x;
+        &mut self.pixels[start..start + len.min(width - x)]
+    }
+}
+
+impl Canvas for PixelBuffer<'_> {
+    fn size(&self) -> (usize, usize) {
+        match self.width {
+            0 => (0, 0),
+            width => (width, self.pixels.len() / width),
+        }
+    }
+
+    fn fill_span(&mut self, x: usize, y: usize, len: usize, color: BltPixel) {
+        self.row_mut(x, y, len).fill(color);
+    }
+
+    fn draw_span(&mut self, x: usize, y: usize, pixels: &[BltPixel]) {
+        let row = self.row_mut(x, y, pixels.len());
+        let len = row.len();
+        row.copy_from_slice(&pixels[..len]);
+    }
+}
+
+/// A [`Canvas`] drawing directly into the frame buffer of a
+/// [`GraphicsOutput`] device, converting the pixels to its format.
+///
+/// Changes are visible immediately. Use a `FrameBufferSurface` to avoid
+/// showing partially drawn frames.
+#[derive(Debug)]
+pub struct FrameBufferCanvas<'gop> {
+    frame_buffer: FrameBuffer<'gop>,
+    width: usize,
+    height: usize,
+    stride: usize,
+    encoder: PixelEncoder,
+}
+
+impl<'gop> FrameBufferCanvas<'gop> {
+    /// Creates a canvas for the frame buffer of the current mode of `gop`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::UNSUPPORTED`]: the current mode is
+    ///   [`PixelFormat::BltOnly`], so the frame buffer can't be accessed.
+    pub fn new(gop: &'gop mut GraphicsOutput) -> Result<Self> {
+        let info = gop.current_mode_info();
+        let encoder = match info.pixel_format() {
+            PixelFormat::Rgb => PixelEncoder::RGB,
+            PixelFormat::Bgr => PixelEncoder::BGR,
+            PixelFormat::Bitmask => PixelEncoder::from_bitmask(
+                // OK to unwrap: the format is `Bitmask`.
+                &info.pixel_bitmask().unwrap(),
+            ),
+            PixelFormat::BltOnly => return Err(Status::UNSUPPORTED.into()),
+        };
+        let (width, height) = info.resolution();
+        Ok(Self {
+            frame_buffer: gop.frame_buffer(),
+            width,
+            height,
+            stride: info.stride(),
+            encoder,
+        })
+    }
+
+    /// Writes pixels to row `y` starting at column `x`, clipped to the
+    /// screen.
+    fn write_span(&mut self, x: usize, y: usize, pixels: impl Iterator<Item = BltPixel>) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let mut index = (y * self.stride + x) * 4;
+        for pixel in pixels.take(self.width - x) {
+            // SAFETY: the pixel is on the screen, and each pixel is 32 bits.
+            unsafe {
+                self.frame_buffer
+                    .write_value(index, self.encoder.encode(pixel))
+            };
+            index += 4;
+        }
+    }
+}
+
+impl Canvas for FrameBufferCanvas<'_> {
+    fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    fn fill_span(&mut self, x: usize, y: usize, len: usize, color: BltPixel) {
+        self.write_span(x, y, core::iter::repeat_n(color, len));
+    }
+
+    fn draw_span(&mut self, x: usize, y: usize, pixels: &[BltPixel]) {
+        self.write_span(x, y, pixels.iter().copied());
+    }
+}
+
+/// Conversion of [`BltPixel`]s to the 32-bit pixels of a frame buffer.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct PixelEncoder {
+    /// For each of red, green, and blue: the shift of the channel's lowest
+    /// bit and the number of bits of the channel.
+    channels: [(u32, u32); 3],
+}
+
+impl PixelEncoder {
+    const RGB: Self = Self {
+        channels: [(0, 8), (8, 8), (16, 8)],
+    };
+    const BGR: Self = Self {
+        channels: [(16, 8), (8, 8), (0, 8)],
+    };
+
+    const fn from_bitmask(mask: &PixelBitmask) -> Self {
+        const fn channel(mask: u32) -> (u32, u32) {
+            if mask == 0 {
+                (0, 0)
+            } else {
+                (mask.trailing_zeros(), mask.count_ones())
+            }
+        }
+        Self {
+            channels: [channel(mask.red), channel(mask.green), channel(mask.blue)],
+        }
+    }
+
+    fn encode(&self, pixel: BltPixel) -> u32 {
+        [pixel.red, pixel.green, pixel.blue]
+            .into_iter()
+            .zip(self.channels)
+            .map(|(value, (shift, bits))| match bits {
+                0 => 0,
+                1..=8 => (u32::from(value) >> (8 - bits)) << shift,
+                // Scale up by repeating the high bits.
+                _ => (u32::from(value) * ((1 << bits) - 1) / 0xff) << shift,
+            })
+            .fold(0, |acc, channel| acc | channel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLACK: BltPixel = BltPixel::new(0, 0, 0);
+    const WHITE: BltPixel = BltPixel::new(0xff, 0xff, 0xff);
+
+    /// Renders the white pixels of a canvas as `#`.
+    fn render(canvas: &PixelBuffer) -> [[u8; 8]; 8] {
+        let mut rows = [[b'.'; 8]; 8];
+        for (i, pixel) in canvas.pixels().iter().enumerate() {
+            if *pixel == WHITE {
+                rows[i / 8][i % 8] = b'#';
+            }
+        }
+        rows
+    }
+
+    #[test]
+    fn test_shapes() {
+        let mut pixels = [BLACK; 64];
+        let mut canvas = PixelBuffer::new(&mut pixels, 8);
+        assert_eq!(canvas.size(), (8, 8));
+        canvas.draw_rect(Rect::new(4, 4, 10, 3), WHITE);
+        canvas.line((-2, -2), (3, 3), WHITE);
+        canvas.line((7, 0), (5, 1), WHITE);
+        canvas.draw_span(6, 7, &[WHITE; 4]);
+        assert_eq!(
+            render(&canvas),
+            [
+                *b"#......#",
+                *b".#...##.",
+                *b"..#.....",
+                *b"...#....",
+                *b"....####",
+                *b"....#...",
+                *b"....####",
+                *b"......##",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_circles() {
+        let mut pixels = [BLACK; 64];
+        let mut canvas = PixelBuffer::new(&mut pixels, 8);
+        canvas.circle((3, 3), 3, WHITE);
+        canvas.fill_circle((8, 7), 1, WHITE);
+        assert_eq!(
+            render(&canvas),
+            [
+                *b"..###...",
+                *b".#...#..",
+                *b"#.....#.",
+                *b"#.....#.",
+                *b"#.....#.",
+                *b".#...#..",
+                *b"..###...",
+                *b".......#",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pixel_encoder() {
+        let pixel = BltPixel::new(0x12, 0x34, 0x56);
+        assert_eq!(PixelEncoder::RGB.encode(pixel), 0x0056_3412);
+        assert_eq!(PixelEncoder::BGR.encode(pixel), 0x0012_3456);
+
+        // RGB565.
+        let mask = PixelBitmask {
+            red: 0xf800,
+            green: 0x07e0,
+            blue: 0x001f,
+            reserved: 0,
+        };
+        let encoder = PixelEncoder::from_bitmask(&mask);
+        assert_eq!(encoder.encode(BltPixel::new(0xff, 0xff, 0xff)), 0xffff);
+        assert_eq!(encoder.encode(BltPixel::new(0xff, 0, 0)), 0xf800);
+        assert_eq!(encoder.encode(BltPixel::new(0, 0x80, 0)), 0x0400);
+    }
+}
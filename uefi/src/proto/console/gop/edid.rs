@@ -0,0 +1,476 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! EDID protocols and parsing.
+
+use super::{GraphicsOutput, Mode};
+use crate::proto::unsafe_protocol;
+use crate::{Handle, Result, StatusExt};
+use core::fmt::{self, Display, Formatter};
+use core::{ptr, slice};
+use uefi_raw::protocol::console::{
+    EdidActiveProtocol, EdidDiscoveredProtocol, EdidOverrideProtocol,
+};
+
+pub use uefi_raw::protocol::console::EdidOverrideAttributes;
+
+/// Size of the base EDID block in bytes.
+const BLOCK_SIZE: usize = 128;
+/// Fixed header of the base EDID block.
+const HEADER: [u8; 8] = [0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00];
+/// Offset of the first of the four 18-byte descriptors.
+const DESCRIPTORS_OFFSET: usize = 54;
+/// Size of a descriptor in bytes.
+const DESCRIPTOR_SIZE: usize = 18;
+/// Tag of the display descriptor holding the monitor name.
+const TAG_MONITOR_NAME: u8 = 0xfc;
+/// Tag of the display descriptor holding the serial number string.
+const TAG_SERIAL_NUMBER: u8 = 0xff;
+
+/// Error returned when parsing an EDID block.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EdidError {
+    /// The data doesn't start with the fixed EDID header.
+    InvalidHeader,
+    /// The data is shorter than the 128-byte base block.
+    Truncated,
+    /// The bytes of the base block don't sum to zero.
+    InvalidChecksum,
+}
+
+impl Display for EdidError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidHeader => write!(f, "invalid EDID header"),
+            Self::Truncated => write!(f, "EDID data is truncated"),
+            Self::InvalidChecksum => write!(f, "invalid EDID checksum"),
+        }
+    }
+}
+
+impl core::error::Error for EdidError {}
+
+/// A detailed timing descriptor, describing a video mode of the display.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DetailedTiming {
+    /// Pixel clock in kHz.
+    pub pixel_clock_khz: u32,
+    /// Number of visible pixels per line.
+    pub horizontal_active: u16,
+    /// Number of blanking pixels per line.
+    pub horizontal_blanking: u16,
+    /// Number of visible lines.
+    pub vertical_active: u16,
+    /// Number of blanking lines.
+    pub vertical_blanking: u16,
+    /// Width of the visible area in millimeters.
+    pub horizontal_size_mm: u16,
+    /// Height of the visible area in millimeters.
+    pub vertical_size_mm: u16,
+    /// Whether the mode is interlaced.
+    pub interlaced: bool,
+}
+
+impl DetailedTiming {
+    /// Parses an 18-byte descriptor, returning `None` if it is a display
+    /// descriptor rather than a timing.
+    fn parse(bytes: &[u8]) -> Option<Self> {
+        let pixel_clock = u16::from_le_bytes([bytes[0], bytes[1]]);
+        if pixel_clock == 0 {
+            return None;
+        }
+        let high = |low: u8, nibble: u8| u16::from(low) | (u16::from(nibble) << 8);
+        Some(Self {
+            pixel_clock_khz: u32::from(pixel_clock) * 10,
+            horizontal_active: high(bytes[2], bytes[4] >> 4),
+            horizontal_blanking: high(bytes[3], bytes[4] & 0xf),
+            vertical_active: high(bytes[5], bytes[7] >> 4),
+            vertical_blanking: high(bytes[6], bytes[7] & 0xf),
+            horizontal_size_mm: high(bytes[12], bytes[14] >> 4),
+            vertical_size_mm: high(bytes[13], bytes[14] & 0xf),
+            interlaced: bytes[17] & 0x80 != 0,
+        })
+    }
+
+    /// Returns the visible resolution as `(width, height)`.
+    #[must_use]
+    pub const fn resolution(&self) -> (usize, usize) {
+        (
+            self.horizontal_active as usize,
+            self.vertical_active as usize,
+        )
+    }
+
+    /// Returns the refresh rate in Hz, rounded to the nearest integer.
+    #[must_use]
+    pub fn refresh_rate(&self) -> u32 {
+        let horizontal = u32::from(self.horizontal_active) + u32::from(self.horizontal_blanking);
+        let vertical = u32::from(self.vertical_active) + u32::from(self.vertical_blanking);
+        let pixels = u64::from(horizontal) * u64::from(vertical);
+        if pixels == 0 {
+            return 0;
+        }
+        let hz = (u64::from(self.pixel_clock_khz) * 1000 + pixels / 2) / pixels;
+        u32::try_from(hz).unwrap_or(u32::MAX)
+    }
+}
+
+/// A parsed EDID (Extended Display Identification Data) block, describing
+/// a display.
+///
+/// Only the 128-byte base block is parsed; extension blocks are ignored.
+///
+/// # Example
+///
+/// Set the GOP mode matching the native resolution of the display:
+///
+/// ```no_run
+/// use uefi::boot::{self, ScopedProtocol};
+/// use uefi::proto::console::gop::{EdidActive, GraphicsOutput};
+///
+/// # fn example(gop: &mut ScopedProtocol<GraphicsOutput>) -> uefi::Result {
+/// let handle = boot::get_handle_for_protocol::<EdidActive>()?;
+/// let edid_active = boot::open_protocol_exclusive::<EdidActive>(handle)?;
+/// if let Ok(edid) = edid_active.edid() {
+///     if let Some(mode) = gop.mode_for_edid(&edid) {
+///         gop.set_mode(&mode)?;
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Edid<'a> {
+    block: &'a [u8; BLOCK_SIZE],
+}
+
+impl<'a> Edid<'a> {
+    /// Parses the base EDID block at the start of `data`.
+    ///
+    /// # Errors
+    ///
+    /// * [`EdidError::Truncated`]: `data` is shorter than 128 bytes.
+    /// * [`EdidError::InvalidHeader`]: `data` doesn't start with the EDID
+    ///   header.
+    /// * [`EdidError::InvalidChecksum`]: the checksum of the block is wrong.
+    pub fn parse(data: &'a [u8]) -> core::result::Result<Self, EdidError> {
+        let block: &[u8; BLOCK_SIZE] = data
+            .get(..BLOCK_SIZE)
+            .and_then(|block| block.try_into().ok())
+            .ok_or(EdidError::Truncated)?;
+        if block[..HEADER.len()] != HEADER {
+            return Err(EdidError::InvalidHeader);
+        }
+        if block.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte)) != 0 {
+            return Err(EdidError::InvalidChecksum);
+        }
+        Ok(Self { block })
+    }
+
+    /// Returns the raw base block.
+    #[must_use]
+    pub const fn as_bytes(&self) -> &'a [u8; BLOCK_SIZE] {
+        self.block
+    }
+
+    /// Returns the three-letter PNP ID of the manufacturer, e.g. `*b"DEL"`.
+    #[must_use]
+    pub const fn manufacturer_id(&self) -> [u8; 3] {
+        let id = u16::from_be_bytes([self.block[8], self.block[9]]);
+        [
+            b'A' - 1 + ((id >> 10) & 0x1f) as u8,
+            b'A' - 1 + ((id >> 5) & 0x1f) as u8,
+            b'A' - 1 + (id & 0x1f) as u8,
+        ]
+    }
+
+    /// Returns the manufacturer's product code.
+    #[must_use]
+    pub const fn product_code(&self) -> u16 {
+        u16::from_le_bytes([self.block[10], self.block[11]])
+    }
+
+    /// Returns the numeric serial number, or 0 if unused.
+    #[must_use]
+    pub const fn serial_number(&self) -> u32 {
+        u32::from_le_bytes([
+            self.block[12],
+            self.block[13],
+            self.block[14],
+            self.block[15],
+        ])
+    }
+
+    /// Returns the year of manufacture, or the model year if the week is
+    /// `0xff`.
+    #[must_use]
+    pub const fn manufacture_year(&self) -> u16 {
+        1990 + self.block[17] as u16
+    }
+
+    /// Returns the EDID version and revision, e.g. `(1, 4)`.
+    #[must_use]
+    pub const fn version(&self) -> (u8, u8) {
+        (self.block[18], self.block[19])
+    }
+
+    /// Returns the size of the display as `(width, height)` in centimeters,
+    /// or `None` if it is unknown or the display is a projector.
+    #[must_use]
+    pub const fn physical_size_cm(&self) -> Option<(u8, u8)> {
+        match (self.block[21], self.block[22]) {
+            (0, _) | (_, 0) => None,
+            size => Some(size),
+        }
+    }
+
+    /// Returns the number of extension blocks following the base block.
+    #[must_use]
+    pub const fn extension_count(&self) -> u8 {
+        self.block[126]
+    }
+
+    /// Returns the four 18-byte descriptors.
+    fn descriptors(&self) -> impl Iterator<Item = &'a [u8]> + 'a {
+        let block: &'a [u8; BLOCK_SIZE] = self.block;
+        block[DESCRIPTORS_OFFSET..DESCRIPTORS_OFFSET + 4 * DESCRIPTOR_SIZE]
+            .chunks_exact(DESCRIPTOR_SIZE)
+    }
+
+    /// Returns the detailed timings of the base block, the first of which
+    /// is the preferred one.
+    pub fn detailed_timings(&self) -> impl Iterator<Item = DetailedTiming> + 'a {
+        self.descriptors().filter_map(DetailedTiming::parse)
+    }
+
+    /// Returns the preferred timing, which is the native mode of the
+    /// display.
+    #[must_use]
+    pub fn preferred_timing(&self) -> Option<DetailedTiming> {
+        self.detailed_timings().next()
+    }
+
+    /// Returns the resolution of the preferred timing as
+    /// `(width, height)`.
+    #[must_use]
+    pub fn preferred_resolution(&self) -> Option<(usize, usize)> {
+        self.preferred_timing().map(|timing| timing.resolution())
+    }
+
+    /// Returns the text of the display descriptor with `tag`, without
+    /// the padding.
+    fn descriptor_text(&self, tag: u8) -> Option<&'a str> {
+        let descriptor = self
+            .descriptors()
+            .find(|d| d[..3] == [0, 0, 0] && d[3] == tag)?;
+        let text = &descriptor[5..];
+        let end = text.iter().position(|b| *b == b'\n').unwrap_or(text.len());
+        core::str::from_utf8(&text[..end])
+            .ok()
+            .map(|text| text.trim_end())
+    }
+
+    /// Returns the name of the display model, if present.
+    #[must_use]
+    pub fn monitor_name(&self) -> Option<&'a str> {
+        self.descriptor_text(TAG_MONITOR_NAME)
+    }
+
+    /// Returns the serial number string of the display, if present.
+    #[must_use]
+    pub fn serial_number_string(&self) -> Option<&'a str> {
+        self.descriptor_text(TAG_SERIAL_NUMBER)
+    }
+}
+
+/// Returns the EDID of a discovered or active protocol.
+///
+/// # Safety
+///
+/// `edid` must be null or point to `size` bytes.
+const unsafe fn edid_bytes<'a>(edid: *const u8, size: u32) -> &'a [u8] {
+    if edid.is_null() {
+        &[]
+    } else {
+        unsafe { slice::from_raw_parts(edid, size as usize) }
+    }
+}
+
+/// EDID Discovered [`Protocol`].
+///
+/// Installed on the handle of a video output with the EDID read from the
+/// attached display.
+///
+/// The corresponding C type is `EFI_EDID_DISCOVERED_PROTOCOL`.
+///
+/// [`Protocol`]: uefi::proto::Protocol
+#[derive(Debug)]
+#[repr(transparent)]
+#[unsafe_protocol(EdidDiscoveredProtocol::GUID)]
+pub struct EdidDiscovered(EdidDiscoveredProtocol);
+
+impl EdidDiscovered {
+    /// Returns the raw EDID, which may include extension blocks. It is
+    /// empty if the display provides no EDID.
+    #[must_use]
+    pub const fn edid_bytes(&self) -> &[u8] {
+        unsafe { edid_bytes(self.0.edid, self.0.size_of_edid) }
+    }
+
+    /// Parses the EDID.
+    ///
+    /// # Errors
+    ///
+    /// See [`Edid::parse`].
+    pub fn edid(&self) -> core::result::Result<Edid<'_>, EdidError> {
+        Edid::parse(self.edid_bytes())
+    }
+}
+
+/// EDID Active [`Protocol`].
+///
+/// Installed on the handle of a video output with the EDID used by the
+/// firmware, which is the discovered EDID unless the platform overrides it
+/// with the [`EdidOverride`] protocol.
+///
+/// The corresponding C type is `EFI_EDID_ACTIVE_PROTOCOL`.
+///
+/// [`Protocol`]: uefi::proto::Protocol
+#[derive(Debug)]
+#[repr(transparent)]
+#[unsafe_protocol(EdidActiveProtocol::GUID)]
+pub struct EdidActive(EdidActiveProtocol);
+
+impl EdidActive {
+    /// Returns the raw EDID, which may include extension blocks. It is
+    /// empty if there is no EDID.
+    #[must_use]
+    pub const fn edid_bytes(&self) -> &[u8] {
+        unsafe { edid_bytes(self.0.edid, self.0.size_of_edid) }
+    }
+
+    /// Parses the EDID.
+    ///
+    /// # Errors
+    ///
+    /// See [`Edid::parse`].
+    pub fn edid(&self) -> core::result::Result<Edid<'_>, EdidError> {
+        Edid::parse(self.edid_bytes())
+    }
+}
+
+/// EDID Override [`Protocol`].
+///
+/// Produced by the platform to override the EDID of displays.
+///
+/// The corresponding C type is `EFI_EDID_OVERRIDE_PROTOCOL`.
+///
+/// [`Protocol`]: uefi::proto::Protocol
+#[derive(Debug)]
+#[repr(transparent)]
+#[unsafe_protocol(EdidOverrideProtocol::GUID)]
+pub struct EdidOverride(EdidOverrideProtocol);
+
+impl EdidOverride {
+    /// Returns the overriding EDID for the video output `child`, and how to
+    /// use it. The EDID is `None` if the attributes apply to the
+    /// discovered EDID.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::NOT_FOUND`]: there is no override for `child`.
+    ///
+    /// [`Status::NOT_FOUND`]: crate::Status::NOT_FOUND
+    pub fn get_edid(&self, child: Handle) -> Result<(EdidOverrideAttributes, Option<&[u8]>)> {
+        let child = child.as_ptr();
+        let mut attributes = EdidOverrideAttributes::empty();
+        let mut size = 0;
+        let mut edid = ptr::null_mut();
+        unsafe { (self.0.get_edid)(&self.0, &child, &mut attributes, &mut size, &mut edid) }
+            .to_result_with_val(|| {
+                // SAFETY: the EDID is owned by the protocol.
+                let edid = (!edid.is_null() && size != 0)
+                    .then(|| unsafe { slice::from_raw_parts(edid.cast_const(), size) });
+                (attributes, edid)
+            })
+    }
+}
+
+impl GraphicsOutput {
+    /// Returns the mode matching the preferred resolution of `edid`, i.e.
+    /// the native resolution of the display.
+    #[must_use]
+    pub fn mode_for_edid(&self, edid: &Edid) -> Option<Mode> {
+        let resolution = edid.preferred_resolution()?;
+        self.modes()
+            .find(|mode| mode.info().resolution() == resolution)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a base block with a 1920x1080 preferred timing and a monitor
+    /// name.
+    fn block() -> [u8; BLOCK_SIZE] {
+        let mut block = [0; BLOCK_SIZE];
+        block[..8].copy_from_slice(&HEADER);
+        // "DEL", product 0xa0c1, serial 0x12345678.
+        block[8..16].copy_from_slice(&[0x10, 0xac, 0xc1, 0xa0, 0x78, 0x56, 0x34, 0x12]);
+        block[17] = 30;
+        block[18..20].copy_from_slice(&[1, 4]);
+        block[21..23].copy_from_slice(&[53, 30]);
+        // 1920x1080 at 148.5 MHz, with 280 and 45 blanking, 531x299 mm.
+        block[54..72].copy_from_slice(&[
+            0x02, 0x3a, 0x80, 0x18, 0x71, 0x38, 0x2d, 0x40, 0x58, 0x2c, 0x45, 0x00, 0x13, 0x2b,
+            0x21, 0x00, 0x00, 0x1e,
+        ]);
+        block[72..77].copy_from_slice(&[0, 0, 0, TAG_MONITOR_NAME, 0]);
+        block[77..90].copy_from_slice(b"DELL U2415\n  ");
+        block[127] = 0u8.wrapping_sub(block.iter().fold(0u8, |sum, b| sum.wrapping_add(*b)));
+        block
+    }
+
+    #[test]
+    fn test_parse() {
+        let block = block();
+        let edid = Edid::parse(&block).unwrap();
+        assert_eq!(&edid.manufacturer_id(), b"DEL");
+        assert_eq!(edid.product_code(), 0xa0c1);
+        assert_eq!(edid.serial_number(), 0x1234_5678);
+        assert_eq!(edid.manufacture_year(), 2020);
+        assert_eq!(edid.version(), (1, 4));
+        assert_eq!(edid.physical_size_cm(), Some((53, 30)));
+        assert_eq!(edid.extension_count(), 0);
+        assert_eq!(edid.monitor_name(), Some("DELL U2415"));
+        assert_eq!(edid.serial_number_string(), None);
+
+        let timing = edid.preferred_timing().unwrap();
+        assert_eq!(
+            timing,
+            DetailedTiming {
+                pixel_clock_khz: 148_500,
+                horizontal_active: 1920,
+                horizontal_blanking: 280,
+                vertical_active: 1080,
+                vertical_blanking: 45,
+                horizontal_size_mm: 531,
+                vertical_size_mm: 299,
+                interlaced: false,
+            }
+        );
+        assert_eq!(timing.refresh_rate(), 60);
+        assert_eq!(edid.preferred_resolution(), Some((1920, 1080)));
+        assert_eq!(edid.detailed_timings().count(), 1);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut block = block();
+        assert_eq!(Edid::parse(&block[..127]), Err(EdidError::Truncated));
+        block[100] ^= 1;
+        assert_eq!(Edid::parse(&block), Err(EdidError::InvalidChecksum));
+        block[0] = 1;
+        assert_eq!(Edid::parse(&block), Err(EdidError::InvalidHeader));
+    }
+}
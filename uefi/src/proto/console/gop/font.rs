@@ -0,0 +1,758 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Text rendering with bitmap fonts.
+
+use super::{BltPixel, Canvas};
+use crate::CStr16;
+use core::fmt::{self, Display, Formatter};
+
+/// Number of columns between tab stops.
+const TAB_WIDTH: usize = 8;
+
+/// Magic number of a PSF1 font.
+const PSF1_MAGIC: [u8; 2] = [0x36, 0x04];
+/// PSF1 mode flag: the font has 512 glyphs instead of 256.
+const PSF1_MODE512: u8 = 0x01;
+/// PSF1 mode flags: the font has a Unicode table.
+const PSF1_MODEHASTAB: u8 = 0x02 | 0x04;
+/// PSF1 Unicode table value starting the sequences of a glyph.
+const PSF1_STARTSEQ: u16 = 0xfffe;
+/// PSF1 Unicode table value ending the entries of a glyph.
+const PSF1_SEPARATOR: u16 = 0xffff;
+
+/// Magic number of a PSF2 font.
+const PSF2_MAGIC: [u8; 4] = [0x72, 0xb5, 0x4a, 0x86];
+/// Size of the PSF2 header in bytes.
+const PSF2_HEADER_SIZE: usize = 32;
+/// PSF2 flag: the font has a Unicode table.
+const PSF2_HAS_UNICODE_TABLE: u32 = 0x01;
+/// PSF2 Unicode table byte starting the sequences of a glyph.
+const PSF2_STARTSEQ: u8 = 0xfe;
+/// PSF2 Unicode table byte ending the entries of a glyph.
+const PSF2_SEPARATOR: u8 = 0xff;
+
+/// Glyphs of the built-in font, for the characters from `' '` to `'~'`.
+///
+/// The glyphs were drawn for this crate, and are covered by its license.
+#[rustfmt::skip]
+const DEFAULT_GLYPHS: [u8; 95 * 16] = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // space
+    0x00, 0x00, 0x00, 0x10, 0x38, 0x38, 0x38, 0x10, 0x10, 0x10, 0x00, 0x10, 0x10, 0x00, 0x00, 0x00, // !
+    0x00, 0x00, 0x00, 0x6c, 0x6c, 0x24, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // "
+    0x00, 0x00, 0x00, 0x00, 0x6c, 0x6c, 0xfe, 0x6c, 0x6c, 0x6c, 0xfe, 0x6c, 0x6c, 0x00, 0x00, 0x00, // #
+    0x00, 0x00, 0x00, 0x10, 0x7c, 0xc6, 0xc0, 0x78, 0x0c, 0x06, 0xc6, 0x7c, 0x10, 0x10, 0x00, 0x00, // $
+    0x00, 0x00, 0x00, 0x00, 0xc2, 0xc6, 0x0c, 0x18, 0x30, 0x60, 0xc6, 0x86, 0x00, 0x00, 0x00, 0x00, // %
+    0x00, 0x00, 0x00, 0x38, 0x6c, 0x6c, 0x38, 0x76, 0xdc, 0xcc, 0xcc, 0xcc, 0x76, 0x00, 0x00, 0x00, // &
+    0x00, 0x00, 0x00, 0x30, 0x30, 0x60, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // '
+    0x00, 0x00, 0x00, 0x0c, 0x18, 0x30, 0x30, 0x30, 0x30, 0x30, 0x30, 0x18, 0x0c, 0x00, 0x00, 0x00, // (
+    0x00, 0x00, 0x00, 0x60, 0x30, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x30, 0x60, 0x00, 0x00, 0x00, // )
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x6c, 0x38, 0xfe, 0x38, 0x6c, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // *
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x18, 0x18, 0x7e, 0x18, 0x18, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // +
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x18, 0x18, 0x18, 0x30, 0x00, 0x00, // ,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x7e, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // -
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x18, 0x18, 0x00, 0x00, 0x00, // .
+    0x00, 0x00, 0x00, 0x00, 0x02, 0x06, 0x0c, 0x18, 0x30, 0x60, 0xc0, 0x80, 0x00, 0x00, 0x00, 0x00, // /
+    0x00, 0x00, 0x00, 0x38, 0x6c, 0xc6, 0xce, 0xde, 0xf6, 0xe6, 0xc6, 0x6c, 0x38, 0x00, 0x00, 0x00, // 0
+    0x00, 0x00, 0x00, 0x18, 0x38, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x7e, 0x00, 0x00, 0x00, // 1
+    0x00, 0x00, 0x00, 0x7c, 0xc6, 0x06, 0x0c, 0x18, 0x30, 0x60, 0xc0, 0xc6, 0xfe, 0x00, 0x00, 0x00, // 2
+    0x00, 0x00, 0x00, 0x7c, 0xc6, 0x06, 0x06, 0x3c, 0x06, 0x06, 0x06, 0xc6, 0x7c, 0x00, 0x00, 0x00, // 3
+    0x00, 0x00, 0x00, 0x0c, 0x1c, 0x3c, 0x6c, 0xcc, 0xfe, 0x0c, 0x0c, 0x0c, 0x1e, 0x00, 0x00, 0x00, // 4
+    0x00, 0x00, 0x00, 0xfe, 0xc0, 0xc0, 0xc0, 0xfc, 0x06, 0x06, 0x06, 0xc6, 0x7c, 0x00, 0x00, 0x00, // 5
+    0x00, 0x00, 0x00, 0x38, 0x60, 0xc0, 0xc0, 0xfc, 0xc6, 0xc6, 0xc6, 0xc6, 0x7c, 0x00, 0x00, 0x00, // 6
+    0x00, 0x00, 0x00, 0xfe, 0xc6, 0x06, 0x0c, 0x18, 0x30, 0x30, 0x30, 0x30, 0x30, 0x00, 0x00, 0x00, // 7
+    0x00, 0x00, 0x00, 0x7c, 0xc6, 0xc6, 0xc6, 0x7c, 0xc6, 0xc6, 0xc6, 0xc6, 0x7c, 0x00, 0x00, 0x00, // 8
+    0x00, 0x00, 0x00, 0x7c, 0xc6, 0xc6, 0xc6, 0x7e, 0x06, 0x06, 0x06, 0x0c, 0x78, 0x00, 0x00, 0x00, // 9
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x18, 0x18, 0x00, 0x00, 0x00, 0x18, 0x18, 0x00, 0x00, 0x00, 0x00, // :
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x18, 0x18, 0x00, 0x00, 0x00, 0x18, 0x18, 0x30, 0x00, 0x00, 0x00, // ;
+    0x00, 0x00, 0x00, 0x00, 0x06, 0x0c, 0x18, 0x30, 0x60, 0x30, 0x18, 0x0c, 0x06, 0x00, 0x00, 0x00, // <
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x7e, 0x00, 0x00, 0x7e, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // =
+    0x00, 0x00, 0x00, 0x00, 0x60, 0x30, 0x18, 0x0c, 0x06, 0x0c, 0x18, 0x30, 0x60, 0x00, 0x00, 0x00, // >
+    0x00, 0x00, 0x00, 0x7c, 0xc6, 0xc6, 0x0c, 0x18, 0x18, 0x18, 0x00, 0x18, 0x18, 0x00, 0x00, 0x00, // ?
+    0x00, 0x00, 0x00, 0x00, 0x7c, 0xc6, 0xc6, 0xde, 0xde, 0xde, 0xdc, 0xc0, 0x7c, 0x00, 0x00, 0x00, // @
+    0x00, 0x00, 0x00, 0x10, 0x38, 0x6c, 0xc6, 0xc6, 0xfe, 0xc6, 0xc6, 0xc6, 0xc6, 0x00, 0x00, 0x00, // A
+    0x00, 0x00, 0x00, 0xfc, 0x66, 0x66, 0x66, 0x7c, 0x66, 0x66, 0x66, 0x66, 0xfc, 0x00, 0x00, 0x00, // B
+    0x00, 0x00, 0x00, 0x3c, 0x66, 0xc2, 0xc0, 0xc0, 0xc0, 0xc0, 0xc2, 0x66, 0x3c, 0x00, 0x00, 0x00, // C
+    0x00, 0x00, 0x00, 0xf8, 0x6c, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x6c, 0xf8, 0x00, 0x00, 0x00, // D
+    0x00, 0x00, 0x00, 0xfe, 0x66, 0x62, 0x68, 0x78, 0x68, 0x60, 0x62, 0x66, 0xfe, 0x00, 0x00, 0x00, // E
+    0x00, 0x00, 0x00, 0xfe, 0x66, 0x62, 0x68, 0x78, 0x68, 0x60, 0x60, 0x60, 0xf0, 0x00, 0x00, 0x00, // F
+    0x00, 0x00, 0x00, 0x3c, 0x66, 0xc2, 0xc0, 0xc0, 0xde, 0xc6, 0xc6, 0x66, 0x3a, 0x00, 0x00, 0x00, // G
+    0x00, 0x00, 0x00, 0xc6, 0xc6, 0xc6, 0xc6, 0xfe, 0xc6, 0xc6, 0xc6, 0xc6, 0xc6, 0x00, 0x00, 0x00, // H
+    0x00, 0x00, 0x00, 0x3c, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3c, 0x00, 0x00, 0x00, // I
+    0x00, 0x00, 0x00, 0x1e, 0x0c, 0x0c, 0x0c, 0x0c, 0x0c, 0xcc, 0xcc, 0xcc, 0x78, 0x00, 0x00, 0x00, // J
+    0x00, 0x00, 0x00, 0xe6, 0x66, 0x6c, 0x6c, 0x78, 0x78, 0x6c, 0x66, 0x66, 0xe6, 0x00, 0x00, 0x00, // K
+    0x00, 0x00, 0x00, 0xf0, 0x60, 0x60, 0x60, 0x60, 0x60, 0x60, 0x62, 0x66, 0xfe, 0x00, 0x00, 0x00, // L
+    0x00, 0x00, 0x00, 0xc6, 0xee, 0xfe, 0xfe, 0xd6, 0xc6, 0xc6, 0xc6, 0xc6, 0xc6, 0x00, 0x00, 0x00, // M
+    0x00, 0x00, 0x00, 0xc6, 0xe6, 0xf6, 0xfe, 0xde, 0xce, 0xc6, 0xc6, 0xc6, 0xc6, 0x00, 0x00, 0x00, // N
+    0x00, 0x00, 0x00, 0x7c, 0xc6, 0xc6, 0xc6, 0xc6, 0xc6, 0xc6, 0xc6, 0xc6, 0x7c, 0x00, 0x00, 0x00, // O
+    0x00, 0x00, 0x00, 0xfc, 0x66, 0x66, 0x66, 0x7c, 0x60, 0x60, 0x60, 0x60, 0xf0, 0x00, 0x00, 0x00, // P
+    0x00, 0x00, 0x00, 0x7c, 0xc6, 0xc6, 0xc6, 0xc6, 0xc6, 0xc6, 0xd6, 0xde, 0x7c, 0x0c, 0x0e, 0x00, // Q
+    0x00, 0x00, 0x00, 0xfc, 0x66, 0x66, 0x66, 0x7c, 0x6c, 0x66, 0x66, 0x66, 0xe6, 0x00, 0x00, 0x00, // R
+    0x00, 0x00, 0x00, 0x7c, 0xc6, 0xc6, 0x60, 0x38, 0x0c, 0x06, 0xc6, 0xc6, 0x7c, 0x00, 0x00, 0x00, // S
+    0x00, 0x00, 0x00, 0xfc, 0xb4, 0x30, 0x30, 0x30, 0x30, 0x30, 0x30, 0x30, 0x78, 0x00, 0x00, 0x00, // T
+    0x00, 0x00, 0x00, 0xc6, 0xc6, 0xc6, 0xc6, 0xc6, 0xc6, 0xc6, 0xc6, 0xc6, 0x7c, 0x00, 0x00, 0x00, // U
+    0x00, 0x00, 0x00, 0xc6, 0xc6, 0xc6, 0xc6, 0xc6, 0xc6, 0xc6, 0x6c, 0x38, 0x10, 0x00, 0x00, 0x00, // V
+    0x00, 0x00, 0x00, 0xc6, 0xc6, 0xc6, 0xc6, 0xd6, 0xd6, 0xd6, 0xfe, 0xee, 0x6c, 0x00, 0x00, 0x00, // W
+    0x00, 0x00, 0x00, 0xc6, 0xc6, 0x6c, 0x7c, 0x38, 0x38, 0x7c, 0x6c, 0xc6, 0xc6, 0x00, 0x00, 0x00, // X
+    0x00, 0x00, 0x00, 0xcc, 0xcc, 0xcc, 0xcc, 0x78, 0x30, 0x30, 0x30, 0x30, 0x78, 0x00, 0x00, 0x00, // Y
+    0x00, 0x00, 0x00, 0xfe, 0xc6, 0x86, 0x0c, 0x18, 0x30, 0x60, 0xc2, 0xc6, 0xfe, 0x00, 0x00, 0x00, // Z
+    0x00, 0x00, 0x00, 0x3c, 0x30, 0x30, 0x30, 0x30, 0x30, 0x30, 0x30, 0x30, 0x3c, 0x00, 0x00, 0x00, // [
+    0x00, 0x00, 0x00, 0x00, 0x80, 0xc0, 0x60, 0x30, 0x18, 0x0c, 0x06, 0x02, 0x00, 0x00, 0x00, 0x00, // \
+    0x00, 0x00, 0x00, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x78, 0x00, 0x00, 0x00, // ]
+    0x00, 0x00, 0x00, 0x10, 0x38, 0x6c, 0xc6, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // ^
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xfe, 0x00, // _
+    0x00, 0x00, 0x00, 0x30, 0x30, 0x18, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // `
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x78, 0x0c, 0x7c, 0xcc, 0xcc, 0xcc, 0x76, 0x00, 0x00, 0x00, // a
+    0x00, 0x00, 0x00, 0xe0, 0x60, 0x60, 0x78, 0x6c, 0x66, 0x66, 0x66, 0x66, 0x7c, 0x00, 0x00, 0x00, // b
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x7c, 0xc6, 0xc0, 0xc0, 0xc0, 0xc6, 0x7c, 0x00, 0x00, 0x00, // c
+    0x00, 0x00, 0x00, 0x1c, 0x0c, 0x0c, 0x3c, 0x6c, 0xcc, 0xcc, 0xcc, 0xcc, 0x76, 0x00, 0x00, 0x00, // d
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x7c, 0xc6, 0xfe, 0xc0, 0xc0, 0xc6, 0x7c, 0x00, 0x00, 0x00, // e
+    0x00, 0x00, 0x00, 0x38, 0x6c, 0x64, 0x60, 0xf0, 0x60, 0x60, 0x60, 0x60, 0xf0, 0x00, 0x00, 0x00, // f
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x76, 0xcc, 0xcc, 0xcc, 0xcc, 0xcc, 0x7c, 0x0c, 0xcc, 0x78, // g
+    0x00, 0x00, 0x00, 0xe0, 0x60, 0x60, 0x6c, 0x76, 0x66, 0x66, 0x66, 0x66, 0xe6, 0x00, 0x00, 0x00, // h
+    0x00, 0x00, 0x00, 0x18, 0x18, 0x00, 0x38, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3c, 0x00, 0x00, 0x00, // i
+    0x00, 0x00, 0x00, 0x06, 0x06, 0x00, 0x0e, 0x06, 0x06, 0x06, 0x06, 0x06, 0x06, 0x66, 0x66, 0x3c, // j
+    0x00, 0x00, 0x00, 0xe0, 0x60, 0x60, 0x66, 0x6c, 0x78, 0x78, 0x6c, 0x66, 0xe6, 0x00, 0x00, 0x00, // k
+    0x00, 0x00, 0x00, 0x38, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3c, 0x00, 0x00, 0x00, // l
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xec, 0xfe, 0xd6, 0xd6, 0xd6, 0xd6, 0xc6, 0x00, 0x00, 0x00, // m
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xdc, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x00, 0x00, 0x00, // n
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x7c, 0xc6, 0xc6, 0xc6, 0xc6, 0xc6, 0x7c, 0x00, 0x00, 0x00, // o
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xdc, 0x66, 0x66, 0x66, 0x66, 0x66, 0x7c, 0x60, 0x60, 0xf0, // p
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x76, 0xcc, 0xcc, 0xcc, 0xcc, 0xcc, 0x7c, 0x0c, 0x0c, 0x1e, // q
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xdc, 0x76, 0x66, 0x60, 0x60, 0x60, 0xf0, 0x00, 0x00, 0x00, // r
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x7c, 0xc6, 0x60, 0x38, 0x0c, 0xc6, 0x7c, 0x00, 0x00, 0x00, // s
+    0x00, 0x00, 0x00, 0x10, 0x30, 0x30, 0xfc, 0x30, 0x30, 0x30, 0x30, 0x36, 0x1c, 0x00, 0x00, 0x00, // t
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xcc, 0xcc, 0xcc, 0xcc, 0xcc, 0xcc, 0x76, 0x00, 0x00, 0x00, // u
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xc6, 0xc6, 0xc6, 0xc6, 0x6c, 0x38, 0x10, 0x00, 0x00, 0x00, // v
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xc6, 0xc6, 0xd6, 0xd6, 0xd6, 0xfe, 0x6c, 0x00, 0x00, 0x00, // w
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xc6, 0x6c, 0x38, 0x10, 0x38, 0x6c, 0xc6, 0x00, 0x00, 0x00, // x
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xc6, 0xc6, 0xc6, 0xc6, 0xc6, 0xc6, 0x7e, 0x06, 0x0c, 0xf8, // y
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xfe, 0xcc, 0x18, 0x30, 0x60, 0xc6, 0xfe, 0x00, 0x00, 0x00, // z
+    0x00, 0x00, 0x00, 0x0e, 0x18, 0x18, 0x18, 0x70, 0x18, 0x18, 0x18, 0x18, 0x0e, 0x00, 0x00, 0x00, // {
+    0x00, 0x00, 0x00, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x00, 0x00, // |
+    0x00, 0x00, 0x00, 0xe0, 0x30, 0x30, 0x30, 0x1c, 0x30, 0x30, 0x30, 0x30, 0xe0, 0x00, 0x00, 0x00, // }
+    0x00, 0x00, 0x00, 0x76, 0xdc, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // ~
+];
+
+/// Error returned when parsing a PC Screen Font.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PsfError {
+    /// The data doesn't start with the magic number of a PSF1 or PSF2 font.
+    InvalidMagic,
+    /// The PSF2 version is not supported.
+    UnsupportedVersion(u32),
+    /// The header describes empty or inconsistently sized glyphs.
+    InvalidHeader,
+    /// The data ends before the glyphs described by the header.
+    Truncated,
+}
+
+impl Display for PsfError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidMagic => write!(f, "not a PSF font"),
+            Self::UnsupportedVersion(version) => write!(f, "unsupported PSF2 version {version}"),
+            Self::InvalidHeader => write!(f, "invalid PSF header"),
+            Self::Truncated => write!(f, "PSF data is truncated"),
+        }
+    }
+}
+
+impl core::error::Error for PsfError {}
+
+/// How characters map to glyphs.
+#[derive(Clone, Copy, Debug)]
+enum CharMap<'a> {
+    /// Glyph `i` is for the character with code point `first + i`.
+    Direct { first: u32 },
+    /// PSF1 Unicode table of UCS-2 values.
+    Psf1(&'a [u8]),
+    /// PSF2 Unicode table of UTF-8 characters.
+    Psf2(&'a [u8]),
+}
+
+impl CharMap<'_> {
+    /// Returns the index of the glyph for `c`, which may be out of range.
+    fn index(&self, c: char) -> Option<usize> {
+        match *self {
+            Self::Direct { first } => (c as u32).checked_sub(first).map(|i| i as usize),
+            Self::Psf1(table) => {
+                let mut glyph = 0;
+                let mut in_sequence = false;
+                for value in table.chunks_exact(2) {
+                    match u16::from_le_bytes([value[0], value[1]]) {
+                        PSF1_SEPARATOR => {
+                            glyph += 1;
+                            in_sequence = false;
+                        }
+                        PSF1_STARTSEQ => in_sequence = true,
+                        value if !in_sequence && u32::from(value) == c as u32 => {
+                            return Some(glyph);
+                        }
+                        _ => {}
+                    }
+                }
+                None
+            }
+            Self::Psf2(mut table) => {
+                let mut glyph = 0;
+                let mut in_sequence = false;
+                while let Some(&byte) = table.first() {
+                    let len = match byte {
+                        PSF2_SEPARATOR => {
+                            glyph += 1;
+                            in_sequence = false;
+                            1
+                        }
+                        PSF2_STARTSEQ => {
+                            in_sequence = true;
+                            1
+                        }
+                        0xf0.. => 4,
+                        0xe0.. => 3,
+                        0xc0.. => 2,
+                        _ => 1,
+                    };
+                    let (entry, rest) = table.split_at(len.min(table.len()));
+                    if !in_sequence && core::str::from_utf8(entry).is_ok_and(|s| s.starts_with(c)) {
+                        return Some(glyph);
+                    }
+                    table = rest;
+                }
+                None
+            }
+        }
+    }
+}
+
+/// The bitmap of a glyph, as returned by [`BitmapFont::glyph`].
+#[derive(Clone, Copy, Debug)]
+pub struct Glyph<'a> {
+    data: &'a [u8],
+    width: usize,
+    height: usize,
+}
+
+impl Glyph<'_> {
+    /// Returns the width in pixels.
+    #[must_use]
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the height in pixels.
+    #[must_use]
+    pub const fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns whether the pixel at (`x`, `y`) is part of the character.
+    /// Pixels outside of the glyph are not.
+    #[must_use]
+    pub fn is_set(&self, x: usize, y: usize) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+        let row = &self.data[y * self.width.div_ceil(8)..];
+        row[x / 8] & (0x80 >> (x % 8)) != 0
+    }
+
+    /// Draws the glyph with its top-left corner at `pos`. Pixels that are
+    /// not part of the character are filled with `bg`, or left unchanged if
+    /// it is `None`.
+    pub fn draw<C: Canvas + ?Sized>(
+        &self,
+        canvas: &mut C,
+        pos: (usize, usize),
+        fg: BltPixel,
+        bg: Option<BltPixel>,
+    ) {
+        let (x0, y0) = pos;
+        for y in 0..self.height {
+            // Draw runs of pixels of the same color at once.
+            let mut start = 0;
+            while start < self.width {
+                let set = self.is_set(start, y);
+                let len = (start..self.width)
+                    .take_while(|&x| self.is_set(x, y) == set)
+                    .count();
+                match (set, bg) {
+                    (true, _) => canvas.fill_span(x0 + start, y0 + y, len, fg),
+                    (false, Some(bg)) => canvas.fill_span(x0 + start, y0 + y, len, bg),
+                    (false, None) => {}
+                }
+                start += len;
+            }
+        }
+    }
+}
+
+/// A monospaced bitmap font, for drawing text on a [`Canvas`].
+///
+/// This doesn't depend on the text console or the HII font database, so it
+/// works with any graphics mode, also on firmware without HII support.
+/// [`BitmapFont::DEFAULT`] is a built-in 8x16 font covering printable
+/// ASCII. Fonts with more characters can be loaded from PC Screen Font
+/// (PSF) files, the format of the Linux console fonts, with [`from_psf`].
+///
+/// Characters without a glyph are drawn as `U+FFFD` if the font has it, or
+/// as `?`.
+///
+/// # Example
+///
+/// ```
+/// use uefi::proto::console::gop::{BitmapFont, BltPixel, PixelBuffer};
+///
+/// let mut pixels = [BltPixel::new(0, 0, 0); 320 * 32];
+/// let mut canvas = PixelBuffer::new(&mut pixels, 320);
+/// let font = BitmapFont::DEFAULT;
+/// let white = BltPixel::new(0xff, 0xff, 0xff);
+/// let end = font.draw_str(&mut canvas, (0, 0), "Hello,\nworld!", white, None);
+/// assert_eq!(end, (6 * 8, 16));
+/// ```
+///
+/// [`from_psf`]: Self::from_psf
+#[derive(Clone, Copy, Debug)]
+pub struct BitmapFont<'a> {
+    glyphs: &'a [u8],
+    width: usize,
+    height: usize,
+    /// Size of a glyph in bytes.
+    glyph_size: usize,
+    map: CharMap<'a>,
+}
+
+impl BitmapFont<'static> {
+    /// Built-in 8x16 font covering the printable ASCII characters.
+    pub const DEFAULT: Self = Self::new(8, 16, ' ', &DEFAULT_GLYPHS);
+}
+
+impl<'a> BitmapFont<'a> {
+    /// Creates a font from raw glyph bitmaps, for consecutive characters
+    /// starting at `first_char`.
+    ///
+    /// Each glyph is `height` rows of `width` pixels, with one bit per
+    /// pixel and the most significant bit first. Each row is padded to a
+    /// whole number of bytes. Trailing bytes that don't form a whole glyph
+    /// are ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` or `height` is 0.
+    #[must_use]
+    pub const fn new(width: usize, height: usize, first_char: char, glyphs: &'a [u8]) -> Self {
+        assert!(width > 0 && height > 0, "empty glyphs");
+        Self {
+            glyphs,
+            width,
+            height,
+            glyph_size: width.div_ceil(8) * height,
+            map: CharMap::Direct {
+                first: first_char as u32,
+            },
+        }
+    }
+
+    /// Parses a PC Screen Font, version 1 or 2.
+    ///
+    /// The glyphs are mapped to characters with the Unicode table of the
+    /// font. Without one, glyph `i` is used for the character with code
+    /// point `i`.
+    ///
+    /// # Errors
+    ///
+    /// See [`PsfError`].
+    pub fn from_psf(data: &'a [u8]) -> core::result::Result<Self, PsfError> {
+        if data.starts_with(&PSF1_MAGIC) {
+            Self::from_psf1(data)
+        } else if data.starts_with(&PSF2_MAGIC) {
+            Self::from_psf2(data)
+        } else {
+            Err(PsfError::InvalidMagic)
+        }
+    }
+
+    fn from_psf1(data: &'a [u8]) -> core::result::Result<Self, PsfError> {
+        let [_, _, mode, height, ..] = *data else {
+            return Err(PsfError::Truncated);
+        };
+        let height = usize::from(height);
+        if height == 0 {
+            return Err(PsfError::InvalidHeader);
+        }
+        let count = if mode & PSF1_MODE512 != 0 { 512 } else { 256 };
+        let glyphs = data.get(4..4 + count * height).ok_or(PsfError::Truncated)?;
+        let map = if mode & PSF1_MODEHASTAB != 0 {
+            CharMap::Psf1(&data[4 + glyphs.len()..])
+        } else {
+            CharMap::Direct { first: 0 }
+        };
+        Ok(Self {
+            glyphs,
+            width: 8,
+            height,
+            glyph_size: height,
+            map,
+        })
+    }
+
+    fn from_psf2(data: &'a [u8]) -> core::result::Result<Self, PsfError> {
+        let header = data.get(..PSF2_HEADER_SIZE).ok_or(PsfError::Truncated)?;
+        let field = |i: usize| {
+            let bytes = &header[4 + 4 * i..8 + 4 * i];
+            u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize
+        };
+        let [
+            version,
+            header_size,
+            flags,
+            count,
+            glyph_size,
+            height,
+            width,
+        ] = core::array::from_fn(field);
+        if version != 0 {
+            return Err(PsfError::UnsupportedVersion(version as u32));
+        }
+        if header_size < PSF2_HEADER_SIZE
+            || width == 0
+            || height == 0
+            || glyph_size < width.div_ceil(8) * height
+        {
+            return Err(PsfError::InvalidHeader);
+        }
+        let len = count
+            .checked_mul(glyph_size)
+            .ok_or(PsfError::InvalidHeader)?;
+        let glyphs = data
+            .get(header_size..)
+            .and_then(|glyphs| glyphs.get(..len))
+            .ok_or(PsfError::Truncated)?;
+        let map = if flags as u32 & PSF2_HAS_UNICODE_TABLE != 0 {
+            CharMap::Psf2(&data[header_size + len..])
+        } else {
+            CharMap::Direct { first: 0 }
+        };
+        Ok(Self {
+            glyphs,
+            width,
+            height,
+            glyph_size,
+            map,
+        })
+    }
+
+    /// Returns the width of a character in pixels.
+    #[must_use]
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the height of a character in pixels.
+    #[must_use]
+    pub const fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the number of glyphs.
+    #[must_use]
+    pub const fn glyph_count(&self) -> usize {
+        self.glyphs.len() / self.glyph_size
+    }
+
+    /// Returns the glyph for `c`, or `None` if the font doesn't have one.
+    #[must_use]
+    pub fn glyph(&self, c: char) -> Option<Glyph<'a>> {
+        let index = self.map.index(c)?;
+        let start = index.checked_mul(self.glyph_size)?;
+        let data = self.glyphs.get(start..start + self.glyph_size)?;
+        Some(Glyph {
+            data,
+            width: self.width,
+            height: self.height,
+        })
+    }
+
+    /// Returns the glyph to draw for `c`, which is a replacement if the
+    /// font doesn't have one.
+    fn glyph_or_replacement(&self, c: char) -> Option<Glyph<'a>> {
+        self.glyph(c)
+            .or_else(|| self.glyph(char::REPLACEMENT_CHARACTER))
+            .or_else(|| self.glyph('?'))
+    }
+
+    /// Draws `c` with its top-left corner at `pos`. Pixels that are not
+    /// part of the character are filled with `bg`, or left unchanged if it
+    /// is `None`.
+    pub fn draw_char<C: Canvas + ?Sized>(
+        &self,
+        canvas: &mut C,
+        pos: (usize, usize),
+        c: char,
+        fg: BltPixel,
+        bg: Option<BltPixel>,
+    ) {
+        if let Some(glyph) = self.glyph_or_replacement(c) {
+            glyph.draw(canvas, pos, fg, bg);
+        }
+    }
+
+    /// Draws `chars` starting with the top-left corner of the first one at
+    /// `pos`, and returns the position of the next character.
+    ///
+    /// `'\n'` moves to the start of the next line, `'\r'` to the start of
+    /// the current line, and `'\t'` to the next tab stop. Lines are not
+    /// wrapped.
+    pub fn draw_chars<C: Canvas + ?Sized>(
+        &self,
+        canvas: &mut C,
+        pos: (usize, usize),
+        chars: impl IntoIterator<Item = char>,
+        fg: BltPixel,
+        bg: Option<BltPixel>,
+    ) -> (usize, usize) {
+        let (x0, mut y) = pos;
+        let mut column = 0;
+        for c in chars {
+            match c {
+                '\n' => {
+                    column = 0;
+                    y += self.height;
+                }
+                '\r' => column = 0,
+                '\t' => {
+                    let next = (column / TAB_WIDTH + 1) * TAB_WIDTH;
+                    if let Some(bg) = bg {
+                        for column in column..next {
+                            let x = x0 + column * self.width;
+                            self.draw_char(canvas, (x, y), ' ', bg, Some(bg));
+                        }
+                    }
+                    column = next;
+                }
+                c => {
+                    self.draw_char(canvas, (x0 + column * self.width, y), c, fg, bg);
+                    column += 1;
+                }
+            }
+        }
+        (x0 + column * self.width, y)
+    }
+
+    /// Draws `text` like [`draw_chars`].
+    ///
+    /// [`draw_chars`]: Self::draw_chars
+    pub fn draw_str<C: Canvas + ?Sized>(
+        &self,
+        canvas: &mut C,
+        pos: (usize, usize),
+        text: &str,
+        fg: BltPixel,
+        bg: Option<BltPixel>,
+    ) -> (usize, usize) {
+        self.draw_chars(canvas, pos, text.chars(), fg, bg)
+    }
+
+    /// Draws `text` like [`draw_chars`].
+    ///
+    /// [`draw_chars`]: Self::draw_chars
+    pub fn draw_cstr16<C: Canvas + ?Sized>(
+        &self,
+        canvas: &mut C,
+        pos: (usize, usize),
+        text: &CStr16,
+        fg: BltPixel,
+        bg: Option<BltPixel>,
+    ) -> (usize, usize) {
+        let chars = text
+            .iter()
+            .map(|&c| char::from_u32(u16::from(c).into()).unwrap_or(char::REPLACEMENT_CHARACTER));
+        self.draw_chars(canvas, pos, chars, fg, bg)
+    }
+
+    /// Returns the width and height in pixels of `text` drawn with
+    /// [`draw_str`].
+    ///
+    /// [`draw_str`]: Self::draw_str
+    #[must_use]
+    pub fn text_size(&self, text: &str) -> (usize, usize) {
+        let mut columns = 0;
+        let mut lines = 0;
+        for line in text.split('\n') {
+            let mut column = 0;
+            for c in line.chars() {
+                column = match c {
+                    '\r' => 0,
+                    '\t' => (column / TAB_WIDTH + 1) * TAB_WIDTH,
+                    _ => column + 1,
+                };
+                columns = columns.max(column);
+            }
+            lines += 1;
+        }
+        (columns * self.width, lines * self.height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cstr16;
+    use crate::proto::console::gop::PixelBuffer;
+
+    const BLACK: BltPixel = BltPixel::new(0, 0, 0);
+    const WHITE: BltPixel = BltPixel::new(0xff, 0xff, 0xff);
+    const GRAY: BltPixel = BltPixel::new(0x80, 0x80, 0x80);
+
+    /// A 4x2 glyph with its top-left and bottom-right pixels set.
+    const GLYPH: [u8; 2] = [0x80, 0x10];
+
+    /// Renders the pixels of a canvas as `#` for white, `-` for gray, and
+    /// `.` for black.
+    fn render<const W: usize, const H: usize>(canvas: &PixelBuffer) -> [[u8; W]; H] {
+        let mut rows = [[b'.'; W]; H];
+        for (i, pixel) in canvas.pixels().iter().enumerate() {
+            rows[i / W][i % W] = match *pixel {
+                WHITE => b'#',
+                GRAY => b'-',
+                _ => b'.',
+            };
+        }
+        rows
+    }
+
+    #[test]
+    fn test_default_font() {
+        let font = BitmapFont::DEFAULT;
+        assert_eq!((font.width(), font.height()), (8, 16));
+        assert_eq!(font.glyph_count(), 95);
+        let a = font.glyph('A').unwrap();
+        assert!(a.is_set(3, 3));
+        assert!(!a.is_set(0, 3));
+        assert!(!a.is_set(8, 3));
+        assert!(font.glyph('~').is_some());
+        assert!(font.glyph('\x7f').is_none());
+        assert!(font.glyph('\x1f').is_none());
+
+        // Unknown characters are drawn as '?'.
+        let mut unknown = [BLACK; 8 * 16];
+        let mut question = [BLACK; 8 * 16];
+        font.draw_char(
+            &mut PixelBuffer::new(&mut unknown, 8),
+            (0, 0),
+            'é',
+            WHITE,
+            None,
+        );
+        font.draw_char(
+            &mut PixelBuffer::new(&mut question, 8),
+            (0, 0),
+            '?',
+            WHITE,
+            None,
+        );
+        assert_eq!(unknown, question);
+
+        assert_eq!(font.text_size("ab\tc\nd"), (9 * 8, 32));
+        assert_eq!(font.text_size(""), (0, 16));
+    }
+
+    #[test]
+    fn test_draw() {
+        let font = BitmapFont::new(4, 2, 'x', &GLYPH);
+        let mut pixels = [BLACK; 10 * 4];
+        let mut canvas = PixelBuffer::new(&mut pixels, 10);
+        let end = font.draw_str(&mut canvas, (1, 0), "xx\nyx", WHITE, Some(GRAY));
+        assert_eq!(end, (9, 2));
+        // The 'y' has no glyph, and there is no replacement.
+        assert_eq!(
+            render::<10, 4>(&canvas),
+            [
+                *b".#---#---.",
+                *b".---#---#.",
+                *b".....#---.",
+                *b".....---#.",
+            ]
+        );
+
+        let end = font.draw_cstr16(&mut canvas, (0, 0), cstr16!("\tx\rx"), BLACK, None);
+        assert_eq!(end, (4, 0));
+    }
+
+    #[test]
+    fn test_psf1() {
+        let mut data = [0; 4 + 256 * 2 + 7 * 2];
+        data[..4].copy_from_slice(&[0x36, 0x04, PSF1_MODEHASTAB, 2]);
+        data[4 + 2..4 + 4].copy_from_slice(&GLYPH);
+        // Glyph 0 is for U+FFFD, glyph 1 for 'a' and the sequence "bc".
+        let table = [
+            0xfffd, 0xffff, 'a' as u16, 0xfffe, 'b' as u16, 'c' as u16, 0xffff,
+        ];
+        for (i, value) in table.iter().enumerate() {
+            data[4 + 512 + 2 * i..][..2].copy_from_slice(&value.to_le_bytes());
+        }
+        let font = BitmapFont::from_psf(&data).unwrap();
+        assert_eq!((font.width(), font.height()), (8, 2));
+        assert_eq!(font.glyph_count(), 256);
+        assert!(font.glyph('a').unwrap().is_set(0, 0));
+        assert!(font.glyph('b').is_none());
+        assert!(!font.glyph('\u{fffd}').unwrap().is_set(0, 0));
+
+        assert_eq!(
+            BitmapFont::from_psf(&data[..100]).unwrap_err(),
+            PsfError::Truncated
+        );
+        assert_eq!(
+            BitmapFont::from_psf(&[0; 4]).unwrap_err(),
+            PsfError::InvalidMagic
+        );
+    }
+
+    #[test]
+    fn test_first_char() {
+        static GLYPHS: [u8; 3] = [1, 2, 3];
+        let font = BitmapFont::new(8, 1, 'a', &GLYPHS);
+        assert!(font.glyph('b').unwrap().is_set(6, 0));
+        assert!(font.glyph('\0').is_none());
+        assert!(font.glyph('d').is_none());
+    }
+
+    #[test]
+    fn test_psf2() {
+        let mut data = [0; 32 + 4 * 4 + 6];
+        let header = [0, 32, 1, 4, 4, 2, 12];
+        data[..4].copy_from_slice(&PSF2_MAGIC);
+        for (i, value) in header.iter().enumerate() {
+            data[4 + 4 * i..][..4].copy_from_slice(&u32::to_le_bytes(*value));
+        }
+        // Glyph 1 is 12 pixels wide, with the last pixel of the first row
+        // set.
+        data[32 + 4..][..2].copy_from_slice(&[0x00, 0x10]);
+        // Glyph 0 is for 'é', glyph 1 for 'a'.
+        data[48..].copy_from_slice(&[0xc3, 0xa9, 0xff, b'a', 0xff, 0xff]);
+        let font = BitmapFont::from_psf(&data).unwrap();
+        assert_eq!((font.width(), font.height()), (12, 2));
+        assert_eq!(font.glyph_count(), 4);
+        assert!(font.glyph('a').unwrap().is_set(11, 0));
+        assert!(font.glyph('é').is_some());
+        assert!(font.glyph('b').is_none());
+
+        // Without a Unicode table, glyph `i` is for code point `i`.
+        data[4 + 4 * 2] = 0;
+        let font = BitmapFont::from_psf(&data[..48]).unwrap();
+        assert!(font.glyph('\u{1}').unwrap().is_set(11, 0));
+        assert!(font.glyph('\u{4}').is_none());
+        data[4 + 4 * 2] = 1;
+
+        data[4] = 1;
+        assert_eq!(
+            BitmapFont::from_psf(&data).unwrap_err(),
+            PsfError::UnsupportedVersion(1)
+        );
+        data[4] = 0;
+        data[4 + 4 * 6] = 20;
+        assert_eq!(
+            BitmapFont::from_psf(&data).unwrap_err(),
+            PsfError::InvalidHeader
+        );
+    }
+}
@@ -49,8 +49,9 @@
 //! UEFI does not mention if double buffering is used, nor how often
 //! the frame buffer gets sent to the screen, but it's safe to assume that
 //! the graphics card will re-draw the buffer at around the monitor's refresh rate.
-//! You will have to implement your own double buffering if you want to
-//! avoid tearing with animations.
+//! You will have to use double buffering if you want to avoid tearing with
+//! animations, e.g. with a `FrameBufferSurface` (requires the `alloc`
+//! feature).
 
 use crate::proto::unsafe_protocol;
 use crate::util::usize_from_u32;
@@ -65,6 +66,30 @@ use uefi_raw::protocol::console::{
 
 pub use uefi_raw::protocol::console::PixelBitmask;
 
+mod bmp;
+pub use bmp::{Bmp, BmpError};
+
+mod draw;
+pub use draw::{Canvas, FrameBufferCanvas, PixelBuffer, Rect};
+
+mod edid;
+pub use edid::{
+    DetailedTiming, Edid, EdidActive, EdidDiscovered, EdidError, EdidOverride,
+    EdidOverrideAttributes,
+};
+
+mod font;
+pub use font::{BitmapFont, Glyph, PsfError};
+
+#[cfg(feature = "alloc")]
+mod queue;
+#[cfg(feature = "alloc")]
+pub use queue::BltQueue;
+#[cfg(feature = "alloc")]
+mod surface;
+#[cfg(feature = "alloc")]
+pub use surface::FrameBufferSurface;
+
 /// Graphics Output [`Protocol`] (GOP). Provides access to the video hardware's
 /// frame buffer.
 ///
@@ -456,7 +481,7 @@ impl ExactSizeIterator for ModeIter<'_> {}
 ///
 /// This is a BGR 24-bit format with an 8-bit padding, to keep each pixel 32-bit in size.
 #[allow(missing_docs)]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 #[repr(C)]
 pub struct BltPixel {
     pub blue: u8,
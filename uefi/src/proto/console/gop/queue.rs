@@ -0,0 +1,274 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Batching of blit operations.
+
+use super::{BltOp, BltPixel, BltRegion, GraphicsOutput, Rect};
+use crate::Result;
+use alloc::vec::Vec;
+
+/// A recorded blit operation.
+#[derive(Clone, Copy, Debug)]
+enum QueuedOp {
+    Fill { color: BltPixel, rect: Rect },
+    Redraw { rect: Rect },
+    Copy { src: (usize, usize), dest: Rect },
+}
+
+impl QueuedOp {
+    /// Returns whether the operation reads or writes any pixel of `rect`.
+    const fn touches(&self, rect: &Rect) -> bool {
+        match self {
+            Self::Fill { rect: r, .. } | Self::Redraw { rect: r } => r.intersects(rect),
+            Self::Copy { src, dest } => {
+                let src = Rect::new(src.0, src.1, dest.width, dest.height);
+                src.intersects(rect) || dest.intersects(rect)
+            }
+        }
+    }
+}
+
+/// Records blit operations and submits them with as few calls to
+/// [`GraphicsOutput::blt`] as possible.
+///
+/// On some firmware, each call to `blt` is expensive. `BltQueue` merges
+/// operations that can be combined into a single call:
+///
+/// * Fills with the same color whose rectangles together form a rectangle.
+/// * Regions invalidated with [`invalidate`], which are redrawn from a
+///   caller-provided backbuffer. Since redrawing is idempotent, regions are
+///   merged whenever their bounding box is no larger than the two regions
+///   combined.
+///
+/// Operations are submitted in the order they were recorded, except that an
+/// operation may be merged into an earlier one if no operation in between
+/// touches the affected pixels. The visible result is therefore the same as
+/// submitting each operation individually.
+///
+/// # Example
+///
+/// ```no_run
+/// use uefi::proto::console::gop::{BltPixel, BltQueue, GraphicsOutput, Rect};
+///
+/// # fn example(gop: &mut GraphicsOutput, backbuffer: &[BltPixel], width: usize) -> uefi::Result {
+/// let mut queue = BltQueue::new();
+/// let black = BltPixel::new(0, 0, 0);
+/// queue.fill(black, Rect::new(0, 0, 100, 10));
+/// queue.fill(black, Rect::new(0, 10, 100, 10));
+/// queue.invalidate(Rect::new(200, 0, 16, 16));
+/// queue.invalidate(Rect::new(216, 0, 16, 16));
+/// // Two calls to `blt`: one fill and one buffer transfer.
+/// queue.submit(gop, backbuffer, width)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`invalidate`]: Self::invalidate
+#[derive(Clone, Debug, Default)]
+pub struct BltQueue {
+    ops: Vec<QueuedOp>,
+}
+
+impl BltQueue {
+    /// Creates an empty queue.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { ops: Vec::new() }
+    }
+
+    /// Returns the number of `blt` calls needed to submit the queue.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// Returns whether the queue is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// Removes all recorded operations without submitting them.
+    pub fn clear(&mut self) {
+        self.ops.clear();
+    }
+
+    /// Tries to merge an operation on `rect` into an earlier operation.
+    ///
+    /// `merge` is called for each earlier operation that may be combined
+    /// with the new one, starting with the most recent one, and returns the
+    /// merged rectangle if the operations can be combined.
+    fn try_merge(&mut self, rect: Rect, merge: impl Fn(&QueuedOp) -> Option<Rect>) -> bool {
+        for i in (0..self.ops.len()).rev() {
+            if let Some(union) = merge(&self.ops[i]) {
+                if self.ops[i + 1..].iter().all(|op| !op.touches(&union)) {
+                    match &mut self.ops[i] {
+                        QueuedOp::Fill { rect, .. } | QueuedOp::Redraw { rect } => *rect = union,
+                        QueuedOp::Copy { .. } => unreachable!(),
+                    }
+                    return true;
+                }
+            }
+            // The new operation can't be moved before one that touches the
+            // same pixels.
+            if self.ops[i].touches(&rect) {
+                return false;
+            }
+        }
+        false
+    }
+
+    /// Records filling `rect` with `color`.
+    pub fn fill(&mut self, color: BltPixel, rect: Rect) {
+        if rect.is_empty() {
+            return;
+        }
+        let merged = self.try_merge(rect, |op| match op {
+            QueuedOp::Fill { color: c, rect: r } if *c == color && r.union_is_exact(&rect) => {
+                Some(r.union(&rect))
+            }
+            _ => None,
+        });
+        if !merged {
+            self.ops.push(QueuedOp::Fill { color, rect });
+        }
+    }
+
+    /// Records that `rect` must be redrawn from the backbuffer passed to
+    /// [`submit`].
+    ///
+    /// [`submit`]: Self::submit
+    pub fn invalidate(&mut self, rect: Rect) {
+        if rect.is_empty() {
+            return;
+        }
+        let merged = self.try_merge(rect, |op| match op {
+            QueuedOp::Redraw { rect: r } => {
+                let union = r.union(&rect);
+                (union.area() <= r.area() + rect.area()).then_some(union)
+            }
+            _ => None,
+        });
+        if !merged {
+            self.ops.push(QueuedOp::Redraw { rect });
+        }
+    }
+
+    /// Records copying the rectangle at `src` to `dest`. Copies are never
+    /// merged.
+    pub fn copy(&mut self, src: (usize, usize), dest: Rect) {
+        if !dest.is_empty() {
+            self.ops.push(QueuedOp::Copy { src, dest });
+        }
+    }
+
+    /// Submits all recorded operations to `gop` and clears the queue.
+    ///
+    /// `backbuffer` holds the pixels for regions recorded with
+    /// [`invalidate`], in rows of `width` pixels using the same coordinates
+    /// as the screen. If no region was invalidated, it is not accessed and
+    /// may be empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an invalidated region is outside of the backbuffer or the
+    /// frame buffer.
+    ///
+    /// [`invalidate`]: Self::invalidate
+    pub fn submit(
+        &mut self,
+        gop: &mut GraphicsOutput,
+        backbuffer: &[BltPixel],
+        width: usize,
+    ) -> Result {
+        for op in self.ops.drain(..) {
+            let op = match op {
+                QueuedOp::Fill { color, rect } => BltOp::VideoFill {
+                    color,
+                    dest: (rect.x, rect.y),
+                    dims: (rect.width, rect.height),
+                },
+                QueuedOp::Redraw { rect } => BltOp::BufferToVideo {
+                    buffer: backbuffer,
+                    src: BltRegion::SubRectangle {
+                        coords: (rect.x, rect.y),
+                        px_stride: width,
+                    },
+                    dest: (rect.x, rect.y),
+                    dims: (rect.width, rect.height),
+                },
+                QueuedOp::Copy { src, dest } => BltOp::VideoToVideo {
+                    src,
+                    dest: (dest.x, dest.y),
+                    dims: (dest.width, dest.height),
+                },
+            };
+            gop.blt(op)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: BltPixel = BltPixel::new(0xff, 0, 0);
+    const BLUE: BltPixel = BltPixel::new(0, 0, 0xff);
+
+    #[test]
+    fn test_rect() {
+        let a = Rect::new(0, 0, 10, 10);
+        let b = Rect::new(10, 0, 5, 10);
+        let c = Rect::new(5, 5, 10, 10);
+        assert!(!a.intersects(&b));
+        assert!(a.intersects(&c));
+        assert_eq!(a.union(&b), Rect::new(0, 0, 15, 10));
+        assert_eq!(a.intersection(&c), Rect::new(5, 5, 5, 5));
+        assert!(a.intersection(&b).is_empty());
+        assert!(a.union_is_exact(&b));
+        assert!(!a.union_is_exact(&c));
+        assert!(!a.intersects(&Rect::new(5, 5, 0, 3)));
+    }
+
+    #[test]
+    fn test_merge_fills() {
+        let mut queue = BltQueue::new();
+        queue.fill(RED, Rect::new(0, 0, 10, 10));
+        queue.fill(RED, Rect::new(0, 10, 10, 10));
+        queue.fill(RED, Rect::new(10, 0, 10, 20));
+        assert_eq!(queue.len(), 1);
+
+        // Different color or not forming a rectangle.
+        queue.fill(BLUE, Rect::new(20, 0, 10, 20));
+        queue.fill(RED, Rect::new(40, 40, 10, 10));
+        assert_eq!(queue.len(), 3);
+
+        // Can't be merged into the first fill, since the blue fill in between
+        // touches the same pixels.
+        queue.fill(RED, Rect::new(20, 0, 10, 20));
+        assert_eq!(queue.len(), 4);
+    }
+
+    #[test]
+    fn test_merge_invalidations() {
+        let mut queue = BltQueue::new();
+        queue.invalidate(Rect::new(0, 0, 8, 16));
+        queue.invalidate(Rect::new(8, 0, 8, 16));
+        queue.fill(RED, Rect::new(100, 100, 10, 10));
+        // Merged across the unrelated fill.
+        queue.invalidate(Rect::new(16, 0, 8, 16));
+        assert_eq!(queue.len(), 2);
+
+        // Too much overdraw.
+        queue.invalidate(Rect::new(200, 200, 8, 8));
+        assert_eq!(queue.len(), 3);
+
+        // Copies act as barriers.
+        queue.copy((0, 0), Rect::new(0, 20, 24, 16));
+        queue.invalidate(Rect::new(0, 16, 24, 16));
+        assert_eq!(queue.len(), 5);
+
+        queue.clear();
+        assert!(queue.is_empty());
+    }
+}
@@ -0,0 +1,348 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Double buffering.
+
+use super::{BltOp, BltPixel, BltRegion, Canvas, FrameBufferCanvas, GraphicsOutput, Rect};
+use crate::{Result, Status};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Maximum number of separate dirty regions. Further regions are merged
+/// into the existing ones.
+const MAX_DIRTY: usize = 16;
+
+/// An in-memory backbuffer for a [`GraphicsOutput`] device.
+///
+/// Drawing happens in the backbuffer, which records the regions that
+/// changed. [`flush`] then copies those regions to the screen, so that
+/// frames are only shown once complete, without tearing from partially
+/// drawn content. Flushing only the dirty regions also keeps the expensive
+/// accesses to video memory to a minimum.
+///
+/// The surface has the resolution of the mode that was current when it was
+/// created. Call [`resize`] after changing the mode.
+///
+/// # Example
+///
+/// ```no_run
+/// use uefi::proto::console::gop::{BltPixel, FrameBufferSurface, GraphicsOutput, Rect};
+///
+/// # fn example(gop: &mut GraphicsOutput) -> uefi::Result {
+/// let mut surface = FrameBufferSurface::new(gop);
+/// for x in 0..100 {
+///     surface.clear(BltPixel::new(0, 0, 0));
+///     surface.fill_rect(Rect::new(x, 10, 20, 20), BltPixel::new(0xff, 0, 0));
+///     surface.flush(gop)?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`flush`]: Self::flush
+/// [`resize`]: Self::resize
+#[derive(Clone, Debug)]
+pub struct FrameBufferSurface {
+    width: usize,
+    height: usize,
+    pixels: Vec<BltPixel>,
+    dirty: Vec<Rect>,
+}
+
+impl FrameBufferSurface {
+    /// Creates a black surface matching the current mode of `gop`.
+    #[must_use]
+    pub fn new(gop: &GraphicsOutput) -> Self {
+        let (width, height) = gop.current_mode_info().resolution();
+        Self::with_size(width, height)
+    }
+
+    /// Creates a black surface of the given size.
+    #[must_use]
+    pub fn with_size(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![BltPixel::new(0, 0, 0); width * height],
+            dirty: Vec::new(),
+        }
+    }
+
+    /// Resizes the surface to match the current mode of `gop`, clearing it
+    /// to black and marking it dirty.
+    pub fn resize(&mut self, gop: &GraphicsOutput) {
+        let (width, height) = gop.current_mode_info().resolution();
+        *self = Self::with_size(width, height);
+        self.mark_all_dirty();
+    }
+
+    /// Returns the width in pixels.
+    #[must_use]
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the height in pixels.
+    #[must_use]
+    pub const fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the rectangle covering the whole surface.
+    #[must_use]
+    pub const fn bounds(&self) -> Rect {
+        Rect::new(0, 0, self.width, self.height)
+    }
+
+    /// Returns the pixels, in rows of [`width`] pixels.
+    ///
+    /// [`width`]: Self::width
+    #[must_use]
+    pub fn pixels(&self) -> &[BltPixel] {
+        &self.pixels
+    }
+
+    /// Returns the pixels for modification, marking the whole surface
+    /// dirty.
+    pub fn pixels_mut(&mut self) -> &mut [BltPixel] {
+        self.mark_all_dirty();
+        &mut self.pixels
+    }
+
+    /// Returns the pixel at (`x`, `y`), or `None` if it is outside of the
+    /// surface.
+    #[must_use]
+    pub fn pixel(&self, x: usize, y: usize) -> Option<BltPixel> {
+        (x < self.width && y < self.height).then(|| self.pixels[y * self.width + x])
+    }
+
+    /// Sets the pixel at (`x`, `y`). Pixels outside of the surface are
+    /// ignored.
+    pub fn set_pixel(&mut self, x: usize, y: usize, color: BltPixel) {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x] = color;
+            self.mark_dirty(Rect::new(x, y, 1, 1));
+        }
+    }
+
+    /// Fills `rect` with `color`, clipped to the surface.
+    pub fn fill_rect(&mut self, rect: Rect, color: BltPixel) {
+        let rect = rect.intersection(&self.bounds());
+        if rect.is_empty() {
+            return;
+        }
+        for y in rect.y..rect.bottom() {
+            let row = y * self.width;
+            self.pixels[row + rect.x..row + rect.right()].fill(color);
+        }
+        self.mark_dirty(rect);
+    }
+
+    /// Fills the whole surface with `color`.
+    pub fn clear(&mut self, color: BltPixel) {
+        self.fill_rect(self.bounds(), color);
+    }
+
+    /// Copies an image of `src_width` pixels per row to the surface, with
+    /// its top-left corner at `dest`. The image is clipped to the surface.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `src` is not a whole number of rows.
+    pub fn blit(&mut self, src: &[BltPixel], src_width: usize, dest: (usize, usize)) {
+        if src_width == 0 {
+            return;
+        }
+        assert_eq!(src.len() % src_width, 0, "incomplete row in source image");
+        let rect = Rect::new(dest.0, dest.1, src_width, src.len() / src_width)
+            .intersection(&self.bounds());
+        if rect.is_empty() {
+            return;
+        }
+        for (src_row, y) in src.chunks_exact(src_width).zip(rect.y..rect.bottom()) {
+            let row = y * self.width;
+            self.pixels[row + rect.x..row + rect.right()].copy_from_slice(&src_row[..rect.width]);
+        }
+        self.mark_dirty(rect);
+    }
+
+    /// Returns the regions changed since the last flush.
+    #[must_use]
+    pub fn dirty_rects(&self) -> &[Rect] {
+        &self.dirty
+    }
+
+    /// Marks `rect` as changed, so that it is copied by the next flush.
+    pub fn mark_dirty(&mut self, rect: Rect) {
+        let mut rect = rect.intersection(&self.bounds());
+        if rect.is_empty() {
+            return;
+        }
+        // Merge with the regions it overlaps or extends exactly.
+        while let Some(i) = self
+            .dirty
+            .iter()
+            .position(|other| other.intersects(&rect) || other.union_is_exact(&rect))
+        {
+            rect = rect.union(&self.dirty.swap_remove(i));
+        }
+        if self.dirty.len() < MAX_DIRTY {
+            self.dirty.push(rect);
+            return;
+        }
+        // Merge with the region whose bounding box grows the least.
+        let growth = |other: &Rect| other.union(&rect).area() - other.area();
+        if let Some(other) = self.dirty.iter_mut().min_by_key(|other| growth(other)) {
+            *other = other.union(&rect);
+        }
+    }
+
+    /// Marks the whole surface as changed.
+    pub fn mark_all_dirty(&mut self) {
+        self.dirty.clear();
+        if !self.bounds().is_empty() {
+            self.dirty.push(self.bounds());
+        }
+    }
+
+    /// Returns an error if the surface doesn't match the current mode of
+    /// `gop`.
+    fn check_mode(&self, gop: &GraphicsOutput) -> Result {
+        if gop.current_mode_info().resolution() == (self.width, self.height) {
+            Ok(())
+        } else {
+            Err(Status::BAD_BUFFER_SIZE.into())
+        }
+    }
+
+    /// Copies the dirty regions to the screen with [`GraphicsOutput::blt`].
+    ///
+    /// This works in all modes, including [`PixelFormat::BltOnly`].
+    ///
+    /// [`PixelFormat::BltOnly`]: super::PixelFormat::BltOnly
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::BAD_BUFFER_SIZE`]: the resolution of the current mode of
+    ///   `gop` doesn't match the surface.
+    /// * [`Status::DEVICE_ERROR`]: the blit failed. The regions that were
+    ///   not copied are still dirty.
+    pub fn flush(&mut self, gop: &mut GraphicsOutput) -> Result {
+        self.check_mode(gop)?;
+        while let Some(rect) = self.dirty.last().copied() {
+            gop.blt(BltOp::BufferToVideo {
+                buffer: &self.pixels,
+                src: BltRegion::SubRectangle {
+                    coords: (rect.x, rect.y),
+                    px_stride: self.width,
+                },
+                dest: (rect.x, rect.y),
+                dims: (rect.width, rect.height),
+            })?;
+            self.dirty.pop();
+        }
+        Ok(())
+    }
+
+    /// Copies the dirty regions to the screen by writing directly to the
+    /// frame buffer, converting the pixels to its format.
+    ///
+    /// This avoids the overhead of [`GraphicsOutput::blt`] on firmware
+    /// where it is slow.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::BAD_BUFFER_SIZE`]: the resolution of the current mode of
+    ///   `gop` doesn't match the surface.
+    /// * [`Status::UNSUPPORTED`]: the current mode is
+    ///   [`PixelFormat::BltOnly`]. Use [`flush`] instead.
+    ///
+    /// [`PixelFormat::BltOnly`]: super::PixelFormat::BltOnly
+    ///
+    /// [`flush`]: Self::flush
+    pub fn flush_to_frame_buffer(&mut self, gop: &mut GraphicsOutput) -> Result {
+        self.check_mode(gop)?;
+        let mut canvas = FrameBufferCanvas::new(gop)?;
+        for rect in self.dirty.drain(..) {
+            for y in rect.y..rect.bottom() {
+                let row = &self.pixels[y * self.width..][rect.x..rect.right()];
+                canvas.draw_span(rect.x, y, row);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Canvas for FrameBufferSurface {
+    fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    fn fill_span(&mut self, x: usize, y: usize, len: usize, color: BltPixel) {
+        Self::fill_rect(self, Rect::new(x, y, len, 1), color);
+    }
+
+    fn draw_span(&mut self, x: usize, y: usize, pixels: &[BltPixel]) {
+        self.blit(pixels, pixels.len(), (x, y));
+    }
+
+    fn set_pixel(&mut self, x: usize, y: usize, color: BltPixel) {
+        Self::set_pixel(self, x, y, color);
+    }
+
+    fn fill_rect(&mut self, rect: Rect, color: BltPixel) {
+        Self::fill_rect(self, rect, color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: BltPixel = BltPixel::new(0xff, 0, 0);
+
+    #[test]
+    fn test_drawing() {
+        let mut surface = FrameBufferSurface::with_size(8, 4);
+        assert!(surface.dirty_rects().is_empty());
+
+        surface.fill_rect(Rect::new(6, 2, 10, 10), RED);
+        assert_eq!(surface.dirty_rects(), [Rect::new(6, 2, 2, 2)]);
+        assert_eq!(surface.pixel(7, 3), Some(RED));
+        assert_eq!(surface.pixel(5, 3), Some(BltPixel::new(0, 0, 0)));
+        assert_eq!(surface.pixel(8, 3), None);
+
+        // Adjacent to the existing region, forming a rectangle.
+        surface.set_pixel(5, 2, RED);
+        surface.set_pixel(5, 3, RED);
+        assert_eq!(surface.dirty_rects(), [Rect::new(5, 2, 3, 2)]);
+
+        let image = [RED; 6];
+        surface.blit(&image, 3, (0, 0));
+        assert_eq!(
+            surface.dirty_rects(),
+            [Rect::new(5, 2, 3, 2), Rect::new(0, 0, 3, 2)]
+        );
+        assert_eq!(surface.pixel(2, 1), Some(RED));
+        assert_eq!(surface.pixel(3, 1), Some(BltPixel::new(0, 0, 0)));
+
+        // Clipped to the surface.
+        surface.blit(&image, 2, (7, 0));
+        assert_eq!(surface.pixel(7, 0), Some(RED));
+
+        surface.mark_all_dirty();
+        assert_eq!(surface.dirty_rects(), [surface.bounds()]);
+    }
+
+    #[test]
+    fn test_dirty_limit() {
+        let mut surface = FrameBufferSurface::with_size(100, 100);
+        for i in 0..MAX_DIRTY + 4 {
+            surface.set_pixel(i * 5, i * 5, RED);
+        }
+        assert_eq!(surface.dirty_rects().len(), MAX_DIRTY);
+        for i in 0..MAX_DIRTY + 4 {
+            let pixel = Rect::new(i * 5, i * 5, 1, 1);
+            assert!(surface.dirty_rects().iter().any(|r| r.intersects(&pixel)));
+        }
+    }
+}
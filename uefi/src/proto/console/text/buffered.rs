@@ -0,0 +1,366 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Buffered text output.
+//!
+//! Every call to [`Output`] goes through the firmware, and on real hardware
+//! the per-call overhead often dominates the time it takes to print text.
+//! [`BufferedOutput`] coalesces many small writes into few calls and skips
+//! redundant color changes. For full-screen text user interfaces,
+//! [`ScreenBuffer`] additionally keeps a copy of the screen contents and
+//! only redraws the lines that changed.
+
+use super::{Color, Output};
+use crate::{CStr16, Result};
+use core::fmt;
+#[cfg(feature = "alloc")]
+use {crate::Status, alloc::vec, alloc::vec::Vec};
+
+/// Number of characters buffered before they are written.
+const BUFFER_LEN: usize = 256;
+
+/// Computes the attribute value for a color combination.
+const fn attribute(foreground: Color, background: Color) -> u8 {
+    ((background as u8 & 0x07) << 4) | (foreground as u8 & 0x0f)
+}
+
+/// Wrapper around [`Output`] that buffers written text and only writes it to
+/// the device when the buffer is full, when the colors or cursor position
+/// change, or when [`flush`] is called.
+///
+/// The buffer is flushed when the `BufferedOutput` is dropped; use
+/// [`flush`] to handle errors.
+///
+/// # Example
+///
+/// ```no_run
+/// use core::fmt::Write;
+/// use uefi::proto::console::text::{BufferedOutput, Color, Output};
+///
+/// # fn example(output: &mut Output) -> uefi::Result {
+/// let mut out = BufferedOutput::new(output);
+/// for i in 0..10 {
+///     out.set_color(Color::Yellow, Color::Black)?;
+///     write!(out, "{i} ").unwrap();
+/// }
+/// out.flush()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`flush`]: Self::flush
+#[derive(Debug)]
+pub struct BufferedOutput<'out> {
+    output: &'out mut Output,
+    /// Buffered characters, with room for the null terminator.
+    buffer: [u16; BUFFER_LEN + 1],
+    len: usize,
+    /// Attribute last set through this wrapper.
+    attribute: Option<u8>,
+}
+
+impl<'out> BufferedOutput<'out> {
+    /// Creates a buffered wrapper around `output`.
+    #[must_use]
+    pub const fn new(output: &'out mut Output) -> Self {
+        Self {
+            output,
+            buffer: [0; BUFFER_LEN + 1],
+            len: 0,
+            attribute: None,
+        }
+    }
+
+    /// Appends a character to the buffer, flushing it if it is full.
+    pub(super) fn push(&mut self, c: u16) -> Result {
+        self.buffer[self.len] = c;
+        self.len += 1;
+        if self.len == BUFFER_LEN {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Buffers `string` for output.
+    pub fn write_cstr16(&mut self, string: &CStr16) -> Result {
+        for c in string.iter() {
+            self.push(u16::from(*c))?;
+        }
+        Ok(())
+    }
+
+    /// Writes all buffered text to the device.
+    pub fn flush(&mut self) -> Result {
+        if self.len == 0 {
+            return Ok(());
+        }
+        self.buffer[self.len] = 0;
+        let len = self.len;
+        self.len = 0;
+        // OK to unwrap: the buffer only contains characters of valid
+        // strings, and is null-terminated.
+        let string = CStr16::from_u16_with_nul(&self.buffer[..=len]).unwrap();
+        self.output.output_string(string)
+    }
+
+    /// Sets the colors of subsequent text. Nothing is done if the colors
+    /// are unchanged since the last call.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `background` is not one of the first 8 colors; see
+    /// [`Output::set_color`].
+    pub fn set_color(&mut self, foreground: Color, background: Color) -> Result {
+        let attribute = attribute(foreground, background);
+        if self.attribute == Some(attribute) {
+            return Ok(());
+        }
+        self.flush()?;
+        self.output.set_color(foreground, background)?;
+        self.attribute = Some(attribute);
+        Ok(())
+    }
+
+    /// Moves the cursor, after writing the buffered text.
+    pub fn set_cursor_position(&mut self, column: usize, row: usize) -> Result {
+        self.flush()?;
+        self.output.set_cursor_position(column, row)
+    }
+
+    /// Returns the wrapped output, after writing the buffered text.
+    pub(super) fn output(&mut self) -> Result<&mut Output> {
+        self.flush()?;
+        Ok(self.output)
+    }
+}
+
+impl fmt::Write for BufferedOutput<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        // Convert Rust line feeds to UEFI line feeds, as done by `Output`.
+        let mut push = |c| self.push(c).map_err(|_| ucs2::Error::BufferOverflow);
+        ucs2::encode_with(s, |c| {
+            if c == u16::from(b'\n') {
+                push(u16::from(b'\r'))?;
+            }
+            push(c)
+        })
+        .map_err(|_| fmt::Error)
+    }
+}
+
+impl Drop for BufferedOutput<'_> {
+    fn drop(&mut self) {
+        // Errors are ignored since they can't be propagated from `drop`.
+        let _ = self.flush();
+    }
+}
+
+/// A character on the screen, together with its attribute.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Cell {
+    c: u16,
+    attribute: u8,
+}
+
+/// An in-memory copy of the screen that only redraws changed lines.
+///
+/// Text is drawn into the buffer with [`put_str`] and similar methods; no
+/// output happens until [`present`] is called. `present` compares each line
+/// with what was shown last, and only writes the changed part of changed
+/// lines, using as few calls to the device as possible.
+///
+/// The bottom right cell is never written, since writing it would scroll
+/// the screen on most consoles.
+///
+/// [`put_str`]: Self::put_str
+/// [`present`]: Self::present
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug)]
+pub struct ScreenBuffer {
+    columns: usize,
+    rows: usize,
+    cells: Vec<Cell>,
+    /// Contents of the screen after the last `present`, `None` if unknown.
+    shown: Vec<Option<Cell>>,
+}
+
+#[cfg(feature = "alloc")]
+impl ScreenBuffer {
+    /// Creates a buffer of `columns` x `rows` characters, filled with spaces
+    /// in light gray on black.
+    #[must_use]
+    pub fn new(columns: usize, rows: usize) -> Self {
+        let blank = Cell {
+            c: u16::from(b' '),
+            attribute: attribute(Color::LightGray, Color::Black),
+        };
+        Self {
+            columns,
+            rows,
+            cells: vec![blank; columns * rows],
+            shown: vec![None; columns * rows],
+        }
+    }
+
+    /// Creates a buffer matching the current mode of `output`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::UNSUPPORTED`]: the current mode is unknown.
+    pub fn for_output(output: &Output) -> Result<Self> {
+        let mode = output.current_mode()?.ok_or(Status::UNSUPPORTED)?;
+        Ok(Self::new(mode.columns(), mode.rows()))
+    }
+
+    /// Returns the size of the buffer as `(columns, rows)`.
+    #[must_use]
+    pub const fn size(&self) -> (usize, usize) {
+        (self.columns, self.rows)
+    }
+
+    /// Fills the whole buffer with spaces in the given colors.
+    pub fn clear(&mut self, foreground: Color, background: Color) {
+        self.cells.fill(Cell {
+            c: u16::from(b' '),
+            attribute: attribute(foreground, background),
+        });
+    }
+
+    /// Draws `s` starting at (`column`, `row`). Text that doesn't fit on the
+    /// line is cut off; characters outside the Basic Multilingual Plane and
+    /// control characters are replaced with `?`.
+    pub fn put_str(
+        &mut self,
+        column: usize,
+        row: usize,
+        s: &str,
+        foreground: Color,
+        background: Color,
+    ) {
+        if row >= self.rows {
+            return;
+        }
+        let attribute = attribute(foreground, background);
+        let line = &mut self.cells[row * self.columns..(row + 1) * self.columns];
+        for (cell, c) in line.iter_mut().skip(column).zip(s.chars()) {
+            let c = u16::try_from(u32::from(c))
+                .ok()
+                .filter(|c| *c >= 0x20 && !(0xd800..0xe000).contains(c))
+                .unwrap_or(u16::from(b'?'));
+            *cell = Cell { c, attribute };
+        }
+    }
+
+    /// Forces the whole screen to be redrawn by the next [`present`], e.g.
+    /// after something else has written to the screen.
+    ///
+    /// [`present`]: Self::present
+    pub fn invalidate(&mut self) {
+        self.shown.fill(None);
+    }
+
+    /// Returns the range of columns in `row` that differ from what is shown.
+    fn dirty_range(&self, row: usize) -> Option<(usize, usize)> {
+        let start = row * self.columns;
+        let mut end = start + self.columns;
+        if row + 1 == self.rows {
+            // Never write the bottom right cell.
+            end -= 1;
+        }
+        let differs = |i: &usize| self.shown[*i] != Some(self.cells[*i]);
+        let first = (start..end).find(differs)?;
+        let last = (start..end).rev().find(differs)?;
+        Some((first - start, last - start))
+    }
+
+    /// Writes all changed lines to `output`.
+    pub fn present(&mut self, output: &mut Output) -> Result {
+        let mut out = BufferedOutput::new(output);
+        for row in 0..self.rows {
+            let Some((first, last)) = self.dirty_range(row) else {
+                continue;
+            };
+            out.set_cursor_position(first, row)?;
+            for i in row * self.columns + first..=row * self.columns + last {
+                let Cell { c, attribute } = self.cells[i];
+                if out.attribute != Some(attribute) {
+                    out.flush()?;
+                    out.output
+                        .set_color(color(attribute & 0x0f), color((attribute >> 4) & 0x07))?;
+                    out.attribute = Some(attribute);
+                }
+                out.push(c)?;
+                self.shown[i] = Some(self.cells[i]);
+            }
+        }
+        out.flush()
+    }
+}
+
+/// Converts a color index (0-15) to a [`Color`].
+#[cfg(feature = "alloc")]
+const fn color(index: u8) -> Color {
+    match index {
+        0 => Color::Black,
+        1 => Color::Blue,
+        2 => Color::Green,
+        3 => Color::Cyan,
+        4 => Color::Red,
+        5 => Color::Magenta,
+        6 => Color::Brown,
+        7 => Color::LightGray,
+        8 => Color::DarkGray,
+        9 => Color::LightBlue,
+        10 => Color::LightGreen,
+        11 => Color::LightCyan,
+        12 => Color::LightRed,
+        13 => Color::LightMagenta,
+        14 => Color::Yellow,
+        _ => Color::White,
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dirty_range() {
+        let mut screen = ScreenBuffer::new(10, 2);
+        assert_eq!(screen.dirty_range(0), Some((0, 9)));
+        // The bottom right cell is excluded.
+        assert_eq!(screen.dirty_range(1), Some((0, 8)));
+
+        // Mark everything as shown.
+        for i in 0..screen.cells.len() {
+            screen.shown[i] = Some(screen.cells[i]);
+        }
+        assert_eq!(screen.dirty_range(0), None);
+
+        screen.put_str(2, 0, "ab", Color::White, Color::Blue);
+        screen.put_str(6, 0, " ", Color::LightGray, Color::Black);
+        assert_eq!(screen.dirty_range(0), Some((2, 3)));
+        screen.put_str(6, 0, "c", Color::White, Color::Black);
+        assert_eq!(screen.dirty_range(0), Some((2, 6)));
+        assert_eq!(screen.dirty_range(1), None);
+
+        screen.put_str(9, 1, "x", Color::White, Color::Black);
+        assert_eq!(screen.dirty_range(1), None);
+
+        screen.invalidate();
+        assert_eq!(screen.dirty_range(1), Some((0, 8)));
+    }
+
+    #[test]
+    fn test_put_str_clipping() {
+        let mut screen = ScreenBuffer::new(4, 1);
+        screen.put_str(2, 0, "xyz", Color::White, Color::Black);
+        screen.put_str(0, 5, "ignored", Color::White, Color::Black);
+        screen.put_str(0, 0, "\t", Color::White, Color::Black);
+        let chars: Vec<u16> = screen.cells.iter().map(|cell| cell.c).collect();
+        assert_eq!(
+            chars,
+            [u16::from(b'?'), 0x20, u16::from(b'x'), u16::from(b'y')]
+        );
+    }
+}
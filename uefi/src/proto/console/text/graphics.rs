@@ -0,0 +1,567 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Text console rendered with the Graphics Output Protocol.
+//!
+//! [`GraphicsConsole`] implements the Simple Text Output protocol on top of
+//! [`GraphicsOutput`], drawing characters with a [`BitmapFont`]. This allows
+//! an application that takes over the display to keep using text output,
+//! e.g. through the logger or [`println!`], by making the console the system
+//! table's stdout with [`GraphicsConsole::set_as_stdout`].
+//!
+//! [`println!`]: crate::println
+
+use super::Output;
+use crate::boot::{self, ScopedProtocol};
+use crate::proto::console::gop::{BitmapFont, BltOp, BltPixel, BltRegion, GraphicsOutput};
+use crate::{Handle, Result, Status, system};
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::marker::PhantomPinned;
+use core::pin::Pin;
+use core::ptr;
+use uefi_raw::Boolean;
+use uefi_raw::protocol::console::{SimpleTextOutputMode, SimpleTextOutputProtocol};
+
+/// Default palette for the 16 console colors, matching the colors used by
+/// the EDK2 graphics console.
+const DEFAULT_PALETTE: [BltPixel; 16] = [
+    BltPixel::new(0x00, 0x00, 0x00),
+    BltPixel::new(0x00, 0x00, 0x98),
+    BltPixel::new(0x00, 0x98, 0x00),
+    BltPixel::new(0x00, 0x98, 0x98),
+    BltPixel::new(0x98, 0x00, 0x00),
+    BltPixel::new(0x98, 0x00, 0x98),
+    BltPixel::new(0x98, 0x98, 0x00),
+    BltPixel::new(0x98, 0x98, 0x98),
+    BltPixel::new(0x30, 0x30, 0x30),
+    BltPixel::new(0x00, 0x00, 0xff),
+    BltPixel::new(0x00, 0xff, 0x00),
+    BltPixel::new(0x00, 0xff, 0xff),
+    BltPixel::new(0xff, 0x00, 0x00),
+    BltPixel::new(0xff, 0x00, 0xff),
+    BltPixel::new(0xff, 0xff, 0x00),
+    BltPixel::new(0xff, 0xff, 0xff),
+];
+
+/// Light gray on black.
+const DEFAULT_ATTRIBUTE: u8 = 0x07;
+
+/// Number of text modes. Mode 0 is 80x25 and mode 1 is 80x50, as required
+/// by the UEFI specification; mode 2 uses the whole screen. Modes that
+/// don't fit on the screen are not available.
+const MODE_COUNT: usize = 3;
+
+/// Number of pixel rows used to draw the cursor.
+const CURSOR_HEIGHT: usize = 2;
+
+/// A character on the screen, together with its attribute.
+#[derive(Clone, Copy, Debug)]
+struct Cell {
+    c: u16,
+    attribute: u8,
+}
+
+impl Cell {
+    const fn blank(attribute: u8) -> Self {
+        Self {
+            c: b' ' as u16,
+            attribute,
+        }
+    }
+}
+
+/// A text console implementing the Simple Text Output protocol by drawing
+/// into a [`GraphicsOutput`] device.
+///
+/// The console is pinned since the firmware refers to it by address once
+/// it's installed with [`install`] or [`set_as_stdout`]. When the console is
+/// dropped, the previous stdout is restored and the protocol is uninstalled.
+///
+/// # Example
+///
+/// ```no_run
+/// use uefi::boot;
+/// use uefi::proto::console::gop::{BitmapFont, GraphicsOutput};
+/// use uefi::proto::console::text::GraphicsConsole;
+///
+/// # fn example() -> uefi::Result {
+/// let handle = boot::get_handle_for_protocol::<GraphicsOutput>()?;
+/// let gop = boot::open_protocol_exclusive::<GraphicsOutput>(handle)?;
+/// let mut console = GraphicsConsole::new(gop, BitmapFont::DEFAULT)?;
+/// console.as_mut().set_as_stdout()?;
+/// uefi::println!("Hello from the graphics console");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`install`]: Self::install
+/// [`set_as_stdout`]: Self::set_as_stdout
+#[derive(Debug)]
+#[repr(C)]
+pub struct GraphicsConsole {
+    /// Must be the first field, so that a pointer to the protocol is also a
+    /// pointer to the console.
+    protocol: SimpleTextOutputProtocol,
+    mode: SimpleTextOutputMode,
+    gop: ScopedProtocol<GraphicsOutput>,
+    font: BitmapFont<'static>,
+    palette: [BltPixel; 16],
+    /// Size in characters of each mode, `None` if the mode is unavailable.
+    modes: [Option<(usize, usize)>; MODE_COUNT],
+    columns: usize,
+    rows: usize,
+    /// Pixel coordinates of the top left corner of the text area.
+    origin: (usize, usize),
+    cells: Vec<Cell>,
+    /// Scratch buffer for drawing a single character.
+    glyph_buffer: Vec<BltPixel>,
+    handle: Option<Handle>,
+    previous_stdout: Option<(uefi_raw::Handle, *mut SimpleTextOutputProtocol)>,
+    _pinned: PhantomPinned,
+}
+
+impl GraphicsConsole {
+    /// Creates a console drawing to `gop` with `font`.
+    ///
+    /// The largest available text mode is selected and the screen is
+    /// cleared.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::UNSUPPORTED`]: the screen is too small for a single
+    ///   character.
+    /// * [`Status::DEVICE_ERROR`]: the screen could not be cleared.
+    pub fn new(
+        gop: ScopedProtocol<GraphicsOutput>,
+        font: BitmapFont<'static>,
+    ) -> Result<Pin<Box<Self>>> {
+        let (width, height) = gop.current_mode_info().resolution();
+        let max = (width / font.width(), height / font.height());
+        if max.0 == 0 || max.1 == 0 {
+            return Err(Status::UNSUPPORTED.into());
+        }
+        let fits = |(columns, rows): (usize, usize)| columns <= max.0 && rows <= max.1;
+        let modes = [(80, 25), (80, 50), max].map(|mode| fits(mode).then_some(mode));
+        let modes = match modes {
+            // Fall back to the whole screen if it is smaller than 80x25.
+            [None, _, _] => [Some(max), None, None],
+            [Some(m0), m1, _] if m0 == max || m1 == Some(max) => [Some(m0), m1, None],
+            modes => modes,
+        };
+        let initial_mode = modes.iter().rposition(Option::is_some).unwrap();
+
+        let mut console = Box::pin(Self {
+            protocol: SimpleTextOutputProtocol {
+                reset: Self::reset,
+                output_string: Self::output_string,
+                test_string: Self::test_string,
+                query_mode: Self::query_mode,
+                set_mode: Self::set_mode,
+                set_attribute: Self::set_attribute,
+                clear_screen: Self::clear_screen,
+                set_cursor_position: Self::set_cursor_position,
+                enable_cursor: Self::enable_cursor,
+                mode: ptr::null_mut(),
+            },
+            mode: SimpleTextOutputMode {
+                max_mode: MODE_COUNT as i32,
+                mode: 0,
+                attribute: i32::from(DEFAULT_ATTRIBUTE),
+                cursor_column: 0,
+                cursor_row: 0,
+                cursor_visible: Boolean::TRUE,
+            },
+            gop,
+            font,
+            palette: DEFAULT_PALETTE,
+            modes,
+            columns: 0,
+            rows: 0,
+            origin: (0, 0),
+            cells: Vec::new(),
+            glyph_buffer: vec![BltPixel::new(0, 0, 0); font.width() * font.height()],
+            handle: None,
+            previous_stdout: None,
+            _pinned: PhantomPinned,
+        });
+
+        // SAFETY: the console is not moved out of the pin.
+        let this = unsafe { console.as_mut().get_unchecked_mut() };
+        this.protocol.mode = &mut this.mode;
+        this.change_mode(initial_mode)?;
+        Ok(console)
+    }
+
+    /// Returns the console as an [`Output`] protocol.
+    #[must_use]
+    pub fn output(self: Pin<&mut Self>) -> &mut Output {
+        // SAFETY: the console is not moved out of the pin. `Output` is a
+        // `repr(transparent)` wrapper around the raw protocol.
+        let this = unsafe { self.get_unchecked_mut() };
+        unsafe { &mut *ptr::from_mut(&mut this.protocol).cast::<Output>() }
+    }
+
+    /// Changes the 16 colors used for the console attributes. The screen is
+    /// not redrawn.
+    pub const fn set_palette(self: Pin<&mut Self>, palette: [BltPixel; 16]) {
+        // SAFETY: the console is not moved out of the pin.
+        unsafe { self.get_unchecked_mut() }.palette = palette;
+    }
+
+    /// Installs the Simple Text Output protocol of the console on a new
+    /// handle and returns the handle. If the protocol is already installed,
+    /// the existing handle is returned.
+    ///
+    /// The protocol is uninstalled when the console is dropped.
+    pub fn install(self: Pin<&mut Self>) -> Result<Handle> {
+        // SAFETY: the console is not moved out of the pin.
+        let this = unsafe { self.get_unchecked_mut() };
+        if let Some(handle) = this.handle {
+            return Ok(handle);
+        }
+        // SAFETY: the interface stays valid until it is uninstalled in `drop`.
+        let handle = unsafe {
+            boot::install_protocol_interface(
+                None,
+                &SimpleTextOutputProtocol::GUID,
+                ptr::from_ref(&this.protocol).cast(),
+            )
+        }?;
+        this.handle = Some(handle);
+        Ok(handle)
+    }
+
+    /// Makes the console the system table's stdout (`ConOut`), installing
+    /// the protocol first if necessary. All text output through the system
+    /// table, including [`println!`] and the logger, is drawn by the console
+    /// afterwards.
+    ///
+    /// The previous stdout is restored when the console is dropped.
+    ///
+    /// [`println!`]: crate::println
+    pub fn set_as_stdout(mut self: Pin<&mut Self>) -> Result {
+        let handle = self.as_mut().install()?;
+        // SAFETY: the console is not moved out of the pin.
+        let this = unsafe { self.get_unchecked_mut() };
+        if this.previous_stdout.is_none() {
+            // SAFETY: the protocol stays valid until stdout is restored in
+            // `drop`.
+            let previous = unsafe { system::swap_stdout(handle.as_ptr(), &mut this.protocol) };
+            this.previous_stdout = Some(previous);
+        }
+        Ok(())
+    }
+
+    /// Gets the console from a pointer to its protocol.
+    ///
+    /// # Safety
+    ///
+    /// `this` must be the `protocol` field of a console.
+    const unsafe fn from_protocol<'a>(this: *mut SimpleTextOutputProtocol) -> &'a mut Self {
+        unsafe { &mut *this.cast::<Self>() }
+    }
+
+    const fn attribute(&self) -> u8 {
+        self.mode.attribute as u8
+    }
+
+    const fn cursor(&self) -> (usize, usize) {
+        (
+            self.mode.cursor_column as usize,
+            self.mode.cursor_row as usize,
+        )
+    }
+
+    const fn cell_position(&self, column: usize, row: usize) -> (usize, usize) {
+        (
+            self.origin.0 + column * self.font.width(),
+            self.origin.1 + row * self.font.height(),
+        )
+    }
+
+    fn blt(&mut self, op: BltOp) -> Result {
+        self.gop.blt(op).map_err(|_| Status::DEVICE_ERROR.into())
+    }
+
+    /// Draws the character at (`column`, `row`), with the cursor if
+    /// `with_cursor` is set.
+    fn draw_cell(&mut self, column: usize, row: usize, with_cursor: bool) -> Result {
+        let cell = self.cells[row * self.columns + column];
+        let foreground = self.palette[usize::from(cell.attribute & 0x0f)];
+        let background = self.palette[usize::from((cell.attribute >> 4) & 0x07)];
+        let (width, height) = (self.font.width(), self.font.height());
+        let glyph = char::from_u32(u32::from(cell.c)).and_then(|c| self.font.glyph(c));
+
+        let mut buffer = core::mem::take(&mut self.glyph_buffer);
+        for y in 0..height {
+            let is_cursor = with_cursor && y >= height.saturating_sub(CURSOR_HEIGHT);
+            for x in 0..width {
+                let set = is_cursor || glyph.is_some_and(|g| g.is_set(x, y));
+                buffer[y * width + x] = if set { foreground } else { background };
+            }
+        }
+        let result = self.blt(BltOp::BufferToVideo {
+            buffer: &buffer,
+            src: BltRegion::Full,
+            dest: self.cell_position(column, row),
+            dims: (width, height),
+        });
+        self.glyph_buffer = buffer;
+        result
+    }
+
+    /// Draws or erases the cursor at the current cursor position.
+    fn draw_cursor(&mut self, visible: bool) -> Result {
+        let (column, row) = self.cursor();
+        self.draw_cell(column, row, visible && self.mode.cursor_visible.into())
+    }
+
+    const fn move_cursor(&mut self, column: usize, row: usize) {
+        self.mode.cursor_column = column as i32;
+        self.mode.cursor_row = row as i32;
+    }
+
+    /// Scrolls the text area up by one line.
+    fn scroll(&mut self) -> Result {
+        let (width, height) = (self.font.width(), self.font.height());
+        let background = self.palette[usize::from((self.attribute() >> 4) & 0x07)];
+        if self.rows > 1 {
+            self.blt(BltOp::VideoToVideo {
+                src: self.cell_position(0, 1),
+                dest: self.origin,
+                dims: (self.columns * width, (self.rows - 1) * height),
+            })?;
+        }
+        self.blt(BltOp::VideoFill {
+            color: background,
+            dest: self.cell_position(0, self.rows - 1),
+            dims: (self.columns * width, height),
+        })?;
+
+        self.cells.copy_within(self.columns.., 0);
+        let blank = Cell::blank(self.attribute());
+        let last_row = self.cells.len() - self.columns;
+        self.cells[last_row..].fill(blank);
+        Ok(())
+    }
+
+    fn new_line(&mut self) -> Result {
+        let (column, row) = self.cursor();
+        if row + 1 < self.rows {
+            self.move_cursor(column, row + 1);
+        } else {
+            self.scroll()?;
+        }
+        Ok(())
+    }
+
+    /// Writes a single character at the cursor position, without drawing
+    /// the cursor.
+    fn put_char(&mut self, c: u16) -> Result {
+        let (column, row) = self.cursor();
+        match c {
+            0x0d => self.move_cursor(0, row),
+            0x0a => self.new_line()?,
+            0x08 => self.move_cursor(column.saturating_sub(1), row),
+            c => {
+                self.cells[row * self.columns + column] = Cell {
+                    c,
+                    attribute: self.attribute(),
+                };
+                self.draw_cell(column, row, false)?;
+                if column + 1 < self.columns {
+                    self.move_cursor(column + 1, row);
+                } else {
+                    self.move_cursor(0, row);
+                    self.new_line()?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn is_supported(&self, c: u16) -> bool {
+        matches!(c, 0x0d | 0x0a | 0x08)
+            || char::from_u32(u32::from(c)).is_some_and(|c| self.font.glyph(c).is_some())
+    }
+
+    fn clear(&mut self) -> Result {
+        let background = self.palette[usize::from((self.attribute() >> 4) & 0x07)];
+        let dims = self.gop.current_mode_info().resolution();
+        self.blt(BltOp::VideoFill {
+            color: background,
+            dest: (0, 0),
+            dims,
+        })?;
+        let blank = Cell::blank(self.attribute());
+        self.cells.fill(blank);
+        self.move_cursor(0, 0);
+        self.draw_cursor(true)
+    }
+
+    fn change_mode(&mut self, mode: usize) -> Result {
+        let (columns, rows) = self
+            .modes
+            .get(mode)
+            .copied()
+            .flatten()
+            .ok_or(Status::UNSUPPORTED)?;
+        let (width, height) = self.gop.current_mode_info().resolution();
+        self.columns = columns;
+        self.rows = rows;
+        self.origin = (
+            (width - columns * self.font.width()) / 2,
+            (height - rows * self.font.height()) / 2,
+        );
+        self.cells = vec![Cell::blank(self.attribute()); columns * rows];
+        self.mode.mode = mode as i32;
+        self.clear()
+    }
+
+    unsafe extern "efiapi" fn reset(
+        this: *mut SimpleTextOutputProtocol,
+        _extended: Boolean,
+    ) -> Status {
+        let this = unsafe { Self::from_protocol(this) };
+        this.mode.attribute = i32::from(DEFAULT_ATTRIBUTE);
+        status(this.clear())
+    }
+
+    unsafe extern "efiapi" fn output_string(
+        this: *mut SimpleTextOutputProtocol,
+        string: *const uefi_raw::Char16,
+    ) -> Status {
+        let this = unsafe { Self::from_protocol(this) };
+        let mut unknown_glyph = false;
+        let result = this.draw_cursor(false).and_then(|()| {
+            let mut i = 0;
+            loop {
+                let c = unsafe { string.add(i).read() };
+                if c == 0 {
+                    break;
+                }
+                unknown_glyph |= !this.is_supported(c);
+                this.put_char(c)?;
+                i += 1;
+            }
+            this.draw_cursor(true)
+        });
+        match result {
+            Ok(()) if unknown_glyph => Status::WARN_UNKNOWN_GLYPH,
+            result => status(result),
+        }
+    }
+
+    unsafe extern "efiapi" fn test_string(
+        this: *mut SimpleTextOutputProtocol,
+        string: *const uefi_raw::Char16,
+    ) -> Status {
+        let this = unsafe { Self::from_protocol(this) };
+        let mut i = 0;
+        loop {
+            let c = unsafe { string.add(i).read() };
+            if c == 0 {
+                return Status::SUCCESS;
+            }
+            if !this.is_supported(c) {
+                return Status::UNSUPPORTED;
+            }
+            i += 1;
+        }
+    }
+
+    unsafe extern "efiapi" fn query_mode(
+        this: *mut SimpleTextOutputProtocol,
+        mode: usize,
+        columns: *mut usize,
+        rows: *mut usize,
+    ) -> Status {
+        let this = unsafe { Self::from_protocol(this) };
+        match this.modes.get(mode).copied().flatten() {
+            Some((c, r)) => {
+                unsafe {
+                    columns.write(c);
+                    rows.write(r);
+                }
+                Status::SUCCESS
+            }
+            None => Status::UNSUPPORTED,
+        }
+    }
+
+    unsafe extern "efiapi" fn set_mode(this: *mut SimpleTextOutputProtocol, mode: usize) -> Status {
+        let this = unsafe { Self::from_protocol(this) };
+        status(this.change_mode(mode))
+    }
+
+    unsafe extern "efiapi" fn set_attribute(
+        this: *mut SimpleTextOutputProtocol,
+        attribute: usize,
+    ) -> Status {
+        let this = unsafe { Self::from_protocol(this) };
+        match u8::try_from(attribute) {
+            Ok(attribute) if attribute <= 0x7f => {
+                this.mode.attribute = i32::from(attribute);
+                Status::SUCCESS
+            }
+            _ => Status::UNSUPPORTED,
+        }
+    }
+
+    unsafe extern "efiapi" fn clear_screen(this: *mut SimpleTextOutputProtocol) -> Status {
+        let this = unsafe { Self::from_protocol(this) };
+        status(this.clear())
+    }
+
+    unsafe extern "efiapi" fn set_cursor_position(
+        this: *mut SimpleTextOutputProtocol,
+        column: usize,
+        row: usize,
+    ) -> Status {
+        let this = unsafe { Self::from_protocol(this) };
+        if column >= this.columns || row >= this.rows {
+            return Status::UNSUPPORTED;
+        }
+        let result = this.draw_cursor(false).and_then(|()| {
+            this.move_cursor(column, row);
+            this.draw_cursor(true)
+        });
+        status(result)
+    }
+
+    unsafe extern "efiapi" fn enable_cursor(
+        this: *mut SimpleTextOutputProtocol,
+        visible: Boolean,
+    ) -> Status {
+        let this = unsafe { Self::from_protocol(this) };
+        this.mode.cursor_visible = visible;
+        status(this.draw_cursor(true))
+    }
+}
+
+impl Drop for GraphicsConsole {
+    fn drop(&mut self) {
+        if let Some((handle, stdout)) = self.previous_stdout.take() {
+            // SAFETY: the previous stdout was valid before it was replaced.
+            unsafe { system::swap_stdout(handle, stdout) };
+        }
+        if let Some(handle) = self.handle.take() {
+            // Errors are ignored since they can't be propagated from `drop`.
+            let _ = unsafe {
+                boot::uninstall_protocol_interface(
+                    handle,
+                    &SimpleTextOutputProtocol::GUID,
+                    ptr::from_ref(&self.protocol).cast(),
+                )
+            };
+        }
+    }
+}
+
+const fn status(result: Result) -> Status {
+    match result {
+        Ok(()) => Status::SUCCESS,
+        Err(err) => err.status(),
+    }
+}
@@ -2,6 +2,7 @@
 
 use crate::proto::unsafe_protocol;
 use crate::{Char16, Event, Result, Status, StatusExt};
+use core::iter::FusedIterator;
 use core::mem::MaybeUninit;
 use uefi_raw::protocol::console::{InputKey, SimpleTextInputProtocol};
 
@@ -80,6 +81,31 @@ impl Input {
         }
     }
 
+    /// Returns an iterator over the keys that are currently pending, without
+    /// waiting for more.
+    ///
+    /// This is useful in event loops that handle input alongside other work:
+    ///
+    /// ```no_run
+    /// use uefi::proto::console::text::{Input, Key, KeySequence, ScanCode};
+    ///
+    /// fn poll(input: &mut Input, quit: &mut KeySequence) -> uefi::Result<bool> {
+    ///     for key in input.keys() {
+    ///         match key? {
+    ///             Key::Special(ScanCode::FUNCTION_2) => log::info!("setup requested"),
+    ///             key if quit.feed(key) => return Ok(true),
+    ///             _ => {}
+    ///         }
+    ///     }
+    ///     Ok(false)
+    /// }
+    /// ```
+    ///
+    /// The iterator ends when no key is pending, or after yielding an error.
+    pub const fn keys(&mut self) -> Keys<'_> {
+        Keys { input: Some(self) }
+    }
+
     /// Event to be used with [`boot::wait_for_event`] in order to wait
     /// for a key to be available
     ///
@@ -90,6 +116,34 @@ impl Input {
     }
 }
 
+/// Iterator over the pending keys of an [`Input`], returned by
+/// [`Input::keys`].
+#[derive(Debug)]
+pub struct Keys<'a> {
+    input: Option<&'a mut Input>,
+}
+
+impl Iterator for Keys<'_> {
+    type Item = Result<Key>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let result = self.input.as_mut()?.read_key();
+        match result {
+            Ok(Some(key)) => Some(Ok(key)),
+            Ok(None) => {
+                self.input = None;
+                None
+            }
+            Err(err) => {
+                self.input = None;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+impl FusedIterator for Keys<'_> {}
+
 /// A key read from the console (high-level version)
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum Key {
@@ -100,10 +154,101 @@ pub enum Key {
     Special(ScanCode),
 }
 
+impl Key {
+    /// Returns the key produced by holding Ctrl and pressing the letter `c`,
+    /// e.g. `Key::ctrl('c')` for Ctrl+C.
+    ///
+    /// The Simple Text Input protocol reports these combinations as the
+    /// ASCII control characters 0x01 to 0x1a.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `c` is not an ASCII letter.
+    #[must_use]
+    pub const fn ctrl(c: char) -> Self {
+        assert!(c.is_ascii_alphabetic(), "not an ASCII letter");
+        let code = (c.to_ascii_lowercase() as u8 - b'a' + 1) as u16;
+        // SAFETY: control characters are valid UCS-2.
+        Self::Printable(unsafe { Char16::from_u16_unchecked(code) })
+    }
+
+    /// Returns the key for the function key F`n`, or `None` if there is no
+    /// scan code for it.
+    #[must_use]
+    pub const fn function(n: u8) -> Option<Self> {
+        let code = match n {
+            1..=12 => ScanCode::FUNCTION_1.0 + (n as u16 - 1),
+            13..=24 => ScanCode::FUNCTION_13.0 + (n as u16 - 13),
+            _ => return None,
+        };
+        Some(Self::Special(ScanCode(code)))
+    }
+}
+
+/// Matches a sequence of keys, such as a Ctrl+C or a cheat code, against a
+/// stream of keys fed one at a time.
+///
+/// ```
+/// use uefi::proto::console::text::{Key, KeySequence};
+///
+/// let keys = [Key::ctrl('x'), Key::ctrl('c')];
+/// let mut quit = KeySequence::new(&keys);
+/// assert!(!quit.feed(Key::ctrl('x')));
+/// assert!(quit.feed(Key::ctrl('c')));
+/// ```
+#[derive(Clone, Debug)]
+pub struct KeySequence<'a> {
+    keys: &'a [Key],
+    matched: usize,
+}
+
+impl<'a> KeySequence<'a> {
+    /// Creates a matcher for `keys`.
+    #[must_use]
+    pub const fn new(keys: &'a [Key]) -> Self {
+        Self { keys, matched: 0 }
+    }
+
+    /// Feeds the next key, returning whether it completed the sequence.
+    ///
+    /// After a completed sequence, matching starts over. A key that breaks
+    /// a partial match keeps the longest part of it that can still be
+    /// completed.
+    pub fn feed(&mut self, key: Key) -> bool {
+        // The new match is the longest prefix of the sequence that ends
+        // with the previously matched keys followed by `key`.
+        let seen = &self.keys[..self.matched];
+        self.matched = (1..=(self.matched + 1).min(self.keys.len()))
+            .rev()
+            .find(|&len| {
+                self.keys[len - 1] == key && self.keys[..len - 1] == seen[seen.len() + 1 - len..]
+            })
+            .unwrap_or(0);
+        if !self.keys.is_empty() && self.matched == self.keys.len() {
+            self.matched = 0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns the number of keys matched so far.
+    #[must_use]
+    pub const fn matched(&self) -> usize {
+        self.matched
+    }
+
+    /// Discards a partial match.
+    pub const fn reset(&mut self) {
+        self.matched = 0;
+    }
+}
+
 impl From<InputKey> for Key {
     fn from(k: InputKey) -> Self {
         if k.scan_code == ScanCode::NULL.0 {
-            Self::Printable(Char16::try_from(k.unicode_char).unwrap())
+            // Don't panic on a surrogate reported by the firmware.
+            Self::Printable(Char16::from_u16_lossy(k.unicode_char))
         } else {
             Self::Special(ScanCode(k.scan_code))
         }
@@ -172,3 +317,46 @@ pub enum ScanCode: u16 => #[allow(missing_docs)] {
     RECOVERY        = 0x105,
     EJECT           = 0x106,
 }}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_constructors() {
+        assert_eq!(Key::ctrl('c'), Key::Printable(Char16::try_from(3).unwrap()));
+        assert_eq!(
+            Key::ctrl('Z'),
+            Key::Printable(Char16::try_from(26).unwrap())
+        );
+        assert_eq!(Key::function(2), Some(Key::Special(ScanCode::FUNCTION_2)));
+        assert_eq!(Key::function(12), Some(Key::Special(ScanCode::FUNCTION_12)));
+        assert_eq!(Key::function(13), Some(Key::Special(ScanCode::FUNCTION_13)));
+        assert_eq!(Key::function(24), Some(Key::Special(ScanCode::FUNCTION_24)));
+        assert_eq!(Key::function(0), None);
+        assert_eq!(Key::function(25), None);
+    }
+
+    #[test]
+    fn sequence() {
+        let up = Key::Special(ScanCode::UP);
+        let down = Key::Special(ScanCode::DOWN);
+        let keys = [up, up, down];
+        let mut seq = KeySequence::new(&keys);
+
+        assert!(!seq.feed(up));
+        assert!(!seq.feed(down));
+        assert_eq!(seq.matched(), 0);
+
+        // A broken match keeps the keys that still form a prefix.
+        assert!(!seq.feed(up));
+        assert!(!seq.feed(up));
+        assert!(!seq.feed(up));
+        assert_eq!(seq.matched(), 2);
+        assert!(seq.feed(down));
+        assert_eq!(seq.matched(), 0);
+
+        let mut empty = KeySequence::new(&[]);
+        assert!(!empty.feed(up));
+    }
+}
@@ -0,0 +1,305 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use super::{Key, ScanCode};
+use crate::proto::unsafe_protocol;
+use crate::{Event, Result, Status, StatusExt};
+use core::mem::MaybeUninit;
+use uefi_raw::protocol::console::{InputKey, KeyData, KeyState, SimpleTextInputExProtocol};
+
+#[cfg(feature = "alloc")]
+use {
+    crate::boot::{self, Tpl},
+    crate::sync::SpinLock,
+    alloc::sync::Arc,
+    core::ffi::c_void,
+    core::ptr,
+    uefi_raw::protocol::console::KeyNotifyFunction,
+};
+
+pub use uefi_raw::protocol::console::{KeyShiftState, KeyToggleState};
+
+/// Simple Text Input Ex [`Protocol`]. Like [`Input`], but reports the state
+/// of the shift and toggle keys, which is needed to tell apart key
+/// combinations with Ctrl, Alt, or the logo keys.
+///
+/// ```no_run
+/// use uefi::proto::console::text::{InputEx, Key};
+///
+/// fn is_ctrl_s(input: &mut InputEx) -> uefi::Result<bool> {
+///     let Some(stroke) = input.read_key_stroke_ex()? else {
+///         return Ok(false);
+///     };
+///     let s = Key::Printable(uefi::Char16::try_from('s').unwrap());
+///     Ok(stroke.ctrl() && stroke.key == s)
+/// }
+/// ```
+///
+/// [`Input`]: super::Input
+/// [`Protocol`]: uefi::proto::Protocol
+#[derive(Debug)]
+#[repr(transparent)]
+#[unsafe_protocol(SimpleTextInputExProtocol::GUID)]
+pub struct InputEx(SimpleTextInputExProtocol);
+
+impl InputEx {
+    /// Resets the input device hardware.
+    ///
+    /// The `extended_verification` parameter is used to request that UEFI
+    /// performs an extended check and reset of the input device.
+    ///
+    /// # Errors
+    ///
+    /// - [`Status::DEVICE_ERROR`] if the device is malfunctioning and cannot
+    ///   be reset.
+    pub fn reset(&mut self, extended_verification: bool) -> Result {
+        unsafe { (self.0.reset)(&mut self.0, extended_verification.into()) }.to_result()
+    }
+
+    /// Reads the next keystroke from the input device, if any, with the
+    /// state of the shift and toggle keys.
+    ///
+    /// # Errors
+    ///
+    /// - [`Status::DEVICE_ERROR`] if there was an issue with the input device
+    /// - [`Status::UNSUPPORTED`] if the device doesn't support this
+    ///   keystroke
+    pub fn read_key_stroke_ex(&mut self) -> Result<Option<KeyStroke>> {
+        let mut key_data = MaybeUninit::<KeyData>::uninit();
+        match unsafe { (self.0.read_key_stroke_ex)(&mut self.0, key_data.as_mut_ptr()) } {
+            Status::NOT_READY => Ok(None),
+            other => other.to_result_with_val(|| Some(unsafe { key_data.assume_init() }.into())),
+        }
+    }
+
+    /// Event to be used with [`boot::wait_for_event`] in order to wait
+    /// for a key to be available
+    ///
+    /// [`boot::wait_for_event`]: crate::boot::wait_for_event
+    #[must_use]
+    pub fn wait_for_key_event(&self) -> Option<Event> {
+        unsafe { Event::from_ptr(self.0.wait_for_key_ex) }
+    }
+
+    /// Sets the state of the toggle keys, e.g. to turn on Num Lock.
+    /// [`KeyToggleState::TOGGLE_STATE_VALID`] must be set.
+    ///
+    /// # Errors
+    ///
+    /// - [`Status::DEVICE_ERROR`] if the device is not functioning.
+    /// - [`Status::UNSUPPORTED`] if the device doesn't support setting the
+    ///   state.
+    pub fn set_state(&mut self, state: KeyToggleState) -> Result {
+        unsafe { (self.0.set_state)(&mut self.0, &state) }.to_result()
+    }
+
+    /// Registers `f` to be called whenever `key` is pressed. Only the shift
+    /// and toggle states that are marked valid in `key` are compared.
+    ///
+    /// The callback runs in an event notification function, so it must not
+    /// block. At most 8 callbacks can be registered at the same time. They
+    /// stay registered until passed to [`Self::unregister_key_notify`].
+    ///
+    /// # Errors
+    ///
+    /// - [`Status::OUT_OF_RESOURCES`] if too many callbacks are registered.
+    #[cfg(feature = "alloc")]
+    pub fn register_key_notify(
+        &mut self,
+        key: KeyStroke,
+        f: impl Fn(KeyStroke) + Send + Sync + 'static,
+    ) -> Result<KeyNotifyHandle> {
+        let _tpl = unsafe { boot::raise_tpl(Tpl::NOTIFY) };
+        let slot = NOTIFY_SLOTS
+            .iter()
+            .position(|slot| slot.lock().is_none())
+            .ok_or(Status::OUT_OF_RESOURCES)?;
+        *NOTIFY_SLOTS[slot].lock() = Some(Arc::new(f));
+
+        let key_data = KeyData::from(key);
+        let mut handle = ptr::null_mut();
+        let status = unsafe {
+            (self.0.register_key_notify)(&mut self.0, &key_data, TRAMPOLINES[slot], &mut handle)
+        };
+        if status.is_error() {
+            *NOTIFY_SLOTS[slot].lock() = None;
+        }
+        status.to_result_with_val(|| KeyNotifyHandle { handle, slot })
+    }
+
+    /// Unregisters a callback registered with
+    /// [`Self::register_key_notify`].
+    ///
+    /// # Errors
+    ///
+    /// - [`Status::INVALID_PARAMETER`] if the handle is not registered with
+    ///   this protocol.
+    #[cfg(feature = "alloc")]
+    pub fn unregister_key_notify(&mut self, handle: KeyNotifyHandle) -> Result {
+        unsafe { (self.0.unregister_key_notify)(&mut self.0, handle.handle) }.to_result()?;
+        let _tpl = unsafe { boot::raise_tpl(Tpl::NOTIFY) };
+        *NOTIFY_SLOTS[handle.slot].lock() = None;
+        Ok(())
+    }
+}
+
+/// A key read with [`InputEx`], with the state of the modifier keys.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct KeyStroke {
+    /// The key. If [`KeyToggleState::KEY_STATE_EXPOSED`] is set, this is
+    /// the null character for key strokes of only shift or toggle keys.
+    pub key: Key,
+    /// State of the shift keys.
+    pub shift_state: KeyShiftState,
+    /// State of the toggle keys.
+    pub toggle_state: KeyToggleState,
+}
+
+impl KeyStroke {
+    /// Creates a key stroke for `key` with the given shift keys, e.g. to
+    /// register a callback for Ctrl+Alt+Del. The toggle keys are ignored.
+    #[must_use]
+    pub const fn new(key: Key, shift_state: KeyShiftState) -> Self {
+        Self {
+            key,
+            shift_state: shift_state.union(KeyShiftState::SHIFT_STATE_VALID),
+            toggle_state: KeyToggleState::empty(),
+        }
+    }
+
+    /// Returns whether any of the shift states `flags` is set. Always
+    /// `false` if the shift state is not valid.
+    const fn shift_pressed(&self, flags: KeyShiftState) -> bool {
+        self.shift_state.contains(KeyShiftState::SHIFT_STATE_VALID)
+            && self.shift_state.intersects(flags)
+    }
+
+    /// Returns whether a Shift key is pressed.
+    #[must_use]
+    pub const fn shift(&self) -> bool {
+        self.shift_pressed(
+            KeyShiftState::LEFT_SHIFT_PRESSED.union(KeyShiftState::RIGHT_SHIFT_PRESSED),
+        )
+    }
+
+    /// Returns whether a Ctrl key is pressed.
+    #[must_use]
+    pub const fn ctrl(&self) -> bool {
+        self.shift_pressed(
+            KeyShiftState::LEFT_CONTROL_PRESSED.union(KeyShiftState::RIGHT_CONTROL_PRESSED),
+        )
+    }
+
+    /// Returns whether an Alt key is pressed.
+    #[must_use]
+    pub const fn alt(&self) -> bool {
+        self.shift_pressed(KeyShiftState::LEFT_ALT_PRESSED.union(KeyShiftState::RIGHT_ALT_PRESSED))
+    }
+
+    /// Returns whether a logo key, e.g. the Windows key, is pressed.
+    #[must_use]
+    pub const fn logo(&self) -> bool {
+        self.shift_pressed(
+            KeyShiftState::LEFT_LOGO_PRESSED.union(KeyShiftState::RIGHT_LOGO_PRESSED),
+        )
+    }
+}
+
+impl From<KeyData> for KeyStroke {
+    fn from(data: KeyData) -> Self {
+        Self {
+            key: data.key.into(),
+            shift_state: data.key_state.key_shift_state,
+            toggle_state: data.key_state.key_toggle_state,
+        }
+    }
+}
+
+impl From<KeyStroke> for KeyData {
+    fn from(stroke: KeyStroke) -> Self {
+        let key = match stroke.key {
+            Key::Printable(c) => InputKey {
+                scan_code: ScanCode::NULL.0,
+                unicode_char: c.into(),
+            },
+            Key::Special(scan_code) => InputKey {
+                scan_code: scan_code.0,
+                unicode_char: 0,
+            },
+        };
+        Self {
+            key,
+            key_state: KeyState {
+                key_shift_state: stroke.shift_state,
+                key_toggle_state: stroke.toggle_state,
+            },
+        }
+    }
+}
+
+/// A callback registered with [`InputEx::register_key_notify`].
+#[cfg(feature = "alloc")]
+#[derive(Debug)]
+pub struct KeyNotifyHandle {
+    handle: *mut c_void,
+    slot: usize,
+}
+
+#[cfg(feature = "alloc")]
+type KeyNotifyCallback = Arc<dyn Fn(KeyStroke) + Send + Sync>;
+
+/// Callbacks of [`InputEx::register_key_notify`]. The key notification
+/// function gets no context, so each slot has its own [`trampoline`].
+#[cfg(feature = "alloc")]
+static NOTIFY_SLOTS: [SpinLock<Option<KeyNotifyCallback>>; 8] = [const { SpinLock::new(None) }; 8];
+
+#[cfg(feature = "alloc")]
+const TRAMPOLINES: [KeyNotifyFunction; 8] = [
+    trampoline::<0>,
+    trampoline::<1>,
+    trampoline::<2>,
+    trampoline::<3>,
+    trampoline::<4>,
+    trampoline::<5>,
+    trampoline::<6>,
+    trampoline::<7>,
+];
+
+#[cfg(feature = "alloc")]
+unsafe extern "efiapi" fn trampoline<const N: usize>(key_data: *mut KeyData) -> Status {
+    // The lock is released before calling `f`, which may register or
+    // unregister callbacks itself.
+    let f = NOTIFY_SLOTS[N].lock().clone();
+    if let (Some(key_data), Some(f)) = (unsafe { key_data.as_ref() }, f) {
+        f((*key_data).into());
+    }
+    Status::SUCCESS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Char16;
+
+    #[test]
+    fn key_stroke() {
+        let c = Key::Printable(Char16::try_from('c').unwrap());
+        let stroke = KeyStroke::new(c, KeyShiftState::LEFT_CONTROL_PRESSED);
+        assert!(stroke.ctrl());
+        assert!(!stroke.alt() && !stroke.shift() && !stroke.logo());
+        assert_eq!(KeyStroke::from(KeyData::from(stroke)), stroke);
+
+        // Without a valid shift state, no modifier is reported.
+        let stroke = KeyStroke {
+            shift_state: KeyShiftState::LEFT_CONTROL_PRESSED,
+            ..stroke
+        };
+        assert!(!stroke.ctrl());
+
+        let del = KeyData::from(KeyStroke::new(
+            Key::Special(ScanCode::DELETE),
+            KeyShiftState::LEFT_ALT_PRESSED,
+        ));
+        assert_eq!(del.key.scan_code, 0x08);
+        assert_eq!(del.key.unicode_char, 0);
+    }
+}
@@ -0,0 +1,516 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Software keyboard layouts.
+//!
+//! Many firmware implementations only support a US keyboard layout and
+//! report keys accordingly. A [`SoftwareLayout`] remaps the characters
+//! reported by [`Input`] to the characters printed on the keys of a different
+//! layout, and a [`LayoutMapper`] applies such a layout to a stream of keys,
+//! including the composition of dead keys (e.g. `^` followed by `e` yields
+//! `ê`).
+//!
+//! Keys that can only be reached with AltGr are not supported, since
+//! [`Input`] does not report the state of modifier keys.
+//!
+//! [`Input`]: super::Input
+
+use super::{Input, Key};
+use crate::{Char16, Result};
+
+/// A dead key: a key that produces no character by itself, but modifies the
+/// next key.
+#[derive(Debug)]
+struct DeadKey {
+    /// Character produced when the dead key is followed by a space, or by a
+    /// key that it cannot be combined with.
+    standalone: char,
+
+    /// Pairs of base character and composed character.
+    compositions: &'static [(char, char)],
+}
+
+impl DeadKey {
+    fn compose(&self, c: char) -> Option<char> {
+        self.compositions
+            .iter()
+            .find(|(base, _)| *base == c)
+            .map(|(_, composed)| *composed)
+    }
+}
+
+const CIRCUMFLEX: DeadKey = DeadKey {
+    standalone: '^',
+    compositions: &[
+        ('a', 'â'),
+        ('e', 'ê'),
+        ('i', 'î'),
+        ('o', 'ô'),
+        ('u', 'û'),
+        ('A', 'Â'),
+        ('E', 'Ê'),
+        ('I', 'Î'),
+        ('O', 'Ô'),
+        ('U', 'Û'),
+    ],
+};
+
+const ACUTE: DeadKey = DeadKey {
+    standalone: '´',
+    compositions: &[
+        ('a', 'á'),
+        ('e', 'é'),
+        ('i', 'í'),
+        ('o', 'ó'),
+        ('u', 'ú'),
+        ('y', 'ý'),
+        ('A', 'Á'),
+        ('E', 'É'),
+        ('I', 'Í'),
+        ('O', 'Ó'),
+        ('U', 'Ú'),
+        ('Y', 'Ý'),
+    ],
+};
+
+const GRAVE: DeadKey = DeadKey {
+    standalone: '`',
+    compositions: &[
+        ('a', 'à'),
+        ('e', 'è'),
+        ('i', 'ì'),
+        ('o', 'ò'),
+        ('u', 'ù'),
+        ('A', 'À'),
+        ('E', 'È'),
+        ('I', 'Ì'),
+        ('O', 'Ò'),
+        ('U', 'Ù'),
+    ],
+};
+
+const DIAERESIS: DeadKey = DeadKey {
+    standalone: '¨',
+    compositions: &[
+        ('a', 'ä'),
+        ('e', 'ë'),
+        ('i', 'ï'),
+        ('o', 'ö'),
+        ('u', 'ü'),
+        ('y', 'ÿ'),
+        ('A', 'Ä'),
+        ('E', 'Ë'),
+        ('I', 'Ï'),
+        ('O', 'Ö'),
+        ('U', 'Ü'),
+    ],
+};
+
+const TILDE: DeadKey = DeadKey {
+    standalone: '~',
+    compositions: &[
+        ('a', 'ã'),
+        ('n', 'ñ'),
+        ('o', 'õ'),
+        ('A', 'Ã'),
+        ('N', 'Ñ'),
+        ('O', 'Õ'),
+    ],
+};
+
+/// A keyboard layout applied in software on top of the US layout reported
+/// by the firmware.
+#[derive(Debug)]
+pub struct SoftwareLayout {
+    name: &'static str,
+
+    /// Pairs of character reported by the firmware and character of the
+    /// layout. Characters not in this list are passed through unchanged.
+    remap: &'static [(char, char)],
+
+    /// Pairs of character of the layout (after remapping) and the dead key
+    /// it represents.
+    dead_keys: &'static [(char, DeadKey)],
+}
+
+impl SoftwareLayout {
+    /// US layout. Keys are passed through unchanged.
+    pub const US: Self = Self {
+        name: "us",
+        remap: &[],
+        dead_keys: &[],
+    };
+
+    /// US International layout, with dead keys for `'`, `"`, `` ` ``, `~`,
+    /// and `^`.
+    pub const US_INTERNATIONAL: Self = Self {
+        name: "us-intl",
+        remap: &[],
+        dead_keys: &[
+            (
+                '\'',
+                DeadKey {
+                    standalone: '\'',
+                    ..ACUTE
+                },
+            ),
+            (
+                '"',
+                DeadKey {
+                    standalone: '"',
+                    ..DIAERESIS
+                },
+            ),
+            ('`', GRAVE),
+            ('~', TILDE),
+            ('^', CIRCUMFLEX),
+        ],
+    };
+
+    /// German QWERTZ layout.
+    pub const GERMAN: Self = Self {
+        name: "de",
+        remap: &[
+            ('`', '^'),
+            ('~', '°'),
+            ('@', '"'),
+            ('#', '§'),
+            ('^', '&'),
+            ('&', '/'),
+            ('*', '('),
+            ('(', ')'),
+            (')', '='),
+            ('-', 'ß'),
+            ('_', '?'),
+            ('=', '´'),
+            ('+', '`'),
+            ('y', 'z'),
+            ('Y', 'Z'),
+            ('[', 'ü'),
+            ('{', 'Ü'),
+            (']', '+'),
+            ('}', '*'),
+            ('\\', '#'),
+            ('|', '\''),
+            (';', 'ö'),
+            (':', 'Ö'),
+            ('\'', 'ä'),
+            ('"', 'Ä'),
+            ('z', 'y'),
+            ('Z', 'Y'),
+            ('<', ';'),
+            ('>', ':'),
+            ('/', '-'),
+            ('?', '_'),
+        ],
+        dead_keys: &[('^', CIRCUMFLEX), ('´', ACUTE), ('`', GRAVE)],
+    };
+
+    /// French AZERTY layout.
+    pub const FRENCH: Self = Self {
+        name: "fr",
+        remap: &[
+            ('`', '²'),
+            ('1', '&'),
+            ('2', 'é'),
+            ('3', '"'),
+            ('4', '\''),
+            ('5', '('),
+            ('6', '-'),
+            ('7', 'è'),
+            ('8', '_'),
+            ('9', 'ç'),
+            ('0', 'à'),
+            ('-', ')'),
+            ('!', '1'),
+            ('@', '2'),
+            ('#', '3'),
+            ('$', '4'),
+            ('%', '5'),
+            ('^', '6'),
+            ('&', '7'),
+            ('*', '8'),
+            ('(', '9'),
+            (')', '0'),
+            ('_', '°'),
+            ('q', 'a'),
+            ('Q', 'A'),
+            ('w', 'z'),
+            ('W', 'Z'),
+            ('[', '^'),
+            ('{', '¨'),
+            (']', '$'),
+            ('}', '£'),
+            ('\\', '*'),
+            ('|', 'µ'),
+            ('a', 'q'),
+            ('A', 'Q'),
+            (';', 'm'),
+            (':', 'M'),
+            ('\'', 'ù'),
+            ('"', '%'),
+            ('z', 'w'),
+            ('Z', 'W'),
+            ('m', ','),
+            ('M', '?'),
+            (',', ';'),
+            ('<', '.'),
+            ('.', ':'),
+            ('>', '/'),
+            ('/', '!'),
+            ('?', '§'),
+        ],
+        dead_keys: &[('^', CIRCUMFLEX), ('¨', DIAERESIS)],
+    };
+
+    /// All built-in layouts.
+    pub const ALL: &'static [&'static Self] = &[
+        &Self::US,
+        &Self::US_INTERNATIONAL,
+        &Self::GERMAN,
+        &Self::FRENCH,
+    ];
+
+    /// Returns the built-in layout called `name`, ignoring ASCII case. See
+    /// [`SoftwareLayout::name`] for the names of the built-in layouts.
+    #[must_use]
+    pub fn by_name(name: &str) -> Option<&'static Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|layout| layout.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Returns the short name of the layout, such as `us` or `de`.
+    #[must_use]
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn remap(&self, c: char) -> char {
+        self.remap
+            .iter()
+            .find(|(from, _)| *from == c)
+            .map_or(c, |(_, to)| *to)
+    }
+
+    fn dead_key(&self, c: char) -> Option<&DeadKey> {
+        self.dead_keys
+            .iter()
+            .find(|(key, _)| *key == c)
+            .map(|(_, dead_key)| dead_key)
+    }
+}
+
+/// Applies a [`SoftwareLayout`] to keys read from an [`Input`] device.
+///
+/// A dead key followed by a character it can be combined with produces the
+/// composed character. Followed by a space, the dead key produces its own
+/// character. Followed by backspace, the dead key is discarded. Followed by
+/// any other key, the dead key's character is produced first, then the key.
+///
+/// # Example
+///
+/// ```no_run
+/// use uefi::proto::console::text::{Input, Key, LayoutMapper, SoftwareLayout};
+///
+/// # fn example(input: &mut Input) -> uefi::Result {
+/// let mut mapper = LayoutMapper::new(&SoftwareLayout::GERMAN);
+/// while let Some(key) = mapper.read_key(input)? {
+///     if let Key::Printable(c) = key {
+///         uefi::print!("{c}");
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct LayoutMapper {
+    layout: &'static SoftwareLayout,
+    dead_key: Option<&'static DeadKey>,
+    queued: Option<Key>,
+}
+
+impl LayoutMapper {
+    /// Creates a mapper using `layout`.
+    #[must_use]
+    pub const fn new(layout: &'static SoftwareLayout) -> Self {
+        Self {
+            layout,
+            dead_key: None,
+            queued: None,
+        }
+    }
+
+    /// Returns the layout in use.
+    #[must_use]
+    pub const fn layout(&self) -> &'static SoftwareLayout {
+        self.layout
+    }
+
+    /// Changes the layout. A pending dead key is discarded.
+    pub const fn set_layout(&mut self, layout: &'static SoftwareLayout) {
+        self.layout = layout;
+        self.dead_key = None;
+    }
+
+    /// Translates `key`, as reported by the firmware.
+    ///
+    /// Returns `None` if the key was consumed, e.g. because it is a dead
+    /// key. If the key produces two keys, the second one is returned by the
+    /// next call to [`next_queued`].
+    ///
+    /// [`next_queued`]: Self::next_queued
+    pub fn translate(&mut self, key: Key) -> Option<Key> {
+        let layout = self.layout;
+        let c = match key {
+            Key::Printable(c) => layout.remap(char::from(c)),
+            Key::Special(_) => return self.flush_dead_key(key),
+        };
+
+        let Some(dead_key) = self.dead_key.take() else {
+            if let Some(dead_key) = layout.dead_key(c) {
+                self.dead_key = Some(dead_key);
+                return None;
+            }
+            return Some(printable(c));
+        };
+
+        match c {
+            ' ' => Some(printable(dead_key.standalone)),
+            '\u{8}' => None,
+            c => match dead_key.compose(c) {
+                Some(composed) => Some(printable(composed)),
+                None => {
+                    self.queued = Some(printable(c));
+                    Some(printable(dead_key.standalone))
+                }
+            },
+        }
+    }
+
+    /// Returns the second key produced by the last call to [`translate`], if
+    /// any.
+    ///
+    /// [`translate`]: Self::translate
+    pub const fn next_queued(&mut self) -> Option<Key> {
+        self.queued.take()
+    }
+
+    /// Reads the next key from `input` and translates it.
+    ///
+    /// Returns `Ok(None)` if no translated key is available, either because
+    /// no key was pressed or because a dead key is waiting for the next key.
+    ///
+    /// # Errors
+    ///
+    /// See [`Input::read_key`].
+    pub fn read_key(&mut self, input: &mut Input) -> Result<Option<Key>> {
+        if let Some(key) = self.next_queued() {
+            return Ok(Some(key));
+        }
+        while let Some(key) = input.read_key()? {
+            if let Some(key) = self.translate(key) {
+                return Ok(Some(key));
+            }
+        }
+        Ok(None)
+    }
+
+    fn flush_dead_key(&mut self, key: Key) -> Option<Key> {
+        match self.dead_key.take() {
+            Some(dead_key) => {
+                self.queued = Some(key);
+                Some(printable(dead_key.standalone))
+            }
+            None => Some(key),
+        }
+    }
+}
+
+/// Creates a printable key. Characters outside the Basic Multilingual
+/// Plane can't occur since all layout tables only contain UCS-2 characters.
+fn printable(c: char) -> Key {
+    Key::Printable(Char16::try_from(c).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::console::text::ScanCode;
+
+    fn translate_str(layout: &'static SoftwareLayout, input: &str) -> [Option<char>; 8] {
+        let mut mapper = LayoutMapper::new(layout);
+        let mut output = [None; 8];
+        let mut out = output.iter_mut();
+        for c in input.chars() {
+            for key in [mapper.translate(printable(c)), mapper.next_queued()]
+                .into_iter()
+                .flatten()
+            {
+                let Key::Printable(c) = key else {
+                    unreachable!()
+                };
+                *out.next().unwrap() = Some(char::from(c));
+            }
+        }
+        output
+    }
+
+    fn chars<const N: usize>(s: [char; N]) -> [Option<char>; 8] {
+        let mut output = [None; 8];
+        for (o, c) in output.iter_mut().zip(s) {
+            *o = Some(c);
+        }
+        output
+    }
+
+    #[test]
+    fn test_remap() {
+        assert_eq!(
+            translate_str(&SoftwareLayout::US, "yz[;"),
+            chars(['y', 'z', '[', ';'])
+        );
+        assert_eq!(
+            translate_str(&SoftwareLayout::GERMAN, "yZ[;@"),
+            chars(['z', 'Y', 'ü', 'ö', '"'])
+        );
+        assert_eq!(
+            translate_str(&SoftwareLayout::FRENCH, "qa2m"),
+            chars(['a', 'q', 'é', ','])
+        );
+    }
+
+    #[test]
+    fn test_dead_keys() {
+        // Composition, standalone via space, fallback.
+        assert_eq!(
+            translate_str(&SoftwareLayout::GERMAN, "`e` `x"),
+            chars(['ê', '^', '^', 'x'])
+        );
+        // Backspace discards the dead key.
+        assert_eq!(
+            translate_str(&SoftwareLayout::FRENCH, "{\u{8}e{e"),
+            chars(['e', 'ë'])
+        );
+        assert_eq!(
+            translate_str(&SoftwareLayout::US_INTERNATIONAL, "'e\"u~n"),
+            chars(['é', 'ü', 'ñ'])
+        );
+    }
+
+    #[test]
+    fn test_special_key_flushes_dead_key() {
+        let mut mapper = LayoutMapper::new(&SoftwareLayout::GERMAN);
+        assert_eq!(mapper.translate(printable('`')), None);
+        let escape = Key::Special(ScanCode::ESCAPE);
+        assert_eq!(mapper.translate(escape), Some(printable('^')));
+        assert_eq!(mapper.next_queued(), Some(escape));
+        assert_eq!(mapper.next_queued(), None);
+    }
+
+    #[test]
+    fn test_by_name() {
+        assert_eq!(SoftwareLayout::by_name("DE").unwrap().name(), "de");
+        assert!(SoftwareLayout::by_name("xx").is_none());
+    }
+}
@@ -2,8 +2,42 @@
 
 //! Text I/O.
 
+mod buffered;
+pub use buffered::BufferedOutput;
+#[cfg(feature = "alloc")]
+pub use buffered::ScreenBuffer;
+
+#[cfg(feature = "alloc")]
+mod graphics;
+#[cfg(feature = "alloc")]
+pub use graphics::GraphicsConsole;
+
 mod input;
-pub use input::{Input, Key, ScanCode};
+pub use input::{Input, Key, KeySequence, Keys, ScanCode};
+
+mod input_ex;
+#[cfg(feature = "alloc")]
+pub use input_ex::KeyNotifyHandle;
+pub use input_ex::{InputEx, KeyShiftState, KeyStroke, KeyToggleState};
+
+mod layout;
+pub use layout::{LayoutMapper, SoftwareLayout};
 
 mod output;
-pub use output::{Color, Output, OutputMode};
+pub use output::{Color, CursorGuard, Output, OutputMode, OutputModeInfo};
+
+#[cfg(feature = "alloc")]
+mod splitter;
+#[cfg(feature = "alloc")]
+pub use splitter::{OutputSink, OutputSplitter};
+
+#[cfg(feature = "alloc")]
+mod stream;
+#[cfg(feature = "alloc")]
+pub use stream::KeyStream;
+
+mod terminal;
+pub use terminal::Terminal;
+
+mod tui;
+pub use tui::{FocusOrder, KeyRepeat, Navigation, Style, Theme};
@@ -3,6 +3,10 @@
 use crate::proto::unsafe_protocol;
 use crate::{CStr16, Result, ResultExt, Status, StatusExt};
 use core::fmt;
+use core::ops::{Deref, DerefMut};
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
 use uefi_raw::protocol::console::{SimpleTextOutputMode, SimpleTextOutputProtocol};
 
 /// Simple Text Output [`Protocol`]. Interface for text-based output devices.
@@ -64,8 +68,9 @@ impl Output {
     ///
     /// UEFI applications are encouraged to try to print a string even if it contains
     /// some unsupported characters.
-    pub fn test_string(&mut self, string: &CStr16) -> Result<bool> {
-        match unsafe { (self.0.test_string)(&mut self.0, string.as_ptr().cast()) } {
+    pub fn test_string(&self, string: &CStr16) -> Result<bool> {
+        let this: *const _ = &self.0;
+        match unsafe { (self.0.test_string)(this.cast_mut(), string.as_ptr().cast()) } {
             Status::UNSUPPORTED => Ok(false),
             other => other.to_result_with_val(|| true),
         }
@@ -73,8 +78,9 @@ impl Output {
 
     /// Returns an iterator of all supported text modes.
     // TODO: Bring back impl Trait once the story around bounds improves
-    pub const fn modes(&mut self) -> OutputModeIter<'_> {
-        let max = self.data().max_mode as usize;
+    #[must_use]
+    pub const fn modes(&self) -> OutputModeIter<'_> {
+        let max = self.max_mode();
         OutputModeIter {
             output: self,
             current: 0,
@@ -82,7 +88,16 @@ impl Output {
         }
     }
 
-    /// Returns the width (column count) and height (row count) of a text mode.
+    /// Returns the number of text modes supported by the device, i.e. the
+    /// exclusive upper bound of mode indices. Some indices below it may
+    /// still be unsupported.
+    #[must_use]
+    pub const fn max_mode(&self) -> usize {
+        self.data().max_mode as usize
+    }
+
+    /// Returns the text mode with the given index, including its width
+    /// (column count) and height (row count).
     ///
     /// Devices are required to support at least an 80x25 text mode and to
     /// assign index 0 to it. If 80x50 is supported, then it will be mode 1,
@@ -92,11 +107,55 @@ impl Output {
     /// If you want to iterate over all text modes supported by the device,
     /// consider using the iterator produced by `modes()` as a more ergonomic
     /// alternative to this method.
-    fn query_mode(&self, index: usize) -> Result<(usize, usize)> {
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::DEVICE_ERROR`]: the device had an error.
+    /// * [`Status::UNSUPPORTED`]: the mode number is not valid.
+    pub fn query_mode(&self, index: usize) -> Result<OutputMode> {
         let (mut columns, mut rows) = (0, 0);
         let this: *const _ = &self.0;
         unsafe { (self.0.query_mode)(this.cast_mut(), index, &mut columns, &mut rows) }
-            .to_result_with_val(|| (columns, rows))
+            .to_result_with_val(|| OutputMode {
+                index,
+                dims: (columns, rows),
+            })
+    }
+
+    /// Returns all supported text modes, ordered by index.
+    #[cfg(feature = "alloc")]
+    #[must_use]
+    pub fn mode_list(&self) -> Vec<OutputMode> {
+        (0..self.max_mode())
+            .filter_map(|index| self.query_mode(index).ok())
+            .collect()
+    }
+
+    /// Returns the supported text mode with the largest area (columns times
+    /// rows). Among modes of equal area, the one with the lowest index is
+    /// returned.
+    #[must_use]
+    pub fn best_mode(&self) -> Option<OutputMode> {
+        (0..self.max_mode())
+            .filter_map(|index| self.query_mode(index).ok())
+            .fold(None, |best: Option<OutputMode>, mode| match best {
+                Some(best) if best.area() >= mode.area() => Some(best),
+                _ => Some(mode),
+            })
+    }
+
+    /// Switches to the text mode returned by [`best_mode`], and returns it.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::UNSUPPORTED`]: the device reports no valid mode.
+    /// * [`Status::DEVICE_ERROR`]: the device had an error.
+    ///
+    /// [`best_mode`]: Self::best_mode
+    pub fn set_best_mode(&mut self) -> Result<OutputMode> {
+        let mode = self.best_mode().ok_or(Status::UNSUPPORTED)?;
+        self.set_mode(mode)?;
+        Ok(mode)
     }
 
     /// Returns the current text mode.
@@ -105,8 +164,7 @@ impl Output {
             -1 => Ok(None),
             n if n >= 0 => {
                 let index = n as usize;
-                self.query_mode(index)
-                    .map(|dims| Some(OutputMode { index, dims }))
+                self.query_mode(index).map(Some)
             }
             _ => unreachable!(),
         }
@@ -117,6 +175,13 @@ impl Output {
         unsafe { (self.0.set_mode)(&mut self.0, mode.index) }.to_result()
     }
 
+    /// Returns a snapshot of the current state of the device: the text
+    /// mode, the colors, and the cursor.
+    #[must_use]
+    pub const fn mode_info(&self) -> OutputModeInfo {
+        OutputModeInfo(*self.data())
+    }
+
     /// Returns whether the cursor is currently shown or not.
     #[must_use]
     pub fn cursor_visible(&self) -> bool {
@@ -160,6 +225,57 @@ impl Output {
         unsafe { (self.0.set_attribute)(&mut self.0, attr) }.to_result()
     }
 
+    /// Saves the cursor position and visibility, and returns a guard
+    /// restoring them when dropped.
+    ///
+    /// The guard dereferences to the `Output`, so it can be used to print in
+    /// the meantime:
+    ///
+    /// ```no_run
+    /// use core::fmt::Write;
+    /// use uefi::proto::console::text::Output;
+    ///
+    /// fn print_status(stdout: &mut Output, status: &str) -> uefi::Result {
+    ///     let mut stdout = stdout.save_cursor();
+    ///     stdout.set_cursor_position(0, 0)?;
+    ///     let _ = write!(stdout, "{status}");
+    ///     Ok(())
+    /// }
+    /// ```
+    #[must_use]
+    pub fn save_cursor(&mut self) -> CursorGuard<'_> {
+        let (column, row) = self.cursor_position();
+        let visible = self.cursor_visible();
+        CursorGuard {
+            output: self,
+            column,
+            row,
+            visible,
+        }
+    }
+
+    /// Runs `f` with the given text and background colors, then restores the
+    /// previous colors, even if `f` fails.
+    ///
+    /// See [`set_color`] for the valid colors.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::DEVICE_ERROR`]: the colors could not be changed.
+    ///
+    /// [`set_color`]: Self::set_color
+    pub fn with_colors<R>(
+        &mut self,
+        foreground: Color,
+        background: Color,
+        f: impl FnOnce(&mut Self) -> R,
+    ) -> Result<R> {
+        let attribute = self.data().attribute as usize;
+        self.set_color(foreground, background)?;
+        let result = f(self);
+        unsafe { (self.0.set_attribute)(&mut self.0, attribute) }.to_result_with_val(|| result)
+    }
+
     /// Get a reference to `OutputData`. The lifetime of the reference is tied
     /// to `self`.
     const fn data(&self) -> &SimpleTextOutputMode {
@@ -220,6 +336,39 @@ impl fmt::Write for Output {
     }
 }
 
+/// Guard returned by [`Output::save_cursor`], restoring the cursor position
+/// and visibility when dropped.
+#[derive(Debug)]
+pub struct CursorGuard<'a> {
+    output: &'a mut Output,
+    column: usize,
+    row: usize,
+    visible: bool,
+}
+
+impl Deref for CursorGuard<'_> {
+    type Target = Output;
+
+    fn deref(&self) -> &Output {
+        self.output
+    }
+}
+
+impl DerefMut for CursorGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Output {
+        self.output
+    }
+}
+
+impl Drop for CursorGuard<'_> {
+    fn drop(&mut self) {
+        let _ = self.output.set_cursor_position(self.column, self.row);
+        if self.output.cursor_visible() != self.visible {
+            let _ = self.output.enable_cursor(self.visible);
+        }
+    }
+}
+
 /// The text mode (resolution) of the output device.
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
 pub struct OutputMode {
@@ -248,12 +397,63 @@ impl OutputMode {
     pub const fn rows(&self) -> usize {
         self.dims.1
     }
+
+    /// Returns the number of character cells, i.e. columns times rows.
+    #[inline]
+    #[must_use]
+    pub const fn area(&self) -> usize {
+        self.dims.0 * self.dims.1
+    }
+}
+
+/// Snapshot of the state of an output device, returned by
+/// [`Output::mode_info`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct OutputModeInfo(SimpleTextOutputMode);
+
+impl OutputModeInfo {
+    /// Returns the number of text modes supported by the device. See
+    /// [`Output::max_mode`].
+    #[must_use]
+    pub const fn max_mode(&self) -> usize {
+        self.0.max_mode as usize
+    }
+
+    /// Returns the index of the current text mode, or `None` if the device
+    /// has no valid mode.
+    #[must_use]
+    pub const fn mode(&self) -> Option<usize> {
+        if self.0.mode < 0 {
+            None
+        } else {
+            Some(self.0.mode as usize)
+        }
+    }
+
+    /// Returns the current text attribute. Bits 0..4 are the foreground
+    /// color, and bits 4..7 the background color, as [`Color`] values.
+    #[must_use]
+    pub const fn attribute(&self) -> usize {
+        self.0.attribute as usize
+    }
+
+    /// Returns the column and row of the cursor.
+    #[must_use]
+    pub const fn cursor_position(&self) -> (usize, usize) {
+        (self.0.cursor_column as usize, self.0.cursor_row as usize)
+    }
+
+    /// Returns whether the cursor is shown.
+    #[must_use]
+    pub const fn cursor_visible(&self) -> bool {
+        self.0.cursor_visible.0 != 0
+    }
 }
 
 /// An iterator of the text modes (possibly) supported by a device.
 #[derive(Debug)]
 pub struct OutputModeIter<'out> {
-    output: &'out mut Output,
+    output: &'out Output,
     current: usize,
     max: usize,
 }
@@ -266,8 +466,8 @@ impl Iterator for OutputModeIter<'_> {
         if index < self.max {
             self.current += 1;
 
-            if let Ok(dims) = self.output.query_mode(index) {
-                Some(OutputMode { index, dims })
+            if let Ok(mode) = self.output.query_mode(index) {
+                Some(mode)
             } else {
                 self.next()
             }
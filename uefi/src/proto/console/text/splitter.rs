@@ -0,0 +1,420 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Duplication of console output to additional sinks.
+//!
+//! [`OutputSplitter`] replaces the Simple Text Output protocol of the
+//! console out device (`ConOut`) with an interposer that forwards all calls
+//! to the original protocol and additionally writes the output to any number
+//! of [`OutputSink`]s, similar to the `ConSplitter` driver of EDK2. Since the
+//! system table is updated as well, everything printed through stdout,
+//! including [`println!`] and the logger, reaches all sinks.
+//!
+//! [`println!`]: crate::println
+
+use super::{Color, Output};
+use crate::boot::{self, ScopedProtocol};
+use crate::proto::Protocol;
+use crate::proto::console::serial::Serial;
+use crate::proto::media::file::RegularFile;
+use crate::{CStr16, Handle, Result, ResultExt, Status, system, table};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::marker::PhantomPinned;
+use core::pin::Pin;
+use core::ptr;
+use uefi_raw::protocol::console::SimpleTextOutputProtocol;
+use uefi_raw::{Boolean, Char16};
+
+/// A destination for text output.
+///
+/// Only [`output_string`] is required; the remaining methods do nothing by
+/// default, which is appropriate for sinks without a screen such as a serial
+/// port or a log file.
+///
+/// [`output_string`]: Self::output_string
+pub trait OutputSink {
+    /// Writes a string. Line breaks are encoded as `\r\n`.
+    fn output_string(&mut self, string: &CStr16) -> Result;
+
+    /// Clears the screen.
+    fn clear(&mut self) -> Result {
+        Ok(())
+    }
+
+    /// Sets the colors of subsequent output.
+    fn set_color(&mut self, _foreground: Color, _background: Color) -> Result {
+        Ok(())
+    }
+
+    /// Moves the cursor.
+    fn set_cursor_position(&mut self, _column: usize, _row: usize) -> Result {
+        Ok(())
+    }
+
+    /// Shows or hides the cursor.
+    fn enable_cursor(&mut self, _visible: bool) -> Result {
+        Ok(())
+    }
+}
+
+impl OutputSink for Output {
+    fn output_string(&mut self, string: &CStr16) -> Result {
+        self.output_string_lossy(string)
+    }
+
+    fn clear(&mut self) -> Result {
+        Self::clear(self)
+    }
+
+    fn set_color(&mut self, foreground: Color, background: Color) -> Result {
+        Self::set_color(self, foreground, background)
+    }
+
+    fn set_cursor_position(&mut self, column: usize, row: usize) -> Result {
+        Self::set_cursor_position(self, column, row)
+    }
+
+    fn enable_cursor(&mut self, visible: bool) -> Result {
+        Self::enable_cursor(self, visible)
+    }
+}
+
+/// Writes the string as UTF-8.
+impl OutputSink for Serial {
+    fn output_string(&mut self, string: &CStr16) -> Result {
+        write_utf8(string, |bytes| self.write(bytes).discard_errdata())
+    }
+}
+
+/// Writes the string as UTF-8 at the current position of the file.
+impl OutputSink for RegularFile {
+    fn output_string(&mut self, string: &CStr16) -> Result {
+        write_utf8(string, |bytes| self.write(bytes).discard_errdata())
+    }
+}
+
+impl<P: Protocol + OutputSink + ?Sized> OutputSink for ScopedProtocol<P> {
+    fn output_string(&mut self, string: &CStr16) -> Result {
+        (**self).output_string(string)
+    }
+
+    fn clear(&mut self) -> Result {
+        (**self).clear()
+    }
+
+    fn set_color(&mut self, foreground: Color, background: Color) -> Result {
+        (**self).set_color(foreground, background)
+    }
+
+    fn set_cursor_position(&mut self, column: usize, row: usize) -> Result {
+        (**self).set_cursor_position(column, row)
+    }
+
+    fn enable_cursor(&mut self, visible: bool) -> Result {
+        (**self).enable_cursor(visible)
+    }
+}
+
+/// Converts `string` to UTF-8 and passes it to `write` in chunks.
+fn write_utf8(string: &CStr16, mut write: impl FnMut(&[u8]) -> Result) -> Result {
+    let mut buf = [0u8; 128];
+    let mut len = 0;
+    for c in string.iter() {
+        if len + 4 > buf.len() {
+            write(&buf[..len])?;
+            len = 0;
+        }
+        len += char::from(*c).encode_utf8(&mut buf[len..]).len();
+    }
+    if len > 0 {
+        write(&buf[..len])?;
+    }
+    Ok(())
+}
+
+/// Console colors in attribute order.
+const COLORS: [Color; 16] = [
+    Color::Black,
+    Color::Blue,
+    Color::Green,
+    Color::Cyan,
+    Color::Red,
+    Color::Magenta,
+    Color::Brown,
+    Color::LightGray,
+    Color::DarkGray,
+    Color::LightBlue,
+    Color::LightGreen,
+    Color::LightCyan,
+    Color::LightRed,
+    Color::LightMagenta,
+    Color::Yellow,
+    Color::White,
+];
+
+/// Interposer on the console out device that duplicates output to
+/// additional [`OutputSink`]s.
+///
+/// All calls are forwarded to the original console first, and its status is
+/// returned to the caller. Errors of the additional sinks are ignored. When
+/// the splitter is dropped, the original protocol is restored.
+///
+/// # Example
+///
+/// ```no_run
+/// use uefi::boot;
+/// use uefi::proto::console::serial::Serial;
+/// use uefi::proto::console::text::OutputSplitter;
+///
+/// # fn example() -> uefi::Result {
+/// let handle = boot::get_handle_for_protocol::<Serial>()?;
+/// let serial = boot::open_protocol_exclusive::<Serial>(handle)?;
+///
+/// let mut splitter = OutputSplitter::install()?;
+/// splitter.as_mut().add_sink(serial);
+/// uefi::println!("Printed to the console and the serial port");
+/// # Ok(())
+/// # }
+/// ```
+#[repr(C)]
+pub struct OutputSplitter {
+    /// Must be the first field, so that a pointer to the protocol is also a
+    /// pointer to the splitter.
+    protocol: SimpleTextOutputProtocol,
+    original: *mut SimpleTextOutputProtocol,
+    handle: Handle,
+    sinks: Vec<Box<dyn OutputSink>>,
+    _pinned: PhantomPinned,
+}
+
+impl core::fmt::Debug for OutputSplitter {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("OutputSplitter")
+            .field("original", &self.original)
+            .field("handle", &self.handle)
+            .field("sinks", &self.sinks.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl OutputSplitter {
+    /// Installs the splitter on the console out device and makes it the
+    /// system table's stdout. Initially, the splitter has no sinks.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::UNSUPPORTED`]: there is no console out device.
+    /// * [`Status::ACCESS_DENIED`]: the protocol of the console out device
+    ///   could not be replaced.
+    pub fn install() -> Result<Pin<Box<Self>>> {
+        // SAFETY: valid per requirements of `set_system_table`.
+        let st = unsafe { table::system_table_raw_panicking().as_ref() };
+        let handle = unsafe { Handle::from_ptr(st.stdout_handle) }.ok_or(Status::UNSUPPORTED)?;
+        let original = st.stdout;
+        if original.is_null() {
+            return Err(Status::UNSUPPORTED.into());
+        }
+
+        let mut splitter = Box::pin(Self {
+            protocol: SimpleTextOutputProtocol {
+                reset: Self::reset,
+                output_string: Self::output_string,
+                test_string: Self::test_string,
+                query_mode: Self::query_mode,
+                set_mode: Self::set_mode,
+                set_attribute: Self::set_attribute,
+                clear_screen: Self::clear_screen,
+                set_cursor_position: Self::set_cursor_position,
+                enable_cursor: Self::enable_cursor,
+                // The mode of the original console is shared, so that it
+                // always reflects the state of the screen.
+                mode: unsafe { (*original).mode },
+            },
+            original,
+            handle,
+            sinks: Vec::new(),
+            _pinned: PhantomPinned,
+        });
+
+        // SAFETY: the interface stays valid until it is replaced again in
+        // `drop`.
+        unsafe {
+            boot::reinstall_protocol_interface(
+                handle,
+                &SimpleTextOutputProtocol::GUID,
+                original.cast(),
+                ptr::from_ref(&splitter.protocol).cast(),
+            )
+        }?;
+        // SAFETY: the console is not moved out of the pin. The protocol stays
+        // valid until stdout is restored in `drop`.
+        let this = unsafe { splitter.as_mut().get_unchecked_mut() };
+        unsafe { system::swap_stdout(handle.as_ptr(), &mut this.protocol) };
+        Ok(splitter)
+    }
+
+    /// Adds a sink that receives all subsequent output.
+    pub fn add_sink(self: Pin<&mut Self>, sink: impl OutputSink + 'static) {
+        // SAFETY: the splitter is not moved out of the pin.
+        unsafe { self.get_unchecked_mut() }
+            .sinks
+            .push(Box::new(sink));
+    }
+
+    /// Removes all sinks and returns them.
+    #[must_use]
+    pub fn take_sinks(self: Pin<&mut Self>) -> Vec<Box<dyn OutputSink>> {
+        // SAFETY: the splitter is not moved out of the pin.
+        core::mem::take(&mut unsafe { self.get_unchecked_mut() }.sinks)
+    }
+
+    /// Gets the splitter from a pointer to its protocol.
+    ///
+    /// # Safety
+    ///
+    /// `this` must be the `protocol` field of a splitter.
+    const unsafe fn from_protocol<'a>(this: *mut SimpleTextOutputProtocol) -> &'a mut Self {
+        unsafe { &mut *this.cast::<Self>() }
+    }
+
+    /// Calls `f` for each sink, ignoring errors.
+    fn for_each_sink(&mut self, mut f: impl FnMut(&mut dyn OutputSink) -> Result) {
+        for sink in &mut self.sinks {
+            let _ = f(sink.as_mut());
+        }
+    }
+
+    unsafe extern "efiapi" fn reset(
+        this: *mut SimpleTextOutputProtocol,
+        extended: Boolean,
+    ) -> Status {
+        let this = unsafe { Self::from_protocol(this) };
+        let status = unsafe { ((*this.original).reset)(this.original, extended) };
+        this.for_each_sink(|sink| sink.clear());
+        status
+    }
+
+    unsafe extern "efiapi" fn output_string(
+        this: *mut SimpleTextOutputProtocol,
+        string: *const Char16,
+    ) -> Status {
+        let this = unsafe { Self::from_protocol(this) };
+        let status = unsafe { ((*this.original).output_string)(this.original, string) };
+        let string = unsafe { CStr16::from_ptr(string.cast()) };
+        this.for_each_sink(|sink| sink.output_string(string));
+        status
+    }
+
+    unsafe extern "efiapi" fn test_string(
+        this: *mut SimpleTextOutputProtocol,
+        string: *const Char16,
+    ) -> Status {
+        let this = unsafe { Self::from_protocol(this) };
+        unsafe { ((*this.original).test_string)(this.original, string) }
+    }
+
+    unsafe extern "efiapi" fn query_mode(
+        this: *mut SimpleTextOutputProtocol,
+        mode: usize,
+        columns: *mut usize,
+        rows: *mut usize,
+    ) -> Status {
+        let this = unsafe { Self::from_protocol(this) };
+        unsafe { ((*this.original).query_mode)(this.original, mode, columns, rows) }
+    }
+
+    unsafe extern "efiapi" fn set_mode(this: *mut SimpleTextOutputProtocol, mode: usize) -> Status {
+        let this = unsafe { Self::from_protocol(this) };
+        unsafe { ((*this.original).set_mode)(this.original, mode) }
+    }
+
+    unsafe extern "efiapi" fn set_attribute(
+        this: *mut SimpleTextOutputProtocol,
+        attribute: usize,
+    ) -> Status {
+        let this = unsafe { Self::from_protocol(this) };
+        let status = unsafe { ((*this.original).set_attribute)(this.original, attribute) };
+        if status.is_success() {
+            let foreground = COLORS[attribute & 0x0f];
+            let background = COLORS[(attribute >> 4) & 0x07];
+            this.for_each_sink(|sink| sink.set_color(foreground, background));
+        }
+        status
+    }
+
+    unsafe extern "efiapi" fn clear_screen(this: *mut SimpleTextOutputProtocol) -> Status {
+        let this = unsafe { Self::from_protocol(this) };
+        let status = unsafe { ((*this.original).clear_screen)(this.original) };
+        this.for_each_sink(|sink| sink.clear());
+        status
+    }
+
+    unsafe extern "efiapi" fn set_cursor_position(
+        this: *mut SimpleTextOutputProtocol,
+        column: usize,
+        row: usize,
+    ) -> Status {
+        let this = unsafe { Self::from_protocol(this) };
+        let status = unsafe { ((*this.original).set_cursor_position)(this.original, column, row) };
+        if status.is_success() {
+            this.for_each_sink(|sink| sink.set_cursor_position(column, row));
+        }
+        status
+    }
+
+    unsafe extern "efiapi" fn enable_cursor(
+        this: *mut SimpleTextOutputProtocol,
+        visible: Boolean,
+    ) -> Status {
+        let this = unsafe { Self::from_protocol(this) };
+        let status = unsafe { ((*this.original).enable_cursor)(this.original, visible) };
+        this.for_each_sink(|sink| sink.enable_cursor(visible.into()));
+        status
+    }
+}
+
+impl Drop for OutputSplitter {
+    fn drop(&mut self) {
+        // SAFETY: the original protocol was valid when the splitter was
+        // installed.
+        unsafe {
+            system::swap_stdout(self.handle.as_ptr(), self.original);
+            // Errors are ignored since they can't be propagated from `drop`.
+            let _ = boot::reinstall_protocol_interface(
+                self.handle,
+                &SimpleTextOutputProtocol::GUID,
+                ptr::from_ref(&self.protocol).cast(),
+                self.original.cast(),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CString16, cstr16};
+
+    #[test]
+    fn test_write_utf8() {
+        let mut out = Vec::new();
+        write_utf8(cstr16!("aä€\r\n"), |bytes| {
+            out.extend_from_slice(bytes);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(out, "aä€\r\n".as_bytes());
+
+        // Long strings are written in multiple chunks.
+        let long = CString16::try_from("x".repeat(200).as_str()).unwrap();
+        let mut chunks = 0;
+        let mut len = 0;
+        write_utf8(&long, |bytes| {
+            chunks += 1;
+            len += bytes.len();
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!((chunks, len), (2, 200));
+    }
+}
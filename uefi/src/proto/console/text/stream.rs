@@ -0,0 +1,160 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Asynchronous key input.
+
+use super::{Input, Key};
+use crate::boot::{self, EventType, TimerTrigger, Tpl};
+use crate::{Event, Result, Status};
+use alloc::boxed::Box;
+use core::cell::UnsafeCell;
+use core::ffi::c_void;
+use core::fmt::{self, Debug, Formatter};
+use core::future::poll_fn;
+use core::ptr::NonNull;
+use core::task::{Context, Poll, Waker};
+
+/// Interval at which the key event is checked while a task waits for a key,
+/// in units of 100ns.
+const CHECK_INTERVAL: u64 = 10 * 10_000;
+
+/// Asynchronous access to the keys of an [`Input`].
+///
+/// [`next_key`] returns a future that resolves when a key is available, so
+/// that waiting for input can be combined with other futures, such as a
+/// [`Timeout`], instead of busy-polling [`Input::read_key`].
+///
+/// The wait-for-key event of the input protocol cannot wake a task by
+/// itself, so while a task waits for a key, a timer event checks the wait
+/// event every 10ms and wakes the task once it is signaled. The waker is
+/// woken from the timer's notification function at [`Tpl::CALLBACK`], so it
+/// must be safe to wake at that TPL.
+///
+/// Dropping the stream closes the timer event.
+///
+/// [`next_key`]: Self::next_key
+/// [`Timeout`]: crate::time::Timeout
+pub struct KeyStream<'a> {
+    input: &'a mut Input,
+    timer: Event,
+    shared: Box<UnsafeCell<Shared>>,
+}
+
+/// State shared by a [`KeyStream`] and its timer's notification function.
+/// Only accessed at [`Tpl::CALLBACK`].
+#[derive(Debug)]
+struct Shared {
+    key_event: Event,
+    waker: Option<Waker>,
+}
+
+impl<'a> KeyStream<'a> {
+    /// Creates a stream of the keys read from `input`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::UNSUPPORTED`]: `input` has no wait-for-key event.
+    /// * [`Status::OUT_OF_RESOURCES`]: the timer event could not be created.
+    pub fn new(input: &'a mut Input) -> Result<Self> {
+        let key_event = input.wait_for_key_event().ok_or(Status::UNSUPPORTED)?;
+        let shared = Box::new(UnsafeCell::new(Shared {
+            key_event,
+            waker: None,
+        }));
+        let context = NonNull::from(&*shared).cast();
+        // SAFETY: the notification function only touches the shared state,
+        // which lives until the event is closed.
+        let timer = unsafe {
+            boot::create_event(
+                EventType::TIMER | EventType::NOTIFY_SIGNAL,
+                Tpl::CALLBACK,
+                Some(check_notify),
+                Some(context),
+            )?
+        };
+        if let Err(err) = boot::set_timer(&timer, TimerTrigger::Periodic(CHECK_INTERVAL)) {
+            let _ = boot::close_event(timer);
+            return Err(err);
+        }
+        Ok(Self {
+            input,
+            timer,
+            shared,
+        })
+    }
+
+    /// Waits for the next key.
+    ///
+    /// # Errors
+    ///
+    /// See [`Input::read_key`].
+    pub async fn next_key(&mut self) -> Result<Key> {
+        poll_fn(|cx| self.poll_next_key(cx)).await
+    }
+
+    /// Polls for the next key, registering the waker of `cx` to be woken
+    /// when a key is available. This is the building block of
+    /// [`next_key`], and can be used to implement `Stream` traits of async
+    /// runtimes.
+    ///
+    /// # Errors
+    ///
+    /// See [`Input::read_key`].
+    ///
+    /// [`next_key`]: Self::next_key
+    pub fn poll_next_key(&mut self, cx: &mut Context<'_>) -> Poll<Result<Key>> {
+        match self.input.read_key() {
+            Ok(Some(key)) => return Poll::Ready(Ok(key)),
+            Ok(None) => {}
+            Err(err) => return Poll::Ready(Err(err)),
+        }
+        // SAFETY: raising to the notification function's TPL keeps it from
+        // running while the shared state is borrowed.
+        let _tpl = unsafe { boot::raise_tpl(Tpl::CALLBACK) };
+        // SAFETY: see above.
+        let waker = unsafe { &mut (*self.shared.get()).waker };
+        match waker {
+            Some(waker) if waker.will_wake(cx.waker()) => {}
+            _ => *waker = Some(cx.waker().clone()),
+        }
+        // A key arriving after `read_key` keeps the key event signaled, so
+        // the next timer tick wakes the task.
+        Poll::Pending
+    }
+}
+
+impl Drop for KeyStream<'_> {
+    fn drop(&mut self) {
+        // SAFETY: the event is not used again.
+        let timer = unsafe { self.timer.unsafe_clone() };
+        let _ = boot::close_event(timer);
+    }
+}
+
+impl Debug for KeyStream<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyStream")
+            .field("input", &self.input)
+            .field("timer", &self.timer)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Notification function of the stream's timer event.
+unsafe extern "efiapi" fn check_notify(_event: Event, context: Option<NonNull<c_void>>) {
+    if let Some(context) = context {
+        // SAFETY: the context is the shared state, which outlives the event.
+        // The stream raises the TPL while borrowing it, so this has
+        // exclusive access.
+        let shared = unsafe { &mut *context.cast::<UnsafeCell<Shared>>().as_ref().get() };
+        if shared.waker.is_none() {
+            return;
+        }
+        // SAFETY: the key event stays valid while the input is borrowed.
+        let key_event = unsafe { shared.key_event.unsafe_clone() };
+        if matches!(boot::check_event(key_event), Ok(true)) {
+            if let Some(waker) = shared.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
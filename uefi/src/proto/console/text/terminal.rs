@@ -0,0 +1,758 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! ANSI escape sequences on top of [`Output`].
+//!
+//! Text user interfaces and tools written for serial terminals control the
+//! screen with ANSI (VT100) escape sequences embedded in the text. The
+//! firmware console doesn't understand them, so [`Terminal`] interprets the
+//! common sequences and translates them into calls to [`Output`].
+
+use super::{BufferedOutput, Color, Output};
+use crate::{Result, Status};
+use core::fmt;
+
+/// Maximum number of parameters of a control sequence. Further parameters
+/// are ignored.
+const MAX_PARAMS: usize = 16;
+
+/// Tab stops are every this many columns.
+const TAB_WIDTH: usize = 8;
+
+/// ANSI colors in the order of their SGR codes.
+const ANSI_COLORS: [Color; 8] = [
+    Color::Black,
+    Color::Red,
+    Color::Green,
+    Color::Brown,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::LightGray,
+];
+
+/// Bright variants of [`ANSI_COLORS`].
+const ANSI_BRIGHT_COLORS: [Color; 8] = [
+    Color::DarkGray,
+    Color::LightRed,
+    Color::LightGreen,
+    Color::Yellow,
+    Color::LightBlue,
+    Color::LightMagenta,
+    Color::LightCyan,
+    Color::White,
+];
+
+/// Adapter interpreting ANSI escape sequences in text written to an
+/// [`Output`].
+///
+/// Text is written with [`write_bytes`] or through [`fmt::Write`]. The
+/// following sequences are supported; all others are dropped:
+///
+/// * Cursor movement: `CSI n A` (up), `B` (down), `C` (forward), `D`
+///   (back), `E` (next line), `F` (previous line), `G` (column), `d` (row),
+///   `H` and `f` (position).
+/// * Erasing: `CSI n J` (screen) and `CSI n K` (line).
+/// * Colors: `CSI n m` (SGR) with the 8 standard colors, their bright
+///   variants, bold, and reverse video. Bold text is shown in the bright
+///   variant of its color. Colors of the 256-color and true color modes
+///   are approximated by the nearest of the 16 console colors.
+/// * Cursor visibility: `CSI ?25h` and `CSI ?25l`.
+/// * Saving and restoring the cursor: `CSI s`, `CSI u`, `ESC 7`, `ESC 8`.
+/// * Reset: `ESC c`.
+///
+/// As with [`Output`], a line feed also returns the cursor to the start of
+/// the line. Tabs move the cursor to the next multiple of 8 columns.
+///
+/// Output is buffered; it is flushed when the cursor is queried or moved,
+/// when [`flush`] is called, and when the terminal is dropped.
+///
+/// ```no_run
+/// use core::fmt::Write;
+/// use uefi::proto::console::text::{Output, Terminal};
+///
+/// # fn example(output: &mut Output) -> uefi::Result {
+/// let mut terminal = Terminal::new(output);
+/// write!(terminal, "\x1b[2J\x1b[1;1H\x1b[1;32mOK\x1b[0m").unwrap();
+/// terminal.flush()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`write_bytes`]: Self::write_bytes
+/// [`flush`]: Self::flush
+#[derive(Debug)]
+pub struct Terminal<'out> {
+    out: BufferedOutput<'out>,
+    parser: Parser,
+    utf8: Utf8Decoder,
+    style: Style,
+    /// Whether `style` changed since it was last applied.
+    style_changed: bool,
+    saved_cursor: Option<(usize, usize)>,
+}
+
+impl<'out> Terminal<'out> {
+    /// Creates a terminal writing to `output`.
+    #[must_use]
+    pub const fn new(output: &'out mut Output) -> Self {
+        Self {
+            out: BufferedOutput::new(output),
+            parser: Parser::new(),
+            utf8: Utf8Decoder::new(),
+            style: Style::DEFAULT,
+            style_changed: false,
+            saved_cursor: None,
+        }
+    }
+
+    /// Writes UTF-8 encoded text. Characters may be split across calls;
+    /// invalid UTF-8 is replaced with `?`.
+    pub fn write_bytes(&mut self, bytes: &[u8]) -> Result {
+        for byte in bytes {
+            if let Some(c) = self.utf8.push(*byte) {
+                self.write_char(c)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes a character, interpreting escape sequences.
+    fn write_char(&mut self, c: char) -> Result {
+        match self.parser.advance(c) {
+            Some(Action::Print(c)) => self.print(c),
+            Some(Action::Escape(c)) => self.escape(c),
+            Some(Action::Csi(csi)) => self.control_sequence(&csi),
+            None => Ok(()),
+        }
+    }
+
+    /// Writes all buffered text to the device.
+    pub fn flush(&mut self) -> Result {
+        self.out.flush()
+    }
+
+    fn print(&mut self, c: char) -> Result {
+        if self.style_changed {
+            let (foreground, background) = self.style.colors();
+            self.out.set_color(foreground, background)?;
+            self.style_changed = false;
+        }
+        match c {
+            '\n' => {
+                self.out.push(u16::from(b'\r'))?;
+                self.out.push(u16::from(b'\n'))
+            }
+            '\r' | '\x08' => self.out.push(c as u16),
+            '\t' => {
+                let (column, _) = self.cursor()?;
+                for _ in column % TAB_WIDTH..TAB_WIDTH {
+                    self.out.push(u16::from(b' '))?;
+                }
+                Ok(())
+            }
+            // Other control characters, such as the bell, are dropped.
+            c if c < ' ' || c == '\x7f' => Ok(()),
+            c => {
+                let c = u16::try_from(u32::from(c))
+                    .ok()
+                    .filter(|c| !(0xd800..0xe000).contains(c))
+                    .unwrap_or(u16::from(b'?'));
+                self.out.push(c)
+            }
+        }
+    }
+
+    fn escape(&mut self, c: char) -> Result {
+        match c {
+            '7' => {
+                self.saved_cursor = Some(self.cursor()?);
+                Ok(())
+            }
+            '8' => self.restore_cursor(),
+            'c' => {
+                self.style = Style::DEFAULT;
+                self.style_changed = false;
+                let (foreground, background) = self.style.colors();
+                self.out.set_color(foreground, background)?;
+                self.saved_cursor = None;
+                let output = self.out.output()?;
+                output.clear()?;
+                output.enable_cursor(true).or_else(ignore_unsupported)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn control_sequence(&mut self, csi: &ControlSequence) -> Result {
+        let n = csi.param(0, 1).max(1);
+        match (csi.private, csi.command) {
+            (false, 'A') => self.move_cursor(0, -n),
+            (false, 'B') => self.move_cursor(0, n),
+            (false, 'C') => self.move_cursor(n, 0),
+            (false, 'D') => self.move_cursor(-n, 0),
+            (false, 'E' | 'F') => {
+                let (_, row) = self.cursor()?;
+                let dy = if csi.command == 'E' { n } else { -n };
+                self.set_cursor(0, row as isize + dy)
+            }
+            (false, 'G') => {
+                let (_, row) = self.cursor()?;
+                self.set_cursor(n - 1, row as isize)
+            }
+            (false, 'd') => {
+                let (column, _) = self.cursor()?;
+                self.set_cursor(column as isize, n - 1)
+            }
+            (false, 'H' | 'f') => {
+                let row = csi.param(0, 1).max(1);
+                let column = csi.param(1, 1).max(1);
+                self.set_cursor(column - 1, row - 1)
+            }
+            (false, 'J') => self.erase_screen(csi.param(0, 0)),
+            (false, 'K') => {
+                let (column, row) = self.cursor()?;
+                let (columns, _) = self.size()?;
+                match csi.param(0, 0) {
+                    0 => self.erase(row, column, columns)?,
+                    1 => self.erase(row, 0, column + 1)?,
+                    2 => self.erase(row, 0, columns)?,
+                    _ => {}
+                }
+                self.out.set_cursor_position(column, row)
+            }
+            (false, 'm') => {
+                self.style.apply(csi.params());
+                self.style_changed = true;
+                Ok(())
+            }
+            (false, 's') => {
+                self.saved_cursor = Some(self.cursor()?);
+                Ok(())
+            }
+            (false, 'u') => self.restore_cursor(),
+            (true, 'h' | 'l') if csi.param(0, 0) == 25 => self
+                .out
+                .output()?
+                .enable_cursor(csi.command == 'h')
+                .or_else(ignore_unsupported),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the cursor position, after writing the buffered text.
+    fn cursor(&mut self) -> Result<(usize, usize)> {
+        Ok(self.out.output()?.cursor_position())
+    }
+
+    /// Returns the size of the screen as `(columns, rows)`.
+    fn size(&mut self) -> Result<(usize, usize)> {
+        let mode = self
+            .out
+            .output()?
+            .current_mode()?
+            .ok_or(Status::UNSUPPORTED)?;
+        Ok((mode.columns(), mode.rows()))
+    }
+
+    /// Moves the cursor relative to its position, stopping at the edges of
+    /// the screen.
+    fn move_cursor(&mut self, dx: isize, dy: isize) -> Result {
+        let (column, row) = self.cursor()?;
+        self.set_cursor(column as isize + dx, row as isize + dy)
+    }
+
+    /// Moves the cursor, clamping the position to the screen.
+    fn set_cursor(&mut self, column: isize, row: isize) -> Result {
+        let (columns, rows) = self.size()?;
+        let column = (column.max(0) as usize).min(columns.saturating_sub(1));
+        let row = (row.max(0) as usize).min(rows.saturating_sub(1));
+        self.out.set_cursor_position(column, row)
+    }
+
+    fn restore_cursor(&mut self) -> Result {
+        match self.saved_cursor {
+            Some((column, row)) => self.out.set_cursor_position(column, row),
+            None => self.set_cursor(0, 0),
+        }
+    }
+
+    /// Erases the columns `start..end` of `row` with spaces in the current
+    /// background color. The cursor is left anywhere.
+    fn erase(&mut self, row: usize, start: usize, end: usize) -> Result {
+        let (columns, rows) = self.size()?;
+        let mut end = end.min(columns);
+        if row + 1 == rows {
+            // Writing the bottom right cell would scroll the screen.
+            end = end.min(columns - 1);
+        }
+        if start >= end {
+            return Ok(());
+        }
+        let (foreground, background) = self.style.colors();
+        self.out.set_color(foreground, background)?;
+        self.style_changed = false;
+        self.out.set_cursor_position(start, row)?;
+        for _ in start..end {
+            self.out.push(u16::from(b' '))?;
+        }
+        Ok(())
+    }
+
+    /// Erases part of the screen: 0 from the cursor to the end, 1 from the
+    /// start to the cursor, 2 and 3 everything. The cursor doesn't move.
+    fn erase_screen(&mut self, mode: isize) -> Result {
+        let (column, row) = self.cursor()?;
+        let (columns, rows) = self.size()?;
+        match mode {
+            0 => {
+                self.erase(row, column, columns)?;
+                for row in row + 1..rows {
+                    self.erase(row, 0, columns)?;
+                }
+            }
+            1 => {
+                for row in 0..row {
+                    self.erase(row, 0, columns)?;
+                }
+                self.erase(row, 0, column + 1)?;
+            }
+            2 | 3 => {
+                let (foreground, background) = self.style.colors();
+                self.out.set_color(foreground, background)?;
+                self.style_changed = false;
+                self.out.output()?.clear()?;
+            }
+            _ => {}
+        }
+        self.out.set_cursor_position(column, row)
+    }
+}
+
+impl fmt::Write for Terminal<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            self.write_char(c).map_err(|_| fmt::Error)?;
+        }
+        Ok(())
+    }
+}
+
+/// Treats [`Status::UNSUPPORTED`] as success, for optional features of the
+/// console.
+fn ignore_unsupported(err: crate::Error) -> Result {
+    if err.status() == Status::UNSUPPORTED {
+        Ok(())
+    } else {
+        Err(err)
+    }
+}
+
+/// Incremental UTF-8 decoder.
+#[derive(Debug)]
+struct Utf8Decoder {
+    buf: [u8; 4],
+    len: usize,
+    /// Length of the character being decoded.
+    expected: usize,
+}
+
+impl Utf8Decoder {
+    const fn new() -> Self {
+        Self {
+            buf: [0; 4],
+            len: 0,
+            expected: 0,
+        }
+    }
+
+    /// Adds a byte, returning the decoded character once it is complete.
+    fn push(&mut self, byte: u8) -> Option<char> {
+        if self.len > 0 && byte & 0xc0 != 0x80 {
+            // The character was cut off. The byte starts the next one, which
+            // is lost; this only happens with invalid input.
+            self.len = 0;
+            return Some('?');
+        }
+        if self.len == 0 {
+            self.expected = match byte {
+                0x00..=0x7f => return Some(char::from(byte)),
+                0xc2..=0xdf => 2,
+                0xe0..=0xef => 3,
+                0xf0..=0xf4 => 4,
+                _ => return Some('?'),
+            };
+        }
+        self.buf[self.len] = byte;
+        self.len += 1;
+        if self.len < self.expected {
+            return None;
+        }
+        self.len = 0;
+        let c = core::str::from_utf8(&self.buf[..self.expected])
+            .ok()
+            .and_then(|s| s.chars().next())
+            .unwrap_or('?');
+        Some(c)
+    }
+}
+
+/// A complete control sequence: `ESC [`, optionally `?`, parameters
+/// separated by `;` or `:`, and a final character.
+#[derive(Debug, Eq, PartialEq)]
+struct ControlSequence {
+    params: [u16; MAX_PARAMS],
+    len: usize,
+    /// Whether the sequence starts with `?`.
+    private: bool,
+    command: char,
+}
+
+impl ControlSequence {
+    fn params(&self) -> &[u16] {
+        &self.params[..self.len]
+    }
+
+    /// Returns parameter `i`, or `default` if it is missing or zero.
+    fn param(&self, i: usize, default: isize) -> isize {
+        match self.params().get(i) {
+            Some(0) | None => default,
+            Some(value) => *value as isize,
+        }
+    }
+}
+
+/// Action taken by the terminal for a character.
+#[derive(Debug, Eq, PartialEq)]
+enum Action {
+    /// Print a character or execute a control character.
+    Print(char),
+    /// An escape sequence `ESC c`.
+    Escape(char),
+    /// A control sequence.
+    Csi(ControlSequence),
+}
+
+#[derive(Debug, Eq, PartialEq)]
+enum State {
+    Ground,
+    Escape,
+    Csi,
+    /// Operating system command or other string, ignored up to BEL or ST.
+    String,
+    /// `ESC` in a string, possibly starting the string terminator.
+    StringEscape,
+}
+
+/// State machine splitting text into characters and escape sequences.
+#[derive(Debug)]
+struct Parser {
+    state: State,
+    csi: ControlSequence,
+}
+
+impl Parser {
+    const fn new() -> Self {
+        Self {
+            state: State::Ground,
+            csi: ControlSequence {
+                params: [0; MAX_PARAMS],
+                len: 0,
+                private: false,
+                command: '\0',
+            },
+        }
+    }
+
+    fn advance(&mut self, c: char) -> Option<Action> {
+        match self.state {
+            State::Ground if c == '\x1b' => {
+                self.state = State::Escape;
+                None
+            }
+            State::Ground => Some(Action::Print(c)),
+            State::Escape => {
+                self.state = State::Ground;
+                match c {
+                    '[' => {
+                        self.state = State::Csi;
+                        self.csi.params = [0; MAX_PARAMS];
+                        self.csi.len = 0;
+                        self.csi.private = false;
+                        None
+                    }
+                    ']' | 'P' | 'X' | '^' | '_' => {
+                        self.state = State::String;
+                        None
+                    }
+                    c => Some(Action::Escape(c)),
+                }
+            }
+            State::Csi => match c {
+                '0'..='9' => {
+                    if self.csi.len == 0 {
+                        self.csi.len = 1;
+                    }
+                    if let Some(param) = self.csi.params.get_mut(self.csi.len - 1) {
+                        let digit = c as u16 - u16::from(b'0');
+                        *param = param.saturating_mul(10).saturating_add(digit);
+                    }
+                    None
+                }
+                ';' | ':' => {
+                    self.csi.len = (self.csi.len.max(1) + 1).min(MAX_PARAMS + 1);
+                    None
+                }
+                '?' if self.csi.len == 0 => {
+                    self.csi.private = true;
+                    None
+                }
+                '\x40'..='\x7e' => {
+                    self.state = State::Ground;
+                    self.csi.len = self.csi.len.min(MAX_PARAMS);
+                    self.csi.command = c;
+                    Some(Action::Csi(ControlSequence { ..self.csi }))
+                }
+                // Intermediate bytes are not supported; drop the sequence.
+                _ => {
+                    self.state = State::Ground;
+                    None
+                }
+            },
+            State::String => {
+                match c {
+                    '\x07' => self.state = State::Ground,
+                    '\x1b' => self.state = State::StringEscape,
+                    _ => {}
+                }
+                None
+            }
+            State::StringEscape => {
+                self.state = if c == '\\' {
+                    State::Ground
+                } else {
+                    State::String
+                };
+                None
+            }
+        }
+    }
+}
+
+/// Graphic rendition set with SGR sequences.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Style {
+    /// Foreground color, 0-15.
+    foreground: u8,
+    /// Background color, 0-15. Only 0-7 can be shown.
+    background: u8,
+    bold: bool,
+    reverse: bool,
+}
+
+impl Style {
+    const DEFAULT: Self = Self {
+        foreground: 7,
+        background: 0,
+        bold: false,
+        reverse: false,
+    };
+
+    /// Applies the parameters of an SGR sequence.
+    fn apply(&mut self, params: &[u16]) {
+        if params.is_empty() {
+            *self = Self::DEFAULT;
+        }
+        let mut params = params.iter().copied();
+        while let Some(param) = params.next() {
+            match param {
+                0 => *self = Self::DEFAULT,
+                1 => self.bold = true,
+                22 => self.bold = false,
+                7 => self.reverse = true,
+                27 => self.reverse = false,
+                30..=37 => self.foreground = (param - 30) as u8,
+                39 => self.foreground = Self::DEFAULT.foreground,
+                40..=47 => self.background = (param - 40) as u8,
+                49 => self.background = Self::DEFAULT.background,
+                90..=97 => self.foreground = (param - 90) as u8 + 8,
+                100..=107 => self.background = (param - 100) as u8 + 8,
+                38 | 48 => {
+                    let color = match params.next() {
+                        Some(5) => params.next().map(indexed_color),
+                        Some(2) => {
+                            let mut rgb = [0; 3];
+                            for c in &mut rgb {
+                                *c = params.next().unwrap_or(0);
+                            }
+                            Some(rgb_color(rgb))
+                        }
+                        _ => None,
+                    };
+                    match (param, color) {
+                        (38, Some(color)) => self.foreground = color,
+                        (48, Some(color)) => self.background = color,
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Returns the console colors for the style.
+    fn colors(&self) -> (Color, Color) {
+        let mut foreground = self.foreground;
+        if self.bold {
+            foreground |= 8;
+        }
+        let (foreground, background) = if self.reverse {
+            (self.background, foreground)
+        } else {
+            (foreground, self.background)
+        };
+        (ansi_color(foreground), ansi_color(background & 7))
+    }
+}
+
+/// Converts an ANSI color index, 0-15, to a [`Color`].
+fn ansi_color(index: u8) -> Color {
+    let index = usize::from(index & 15);
+    if index < 8 {
+        ANSI_COLORS[index]
+    } else {
+        ANSI_BRIGHT_COLORS[index - 8]
+    }
+}
+
+/// Approximates a color of the 256-color palette with an ANSI color index.
+fn indexed_color(index: u16) -> u8 {
+    match index {
+        0..=15 => index as u8,
+        // 6x6x6 color cube.
+        16..=231 => {
+            let i = index - 16;
+            let level = |c: u16| [0, 95, 135, 175, 215, 255][usize::from(c)];
+            rgb_color([level(i / 36), level(i / 6 % 6), level(i % 6)])
+        }
+        // Grayscale ramp.
+        232..=255 => {
+            let gray = 8 + (index - 232) * 10;
+            rgb_color([gray; 3])
+        }
+        _ => Style::DEFAULT.foreground,
+    }
+}
+
+/// Approximates an RGB color with an ANSI color index.
+fn rgb_color(rgb: [u16; 3]) -> u8 {
+    let [r, g, b] = rgb.map(|c| c.min(255));
+    let max = r.max(g).max(b);
+    if max < 64 {
+        return 0;
+    }
+    // Components of at least half the brightest one are part of the hue.
+    let on = |c: u16| c * 2 >= max;
+    let hue = u8::from(on(r)) | (u8::from(on(g)) << 1) | (u8::from(on(b)) << 2);
+    let gray = on(r) && on(g) && on(b);
+    match (gray, max) {
+        (true, 0..=127) => 8,
+        (true, 128..=223) => 7,
+        (true, _) => 15,
+        (false, 0..=191) => hue,
+        (false, _) => hue | 8,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> impl Iterator<Item = Action> + use<'_> {
+        let mut parser = Parser::new();
+        s.chars().filter_map(move |c| parser.advance(c))
+    }
+
+    fn csi(params: &[u16], private: bool, command: char) -> Action {
+        let mut csi = ControlSequence {
+            params: [0; MAX_PARAMS],
+            len: params.len(),
+            private,
+            command,
+        };
+        csi.params[..params.len()].copy_from_slice(params);
+        Action::Csi(csi)
+    }
+
+    #[test]
+    fn test_parser() {
+        let mut actions = parse("a\x1b[1;31mb\x1b[H\x1b[?25l\x1b7\x1b]0;title\x07c\x1b]2;x\x1b\\d");
+        assert_eq!(actions.next(), Some(Action::Print('a')));
+        assert_eq!(actions.next(), Some(csi(&[1, 31], false, 'm')));
+        assert_eq!(actions.next(), Some(Action::Print('b')));
+        assert_eq!(actions.next(), Some(csi(&[], false, 'H')));
+        assert_eq!(actions.next(), Some(csi(&[25], true, 'l')));
+        assert_eq!(actions.next(), Some(Action::Escape('7')));
+        assert_eq!(actions.next(), Some(Action::Print('c')));
+        assert_eq!(actions.next(), Some(Action::Print('d')));
+        assert_eq!(actions.next(), None);
+
+        // Empty parameters count as zero.
+        let mut actions = parse("\x1b[;5H\x1b[5;H");
+        assert_eq!(actions.next(), Some(csi(&[0, 5], false, 'H')));
+        assert_eq!(actions.next(), Some(csi(&[5, 0], false, 'H')));
+    }
+
+    #[test]
+    fn test_param() {
+        let Some(Action::Csi(csi)) = parse("\x1b[0;12;99999A").next() else {
+            panic!("not a control sequence");
+        };
+        assert_eq!(csi.param(0, 1), 1);
+        assert_eq!(csi.param(1, 1), 12);
+        // Large values saturate.
+        assert_eq!(csi.param(2, 1), 65535);
+        assert_eq!(csi.param(3, 1), 1);
+    }
+
+    #[test]
+    fn test_style() {
+        let mut style = Style::DEFAULT;
+        style.apply(&[1, 31, 44]);
+        assert!(matches!(style.colors(), (Color::LightRed, Color::Blue)));
+        style.apply(&[22, 7]);
+        assert!(matches!(style.colors(), (Color::Blue, Color::Red)));
+        style.apply(&[]);
+        assert_eq!(style, Style::DEFAULT);
+
+        // Bright backgrounds are shown in the normal variant.
+        style.apply(&[97, 103]);
+        assert!(matches!(style.colors(), (Color::White, Color::Brown)));
+
+        style.apply(&[38, 5, 196, 48, 2, 0, 0, 128]);
+        assert!(matches!(style.colors(), (Color::LightRed, Color::Blue)));
+        style.apply(&[38, 5, 244]);
+        assert!(matches!(style.colors().0, Color::LightGray));
+    }
+
+    #[test]
+    fn test_utf8() {
+        let mut decoder = Utf8Decoder::new();
+        let decode = |decoder: &mut Utf8Decoder, bytes: &[u8]| {
+            let mut chars = ['\0'; 4];
+            let mut n = 0;
+            for c in bytes.iter().filter_map(|b| decoder.push(*b)) {
+                chars[n] = c;
+                n += 1;
+            }
+            (chars, n)
+        };
+        assert_eq!(
+            decode(&mut decoder, "aé".as_bytes()),
+            (['a', 'é', '\0', '\0'], 2)
+        );
+        // A character split across writes.
+        assert_eq!(decode(&mut decoder, &[0xe2, 0x82]).1, 0);
+        assert_eq!(decode(&mut decoder, &[0xac]), (['€', '\0', '\0', '\0'], 1));
+        assert_eq!(decode(&mut decoder, &[0xff, 0xe2, b'x']).0[..2], ['?', '?']);
+    }
+}
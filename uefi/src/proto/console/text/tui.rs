@@ -0,0 +1,470 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Building blocks for accessible text user interfaces.
+//!
+//! * [`FocusOrder`] moves the focus between the interactive elements of a
+//!   screen with the keyboard alone, following the usual conventions: Tab
+//!   and Down move to the next element, Shift+Tab and Up to the previous
+//!   one, Home and End to the first and last one.
+//! * [`Theme`] groups the colors of a user interface, so that a high
+//!   contrast theme such as [`Theme::HIGH_CONTRAST`] can be selected at
+//!   runtime. [`Style::contrast_ratio`] checks colors against the WCAG
+//!   contrast requirements.
+//! * [`KeyRepeat`] slows down the key repeat of the firmware, for users who
+//!   cannot release a key quickly.
+
+use super::{Color, Key, KeyStroke, ScanCode};
+use crate::Char16;
+use core::time::Duration;
+
+/// The Tab key.
+// SAFETY: tab is valid UCS-2.
+const TAB: Key = Key::Printable(unsafe { Char16::from_u16_unchecked(0x09) });
+
+/// A focus movement requested with the keyboard.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Navigation {
+    /// Move to the next element.
+    Next,
+    /// Move to the previous element.
+    Previous,
+    /// Move to the first element.
+    First,
+    /// Move to the last element.
+    Last,
+}
+
+impl Navigation {
+    /// Returns the movement requested by `key`, if any: Tab and Down move to
+    /// the next element, Up to the previous one, and Home and End to the
+    /// first and last one.
+    ///
+    /// [`Input`] does not report Shift+Tab; use [`Self::from_key_stroke`]
+    /// with [`InputEx`] to support it.
+    ///
+    /// [`Input`]: super::Input
+    /// [`InputEx`]: super::InputEx
+    #[must_use]
+    pub const fn from_key(key: Key) -> Option<Self> {
+        match key {
+            TAB | Key::Special(ScanCode::DOWN) => Some(Self::Next),
+            Key::Special(ScanCode::UP) => Some(Self::Previous),
+            Key::Special(ScanCode::HOME) => Some(Self::First),
+            Key::Special(ScanCode::END) => Some(Self::Last),
+            _ => None,
+        }
+    }
+
+    /// Like [`Self::from_key`], but Shift+Tab moves to the previous element.
+    #[must_use]
+    pub fn from_key_stroke(stroke: KeyStroke) -> Option<Self> {
+        if stroke.key == TAB && stroke.shift() {
+            Some(Self::Previous)
+        } else {
+            Self::from_key(stroke.key)
+        }
+    }
+}
+
+/// Tracks which of a fixed number of elements has the keyboard focus.
+///
+/// Elements are identified by their index, and the focus order is the order
+/// of the indices. Elements that currently cannot take the focus, e.g.
+/// because they are disabled or hidden, are skipped by passing a predicate
+/// to [`navigate`].
+///
+/// ```
+/// use uefi::proto::console::text::{FocusOrder, Navigation};
+///
+/// // Three buttons, the second one is disabled.
+/// let enabled = [true, false, true];
+/// let mut focus = FocusOrder::new(enabled.len());
+/// focus.navigate(Navigation::First, |i| enabled[i]);
+/// focus.navigate(Navigation::Next, |i| enabled[i]);
+/// assert_eq!(focus.focused(), Some(2));
+/// ```
+///
+/// [`navigate`]: Self::navigate
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FocusOrder {
+    len: usize,
+    focused: Option<usize>,
+    wrap: bool,
+}
+
+impl FocusOrder {
+    /// Creates a focus order for `len` elements. Initially no element has
+    /// the focus, and moving past the last element wraps around to the
+    /// first one.
+    #[must_use]
+    pub const fn new(len: usize) -> Self {
+        Self {
+            len,
+            focused: None,
+            wrap: true,
+        }
+    }
+
+    /// Sets whether moving past the last or before the first element wraps
+    /// around. Without wrapping, the focus stops at the ends of the list.
+    pub const fn set_wrap(&mut self, wrap: bool) {
+        self.wrap = wrap;
+    }
+
+    /// Returns the number of elements.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether there are no elements.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the index of the focused element, if any.
+    #[must_use]
+    pub const fn focused(&self) -> Option<usize> {
+        self.focused
+    }
+
+    /// Returns whether the element at `index` has the focus.
+    #[must_use]
+    pub fn is_focused(&self, index: usize) -> bool {
+        self.focused == Some(index)
+    }
+
+    /// Moves the focus to the element at `index`, or removes the focus if
+    /// `index` is `None` or out of range.
+    pub fn set_focus(&mut self, index: Option<usize>) {
+        self.focused = index.filter(|i| *i < self.len);
+    }
+
+    /// Moves the focus as requested, skipping elements for which
+    /// `can_focus` returns `false`. Returns whether the focus changed.
+    ///
+    /// If no element has the focus, [`Navigation::Next`] focuses the first
+    /// element and [`Navigation::Previous`] the last one.
+    pub fn navigate(&mut self, navigation: Navigation, can_focus: impl Fn(usize) -> bool) -> bool {
+        let len = self.len;
+        let target = match (navigation, self.focused) {
+            (Navigation::First, _) | (Navigation::Next, None) => (0..len).find(|i| can_focus(*i)),
+            (Navigation::Last, _) | (Navigation::Previous, None) => {
+                (0..len).rev().find(|i| can_focus(*i))
+            }
+            (Navigation::Next, Some(current)) => {
+                let wrapped = if self.wrap { 0..current } else { 0..0 };
+                (current + 1..len).chain(wrapped).find(|i| can_focus(*i))
+            }
+            (Navigation::Previous, Some(current)) => {
+                let wrapped = if self.wrap { current + 1..len } else { 0..0 };
+                (0..current)
+                    .rev()
+                    .chain(wrapped.rev())
+                    .find(|i| can_focus(*i))
+            }
+        };
+        match target {
+            Some(index) if self.focused != Some(index) => {
+                self.focused = Some(index);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Moves the focus if `key` is a navigation key, see
+    /// [`Navigation::from_key`]. Returns whether the key was handled.
+    pub fn handle_key(&mut self, key: Key, can_focus: impl Fn(usize) -> bool) -> bool {
+        Navigation::from_key(key).is_some_and(|navigation| {
+            self.navigate(navigation, can_focus);
+            true
+        })
+    }
+}
+
+/// Foreground and background color of text.
+#[derive(Clone, Copy, Debug)]
+pub struct Style {
+    /// Color of the text.
+    pub foreground: Color,
+    /// Color behind the text. Only the first 8 colors of [`Color`] can be
+    /// used as background colors.
+    pub background: Color,
+}
+
+impl Style {
+    /// Creates a style.
+    #[must_use]
+    pub const fn new(foreground: Color, background: Color) -> Self {
+        Self {
+            foreground,
+            background,
+        }
+    }
+
+    /// Returns the contrast ratio between the foreground and background
+    /// colors, from 1 (no contrast) to 21 (black and white), as defined by
+    /// WCAG 2. Normal text needs a ratio of at least 4.5 for level AA and 7
+    /// for level AAA.
+    ///
+    /// The ratio is computed for the standard VGA palette; the actual colors
+    /// depend on the console.
+    #[must_use]
+    pub fn contrast_ratio(&self) -> f32 {
+        let a = luminance(self.foreground);
+        let b = luminance(self.background);
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+}
+
+/// Relative luminance of a color in the standard VGA palette.
+const fn luminance(color: Color) -> f32 {
+    const LUMINANCE: [f32; 16] = [
+        0.0, 0.029, 0.2875, 0.3165, 0.0855, 0.1145, 0.1504, 0.402, 0.0908, 0.1565, 0.7411, 0.8067,
+        0.2841, 0.3498, 0.9344, 1.0,
+    ];
+    LUMINANCE[color as usize]
+}
+
+/// The colors of a user interface.
+///
+/// Drawing code should take its colors from a theme rather than using fixed
+/// colors, so that the user can switch to [`Theme::HIGH_CONTRAST`], e.g.
+/// based on a setup option.
+#[derive(Clone, Copy, Debug)]
+pub struct Theme {
+    /// Regular text.
+    pub normal: Style,
+    /// The element with the keyboard focus.
+    pub focused: Style,
+    /// Elements that cannot be used.
+    pub disabled: Style,
+    /// Titles and headings.
+    pub title: Style,
+}
+
+impl Theme {
+    /// The colors commonly used by firmware setup screens.
+    pub const DEFAULT: Self = Self {
+        normal: Style::new(Color::LightGray, Color::Black),
+        focused: Style::new(Color::White, Color::Blue),
+        disabled: Style::new(Color::DarkGray, Color::Black),
+        title: Style::new(Color::Yellow, Color::Black),
+    };
+
+    /// A theme in which all text meets the WCAG level AAA contrast ratio.
+    /// Disabled elements are not dimmed, so they must be marked in another
+    /// way, e.g. with a label.
+    pub const HIGH_CONTRAST: Self = Self {
+        normal: Style::new(Color::White, Color::Black),
+        focused: Style::new(Color::Black, Color::LightGray),
+        disabled: Style::new(Color::LightGray, Color::Black),
+        title: Style::new(Color::Yellow, Color::Black),
+    };
+
+    /// Returns the built-in theme called `name`: `"default"` or
+    /// `"high-contrast"`.
+    #[must_use]
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(Self::DEFAULT),
+            "high-contrast" => Some(Self::HIGH_CONTRAST),
+            _ => None,
+        }
+    }
+
+    /// Returns the lowest contrast ratio of the styles of the theme, except
+    /// for [`disabled`](Self::disabled).
+    #[must_use]
+    pub fn min_contrast_ratio(&self) -> f32 {
+        [self.normal, self.focused, self.title]
+            .iter()
+            .map(Style::contrast_ratio)
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    /// Returns the style for an element depending on whether it is focused
+    /// and enabled.
+    #[must_use]
+    pub const fn style(&self, focused: bool, enabled: bool) -> Style {
+        match (focused, enabled) {
+            (true, _) => self.focused,
+            (false, true) => self.normal,
+            (false, false) => self.disabled,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Filters the keys repeated by the firmware while a key is held down.
+///
+/// Firmware starts repeating a key after a short delay, at a fixed rate that
+/// cannot be configured. `KeyRepeat` drops repeated keys until the key has
+/// been held for [`delay`], and then passes at most one key per
+/// [`interval`].
+///
+/// The input protocols don't report key releases, so a key counts as held
+/// while the same key keeps arriving with gaps shorter than [`delay`]. As a
+/// consequence, pressing the same key twice within [`delay`] is treated as
+/// a repeat, which also suppresses accidental double presses.
+///
+/// [`delay`]: Self::delay
+/// [`interval`]: Self::interval
+#[derive(Clone, Debug)]
+pub struct KeyRepeat {
+    /// Time a key must be held before it repeats.
+    pub delay: Duration,
+    /// Minimum time between repeated keys.
+    pub interval: Duration,
+    held: Option<HeldKey>,
+}
+
+#[derive(Clone, Debug)]
+struct HeldKey {
+    key: Key,
+    pressed: Duration,
+    last_passed: Duration,
+    last_seen: Duration,
+}
+
+impl KeyRepeat {
+    /// Creates a filter with the given repeat delay and interval.
+    #[must_use]
+    pub const fn new(delay: Duration, interval: Duration) -> Self {
+        Self {
+            delay,
+            interval,
+            held: None,
+        }
+    }
+
+    /// Returns `key` if it should be handled, or `None` if it is a repeat
+    /// that should be dropped. `now` is the time at which the key was read,
+    /// measured from any fixed point, e.g. by counting the ticks of a
+    /// periodic timer event.
+    pub fn filter(&mut self, key: Key, now: Duration) -> Option<Key> {
+        if let Some(held) = &mut self.held {
+            if held.key == key && now.saturating_sub(held.last_seen) < self.delay {
+                held.last_seen = now;
+                let repeat = now.saturating_sub(held.pressed) >= self.delay
+                    && now.saturating_sub(held.last_passed) >= self.interval;
+                if repeat {
+                    held.last_passed = now;
+                }
+                return repeat.then_some(key);
+            }
+        }
+        self.held = Some(HeldKey {
+            key,
+            pressed: now,
+            last_passed: now,
+            last_seen: now,
+        });
+        Some(key)
+    }
+
+    /// Forgets the held key, so that the next key is always passed.
+    pub const fn reset(&mut self) {
+        self.held = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::console::text::KeyShiftState;
+
+    #[test]
+    fn test_navigation() {
+        let a = Key::Printable(Char16::try_from('a').unwrap());
+        assert_eq!(Navigation::from_key(TAB), Some(Navigation::Next));
+        assert_eq!(
+            Navigation::from_key(Key::Special(ScanCode::END)),
+            Some(Navigation::Last)
+        );
+        assert_eq!(Navigation::from_key(a), None);
+
+        let stroke = KeyStroke::new(TAB, KeyShiftState::LEFT_SHIFT_PRESSED);
+        assert_eq!(
+            Navigation::from_key_stroke(stroke),
+            Some(Navigation::Previous)
+        );
+    }
+
+    #[test]
+    fn test_focus_order() {
+        let enabled = [false, true, false, true, true];
+        let can_focus = |i: usize| enabled[i];
+        let mut focus = FocusOrder::new(enabled.len());
+        assert!(focus.navigate(Navigation::Next, can_focus));
+        assert_eq!(focus.focused(), Some(1));
+        focus.navigate(Navigation::Next, can_focus);
+        assert_eq!(focus.focused(), Some(3));
+        focus.navigate(Navigation::Last, can_focus);
+        assert_eq!(focus.focused(), Some(4));
+
+        // Wrap around in both directions.
+        focus.navigate(Navigation::Next, can_focus);
+        assert_eq!(focus.focused(), Some(1));
+        focus.navigate(Navigation::Previous, can_focus);
+        assert_eq!(focus.focused(), Some(4));
+
+        // Without wrapping, the focus stays at the ends.
+        focus.set_wrap(false);
+        assert!(!focus.navigate(Navigation::Next, can_focus));
+        assert_eq!(focus.focused(), Some(4));
+        focus.set_focus(Some(1));
+        assert!(!focus.navigate(Navigation::Previous, can_focus));
+
+        assert!(focus.handle_key(Key::Special(ScanCode::DOWN), can_focus));
+        assert_eq!(focus.focused(), Some(3));
+
+        // Nothing can take the focus.
+        let mut focus = FocusOrder::new(3);
+        assert!(!focus.navigate(Navigation::First, |_| false));
+        assert_eq!(focus.focused(), None);
+    }
+
+    #[test]
+    fn test_theme_contrast() {
+        assert!(Theme::HIGH_CONTRAST.min_contrast_ratio() >= 7.0);
+        assert!(Theme::HIGH_CONTRAST.disabled.contrast_ratio() >= 7.0);
+        assert!(Theme::DEFAULT.min_contrast_ratio() >= 4.5);
+
+        let white_on_black = Style::new(Color::White, Color::Black);
+        assert!((white_on_black.contrast_ratio() - 21.0).abs() < 0.01);
+        let same = Style::new(Color::Blue, Color::Blue);
+        assert!((same.contrast_ratio() - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_key_repeat() {
+        let ms = Duration::from_millis;
+        let a = Key::Printable(Char16::try_from('a').unwrap());
+        let b = Key::Printable(Char16::try_from('b').unwrap());
+        let mut repeat = KeyRepeat::new(ms(800), ms(300));
+
+        // Firmware repeats every 100 ms while the key is held.
+        assert_eq!(repeat.filter(a, ms(0)), Some(a));
+        for t in (100..800).step_by(100) {
+            assert_eq!(repeat.filter(a, ms(t)), None);
+        }
+        assert_eq!(repeat.filter(a, ms(800)), Some(a));
+        assert_eq!(repeat.filter(a, ms(900)), None);
+        assert_eq!(repeat.filter(a, ms(1100)), Some(a));
+
+        // A different key, or the same key after a pause, passes.
+        assert_eq!(repeat.filter(b, ms(1200)), Some(b));
+        assert_eq!(repeat.filter(b, ms(2100)), Some(b));
+        assert_eq!(repeat.filter(b, ms(2200)), None);
+        repeat.reset();
+        assert_eq!(repeat.filter(b, ms(2300)), Some(b));
+    }
+}
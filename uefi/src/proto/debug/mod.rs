@@ -41,7 +41,7 @@ mod exception;
 pub struct DebugSupport {
     isa: ProcessorArch,
     get_maximum_processor_index:
-        extern "efiapi" fn(this: &mut Self, max_processor_index: &mut usize) -> Status,
+        extern "efiapi" fn(this: *const Self, max_processor_index: &mut usize) -> Status,
     register_periodic_callback: unsafe extern "efiapi" fn(
         this: &mut Self,
         processor_index: usize,
@@ -72,7 +72,8 @@ impl DebugSupport {
     /// `register_periodic_callback()` and `register_exception_callback()`.
     ///
     /// Note: Applications built with EDK2 (such as OVMF) always return `0` as of 2021-09-15
-    pub fn get_maximum_processor_index(&mut self) -> usize {
+    #[must_use]
+    pub fn get_maximum_processor_index(&self) -> usize {
         // initially set to a canary value for testing purposes
         let mut max_processor_index: usize = usize::MAX;
 
@@ -343,7 +343,10 @@ impl<'a> TryFrom<&'a [u8]> for &'a DevicePathNode {
 
     fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
         let dp = <&DevicePathHeader>::try_from(bytes)?;
-        if usize::from(dp.length()) <= bytes.len() {
+        let len = usize::from(dp.length());
+        // A node can't be shorter than its header; a shorter length would
+        // also never advance iteration over the nodes of a path.
+        if (size_of::<DevicePathHeader>()..=bytes.len()).contains(&len) {
             unsafe { Ok(DevicePathNode::from_ffi_ptr(bytes.as_ptr().cast())) }
         } else {
             Err(ByteConversionError::InvalidLength)
@@ -1087,6 +1090,13 @@ mod tests {
         // [`DevicePathNode`] data length exceeds the raw_data slice.
         raw_data[2] += 1;
         assert!(<&DevicePathNode>::try_from(raw_data.as_slice()).is_err());
+
+        // [`DevicePathNode`] length is shorter than its header.
+        raw_data[2] = 0;
+        assert!(<&DevicePathNode>::try_from(raw_data.as_slice()).is_err());
+        raw_data[2] = 3;
+        assert!(<&DevicePathNode>::try_from(raw_data.as_slice()).is_err());
+        assert!(<&DevicePath>::try_from([4, 0x15, 0, 0].as_slice()).is_err());
     }
 
     #[test]
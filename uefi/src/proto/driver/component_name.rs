@@ -8,7 +8,7 @@
 
 use crate::boot::{self, ScopedProtocol};
 use crate::proto::unsafe_protocol;
-use crate::{CStr16, Error, Handle, Result, Status, StatusExt};
+use crate::{CStr16, Char16, Error, Handle, Result, Status, StatusExt};
 use core::fmt::{self, Debug, Display, Formatter};
 use core::{ptr, slice};
 use uefi_raw::protocol::driver::ComponentName2Protocol;
@@ -61,7 +61,8 @@ impl ComponentName1 {
         let language = language_to_cstr(language)?;
         let mut driver_name = ptr::null();
         unsafe { (self.0.get_driver_name)(&self.0, language.as_ptr(), &mut driver_name) }
-            .to_result_with_val(|| unsafe { CStr16::from_ptr(driver_name.cast()) })
+            .to_result()?;
+        unsafe { firmware_str(driver_name.cast()) }
     }
 
     /// Get the human-readable name of a controller in the given language.
@@ -86,7 +87,8 @@ impl ComponentName1 {
                 &mut driver_name,
             )
         }
-        .to_result_with_val(|| unsafe { CStr16::from_ptr(driver_name.cast()) })
+        .to_result()?;
+        unsafe { firmware_str(driver_name.cast()) }
     }
 }
 
@@ -133,7 +135,8 @@ impl ComponentName2 {
         let language = language_to_cstr(language)?;
         let mut driver_name = ptr::null();
         unsafe { (self.0.get_driver_name)(&self.0, language.as_ptr(), &mut driver_name) }
-            .to_result_with_val(|| unsafe { CStr16::from_ptr(driver_name.cast()) })
+            .to_result()?;
+        unsafe { firmware_str(driver_name.cast()) }
     }
 
     /// Get the human-readable name of a controller in the given language.
@@ -158,7 +161,8 @@ impl ComponentName2 {
                 &mut driver_name,
             )
         }
-        .to_result_with_val(|| unsafe { CStr16::from_ptr(driver_name.cast()) })
+        .to_result()?;
+        unsafe { firmware_str(driver_name.cast()) }
     }
 }
 
@@ -354,6 +358,16 @@ fn language_to_cstr(language: &str) -> Result<LanguageCStr> {
     Ok(lang_cstr)
 }
 
+/// Wraps a name returned by the driver, failing with
+/// [`Status::COMPROMISED_DATA`] if it is not valid UCS-2.
+///
+/// # Safety
+///
+/// `name` must point to a null-terminated string that outlives `'a`.
+unsafe fn firmware_str<'a>(name: *const Char16) -> Result<&'a CStr16> {
+    unsafe { CStr16::from_ptr_checked(name) }.map_err(|_| Error::from(Status::COMPROMISED_DATA))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,299 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::proto::provider::{self, provider_from_protocol};
+use crate::{CStr8, CStr16, Handle, Result, Status};
+use uefi_raw::protocol::driver::ComponentName2Protocol;
+
+/// Function returning the name of a controller managed by a driver, used by
+/// [`ComponentName2Provider::with_controller_name`].
+///
+/// Called with the controller handle, the child handle if the name of a
+/// child controller is requested, and the language of the name, which is one
+/// of the languages of the provider. Returns `None` if the driver doesn't
+/// manage the controller.
+pub type ControllerNameFn =
+    fn(controller: Handle, child: Option<Handle>, language: &str) -> Option<&'static CStr16>;
+
+/// Implementation of the [`ComponentName2`] protocol for a Rust driver.
+///
+/// The provider is usually created with the [`component_name2!`] macro and
+/// stored in a `static`:
+///
+/// ```no_run
+/// use uefi::proto::driver::ComponentName2Provider;
+/// use uefi::{CStr16, Handle, component_name2, cstr16};
+///
+/// fn controller_name(
+///     controller: Handle,
+///     child: Option<Handle>,
+///     language: &str,
+/// ) -> Option<&'static CStr16> {
+///     match language {
+///         "de" => Some(cstr16!("Beispielcontroller")),
+///         _ => Some(cstr16!("Example controller")),
+///     }
+/// }
+///
+/// static COMPONENT_NAME: ComponentName2Provider = component_name2! {
+///     "en" => "Example driver",
+///     "de" => "Beispieltreiber",
+/// }
+/// .with_controller_name(controller_name);
+///
+/// # fn example(driver_binding_handle: Handle) -> uefi::Result {
+/// COMPONENT_NAME.install(driver_binding_handle)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`ComponentName2`]: super::ComponentName2
+/// [`component_name2!`]: crate::component_name2
+#[derive(Debug)]
+#[repr(C)]
+pub struct ComponentName2Provider {
+    // Must be the first field, so that the callbacks can get the provider
+    // from the protocol pointer.
+    protocol: ComponentName2Protocol,
+    driver_names: &'static [(&'static str, &'static CStr16)],
+    controller_name: Option<ControllerNameFn>,
+}
+
+// SAFETY: the only pointer in the protocol refers to a `&'static str`.
+unsafe impl Sync for ComponentName2Provider {}
+
+impl ComponentName2Provider {
+    /// Creates a provider returning the names in `driver_names`, which are
+    /// pairs of an [RFC 4646] language code and the name of the driver in
+    /// that language.
+    ///
+    /// `supported_languages` must contain the language codes of
+    /// `driver_names`, separated by `;` and terminated by a null character.
+    /// The [`component_name2!`] macro builds it automatically.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `supported_languages` is not an ASCII string with a single
+    /// null character at the end. In a `static`, this is a compile-time
+    /// error.
+    ///
+    /// [RFC 4646]: https://www.rfc-editor.org/rfc/rfc4646
+    /// [`component_name2!`]: crate::component_name2
+    #[must_use]
+    pub const fn new(
+        supported_languages: &'static str,
+        driver_names: &'static [(&'static str, &'static CStr16)],
+    ) -> Self {
+        let bytes = supported_languages.as_bytes();
+        assert!(
+            !bytes.is_empty() && bytes[bytes.len() - 1] == 0,
+            "supported languages must be null-terminated"
+        );
+        let mut i = 0;
+        while i < bytes.len() - 1 {
+            assert!(
+                bytes[i] != 0 && bytes[i].is_ascii(),
+                "supported languages must be an ASCII string"
+            );
+            i += 1;
+        }
+
+        Self {
+            protocol: ComponentName2Protocol {
+                get_driver_name: Self::get_driver_name,
+                get_controller_name: Self::get_controller_name,
+                supported_languages: bytes.as_ptr(),
+            },
+            driver_names,
+            controller_name: None,
+        }
+    }
+
+    /// Sets the function returning controller names. Without it, requests
+    /// for controller names fail with [`Status::UNSUPPORTED`].
+    #[must_use]
+    pub const fn with_controller_name(mut self, controller_name: ControllerNameFn) -> Self {
+        self.controller_name = Some(controller_name);
+        self
+    }
+
+    /// Returns the index of the entry of `driver_names` matching `language`,
+    /// which is a null-terminated ASCII string.
+    unsafe fn find_language(&self, language: *const u8) -> Option<usize> {
+        if language.is_null() {
+            return None;
+        }
+        // SAFETY: the caller passes a null-terminated string.
+        let language = unsafe { CStr8::from_ptr(language.cast()) }.as_bytes();
+        let language = &language[..language.len() - 1];
+        self.driver_names
+            .iter()
+            .position(|(lang, _)| lang.as_bytes().eq_ignore_ascii_case(language))
+    }
+
+    unsafe extern "efiapi" fn get_driver_name(
+        this: *const ComponentName2Protocol,
+        language: *const u8,
+        driver_name: *mut *const u16,
+    ) -> Status {
+        // SAFETY: the firmware passes the protocol installed by `install`.
+        let this: &Self = unsafe { provider_from_protocol(this) };
+        if language.is_null() || driver_name.is_null() {
+            return Status::INVALID_PARAMETER;
+        }
+        let Some(index) = (unsafe { this.find_language(language) }) else {
+            return Status::UNSUPPORTED;
+        };
+        unsafe { driver_name.write(this.driver_names[index].1.as_ptr().cast()) };
+        Status::SUCCESS
+    }
+
+    unsafe extern "efiapi" fn get_controller_name(
+        this: *const ComponentName2Protocol,
+        controller_handle: uefi_raw::Handle,
+        child_handle: uefi_raw::Handle,
+        language: *const u8,
+        controller_name: *mut *const u16,
+    ) -> Status {
+        // SAFETY: as in `get_driver_name`.
+        let this: &Self = unsafe { provider_from_protocol(this) };
+        let Some(controller) = (unsafe { Handle::from_ptr(controller_handle) }) else {
+            return Status::INVALID_PARAMETER;
+        };
+        if language.is_null() || controller_name.is_null() {
+            return Status::INVALID_PARAMETER;
+        }
+        let Some(f) = this.controller_name else {
+            return Status::UNSUPPORTED;
+        };
+        let Some(index) = (unsafe { this.find_language(language) }) else {
+            return Status::UNSUPPORTED;
+        };
+        let child = unsafe { Handle::from_ptr(child_handle) };
+        match f(controller, child, this.driver_names[index].0) {
+            Some(name) => {
+                unsafe { controller_name.write(name.as_ptr().cast()) };
+                Status::SUCCESS
+            }
+            None => Status::UNSUPPORTED,
+        }
+    }
+
+    /// Installs the protocol on `handle`, usually the driver binding handle
+    /// of the driver.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::INVALID_PARAMETER`]: the protocol is already installed on
+    ///   `handle`.
+    pub fn install(&'static self, handle: Handle) -> Result {
+        provider::install_static(Some(handle), &ComponentName2Protocol::GUID, &self.protocol)
+            .map(|_| ())
+    }
+
+    /// Uninstalls the protocol from `handle`, after which the names of the
+    /// driver and its controllers are no longer reported.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::NOT_FOUND`]: this provider is not installed on `handle`.
+    /// * [`Status::ACCESS_DENIED`]: the protocol is still open by another
+    ///   agent.
+    pub fn uninstall(&'static self, handle: Handle) -> Result {
+        provider::uninstall_static(handle, &ComponentName2Protocol::GUID, &self.protocol)
+    }
+}
+
+/// Creates a [`ComponentName2Provider`] from pairs of an [RFC 4646]
+/// language code and the name of the driver in that language. The first
+/// language is the default.
+///
+/// The result must be used in a `static` or `const` item. See
+/// [`ComponentName2Provider`] for an example.
+///
+/// [RFC 4646]: https://www.rfc-editor.org/rfc/rfc4646
+/// [`ComponentName2Provider`]: crate::proto::driver::ComponentName2Provider
+#[macro_export]
+macro_rules! component_name2 {
+    ($lang:literal => $name:literal $(, $langs:literal => $names:literal)* $(,)?) => {
+        $crate::proto::driver::ComponentName2Provider::new(
+            concat!($lang $(, ";", $langs)*, "\0"),
+            &[
+                ($lang, $crate::cstr16!($name))
+                $(, ($langs, $crate::cstr16!($names)))*
+            ],
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cstr16;
+    use crate::proto::driver::ComponentName2;
+    use core::ffi::c_void;
+    use core::ptr;
+
+    static PROVIDER: ComponentName2Provider = component_name2! {
+        "en" => "Driver",
+        "de-DE" => "Treiber",
+    }
+    .with_controller_name(controller_name);
+
+    fn controller_name(
+        _controller: Handle,
+        child: Option<Handle>,
+        language: &str,
+    ) -> Option<&'static CStr16> {
+        match (child, language) {
+            (None, "en") => Some(cstr16!("Controller")),
+            _ => None,
+        }
+    }
+
+    fn protocol() -> &'static ComponentName2 {
+        unsafe { &*ptr::from_ref(&PROVIDER.protocol).cast::<ComponentName2>() }
+    }
+
+    #[test]
+    fn test_supported_languages() {
+        let languages: alloc::vec::Vec<_> = protocol().supported_languages().unwrap().collect();
+        assert_eq!(languages, ["en", "de-DE"]);
+    }
+
+    #[test]
+    fn test_driver_name() {
+        let protocol = protocol();
+        assert_eq!(protocol.driver_name("en"), Ok(cstr16!("Driver")));
+        assert_eq!(protocol.driver_name("DE-de"), Ok(cstr16!("Treiber")));
+        assert_eq!(
+            protocol.driver_name("fr").unwrap_err().status(),
+            Status::UNSUPPORTED
+        );
+    }
+
+    #[test]
+    fn test_controller_name() {
+        let protocol = protocol();
+        let mut dummy = 0u8;
+        let controller =
+            unsafe { Handle::from_ptr(ptr::from_mut(&mut dummy).cast::<c_void>()) }.unwrap();
+        assert_eq!(
+            protocol.controller_name(controller, None, "en"),
+            Ok(cstr16!("Controller"))
+        );
+        assert_eq!(
+            protocol
+                .controller_name(controller, Some(controller), "en")
+                .unwrap_err()
+                .status(),
+            Status::UNSUPPORTED
+        );
+        assert_eq!(
+            protocol
+                .controller_name(controller, None, "de-DE")
+                .unwrap_err()
+                .status(),
+            Status::UNSUPPORTED
+        );
+    }
+}
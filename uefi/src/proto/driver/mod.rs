@@ -3,5 +3,11 @@
 //! UEFI driver model protocols.
 
 mod component_name;
+mod component_name_provider;
+mod platform_config;
+mod version;
 
 pub use component_name::*;
+pub use component_name_provider::*;
+pub use platform_config::*;
+pub use version::*;
@@ -0,0 +1,179 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::proto::unsafe_protocol;
+use crate::{Guid, Handle, Result, StatusExt};
+use core::ffi::c_void;
+use core::{ptr, slice};
+use uefi_raw::protocol::driver::PlatformToDriverConfigurationProtocol;
+
+pub use uefi_raw::protocol::driver::{ConfigureClpParameterBlock, PlatformConfigurationAction};
+
+/// Platform to Driver Configuration [`Protocol`].
+///
+/// Installed by the platform to pass configuration data to drivers. A driver
+/// queries the configuration blocks for a controller one instance at a time
+/// with [`query`], applies each block, and reports the result with
+/// [`ConfigurationBlock::response`] before querying the next instance:
+///
+/// ```no_run
+/// use uefi::boot;
+/// use uefi::proto::driver::{PlatformConfigurationAction, PlatformToDriverConfiguration};
+/// use uefi::{Handle, Status};
+///
+/// # fn example(controller: Handle) -> uefi::Result {
+/// let handle = boot::get_handle_for_protocol::<PlatformToDriverConfiguration>()?;
+/// let mut config = boot::open_protocol_exclusive::<PlatformToDriverConfiguration>(handle)?;
+/// for instance in 0.. {
+///     let block = match config.query(controller, None, instance) {
+///         Ok(block) => block,
+///         Err(err) if err.status() == Status::NOT_FOUND => break,
+///         Err(err) => return Err(err),
+///     };
+///     // Apply `block.data()` if `block.parameter_type()` is supported.
+///     block.response(PlatformConfigurationAction::UNSUPPORTED_GUID)?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The corresponding C type is `EFI_PLATFORM_TO_DRIVER_CONFIGURATION_PROTOCOL`.
+///
+/// [`query`]: Self::query
+/// [`Protocol`]: uefi::proto::Protocol
+#[derive(Debug)]
+#[repr(transparent)]
+#[unsafe_protocol(PlatformToDriverConfigurationProtocol::GUID)]
+pub struct PlatformToDriverConfiguration(PlatformToDriverConfigurationProtocol);
+
+impl PlatformToDriverConfiguration {
+    /// Parameter type of configuration blocks in the
+    /// [`ConfigureClpParameterBlock`] format.
+    pub const CLP_PARAMETER_TYPE: Guid = PlatformToDriverConfigurationProtocol::CLP_GUID;
+
+    /// Gets configuration block number `instance` for `controller`, or for
+    /// its child controller `child` if set. Instances are numbered starting
+    /// at 0.
+    ///
+    /// The block is owned by the platform until it is returned with
+    /// [`ConfigurationBlock::response`], so only one block can be processed
+    /// at a time.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::NOT_FOUND`]: there is no configuration block for
+    ///   `instance`, e.g. because all blocks were already returned.
+    /// * [`Status::INVALID_PARAMETER`]: `controller` or `child` is invalid.
+    ///
+    /// [`Status::NOT_FOUND`]: crate::Status::NOT_FOUND
+    /// [`Status::INVALID_PARAMETER`]: crate::Status::INVALID_PARAMETER
+    pub fn query(
+        &mut self,
+        controller: Handle,
+        child: Option<Handle>,
+        instance: usize,
+    ) -> Result<ConfigurationBlock<'_>> {
+        let mut parameter_type = ptr::null();
+        let mut data = ptr::null_mut();
+        let mut size = 0;
+        unsafe {
+            (self.0.query)(
+                &self.0,
+                controller.as_ptr(),
+                Handle::opt_to_ptr(child),
+                &instance,
+                &mut parameter_type,
+                &mut data,
+                &mut size,
+            )
+        }
+        .to_result_with_val(|| ConfigurationBlock {
+            protocol: &self.0,
+            controller,
+            child,
+            instance,
+            parameter_type,
+            data: if data.is_null() {
+                &mut []
+            } else {
+                // SAFETY: the block is owned by the platform and stays valid
+                // until the response is sent.
+                unsafe { slice::from_raw_parts_mut(data.cast(), size) }
+            },
+        })
+    }
+}
+
+/// A configuration block returned by [`PlatformToDriverConfiguration::query`].
+///
+/// The block must be returned to the platform with [`response`] once it was
+/// processed.
+///
+/// [`response`]: Self::response
+#[derive(Debug)]
+pub struct ConfigurationBlock<'a> {
+    protocol: &'a PlatformToDriverConfigurationProtocol,
+    controller: Handle,
+    child: Option<Handle>,
+    instance: usize,
+    /// GUID returned by the platform, which must be passed back to it.
+    parameter_type: *const Guid,
+    data: &'a mut [u8],
+}
+
+impl ConfigurationBlock<'_> {
+    /// Returns the instance number of the block.
+    #[must_use]
+    pub const fn instance(&self) -> usize {
+        self.instance
+    }
+
+    /// Returns the GUID identifying the format of the block, e.g.
+    /// [`PlatformToDriverConfiguration::CLP_PARAMETER_TYPE`].
+    #[must_use]
+    pub const fn parameter_type(&self) -> Guid {
+        // SAFETY: the platform returns a valid GUID, which stays valid until
+        // the response is sent.
+        unsafe { *self.parameter_type }
+    }
+
+    /// Returns the contents of the block.
+    #[must_use]
+    pub const fn data(&self) -> &[u8] {
+        self.data
+    }
+
+    /// Returns the contents of the block mutably. Some formats, such as
+    /// [`ConfigureClpParameterBlock`], contain fields for the driver to fill
+    /// in before sending the response.
+    #[must_use]
+    pub const fn data_mut(&mut self) -> &mut [u8] {
+        self.data
+    }
+
+    /// Reports to the platform that the block was processed, and which
+    /// action is required for the configuration to take effect.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::NOT_FOUND`]: the block is not the one the platform
+    ///   returned last for the controller.
+    /// * [`Status::INVALID_PARAMETER`]: the controller or child is invalid.
+    ///
+    /// [`Status::NOT_FOUND`]: crate::Status::NOT_FOUND
+    /// [`Status::INVALID_PARAMETER`]: crate::Status::INVALID_PARAMETER
+    pub fn response(self, action: PlatformConfigurationAction) -> Result {
+        unsafe {
+            (self.protocol.response)(
+                self.protocol,
+                self.controller.as_ptr(),
+                Handle::opt_to_ptr(self.child),
+                &self.instance,
+                self.parameter_type,
+                self.data.as_ptr().cast::<c_void>(),
+                self.data.len(),
+                action,
+            )
+        }
+        .to_result()
+    }
+}
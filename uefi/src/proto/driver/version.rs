@@ -0,0 +1,195 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::proto::provider::{self, provider_from_protocol};
+use crate::proto::unsafe_protocol;
+use crate::table::Revision;
+use crate::{Handle, Result};
+use uefi_raw::protocol::driver::{DriverFamilyOverrideProtocol, DriverSupportedEfiVersionProtocol};
+
+/// Driver Family Override [`Protocol`].
+///
+/// Installed by a driver on its driver binding handle to take precedence
+/// over other drivers of the same family when the firmware connects
+/// controllers. Of the drivers that produce this protocol, the one with the
+/// highest version is tried first.
+///
+/// Use [`DriverFamilyOverrideProvider`] to produce this protocol.
+///
+/// The corresponding C type is `EFI_DRIVER_FAMILY_OVERRIDE_PROTOCOL`.
+///
+/// [`Protocol`]: uefi::proto::Protocol
+#[derive(Debug)]
+#[repr(transparent)]
+#[unsafe_protocol(DriverFamilyOverrideProtocol::GUID)]
+pub struct DriverFamilyOverride(DriverFamilyOverrideProtocol);
+
+impl DriverFamilyOverride {
+    /// Returns the version of the driver. Higher versions take precedence.
+    #[must_use]
+    pub fn version(&self) -> u32 {
+        unsafe { (self.0.get_version)(&self.0) }
+    }
+}
+
+/// Implementation of the [`DriverFamilyOverride`] protocol for a Rust
+/// driver.
+///
+/// The provider is typically stored in a `static`:
+///
+/// ```no_run
+/// use uefi::proto::driver::DriverFamilyOverrideProvider;
+/// use uefi::Handle;
+///
+/// static FAMILY_OVERRIDE: DriverFamilyOverrideProvider = DriverFamilyOverrideProvider::new(3);
+///
+/// # fn example(driver_binding_handle: Handle) -> uefi::Result {
+/// FAMILY_OVERRIDE.install(driver_binding_handle)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+#[repr(C)]
+pub struct DriverFamilyOverrideProvider {
+    // Must be the first field, so that `get_version` can get the provider
+    // from the protocol pointer.
+    protocol: DriverFamilyOverrideProtocol,
+    version: u32,
+}
+
+impl DriverFamilyOverrideProvider {
+    /// Creates a provider reporting `version`.
+    #[must_use]
+    pub const fn new(version: u32) -> Self {
+        Self {
+            protocol: DriverFamilyOverrideProtocol {
+                get_version: Self::get_version,
+            },
+            version,
+        }
+    }
+
+    /// Returns the reported version.
+    #[must_use]
+    pub const fn version(&self) -> u32 {
+        self.version
+    }
+
+    const unsafe extern "efiapi" fn get_version(this: *const DriverFamilyOverrideProtocol) -> u32 {
+        // SAFETY: `this` is the `protocol` field of the installed provider.
+        unsafe { provider_from_protocol::<Self, _>(this).version }
+    }
+
+    /// Installs the protocol on `handle`, usually the driver binding handle
+    /// of the driver.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::INVALID_PARAMETER`]: the protocol is already installed on
+    ///   `handle`.
+    ///
+    /// [`Status::INVALID_PARAMETER`]: crate::Status::INVALID_PARAMETER
+    pub fn install(&'static self, handle: Handle) -> Result {
+        provider::install_static(
+            Some(handle),
+            &DriverFamilyOverrideProtocol::GUID,
+            &self.protocol,
+        )
+        .map(|_| ())
+    }
+
+    /// Uninstalls the protocol from `handle`. A driver that supports being
+    /// unloaded must call this from its unload function, since the provider
+    /// is freed with the image.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::NOT_FOUND`]: this provider is not installed on `handle`.
+    /// * [`Status::ACCESS_DENIED`]: the protocol is still open by another
+    ///   agent.
+    ///
+    /// [`Status::NOT_FOUND`]: crate::Status::NOT_FOUND
+    /// [`Status::ACCESS_DENIED`]: crate::Status::ACCESS_DENIED
+    pub fn uninstall(&'static self, handle: Handle) -> Result {
+        provider::uninstall_static(handle, &DriverFamilyOverrideProtocol::GUID, &self.protocol)
+    }
+}
+
+/// Driver Supported EFI Version [`Protocol`].
+///
+/// Installed by a driver on its image handle to report the version of the
+/// UEFI specification the driver conforms to.
+///
+/// The protocol only contains data, so it can be produced by storing it in a
+/// `static` and calling [`install`]:
+///
+/// ```no_run
+/// use uefi::proto::driver::DriverSupportedEfiVersion;
+/// use uefi::table::Revision;
+/// use uefi::Handle;
+///
+/// static EFI_VERSION: DriverSupportedEfiVersion = DriverSupportedEfiVersion::new(Revision::EFI_2_100);
+///
+/// # fn example(image_handle: Handle) -> uefi::Result {
+/// EFI_VERSION.install(image_handle)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The corresponding C type is `EFI_DRIVER_SUPPORTED_EFI_VERSION_PROTOCOL`.
+///
+/// [`install`]: Self::install
+/// [`Protocol`]: uefi::proto::Protocol
+#[derive(Debug)]
+#[repr(transparent)]
+#[unsafe_protocol(DriverSupportedEfiVersionProtocol::GUID)]
+pub struct DriverSupportedEfiVersion(DriverSupportedEfiVersionProtocol);
+
+impl DriverSupportedEfiVersion {
+    /// Creates the protocol for a driver conforming to `firmware_version`.
+    #[must_use]
+    pub const fn new(firmware_version: Revision) -> Self {
+        Self(DriverSupportedEfiVersionProtocol {
+            length: size_of::<DriverSupportedEfiVersionProtocol>() as u32,
+            firmware_version,
+        })
+    }
+
+    /// Returns the version of the UEFI specification the driver conforms
+    /// to.
+    #[must_use]
+    pub const fn firmware_version(&self) -> Revision {
+        self.0.firmware_version
+    }
+
+    /// Installs the protocol on `handle`, usually the image handle of the
+    /// driver.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::INVALID_PARAMETER`]: the protocol is already installed on
+    ///   `handle`.
+    ///
+    /// [`Status::INVALID_PARAMETER`]: crate::Status::INVALID_PARAMETER
+    pub fn install(&'static self, handle: Handle) -> Result {
+        provider::install_static(
+            Some(handle),
+            &DriverSupportedEfiVersionProtocol::GUID,
+            &self.0,
+        )
+        .map(|_| ())
+    }
+
+    /// Uninstalls the protocol from the image handle `handle`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::NOT_FOUND`]: this provider is not installed on `handle`.
+    /// * [`Status::ACCESS_DENIED`]: the protocol is still open by another
+    ///   agent.
+    ///
+    /// [`Status::NOT_FOUND`]: crate::Status::NOT_FOUND
+    /// [`Status::ACCESS_DENIED`]: crate::Status::ACCESS_DENIED
+    pub fn uninstall(&'static self, handle: Handle) -> Result {
+        provider::uninstall_static(handle, &DriverSupportedEfiVersionProtocol::GUID, &self.0)
+    }
+}
@@ -0,0 +1,948 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! A minimal keyboard-driven forms browser.
+//!
+//! Applications usually display setup forms with the platform's
+//! [`FormBrowser2`] protocol, which is not always available to them, e.g.
+//! after the platform's setup has been exited. [`Browser`] is a fallback:
+//! it displays the forms of a [`FormSetInfo`] on a text console, and edits
+//! the values of checkbox, numeric, and one-of questions in their varstores.
+//! Other questions are shown, but can't be changed.
+//!
+//! Varstores are read when the browser is created, and written when the
+//! changes are saved, through a [`VarstoreAccess`]. [`FirmwareVarstores`]
+//! reads and writes UEFI variables directly, and buffer varstores through
+//! the [`HiiConfigRouting`] protocol, which forwards the requests to the
+//! Config Access protocol of the driver owning the formset.
+//!
+//! The browser is controlled with these keys:
+//!
+//! | Key                   | Action                                     |
+//! |-----------------------|--------------------------------------------|
+//! | Up, Down              | Select a question                          |
+//! | Enter, Space          | Toggle a checkbox, open a linked form, or  |
+//! |                       | commit a typed number                      |
+//! | `+`, `-`, Right, Left | Change a numeric or one-of value           |
+//! | `0`-`9`, Backspace    | Type the value of a numeric question       |
+//! | F9                    | Load the standard defaults                 |
+//! | F10                   | Save the changes and exit                  |
+//! | Esc                   | Cancel typing, go back, or exit            |
+//!
+//! ```no_run
+//! use uefi::proto::console::text::{Input, Output};
+//! use uefi::proto::device_path::DevicePath;
+//! use uefi::proto::hii::browser::{Browser, FirmwareVarstores};
+//! use uefi::proto::hii::settings::FormSetInfo;
+//!
+//! # fn example(
+//! #     formset: &FormSetInfo,
+//! #     driver_path: &DevicePath,
+//! #     input: &mut Input,
+//! #     output: &mut Output,
+//! # ) -> uefi::Result {
+//! let mut browser = Browser::new(formset, FirmwareVarstores::new(driver_path));
+//! let exit = browser.run(input, output)?;
+//! log::info!("browser closed: {exit:?}");
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`FormBrowser2`]: super::form_browser::FormBrowser2
+
+use super::VarstoreId;
+use super::config_routing::HiiConfigRouting;
+use super::config_str::ConfigHdr;
+use super::settings::{
+    FormInfo, FormSetInfo, QuestionInfo, QuestionKind, VarstoreInfo, VarstoreKind,
+};
+use crate::proto::console::text::{Color, Input, Key, Output, ScanCode};
+use crate::proto::device_path::DevicePath;
+use crate::runtime::{self, VariableVendor};
+use crate::{CString16, Result, Status, boot};
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use uefi_raw::protocol::hii::ifr::{IfrDefaultStore, IfrQuestionFlags};
+
+/// Reads and writes the varstores edited by a [`Browser`].
+pub trait VarstoreAccess {
+    /// Returns the contents of `varstore` of `formset`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the varstore can't be read. Its questions are
+    /// shown as unavailable.
+    fn load(&mut self, formset: &FormSetInfo, varstore: &VarstoreInfo) -> Result<Vec<u8>>;
+
+    /// Replaces the contents of `varstore` of `formset` with `data`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the varstore can't be written.
+    fn store(&mut self, formset: &FormSetInfo, varstore: &VarstoreInfo, data: &[u8]) -> Result;
+}
+
+/// Access to the varstores of a formset installed in the HII database.
+///
+/// EFI varstores are UEFI variables, read and written with the runtime
+/// services. Buffer varstores are read and written with the
+/// [`HiiConfigRouting`] protocol. Name/value varstores are not supported.
+#[derive(Debug)]
+pub struct FirmwareVarstores {
+    /// The device path of the driver handle.
+    device_path: Box<DevicePath>,
+}
+
+impl FirmwareVarstores {
+    /// Creates access to the varstores of a formset whose driver is
+    /// installed on the handle with `device_path`.
+    #[must_use]
+    pub fn new(device_path: &DevicePath) -> Self {
+        Self {
+            device_path: device_path.to_boxed(),
+        }
+    }
+
+    /// Returns the `<ConfigHdr>` of `varstore`.
+    fn config_header(&self, varstore: &VarstoreInfo) -> ConfigHdr {
+        ConfigHdr {
+            guid: varstore.guid,
+            name: varstore.name.clone(),
+            device_path: self.device_path.to_boxed(),
+        }
+    }
+}
+
+impl Clone for FirmwareVarstores {
+    fn clone(&self) -> Self {
+        Self::new(&self.device_path)
+    }
+}
+
+/// Opens the HII Config Routing protocol.
+fn config_routing() -> Result<boot::ScopedProtocol<HiiConfigRouting>> {
+    boot::open_protocol_exclusive(boot::get_handle_for_protocol::<HiiConfigRouting>()?)
+}
+
+/// Converts `s` to a [`CString16`], failing with `INVALID_PARAMETER`.
+fn to_cstring16(s: &str) -> Result<CString16> {
+    CString16::try_from(s).map_err(|_| Status::INVALID_PARAMETER.into())
+}
+
+impl VarstoreAccess for FirmwareVarstores {
+    fn load(&mut self, _formset: &FormSetInfo, varstore: &VarstoreInfo) -> Result<Vec<u8>> {
+        let size = usize::from(varstore.size);
+        match varstore.kind {
+            VarstoreKind::Efi { .. } => {
+                let name = to_cstring16(&varstore.name)?;
+                let (data, _) = runtime::get_variable_boxed(&name, &VariableVendor(varstore.guid))?;
+                let mut data = data.into_vec();
+                data.resize(size.max(data.len()), 0);
+                Ok(data)
+            }
+            VarstoreKind::Buffer => {
+                let request = to_cstring16(&self.config_header(varstore).to_string())?;
+                let routing = config_routing()?;
+                let response = to_cstring16(&routing.extract_config(&request)?)?;
+                let mut data = vec![0; size];
+                routing
+                    .config_to_block(&response, &mut data)
+                    .map_err(|err| err.status())?;
+                Ok(data)
+            }
+            VarstoreKind::NameValue => Err(Status::UNSUPPORTED.into()),
+        }
+    }
+
+    fn store(&mut self, _formset: &FormSetInfo, varstore: &VarstoreInfo, data: &[u8]) -> Result {
+        match varstore.kind {
+            VarstoreKind::Efi { attributes } => {
+                let name = to_cstring16(&varstore.name)?;
+                runtime::set_variable(&name, &VariableVendor(varstore.guid), attributes, data)
+                    .map_err(Into::into)
+            }
+            VarstoreKind::Buffer => {
+                let request = self
+                    .config_header(varstore)
+                    .request()
+                    .with_block(0, data.len() as u64)
+                    .to_string();
+                let routing = config_routing()?;
+                let config = routing.block_to_config(&to_cstring16(&request)?, data)?;
+                routing.route_config(&to_cstring16(&config)?)
+            }
+            VarstoreKind::NameValue => Err(Status::UNSUPPORTED.into()),
+        }
+    }
+}
+
+/// How a [`Browser`] was closed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BrowserExit {
+    /// The changes were saved.
+    Saved,
+    /// The changes, if any, were discarded.
+    Discarded,
+}
+
+/// The loaded contents of a varstore.
+#[derive(Clone, Debug)]
+struct Store {
+    data: Vec<u8>,
+    modified: bool,
+}
+
+/// Style of a [`ScreenLine`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum LineStyle {
+    Title,
+    Normal,
+    Disabled,
+    Selected,
+    Status,
+}
+
+/// A line of the screen, without its row.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct ScreenLine {
+    text: String,
+    style: LineStyle,
+}
+
+/// Key hints shown on the last line.
+const HINTS: &str = "Up/Down: Select  Enter/+/-: Change  F9: Defaults  F10: Save  Esc: Exit";
+
+/// A keyboard-driven browser for the forms of a formset.
+///
+/// See the [module documentation](self) for an overview.
+#[derive(Debug)]
+pub struct Browser<'a, A> {
+    formset: &'a FormSetInfo,
+    access: A,
+    stores: BTreeMap<VarstoreId, Store>,
+    /// Index of the displayed form.
+    form: usize,
+    /// Index of the selected question in the form.
+    selected: usize,
+    /// The forms the displayed form was opened from, with their selection.
+    history: Vec<(usize, usize)>,
+    /// The number typed for the selected numeric question.
+    typed: Option<u64>,
+    status: Option<String>,
+    /// Whether Esc was pressed once with unsaved changes.
+    confirm_discard: bool,
+}
+
+impl<'a, A: VarstoreAccess> Browser<'a, A> {
+    /// Creates a browser showing the first form of `formset`, and loads its
+    /// varstores with `access`.
+    pub fn new(formset: &'a FormSetInfo, mut access: A) -> Self {
+        let mut stores = BTreeMap::new();
+        let mut status = None;
+        for varstore in &formset.varstores {
+            match access.load(formset, varstore) {
+                Ok(data) => {
+                    stores.insert(
+                        varstore.id,
+                        Store {
+                            data,
+                            modified: false,
+                        },
+                    );
+                }
+                Err(err) => {
+                    status = Some(format!("Can't read {}: {}", varstore.name, err.status()));
+                }
+            }
+        }
+        let mut browser = Self {
+            formset,
+            access,
+            stores,
+            form: 0,
+            selected: 0,
+            history: Vec::new(),
+            typed: None,
+            status,
+            confirm_discard: false,
+        };
+        browser.selected = browser.next_selectable(0, true).unwrap_or(0);
+        browser
+    }
+
+    /// Returns the varstore access, e.g. to inspect it after the browser
+    /// was closed.
+    pub const fn access(&self) -> &A {
+        &self.access
+    }
+
+    /// Returns the displayed form, or `None` if the formset has no forms.
+    #[must_use]
+    pub fn form(&self) -> Option<&'a FormInfo> {
+        self.formset.forms.get(self.form)
+    }
+
+    /// Returns the selected question, or `None` if the form has no
+    /// questions.
+    #[must_use]
+    pub fn selected(&self) -> Option<&'a QuestionInfo> {
+        self.form()?.questions.get(self.selected)
+    }
+
+    /// Returns the current value of `question`, or `None` if it is not an
+    /// integer stored in a loaded varstore.
+    #[must_use]
+    pub fn value(&self, question: &QuestionInfo) -> Option<u64> {
+        if !is_integer(question) {
+            return None;
+        }
+        let store = self.stores.get(&question.varstore?)?;
+        let start = usize::from(question.offset);
+        let bytes = store.data.get(start..start + usize::from(question.size))?;
+        let mut buf = [0; 8];
+        buf[..bytes.len()].copy_from_slice(bytes);
+        Some(u64::from_le_bytes(buf))
+    }
+
+    /// Changes the value of `question` to `value`. Returns `false` if the
+    /// question can't be changed, or `value` is not valid for it.
+    pub fn set_value(&mut self, question: &QuestionInfo, value: u64) -> bool {
+        if !self.is_editable(question) || !is_valid(question, value) {
+            return false;
+        }
+        let Some(store) = question.varstore.and_then(|id| self.stores.get_mut(&id)) else {
+            return false;
+        };
+        let start = usize::from(question.offset);
+        let size = usize::from(question.size);
+        let Some(bytes) = store.data.get_mut(start..start + size) else {
+            return false;
+        };
+        if bytes != &value.to_le_bytes()[..size] {
+            bytes.copy_from_slice(&value.to_le_bytes()[..size]);
+            store.modified = true;
+        }
+        true
+    }
+
+    /// Returns whether any value was changed since the varstores were
+    /// loaded or saved.
+    #[must_use]
+    pub fn is_modified(&self) -> bool {
+        self.stores.values().any(|store| store.modified)
+    }
+
+    /// Sets all questions of the formset with a default in the default
+    /// store `default_id`, such as [`IfrDefaultStore::STANDARD`], to their
+    /// default value.
+    pub fn load_defaults(&mut self, default_id: u16) {
+        let formset = self.formset;
+        for form in &formset.forms {
+            for question in &form.questions {
+                if let Some(value) = question.default_value(default_id) {
+                    self.set_value(question, value);
+                }
+            }
+        }
+    }
+
+    /// Writes all modified varstores.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error of [`VarstoreAccess::store`]. The varstores
+    /// that failed to be written remain modified.
+    pub fn save(&mut self) -> Result {
+        let mut result = Ok(());
+        for varstore in &self.formset.varstores {
+            let Some(store) = self.stores.get_mut(&varstore.id) else {
+                continue;
+            };
+            if !store.modified {
+                continue;
+            }
+            match self.access.store(self.formset, varstore, &store.data) {
+                Ok(()) => store.modified = false,
+                Err(err) => {
+                    if result.is_ok() {
+                        result = Err(err);
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// Handles a key press. Returns how the browser was closed, or `None`
+    /// if it is still open.
+    pub fn handle_key(&mut self, key: Key) -> Option<BrowserExit> {
+        if key != Key::Special(ScanCode::ESCAPE) {
+            self.confirm_discard = false;
+        }
+        self.status = None;
+        match key {
+            Key::Special(ScanCode::UP) => self.move_selection(false),
+            Key::Special(ScanCode::DOWN) => self.move_selection(true),
+            Key::Special(ScanCode::RIGHT) => self.step(true),
+            Key::Special(ScanCode::LEFT) => self.step(false),
+            Key::Special(ScanCode::FUNCTION_9) => {
+                self.load_defaults(IfrDefaultStore::STANDARD);
+                self.status = Some("Loaded the default values".into());
+            }
+            Key::Special(ScanCode::FUNCTION_10) => match self.save() {
+                Ok(()) => return Some(BrowserExit::Saved),
+                Err(err) => self.status = Some(format!("Can't save: {}", err.status())),
+            },
+            Key::Special(ScanCode::ESCAPE) => return self.escape(),
+            Key::Printable(c) => match char::from(c) {
+                '\r' | ' ' => self.activate(),
+                '+' => self.step(true),
+                '-' => self.step(false),
+                '\u{8}' => self.typed = self.typed.map(|typed| typed / 10).filter(|&t| t != 0),
+                c @ '0'..='9' => self.type_digit(c as u64 - '0' as u64),
+                _ => {}
+            },
+            Key::Special(_) => {}
+        }
+        None
+    }
+
+    /// Displays the browser on `output`, and handles key presses from
+    /// `input` until it is closed.
+    ///
+    /// # Errors
+    ///
+    /// Returns errors of the console.
+    pub fn run(&mut self, input: &mut Input, output: &mut Output) -> Result<BrowserExit> {
+        let cursor = output.cursor_visible();
+        let _ = output.enable_cursor(false);
+        let exit = loop {
+            self.render(output)?;
+            let mut events = [input.wait_for_key_event().ok_or(Status::UNSUPPORTED)?];
+            boot::wait_for_event(&mut events).map_err(|err| err.to_err_without_payload())?;
+            if let Some(key) = input.read_key()? {
+                if let Some(exit) = self.handle_key(key) {
+                    break exit;
+                }
+            }
+        };
+        output.set_color(Color::LightGray, Color::Black)?;
+        output.clear()?;
+        let _ = output.enable_cursor(cursor);
+        Ok(exit)
+    }
+
+    /// Draws the browser on `output`.
+    ///
+    /// # Errors
+    ///
+    /// Returns errors of the console.
+    pub fn render(&self, output: &mut Output) -> Result {
+        let (columns, rows) = output
+            .current_mode()?
+            .map_or((80, 25), |mode| (mode.columns(), mode.rows()));
+        output.set_color(Color::LightGray, Color::Black)?;
+        output.clear()?;
+        for (row, line) in self.screen(columns, rows).iter().enumerate() {
+            let (foreground, background) = match line.style {
+                LineStyle::Title => (Color::White, Color::Blue),
+                LineStyle::Normal => (Color::LightGray, Color::Black),
+                LineStyle::Disabled => (Color::DarkGray, Color::Black),
+                LineStyle::Selected => (Color::Black, Color::LightGray),
+                LineStyle::Status => (Color::Yellow, Color::Black),
+            };
+            output.set_color(foreground, background)?;
+            output.set_cursor_position(0, row)?;
+            // Writing to the last cell of the screen would scroll it.
+            let _ = output.write_str(&fit(&line.text, columns - 1));
+        }
+        Ok(())
+    }
+
+    /// Returns the lines of the screen for a console of `columns` by
+    /// `rows` characters.
+    fn screen(&self, columns: usize, rows: usize) -> Vec<ScreenLine> {
+        let line = |text: String, style| ScreenLine { text, style };
+        let mut lines = Vec::new();
+        let title = self.formset.title.as_deref().unwrap_or("Setup");
+        lines.push(line(center(title, columns), LineStyle::Title));
+        let form_title = self
+            .form()
+            .and_then(|form| form.title.as_deref())
+            .unwrap_or_default();
+        lines.push(line(center(form_title, columns), LineStyle::Title));
+        lines.push(line(String::new(), LineStyle::Normal));
+
+        // Questions, scrolled to keep the selection visible.
+        let questions = self.form().map_or(&[][..], |form| &form.questions);
+        let height = rows.saturating_sub(6).max(1);
+        let first = self.selected.saturating_sub(height - 1);
+        let prompt_width = (columns / 2).saturating_sub(3);
+        for (index, question) in questions.iter().enumerate().skip(first).take(height) {
+            let prompt = question.prompt.as_deref().unwrap_or_default();
+            let mut text = format!("  {}", fit(prompt, prompt_width));
+            while text.chars().count() < columns / 2 {
+                text.push(' ');
+            }
+            text.push_str(&self.value_text(question, index == self.selected));
+            let style = if index == self.selected {
+                LineStyle::Selected
+            } else if self.is_editable(question) {
+                LineStyle::Normal
+            } else {
+                LineStyle::Disabled
+            };
+            lines.push(line(text, style));
+        }
+        while lines.len() < rows.saturating_sub(3) {
+            lines.push(line(String::new(), LineStyle::Normal));
+        }
+
+        lines.push(line(String::new(), LineStyle::Normal));
+        let (status, style) = match &self.status {
+            Some(status) => (status.as_str(), LineStyle::Status),
+            None => (
+                self.selected()
+                    .and_then(|question| question.help.as_deref())
+                    .unwrap_or_default(),
+                LineStyle::Normal,
+            ),
+        };
+        lines.push(line(status.into(), style));
+        lines.push(line(HINTS.into(), LineStyle::Title));
+        lines.truncate(rows);
+        lines
+    }
+
+    /// Returns the text shown for the value of `question`.
+    fn value_text(&self, question: &QuestionInfo, selected: bool) -> String {
+        if question.kind == QuestionKind::Ref {
+            return if question.target_form.is_some() {
+                ">".into()
+            } else {
+                String::new()
+            };
+        }
+        if !is_integer(question) {
+            return if question.kind == QuestionKind::Action {
+                String::new()
+            } else {
+                "(not supported)".into()
+            };
+        }
+        let Some(value) = self.value(question) else {
+            return "(unavailable)".into();
+        };
+        match question.kind {
+            QuestionKind::Checkbox => if value != 0 { "[X]" } else { "[ ]" }.into(),
+            QuestionKind::OneOf => {
+                let text = question
+                    .option(value)
+                    .and_then(|option| option.text.as_deref());
+                match text {
+                    Some(text) => format!("<{text}>"),
+                    None => format!("<{value}>"),
+                }
+            }
+            _ => match self.typed.filter(|_| selected) {
+                Some(typed) => format!("[{typed}_]"),
+                None => format!("[{value}]"),
+            },
+        }
+    }
+
+    /// Returns whether `question` can be changed or activated.
+    fn is_editable(&self, question: &QuestionInfo) -> bool {
+        if question.flags.contains(IfrQuestionFlags::READ_ONLY) {
+            return false;
+        }
+        match question.kind {
+            QuestionKind::Ref => question
+                .target_form
+                .is_some_and(|id| self.formset.forms.iter().any(|form| form.id == id)),
+            QuestionKind::Checkbox | QuestionKind::Numeric | QuestionKind::OneOf => question
+                .varstore
+                .is_some_and(|id| self.stores.contains_key(&id)),
+            _ => false,
+        }
+    }
+
+    /// Returns the index of the first editable question at or after
+    /// `start` if `forward`, or at or before it otherwise.
+    fn next_selectable(&self, start: usize, forward: bool) -> Option<usize> {
+        let questions = &self.form()?.questions;
+        if forward {
+            (start..questions.len()).find(|&i| self.is_editable(&questions[i]))
+        } else {
+            (0..=start.min(questions.len().checked_sub(1)?))
+                .rev()
+                .find(|&i| self.is_editable(&questions[i]))
+        }
+    }
+
+    fn move_selection(&mut self, forward: bool) {
+        self.typed = None;
+        let next = if forward {
+            self.next_selectable(self.selected + 1, true)
+        } else {
+            self.selected
+                .checked_sub(1)
+                .and_then(|start| self.next_selectable(start, false))
+        };
+        if let Some(next) = next {
+            self.selected = next;
+        }
+    }
+
+    /// Toggles, opens, or commits the selected question.
+    fn activate(&mut self) {
+        let Some(question) = self.selected() else {
+            return;
+        };
+        match question.kind {
+            QuestionKind::Checkbox => {
+                if let Some(value) = self.value(question) {
+                    self.set_value(question, u64::from(value == 0));
+                }
+            }
+            QuestionKind::Ref => {
+                let target = question
+                    .target_form
+                    .and_then(|id| self.formset.forms.iter().position(|form| form.id == id));
+                if let Some(target) = target.filter(|_| self.is_editable(question)) {
+                    self.history.push((self.form, self.selected));
+                    self.form = target;
+                    self.selected = self.next_selectable(0, true).unwrap_or(0);
+                }
+            }
+            QuestionKind::Numeric => self.commit_typed(),
+            QuestionKind::OneOf => self.step(true),
+            _ => {}
+        }
+    }
+
+    /// Changes a numeric question by its step, or selects the next or
+    /// previous option of a one-of question.
+    fn step(&mut self, up: bool) {
+        let Some(question) = self.selected() else {
+            return;
+        };
+        self.typed = None;
+        let Some(value) = self.value(question) else {
+            return;
+        };
+        let next = match question.kind {
+            QuestionKind::Numeric => {
+                let Some(range) = question.range else {
+                    return;
+                };
+                let step = range.step.max(1);
+                if up {
+                    value.saturating_add(step).min(range.max)
+                } else {
+                    value.saturating_sub(step).max(range.min)
+                }
+            }
+            QuestionKind::OneOf => {
+                let options = &question.options;
+                if options.is_empty() {
+                    return;
+                }
+                let index = options.iter().position(|option| option.value == value);
+                let next = match (index, up) {
+                    (None, _) => 0,
+                    (Some(i), true) => (i + 1) % options.len(),
+                    (Some(i), false) => (i + options.len() - 1) % options.len(),
+                };
+                options[next].value
+            }
+            _ => return,
+        };
+        self.set_value(question, next);
+    }
+
+    fn type_digit(&mut self, digit: u64) {
+        let Some(question) = self.selected() else {
+            return;
+        };
+        if question.kind != QuestionKind::Numeric || !self.is_editable(question) {
+            return;
+        }
+        let typed = self.typed.unwrap_or(0);
+        self.typed = typed.checked_mul(10).and_then(|t| t.checked_add(digit));
+    }
+
+    fn commit_typed(&mut self) {
+        let (Some(question), Some(typed)) = (self.selected(), self.typed.take()) else {
+            return;
+        };
+        if !self.set_value(question, typed) {
+            self.status = Some(match question.range {
+                Some(range) => format!("The value must be between {} and {}", range.min, range.max),
+                None => "Invalid value".into(),
+            });
+        }
+    }
+
+    fn escape(&mut self) -> Option<BrowserExit> {
+        if self.typed.take().is_some() {
+            return None;
+        }
+        if let Some((form, selected)) = self.history.pop() {
+            self.form = form;
+            self.selected = selected;
+            return None;
+        }
+        if self.is_modified() && !self.confirm_discard {
+            self.confirm_discard = true;
+            self.status = Some("Press Esc again to discard the changes, or F10 to save".into());
+            return None;
+        }
+        Some(BrowserExit::Discarded)
+    }
+}
+
+/// Returns whether the value of `question` is an integer.
+fn is_integer(question: &QuestionInfo) -> bool {
+    matches!(
+        question.kind,
+        QuestionKind::Checkbox | QuestionKind::Numeric | QuestionKind::OneOf
+    ) && (1..=8).contains(&question.size)
+}
+
+/// Returns whether `value` is in the range or options of `question`.
+fn is_valid(question: &QuestionInfo, value: u64) -> bool {
+    match question.kind {
+        QuestionKind::Checkbox => value <= 1,
+        QuestionKind::Numeric => question.range.is_none_or(|range| {
+            (range.min..=range.max).contains(&value)
+                && (range.step == 0 || (value - range.min) % range.step == 0)
+        }),
+        QuestionKind::OneOf => question.option(value).is_some(),
+        _ => false,
+    }
+}
+
+/// Truncates `text` to `width` characters.
+fn fit(text: &str, width: usize) -> String {
+    text.chars().take(width).collect()
+}
+
+/// Centers `text` in a line of `width` characters.
+fn center(text: &str, width: usize) -> String {
+    let text = fit(text, width);
+    let padding = (width - text.chars().count()) / 2;
+    format!("{:padding$}{text}", "")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::hii::form::{FormSetBuilder, NumericSize, Question};
+    use crate::proto::hii::package::PackageListBuilder;
+    use crate::proto::hii::settings::Settings;
+    use crate::runtime::VariableAttributes;
+    use crate::{Char16, Guid, cstr8, cstr16, guid};
+
+    const GUID: Guid = guid!("6f7e1c7b-5d0b-4a8f-9a53-1c2b5d8e4f10");
+
+    /// Varstores in memory.
+    #[derive(Default)]
+    struct MemoryVarstores {
+        data: BTreeMap<String, Vec<u8>>,
+        stores: usize,
+    }
+
+    impl VarstoreAccess for MemoryVarstores {
+        fn load(&mut self, _: &FormSetInfo, varstore: &VarstoreInfo) -> Result<Vec<u8>> {
+            self.data
+                .get(&varstore.name)
+                .cloned()
+                .ok_or(Status::NOT_FOUND.into())
+        }
+
+        fn store(&mut self, _: &FormSetInfo, varstore: &VarstoreInfo, data: &[u8]) -> Result {
+            self.stores += 1;
+            self.data.insert(varstore.name.clone(), data.to_vec());
+            Ok(())
+        }
+    }
+
+    fn settings() -> Settings {
+        let attributes = VariableAttributes::NON_VOLATILE | VariableAttributes::BOOTSERVICE_ACCESS;
+        let ifr = FormSetBuilder::new(GUID, 1, 2)
+            .varstore_efi(1, GUID, cstr8!("Setup"), attributes, 4)
+            .form(1, 3, |form| {
+                form.checkbox(Question::new(1, 4, 2).varstore(1, 0), true)
+                    .numeric(
+                        Question::new(2, 5, 2).varstore(1, 2),
+                        NumericSize::U16,
+                        1..=100,
+                        1,
+                        10,
+                    )
+                    .one_of(
+                        Question::new(3, 6, 2).varstore(1, 1),
+                        NumericSize::U8,
+                        &[(7, 0), (8, 1)],
+                        1,
+                    )
+                    .goto(Question::new(4, 9, 2), 2);
+            })
+            .form(2, 9, |form| {
+                form.checkbox(Question::new(5, 4, 2), false);
+            })
+            .build();
+        let strings = [
+            cstr16!("Setup"),
+            cstr16!("Help"),
+            cstr16!("Main"),
+            cstr16!("Enable"),
+            cstr16!("Level"),
+            cstr16!("Mode"),
+            cstr16!("Off"),
+            cstr16!("On"),
+            cstr16!("Advanced"),
+        ];
+        let dump = PackageListBuilder::new(GUID)
+            .strings(cstr8!("en-US"), &strings)
+            .forms(&ifr)
+            .build();
+        Settings::parse(&dump, "en-US").unwrap()
+    }
+
+    fn memory() -> MemoryVarstores {
+        let mut access = MemoryVarstores::default();
+        access.data.insert("Setup".into(), vec![0, 1, 50, 0]);
+        access
+    }
+
+    fn key(c: char) -> Key {
+        Key::Printable(Char16::try_from(c).unwrap())
+    }
+
+    #[test]
+    fn test_edit() {
+        let settings = settings();
+        let formset = &settings.formsets[0];
+        let mut browser = Browser::new(formset, memory());
+        let [checkbox, numeric, one_of, _] = &formset.forms[0].questions[..] else {
+            panic!("unexpected questions");
+        };
+        assert_eq!(browser.selected(), Some(checkbox));
+        assert_eq!(browser.value(checkbox), Some(0));
+        assert_eq!(browser.value(numeric), Some(50));
+        assert_eq!(browser.value(one_of), Some(1));
+
+        assert_eq!(browser.handle_key(key(' ')), None);
+        assert_eq!(browser.value(checkbox), Some(1));
+        assert!(browser.is_modified());
+
+        browser.handle_key(Key::Special(ScanCode::DOWN));
+        assert_eq!(browser.selected(), Some(numeric));
+        browser.handle_key(key('+'));
+        assert_eq!(browser.value(numeric), Some(51));
+        for c in ['2', '0', '0', '\r'] {
+            browser.handle_key(key(c));
+        }
+        assert_eq!(browser.value(numeric), Some(51));
+        assert!(browser.status.is_some());
+        for c in ['4', '2', '\r'] {
+            browser.handle_key(key(c));
+        }
+        assert_eq!(browser.value(numeric), Some(42));
+
+        browser.handle_key(Key::Special(ScanCode::DOWN));
+        browser.handle_key(Key::Special(ScanCode::RIGHT));
+        assert_eq!(browser.value(one_of), Some(0));
+        assert!(!browser.set_value(one_of, 2));
+
+        browser.handle_key(Key::Special(ScanCode::FUNCTION_9));
+        assert_eq!(browser.value(checkbox), Some(1));
+        assert_eq!(browser.value(numeric), Some(10));
+        assert_eq!(browser.value(one_of), Some(1));
+
+        assert_eq!(
+            browser.handle_key(Key::Special(ScanCode::FUNCTION_10)),
+            Some(BrowserExit::Saved)
+        );
+        assert!(!browser.is_modified());
+        assert_eq!(browser.access().data["Setup"], [1, 1, 10, 0]);
+        assert_eq!(browser.access().stores, 1);
+    }
+
+    #[test]
+    fn test_navigation() {
+        let settings = settings();
+        let formset = &settings.formsets[0];
+        let mut browser = Browser::new(formset, memory());
+        for _ in 0..5 {
+            browser.handle_key(Key::Special(ScanCode::DOWN));
+        }
+        assert_eq!(browser.selected().unwrap().kind, QuestionKind::Ref);
+
+        // The linked form has only a question without varstore.
+        browser.handle_key(key('\r'));
+        assert_eq!(browser.form().unwrap().id, 2);
+        let screen = browser.screen(40, 10);
+        assert_eq!(screen.len(), 10);
+        assert_eq!(screen[1].text.trim(), "Advanced");
+        assert_eq!(screen[3].text, format!("  Enable{:12}(unavailable)", ""));
+        assert_eq!(screen[3].style, LineStyle::Selected);
+
+        assert_eq!(browser.handle_key(Key::Special(ScanCode::ESCAPE)), None);
+        assert_eq!(browser.form().unwrap().id, 1);
+        assert_eq!(browser.selected().unwrap().kind, QuestionKind::Ref);
+
+        // Unsaved changes need a second Esc.
+        browser.handle_key(Key::Special(ScanCode::LEFT));
+        browser.handle_key(Key::Special(ScanCode::UP));
+        browser.handle_key(Key::Special(ScanCode::RIGHT));
+        assert!(browser.is_modified());
+        assert_eq!(browser.handle_key(Key::Special(ScanCode::ESCAPE)), None);
+        assert_eq!(
+            browser.handle_key(Key::Special(ScanCode::ESCAPE)),
+            Some(BrowserExit::Discarded)
+        );
+        assert_eq!(browser.access().stores, 0);
+    }
+
+    #[test]
+    fn test_screen() {
+        let settings = settings();
+        let formset = &settings.formsets[0];
+        let browser = Browser::new(formset, memory());
+        let screen = browser.screen(40, 12);
+        let text: Vec<_> = screen.iter().map(|line| line.text.as_str()).collect();
+        assert_eq!(
+            text,
+            [
+                "                 Setup",
+                "                  Main",
+                "",
+                "  Enable            [ ]",
+                "  Level             [50]",
+                "  Mode              <On>",
+                "  Advanced          >",
+                "",
+                "",
+                "",
+                "Help",
+                HINTS,
+            ]
+        );
+        assert_eq!(screen[3].style, LineStyle::Selected);
+
+        // Questions of missing varstores are unavailable.
+        let browser = Browser::new(formset, MemoryVarstores::default());
+        assert_eq!(browser.selected().unwrap().kind, QuestionKind::Ref);
+        let screen = browser.screen(40, 12);
+        assert_eq!(screen[3].text, "  Enable            (unavailable)");
+        assert_eq!(screen[3].style, LineStyle::Disabled);
+        assert_eq!(screen[10].text, "Can't read Setup: NOT_FOUND");
+    }
+}
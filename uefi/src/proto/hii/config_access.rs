@@ -0,0 +1,441 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Implementation of the HII Config Access protocol for Rust drivers.
+//!
+//! A driver publishing a setup form implements [`ConfigAccessHandler`] to
+//! read and write its configuration, wraps it in a [`ConfigAccessProvider`],
+//! and installs the provider on the handle of its package list.
+
+use super::QuestionId;
+use crate::boot::{self, MemoryType};
+use crate::proto::provider::{self, provider_from_protocol};
+use crate::{CStr16, CString16, Handle, Result, Status};
+use core::ptr;
+use uefi_raw::Char16;
+use uefi_raw::protocol::hii::config::{HiiConfigAccessProtocol, IfrTypeValue};
+use uefi_raw::protocol::hii::ifr::IfrType;
+
+pub use super::StringId;
+pub use uefi_raw::protocol::hii::config::{BrowserAction, HiiDate, HiiRef, HiiTime};
+pub use uefi_raw::protocol::hii::form_browser::BrowserActionRequest;
+
+/// Configuration access of a driver, called by the HII Config Routing
+/// protocol and by the form browser.
+///
+/// Configuration is exchanged as configuration strings, which can be built
+/// and parsed with [`ConfigurationString`].
+///
+/// [`ConfigurationString`]: super::config_str::ConfigurationString
+pub trait ConfigAccessHandler {
+    /// Returns the configuration selected by `request` as a `<ConfigAltResp>`
+    /// string. If `request` is `None`, all configuration of the driver is
+    /// returned.
+    ///
+    /// Fails with [`Status::NOT_FOUND`] if the request is for another
+    /// driver's configuration.
+    fn extract_config(&self, request: Option<&CStr16>) -> Result<CString16>;
+
+    /// Applies the `<ConfigResp>` string `configuration`.
+    ///
+    /// Fails with [`Status::NOT_FOUND`] if the configuration is for another
+    /// driver.
+    fn route_config(&self, configuration: &CStr16) -> Result;
+
+    /// Handles an action of the form browser on the question `question_id`.
+    /// `value` is the current value of the question if the question has one,
+    /// and changes to it are passed on to the form browser.
+    ///
+    /// Returns the action the form browser takes afterwards. The default
+    /// implementation fails with [`Status::UNSUPPORTED`], which tells the
+    /// form browser that no action is needed.
+    fn callback(
+        &self,
+        action: BrowserAction,
+        question_id: QuestionId,
+        value: Option<&mut CallbackValue>,
+    ) -> Result<BrowserActionRequest> {
+        let _ = (action, question_id, value);
+        Err(Status::UNSUPPORTED.into())
+    }
+}
+
+/// Value of a question passed to [`ConfigAccessHandler::callback`].
+#[derive(Clone, Copy, Debug)]
+pub enum CallbackValue {
+    /// An 8-bit number.
+    U8(u8),
+    /// A 16-bit number.
+    U16(u16),
+    /// A 32-bit number.
+    U32(u32),
+    /// A 64-bit number.
+    U64(u64),
+    /// A boolean.
+    Bool(bool),
+    /// A time.
+    Time(HiiTime),
+    /// A date.
+    Date(HiiDate),
+    /// The string of a string question.
+    String(StringId),
+    /// The result string of an action button.
+    Action(StringId),
+    /// A reference to another form.
+    Ref(HiiRef),
+    /// A value of another type, e.g. the buffer of an ordered list. Changes
+    /// to it are not passed on to the form browser.
+    Other(IfrType),
+}
+
+impl CallbackValue {
+    /// Reads a value of type `ty`.
+    ///
+    /// # Safety
+    ///
+    /// `value` must hold a value of type `ty`.
+    const unsafe fn read(ty: IfrType, value: &IfrTypeValue) -> Self {
+        unsafe {
+            match ty {
+                IfrType::NUM_SIZE_8 => Self::U8(value.u8),
+                IfrType::NUM_SIZE_16 => Self::U16(value.u16),
+                IfrType::NUM_SIZE_32 => Self::U32(value.u32),
+                IfrType::NUM_SIZE_64 => Self::U64(value.u64),
+                IfrType::BOOLEAN => Self::Bool(value.b.0 != 0),
+                IfrType::TIME => Self::Time(value.time),
+                IfrType::DATE => Self::Date(value.date),
+                IfrType::STRING => Self::String(value.string),
+                IfrType::ACTION => Self::Action(value.string),
+                IfrType::REF => Self::Ref(value.hii_ref),
+                _ => Self::Other(ty),
+            }
+        }
+    }
+
+    /// Writes the value back.
+    const fn write(self, value: &mut IfrTypeValue) {
+        match self {
+            Self::U8(v) => value.u8 = v,
+            Self::U16(v) => value.u16 = v,
+            Self::U32(v) => value.u32 = v,
+            Self::U64(v) => value.u64 = v,
+            Self::Bool(v) => value.b = uefi_raw::Boolean(v as u8),
+            Self::Time(v) => value.time = v,
+            Self::Date(v) => value.date = v,
+            Self::String(v) | Self::Action(v) => value.string = v,
+            Self::Ref(v) => value.hii_ref = v,
+            Self::Other(_) => {}
+        }
+    }
+}
+
+/// Implementation of the [`HiiConfigAccess`] protocol, which passes the
+/// calls on to a [`ConfigAccessHandler`].
+///
+/// ```no_run
+/// use uefi::proto::hii::config_access::{ConfigAccessHandler, ConfigAccessProvider};
+/// use uefi::{CStr16, CString16, Handle, Status};
+///
+/// struct Driver;
+///
+/// impl ConfigAccessHandler for Driver {
+///     fn extract_config(&self, request: Option<&CStr16>) -> uefi::Result<CString16> {
+///         Err(Status::NOT_FOUND.into())
+///     }
+///
+///     fn route_config(&self, configuration: &CStr16) -> uefi::Result {
+///         Err(Status::NOT_FOUND.into())
+///     }
+/// }
+///
+/// static CONFIG_ACCESS: ConfigAccessProvider<Driver> = ConfigAccessProvider::new(Driver);
+///
+/// # fn example(handle: Handle) -> uefi::Result {
+/// CONFIG_ACCESS.install(Some(handle))?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`HiiConfigAccess`]: super::config::HiiConfigAccess
+#[derive(Debug)]
+#[repr(C)]
+pub struct ConfigAccessProvider<H> {
+    // Must be the first field, so that the callbacks can get the provider
+    // from the protocol pointer.
+    protocol: HiiConfigAccessProtocol,
+    handler: H,
+}
+
+impl<H: ConfigAccessHandler> ConfigAccessProvider<H> {
+    /// Creates a provider passing calls on to `handler`.
+    #[must_use]
+    pub const fn new(handler: H) -> Self {
+        Self {
+            protocol: HiiConfigAccessProtocol {
+                extract_config: Self::extract_config,
+                route_config: Self::route_config,
+                callback: Self::callback,
+            },
+            handler,
+        }
+    }
+
+    /// Returns the handler.
+    #[must_use]
+    pub const fn handler(&self) -> &H {
+        &self.handler
+    }
+
+    /// Returns a pointer to the end of the null-terminated `string`.
+    unsafe fn end_of(string: *const Char16) -> *const Char16 {
+        let len = unsafe { CStr16::from_ptr(string.cast()) }.num_chars();
+        unsafe { string.add(len) }
+    }
+
+    unsafe extern "efiapi" fn extract_config(
+        this: *const HiiConfigAccessProtocol,
+        request: *const Char16,
+        progress: *mut *const Char16,
+        results: *mut *const Char16,
+    ) -> Status {
+        // SAFETY: the firmware passes the protocol installed by `install`.
+        let this: &Self = unsafe { provider_from_protocol(this) };
+        if progress.is_null() || results.is_null() {
+            return Status::INVALID_PARAMETER;
+        }
+        unsafe { progress.write(request) };
+
+        let request_str = (!request.is_null()).then(|| unsafe { CStr16::from_ptr(request.cast()) });
+        let response = match this.handler.extract_config(request_str) {
+            Ok(response) => response,
+            Err(err) => return err.status(),
+        };
+
+        // The caller frees the results with `FreePool`.
+        let size = response.num_bytes();
+        let Ok(buffer) = boot::allocate_pool(MemoryType::BOOT_SERVICES_DATA, size) else {
+            return Status::OUT_OF_RESOURCES;
+        };
+        unsafe {
+            ptr::copy_nonoverlapping(response.as_ptr().cast::<u8>(), buffer.as_ptr(), size);
+            results.write(buffer.as_ptr().cast());
+            if !request.is_null() {
+                progress.write(Self::end_of(request));
+            }
+        }
+        Status::SUCCESS
+    }
+
+    unsafe extern "efiapi" fn route_config(
+        this: *const HiiConfigAccessProtocol,
+        configuration: *const Char16,
+        progress: *mut *const Char16,
+    ) -> Status {
+        // SAFETY: as in `extract_config`.
+        let this: &Self = unsafe { provider_from_protocol(this) };
+        if configuration.is_null() || progress.is_null() {
+            return Status::INVALID_PARAMETER;
+        }
+        unsafe { progress.write(configuration) };
+
+        let configuration_str = unsafe { CStr16::from_ptr(configuration.cast()) };
+        match this.handler.route_config(configuration_str) {
+            Ok(()) => {
+                unsafe { progress.write(Self::end_of(configuration)) };
+                Status::SUCCESS
+            }
+            Err(err) => err.status(),
+        }
+    }
+
+    unsafe extern "efiapi" fn callback(
+        this: *const HiiConfigAccessProtocol,
+        action: BrowserAction,
+        question_id: QuestionId,
+        value_type: u8,
+        value: *mut IfrTypeValue,
+        action_request: *mut BrowserActionRequest,
+    ) -> Status {
+        // SAFETY: as in `extract_config`.
+        let this: &Self = unsafe { provider_from_protocol(this) };
+        // SAFETY: the form browser passes a value of type `value_type`.
+        let mut callback_value = unsafe { value.as_ref() }
+            .map(|value| unsafe { CallbackValue::read(IfrType(value_type), value) });
+
+        let result = this
+            .handler
+            .callback(action, question_id, callback_value.as_mut());
+        match result {
+            Ok(request) => {
+                if let (Some(value), Some(callback_value)) =
+                    (unsafe { value.as_mut() }, callback_value)
+                {
+                    callback_value.write(value);
+                }
+                if !action_request.is_null() {
+                    unsafe { action_request.write(request) };
+                }
+                Status::SUCCESS
+            }
+            Err(err) => err.status(),
+        }
+    }
+}
+
+impl<H: ConfigAccessHandler + 'static> ConfigAccessProvider<H> {
+    /// Installs the protocol on `handle`, or on a new handle if `handle` is
+    /// `None`, and returns the handle.
+    ///
+    /// The handle must be the handle passed to
+    /// [`HiiDatabase::new_package_list`] when publishing the package list
+    /// containing the driver's forms, which usually also carries the device
+    /// path of the configuration.
+    ///
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::INVALID_PARAMETER`]: the protocol is already installed on
+    ///   `handle`.
+    /// * [`Status::OUT_OF_RESOURCES`]: a new handle could not be allocated.
+    ///
+    /// [`HiiDatabase::new_package_list`]: super::database::HiiDatabase::new_package_list
+    pub fn install(&'static self, handle: Option<Handle>) -> Result<Handle> {
+        provider::install_static(handle, &HiiConfigAccessProtocol::GUID, &self.protocol)
+    }
+
+    /// Uninstalls the protocol from `handle`. Remove the package list
+    /// published with the handle first, so that the form browser no longer
+    /// calls the handler.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::NOT_FOUND`]: this provider is not installed on `handle`.
+    /// * [`Status::ACCESS_DENIED`]: the protocol is still open by another
+    ///   agent.
+    pub fn uninstall(&'static self, handle: Handle) -> Result {
+        provider::uninstall_static(handle, &HiiConfigAccessProtocol::GUID, &self.protocol)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cstr16;
+    use core::cell::Cell;
+
+    struct Handler {
+        level: Cell<u16>,
+    }
+
+    impl ConfigAccessHandler for Handler {
+        fn extract_config(&self, _request: Option<&CStr16>) -> Result<CString16> {
+            Err(Status::NOT_FOUND.into())
+        }
+
+        fn route_config(&self, configuration: &CStr16) -> Result {
+            if configuration == cstr16!("GUID=00&NAME=00&PATH=00&OFFSET=0&WIDTH=2&VALUE=2a") {
+                self.level.set(42);
+                Ok(())
+            } else {
+                Err(Status::NOT_FOUND.into())
+            }
+        }
+
+        fn callback(
+            &self,
+            action: BrowserAction,
+            question_id: QuestionId,
+            value: Option<&mut CallbackValue>,
+        ) -> Result<BrowserActionRequest> {
+            match (action, question_id, value) {
+                (BrowserAction::RETRIEVE, 1, Some(CallbackValue::U16(value))) => {
+                    *value = self.level.get();
+                    Ok(BrowserActionRequest::NONE)
+                }
+                (BrowserAction::CHANGED, 1, Some(CallbackValue::U16(value))) => {
+                    self.level.set(*value);
+                    Ok(BrowserActionRequest::SUBMIT)
+                }
+                _ => Err(Status::UNSUPPORTED.into()),
+            }
+        }
+    }
+
+    fn provider() -> ConfigAccessProvider<Handler> {
+        ConfigAccessProvider::new(Handler {
+            level: Cell::new(10),
+        })
+    }
+
+    #[test]
+    fn test_route_config() {
+        let provider = provider();
+        let mut progress = ptr::null();
+
+        let config = cstr16!("GUID=00&NAME=00&PATH=00&OFFSET=0&WIDTH=2&VALUE=2a");
+        let status = unsafe {
+            (provider.protocol.route_config)(
+                &provider.protocol,
+                config.as_ptr().cast(),
+                &mut progress,
+            )
+        };
+        assert_eq!(status, Status::SUCCESS);
+        assert_eq!(provider.handler().level.get(), 42);
+        assert_eq!(
+            progress,
+            unsafe { config.as_ptr().add(config.num_chars()) }.cast()
+        );
+
+        let config = cstr16!("GUID=01&NAME=00&PATH=00");
+        let status = unsafe {
+            (provider.protocol.route_config)(
+                &provider.protocol,
+                config.as_ptr().cast(),
+                &mut progress,
+            )
+        };
+        assert_eq!(status, Status::NOT_FOUND);
+        assert_eq!(progress, config.as_ptr().cast());
+    }
+
+    #[test]
+    fn test_callback() {
+        let provider = provider();
+        let callback = |action, question_id, value: &mut IfrTypeValue| {
+            let mut request = BrowserActionRequest::EXIT;
+            let status = unsafe {
+                (provider.protocol.callback)(
+                    &provider.protocol,
+                    action,
+                    question_id,
+                    IfrType::NUM_SIZE_16.0,
+                    value,
+                    &mut request,
+                )
+            };
+            (status, request)
+        };
+
+        // The value is passed back to the form browser.
+        let mut value = IfrTypeValue { u64: 0 };
+        assert_eq!(
+            callback(BrowserAction::RETRIEVE, 1, &mut value),
+            (Status::SUCCESS, BrowserActionRequest::NONE)
+        );
+        assert_eq!(unsafe { value.u16 }, 10);
+
+        // The value is passed to the handler.
+        value.u16 = 7;
+        assert_eq!(
+            callback(BrowserAction::CHANGED, 1, &mut value),
+            (Status::SUCCESS, BrowserActionRequest::SUBMIT)
+        );
+        assert_eq!(provider.handler().level.get(), 7);
+
+        // Unsupported actions leave the action request alone.
+        assert_eq!(
+            callback(BrowserAction::FORM_OPEN, 1, &mut value),
+            (Status::UNSUPPORTED, BrowserActionRequest::EXIT)
+        );
+    }
+}
@@ -2,14 +2,14 @@
 
 //! HII Configuration protocols.
 
-use core::ptr;
+use core::ptr::{self, NonNull};
 
 use alloc::string::{String, ToString};
 use uefi_macros::unsafe_protocol;
 use uefi_raw::Char16;
 use uefi_raw::protocol::hii::config::HiiConfigRoutingProtocol;
 
-use crate::{CStr16, StatusExt};
+use crate::{CStr16, Status, StatusExt, boot};
 
 /// The HII Configuration Routing Protocol.
 ///
@@ -29,10 +29,131 @@ impl HiiConfigRouting {
     ///
     /// Use `super::config_str::MultiConfigurationStringIter` to parse the returned `String`.
     pub fn export(&self) -> uefi::Result<String> {
+        let mut results: *const Char16 = ptr::null();
         unsafe {
-            let mut results: *const Char16 = ptr::null();
-            (self.0.export_config)(&self.0, &mut results)
-                .to_result_with_val(|| CStr16::from_ptr(results.cast()).to_string())
+            (self.0.export_config)(&self.0, &mut results).to_result()?;
+            Ok(take_pool_string(results))
         }
     }
+
+    /// Request the configuration described by `request`, a configuration
+    /// string in `<MultiConfigRequest>` format, from the drivers owning it.
+    ///
+    /// Use `super::config_str::MultiConfigurationStringIter` to parse the returned `String`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::NOT_FOUND`]: no driver provides the requested configuration.
+    /// * [`Status::INVALID_PARAMETER`]: the request is malformed.
+    ///
+    /// [`Status::NOT_FOUND`]: uefi::Status::NOT_FOUND
+    /// [`Status::INVALID_PARAMETER`]: uefi::Status::INVALID_PARAMETER
+    pub fn extract_config(&self, request: &CStr16) -> uefi::Result<String> {
+        let mut progress: *const Char16 = ptr::null();
+        let mut results: *const Char16 = ptr::null();
+        unsafe {
+            (self.0.extract_config)(
+                &self.0,
+                request.as_ptr().cast(),
+                &mut progress,
+                &mut results,
+            )
+            .to_result()?;
+            Ok(take_pool_string(results))
+        }
+    }
+
+    /// Route `configuration`, a configuration string in
+    /// `<MultiConfigResp>` format, to the drivers owning it.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::NOT_FOUND`]: no driver accepts the configuration.
+    /// * [`Status::INVALID_PARAMETER`]: the configuration is malformed.
+    ///
+    /// [`Status::NOT_FOUND`]: uefi::Status::NOT_FOUND
+    /// [`Status::INVALID_PARAMETER`]: uefi::Status::INVALID_PARAMETER
+    pub fn route_config(&self, configuration: &CStr16) -> uefi::Result {
+        let mut progress: *const Char16 = ptr::null();
+        unsafe { (self.0.route_config)(&self.0, configuration.as_ptr().cast(), &mut progress) }
+            .to_result()
+    }
+
+    /// Converts the bytes of `block`, a varstore buffer, to a configuration
+    /// string in `<ConfigResp>` format.
+    ///
+    /// `request` is a `<ConfigRequest>` string: a `<ConfigHdr>` followed by
+    /// the `OFFSET` and `WIDTH` pairs of the bytes to convert.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::INVALID_PARAMETER`]: the request is malformed.
+    /// * [`Status::DEVICE_ERROR`]: the request references bytes outside of
+    ///   `block`.
+    ///
+    /// [`Status::INVALID_PARAMETER`]: uefi::Status::INVALID_PARAMETER
+    /// [`Status::DEVICE_ERROR`]: uefi::Status::DEVICE_ERROR
+    pub fn block_to_config(&self, request: &CStr16, block: &[u8]) -> uefi::Result<String> {
+        let mut config: *const Char16 = ptr::null();
+        let mut progress: *const Char16 = ptr::null();
+        unsafe {
+            (self.0.block_to_config)(
+                &self.0,
+                request.as_ptr().cast(),
+                block.as_ptr(),
+                block.len(),
+                &mut config,
+                &mut progress,
+            )
+            .to_result()?;
+            Ok(take_pool_string(config))
+        }
+    }
+
+    /// Writes the values of `config`, a configuration string in
+    /// `<ConfigResp>` format, to `block`, a varstore buffer. Bytes not
+    /// referenced by `config` are left unchanged.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::BUFFER_TOO_SMALL`]: `block` is too small for the
+    ///   offsets in `config`. The required size is returned as error data.
+    /// * [`Status::INVALID_PARAMETER`]: the configuration is malformed.
+    ///
+    /// [`Status::BUFFER_TOO_SMALL`]: uefi::Status::BUFFER_TOO_SMALL
+    /// [`Status::INVALID_PARAMETER`]: uefi::Status::INVALID_PARAMETER
+    pub fn config_to_block(
+        &self,
+        config: &CStr16,
+        block: &mut [u8],
+    ) -> uefi::Result<(), Option<usize>> {
+        let mut size = block.len();
+        let mut progress: *const Char16 = ptr::null();
+        unsafe {
+            (self.0.config_to_block)(
+                &self.0,
+                config.as_ptr().cast(),
+                block.as_mut_ptr(),
+                &mut size,
+                &mut progress,
+            )
+        }
+        .to_result_with_err(|status| (status == Status::BUFFER_TOO_SMALL).then_some(size))
+    }
+}
+
+/// Copies a string allocated from pool by the routing protocol, and frees
+/// the pool allocation.
+///
+/// # Safety
+///
+/// `string` must be null or a valid null-terminated string allocated from
+/// pool.
+unsafe fn take_pool_string(string: *const Char16) -> String {
+    let Some(ptr) = NonNull::new(string.cast_mut()) else {
+        return String::new();
+    };
+    let copy = unsafe { CStr16::from_ptr(ptr.as_ptr().cast()) }.to_string();
+    let _ = unsafe { boot::free_pool(ptr.cast()) };
+    copy
 }
@@ -1,10 +1,11 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-//! UEFI Configuration String parsing according to Spec 35.2.1
+//! UEFI Configuration String parsing and encoding according to Spec 35.2.1
 
 use alloc::boxed::Box;
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter, Write};
 use core::iter::Peekable;
 use core::slice;
 use core::str::{self, FromStr};
@@ -98,16 +99,271 @@ pub struct ConfigurationStringElement {
     // nvconfig: HashMap<String, Vec<u8>>,
 }
 
+/// A name/value pair within a UEFI Configuration String, used by name/value
+/// varstores instead of [`ConfigurationStringElement`]s.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ConfigurationStringNameValue {
+    /// Name of the value
+    pub name: String,
+    /// Value bytes
+    pub value: Vec<u8>,
+}
+
 /// Internal module with known keys found in uefi configuration strings.
 mod keys {
     pub const ALTCFG: &str = "ALTCFG";
     pub const GUID: &str = "GUID";
     pub const NAME: &str = "NAME";
     pub const OFFSET: &str = "OFFSET";
+    pub const PATH: &str = "PATH";
     pub const VALUE: &str = "VALUE";
     pub const WIDTH: &str = "WIDTH";
 }
 
+/// A UEFI {ConfigHdr}, which routes a configuration string to a varstore.
+///
+/// It consists of the GUID and name of the varstore, and the device path of
+/// the handle the driver owning the varstore installed its HII Config Access
+/// protocol on. Its [`Display`] implementation encodes it as
+/// `GUID=...&NAME=...&PATH=...`, and [`FromStr`] parses the header at the
+/// start of a configuration string.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ConfigHdr {
+    /// GUID of the varstore
+    pub guid: Guid,
+    /// Name of the varstore
+    pub name: String,
+    /// Device path of the driver handle
+    pub device_path: Box<DevicePath>,
+}
+
+impl ConfigHdr {
+    /// Creates a header for the varstore with `guid` and `name`, owned by
+    /// the driver on the handle with `device_path`.
+    #[must_use]
+    pub fn new(guid: Guid, name: &CStr16, device_path: &DevicePath) -> Self {
+        Self {
+            guid,
+            name: name.to_string(),
+            device_path: device_path.to_boxed(),
+        }
+    }
+
+    /// Returns whether the configuration string `config` starts with a
+    /// header for the same varstore. Like in EDK2, only the GUID and name
+    /// are compared, since the device path was already used to route the
+    /// string to the driver.
+    ///
+    /// This is useful in [`ConfigAccessHandler`] implementations, to check
+    /// whether a request is for a varstore of the driver.
+    ///
+    /// [`ConfigAccessHandler`]: super::config_access::ConfigAccessHandler
+    #[must_use]
+    pub fn matches(&self, config: &str) -> bool {
+        let mut splitter = ConfigurationStringIter::new(config);
+        let guid = match splitter.next() {
+            Some((keys::GUID, Some(guid))) => ConfigurationString::parse_guid_from_hex(guid),
+            _ => None,
+        };
+        let name = match splitter.next() {
+            Some((keys::NAME, Some(name))) => ConfigurationString::parse_string_from_hex(name),
+            _ => None,
+        };
+        guid == Some(self.guid) && name.as_deref() == Some(self.name.as_str())
+    }
+
+    /// Creates a {ConfigRequest} for the whole varstore. Add blocks or names
+    /// to request only parts of it.
+    #[must_use]
+    pub fn request(&self) -> ConfigRequest {
+        ConfigRequest {
+            header: self.clone(),
+            blocks: Vec::new(),
+            names: Vec::new(),
+        }
+    }
+
+    /// Parses the header from the given kv-pair iterator.
+    fn parse_from(
+        splitter: &mut Peekable<ConfigurationStringIter<'_>>,
+    ) -> Result<Self, ParseError> {
+        let guid = ConfigurationString::try_parse_with(
+            ParseError::ConfigHdr(ConfigHdrSection::Guid),
+            || {
+                let v = splitter.next()?;
+                let v = (v.0 == keys::GUID).then_some(v.1).flatten()?;
+                ConfigurationString::parse_guid_from_hex(v)
+            },
+        )?;
+        let name = ConfigurationString::try_parse_with(
+            ParseError::ConfigHdr(ConfigHdrSection::Name),
+            || {
+                let v = splitter.next()?;
+                let v = (v.0 == keys::NAME).then_some(v.1).flatten()?;
+                ConfigurationString::parse_string_from_hex(v)
+            },
+        )?;
+        let device_path = ConfigurationString::try_parse_with(
+            ParseError::ConfigHdr(ConfigHdrSection::Path),
+            || {
+                let v = splitter.next()?.1?;
+                let v: Vec<_> = ConfigurationString::parse_bytes_from_hex(v).collect();
+                let v = <&DevicePath>::try_from(v.as_slice()).ok()?;
+                Some(v.to_boxed())
+            },
+        )?;
+        Ok(Self {
+            guid,
+            name,
+            device_path,
+        })
+    }
+}
+
+impl Clone for ConfigHdr {
+    fn clone(&self) -> Self {
+        Self {
+            guid: self.guid,
+            name: self.name.clone(),
+            device_path: self.device_path.to_boxed(),
+        }
+    }
+}
+
+impl Display for ConfigHdr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_header(f, &self.guid, &self.name, &self.device_path)
+    }
+}
+
+/// Parses the {ConfigHdr} at the start of a configuration string, ignoring
+/// the rest.
+impl FromStr for ConfigHdr {
+    type Err = ParseError;
+
+    fn from_str(bfr: &str) -> Result<Self, Self::Err> {
+        Self::parse_from(&mut ConfigurationStringIter::new(bfr).peekable())
+    }
+}
+
+/// Writes the {ConfigHdr} for the varstore with `guid` and `name`.
+fn write_header(f: &mut Formatter<'_>, guid: &Guid, name: &str, path: &DevicePath) -> fmt::Result {
+    write!(
+        f,
+        "{}={}&{}={}&{}={}",
+        keys::GUID,
+        ConfigurationString::encode_guid_to_hex(guid),
+        keys::NAME,
+        ConfigurationString::encode_string_to_hex(name),
+        keys::PATH,
+        ConfigurationString::encode_bytes_to_hex(path.as_bytes().iter().copied()),
+    )
+}
+
+/// A UEFI {BlockName}: a range of a buffer varstore in a [`ConfigRequest`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ConfigBlockName {
+    /// Byte offset in the configuration block
+    pub offset: u64,
+    /// Length of the range
+    pub width: u64,
+}
+
+/// A UEFI {ConfigRequest}, which requests the values of a varstore with
+/// [`HiiConfigRouting::extract_config`] or
+/// [`ConfigAccessHandler::extract_config`].
+///
+/// It consists of a [`ConfigHdr`], followed by the ranges of a buffer
+/// varstore or the names of a name/value varstore to request. If there are
+/// neither, the whole varstore is requested. The response can be parsed
+/// with [`MultiConfigurationStringIter`] into [`ConfigurationString`]s.
+///
+/// [`HiiConfigRouting::extract_config`]: super::config_routing::HiiConfigRouting::extract_config
+/// [`ConfigAccessHandler::extract_config`]: super::config_access::ConfigAccessHandler::extract_config
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConfigRequest {
+    /// Header of the varstore
+    pub header: ConfigHdr,
+    /// Requested ranges of a buffer varstore
+    pub blocks: Vec<ConfigBlockName>,
+    /// Requested names of a name/value varstore
+    pub names: Vec<String>,
+}
+
+impl ConfigRequest {
+    /// Adds the range of `width` bytes at `offset` to the request.
+    #[must_use]
+    pub fn with_block(mut self, offset: u64, width: u64) -> Self {
+        self.blocks.push(ConfigBlockName { offset, width });
+        self
+    }
+
+    /// Adds the value `name` to the request.
+    #[must_use]
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.names.push(name.into());
+        self
+    }
+}
+
+/// Encodes the request in the UEFI {ConfigRequest} format.
+impl Display for ConfigRequest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.header, f)?;
+        for block in &self.blocks {
+            write!(
+                f,
+                "&{}={}&{}={}",
+                keys::OFFSET,
+                ConfigurationString::encode_size_to_hex(block.offset),
+                keys::WIDTH,
+                ConfigurationString::encode_size_to_hex(block.width),
+            )?;
+        }
+        for name in &self.names {
+            write!(f, "&{name}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a {ConfigRequest}, e.g. in [`ConfigAccessHandler::extract_config`].
+///
+/// [`ConfigAccessHandler::extract_config`]: super::config_access::ConfigAccessHandler::extract_config
+impl FromStr for ConfigRequest {
+    type Err = ParseError;
+
+    fn from_str(bfr: &str) -> Result<Self, Self::Err> {
+        let mut splitter = ConfigurationStringIter::new(bfr).peekable();
+        let header = ConfigHdr::parse_from(&mut splitter)?;
+        let mut blocks = Vec::new();
+        let mut names = Vec::new();
+        while let Some((key, value)) = splitter.next() {
+            match (key, value) {
+                (keys::OFFSET, Some(offset)) => {
+                    let offset = ConfigurationString::parse_number_from_hex(offset)
+                        .ok_or(ParseError::BlockName)?;
+                    let width = match splitter.next() {
+                        Some((keys::WIDTH, Some(width))) => {
+                            ConfigurationString::parse_number_from_hex(width)
+                                .ok_or(ParseError::BlockName)?
+                        }
+                        _ => return Err(ParseError::BlockName),
+                    };
+                    blocks.push(ConfigBlockName { offset, width });
+                }
+                (name, None) if !name.is_empty() => names.push(name.to_string()),
+                _ => return Err(ParseError::BlockName),
+            }
+        }
+        Ok(Self {
+            header,
+            blocks,
+            names,
+        })
+    }
+}
+
 /// A full UEFI Configuration String representation.
 ///
 /// This structure contains routing information such as GUID and device path,
@@ -124,6 +380,8 @@ pub struct ConfigurationString {
     pub alt_cfg_id: Option<u16>,
     /// Parsed UEFI {ConfigElement} sections
     pub elements: Vec<ConfigurationStringElement>,
+    /// Parsed UEFI {NvConfig} sections of name/value varstores
+    pub name_values: Vec<ConfigurationStringNameValue>,
 }
 
 impl ConfigurationString {
@@ -134,6 +392,16 @@ impl ConfigurationString {
         parse_fn().ok_or(err)
     }
 
+    /// Returns the {ConfigHdr} of the configuration string.
+    #[must_use]
+    pub fn header(&self) -> ConfigHdr {
+        ConfigHdr {
+            guid: self.guid,
+            name: self.name.clone(),
+            device_path: self.device_path.to_boxed(),
+        }
+    }
+
     /// Parses a hexadecimal string into an iterator of bytes.
     ///
     /// # Arguments
@@ -213,27 +481,98 @@ impl ConfigurationString {
         Some(Guid::from_bytes(v.try_into().ok()?))
     }
 
+    /// Encodes bytes into a hexadecimal string. Inverse of
+    /// [`Self::parse_bytes_from_hex`].
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The binary data to encode.
+    ///
+    /// # Returns
+    ///
+    /// A lowercase hexadecimal `String`.
+    #[must_use]
+    pub fn encode_bytes_to_hex(bytes: impl IntoIterator<Item = u8>) -> String {
+        let mut hex = String::new();
+        for byte in bytes {
+            let _ = write!(hex, "{byte:02x}");
+        }
+        hex
+    }
+
+    /// Encodes a numeric value into a hexadecimal string. Inverse of
+    /// [`Self::parse_number_from_hex`].
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The number to encode.
+    /// * `width` - The width of the number in bytes: 1, 2, 4 or 8.
+    ///
+    /// # Returns
+    ///
+    /// A `String` of `2 * width` hexadecimal digits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` is not 1, 2, 4 or 8.
+    #[must_use]
+    pub fn encode_number_to_hex(value: u64, width: usize) -> String {
+        assert!(
+            matches!(width, 1 | 2 | 4 | 8),
+            "invalid configuration string number width"
+        );
+        Self::encode_bytes_to_hex(value.to_be_bytes()[8 - width..].iter().copied())
+    }
+
+    /// Encodes a string into the hexadecimal representation of its UTF-16
+    /// characters. Inverse of [`Self::parse_string_from_hex`].
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The string to encode.
+    ///
+    /// # Returns
+    ///
+    /// A `String` with four hexadecimal digits per UTF-16 character.
+    #[must_use]
+    pub fn encode_string_to_hex(data: &str) -> String {
+        Self::encode_bytes_to_hex(data.encode_utf16().flat_map(u16::to_be_bytes))
+    }
+
+    /// Encodes a UEFI GUID into a hexadecimal string. Inverse of
+    /// [`Self::parse_guid_from_hex`].
+    ///
+    /// # Arguments
+    ///
+    /// * `guid` - The GUID to encode.
+    ///
+    /// # Returns
+    ///
+    /// A `String` of 32 hexadecimal digits.
+    #[must_use]
+    pub fn encode_guid_to_hex(guid: &Guid) -> String {
+        Self::encode_bytes_to_hex(guid.to_bytes())
+    }
+
+    /// Encodes an offset or width with at least two bytes, like EDK2.
+    fn encode_size_to_hex(value: u64) -> String {
+        let width = match value {
+            0..=0xffff => 2,
+            0x1_0000..=0xffff_ffff => 4,
+            _ => 8,
+        };
+        Self::encode_number_to_hex(value, width)
+    }
+
     /// Parse an instance of `Peekable<ConfigurationStringIter>` from the given kv-pair iterator.
     fn parse_from(
         splitter: &mut Peekable<ConfigurationStringIter<'_>>,
     ) -> Result<Self, ParseError> {
-        let guid = Self::try_parse_with(ParseError::ConfigHdr(ConfigHdrSection::Guid), || {
-            let v = splitter.next()?;
-            let v = (v.0 == keys::GUID).then_some(v.1).flatten()?;
-            Self::parse_guid_from_hex(v)
-        })?;
-        let name = Self::try_parse_with(ParseError::ConfigHdr(ConfigHdrSection::Name), || {
-            let v = splitter.next()?;
-            let v = (v.0 == keys::NAME).then_some(v.1).flatten()?;
-            Self::parse_string_from_hex(v)
-        })?;
-        let device_path =
-            Self::try_parse_with(ParseError::ConfigHdr(ConfigHdrSection::Path), || {
-                let v = splitter.next()?.1?;
-                let v: Vec<_> = Self::parse_bytes_from_hex(v).collect();
-                let v = <&DevicePath>::try_from(v.as_slice()).ok()?;
-                Some(v.to_boxed())
-            })?;
+        let ConfigHdr {
+            guid,
+            name,
+            device_path,
+        } = ConfigHdr::parse_from(splitter)?;
         let alt_cfg_id = match splitter.peek() {
             Some((keys::ALTCFG, _)) => Some(Self::try_parse_with(
                 ParseError::ConfigHdr(ConfigHdrSection::DescHdr),
@@ -246,11 +585,23 @@ impl ConfigurationString {
         };
 
         let mut elements = Vec::new();
+        let mut name_values = Vec::new();
         loop {
             let offset = match splitter.next() {
                 Some((keys::OFFSET, Some(data))) => {
                     Self::parse_number_from_hex(data).ok_or(ParseError::BlockName)?
                 }
+                // Name/value pairs are numbers, like `VALUE`.
+                Some((name, Some(data))) if name != keys::GUID => {
+                    name_values.push(ConfigurationStringNameValue {
+                        name: name.to_string(),
+                        value: Self::parse_bytes_from_hex(data).rev().collect(),
+                    });
+                    if let Some((keys::GUID, _)) = splitter.peek() {
+                        break;
+                    }
+                    continue;
+                }
                 None => break,
                 _ => return Err(ParseError::BlockName),
             };
@@ -292,10 +643,48 @@ impl ConfigurationString {
             device_path,
             alt_cfg_id,
             elements,
+            name_values,
         })
     }
 }
 
+/// Encodes the configuration string in the UEFI {ConfigResp} format, which
+/// can be parsed again with [`FromStr`].
+impl Display for ConfigurationString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_header(f, &self.guid, &self.name, &self.device_path)?;
+        if let Some(alt_cfg_id) = self.alt_cfg_id {
+            write!(
+                f,
+                "&{}={}",
+                keys::ALTCFG,
+                Self::encode_number_to_hex(alt_cfg_id.into(), 2)
+            )?;
+        }
+        for element in &self.elements {
+            write!(
+                f,
+                "&{}={}&{}={}&{}={}",
+                keys::OFFSET,
+                Self::encode_size_to_hex(element.offset),
+                keys::WIDTH,
+                Self::encode_size_to_hex(element.width),
+                keys::VALUE,
+                Self::encode_bytes_to_hex(element.value.iter().rev().copied()),
+            )?;
+        }
+        for name_value in &self.name_values {
+            write!(
+                f,
+                "&{}={}",
+                name_value.name,
+                Self::encode_bytes_to_hex(name_value.value.iter().rev().copied()),
+            )?;
+        }
+        Ok(())
+    }
+}
+
 impl FromStr for ConfigurationString {
     type Err = ParseError;
 
@@ -329,10 +718,92 @@ impl<'a> Iterator for MultiConfigurationStringIter<'a> {
 
 #[cfg(test)]
 mod tests {
-    use crate::proto::hii::config_str::{ConfigurationString, MultiConfigurationStringIter};
+    use crate::cstr16;
+    use crate::proto::device_path::DevicePath;
+    use crate::proto::hii::config_str::{
+        ConfigBlockName, ConfigHdr, ConfigRequest, ConfigurationString,
+        MultiConfigurationStringIter, ParseError,
+    };
+    use alloc::string::ToString;
     use alloc::vec::Vec;
     use core::str::FromStr;
 
+    const HEADER: &str = "GUID=16d6474bd6a852459d44ccad2e0f4cf9&NAME=0041&PATH=7fff0400";
+
+    #[test]
+    fn config_hdr() {
+        let path = <&DevicePath>::try_from([0x7f, 0xff, 0x04, 0x00].as_slice()).unwrap();
+        let header = ConfigHdr::new(
+            guid!("4b47d616-a8d6-4552-9d44-ccad2e0f4cf9"),
+            cstr16!("A"),
+            path,
+        );
+        assert_eq!(header.to_string(), HEADER);
+        assert_eq!(ConfigHdr::from_str(HEADER).unwrap(), header);
+        assert_eq!(
+            ConfigHdr::from_str(&alloc::format!("{HEADER}&OFFSET=0000&WIDTH=0002")).unwrap(),
+            header
+        );
+        assert!(matches!(
+            ConfigHdr::from_str("GUID=16d6474bd6a852459d44ccad2e0f4cf9&PATH=7fff0400"),
+            Err(ParseError::ConfigHdr(_))
+        ));
+
+        assert!(header.matches(HEADER));
+        // The device path is not compared, and hex digits may be uppercase.
+        assert!(header.matches("GUID=16D6474BD6A852459D44CCAD2E0F4CF9&NAME=0041&PATH=00"));
+        assert!(!header.matches("GUID=16d6474bd6a852459d44ccad2e0f4cf9&NAME=0042&PATH=7fff0400"));
+        assert!(!header.matches("NAME=0041&GUID=16d6474bd6a852459d44ccad2e0f4cf9"));
+        assert!(!header.matches(""));
+
+        let parsed = ConfigurationString::from_str(&alloc::format!(
+            "{HEADER}&OFFSET=0000&WIDTH=0001&VALUE=00"
+        ))
+        .unwrap();
+        assert_eq!(parsed.header(), header);
+    }
+
+    #[test]
+    fn config_request() {
+        let header = ConfigHdr::from_str(HEADER).unwrap();
+        let request = header.request();
+        assert_eq!(request.to_string(), HEADER);
+        assert_eq!(ConfigRequest::from_str(HEADER).unwrap(), request);
+
+        let request = header.request().with_block(0x1d8, 2).with_block(0x10000, 1);
+        let encoded = alloc::format!("{HEADER}&OFFSET=01d8&WIDTH=0002&OFFSET=00010000&WIDTH=0001");
+        assert_eq!(request.to_string(), encoded);
+        let parsed = ConfigRequest::from_str(&encoded).unwrap();
+        assert_eq!(
+            parsed.blocks,
+            [
+                ConfigBlockName {
+                    offset: 0x1d8,
+                    width: 2
+                },
+                ConfigBlockName {
+                    offset: 0x10000,
+                    width: 1
+                },
+            ]
+        );
+        assert_eq!(parsed, request);
+
+        let request = header.request().with_name("Mode").with_name("Level");
+        let encoded = alloc::format!("{HEADER}&Mode&Level");
+        assert_eq!(request.to_string(), encoded);
+        assert_eq!(ConfigRequest::from_str(&encoded).unwrap(), request);
+
+        assert!(matches!(
+            ConfigRequest::from_str(&alloc::format!("{HEADER}&OFFSET=0000")),
+            Err(ParseError::BlockName)
+        ));
+        assert!(matches!(
+            ConfigRequest::from_str(&alloc::format!("{HEADER}&OFFSET=0000&WIDTH=0001&VALUE=00")),
+            Err(ParseError::BlockName)
+        ));
+    }
+
     #[test]
     fn parse_single() {
         // exemplary (shortened / manually constructed) UEFI configuration string
@@ -438,4 +909,48 @@ mod tests {
             &[0x55, 0x44, 0x33, 0x22, 0x11]
         );
     }
+
+    #[test]
+    fn encode_roundtrip() {
+        let input = "GUID=16d6474bd6a852459d44ccad2e0f4cf9&NAME=00490053004300530049005f0043004f004e004600490047005f004900460052005f004e00560044004100540041&PATH=0104140016d6474bd6a852459d44ccad2e0f4cf97fff0400&ALTCFG=0001&OFFSET=01d8&WIDTH=0002&VALUE=03e8&OFFSET=1337&WIDTH=0005&VALUE=1122334455";
+        let parsed = ConfigurationString::from_str(input).unwrap();
+        assert_eq!(parsed.to_string(), input);
+    }
+
+    #[test]
+    fn name_values() {
+        let input = "GUID=16d6474bd6a852459d44ccad2e0f4cf9&NAME=0041&PATH=7fff0400&Mode=0102&Level=03&GUID=16d6474bd6a852459d44ccad2e0f4cf9&NAME=0041&PATH=7fff0400&OFFSET=0000&WIDTH=0001&VALUE=00";
+        let parsed: Vec<_> = MultiConfigurationStringIter::new(input)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].name, "A");
+        assert!(parsed[0].elements.is_empty());
+        assert_eq!(parsed[0].name_values.len(), 2);
+        assert_eq!(parsed[0].name_values[0].name, "Mode");
+        assert_eq!(parsed[0].name_values[0].value, [0x02, 0x01]);
+        assert_eq!(parsed[0].name_values[1].name, "Level");
+        assert_eq!(parsed[0].name_values[1].value, [0x03]);
+        assert_eq!(parsed[1].elements.len(), 1);
+
+        let (first, _) = input.split_at(input.find("&GUID").unwrap());
+        assert_eq!(parsed[0].to_string(), first);
+    }
+
+    #[test]
+    fn encode_numbers() {
+        assert_eq!(ConfigurationString::encode_number_to_hex(0x3e8, 2), "03e8");
+        assert_eq!(
+            ConfigurationString::encode_number_to_hex(0x1, 8),
+            "0000000000000001"
+        );
+        assert_eq!(ConfigurationString::encode_string_to_hex("Ab"), "00410062");
+        assert_eq!(
+            ConfigurationString::parse_string_from_hex(&ConfigurationString::encode_string_to_hex(
+                "Ab"
+            ))
+            .unwrap(),
+            "Ab"
+        );
+    }
 }
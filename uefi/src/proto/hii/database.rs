@@ -3,11 +3,18 @@
 //! HII Database protocol.
 
 use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ffi::c_void;
+use core::{mem, ptr};
 use uefi_macros::unsafe_protocol;
+use uefi_raw::protocol::hii::HiiPackageType;
 use uefi_raw::protocol::hii::database::HiiDatabaseProtocol;
 
+use super::HiiHandle;
+use super::keyboard::KeyboardLayout;
 use crate::mem::make_boxed;
-use crate::{Error, StatusExt};
+use crate::{Error, Guid, Handle, Status, StatusExt};
 
 /// The HII Configuration Access Protocol.
 ///
@@ -24,28 +31,186 @@ use crate::{Error, StatusExt};
 pub struct HiiDatabase(HiiDatabaseProtocol);
 
 impl HiiDatabase {
-    /// Export all package lists as raw byte buffer.
-    pub fn export_all_raw(&self) -> crate::Result<Box<[u8]>> {
+    /// Adds the packages in `package_list` to the HII database and returns
+    /// the handle of the new package list.
+    ///
+    /// `package_list` must start with an `EFI_HII_PACKAGE_LIST_HEADER`
+    /// followed by the packages, and end with an end package. If
+    /// `driver_handle` is set and has a device path, a device path package
+    /// is added to the package list.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::OUT_OF_RESOURCES`]: not enough resources to add the
+    ///   package list.
+    /// * [`Status::INVALID_PARAMETER`]: the package list is malformed.
+    ///
+    /// [`Status::OUT_OF_RESOURCES`]: crate::Status::OUT_OF_RESOURCES
+    /// [`Status::INVALID_PARAMETER`]: crate::Status::INVALID_PARAMETER
+    pub fn new_package_list(
+        &self,
+        package_list: &[u8],
+        driver_handle: Option<Handle>,
+    ) -> crate::Result<HiiHandle> {
+        let mut handle = ptr::null_mut();
+        unsafe {
+            (self.0.new_package_list)(
+                &self.0,
+                package_list.as_ptr().cast(),
+                Handle::opt_to_ptr(driver_handle),
+                &mut handle,
+            )
+        }
+        .to_result()?;
+        // SAFETY: the handle was just returned by the firmware.
+        unsafe { HiiHandle::from_ptr(handle) }.ok_or_else(|| Status::PROTOCOL_ERROR.into())
+    }
+
+    /// Removes the package list `handle` from the HII database.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::NOT_FOUND`]: `handle` is not a valid package list handle.
+    ///
+    /// [`Status::NOT_FOUND`]: crate::Status::NOT_FOUND
+    pub fn remove_package_list(&self, handle: HiiHandle) -> crate::Result {
+        unsafe { (self.0.remove_package_list)(&self.0, handle.as_ptr()) }.to_result()
+    }
+
+    /// Returns the GUIDs of all keyboard layouts in the HII database.
+    pub fn keyboard_layouts(&self) -> crate::Result<Vec<Guid>> {
+        let mut len = 0u16;
+        let status = unsafe { (self.0.find_keyboard_layouts)(&self.0, &mut len, ptr::null_mut()) };
+        match status {
+            Status::SUCCESS => return Ok(Vec::new()),
+            Status::BUFFER_TOO_SMALL => {}
+            status => return Err(status.into()),
+        }
+
+        let mut guids = vec![Guid::ZERO; usize::from(len) / mem::size_of::<Guid>()];
+        unsafe { (self.0.find_keyboard_layouts)(&self.0, &mut len, guids.as_mut_ptr()) }
+            .to_result()?;
+        guids.truncate(usize::from(len) / mem::size_of::<Guid>());
+        Ok(guids)
+    }
+
+    /// Returns the keyboard layout identified by `guid`, or the current
+    /// keyboard layout if `guid` is `None`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::NOT_FOUND`]: there is no layout with the given GUID, or
+    ///   no current layout.
+    /// * [`Status::VOLUME_CORRUPTED`]: the layout is malformed.
+    ///
+    /// [`Status::NOT_FOUND`]: crate::Status::NOT_FOUND
+    /// [`Status::VOLUME_CORRUPTED`]: crate::Status::VOLUME_CORRUPTED
+    pub fn keyboard_layout(&self, guid: Option<&Guid>) -> crate::Result<KeyboardLayout> {
+        let guid = guid.map_or(ptr::null(), ptr::from_ref);
+        let mut len = 0u16;
+        let status =
+            unsafe { (self.0.get_keyboard_layout)(&self.0, guid, &mut len, ptr::null_mut()) };
+        match status {
+            Status::BUFFER_TOO_SMALL => {}
+            Status::SUCCESS => return Err(Status::PROTOCOL_ERROR.into()),
+            status => return Err(status.into()),
+        }
+
+        let mut buf = vec![0u8; usize::from(len)];
+        unsafe { (self.0.get_keyboard_layout)(&self.0, guid, &mut len, buf.as_mut_ptr().cast()) }
+            .to_result()?;
+        KeyboardLayout::try_from(buf.as_slice()).map_err(|_| Status::VOLUME_CORRUPTED.into())
+    }
+
+    /// Sets the current keyboard layout to the layout identified by `guid`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::NOT_FOUND`]: there is no layout with the given GUID.
+    ///
+    /// [`Status::NOT_FOUND`]: crate::Status::NOT_FOUND
+    pub fn set_keyboard_layout(&self, guid: &Guid) -> crate::Result {
+        unsafe { (self.0.set_keyboard_layout)(&self.0, guid) }.to_result()
+    }
+
+    /// Returns the handles of the package lists that contain a package of
+    /// type `package_type`, or of all package lists for
+    /// [`HiiPackageType::ALL`].
+    ///
+    /// `package_guid` selects GUID packages by their GUID, and must be set
+    /// if and only if `package_type` is [`HiiPackageType::TYPE_GUID`].
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::INVALID_PARAMETER`]: `package_guid` doesn't match
+    ///   `package_type`.
+    ///
+    /// [`Status::INVALID_PARAMETER`]: crate::Status::INVALID_PARAMETER
+    pub fn list_package_lists(
+        &self,
+        package_type: HiiPackageType,
+        package_guid: Option<&Guid>,
+    ) -> crate::Result<Vec<HiiHandle>> {
+        let guid = package_guid.map_or(ptr::null(), ptr::from_ref);
+        let mut size = 0;
+        let status = unsafe {
+            (self.0.list_package_lists)(&self.0, package_type.0, guid, &mut size, ptr::null_mut())
+        };
+        match status {
+            Status::BUFFER_TOO_SMALL => {}
+            Status::SUCCESS | Status::NOT_FOUND => return Ok(Vec::new()),
+            status => return Err(status.into()),
+        }
+
+        let mut handles = vec![ptr::null_mut(); size / mem::size_of::<*mut c_void>()];
+        unsafe {
+            (self.0.list_package_lists)(
+                &self.0,
+                package_type.0,
+                guid,
+                &mut size,
+                handles.as_mut_ptr(),
+            )
+        }
+        .to_result()?;
+        handles.truncate(size / mem::size_of::<*mut c_void>());
+        // SAFETY: the handles were just returned by the firmware.
+        Ok(handles
+            .into_iter()
+            .filter_map(|handle| unsafe { HiiHandle::from_ptr(handle) })
+            .collect())
+    }
+
+    /// Exports the package list `handle`, or all package lists if `handle`
+    /// is `None`.
+    ///
+    /// The buffer contains the package lists one after another, each
+    /// starting with an `EFI_HII_PACKAGE_LIST_HEADER`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::NOT_FOUND`]: `handle` is not a valid package list handle.
+    ///
+    /// [`Status::NOT_FOUND`]: crate::Status::NOT_FOUND
+    pub fn export_package_lists(&self, handle: Option<HiiHandle>) -> crate::Result<Box<[u8]>> {
         fn fetch_data_fn<'a>(
             proto: &HiiDatabase,
+            handle: *mut c_void,
             buf: &'a mut [u8],
         ) -> Result<&'a mut [u8], Error<Option<usize>>> {
-            unsafe {
-                let mut size = buf.len();
-                let status = {
-                    (proto.0.export_package_lists)(
-                        &proto.0,
-                        core::ptr::null_mut(),
-                        &mut size,
-                        buf.as_mut_ptr().cast(),
-                    )
-                };
-                status.to_result_with_err(|_| Some(size)).map(|_| buf)
-            }
+            let mut size = buf.len();
+            let status = unsafe {
+                (proto.0.export_package_lists)(&proto.0, handle, &mut size, buf.as_mut_ptr().cast())
+            };
+            status.to_result_with_err(|_| Some(size)).map(|_| buf)
         }
 
-        let buf = make_boxed::<[u8], _>(|buf| fetch_data_fn(self, buf))?;
+        let handle = handle.map_or(ptr::null_mut(), |handle| handle.as_ptr());
+        make_boxed::<[u8], _>(|buf| fetch_data_fn(self, handle, buf))
+    }
 
-        Ok(buf)
+    /// Export all package lists as raw byte buffer.
+    pub fn export_all_raw(&self) -> crate::Result<Box<[u8]>> {
+        self.export_package_lists(None)
     }
 }
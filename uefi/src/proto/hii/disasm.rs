@@ -0,0 +1,643 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Rendering IFR as VFR-like text.
+//!
+//! [`Disassembly`] formats the opcodes of an IFR buffer, such as the body of
+//! a form package, as pseudo-VFR: one line per opcode, indented by scope
+//! depth, with the fields of statements, questions and varstores spelled
+//! out. Opcodes without a dedicated format, e.g. most expression opcodes,
+//! are printed by name. String IDs are printed as `STRING_TOKEN(id)`, and
+//! followed by the string if a [`StringSource`] is given:
+//!
+//! ```text
+//! formset guid = a04a27f4-df00-4d42-b552-39511302113d, title = STRING_TOKEN(0x1) "Sample settings", help = STRING_TOKEN(0x2) "Settings of the sample driver"
+//!   form formid = 1, title = STRING_TOKEN(0x3) "Main"
+//!     checkbox questionid = 0x1000, varid = 1, offset = 0x0, prompt = STRING_TOKEN(0x4) "Enable", help = STRING_TOKEN(0x4) "Enable"
+//!       default defaultid = 0, value = TRUE;
+//!     endcheckbox;
+//!   endform;
+//! endformset;
+//! ```
+//!
+//! The output is meant for reading, and can't be compiled back into IFR.
+//!
+//! ```
+//! use uefi::proto::hii::disasm::Disassembly;
+//! use uefi::proto::hii::package::{Package, PackageList};
+//!
+//! # fn example(export: &[u8]) -> Result<(), uefi::proto::hii::package::PackageError> {
+//! let list = PackageList::parse(export)?;
+//! let strings = list.packages().find_map(|package| match package {
+//!     Ok(Package::Strings(strings)) => Some(strings),
+//!     _ => None,
+//! });
+//! for package in list.packages() {
+//!     if let Package::Forms(ifr) = package? {
+//!         let mut disassembly = Disassembly::new(ifr);
+//!         if let Some(strings) = &strings {
+//!             disassembly = disassembly.strings(strings);
+//!         }
+//!         log::info!("{disassembly}");
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use super::ifr::{
+    IfrAction, IfrCheckbox, IfrDefault, IfrDefaultStore, IfrEqIdId, IfrEqIdVal, IfrEqIdValList,
+    IfrForm, IfrFormSet, IfrGuid, IfrImage, IfrOneOfOption, IfrOp, IfrOpCode, IfrOps,
+    IfrOrderedList, IfrPassword, IfrQuestionHeader, IfrQuestionRef1, IfrRef, IfrRule, IfrRuleRef,
+    IfrString, IfrStringRef1, IfrSubtitle, IfrText, IfrTianoExtension, IfrType, IfrVarStore,
+    IfrVarStoreEfi, IfrVarStoreNameValue,
+};
+use super::package::StringPackage;
+use super::string::HiiString;
+use super::{HiiHandle, StringId};
+use crate::{CStr8, CString16};
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter, Write};
+
+/// Source of the strings referenced by IFR, for [`Disassembly::strings`].
+pub trait StringSource {
+    /// Returns the string with ID `id`, or `None` if it doesn't exist.
+    fn string(&self, id: StringId) -> Option<CString16>;
+}
+
+impl StringSource for StringPackage<'_> {
+    fn string(&self, id: StringId) -> Option<CString16> {
+        Self::string(self, id)
+    }
+}
+
+impl StringSource for BTreeMap<StringId, CString16> {
+    fn string(&self, id: StringId) -> Option<CString16> {
+        self.get(&id).cloned()
+    }
+}
+
+/// Strings of a package list in the HII database, looked up with the
+/// [`HiiString`] protocol.
+#[derive(Debug)]
+pub struct HiiStrings<'a> {
+    hii: &'a HiiString,
+    package_list: HiiHandle,
+    language: &'a CStr8,
+}
+
+impl<'a> HiiStrings<'a> {
+    /// Creates a source for the strings of `package_list` in `language`.
+    #[must_use]
+    pub const fn new(hii: &'a HiiString, package_list: HiiHandle, language: &'a CStr8) -> Self {
+        Self {
+            hii,
+            package_list,
+            language,
+        }
+    }
+}
+
+impl StringSource for HiiStrings<'_> {
+    fn string(&self, id: StringId) -> Option<CString16> {
+        self.hii
+            .get_string(self.language, self.package_list, id)
+            .ok()
+    }
+}
+
+/// An IFR buffer formatted as VFR-like text with [`Display`].
+///
+/// Formatting stops at the first malformed opcode, with a comment
+/// describing the error.
+#[derive(Clone, Copy)]
+pub struct Disassembly<'a> {
+    ifr: &'a [u8],
+    strings: Option<&'a dyn StringSource>,
+}
+
+impl<'a> Disassembly<'a> {
+    /// Creates a disassembly of `ifr`, printing string IDs without
+    /// resolving them.
+    #[must_use]
+    pub const fn new(ifr: &'a [u8]) -> Self {
+        Self { ifr, strings: None }
+    }
+
+    /// Resolves the string IDs with `strings`.
+    #[must_use]
+    pub const fn strings(mut self, strings: &'a dyn StringSource) -> Self {
+        self.strings = Some(strings);
+        self
+    }
+}
+
+impl fmt::Debug for Disassembly<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Disassembly")
+            .field("ifr", &self.ifr)
+            .field("strings", &self.strings.is_some())
+            .finish()
+    }
+}
+
+impl Display for Disassembly<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut line = Line {
+            f,
+            strings: self.strings,
+            first: true,
+        };
+        // Names of the opcodes with an open scope, for their end opcodes.
+        let mut scopes: Vec<String> = Vec::new();
+        for op in IfrOps::new(self.ifr) {
+            let op = match op {
+                Ok(op) => op,
+                Err(err) => return writeln!(line.f, "// {err}"),
+            };
+            write!(line.f, "{:width$}", "", width = 2 * op.depth())?;
+            if op.op_code() == IfrOpCode::END {
+                let name = scopes.pop().unwrap_or_default();
+                writeln!(line.f, "{};", end_name(&name))?;
+                continue;
+            }
+            line.first = true;
+            line.op(&op)?;
+            if op.scope() {
+                scopes.push(op_name(op.op_code()));
+                writeln!(line.f)?;
+            } else {
+                writeln!(line.f, ";")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the VFR keyword of `op_code`, or its name in lowercase if VFR
+/// has no keyword for it.
+fn op_name(op_code: IfrOpCode) -> String {
+    let name = match op_code {
+        IfrOpCode::FORM_SET => "formset",
+        IfrOpCode::ONE_OF => "oneof",
+        IfrOpCode::ONE_OF_OPTION => "option",
+        IfrOpCode::SUPPRESS_IF => "suppressif",
+        IfrOpCode::GRAY_OUT_IF => "grayoutif",
+        IfrOpCode::DISABLE_IF => "disableif",
+        IfrOpCode::NO_SUBMIT_IF => "nosubmitif",
+        IfrOpCode::INCONSISTENT_IF => "inconsistentif",
+        IfrOpCode::WARNING_IF => "warningif",
+        IfrOpCode::ORDERED_LIST => "orderedlist",
+        IfrOpCode::REF => "goto",
+        IfrOpCode::EQ_ID_VAL => "ideqval",
+        IfrOpCode::EQ_ID_ID => "ideqid",
+        IfrOpCode::EQ_ID_VAL_LIST => "ideqvallist",
+        IfrOpCode::DEFAULTSTORE => "defaultstore",
+        IfrOpCode::VARSTORE_EFI => "efivarstore",
+        IfrOpCode::VARSTORE_NAME_VALUE => "namevaluevarstore",
+        IfrOpCode::GUID => "guidop",
+        _ => {
+            let mut name = String::new();
+            let _ = write!(name, "{op_code:?}");
+            if name.contains('(') {
+                // Unknown opcode.
+                name.clear();
+                let _ = write!(name, "opcode(0x{:02x})", op_code.0);
+            } else {
+                name.make_ascii_lowercase();
+                name.retain(|c| c != '_');
+            }
+            return name;
+        }
+    };
+    name.into()
+}
+
+/// Returns the VFR keyword closing the scope of the opcode named `name`.
+fn end_name(name: &str) -> String {
+    match name {
+        "suppressif" | "grayoutif" | "disableif" | "nosubmitif" | "inconsistentif"
+        | "warningif" => "endif".into(),
+        "orderedlist" => "endlist".into(),
+        "" => "end".into(),
+        name => {
+            let mut end = String::from("end");
+            end.push_str(name);
+            end
+        }
+    }
+}
+
+/// Writer of the fields of one line.
+struct Line<'f, 'a, 's> {
+    f: &'f mut Formatter<'a>,
+    strings: Option<&'s dyn StringSource>,
+    first: bool,
+}
+
+impl Line<'_, '_, '_> {
+    /// Writes the separator before a field, and its name.
+    fn key(&mut self, key: &str) -> fmt::Result {
+        let separator = if self.first { " " } else { ", " };
+        self.first = false;
+        write!(self.f, "{separator}{key} = ")
+    }
+
+    fn field(&mut self, key: &str, value: impl Display) -> fmt::Result {
+        self.key(key)?;
+        write!(self.f, "{value}")
+    }
+
+    fn hex(&mut self, key: &str, value: impl fmt::LowerHex) -> fmt::Result {
+        self.key(key)?;
+        write!(self.f, "{value:#x}")
+    }
+
+    fn string(&mut self, key: &str, id: StringId) -> fmt::Result {
+        self.key(key)?;
+        self.string_token(id)
+    }
+
+    /// Writes a string ID, followed by the string if it can be resolved.
+    fn string_token(&mut self, id: StringId) -> fmt::Result {
+        write!(self.f, "STRING_TOKEN({id:#x})")?;
+        if let Some(string) = self.strings.and_then(|strings| strings.string(id)) {
+            let mut s = String::new();
+            let _ = write!(s, "{string}");
+            write!(self.f, " {s:?}")?;
+        }
+        Ok(())
+    }
+
+    fn flags<B: bitflags::Flags>(&mut self, flags: B) -> fmt::Result
+    where
+        B::Bits: bitflags::parser::WriteHex,
+    {
+        if flags.is_empty() {
+            return Ok(());
+        }
+        self.key("flags")?;
+        bitflags::parser::to_writer(&flags, &mut *self.f)
+    }
+
+    fn question(&mut self, question: IfrQuestionHeader) -> fmt::Result {
+        self.hex("questionid", question.question_id)?;
+        if { question.var_store_id } != 0 {
+            self.field("varid", question.var_store_id)?;
+            self.hex("offset", question.var_store_info)?;
+        }
+        self.string("prompt", question.header.prompt)?;
+        self.string("help", question.header.help)?;
+        self.flags(question.flags)
+    }
+
+    fn value(&mut self, op: &IfrOp<'_>) -> fmt::Result {
+        let Some((ty, bytes)) = op.value() else {
+            return Ok(());
+        };
+        self.key("value")?;
+        match ty {
+            IfrType::BOOLEAN => {
+                let value = if bytes[0] != 0 { "TRUE" } else { "FALSE" };
+                write!(self.f, "{value}")
+            }
+            IfrType::STRING if bytes.len() >= 2 => {
+                self.string_token(u16::from_le_bytes([bytes[0], bytes[1]]))
+            }
+            _ => match op.value_uint() {
+                Some(value) => write!(self.f, "{value}"),
+                None => write_bytes(self.f, bytes),
+            },
+        }
+    }
+
+    /// Writes the name and fields of `op`.
+    fn op(&mut self, op: &IfrOp<'_>) -> fmt::Result {
+        let op_code = op.op_code();
+        if let Some(extension) = op.tiano_extension() {
+            return self.tiano_extension(extension);
+        }
+        write!(self.f, "{}", op_name(op_code))?;
+
+        if let Some(question) = op.question() {
+            self.question(question)?;
+        }
+        match op_code {
+            IfrOpCode::FORM_SET => {
+                if let Some(formset) = op.get::<IfrFormSet>() {
+                    self.field("guid", formset.guid)?;
+                    self.string("title", formset.form_set_title)?;
+                    self.string("help", formset.help)?;
+                    for guid in op.class_guids().into_iter().flatten() {
+                        self.field("classguid", guid)?;
+                    }
+                    return Ok(());
+                }
+            }
+            IfrOpCode::FORM => {
+                if let Some(form) = op.get::<IfrForm>() {
+                    self.field("formid", form.form_id)?;
+                    return self.string("title", form.form_title);
+                }
+            }
+            IfrOpCode::SUBTITLE => {
+                if let Some(subtitle) = op.get::<IfrSubtitle>() {
+                    return self.string("text", subtitle.statement.prompt);
+                }
+            }
+            IfrOpCode::TEXT => {
+                if let Some(text) = op.get::<IfrText>() {
+                    self.string("text", text.statement.prompt)?;
+                    self.string("help", text.statement.help)?;
+                    if { text.text_two } != 0 {
+                        self.string("text", text.text_two)?;
+                    }
+                    return Ok(());
+                }
+            }
+            IfrOpCode::IMAGE => {
+                if let Some(image) = op.get::<IfrImage>() {
+                    return write!(self.f, " IMAGE_TOKEN({:#x})", { image.id });
+                }
+            }
+            IfrOpCode::REF => {
+                if let Some(reference) = op.get::<IfrRef>() {
+                    return self.field("formid", reference.form_id);
+                }
+            }
+            IfrOpCode::ACTION => {
+                if let Some(action) = op.get::<IfrAction>() {
+                    return self.string("config", action.question_config);
+                }
+            }
+            IfrOpCode::CHECKBOX => {
+                if let Some(checkbox) = op.get::<IfrCheckbox>() {
+                    if !checkbox.flags.is_empty() {
+                        self.key("checkboxflags")?;
+                        bitflags::parser::to_writer(&checkbox.flags, &mut *self.f)?;
+                    }
+                    return Ok(());
+                }
+            }
+            IfrOpCode::NUMERIC | IfrOpCode::ONE_OF => {
+                if let Some(range) = op.range() {
+                    self.field("minimum", range.min)?;
+                    self.field("maximum", range.max)?;
+                    if range.step != 0 {
+                        self.field("step", range.step)?;
+                    }
+                    return Ok(());
+                }
+            }
+            IfrOpCode::ONE_OF_OPTION => {
+                if let Some(option) = op.get::<IfrOneOfOption>() {
+                    self.string("text", option.option)?;
+                    self.value(op)?;
+                    return self.flags(option.flags);
+                }
+            }
+            IfrOpCode::STRING => {
+                if let Some(string) = op.get::<IfrString>() {
+                    self.field("minsize", string.min_size)?;
+                    return self.field("maxsize", string.max_size);
+                }
+            }
+            IfrOpCode::PASSWORD => {
+                if let Some(password) = op.get::<IfrPassword>() {
+                    self.field("minsize", password.min_size)?;
+                    return self.field("maxsize", password.max_size);
+                }
+            }
+            IfrOpCode::ORDERED_LIST => {
+                if let Some(list) = op.get::<IfrOrderedList>() {
+                    return self.field("maxcontainers", list.max_containers);
+                }
+            }
+            IfrOpCode::DATE | IfrOpCode::TIME => return Ok(()),
+            IfrOpCode::VARSTORE => {
+                if let Some(varstore) = op.get::<IfrVarStore>() {
+                    self.field("varid", varstore.var_store_id)?;
+                    self.field("guid", varstore.guid)?;
+                    if let Some(name) = op.varstore_name() {
+                        self.field("name", name)?;
+                    }
+                    return self.field("size", varstore.size);
+                }
+            }
+            IfrOpCode::VARSTORE_EFI => {
+                if let Some(varstore) = op.get::<IfrVarStoreEfi>() {
+                    self.field("varid", varstore.var_store_id)?;
+                    self.field("guid", varstore.guid)?;
+                    self.hex("attribute", varstore.attributes)?;
+                    if let Some(name) = op.varstore_name() {
+                        self.field("name", name)?;
+                    }
+                    return self.field("size", varstore.size);
+                }
+            }
+            IfrOpCode::VARSTORE_NAME_VALUE => {
+                if let Some(varstore) = op.get::<IfrVarStoreNameValue>() {
+                    self.field("varid", varstore.var_store_id)?;
+                    return self.field("guid", varstore.guid);
+                }
+            }
+            IfrOpCode::DEFAULTSTORE => {
+                if let Some(store) = op.get::<IfrDefaultStore>() {
+                    self.string("prompt", store.default_name)?;
+                    return self.field("attribute", store.default_id);
+                }
+            }
+            IfrOpCode::DEFAULT => {
+                if let Some(default) = op.get::<IfrDefault>() {
+                    self.field("defaultid", default.default_id)?;
+                    return self.value(op);
+                }
+            }
+            IfrOpCode::GUID => {
+                if let Some(guid) = op.get::<IfrGuid>() {
+                    self.field("guid", guid.guid)?;
+                    let data = op.trailing::<IfrGuid>().unwrap_or_default();
+                    if !data.is_empty() {
+                        self.key("data")?;
+                        write_bytes(self.f, data)?;
+                    }
+                    return Ok(());
+                }
+            }
+            IfrOpCode::EQ_ID_VAL => {
+                if let Some(eq) = op.get::<IfrEqIdVal>() {
+                    self.hex("questionid", eq.question_id)?;
+                    return self.field("value", eq.value);
+                }
+            }
+            IfrOpCode::EQ_ID_ID => {
+                if let Some(eq) = op.get::<IfrEqIdId>() {
+                    self.hex("questionid", eq.question_id_1)?;
+                    return self.hex("questionid", eq.question_id_2);
+                }
+            }
+            IfrOpCode::EQ_ID_VAL_LIST => {
+                if let (Some(eq), Some(values)) =
+                    (op.get::<IfrEqIdValList>(), op.trailing::<IfrEqIdValList>())
+                {
+                    self.hex("questionid", eq.question_id)?;
+                    self.key("values")?;
+                    let count = usize::from(eq.list_length);
+                    for (i, value) in values.chunks_exact(2).take(count).enumerate() {
+                        let separator = if i == 0 { "" } else { " " };
+                        let value = u16::from_le_bytes([value[0], value[1]]);
+                        write!(self.f, "{separator}{value}")?;
+                    }
+                    return Ok(());
+                }
+            }
+            IfrOpCode::QUESTION_REF1 => {
+                if let Some(question_ref) = op.get::<IfrQuestionRef1>() {
+                    return self.hex("questionid", question_ref.question_id);
+                }
+            }
+            IfrOpCode::RULE => {
+                if let Some(rule) = op.get::<IfrRule>() {
+                    return self.field("ruleid", rule.rule_id);
+                }
+            }
+            IfrOpCode::RULE_REF => {
+                if let Some(rule) = op.get::<IfrRuleRef>() {
+                    return self.field("ruleid", rule.rule_id);
+                }
+            }
+            IfrOpCode::STRING_REF1 => {
+                if let Some(string) = op.get::<IfrStringRef1>() {
+                    return self.string("string", string.string_id);
+                }
+            }
+            IfrOpCode::UINT8 | IfrOpCode::UINT16 | IfrOpCode::UINT32 | IfrOpCode::UINT64 => {
+                let value = &op.bytes()[2..];
+                if value.len() <= 8 {
+                    let mut buf = [0; 8];
+                    buf[..value.len()].copy_from_slice(value);
+                    return write!(self.f, " {}", u64::from_le_bytes(buf));
+                }
+            }
+            _ => {}
+        }
+
+        // Unknown or malformed opcode.
+        let data = &op.bytes()[2..];
+        if !data.is_empty() {
+            self.key("data")?;
+            write_bytes(self.f, data)?;
+        }
+        Ok(())
+    }
+
+    fn tiano_extension(&mut self, extension: IfrTianoExtension) -> fmt::Result {
+        match extension {
+            IfrTianoExtension::Label(label) => {
+                write!(self.f, "label {:#x}", { label.number })
+            }
+            IfrTianoExtension::Banner(banner) => {
+                write!(self.f, "banner")?;
+                self.string("title", banner.title)?;
+                self.field("line", banner.line_number)?;
+                self.key("align")?;
+                write!(self.f, "{:?}", banner.alignment)
+            }
+            IfrTianoExtension::Timeout(timeout) => {
+                write!(self.f, "timeout {}", { timeout.timeout })
+            }
+            IfrTianoExtension::Class(class) => {
+                write!(self.f, "class ")?;
+                bitflags::parser::to_writer(&{ class.class }, &mut *self.f)
+            }
+            IfrTianoExtension::Subclass(subclass) => {
+                write!(self.f, "subclass {:?}", { subclass.subclass })
+            }
+        }
+    }
+}
+
+/// Writes `bytes` as space-separated hex.
+fn write_bytes(f: &mut Formatter<'_>, bytes: &[u8]) -> fmt::Result {
+    for (i, byte) in bytes.iter().enumerate() {
+        let separator = if i == 0 { "" } else { " " };
+        write!(f, "{separator}{byte:02x}")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Guid, cstr16, guid};
+    use alloc::string::ToString;
+
+    const FORMSET_GUID: Guid = guid!("a04a27f4-df00-4d42-b552-39511302113d");
+
+    fn ifr() -> Vec<u8> {
+        let mut ifr = Vec::new();
+        // FORM_SET, scope: title 1, help 2, no class GUIDs.
+        ifr.extend([0x0e, 0x97]);
+        ifr.extend(FORMSET_GUID.to_bytes());
+        ifr.extend([1, 0, 2, 0, 0]);
+        #[rustfmt::skip]
+        ifr.extend([
+            // FORM, scope: form 1, title 3
+            0x01, 0x86, 1, 0, 3, 0,
+            // SUPPRESS_IF, scope
+            0x0a, 0x82,
+            // EQ_ID_VAL: question 0x1000 == 1
+            0x12, 0x06, 0x00, 0x10, 1, 0,
+            // CHECKBOX, scope: question 0x1000, varstore 1 offset 0,
+            // prompt 4, help 4, callback
+            0x06, 0x8e, 4, 0, 4, 0, 0x00, 0x10, 1, 0, 0, 0, 0x04, 0x01,
+            // DEFAULT: standard, boolean TRUE
+            0x5b, 0x06, 0, 0, 0x04, 1,
+            // END (checkbox)
+            0x29, 0x02,
+            // END (suppress if)
+            0x29, 0x02,
+            // TRUE
+            0x46, 0x02,
+            // END (form)
+            0x29, 0x02,
+            // END (formset)
+            0x29, 0x02,
+        ]);
+        ifr
+    }
+
+    #[test]
+    fn test_disassembly() {
+        let ifr = ifr();
+        let mut strings = BTreeMap::new();
+        strings.insert(1, CString16::from(cstr16!("Title")));
+        strings.insert(4, CString16::from(cstr16!("Say \"hi\"")));
+
+        assert_eq!(
+            Disassembly::new(&ifr).strings(&strings).to_string(),
+            "\
+formset guid = a04a27f4-df00-4d42-b552-39511302113d, title = STRING_TOKEN(0x1) \"Title\", help = STRING_TOKEN(0x2)
+  form formid = 1, title = STRING_TOKEN(0x3)
+    suppressif
+      ideqval questionid = 0x1000, value = 1;
+      checkbox questionid = 0x1000, varid = 1, offset = 0x0, prompt = STRING_TOKEN(0x4) \"Say \\\"hi\\\"\", help = STRING_TOKEN(0x4) \"Say \\\"hi\\\"\", flags = CALLBACK, checkboxflags = DEFAULT
+        default defaultid = 0, value = TRUE;
+      endcheckbox;
+    endif;
+    true;
+  endform;
+endformset;
+"
+        );
+    }
+
+    #[test]
+    fn test_disassembly_error() {
+        let ifr = ifr();
+        let text = Disassembly::new(&ifr[..ifr.len() - 3]).to_string();
+        assert!(text.starts_with("formset guid"));
+        assert!(text.ends_with("    true;\n// truncated IFR opcode at offset 63\n"));
+    }
+}
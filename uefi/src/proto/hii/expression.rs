@@ -0,0 +1,599 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Evaluation of IFR expressions.
+//!
+//! Conditions such as `suppressif` and `grayoutif` are stored in IFR as
+//! expressions: sequences of opcodes that operate on a stack, like
+//! `EFI_IFR_EQ_ID_VAL` pushing the result of comparing a question with a
+//! constant, or `EFI_IFR_AND` popping two booleans and pushing the result.
+//! [`evaluate`] runs such a sequence over question values supplied by a
+//! [`QuestionValues`] implementation, which makes it possible to decide
+//! which statements are visible without the firmware's form browser.
+//!
+//! ```
+//! # extern crate alloc;
+//! use alloc::collections::BTreeMap;
+//! use uefi::proto::hii::expression::{IfrValue, evaluate};
+//!
+//! let mut values = BTreeMap::new();
+//! values.insert(1, IfrValue::Uint(3));
+//!
+//! // EQ_ID_VAL question 1 == 3, NOT
+//! let expr = [0x12, 6, 1, 0, 3, 0, 0x17, 2];
+//! assert_eq!(evaluate(&expr, &values), Ok(IfrValue::Bool(false)));
+//! ```
+//!
+//! Values follow the rules of the UEFI specification: operations on values
+//! of the wrong type, or on undefined question values, produce
+//! [`IfrValue::Undefined`] rather than an error. Errors are only returned for
+//! malformed or unsupported expressions.
+
+use super::{QuestionId, StringId};
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+use uefi_raw::protocol::hii::ifr::IfrOpCode;
+
+/// A value on the expression stack.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum IfrValue {
+    /// The value could not be determined.
+    Undefined,
+    /// A boolean.
+    Bool(bool),
+    /// An unsigned integer. Values of all sizes are extended to 64 bits.
+    Uint(u64),
+    /// A string.
+    String(String),
+}
+
+impl IfrValue {
+    /// Returns the value as integer. Booleans are converted to 0 or 1.
+    #[must_use]
+    pub const fn as_uint(&self) -> Option<u64> {
+        match self {
+            Self::Bool(b) => Some(*b as u64),
+            Self::Uint(u) => Some(*u),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it is a boolean.
+    #[must_use]
+    pub const fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it is a string.
+    #[must_use]
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+/// Source of question values and strings for [`evaluate`].
+pub trait QuestionValues {
+    /// Returns the current value of question `id`, or `None` if it is
+    /// unknown.
+    fn question_value(&self, id: QuestionId) -> Option<IfrValue>;
+
+    /// Returns the string `id` of the package list containing the
+    /// expression. The default implementation returns `None`.
+    fn string(&self, id: StringId) -> Option<String> {
+        let _ = id;
+        None
+    }
+}
+
+impl QuestionValues for BTreeMap<QuestionId, IfrValue> {
+    fn question_value(&self, id: QuestionId) -> Option<IfrValue> {
+        self.get(&id).cloned()
+    }
+}
+
+/// Error returned by [`evaluate`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EvalError {
+    /// An opcode is truncated or has an invalid length.
+    Truncated {
+        /// Byte offset of the opcode.
+        offset: usize,
+    },
+    /// An opcode is not supported in expressions.
+    UnsupportedOpcode {
+        /// Byte offset of the opcode.
+        offset: usize,
+        /// The opcode.
+        op_code: IfrOpCode,
+    },
+    /// An opcode needs more operands than are on the stack.
+    StackUnderflow {
+        /// Byte offset of the opcode.
+        offset: usize,
+    },
+    /// The expression didn't leave exactly one value on the stack.
+    InvalidResult,
+}
+
+impl Display for EvalError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Truncated { offset } => write!(f, "truncated opcode at offset {offset}"),
+            Self::UnsupportedOpcode { offset, op_code } => {
+                write!(f, "unsupported opcode {op_code:?} at offset {offset}")
+            }
+            Self::StackUnderflow { offset } => {
+                write!(f, "missing operand for opcode at offset {offset}")
+            }
+            Self::InvalidResult => write!(f, "expression must produce exactly one value"),
+        }
+    }
+}
+
+impl core::error::Error for EvalError {}
+
+/// Format of `EFI_IFR_TO_STRING`.
+const STRING_UNSIGNED_DEC: u8 = 0;
+const STRING_SIGNED_DEC: u8 = 1;
+const STRING_LOWERCASE_HEX: u8 = 2;
+const STRING_UPPERCASE_HEX: u8 = 3;
+
+/// Expression stack, tracking the offset of the current opcode for errors.
+struct Stack {
+    values: Vec<IfrValue>,
+    offset: usize,
+}
+
+impl Stack {
+    fn pop(&mut self) -> Result<IfrValue, EvalError> {
+        self.values.pop().ok_or(EvalError::StackUnderflow {
+            offset: self.offset,
+        })
+    }
+
+    /// Pops two values, returning them in the order they were pushed.
+    fn pop2(&mut self) -> Result<(IfrValue, IfrValue), EvalError> {
+        let right = self.pop()?;
+        let left = self.pop()?;
+        Ok((left, right))
+    }
+
+    fn push(&mut self, value: IfrValue) {
+        self.values.push(value);
+    }
+}
+
+/// Reads a little-endian integer of `N` bytes at `offset` of `body`.
+fn read<const N: usize>(body: &[u8], offset: usize) -> Option<u64> {
+    let bytes = body.get(offset..offset + N)?;
+    let mut buf = [0; 8];
+    buf[..N].copy_from_slice(bytes);
+    Some(u64::from_le_bytes(buf))
+}
+
+/// Evaluates the IFR expression `expr`, a sequence of expression opcodes
+/// that leaves a single value on the stack.
+///
+/// # Errors
+///
+/// See [`EvalError`].
+pub fn evaluate(expr: &[u8], values: &dyn QuestionValues) -> Result<IfrValue, EvalError> {
+    let mut stack = Stack {
+        values: Vec::new(),
+        offset: 0,
+    };
+    while stack.offset < expr.len() {
+        let offset = stack.offset;
+        let truncated = EvalError::Truncated { offset };
+        let op_code = IfrOpCode(expr[offset]);
+        let len = usize::from(*expr.get(offset + 1).ok_or(truncated)? & 0x7f);
+        if len < 2 {
+            return Err(truncated);
+        }
+        let body = expr.get(offset + 2..offset + len).ok_or(truncated)?;
+        let u16_at = |i| read::<2>(body, i).map(|v| v as u16).ok_or(truncated);
+
+        let value = match op_code {
+            // Constants.
+            IfrOpCode::TRUE => IfrValue::Bool(true),
+            IfrOpCode::FALSE => IfrValue::Bool(false),
+            IfrOpCode::ZERO => IfrValue::Uint(0),
+            IfrOpCode::ONE => IfrValue::Uint(1),
+            IfrOpCode::ONES => IfrValue::Uint(u64::MAX),
+            IfrOpCode::UNDEFINED => IfrValue::Undefined,
+            IfrOpCode::UINT8 => IfrValue::Uint(read::<1>(body, 0).ok_or(truncated)?),
+            IfrOpCode::UINT16 => IfrValue::Uint(read::<2>(body, 0).ok_or(truncated)?),
+            IfrOpCode::UINT32 => IfrValue::Uint(read::<4>(body, 0).ok_or(truncated)?),
+            IfrOpCode::UINT64 => IfrValue::Uint(read::<8>(body, 0).ok_or(truncated)?),
+            IfrOpCode::STRING_REF1 => string(values, u64::from(u16_at(0)?)),
+
+            // Question references.
+            IfrOpCode::QUESTION_REF1 => question(values, u64::from(u16_at(0)?)),
+            IfrOpCode::QUESTION_REF2 => match stack.pop()?.as_uint() {
+                Some(id) => question(values, id),
+                None => IfrValue::Undefined,
+            },
+            IfrOpCode::QUESTION_REF3 => {
+                // The second and third forms refer to a question of another
+                // formset by device path, which can't be resolved here.
+                let device_path = read::<2>(body, 0).unwrap_or(0);
+                match stack.pop()?.as_uint() {
+                    Some(id) if device_path == 0 => question(values, id),
+                    _ => IfrValue::Undefined,
+                }
+            }
+            IfrOpCode::STRING_REF2 => match stack.pop()?.as_uint() {
+                Some(id) => string(values, id),
+                None => IfrValue::Undefined,
+            },
+            IfrOpCode::EQ_ID_VAL => {
+                let value = question(values, u64::from(u16_at(0)?));
+                compare(&value, &IfrValue::Uint(u64::from(u16_at(2)?)), |o| {
+                    o.is_eq()
+                })
+            }
+            IfrOpCode::EQ_ID_ID => {
+                let left = question(values, u64::from(u16_at(0)?));
+                let right = question(values, u64::from(u16_at(2)?));
+                compare(&left, &right, |o| o.is_eq())
+            }
+            IfrOpCode::EQ_ID_VAL_LIST => {
+                let value = question(values, u64::from(u16_at(0)?));
+                let count = usize::from(u16_at(2)?);
+                let list = (0..count)
+                    .map(|i| u16_at(4 + 2 * i))
+                    .collect::<Result<Vec<_>, _>>()?;
+                match value.as_uint() {
+                    Some(v) => IfrValue::Bool(list.iter().any(|&x| u64::from(x) == v)),
+                    None => IfrValue::Undefined,
+                }
+            }
+
+            // Unary operators.
+            IfrOpCode::NOT => match stack.pop()? {
+                IfrValue::Bool(b) => IfrValue::Bool(!b),
+                _ => IfrValue::Undefined,
+            },
+            IfrOpCode::BITWISE_NOT => uint(stack.pop()?.as_uint().map(|v| !v)),
+            IfrOpCode::TO_BOOLEAN => match stack.pop()? {
+                IfrValue::Bool(b) => IfrValue::Bool(b),
+                IfrValue::Uint(u) => IfrValue::Bool(u != 0),
+                IfrValue::String(s) if s.eq_ignore_ascii_case("true") => IfrValue::Bool(true),
+                IfrValue::String(s) if s.eq_ignore_ascii_case("false") => IfrValue::Bool(false),
+                _ => IfrValue::Undefined,
+            },
+            IfrOpCode::TO_UINT => match stack.pop()? {
+                IfrValue::String(s) => uint(parse_uint(&s)),
+                value => uint(value.as_uint()),
+            },
+            IfrOpCode::TO_STRING => {
+                let format = *body.first().ok_or(truncated)?;
+                to_string(stack.pop()?, format)
+            }
+            IfrOpCode::TO_LOWER => match stack.pop()? {
+                IfrValue::String(s) => IfrValue::String(s.to_lowercase()),
+                _ => IfrValue::Undefined,
+            },
+            IfrOpCode::TO_UPPER => match stack.pop()? {
+                IfrValue::String(s) => IfrValue::String(s.to_uppercase()),
+                _ => IfrValue::Undefined,
+            },
+            IfrOpCode::LENGTH => match stack.pop()? {
+                IfrValue::String(s) => IfrValue::Uint(s.chars().count() as u64),
+                _ => IfrValue::Undefined,
+            },
+            IfrOpCode::DUP => {
+                let value = stack.pop()?;
+                stack.push(value.clone());
+                value
+            }
+
+            // Binary operators.
+            IfrOpCode::AND | IfrOpCode::OR => {
+                let (left, right) = stack.pop2()?;
+                match (left.as_bool(), right.as_bool()) {
+                    (Some(l), Some(r)) if op_code == IfrOpCode::AND => IfrValue::Bool(l && r),
+                    (Some(l), Some(r)) => IfrValue::Bool(l || r),
+                    _ => IfrValue::Undefined,
+                }
+            }
+            IfrOpCode::EQUAL => binary(&mut stack, |l, r| compare(&l, &r, |o| o.is_eq()))?,
+            IfrOpCode::NOT_EQUAL => binary(&mut stack, |l, r| compare(&l, &r, |o| o.is_ne()))?,
+            IfrOpCode::GREATER_THAN => binary(&mut stack, |l, r| compare(&l, &r, |o| o.is_gt()))?,
+            IfrOpCode::GREATER_EQUAL => binary(&mut stack, |l, r| compare(&l, &r, |o| o.is_ge()))?,
+            IfrOpCode::LESS_THAN => binary(&mut stack, |l, r| compare(&l, &r, |o| o.is_lt()))?,
+            IfrOpCode::LESS_EQUAL => binary(&mut stack, |l, r| compare(&l, &r, |o| o.is_le()))?,
+            IfrOpCode::ADD => arithmetic(&mut stack, |l, r| Some(l.wrapping_add(r)))?,
+            IfrOpCode::SUBTRACT => arithmetic(&mut stack, |l, r| Some(l.wrapping_sub(r)))?,
+            IfrOpCode::MULTIPLY => arithmetic(&mut stack, |l, r| Some(l.wrapping_mul(r)))?,
+            IfrOpCode::DIVIDE => arithmetic(&mut stack, u64::checked_div)?,
+            IfrOpCode::MODULO => arithmetic(&mut stack, u64::checked_rem)?,
+            IfrOpCode::BITWISE_AND => arithmetic(&mut stack, |l, r| Some(l & r))?,
+            IfrOpCode::BITWISE_OR => arithmetic(&mut stack, |l, r| Some(l | r))?,
+            IfrOpCode::SHIFT_LEFT => arithmetic(&mut stack, |l, r| {
+                Some(l.checked_shl(r as u32).unwrap_or(0))
+            })?,
+            IfrOpCode::SHIFT_RIGHT => arithmetic(&mut stack, |l, r| {
+                Some(l.checked_shr(r as u32).unwrap_or(0))
+            })?,
+            IfrOpCode::CATENATE => binary(&mut stack, |l, r| match (l, r) {
+                (IfrValue::String(mut l), IfrValue::String(r)) => {
+                    l.push_str(&r);
+                    IfrValue::String(l)
+                }
+                _ => IfrValue::Undefined,
+            })?,
+
+            // Ternary operators.
+            IfrOpCode::CONDITIONAL => {
+                let (if_true, if_false) = stack.pop2()?;
+                match stack.pop()? {
+                    IfrValue::Bool(true) => if_true,
+                    IfrValue::Bool(false) => if_false,
+                    _ => IfrValue::Undefined,
+                }
+            }
+            IfrOpCode::MID => {
+                let (index, length) = stack.pop2()?;
+                match (stack.pop()?, index.as_uint(), length.as_uint()) {
+                    (IfrValue::String(s), Some(index), Some(length)) => IfrValue::String(
+                        s.chars()
+                            .skip(index as usize)
+                            .take(length as usize)
+                            .collect(),
+                    ),
+                    _ => IfrValue::Undefined,
+                }
+            }
+            IfrOpCode::FIND => {
+                // Format 0 is case sensitive, 1 is case insensitive.
+                let format = *body.first().ok_or(truncated)?;
+                let (needle, index) = stack.pop2()?;
+                match (stack.pop()?, needle, index.as_uint()) {
+                    (IfrValue::String(haystack), IfrValue::String(needle), Some(index)) => {
+                        find(&haystack, &needle, index as usize, format == 1)
+                    }
+                    _ => IfrValue::Undefined,
+                }
+            }
+
+            op_code => return Err(EvalError::UnsupportedOpcode { offset, op_code }),
+        };
+        stack.push(value);
+        stack.offset += len;
+    }
+
+    match stack.values.len() {
+        1 => Ok(stack.values.remove(0)),
+        _ => Err(EvalError::InvalidResult),
+    }
+}
+
+/// Returns whether `op_code` is an expression opcode, i.e. may appear in
+/// the expression of a condition.
+pub(crate) const fn is_expression_opcode(op_code: IfrOpCode) -> bool {
+    matches!(
+        op_code.0,
+        0x12..=0x17 | 0x20..=0x22 | 0x28 | 0x2a..=0x2c | 0x2f..=0x59 | 0x5e | 0x60 | 0x64
+    )
+}
+
+/// Looks up a question value, `Undefined` if unknown.
+fn question(values: &dyn QuestionValues, id: u64) -> IfrValue {
+    QuestionId::try_from(id)
+        .ok()
+        .and_then(|id| values.question_value(id))
+        .unwrap_or(IfrValue::Undefined)
+}
+
+/// Looks up a string, `Undefined` if unknown.
+fn string(values: &dyn QuestionValues, id: u64) -> IfrValue {
+    StringId::try_from(id)
+        .ok()
+        .and_then(|id| values.string(id))
+        .map_or(IfrValue::Undefined, IfrValue::String)
+}
+
+/// Converts an optional integer to a value.
+fn uint(value: Option<u64>) -> IfrValue {
+    value.map_or(IfrValue::Undefined, IfrValue::Uint)
+}
+
+/// Compares two values of compatible types.
+fn compare(left: &IfrValue, right: &IfrValue, f: fn(core::cmp::Ordering) -> bool) -> IfrValue {
+    let ordering = match (left, right) {
+        (IfrValue::String(l), IfrValue::String(r)) => l.cmp(r),
+        _ => match (left.as_uint(), right.as_uint()) {
+            (Some(l), Some(r)) => l.cmp(&r),
+            _ => return IfrValue::Undefined,
+        },
+    };
+    IfrValue::Bool(f(ordering))
+}
+
+/// Applies a binary operator to the two topmost values.
+fn binary(
+    stack: &mut Stack,
+    f: impl FnOnce(IfrValue, IfrValue) -> IfrValue,
+) -> Result<IfrValue, EvalError> {
+    let (left, right) = stack.pop2()?;
+    Ok(f(left, right))
+}
+
+/// Applies an integer operator to the two topmost values. The result is
+/// `Undefined` if either value is not an integer or `f` returns `None`.
+fn arithmetic(
+    stack: &mut Stack,
+    f: impl FnOnce(u64, u64) -> Option<u64>,
+) -> Result<IfrValue, EvalError> {
+    binary(stack, |left, right| {
+        match (left.as_uint(), right.as_uint()) {
+            (Some(l), Some(r)) => uint(f(l, r)),
+            _ => IfrValue::Undefined,
+        }
+    })
+}
+
+/// Parses a decimal or `0x`-prefixed hexadecimal integer.
+fn parse_uint(s: &str) -> Option<u64> {
+    let s = s.trim();
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+/// Implementation of `EFI_IFR_TO_STRING`.
+fn to_string(value: IfrValue, format: u8) -> IfrValue {
+    let s = match value {
+        IfrValue::String(s) => s,
+        IfrValue::Bool(b) => String::from(if b { "True" } else { "False" }),
+        IfrValue::Uint(u) => match format {
+            STRING_UNSIGNED_DEC => u.to_string(),
+            STRING_SIGNED_DEC => (u as i64).to_string(),
+            STRING_LOWERCASE_HEX => alloc::format!("{u:x}"),
+            STRING_UPPERCASE_HEX => alloc::format!("{u:X}"),
+            _ => return IfrValue::Undefined,
+        },
+        IfrValue::Undefined => return IfrValue::Undefined,
+    };
+    IfrValue::String(s)
+}
+
+/// Implementation of `EFI_IFR_FIND`: the character index of `needle` in
+/// `haystack`, starting at character `index`, or `u64::MAX` if not found.
+fn find(haystack: &str, needle: &str, index: usize, ignore_case: bool) -> IfrValue {
+    let (haystack, needle) = if ignore_case {
+        (haystack.to_lowercase(), needle.to_lowercase())
+    } else {
+        (haystack.into(), needle.into())
+    };
+    let Some((start, _)) = haystack.char_indices().nth(index) else {
+        return IfrValue::Uint(u64::MAX);
+    };
+    match haystack[start..].find(&needle) {
+        Some(pos) => IfrValue::Uint((index + haystack[start..start + pos].chars().count()) as u64),
+        None => IfrValue::Uint(u64::MAX),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Values;
+
+    impl QuestionValues for Values {
+        fn question_value(&self, id: QuestionId) -> Option<IfrValue> {
+            match id {
+                1 => Some(IfrValue::Uint(3)),
+                2 => Some(IfrValue::Bool(true)),
+                3 => Some(IfrValue::String("Hello World".into())),
+                _ => None,
+            }
+        }
+
+        fn string(&self, id: StringId) -> Option<String> {
+            (id == 5).then(|| "World".into())
+        }
+    }
+
+    fn eval(expr: &[u8]) -> Result<IfrValue, EvalError> {
+        evaluate(expr, &Values)
+    }
+
+    const TRUE: [u8; 2] = [0x46, 2];
+    const FALSE: [u8; 2] = [0x47, 2];
+
+    #[test]
+    fn test_logic() {
+        // EQ_ID_VAL q1 == 3
+        assert_eq!(eval(&[0x12, 6, 1, 0, 3, 0]), Ok(IfrValue::Bool(true)));
+        // EQ_ID_VAL q2 == 1 (booleans compare as integers)
+        assert_eq!(eval(&[0x12, 6, 2, 0, 1, 0]), Ok(IfrValue::Bool(true)));
+        // EQ_ID_VAL_LIST q1 in [1, 2]
+        assert_eq!(
+            eval(&[0x14, 10, 1, 0, 2, 0, 1, 0, 2, 0]),
+            Ok(IfrValue::Bool(false))
+        );
+        // TRUE FALSE AND, TRUE FALSE OR, NOT
+        assert_eq!(
+            eval(&[TRUE, FALSE, [0x15, 2]].concat()),
+            Ok(IfrValue::Bool(false))
+        );
+        assert_eq!(
+            eval(&[TRUE, FALSE, [0x16, 2], [0x17, 2]].concat()),
+            Ok(IfrValue::Bool(false))
+        );
+        // Unknown question: EQ_ID_VAL q9 == 0, NOT
+        assert_eq!(
+            eval(&[0x12, 6, 9, 0, 0, 0, 0x17, 2]),
+            Ok(IfrValue::Undefined)
+        );
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        // QUESTION_REF1 q1, UINT8 4, ADD, UINT16 2, MULTIPLY
+        let expr = [0x40, 4, 1, 0, 0x42, 3, 4, 0x3a, 2, 0x43, 4, 2, 0, 0x3c, 2];
+        assert_eq!(eval(&expr), Ok(IfrValue::Uint(14)));
+        // ONE ZERO DIVIDE
+        assert_eq!(eval(&[0x53, 2, 0x52, 2, 0x3d, 2]), Ok(IfrValue::Undefined));
+        // UINT8 1, QUESTION_REF3, and with a device path
+        assert_eq!(eval(&[0x42, 3, 1, 0x51, 2]), Ok(IfrValue::Uint(3)));
+        assert_eq!(eval(&[0x42, 3, 1, 0x51, 4, 1, 0]), Ok(IfrValue::Undefined));
+        // UINT8 7, UINT8 2, LESS_THAN
+        assert_eq!(
+            eval(&[0x42, 3, 7, 0x42, 3, 2, 0x33, 2]),
+            Ok(IfrValue::Bool(false))
+        );
+        // TRUE ONE, CONDITIONAL with TRUE first: ONE
+        assert_eq!(
+            eval(&[TRUE, [0x53, 2], [0x52, 2], [0x50, 2]].concat()),
+            Ok(IfrValue::Uint(1))
+        );
+    }
+
+    #[test]
+    fn test_strings() {
+        // QUESTION_REF1 q3, STRING_REF1 5, UINT8 0, FIND
+        let expr = [0x40, 4, 3, 0, 0x4e, 4, 5, 0, 0x42, 3, 0, 0x4c, 3, 0];
+        assert_eq!(eval(&expr), Ok(IfrValue::Uint(6)));
+        // QUESTION_REF1 q3, UINT8 6, UINT8 5, MID, TO_UPPER
+        let expr = [0x40, 4, 3, 0, 0x42, 3, 6, 0x42, 3, 5, 0x4b, 2, 0x21, 2];
+        assert_eq!(eval(&expr), Ok(IfrValue::String("WORLD".into())));
+        // QUESTION_REF1 q3, LENGTH
+        assert_eq!(eval(&[0x40, 4, 3, 0, 0x56, 2]), Ok(IfrValue::Uint(11)));
+        // UINT8 255, TO_STRING hex, STRING_REF1 5, CATENATE
+        let expr = [0x42, 3, 255, 0x49, 3, 2, 0x4e, 4, 5, 0, 0x5e, 2];
+        assert_eq!(eval(&expr), Ok(IfrValue::String("ffWorld".into())));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(eval(&[]), Err(EvalError::InvalidResult));
+        assert_eq!(
+            eval(&[0x15, 2]),
+            Err(EvalError::StackUnderflow { offset: 0 })
+        );
+        assert_eq!(
+            eval(&[0x46, 2, 0x12, 6, 1]),
+            Err(EvalError::Truncated { offset: 2 })
+        );
+        assert_eq!(
+            eval(&[0x01, 2]),
+            Err(EvalError::UnsupportedOpcode {
+                offset: 0,
+                op_code: IfrOpCode::FORM
+            })
+        );
+        assert_eq!(eval(&[TRUE, TRUE].concat()), Err(EvalError::InvalidResult));
+    }
+}
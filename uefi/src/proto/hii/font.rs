@@ -0,0 +1,412 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! HII Font protocol.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ptr;
+use core::{mem, slice};
+use uefi_macros::unsafe_protocol;
+use uefi_raw::protocol::hii::font::{FontDisplayInfo, FontHandle, FontInfo, HiiFontProtocol};
+use uefi_raw::protocol::hii::image::{ImageOutput, ImageOutputDest};
+
+use super::{BltImage, HiiHandle, StringId, free, take_image};
+use crate::proto::console::gop::BltPixel;
+use crate::{CStr8, CStr16, CString16, Char16, Result, Status, StatusExt};
+
+pub use uefi_raw::protocol::hii::font::{FontInfoMask, HiiFontStyle, HiiOutFlags, HiiRowInfo};
+
+/// Font and colors for rendering text with [`HiiFont`].
+///
+/// Fields that are `None` use the system default: the system font, its size
+/// and style, and the colors of the console.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TextStyle {
+    /// Name of the font.
+    pub font_name: Option<CString16>,
+    /// Height of the font in pixels.
+    pub font_size: Option<u16>,
+    /// Style of the font, such as bold.
+    pub font_style: Option<HiiFontStyle>,
+    /// Color of the text.
+    pub foreground: Option<BltPixel>,
+    /// Color of the background.
+    pub background: Option<BltPixel>,
+}
+
+impl TextStyle {
+    /// Encodes the style as an `EFI_FONT_DISPLAY_INFO` in a buffer with the
+    /// alignment of the structure.
+    fn to_display_info(&self) -> Vec<u32> {
+        let mut mask = FontInfoMask::empty();
+        mask.set(FontInfoMask::SYS_FONT, self.font_name.is_none());
+        mask.set(FontInfoMask::SYS_SIZE, self.font_size.is_none());
+        mask.set(FontInfoMask::SYS_STYLE, self.font_style.is_none());
+        mask.set(FontInfoMask::SYS_FORE_COLOR, self.foreground.is_none());
+        mask.set(FontInfoMask::SYS_BACK_COLOR, self.background.is_none());
+
+        let mut bytes = Vec::new();
+        for color in [self.foreground, self.background] {
+            let color = color.unwrap_or(BltPixel::new(0, 0, 0));
+            bytes.extend_from_slice(&[color.blue, color.green, color.red, 0]);
+        }
+        bytes.extend_from_slice(&mask.bits().to_le_bytes());
+        let style = self.font_style.unwrap_or(HiiFontStyle::NORMAL);
+        bytes.extend_from_slice(&style.bits().to_le_bytes());
+        bytes.extend_from_slice(&self.font_size.unwrap_or(0).to_le_bytes());
+        match &self.font_name {
+            Some(name) => bytes.extend_from_slice(name.as_bytes()),
+            None => bytes.extend_from_slice(&[0, 0]),
+        }
+
+        let mut info = vec![
+            0u32;
+            bytes
+                .len()
+                .div_ceil(4)
+                .max(mem::size_of::<FontDisplayInfo>() / 4)
+        ];
+        for (word, chunk) in info.iter_mut().zip(bytes.chunks(4)) {
+            let mut buf = [0; 4];
+            buf[..chunk.len()].copy_from_slice(chunk);
+            *word = u32::from_ne_bytes(buf);
+        }
+        info
+    }
+}
+
+/// A font known to the HII database, returned by [`HiiFont::system_font`]
+/// and [`HiiFont::fonts`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FontDescription {
+    /// Name of the font.
+    pub name: CString16,
+    /// Height of the font in pixels.
+    pub size: u16,
+    /// Style of the font.
+    pub style: HiiFontStyle,
+}
+
+/// The glyph of a character, returned by [`HiiFont::glyph`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Glyph {
+    /// Image of the glyph.
+    pub image: BltImage,
+    /// Offset of the baseline from the bottom of the image, in pixels.
+    pub baseline: usize,
+}
+
+/// The HII Font Protocol.
+///
+/// # UEFI Spec Description
+///
+/// The protocol which provides the ability to render strings into images,
+/// and to look up glyphs and fonts.
+///
+/// Rendering into a [`BltImage`] makes it possible to draw text with the
+/// firmware's fonts on consoles that only support graphics output:
+///
+/// ```no_run
+/// use uefi::proto::console::gop::GraphicsOutput;
+/// use uefi::proto::hii::font::{HiiFont, HiiOutFlags, TextStyle};
+/// use uefi::{Result, boot, cstr16};
+///
+/// fn hello(gop: &mut GraphicsOutput) -> Result {
+///     let font = boot::open_protocol_exclusive::<HiiFont>(
+///         boot::get_handle_for_protocol::<HiiFont>()?,
+///     )?;
+///     let image = font.string_to_image(
+///         cstr16!("Hello"),
+///         &TextStyle::default(),
+///         HiiOutFlags::empty(),
+///     )?;
+///     gop.blt(image.to_video((10, 10)))
+/// }
+/// ```
+#[derive(Debug)]
+#[repr(transparent)]
+#[unsafe_protocol(HiiFontProtocol::GUID)]
+pub struct HiiFont(HiiFontProtocol);
+
+impl HiiFont {
+    /// Renders `string` into a new image sized to fit the text.
+    ///
+    /// The firmware renders into an image of a fixed size, so long strings
+    /// may be clipped; set [`HiiOutFlags::WRAP`] to wrap them into several
+    /// lines instead.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::INVALID_PARAMETER`]: `flags` are invalid, e.g. both
+    ///   [`HiiOutFlags::WRAP`] and [`HiiOutFlags::IGNORE_LINE_BREAK`].
+    /// * [`Status::OUT_OF_RESOURCES`]: the image could not be allocated.
+    pub fn string_to_image(
+        &self,
+        string: &CStr16,
+        style: &TextStyle,
+        flags: HiiOutFlags,
+    ) -> Result<BltImage> {
+        let info = style.to_display_info();
+        let mut blt = ptr::null_mut();
+        let mut rows = ptr::null_mut();
+        let mut row_count = 0;
+        unsafe {
+            (self.0.string_to_image)(
+                &self.0,
+                image_flags(flags),
+                string.as_ptr().cast(),
+                info.as_ptr().cast(),
+                &mut blt,
+                0,
+                0,
+                &mut rows,
+                &mut row_count,
+                ptr::null_mut(),
+            )
+            .to_result()?;
+            Ok(take_text_image(blt, rows, row_count))
+        }
+    }
+
+    /// Renders the string `string_id` of `package_list` into a new image
+    /// sized to fit the text. The string is looked up in `language`, or in
+    /// the current platform language if `language` is `None`.
+    ///
+    /// See [`string_to_image`] for the handling of long strings.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::NOT_FOUND`]: the string is not in the package list.
+    /// * [`Status::INVALID_PARAMETER`]: `flags` are invalid.
+    ///
+    /// [`string_to_image`]: Self::string_to_image
+    pub fn string_id_to_image(
+        &self,
+        package_list: HiiHandle,
+        string_id: StringId,
+        language: Option<&CStr8>,
+        style: &TextStyle,
+        flags: HiiOutFlags,
+    ) -> Result<BltImage> {
+        let info = style.to_display_info();
+        let mut blt = ptr::null_mut();
+        let mut rows = ptr::null_mut();
+        let mut row_count = 0;
+        unsafe {
+            (self.0.string_id_to_image)(
+                &self.0,
+                image_flags(flags),
+                package_list.as_ptr(),
+                string_id,
+                language.map_or(ptr::null(), |language| language.as_ptr().cast()),
+                info.as_ptr().cast(),
+                &mut blt,
+                0,
+                0,
+                &mut rows,
+                &mut row_count,
+                ptr::null_mut(),
+            )
+            .to_result()?;
+            Ok(take_text_image(blt, rows, row_count))
+        }
+    }
+
+    /// Renders `string` into `image`, with the top left corner of the text
+    /// at `x`, `y`. Text outside of the image is clipped.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::INVALID_PARAMETER`]: `flags` are invalid, or the image is
+    ///   larger than 65535 pixels in either direction.
+    pub fn draw_string(
+        &self,
+        string: &CStr16,
+        style: &TextStyle,
+        flags: HiiOutFlags,
+        image: &mut BltImage,
+        (x, y): (usize, usize),
+    ) -> Result {
+        let (Ok(width), Ok(height)) = (u16::try_from(image.width()), u16::try_from(image.height()))
+        else {
+            return Err(Status::INVALID_PARAMETER.into());
+        };
+        let info = style.to_display_info();
+        let mut output = ImageOutput {
+            width,
+            height,
+            image: ImageOutputDest {
+                bitmap: image.pixels_mut().as_mut_ptr().cast(),
+            },
+        };
+        let mut blt = ptr::from_mut(&mut output);
+        unsafe {
+            (self.0.string_to_image)(
+                &self.0,
+                image_flags(flags) | HiiOutFlags::CLIP,
+                string.as_ptr().cast(),
+                info.as_ptr().cast(),
+                &mut blt,
+                x,
+                y,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        }
+        .to_result()
+    }
+
+    /// Returns the glyph of `c` in the font of `style`. If the font has no
+    /// glyph for `c`, the glyph of the replacement character is returned.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::OUT_OF_RESOURCES`]: the image could not be allocated.
+    pub fn glyph(&self, c: Char16, style: &TextStyle) -> Result<Glyph> {
+        let info = style.to_display_info();
+        let mut blt = ptr::null_mut();
+        let mut baseline = 0;
+        let status = unsafe {
+            (self.0.get_glyph)(
+                &self.0,
+                c.into(),
+                info.as_ptr().cast(),
+                &mut blt,
+                &mut baseline,
+            )
+        };
+        if status != Status::WARN_UNKNOWN_GLYPH {
+            status.to_result()?;
+        }
+        let image = unsafe { take_image(blt, None) };
+        Ok(Glyph { image, baseline })
+    }
+
+    /// Returns the system default font.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::NOT_FOUND`]: there is no system font.
+    pub fn system_font(&self) -> Result<FontDescription> {
+        let mut handle = ptr::null_mut();
+        self.font_info(&mut handle, ptr::null())
+    }
+
+    /// Returns all fonts of the HII database. Fonts that are only provided
+    /// as simple font packages, such as the system font of many
+    /// implementations, are not included.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error of the firmware, other than
+    /// [`Status::NOT_FOUND`] at the end of the list.
+    pub fn fonts(&self) -> Result<Vec<FontDescription>> {
+        let any = TextStyle {
+            font_name: Some(CString16::new()),
+            font_size: Some(0),
+            font_style: Some(HiiFontStyle::NORMAL),
+            ..TextStyle::default()
+        };
+        let mut info = any.to_display_info();
+        // SAFETY: the buffer holds a `FontDisplayInfo`.
+        unsafe {
+            (*info.as_mut_ptr().cast::<FontDisplayInfo>()).font_mask_info =
+                FontInfoMask::ANY_FONT | FontInfoMask::ANY_SIZE | FontInfoMask::ANY_STYLE;
+        }
+
+        let mut fonts = Vec::new();
+        let mut handle = ptr::null_mut();
+        loop {
+            match self.font_info(&mut handle, info.as_ptr().cast()) {
+                Ok(font) => fonts.push(font),
+                Err(err) if err.status() == Status::NOT_FOUND => return Ok(fonts),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Finds the font after `handle` matching `info`, and advances `handle`
+    /// to it.
+    fn font_info(
+        &self,
+        handle: &mut FontHandle,
+        info: *const FontDisplayInfo,
+    ) -> Result<FontDescription> {
+        let mut out = ptr::null_mut::<FontDisplayInfo>();
+        unsafe {
+            (self.0.get_font_info)(&self.0, handle, info, &mut out, ptr::null()).to_result()?;
+            let font_info: *const FontInfo = ptr::addr_of!((*out).font_info);
+            let font = FontDescription {
+                name: CStr16::from_ptr(ptr::addr_of!((*font_info).font_name).cast()).into(),
+                size: (*font_info).font_size,
+                style: (*font_info).font_style,
+            };
+            free(out);
+            Ok(font)
+        }
+    }
+}
+
+/// Returns `flags` without [`HiiOutFlags::DIRECT_TO_SCREEN`], since the
+/// output is always an image.
+fn image_flags(flags: HiiOutFlags) -> HiiOutFlags {
+    flags - HiiOutFlags::DIRECT_TO_SCREEN
+}
+
+/// Copies an image of rendered text allocated by the firmware, cropped to
+/// the rows of text, and frees it along with the row information.
+unsafe fn take_text_image(
+    blt: *mut ImageOutput,
+    rows: *mut HiiRowInfo,
+    row_count: usize,
+) -> BltImage {
+    unsafe {
+        let size = (!rows.is_null()).then(|| {
+            let rows = slice::from_raw_parts(rows, row_count);
+            let width = rows.iter().map(|row| row.line_width).max().unwrap_or(0);
+            let height = rows.iter().map(|row| row.line_height).sum();
+            (width, height)
+        });
+        free(rows);
+        take_image(blt, size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cstr16;
+
+    #[test]
+    fn test_display_info() {
+        let info = TextStyle::default().to_display_info();
+        assert_eq!(info.len() * 4, mem::size_of::<FontDisplayInfo>());
+        let info = unsafe { &*info.as_ptr().cast::<FontDisplayInfo>() };
+        assert_eq!(
+            info.font_mask_info,
+            FontInfoMask::SYS_FONT
+                | FontInfoMask::SYS_SIZE
+                | FontInfoMask::SYS_STYLE
+                | FontInfoMask::SYS_FORE_COLOR
+                | FontInfoMask::SYS_BACK_COLOR
+        );
+
+        let style = TextStyle {
+            font_name: Some(cstr16!("sysdefault").into()),
+            font_size: Some(19),
+            font_style: Some(HiiFontStyle::BOLD),
+            foreground: Some(BltPixel::new(0xff, 0x80, 0)),
+            background: None,
+        };
+        let buf = style.to_display_info();
+        let info = unsafe { &*buf.as_ptr().cast::<FontDisplayInfo>() };
+        assert_eq!(info.font_mask_info, FontInfoMask::SYS_BACK_COLOR);
+        assert_eq!(info.foreground_color.red, 0xff);
+        assert_eq!(info.foreground_color.green, 0x80);
+        assert_eq!(info.font_info.font_style, HiiFontStyle::BOLD);
+        assert_eq!(info.font_info.font_size, 19);
+        let name = unsafe { CStr16::from_ptr(info.font_info.font_name.as_ptr().cast()) };
+        assert_eq!(name, cstr16!("sysdefault"));
+    }
+}
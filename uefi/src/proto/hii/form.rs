@@ -0,0 +1,569 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Building IFR form packages.
+//!
+//! Forms are described in the Internal Forms Representation (IFR), a
+//! sequence of opcodes stored in an HII form package. [`FormSetBuilder`]
+//! creates the IFR for a formset, which can be added to a package list with
+//! [`PackageListBuilder::forms`].
+//!
+//! Strings are referenced by their [`StringId`], and must be added to the
+//! same package list, e.g. from a [`StringTable`].
+//!
+//! [`PackageListBuilder::forms`]: super::package::PackageListBuilder::forms
+//! [`StringTable`]: super::package::StringTable
+
+use super::varstore::VarstoreField;
+use super::{FormId, QuestionId, StringId, VarstoreId};
+use crate::runtime::VariableAttributes;
+use crate::{CStr8, Guid};
+use alloc::vec::Vec;
+use uefi_raw::protocol::hii::ifr::{
+    IfrCheckboxFlags, IfrDefaultStore, IfrFormSet, IfrNumericFlags, IfrOneOfOptionFlags, IfrOpCode,
+    IfrType,
+};
+
+pub use uefi_raw::protocol::hii::ifr::IfrQuestionFlags;
+
+/// Size of the values of a numeric or one-of question.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum NumericSize {
+    /// 8-bit value.
+    U8,
+    /// 16-bit value.
+    U16,
+    /// 32-bit value.
+    U32,
+    /// 64-bit value.
+    U64,
+}
+
+impl NumericSize {
+    /// Returns the size in bytes.
+    #[must_use]
+    pub const fn bytes(self) -> usize {
+        match self {
+            Self::U8 => 1,
+            Self::U16 => 2,
+            Self::U32 => 4,
+            Self::U64 => 8,
+        }
+    }
+
+    const fn flags(self) -> IfrNumericFlags {
+        match self {
+            Self::U8 => IfrNumericFlags::SIZE_1,
+            Self::U16 => IfrNumericFlags::SIZE_2,
+            Self::U32 => IfrNumericFlags::SIZE_4,
+            Self::U64 => IfrNumericFlags::SIZE_8,
+        }
+    }
+
+    const fn ifr_type(self) -> IfrType {
+        match self {
+            Self::U8 => IfrType::NUM_SIZE_8,
+            Self::U16 => IfrType::NUM_SIZE_16,
+            Self::U32 => IfrType::NUM_SIZE_32,
+            Self::U64 => IfrType::NUM_SIZE_64,
+        }
+    }
+
+    /// Appends `value`, truncated to the size.
+    fn push(self, buf: &mut Vec<u8>, value: u64) {
+        buf.extend_from_slice(&value.to_le_bytes()[..self.bytes()]);
+    }
+}
+
+/// Common properties of a question.
+///
+/// # Example
+///
+/// ```
+/// use uefi::proto::hii::form::Question;
+///
+/// // Question 1, with prompt and help strings 2 and 3, stored at offset 4
+/// // of varstore 1.
+/// let question = Question::new(1, 2, 3).varstore(1, 4);
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Question {
+    id: QuestionId,
+    prompt: StringId,
+    help: StringId,
+    varstore: VarstoreId,
+    offset: u16,
+    flags: IfrQuestionFlags,
+}
+
+impl Question {
+    /// Creates a question without storage.
+    #[must_use]
+    pub const fn new(id: QuestionId, prompt: StringId, help: StringId) -> Self {
+        Self {
+            id,
+            prompt,
+            help,
+            varstore: 0,
+            offset: 0,
+            flags: IfrQuestionFlags::empty(),
+        }
+    }
+
+    /// Stores the value of the question at byte `offset` of `varstore`.
+    #[must_use]
+    pub const fn varstore(mut self, varstore: VarstoreId, offset: u16) -> Self {
+        self.varstore = varstore;
+        self.offset = offset;
+        self
+    }
+
+    /// Stores the value of the question in `field` of `varstore`, see
+    /// [`Varstore`].
+    ///
+    /// [`Varstore`]: super::varstore::Varstore
+    #[must_use]
+    pub const fn field<T>(self, varstore: VarstoreId, field: VarstoreField<T>) -> Self {
+        self.varstore(varstore, field.offset())
+    }
+
+    /// Sets the question flags.
+    #[must_use]
+    pub const fn flags(mut self, flags: IfrQuestionFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Returns the question ID.
+    #[must_use]
+    pub const fn id(&self) -> QuestionId {
+        self.id
+    }
+
+    /// Appends the `EFI_IFR_QUESTION_HEADER`.
+    fn push(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.prompt.to_le_bytes());
+        buf.extend_from_slice(&self.help.to_le_bytes());
+        buf.extend_from_slice(&self.id.to_le_bytes());
+        buf.extend_from_slice(&self.varstore.to_le_bytes());
+        buf.extend_from_slice(&self.offset.to_le_bytes());
+        buf.push(self.flags.bits());
+    }
+}
+
+/// Appends an opcode with the given body.
+///
+/// # Panics
+///
+/// Panics if the opcode is longer than 127 bytes, the maximum length that
+/// can be encoded in the opcode header.
+fn push_op(ifr: &mut Vec<u8>, op_code: IfrOpCode, scope: bool, body: &[u8]) {
+    let len = 2 + body.len();
+    assert!(len <= 0x7f, "IFR opcode too large");
+    ifr.push(op_code.0);
+    ifr.push(len as u8 | (u8::from(scope) << 7));
+    ifr.extend_from_slice(body);
+}
+
+/// Appends an `EFI_IFR_END` opcode, closing the innermost scope.
+fn push_end(ifr: &mut Vec<u8>) {
+    push_op(ifr, IfrOpCode::END, false, &[]);
+}
+
+/// Builder for the IFR of a formset.
+///
+/// # Example
+///
+/// ```
+/// use uefi::guid;
+/// use uefi::proto::hii::form::{FormSetBuilder, NumericSize, Question};
+/// use uefi::runtime::VariableAttributes;
+///
+/// let ifr = FormSetBuilder::new(guid!("d6e5b6a9-0a0d-4a4b-9a7f-9d3b6f0c6e61"), 1, 2)
+///     .varstore_efi(
+///         1,
+///         guid!("d6e5b6a9-0a0d-4a4b-9a7f-9d3b6f0c6e61"),
+///         uefi::cstr8!("Config"),
+///         VariableAttributes::NON_VOLATILE | VariableAttributes::BOOTSERVICE_ACCESS,
+///         2,
+///     )
+///     .form(1, 1, |form| {
+///         form.subtitle(3)
+///             .checkbox(Question::new(1, 4, 5).varstore(1, 0), true)
+///             .numeric(
+///                 Question::new(2, 6, 7).varstore(1, 1),
+///                 NumericSize::U8,
+///                 0..=10,
+///                 1,
+///                 5,
+///             );
+///     })
+///     .build();
+/// ```
+#[derive(Debug)]
+pub struct FormSetBuilder {
+    ifr: Vec<u8>,
+}
+
+impl FormSetBuilder {
+    /// Creates a builder for a formset identified by `guid`, with the given
+    /// title and help strings. The formset is shown in the platform setup.
+    #[must_use]
+    pub fn new(guid: Guid, title: StringId, help: StringId) -> Self {
+        let mut body = Vec::new();
+        body.extend_from_slice(&guid.to_bytes());
+        body.extend_from_slice(&title.to_le_bytes());
+        body.extend_from_slice(&help.to_le_bytes());
+        // One class GUID.
+        body.push(1);
+        body.extend_from_slice(&IfrFormSet::PLATFORM_SETUP_FORMSET_GUID.to_bytes());
+
+        let mut ifr = Vec::new();
+        push_op(&mut ifr, IfrOpCode::FORM_SET, true, &body);
+        Self { ifr }
+    }
+
+    /// Declares a default store. Defaults of questions are added to the
+    /// standard default store ([`IfrDefaultStore::STANDARD`]), which should
+    /// be declared for the defaults to be used by the form browser.
+    #[must_use]
+    pub fn default_store(mut self, id: u16, name: StringId) -> Self {
+        let mut body = Vec::new();
+        body.extend_from_slice(&name.to_le_bytes());
+        body.extend_from_slice(&id.to_le_bytes());
+        push_op(&mut self.ifr, IfrOpCode::DEFAULTSTORE, false, &body);
+        self
+    }
+
+    /// Declares a buffer varstore of `size` bytes, whose contents are
+    /// provided by the HII Config Access protocol of the driver.
+    #[must_use]
+    pub fn varstore(mut self, id: VarstoreId, guid: Guid, name: &CStr8, size: u16) -> Self {
+        let mut body = Vec::new();
+        body.extend_from_slice(&guid.to_bytes());
+        body.extend_from_slice(&id.to_le_bytes());
+        body.extend_from_slice(&size.to_le_bytes());
+        body.extend_from_slice(name.as_bytes());
+        push_op(&mut self.ifr, IfrOpCode::VARSTORE, false, &body);
+        self
+    }
+
+    /// Declares a varstore of `size` bytes stored in the UEFI variable
+    /// `name` of vendor `guid`.
+    #[must_use]
+    pub fn varstore_efi(
+        mut self,
+        id: VarstoreId,
+        guid: Guid,
+        name: &CStr8,
+        attributes: VariableAttributes,
+        size: u16,
+    ) -> Self {
+        let mut body = Vec::new();
+        body.extend_from_slice(&id.to_le_bytes());
+        body.extend_from_slice(&guid.to_bytes());
+        body.extend_from_slice(&attributes.bits().to_le_bytes());
+        body.extend_from_slice(&size.to_le_bytes());
+        body.extend_from_slice(name.as_bytes());
+        push_op(&mut self.ifr, IfrOpCode::VARSTORE_EFI, false, &body);
+        self
+    }
+
+    /// Adds a form with the given ID and title. Its contents are added by
+    /// `f`.
+    #[must_use]
+    pub fn form(mut self, id: FormId, title: StringId, f: impl FnOnce(&mut FormBuilder)) -> Self {
+        let mut body = Vec::new();
+        body.extend_from_slice(&id.to_le_bytes());
+        body.extend_from_slice(&title.to_le_bytes());
+        push_op(&mut self.ifr, IfrOpCode::FORM, true, &body);
+        f(&mut FormBuilder { ifr: &mut self.ifr });
+        push_end(&mut self.ifr);
+        self
+    }
+
+    /// Finishes the formset and returns the IFR, which can be added to a
+    /// package list with [`PackageListBuilder::forms`].
+    ///
+    /// [`PackageListBuilder::forms`]: super::package::PackageListBuilder::forms
+    #[must_use]
+    pub fn build(mut self) -> Vec<u8> {
+        push_end(&mut self.ifr);
+        self.ifr
+    }
+}
+
+/// Builder for the contents of a form, see [`FormSetBuilder::form`].
+#[derive(Debug)]
+pub struct FormBuilder<'a> {
+    ifr: &'a mut Vec<u8>,
+}
+
+impl FormBuilder<'_> {
+    /// Adds a subtitle.
+    pub fn subtitle(&mut self, prompt: StringId) -> &mut Self {
+        let mut body = Vec::new();
+        body.extend_from_slice(&prompt.to_le_bytes());
+        body.extend_from_slice(&0u16.to_le_bytes());
+        body.push(0);
+        push_op(self.ifr, IfrOpCode::SUBTITLE, false, &body);
+        self
+    }
+
+    /// Adds a static text with the given prompt, help, and a second text
+    /// shown to the right of the prompt (0 for none).
+    pub fn text(&mut self, prompt: StringId, help: StringId, text: StringId) -> &mut Self {
+        let mut body = Vec::new();
+        body.extend_from_slice(&prompt.to_le_bytes());
+        body.extend_from_slice(&help.to_le_bytes());
+        body.extend_from_slice(&text.to_le_bytes());
+        push_op(self.ifr, IfrOpCode::TEXT, false, &body);
+        self
+    }
+
+    /// Adds a link to the form `form` of the same formset.
+    pub fn goto(&mut self, question: Question, form: FormId) -> &mut Self {
+        let mut body = Vec::new();
+        question.push(&mut body);
+        body.extend_from_slice(&form.to_le_bytes());
+        push_op(self.ifr, IfrOpCode::REF, false, &body);
+        self
+    }
+
+    /// Adds a checkbox, stored as a single byte.
+    pub fn checkbox(&mut self, question: Question, default: bool) -> &mut Self {
+        let mut body = Vec::new();
+        question.push(&mut body);
+        let flags = if default {
+            IfrCheckboxFlags::DEFAULT
+        } else {
+            IfrCheckboxFlags::empty()
+        };
+        body.push(flags.bits());
+        push_op(self.ifr, IfrOpCode::CHECKBOX, true, &body);
+        push_end(self.ifr);
+        self
+    }
+
+    /// Adds a numeric question with values of `size`, limited to `range` in
+    /// increments of `step`.
+    pub fn numeric(
+        &mut self,
+        question: Question,
+        size: NumericSize,
+        range: core::ops::RangeInclusive<u64>,
+        step: u64,
+        default: u64,
+    ) -> &mut Self {
+        let mut body = Vec::new();
+        question.push(&mut body);
+        body.push((size.flags() | IfrNumericFlags::DISPLAY_UINT_DEC).bits());
+        size.push(&mut body, *range.start());
+        size.push(&mut body, *range.end());
+        size.push(&mut body, step);
+        push_op(self.ifr, IfrOpCode::NUMERIC, true, &body);
+        self.default(size, default);
+        push_end(self.ifr);
+        self
+    }
+
+    /// Adds a question with a fixed set of `options`, which are pairs of
+    /// the option text and value.
+    pub fn one_of(
+        &mut self,
+        question: Question,
+        size: NumericSize,
+        options: &[(StringId, u64)],
+        default: u64,
+    ) -> &mut Self {
+        let mut body = Vec::new();
+        question.push(&mut body);
+        body.push((size.flags() | IfrNumericFlags::DISPLAY_UINT_DEC).bits());
+        let min = options.iter().map(|(_, v)| *v).min().unwrap_or(0);
+        let max = options.iter().map(|(_, v)| *v).max().unwrap_or(0);
+        size.push(&mut body, min);
+        size.push(&mut body, max);
+        size.push(&mut body, 0);
+        push_op(self.ifr, IfrOpCode::ONE_OF, true, &body);
+
+        for &(text, value) in options {
+            let flags = if value == default {
+                IfrOneOfOptionFlags::DEFAULT
+            } else {
+                IfrOneOfOptionFlags::empty()
+            };
+            let mut body = Vec::new();
+            body.extend_from_slice(&text.to_le_bytes());
+            body.push(flags.bits());
+            body.push(size.ifr_type().0);
+            size.push(&mut body, value);
+            push_op(self.ifr, IfrOpCode::ONE_OF_OPTION, false, &body);
+        }
+        push_end(self.ifr);
+        self
+    }
+
+    /// Adds a string question accepting between `min_len` and `max_len`
+    /// characters. The value is stored as a UCS-2 string of `max_len`
+    /// characters.
+    pub fn string(&mut self, question: Question, min_len: u8, max_len: u8) -> &mut Self {
+        let mut body = Vec::new();
+        question.push(&mut body);
+        body.push(min_len);
+        body.push(max_len);
+        body.push(0);
+        push_op(self.ifr, IfrOpCode::STRING, true, &body);
+        push_end(self.ifr);
+        self
+    }
+
+    /// Adds the statements of `f` in a `suppressif` scope: they are hidden
+    /// while `expression`, a sequence of IFR expression opcodes, evaluates
+    /// to true. See [`expression`](super::expression).
+    pub fn suppress_if(&mut self, expression: &[u8], f: impl FnOnce(&mut Self)) -> &mut Self {
+        self.condition(IfrOpCode::SUPPRESS_IF, expression, f)
+    }
+
+    /// Adds the statements of `f` in a `grayoutif` scope: they are shown
+    /// but can't be changed while `expression` evaluates to true.
+    pub fn gray_out_if(&mut self, expression: &[u8], f: impl FnOnce(&mut Self)) -> &mut Self {
+        self.condition(IfrOpCode::GRAY_OUT_IF, expression, f)
+    }
+
+    fn condition(
+        &mut self,
+        op_code: IfrOpCode,
+        expression: &[u8],
+        f: impl FnOnce(&mut Self),
+    ) -> &mut Self {
+        push_op(self.ifr, op_code, true, &[]);
+        self.ifr.extend_from_slice(expression);
+        f(self);
+        push_end(self.ifr);
+        self
+    }
+
+    /// Appends an `EFI_IFR_DEFAULT` opcode for the standard default store.
+    fn default(&mut self, size: NumericSize, value: u64) {
+        let mut body = Vec::new();
+        body.extend_from_slice(&IfrDefaultStore::STANDARD.to_le_bytes());
+        body.push(size.ifr_type().0);
+        size.push(&mut body, value);
+        push_op(self.ifr, IfrOpCode::DEFAULT, false, &body);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cstr8, guid};
+
+    const GUID: Guid = guid!("d6e5b6a9-0a0d-4a4b-9a7f-9d3b6f0c6e61");
+
+    /// Returns the opcodes and scope bits of an IFR buffer.
+    fn ops(ifr: &[u8]) -> Vec<(IfrOpCode, bool)> {
+        let mut ops = Vec::new();
+        let mut rest = ifr;
+        while !rest.is_empty() {
+            let len = usize::from(rest[1] & 0x7f);
+            ops.push((IfrOpCode(rest[0]), rest[1] & 0x80 != 0));
+            rest = &rest[len..];
+        }
+        ops
+    }
+
+    #[test]
+    fn test_empty_formset() {
+        let ifr = FormSetBuilder::new(GUID, 1, 2).build();
+        assert_eq!(ifr.len(), 2 + 16 + 2 + 2 + 1 + 16 + 2);
+        assert_eq!(&ifr[..2], &[0x0e, 0x80 | 39]);
+        assert_eq!(&ifr[2..18], &GUID.to_bytes());
+        assert_eq!(&ifr[18..23], &[1, 0, 2, 0, 1]);
+        assert_eq!(&ifr[39..], &[0x29, 2]);
+    }
+
+    #[test]
+    fn test_form() {
+        let ifr = FormSetBuilder::new(GUID, 1, 2)
+            .varstore(1, GUID, cstr8!("Data"), 4)
+            .form(1, 3, |form| {
+                form.subtitle(4)
+                    .checkbox(Question::new(1, 5, 6).varstore(1, 0), true)
+                    .numeric(
+                        Question::new(2, 7, 8).varstore(1, 2),
+                        NumericSize::U16,
+                        1..=100,
+                        1,
+                        10,
+                    )
+                    .one_of(
+                        Question::new(3, 9, 10).varstore(1, 1),
+                        NumericSize::U8,
+                        &[(11, 0), (12, 1)],
+                        1,
+                    );
+            })
+            .build();
+
+        assert_eq!(
+            ops(&ifr),
+            [
+                (IfrOpCode::FORM_SET, true),
+                (IfrOpCode::VARSTORE, false),
+                (IfrOpCode::FORM, true),
+                (IfrOpCode::SUBTITLE, false),
+                (IfrOpCode::CHECKBOX, true),
+                (IfrOpCode::END, false),
+                (IfrOpCode::NUMERIC, true),
+                (IfrOpCode::DEFAULT, false),
+                (IfrOpCode::END, false),
+                (IfrOpCode::ONE_OF, true),
+                (IfrOpCode::ONE_OF_OPTION, false),
+                (IfrOpCode::ONE_OF_OPTION, false),
+                (IfrOpCode::END, false),
+                (IfrOpCode::END, false),
+                (IfrOpCode::END, false),
+            ]
+        );
+
+        // Varstore: GUID, ID 1, size 4, name.
+        let varstore = &ifr[39..];
+        assert_eq!(&varstore[..2], &[0x24, 2 + 16 + 2 + 2 + 5]);
+        assert_eq!(&varstore[18..27], b"\x01\x00\x04\x00Data\0");
+
+        // Numeric: question header, flags, min, max, step.
+        let numeric = ifr.windows(2).position(|w| w == [0x07, 0x80 | 20]).unwrap();
+        assert_eq!(
+            &ifr[numeric + 2..numeric + 20],
+            &[7, 0, 8, 0, 2, 0, 1, 0, 2, 0, 0, 0x11, 1, 0, 100, 0, 1, 0]
+        );
+        // Default: standard store, 16-bit, 10.
+        assert_eq!(&ifr[numeric + 20..numeric + 27], &[0x5b, 7, 0, 0, 1, 10, 0]);
+    }
+
+    #[test]
+    fn test_conditions() {
+        let ifr = FormSetBuilder::new(GUID, 1, 2)
+            .form(1, 3, |form| {
+                form.suppress_if(&[0x46, 2], |form| {
+                    form.gray_out_if(&[0x47, 2], |form| {
+                        form.subtitle(4);
+                    });
+                });
+            })
+            .build();
+        assert_eq!(
+            ops(&ifr)[2..],
+            [
+                (IfrOpCode::SUPPRESS_IF, true),
+                (IfrOpCode::TRUE, false),
+                (IfrOpCode::GRAY_OUT_IF, true),
+                (IfrOpCode::FALSE, false),
+                (IfrOpCode::SUBTITLE, false),
+                (IfrOpCode::END, false),
+                (IfrOpCode::END, false),
+                (IfrOpCode::END, false),
+                (IfrOpCode::END, false),
+            ]
+        );
+    }
+}
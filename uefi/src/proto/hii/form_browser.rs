@@ -0,0 +1,70 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Form Browser 2 protocol.
+
+use core::ptr;
+use uefi_macros::unsafe_protocol;
+use uefi_raw::protocol::hii::form_browser::FormBrowser2Protocol;
+
+use super::{FormId, HiiHandle};
+use crate::{Guid, Result, StatusExt};
+
+pub use uefi_raw::protocol::hii::form_browser::{BrowserActionRequest, ScreenDescriptor};
+
+/// The Form Browser 2 Protocol.
+///
+/// # UEFI Spec Description
+///
+/// This protocol is the interface to call for drivers to leverage the EFI
+/// configuration driver interface.
+#[derive(Debug)]
+#[repr(transparent)]
+#[unsafe_protocol(FormBrowser2Protocol::GUID)]
+pub struct FormBrowser2(FormBrowser2Protocol);
+
+impl FormBrowser2 {
+    /// Displays forms of the package lists `handles` to the user, and
+    /// returns when the user leaves the browser.
+    ///
+    /// * `formset_guid` selects the formset to display. If it is `None`, the
+    ///   first formset of the package lists is displayed, or the formsets
+    ///   with the class GUID of system configuration if there are several.
+    /// * `form_id` selects the form of the formset to display. If it is
+    ///   `None`, the first form of the formset is displayed.
+    /// * `screen` limits the browser to a region of the screen. If it is
+    ///   `None`, the browser uses the whole screen.
+    ///
+    /// Returns the action requested by the user or by a callback of the
+    /// displayed forms, e.g. [`BrowserActionRequest::RESET`] if a changed
+    /// setting only takes effect after a reset. Acting on it is up to the
+    /// caller.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::NOT_FOUND`]: no matching formset was found.
+    /// * [`Status::INVALID_PARAMETER`]: `handles` is empty.
+    ///
+    /// [`Status::NOT_FOUND`]: crate::Status::NOT_FOUND
+    /// [`Status::INVALID_PARAMETER`]: crate::Status::INVALID_PARAMETER
+    pub fn send_form(
+        &self,
+        handles: &[HiiHandle],
+        formset_guid: Option<&Guid>,
+        form_id: Option<FormId>,
+        screen: Option<&ScreenDescriptor>,
+    ) -> Result<BrowserActionRequest> {
+        let mut action_request = BrowserActionRequest::NONE;
+        unsafe {
+            (self.0.send_form)(
+                &self.0,
+                handles.as_ptr().cast(),
+                handles.len(),
+                formset_guid.map_or(ptr::null(), ptr::from_ref),
+                form_id.unwrap_or(0),
+                screen.map_or(ptr::null(), ptr::from_ref),
+                &mut action_request,
+            )
+        }
+        .to_result_with_val(|| action_request)
+    }
+}
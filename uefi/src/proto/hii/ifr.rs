@@ -0,0 +1,676 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Walking the Internal Forms Representation (IFR).
+//!
+//! IFR is a sequence of variable-length opcodes, some of which open a scope
+//! that is closed by a matching [`IfrOpCode::END`]. [`IfrOps`] iterates over
+//! the opcodes of a buffer, such as the body of a form package exported from
+//! the HII database, checking the length of each opcode and tracking the
+//! scope depth. Each [`IfrOp`] can be read as the matching structure from
+//! [`uefi_raw`] with [`IfrOp::get`], and provides accessors for the
+//! variable-length parts that follow some of these structures.
+//!
+//! ```
+//! use uefi::proto::hii::ifr::{IfrCheckbox, IfrOpCode, IfrOps};
+//!
+//! # fn example(ifr: &[u8]) -> Result<(), uefi::proto::hii::ifr::IfrError> {
+//! for op in IfrOps::new(ifr) {
+//!     let op = op?;
+//!     if let Some(checkbox) = op.get::<IfrCheckbox>() {
+//!         let question_id = checkbox.question.question_id;
+//!         let offset = checkbox.question.var_store_info;
+//!         // ...
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{CStr8, Guid};
+use core::fmt::{self, Display, Formatter};
+use core::{mem, ptr};
+
+pub use uefi_raw::protocol::hii::ifr::{
+    IfrAction, IfrBannerAlignment, IfrCheckbox, IfrCheckboxFlags, IfrDate, IfrDefault,
+    IfrDefaultStore, IfrDeviceClass, IfrEnd, IfrEqIdId, IfrEqIdVal, IfrEqIdValList, IfrFind,
+    IfrForm, IfrFormSet, IfrGuid, IfrGuidBanner, IfrGuidClass, IfrGuidLabel, IfrGuidSubclass,
+    IfrGuidTimeout, IfrImage, IfrMinMaxStep8, IfrMinMaxStep16, IfrMinMaxStep32, IfrMinMaxStep64,
+    IfrNumeric, IfrNumericData, IfrNumericFlags, IfrOneOf, IfrOneOfOption, IfrOneOfOptionFlags,
+    IfrOpCode, IfrOpHeader, IfrOrderedList, IfrPassword, IfrQuestionFlags, IfrQuestionHeader,
+    IfrQuestionRef1, IfrRef, IfrRule, IfrRuleRef, IfrSpan, IfrStatementHeader, IfrString,
+    IfrStringRef1, IfrSubclass, IfrSubtitle, IfrText, IfrTianoOpCode, IfrTime, IfrToString,
+    IfrType, IfrUint8, IfrUint16, IfrUint32, IfrUint64, IfrVarStore, IfrVarStoreEfi,
+    IfrVarStoreNameValue,
+};
+
+/// An IFR opcode structure that can be read with [`IfrOp::get`].
+///
+/// # Safety
+///
+/// The type must be `repr(C, packed)`, start with an [`IfrOpHeader`], and
+/// be valid for any bit pattern.
+pub unsafe trait IfrOpStruct: Copy {
+    /// The opcode of the structure.
+    const OP_CODE: IfrOpCode;
+}
+
+macro_rules! impl_ifr_op_struct {
+    ($($ty:ty => $op_code:ident,)*) => {
+        $(
+            unsafe impl IfrOpStruct for $ty {
+                const OP_CODE: IfrOpCode = IfrOpCode::$op_code;
+            }
+        )*
+    };
+}
+
+impl_ifr_op_struct! {
+    IfrFormSet => FORM_SET,
+    IfrForm => FORM,
+    IfrSubtitle => SUBTITLE,
+    IfrText => TEXT,
+    IfrImage => IMAGE,
+    IfrRef => REF,
+    IfrAction => ACTION,
+    IfrCheckbox => CHECKBOX,
+    IfrNumeric => NUMERIC,
+    IfrOneOf => ONE_OF,
+    IfrOneOfOption => ONE_OF_OPTION,
+    IfrString => STRING,
+    IfrPassword => PASSWORD,
+    IfrOrderedList => ORDERED_LIST,
+    IfrDate => DATE,
+    IfrTime => TIME,
+    IfrVarStore => VARSTORE,
+    IfrVarStoreEfi => VARSTORE_EFI,
+    IfrVarStoreNameValue => VARSTORE_NAME_VALUE,
+    IfrDefaultStore => DEFAULTSTORE,
+    IfrDefault => DEFAULT,
+    IfrGuid => GUID,
+    IfrEnd => END,
+    IfrEqIdVal => EQ_ID_VAL,
+    IfrEqIdId => EQ_ID_ID,
+    IfrEqIdValList => EQ_ID_VAL_LIST,
+    IfrQuestionRef1 => QUESTION_REF1,
+    IfrRule => RULE,
+    IfrRuleRef => RULE_REF,
+    IfrStringRef1 => STRING_REF1,
+    IfrUint8 => UINT8,
+    IfrUint16 => UINT16,
+    IfrUint32 => UINT32,
+    IfrUint64 => UINT64,
+    IfrToString => TO_STRING,
+    IfrFind => FIND,
+    IfrSpan => SPAN,
+}
+
+/// Error returned by [`IfrOps`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum IfrError {
+    /// An opcode extends past the end of the buffer, or is shorter than its
+    /// header.
+    Truncated {
+        /// Byte offset of the opcode.
+        offset: usize,
+    },
+    /// An end opcode without matching scope, or a scope that isn't closed
+    /// at the end of the buffer.
+    UnbalancedScope {
+        /// Byte offset of the end opcode, or the length of the buffer.
+        offset: usize,
+    },
+}
+
+impl Display for IfrError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Truncated { offset } => write!(f, "truncated IFR opcode at offset {offset}"),
+            Self::UnbalancedScope { offset } => {
+                write!(f, "unbalanced IFR scope at offset {offset}")
+            }
+        }
+    }
+}
+
+impl core::error::Error for IfrError {}
+
+/// Minimum, maximum, and step of a numeric or one-of question.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct IfrRange {
+    /// Minimum value.
+    pub min: u64,
+    /// Maximum value.
+    pub max: u64,
+    /// Step between values, or 0 for any step.
+    pub step: u64,
+}
+
+/// An EDK2 extension opcode: a GUID opcode with [`IfrGuid::TIANO_GUID`],
+/// read with [`IfrOp::tiano_extension`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum IfrTianoExtension {
+    /// A label, marking a position where content can be inserted at
+    /// runtime.
+    Label(IfrGuidLabel),
+    /// A banner shown on the front page.
+    Banner(IfrGuidBanner),
+    /// The timeout of the front page.
+    Timeout(IfrGuidTimeout),
+    /// The device classes of the formset.
+    Class(IfrGuidClass),
+    /// The subclass of the formset.
+    Subclass(IfrGuidSubclass),
+}
+
+/// Reads a little-endian integer of up to 8 bytes.
+fn uint(bytes: &[u8]) -> u64 {
+    let mut buf = [0; 8];
+    buf[..bytes.len()].copy_from_slice(bytes);
+    u64::from_le_bytes(buf)
+}
+
+/// A single length-checked opcode in an IFR buffer.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct IfrOp<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    depth: usize,
+}
+
+impl<'a> IfrOp<'a> {
+    /// Returns the opcode.
+    #[must_use]
+    pub const fn op_code(&self) -> IfrOpCode {
+        IfrOpCode(self.bytes[0])
+    }
+
+    /// Returns whether the opcode opens a scope.
+    #[must_use]
+    pub const fn scope(&self) -> bool {
+        self.bytes[1] & 0x80 != 0
+    }
+
+    /// Returns all bytes of the opcode, including the header.
+    #[must_use]
+    pub const fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Returns the byte offset of the opcode in the buffer.
+    #[must_use]
+    pub const fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the number of scopes enclosing the opcode. An end opcode has
+    /// the same depth as the opcode that opened its scope.
+    #[must_use]
+    pub const fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Reads the opcode as `T`. Returns `None` if the opcode isn't
+    /// `T::OP_CODE`, or is too short for `T`.
+    #[must_use]
+    pub fn get<T: IfrOpStruct>(&self) -> Option<T> {
+        if self.op_code() != T::OP_CODE || self.bytes.len() < mem::size_of::<T>() {
+            return None;
+        }
+        // SAFETY: the length was checked, and `T` is valid for any bit
+        // pattern per the requirements of `IfrOpStruct`.
+        Some(unsafe { ptr::read_unaligned(self.bytes.as_ptr().cast::<T>()) })
+    }
+
+    /// Returns the bytes following `T` in the opcode, e.g. the name of an
+    /// [`IfrVarStore`]. Returns `None` under the same conditions as
+    /// [`get`].
+    ///
+    /// [`get`]: Self::get
+    #[must_use]
+    pub fn trailing<T: IfrOpStruct>(&self) -> Option<&'a [u8]> {
+        self.get::<T>()?;
+        Some(&self.bytes[mem::size_of::<T>()..])
+    }
+
+    /// Returns the question header of a question opcode, such as a
+    /// checkbox or a numeric.
+    #[must_use]
+    pub fn question(&self) -> Option<IfrQuestionHeader> {
+        match self.op_code() {
+            IfrOpCode::CHECKBOX
+            | IfrOpCode::NUMERIC
+            | IfrOpCode::ONE_OF
+            | IfrOpCode::STRING
+            | IfrOpCode::PASSWORD
+            | IfrOpCode::ORDERED_LIST
+            | IfrOpCode::DATE
+            | IfrOpCode::TIME
+            | IfrOpCode::ACTION
+            | IfrOpCode::REF => {}
+            _ => return None,
+        }
+        let header = self.bytes.get(2..2 + mem::size_of::<IfrQuestionHeader>())?;
+        // SAFETY: the length was checked, and the header is valid for any
+        // bit pattern.
+        Some(unsafe { ptr::read_unaligned(header.as_ptr().cast()) })
+    }
+
+    /// Returns the flags and the minimum, maximum, and step values of a
+    /// numeric or one-of opcode.
+    #[must_use]
+    pub fn numeric_data(&self) -> Option<(IfrNumericFlags, IfrNumericData)> {
+        let (flags, trailing) = match self.op_code() {
+            IfrOpCode::NUMERIC => (
+                self.get::<IfrNumeric>()?.flags,
+                self.trailing::<IfrNumeric>()?,
+            ),
+            IfrOpCode::ONE_OF => (self.get::<IfrOneOf>()?.flags, self.trailing::<IfrOneOf>()?),
+            _ => return None,
+        };
+        let size = flags.value_size();
+        let values = trailing.get(..3 * size)?;
+        let data = IfrNumericData::new(
+            flags,
+            uint(&values[..size]),
+            uint(&values[size..2 * size]),
+            uint(&values[2 * size..]),
+        );
+        Some((flags, data))
+    }
+
+    /// Returns the range of a numeric or one-of opcode.
+    #[must_use]
+    pub fn range(&self) -> Option<IfrRange> {
+        let (flags, data) = self.numeric_data()?;
+        // SAFETY: `IfrNumericData::new` initializes all bytes.
+        let values = unsafe { data.min_max_step(flags) };
+        Some(IfrRange {
+            min: values.min_value,
+            max: values.max_value,
+            step: values.step,
+        })
+    }
+
+    /// Returns the type and bytes of the value of a one-of option or
+    /// default opcode. For integer and boolean types, the bytes are exactly
+    /// the size of the type; otherwise they are the rest of the opcode.
+    #[must_use]
+    pub fn value(&self) -> Option<(IfrType, &'a [u8])> {
+        let (ty, trailing) = match self.op_code() {
+            IfrOpCode::ONE_OF_OPTION => (
+                self.get::<IfrOneOfOption>()?.ty,
+                self.trailing::<IfrOneOfOption>()?,
+            ),
+            IfrOpCode::DEFAULT => (self.get::<IfrDefault>()?.ty, self.trailing::<IfrDefault>()?),
+            _ => return None,
+        };
+        let size = match ty {
+            IfrType::NUM_SIZE_8 | IfrType::BOOLEAN => 1,
+            IfrType::NUM_SIZE_16 => 2,
+            IfrType::NUM_SIZE_32 => 4,
+            IfrType::NUM_SIZE_64 => 8,
+            _ => trailing.len(),
+        };
+        Some((ty, trailing.get(..size)?))
+    }
+
+    /// Returns the value of a one-of option or default opcode as an
+    /// integer, if it has an integer or boolean type.
+    #[must_use]
+    pub fn value_uint(&self) -> Option<u64> {
+        match self.value()? {
+            (
+                IfrType::NUM_SIZE_8
+                | IfrType::NUM_SIZE_16
+                | IfrType::NUM_SIZE_32
+                | IfrType::NUM_SIZE_64
+                | IfrType::BOOLEAN,
+                bytes,
+            ) => Some(uint(bytes)),
+            _ => None,
+        }
+    }
+
+    /// Returns the EDK2 extension of a GUID opcode with
+    /// [`IfrGuid::TIANO_GUID`]. Returns `None` for other opcodes, unknown
+    /// extensions, and extensions that are too short.
+    #[must_use]
+    pub fn tiano_extension(&self) -> Option<IfrTianoExtension> {
+        if { self.get::<IfrGuid>()?.guid } != IfrGuid::TIANO_GUID {
+            return None;
+        }
+        let extend_op_code = IfrTianoOpCode(*self.trailing::<IfrGuid>()?.first()?);
+        // SAFETY: the extension structures are packed, and valid for any
+        // bit pattern.
+        unsafe {
+            Some(match extend_op_code {
+                IfrTianoOpCode::LABEL => IfrTianoExtension::Label(self.read()?),
+                IfrTianoOpCode::BANNER => IfrTianoExtension::Banner(self.read()?),
+                IfrTianoOpCode::TIMEOUT => IfrTianoExtension::Timeout(self.read()?),
+                IfrTianoOpCode::CLASS => IfrTianoExtension::Class(self.read()?),
+                IfrTianoOpCode::SUBCLASS => IfrTianoExtension::Subclass(self.read()?),
+                _ => return None,
+            })
+        }
+    }
+
+    /// Reads the opcode as `T`, without checking the opcode. Returns `None`
+    /// if the opcode is too short for `T`.
+    ///
+    /// # Safety
+    ///
+    /// `T` must be valid for any bit pattern.
+    unsafe fn read<T: Copy>(&self) -> Option<T> {
+        let bytes = self.bytes.get(..mem::size_of::<T>())?;
+        Some(unsafe { ptr::read_unaligned(bytes.as_ptr().cast::<T>()) })
+    }
+
+    /// Returns the class GUIDs of a formset opcode.
+    #[must_use]
+    pub fn class_guids(&self) -> Option<impl Iterator<Item = Guid> + 'a> {
+        let count = usize::from(self.get::<IfrFormSet>()?.flags & 0x3);
+        let guids = self.trailing::<IfrFormSet>()?.get(..16 * count)?;
+        Some(
+            guids
+                .chunks_exact(16)
+                .map(|b| Guid::from_bytes(b.try_into().unwrap())),
+        )
+    }
+
+    /// Returns the name of a buffer or EFI varstore opcode.
+    #[must_use]
+    pub fn varstore_name(&self) -> Option<&'a CStr8> {
+        let trailing = match self.op_code() {
+            IfrOpCode::VARSTORE => self.trailing::<IfrVarStore>()?,
+            IfrOpCode::VARSTORE_EFI => self.trailing::<IfrVarStoreEfi>()?,
+            _ => return None,
+        };
+        let len = trailing.iter().position(|&b| b == 0)?;
+        CStr8::from_bytes_with_nul(&trailing[..=len]).ok()
+    }
+}
+
+/// Iterator over the opcodes of an IFR buffer.
+///
+/// The iterator stops after returning the first error.
+#[derive(Clone, Debug)]
+pub struct IfrOps<'a> {
+    ifr: &'a [u8],
+    offset: usize,
+    depth: usize,
+    done: bool,
+}
+
+impl<'a> IfrOps<'a> {
+    /// Creates an iterator over the opcodes in `ifr`.
+    #[must_use]
+    pub const fn new(ifr: &'a [u8]) -> Self {
+        Self {
+            ifr,
+            offset: 0,
+            depth: 0,
+            done: false,
+        }
+    }
+
+    /// Returns the number of currently open scopes.
+    #[must_use]
+    pub const fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Skips the remaining opcodes of the innermost open scope, including
+    /// its end opcode. Does nothing if no scope is open.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error encountered while skipping.
+    pub fn skip_scope(&mut self) -> Result<(), IfrError> {
+        let Some(target) = self.depth.checked_sub(1) else {
+            return Ok(());
+        };
+        for op in self.by_ref() {
+            let op = op?;
+            if op.op_code() == IfrOpCode::END && op.depth() == target {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Iterator for IfrOps<'a> {
+    type Item = Result<IfrOp<'a>, IfrError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let offset = self.offset;
+        if offset >= self.ifr.len() {
+            self.done = true;
+            return (self.depth != 0).then_some(Err(IfrError::UnbalancedScope { offset }));
+        }
+
+        let result = (|| {
+            let truncated = IfrError::Truncated { offset };
+            let len = usize::from(*self.ifr.get(offset + 1).ok_or(truncated)? & 0x7f);
+            let bytes = self
+                .ifr
+                .get(offset..offset + len)
+                .filter(|_| len >= 2)
+                .ok_or(truncated)?;
+            let mut op = IfrOp {
+                bytes,
+                offset,
+                depth: self.depth,
+            };
+            if op.op_code() == IfrOpCode::END {
+                self.depth = self
+                    .depth
+                    .checked_sub(1)
+                    .ok_or(IfrError::UnbalancedScope { offset })?;
+                op.depth = self.depth;
+            } else if op.scope() {
+                self.depth += 1;
+            }
+            self.offset += len;
+            Ok(op)
+        })();
+        self.done = result.is_err();
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walk() {
+        #[rustfmt::skip]
+        let ifr = [
+            // FORM, scope
+            0x01, 0x86, 1, 0, 2, 0,
+            // NUMERIC, scope: question 3 at offset 4, 1 byte, 0..=10 step 1
+            0x07, 0x91, 5, 0, 6, 0, 3, 0, 1, 0, 4, 0, 0, 0, 0, 10, 1,
+            // DEFAULT: standard, u8 5
+            0x5b, 0x06, 0, 0, 0x00, 5,
+            // END
+            0x29, 0x02,
+            // END
+            0x29, 0x02,
+        ];
+        let mut ops = IfrOps::new(&ifr).map(Result::unwrap);
+
+        let form = ops.next().unwrap();
+        assert_eq!(form.depth(), 0);
+        assert_eq!({ form.get::<IfrForm>().unwrap().form_id }, 1);
+        assert!(form.get::<IfrNumeric>().is_none());
+
+        let numeric = ops.next().unwrap();
+        assert_eq!(numeric.depth(), 1);
+        let question = numeric.question().unwrap();
+        assert_eq!({ question.question_id }, 3);
+        assert_eq!({ question.var_store_info }, 4);
+        assert_eq!(
+            numeric.range(),
+            Some(IfrRange {
+                min: 0,
+                max: 10,
+                step: 1
+            })
+        );
+
+        let default = ops.next().unwrap();
+        assert_eq!(default.depth(), 2);
+        assert_eq!(default.value_uint(), Some(5));
+
+        assert_eq!(ops.next().unwrap().depth(), 1);
+        assert_eq!(ops.next().unwrap().depth(), 0);
+        assert!(ops.next().is_none());
+    }
+
+    #[test]
+    fn numeric_data() {
+        #[rustfmt::skip]
+        let ifr = [
+            // ONE_OF: question 1 at offset 0, u16 options, 0..=0x1234 step 0
+            0x05, 0x14, 2, 0, 3, 0, 1, 0, 1, 0, 0, 0, 0, 0x01,
+            0, 0, 0x34, 0x12, 0, 0,
+            // NUMERIC: question 2 at offset 2, u32 hex, 1..=0x1_0000 step 16
+            0x07, 0x1a, 4, 0, 5, 0, 2, 0, 1, 0, 2, 0, 0, 0x22,
+            1, 0, 0, 0, 0, 0, 1, 0, 16, 0, 0, 0,
+            // NUMERIC: question 3 at offset 6, u64, 0..=u64::MAX step 1
+            0x07, 0x26, 6, 0, 7, 0, 3, 0, 1, 0, 6, 0, 0, 0x03,
+            0, 0, 0, 0, 0, 0, 0, 0,
+            0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+            1, 0, 0, 0, 0, 0, 0, 0,
+            // NUMERIC, truncated u16 data
+            0x07, 0x13, 8, 0, 9, 0, 4, 0, 1, 0, 8, 0, 0, 0x01,
+            0, 0, 1, 0, 2,
+        ];
+        let ops: alloc::vec::Vec<_> = IfrOps::new(&ifr).map(Result::unwrap).collect();
+
+        let (flags, data) = ops[0].numeric_data().unwrap();
+        assert_eq!(flags, IfrNumericFlags::SIZE_2);
+        assert_eq!(flags.value_size(), 2);
+        let values = unsafe { data.u16 };
+        assert_eq!({ values.max_value }, 0x1234);
+        assert_eq!(
+            ops[0].range(),
+            Some(IfrRange {
+                min: 0,
+                max: 0x1234,
+                step: 0
+            })
+        );
+
+        let (flags, data) = ops[1].numeric_data().unwrap();
+        assert_eq!(
+            flags,
+            IfrNumericFlags::SIZE_4 | IfrNumericFlags::DISPLAY_UINT_HEX
+        );
+        let values = unsafe { data.u32 };
+        assert_eq!({ values.min_value }, 1);
+        assert_eq!({ values.max_value }, 0x1_0000);
+        assert_eq!({ values.step }, 16);
+
+        let (flags, data) = ops[2].numeric_data().unwrap();
+        assert_eq!(flags.value_size(), 8);
+        let values = unsafe { data.min_max_step(flags) };
+        assert_eq!(values, unsafe { data.u64 });
+        assert_eq!({ values.max_value }, u64::MAX);
+
+        assert_eq!(ops[3].numeric_data().map(|(flags, _)| flags), None);
+        assert_eq!(ops[3].range(), None);
+
+        // The union has the size of its largest variant.
+        assert_eq!(mem::size_of::<IfrNumericData>(), 24);
+        let data = IfrNumericData::new(IfrNumericFlags::SIZE_1, 0x1ff, 2, 3);
+        assert_eq!({ unsafe { data.u8 }.min_value }, 0xff);
+        assert_eq!({ unsafe { data.u64 }.step }, 0);
+    }
+
+    #[test]
+    fn skip_scope() {
+        let ifr = [
+            0x01, 0x86, 1, 0, 2, 0, 0x02, 0x07, 0, 0, 0, 0, 0, 0x29, 0x02, 0x47, 0x02,
+        ];
+        let mut ops = IfrOps::new(&ifr);
+        ops.next().unwrap().unwrap();
+        ops.skip_scope().unwrap();
+        let op = ops.next().unwrap().unwrap();
+        assert_eq!(op.op_code(), IfrOpCode::FALSE);
+        assert!(ops.next().is_none());
+    }
+
+    #[test]
+    fn errors() {
+        let mut ops = IfrOps::new(&[0x29, 0x02]);
+        assert_eq!(
+            ops.next(),
+            Some(Err(IfrError::UnbalancedScope { offset: 0 }))
+        );
+        assert!(ops.next().is_none());
+
+        let mut ops = IfrOps::new(&[0x01, 0x86, 1, 0, 2, 0]);
+        ops.next().unwrap().unwrap();
+        assert_eq!(
+            ops.next(),
+            Some(Err(IfrError::UnbalancedScope { offset: 6 }))
+        );
+
+        let mut ops = IfrOps::new(&[0x47, 0x05, 0]);
+        assert_eq!(ops.next(), Some(Err(IfrError::Truncated { offset: 0 })));
+
+        let mut ops = IfrOps::new(&[0x47, 0x01]);
+        assert_eq!(ops.next(), Some(Err(IfrError::Truncated { offset: 0 })));
+    }
+
+    #[test]
+    fn tiano_extension() {
+        let op = |extension: &[u8]| {
+            let len = 18 + extension.len();
+            let mut ifr = [0; 32];
+            ifr[..2].copy_from_slice(&[0x5f, len as u8]);
+            ifr[2..18].copy_from_slice(&IfrGuid::TIANO_GUID.to_bytes());
+            ifr[18..len].copy_from_slice(extension);
+            (ifr, len)
+        };
+        let extension = |(ifr, len): ([u8; 32], usize)| {
+            IfrOps::new(&ifr[..len])
+                .next()
+                .unwrap()
+                .unwrap()
+                .tiano_extension()
+        };
+
+        let Some(IfrTianoExtension::Label(label)) = extension(op(&[0, 0x34, 0x12])) else {
+            panic!("not a label");
+        };
+        assert_eq!({ label.number }, 0x1234);
+
+        let Some(IfrTianoExtension::Banner(banner)) = extension(op(&[1, 5, 0, 2, 0, 1])) else {
+            panic!("not a banner");
+        };
+        assert_eq!({ banner.title }, 5);
+        assert_eq!({ banner.line_number }, 2);
+        assert_eq!(banner.alignment, IfrBannerAlignment::CENTER);
+
+        let Some(IfrTianoExtension::Class(class)) = extension(op(&[3, 0x14, 0])) else {
+            panic!("not a class");
+        };
+        assert_eq!(
+            { class.class },
+            IfrDeviceClass::NETWORK | IfrDeviceClass::ON_BOARD
+        );
+
+        // Too short, unknown extension, and other GUID.
+        assert_eq!(extension(op(&[2, 10])), None);
+        assert_eq!(extension(op(&[0x10, 0, 0])), None);
+        let mut other = op(&[0, 0, 0]);
+        other.0[2] ^= 1;
+        assert_eq!(extension(other), None);
+    }
+}
@@ -0,0 +1,209 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! HII Image protocol.
+
+use core::{ptr, slice};
+use uefi_macros::unsafe_protocol;
+use uefi_raw::protocol::hii::image::{HiiImageProtocol, ImageInput, ImageOutput, ImageOutputDest};
+
+use super::{BltImage, HiiHandle, ImageId, free, take_image};
+use crate::proto::console::gop::BltPixel;
+use crate::{Result, Status, StatusExt};
+
+pub use uefi_raw::protocol::hii::image::{HiiDrawFlags, ImageInputFlags};
+
+/// An image stored in the HII database, returned by [`HiiImage::get_image`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StoredImage {
+    /// The pixels of the image.
+    pub image: BltImage,
+    /// Whether the image has transparent pixels.
+    pub flags: ImageInputFlags,
+}
+
+/// The HII Image Protocol.
+///
+/// # UEFI Spec Description
+///
+/// Services to access to images in the images database.
+///
+/// Images are referenced by an [`ImageId`] in their package list, e.g. by
+/// the [`IfrImage`] opcode of a form. [`draw_image_id`] renders such an
+/// image into a [`BltImage`] that can be drawn with the Graphics Output
+/// Protocol.
+///
+/// [`IfrImage`]: super::ifr::IfrImage
+/// [`draw_image_id`]: Self::draw_image_id
+#[derive(Debug)]
+#[repr(transparent)]
+#[unsafe_protocol(HiiImageProtocol::GUID)]
+pub struct HiiImage(HiiImageProtocol);
+
+impl HiiImage {
+    /// Adds `image` to the images of `package_list`, and returns its ID.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::NOT_FOUND`]: the package list is not in the database.
+    /// * [`Status::INVALID_PARAMETER`]: the image is larger than 65535
+    ///   pixels in either direction.
+    /// * [`Status::OUT_OF_RESOURCES`]: the image could not be stored.
+    pub fn new_image(
+        &self,
+        package_list: HiiHandle,
+        image: &BltImage,
+        flags: ImageInputFlags,
+    ) -> Result<ImageId> {
+        let input = image_input(image, flags)?;
+        let mut id = 0;
+        unsafe { (self.0.new_image)(&self.0, package_list.as_ptr(), &mut id, &input) }
+            .to_result_with_val(|| id)
+    }
+
+    /// Returns the image with ID `id` of `package_list`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::NOT_FOUND`]: the image or the package list is not in the
+    ///   database.
+    /// * [`Status::OUT_OF_RESOURCES`]: the image could not be allocated.
+    pub fn get_image(&self, package_list: HiiHandle, id: ImageId) -> Result<StoredImage> {
+        let mut input = ImageInput {
+            flags: ImageInputFlags::empty(),
+            width: 0,
+            height: 0,
+            bitmap: ptr::null(),
+        };
+        unsafe { (self.0.get_image)(&self.0, package_list.as_ptr(), id, &mut input) }
+            .to_result()?;
+
+        let width = usize::from(input.width);
+        let height = usize::from(input.height);
+        // SAFETY: the firmware allocated the bitmap with the size of the
+        // image.
+        let image = unsafe {
+            let pixels = slice::from_raw_parts(input.bitmap.cast::<BltPixel>(), width * height);
+            let image = BltImage::from_rows(pixels, width, width, height);
+            free(input.bitmap.cast_mut());
+            image
+        };
+        Ok(StoredImage {
+            image,
+            flags: input.flags,
+        })
+    }
+
+    /// Replaces the image with ID `id` of `package_list` with `image`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::NOT_FOUND`]: the image or the package list is not in the
+    ///   database.
+    /// * [`Status::INVALID_PARAMETER`]: the image is larger than 65535
+    ///   pixels in either direction.
+    pub fn set_image(
+        &self,
+        package_list: HiiHandle,
+        id: ImageId,
+        image: &BltImage,
+        flags: ImageInputFlags,
+    ) -> Result {
+        let input = image_input(image, flags)?;
+        unsafe { (self.0.set_image)(&self.0, package_list.as_ptr(), id, &input) }.to_result()
+    }
+
+    /// Renders the image with ID `id` of `package_list` into a new image of
+    /// the same size. Transparent pixels are rendered black, unless `flags`
+    /// contains [`HiiDrawFlags::FORCE_OPAQUE`].
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::NOT_FOUND`]: the image or the package list is not in the
+    ///   database.
+    pub fn draw_image_id(
+        &self,
+        package_list: HiiHandle,
+        id: ImageId,
+        flags: HiiDrawFlags,
+    ) -> Result<BltImage> {
+        let mut blt = ptr::null_mut();
+        unsafe {
+            (self.0.draw_image_id)(
+                &self.0,
+                draw_flags(flags),
+                package_list.as_ptr(),
+                id,
+                &mut blt,
+                0,
+                0,
+            )
+            .to_result()?;
+            Ok(take_image(blt, None))
+        }
+    }
+
+    /// Renders the image with ID `id` of `package_list` into `image`, with
+    /// its top left corner at `x`, `y`. Transparent pixels keep the
+    /// contents of `image`, unless `flags` contains
+    /// [`HiiDrawFlags::FORCE_OPAQUE`]. The image is clipped to `image`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::NOT_FOUND`]: the image or the package list is not in the
+    ///   database.
+    /// * [`Status::INVALID_PARAMETER`]: `image` is larger than 65535 pixels
+    ///   in either direction.
+    pub fn draw_image_id_into(
+        &self,
+        package_list: HiiHandle,
+        id: ImageId,
+        flags: HiiDrawFlags,
+        image: &mut BltImage,
+        (x, y): (usize, usize),
+    ) -> Result {
+        let (Ok(width), Ok(height)) = (u16::try_from(image.width()), u16::try_from(image.height()))
+        else {
+            return Err(Status::INVALID_PARAMETER.into());
+        };
+        let mut output = ImageOutput {
+            width,
+            height,
+            image: ImageOutputDest {
+                bitmap: image.pixels_mut().as_mut_ptr().cast(),
+            },
+        };
+        let mut blt = ptr::from_mut(&mut output);
+        unsafe {
+            (self.0.draw_image_id)(
+                &self.0,
+                draw_flags(flags) | HiiDrawFlags::CLIP,
+                package_list.as_ptr(),
+                id,
+                &mut blt,
+                x,
+                y,
+            )
+        }
+        .to_result()
+    }
+}
+
+/// Returns `flags` without [`HiiDrawFlags::DIRECT_TO_SCREEN`], since the
+/// output is always an image.
+fn draw_flags(flags: HiiDrawFlags) -> HiiDrawFlags {
+    flags - HiiDrawFlags::DIRECT_TO_SCREEN
+}
+
+/// Describes `image` as an `EFI_IMAGE_INPUT` borrowing its pixels.
+fn image_input(image: &BltImage, flags: ImageInputFlags) -> Result<ImageInput> {
+    let (Ok(width), Ok(height)) = (u16::try_from(image.width()), u16::try_from(image.height()))
+    else {
+        return Err(Status::INVALID_PARAMETER.into());
+    };
+    Ok(ImageInput {
+        flags,
+        width,
+        height,
+        bitmap: image.pixels().as_ptr().cast(),
+    })
+}
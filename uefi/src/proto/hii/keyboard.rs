@@ -0,0 +1,493 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! HII keyboard layouts.
+//!
+//! A keyboard layout maps physical keys ([`Key`]) to the characters they
+//! produce. Layouts are registered in the HII database as keyboard layout
+//! packages; the current layout can be queried and changed with
+//! [`HiiDatabase::keyboard_layout`] and
+//! [`HiiDatabase::set_keyboard_layout`]. The [`EventGroup::SET_KEYBOARD_LAYOUT`]
+//! event group is signaled when the current layout changes.
+//!
+//! New layouts are created with [`KeyboardLayout::new`] and registered with
+//! [`PackageListBuilder::keyboard_layouts`].
+//!
+//! [`EventGroup::SET_KEYBOARD_LAYOUT`]: crate::boot::EventGroup::SET_KEYBOARD_LAYOUT
+//! [`HiiDatabase::keyboard_layout`]: super::database::HiiDatabase::keyboard_layout
+//! [`HiiDatabase::set_keyboard_layout`]: super::database::HiiDatabase::set_keyboard_layout
+//! [`PackageListBuilder::keyboard_layouts`]: super::package::PackageListBuilder::keyboard_layouts
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+use core::mem;
+use uefi_raw::protocol::hii::database::HiiKeyboardLayout;
+
+use crate::{CStr16, CString16, Guid};
+
+pub use uefi_raw::protocol::hii::{Key, KeyAffectedAttributes, KeyDescriptor, Modifier};
+
+/// Error returned when parsing a [`KeyboardLayout`] from bytes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum KeyboardLayoutError {
+    /// The data is shorter than indicated by the layout's length fields.
+    InvalidLength,
+
+    /// A description string is not terminated or not a valid UCS-2 string.
+    InvalidDescription,
+}
+
+impl Display for KeyboardLayoutError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidLength => write!(f, "keyboard layout is truncated"),
+            Self::InvalidDescription => write!(f, "keyboard layout description is malformed"),
+        }
+    }
+}
+
+impl core::error::Error for KeyboardLayoutError {}
+
+/// State of the modifier keys relevant for translating a key into a
+/// character.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct KeyState {
+    /// A shift key is pressed.
+    pub shift: bool,
+
+    /// The AltGr key is pressed.
+    pub alt_gr: bool,
+
+    /// Caps lock is active.
+    pub caps_lock: bool,
+
+    /// Num lock is active.
+    pub num_lock: bool,
+}
+
+/// A keyboard layout, as stored in a keyboard layout package
+/// (`EFI_HII_KEYBOARD_LAYOUT`).
+#[derive(Clone, Debug)]
+pub struct KeyboardLayout {
+    guid: Guid,
+    descriptors: Vec<KeyDescriptor>,
+    /// Pairs of RFC 4646 language code and description.
+    descriptions: Vec<(String, CString16)>,
+}
+
+impl KeyboardLayout {
+    /// Creates a layout identified by `guid` with the key `descriptors`, and
+    /// no descriptions.
+    #[must_use]
+    pub fn new(guid: Guid, descriptors: impl Into<Vec<KeyDescriptor>>) -> Self {
+        Self {
+            guid,
+            descriptors: descriptors.into(),
+            descriptions: Vec::new(),
+        }
+    }
+
+    /// Adds the `description` of the layout in `language`, an RFC 4646
+    /// language code such as `de-DE`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `language` is empty or contains non-ASCII characters or
+    /// spaces.
+    #[must_use]
+    pub fn with_description(mut self, language: &str, description: &CStr16) -> Self {
+        assert!(
+            !language.is_empty() && language.bytes().all(|b| b.is_ascii_graphic()),
+            "invalid language code"
+        );
+        self.descriptions
+            .push((language.into(), description.into()));
+        self
+    }
+
+    /// Parses the layouts of a keyboard layout package, without the package
+    /// header, e.g. [`Package::KeyboardLayout`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a layout is malformed.
+    ///
+    /// [`Package::KeyboardLayout`]: super::package::Package::KeyboardLayout
+    pub fn parse_package(data: &[u8]) -> Result<Vec<Self>, KeyboardLayoutError> {
+        let count = data
+            .get(..2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+            .ok_or(KeyboardLayoutError::InvalidLength)?;
+        let mut offset = 2;
+        let mut layouts = Vec::with_capacity(usize::from(count));
+        for _ in 0..count {
+            let bytes = data
+                .get(offset..)
+                .ok_or(KeyboardLayoutError::InvalidLength)?;
+            let len = bytes
+                .get(..2)
+                .map(|b| usize::from(u16::from_le_bytes([b[0], b[1]])))
+                .ok_or(KeyboardLayoutError::InvalidLength)?;
+            layouts.push(Self::try_from(bytes)?);
+            offset += len;
+        }
+        Ok(layouts)
+    }
+
+    /// Encodes the layout as an `EFI_HII_KEYBOARD_LAYOUT`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the layout has more than 255 descriptors, or is larger than
+    /// 64 KiB.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let header_len = mem::offset_of!(HiiKeyboardLayout, descriptors);
+        let descriptor_count = u8::try_from(self.descriptors.len()).expect("too many descriptors");
+        let description_offset = if self.descriptions.is_empty() {
+            0
+        } else {
+            header_len + self.descriptors.len() * mem::size_of::<KeyDescriptor>()
+        };
+
+        let mut bytes = Vec::new();
+        // The length is filled in at the end.
+        bytes.extend_from_slice(&[0; 2]);
+        bytes.extend_from_slice(&self.guid.to_bytes());
+        bytes.extend_from_slice(&(description_offset as u32).to_le_bytes());
+        bytes.push(descriptor_count);
+        for d in &self.descriptors {
+            let key = d.key;
+            bytes.extend_from_slice(&key.0.to_le_bytes());
+            for value in [
+                d.unicode,
+                d.shifted_unicode,
+                d.alt_gr_unicode,
+                d.shifted_alt_gr_unicode,
+                d.modifier,
+                d.affected_attribute,
+            ] {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        if !self.descriptions.is_empty() {
+            bytes.extend_from_slice(&(self.descriptions.len() as u16).to_le_bytes());
+            for (language, description) in &self.descriptions {
+                for c in language.bytes().map(u16::from).chain([u16::from(b' ')]) {
+                    bytes.extend_from_slice(&c.to_le_bytes());
+                }
+                bytes.extend_from_slice(description.as_bytes());
+            }
+        }
+
+        let len = u16::try_from(bytes.len()).expect("keyboard layout too large");
+        bytes[..2].copy_from_slice(&len.to_le_bytes());
+        bytes
+    }
+
+    /// Returns the GUID identifying the layout.
+    #[must_use]
+    pub const fn guid(&self) -> Guid {
+        self.guid
+    }
+
+    /// Returns the key descriptors of the layout.
+    #[must_use]
+    pub fn descriptors(&self) -> &[KeyDescriptor] {
+        &self.descriptors
+    }
+
+    /// Returns the descriptor for `key`, if the layout contains one.
+    #[must_use]
+    pub fn descriptor(&self, key: Key) -> Option<&KeyDescriptor> {
+        self.descriptors.iter().find(|d| {
+            let d_key = d.key;
+            d_key == key
+        })
+    }
+
+    /// Returns an iterator over the descriptions of the layout, as pairs of
+    /// RFC 4646 language code and description.
+    pub fn descriptions(&self) -> impl Iterator<Item = (&str, &CStr16)> {
+        self.descriptions
+            .iter()
+            .map(|(language, description)| (language.as_str(), description.as_ref()))
+    }
+
+    /// Returns the description of the layout in `language`, ignoring ASCII
+    /// case.
+    #[must_use]
+    pub fn description(&self, language: &str) -> Option<&CStr16> {
+        self.descriptions()
+            .find(|(l, _)| l.eq_ignore_ascii_case(language))
+            .map(|(_, description)| description)
+    }
+
+    /// Translates `key` into the character it produces with the modifier
+    /// keys in `state`.
+    ///
+    /// Returns `None` if the layout has no descriptor for `key`, if `key` is
+    /// a modifier key, or if the key doesn't produce a character in the
+    /// given state (e.g. a keypad key while num lock is off).
+    #[must_use]
+    pub fn translate(&self, key: Key, state: KeyState) -> Option<char> {
+        let descriptor = *self.descriptor(key)?;
+        if Modifier(descriptor.modifier) != Modifier::NULL {
+            return None;
+        }
+
+        let attributes = KeyAffectedAttributes::from_bits_truncate(descriptor.affected_attribute);
+        if attributes.contains(KeyAffectedAttributes::NUM_LOCK) && !state.num_lock {
+            return None;
+        }
+        let mut shifted = state.shift && attributes.contains(KeyAffectedAttributes::STANDARD_SHIFT);
+        if attributes.contains(KeyAffectedAttributes::CAPS_LOCK) && state.caps_lock {
+            shifted = !shifted;
+        }
+
+        let c = match (state.alt_gr, shifted) {
+            (false, false) => descriptor.unicode,
+            (false, true) => descriptor.shifted_unicode,
+            (true, false) => descriptor.alt_gr_unicode,
+            (true, true) => descriptor.shifted_alt_gr_unicode,
+        };
+        if c == 0 {
+            None
+        } else {
+            char::from_u32(u32::from(c))
+        }
+    }
+}
+
+impl TryFrom<&[u8]> for KeyboardLayout {
+    type Error = KeyboardLayoutError;
+
+    /// Parses a keyboard layout, starting with the `EFI_HII_KEYBOARD_LAYOUT`
+    /// header. Trailing data after the layout is ignored.
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        let header_len = mem::offset_of!(HiiKeyboardLayout, descriptors);
+        let header = bytes
+            .get(..header_len)
+            .ok_or(KeyboardLayoutError::InvalidLength)?;
+        let layout_len = usize::from(u16::from_le_bytes([header[0], header[1]]));
+        let bytes = bytes
+            .get(..layout_len)
+            .ok_or(KeyboardLayoutError::InvalidLength)?;
+        let guid = Guid::from_bytes(header[2..18].try_into().unwrap());
+        let description_offset = u32::from_le_bytes(header[18..22].try_into().unwrap()) as usize;
+        let descriptor_count = usize::from(header[22]);
+
+        let descriptor_len = mem::size_of::<KeyDescriptor>();
+        let descriptors = bytes
+            .get(header_len..header_len + descriptor_count * descriptor_len)
+            .ok_or(KeyboardLayoutError::InvalidLength)?
+            .chunks_exact(descriptor_len)
+            .map(|d| {
+                let u16_at = |i: usize| u16::from_le_bytes([d[i], d[i + 1]]);
+                KeyDescriptor {
+                    key: Key(u32::from_le_bytes(d[0..4].try_into().unwrap())),
+                    unicode: u16_at(4),
+                    shifted_unicode: u16_at(6),
+                    alt_gr_unicode: u16_at(8),
+                    shifted_alt_gr_unicode: u16_at(10),
+                    modifier: u16_at(12),
+                    affected_attribute: u16_at(14),
+                }
+            })
+            .collect();
+
+        let descriptions = if description_offset == 0 {
+            Vec::new()
+        } else {
+            let bundle = bytes
+                .get(description_offset..)
+                .ok_or(KeyboardLayoutError::InvalidLength)?;
+            parse_descriptions(bundle)?
+        };
+
+        Ok(Self {
+            guid,
+            descriptors,
+            descriptions,
+        })
+    }
+}
+
+/// Parses an `EFI_DESCRIPTION_STRING_BUNDLE`.
+///
+/// Each description consists of an RFC 4646 language code, a space, and a
+/// null-terminated description string.
+fn parse_descriptions(bundle: &[u8]) -> Result<Vec<(String, CString16)>, KeyboardLayoutError> {
+    let count = bundle
+        .get(..2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or(KeyboardLayoutError::InvalidLength)?;
+    let mut chars = bundle[2..]
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]));
+
+    let mut descriptions = Vec::with_capacity(usize::from(count));
+    for _ in 0..count {
+        let language = take_until(&mut chars, u16::from(b' '))
+            .ok_or(KeyboardLayoutError::InvalidDescription)?
+            .into_iter()
+            .map(|c| u8::try_from(c).ok().filter(u8::is_ascii).map(char::from))
+            .collect::<Option<String>>()
+            .ok_or(KeyboardLayoutError::InvalidDescription)?;
+
+        let mut description =
+            take_until(&mut chars, 0).ok_or(KeyboardLayoutError::InvalidDescription)?;
+        description.push(0);
+        let description = CString16::try_from(description)
+            .map_err(|_| KeyboardLayoutError::InvalidDescription)?;
+
+        if language.is_empty() {
+            return Err(KeyboardLayoutError::InvalidDescription);
+        }
+        descriptions.push((language, description));
+    }
+    Ok(descriptions)
+}
+
+/// Collects the characters of `chars` up to `delimiter`, which is consumed.
+/// Returns `None` if the input ends before the delimiter.
+fn take_until(chars: &mut impl Iterator<Item = u16>, delimiter: u16) -> Option<Vec<u16>> {
+    let mut taken = Vec::new();
+    loop {
+        match chars.next()? {
+            c if c == delimiter => return Some(taken),
+            c => taken.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cstr16, guid};
+    use alloc::vec;
+
+    fn descriptor(key: Key, chars: [char; 4], modifier: Modifier, attributes: u16) -> [u8; 16] {
+        let mut bytes = [0; 16];
+        bytes[0..4].copy_from_slice(&key.0.to_le_bytes());
+        for (i, c) in chars.iter().enumerate() {
+            bytes[4 + 2 * i..6 + 2 * i].copy_from_slice(&(*c as u16).to_le_bytes());
+        }
+        bytes[12..14].copy_from_slice(&modifier.0.to_le_bytes());
+        bytes[14..16].copy_from_slice(&attributes.to_le_bytes());
+        bytes
+    }
+
+    fn layout_bytes() -> Vec<u8> {
+        let guid = guid!("3ab3bb4a-f3a2-4ba4-a431-3fc9dc69e9ff");
+        let descriptors = [
+            // German `Z` key position.
+            descriptor(Key::C1, ['y', 'Y', '\0', '\0'], Modifier::NULL, 0x3),
+            descriptor(Key::E2, ['2', '"', '²', '\0'], Modifier::NULL, 0x1),
+            descriptor(Key::SEVEN, ['7', '\0', '\0', '\0'], Modifier::NULL, 0x4),
+            descriptor(Key::LSHIFT, ['\0'; 4], Modifier::LEFT_SHIFT, 0),
+        ];
+        let description: Vec<u8> = "de-DE German\0"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+
+        let description_offset = 23 + descriptors.len() * 16;
+        let len = description_offset + 2 + description.len();
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&(len as u16).to_le_bytes());
+        bytes.extend_from_slice(&guid.to_bytes());
+        bytes.extend_from_slice(&(description_offset as u32).to_le_bytes());
+        bytes.push(descriptors.len() as u8);
+        bytes.extend(descriptors.iter().flatten());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&description);
+        bytes
+    }
+
+    #[test]
+    fn test_parse_layout() {
+        let bytes = layout_bytes();
+        let layout = KeyboardLayout::try_from(bytes.as_slice()).unwrap();
+        assert_eq!(layout.guid(), guid!("3ab3bb4a-f3a2-4ba4-a431-3fc9dc69e9ff"));
+        assert_eq!(layout.descriptors().len(), 4);
+        assert_eq!(layout.description("DE-de"), Some(cstr16!("German")));
+        assert_eq!(layout.description("en-US"), None);
+
+        assert_eq!(
+            KeyboardLayout::try_from(&bytes[..bytes.len() - 1]).unwrap_err(),
+            KeyboardLayoutError::InvalidLength
+        );
+
+        // Drop the null terminator of the description.
+        let mut unterminated = bytes[..bytes.len() - 2].to_vec();
+        let len = unterminated.len() as u16;
+        unterminated[..2].copy_from_slice(&len.to_le_bytes());
+        assert_eq!(
+            KeyboardLayout::try_from(unterminated.as_slice()).unwrap_err(),
+            KeyboardLayoutError::InvalidDescription
+        );
+    }
+
+    #[test]
+    fn test_encode_layout() {
+        let bytes = layout_bytes();
+        let layout = KeyboardLayout::try_from(bytes.as_slice()).unwrap();
+        assert_eq!(layout.to_bytes(), bytes);
+
+        let built = KeyboardLayout::new(layout.guid(), layout.descriptors())
+            .with_description("de-DE", cstr16!("German"));
+        assert_eq!(built.to_bytes(), bytes);
+
+        // Without descriptions, the description offset is 0.
+        let plain = KeyboardLayout::new(layout.guid(), &layout.descriptors()[..1]);
+        let plain_bytes = plain.to_bytes();
+        assert_eq!(plain_bytes.len(), 23 + 16);
+        assert_eq!(plain_bytes[18..22], [0; 4]);
+
+        let mut package = vec![2, 0];
+        package.extend_from_slice(&bytes);
+        package.extend_from_slice(&plain_bytes);
+        let layouts = KeyboardLayout::parse_package(&package).unwrap();
+        assert_eq!(layouts.len(), 2);
+        assert_eq!(layouts[0].description("de-DE"), Some(cstr16!("German")));
+        assert_eq!(layouts[1].descriptors().len(), 1);
+        assert_eq!(
+            KeyboardLayout::parse_package(&package[..package.len() - 1]).unwrap_err(),
+            KeyboardLayoutError::InvalidLength
+        );
+    }
+
+    #[test]
+    fn test_translate() {
+        let bytes = layout_bytes();
+        let layout = KeyboardLayout::try_from(bytes.as_slice()).unwrap();
+        let shift = KeyState {
+            shift: true,
+            ..Default::default()
+        };
+        let caps_lock = KeyState {
+            caps_lock: true,
+            ..Default::default()
+        };
+        let alt_gr = KeyState {
+            alt_gr: true,
+            ..Default::default()
+        };
+        let num_lock = KeyState {
+            num_lock: true,
+            ..Default::default()
+        };
+
+        assert_eq!(layout.translate(Key::C1, KeyState::default()), Some('y'));
+        assert_eq!(layout.translate(Key::C1, shift), Some('Y'));
+        assert_eq!(layout.translate(Key::C1, caps_lock), Some('Y'));
+        assert_eq!(layout.translate(Key::E2, shift), Some('"'));
+        assert_eq!(layout.translate(Key::E2, caps_lock), Some('2'));
+        assert_eq!(layout.translate(Key::E2, alt_gr), Some('²'));
+        assert_eq!(layout.translate(Key::SEVEN, KeyState::default()), None);
+        assert_eq!(layout.translate(Key::SEVEN, num_lock), Some('7'));
+        assert_eq!(layout.translate(Key::LSHIFT, KeyState::default()), None);
+        assert_eq!(layout.translate(Key::A0, KeyState::default()), None);
+    }
+}
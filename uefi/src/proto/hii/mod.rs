@@ -2,10 +2,202 @@
 
 //! HII Protocols
 
+#[cfg(feature = "alloc")]
+pub mod browser;
 pub mod config;
 #[cfg(feature = "alloc")]
+pub mod config_access;
+#[cfg(feature = "alloc")]
 pub mod config_routing;
 #[cfg(feature = "alloc")]
 pub mod config_str;
 #[cfg(feature = "alloc")]
 pub mod database;
+#[cfg(feature = "alloc")]
+pub mod disasm;
+#[cfg(feature = "alloc")]
+pub mod expression;
+#[cfg(feature = "alloc")]
+pub mod font;
+#[cfg(feature = "alloc")]
+pub mod form;
+pub mod form_browser;
+pub mod ifr;
+#[cfg(feature = "alloc")]
+pub mod image;
+#[cfg(feature = "alloc")]
+pub mod keyboard;
+#[cfg(feature = "alloc")]
+pub mod package;
+#[cfg(feature = "alloc")]
+pub mod popup;
+#[cfg(feature = "alloc")]
+pub mod settings;
+#[cfg(feature = "alloc")]
+pub mod string;
+pub mod varstore;
+
+pub use uefi_raw::protocol::hii::{FormId, ImageId, QuestionId, StringId, VarstoreId};
+
+use core::ffi::c_void;
+use core::ptr::NonNull;
+
+#[cfg(feature = "alloc")]
+use {
+    crate::boot,
+    crate::proto::console::gop::{BltOp, BltPixel, BltRegion},
+    alloc::vec,
+    alloc::vec::Vec,
+    core::slice,
+    uefi_raw::protocol::hii::image::ImageOutput,
+};
+
+/// Opaque handle to a package list in the HII database, guaranteed to be
+/// non-null.
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
+#[repr(transparent)]
+pub struct HiiHandle(NonNull<c_void>);
+
+impl HiiHandle {
+    /// Creates a new [`HiiHandle`] from a raw pointer. Returns `None` if the
+    /// pointer is null.
+    ///
+    /// # Safety
+    ///
+    /// The caller must be sure that the pointer is a valid HII handle.
+    /// Otherwise, further operations on the handle might result in undefined
+    /// behaviour, even if the methods aren't marked as unsafe.
+    #[must_use]
+    pub unsafe fn from_ptr(ptr: *mut c_void) -> Option<Self> {
+        NonNull::new(ptr).map(Self)
+    }
+
+    /// Get the underlying raw pointer.
+    #[must_use]
+    pub const fn as_ptr(&self) -> *mut c_void {
+        self.0.as_ptr()
+    }
+}
+
+/// An image in the pixel format of GOP Blt buffers, such as text rendered by
+/// [`HiiFont`].
+///
+/// [`HiiFont`]: font::HiiFont
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BltImage {
+    width: usize,
+    height: usize,
+    pixels: Vec<BltPixel>,
+}
+
+#[cfg(feature = "alloc")]
+impl BltImage {
+    /// Creates an image of `width` by `height` pixels filled with `color`.
+    #[must_use]
+    pub fn new(width: usize, height: usize, color: BltPixel) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![color; width * height],
+        }
+    }
+
+    /// Creates an image from the rows of `pixels`, which are `stride` pixels
+    /// apart, cropped to `width` by `height` pixels.
+    fn from_rows(pixels: &[BltPixel], stride: usize, width: usize, height: usize) -> Self {
+        let pixels = pixels
+            .chunks(stride)
+            .take(height)
+            .flat_map(|row| &row[..width])
+            .copied()
+            .collect();
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Returns the width in pixels.
+    #[must_use]
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the height in pixels.
+    #[must_use]
+    pub const fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the pixels, row by row.
+    #[must_use]
+    pub fn pixels(&self) -> &[BltPixel] {
+        &self.pixels
+    }
+
+    /// Returns the pixels mutably, row by row.
+    #[must_use]
+    pub fn pixels_mut(&mut self) -> &mut [BltPixel] {
+        &mut self.pixels
+    }
+
+    /// Consumes the image, returning its pixels row by row.
+    #[must_use]
+    pub fn into_pixels(self) -> Vec<BltPixel> {
+        self.pixels
+    }
+
+    /// Returns the pixel at `x`, `y`, or `None` if it is outside the image.
+    #[must_use]
+    pub fn pixel(&self, x: usize, y: usize) -> Option<BltPixel> {
+        if x >= self.width {
+            return None;
+        }
+        self.pixels.get(y * self.width + x).copied()
+    }
+
+    /// Returns a [`BltOp`] that draws the image to the screen with its
+    /// top left corner at `dest`.
+    #[must_use]
+    pub fn to_video(&self, dest: (usize, usize)) -> BltOp<'_> {
+        BltOp::BufferToVideo {
+            buffer: &self.pixels,
+            src: BltRegion::Full,
+            dest,
+            dims: (self.width, self.height),
+        }
+    }
+}
+
+/// Frees memory allocated by the firmware.
+#[cfg(feature = "alloc")]
+unsafe fn free<T>(ptr: *mut T) {
+    if let Some(ptr) = NonNull::new(ptr.cast::<u8>()) {
+        // Nothing can be done if this fails.
+        let _ = unsafe { boot::free_pool(ptr) };
+    }
+}
+
+/// Copies an image allocated by the firmware, cropped to `size`, and frees
+/// it.
+#[cfg(feature = "alloc")]
+unsafe fn take_image(blt: *mut ImageOutput, size: Option<(usize, usize)>) -> BltImage {
+    unsafe {
+        let width = usize::from((*blt).width);
+        let height = usize::from((*blt).height);
+        let bitmap = (*blt).image.bitmap;
+        let pixels = slice::from_raw_parts(bitmap.cast::<BltPixel>(), width * height);
+        let (crop_width, crop_height) = size.unwrap_or((width, height));
+        let image = BltImage::from_rows(
+            pixels,
+            width,
+            crop_width.min(width),
+            crop_height.min(height),
+        );
+        free(bitmap);
+        free(blt);
+        image
+    }
+}
@@ -0,0 +1,1482 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! HII package lists.
+//!
+//! A package list is the unit in which HII resources (strings, forms, fonts,
+//! images, ...) are registered with the [`HiiDatabase`]. It consists of an
+//! `EFI_HII_PACKAGE_LIST_HEADER`, followed by any number of packages, and is
+//! terminated by an end package.
+//!
+//! Package lists are created with [`PackageListBuilder`], and parsed with
+//! [`PackageLists`] and [`PackageList::packages`].
+//!
+//! [`HiiDatabase`]: super::database::HiiDatabase
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+use core::mem;
+use uefi_raw::protocol::hii::string::HiiStringPackageHeader;
+use uefi_raw::protocol::hii::{HiiPackageHeader, HiiPackageListHeader};
+
+use super::database::HiiDatabase;
+use super::keyboard::KeyboardLayout;
+use super::{HiiHandle, StringId};
+use crate::config::ini::Ini;
+use crate::config::{ParseError, ParseErrorKind};
+use crate::proto::device_path::DevicePath;
+use crate::{CStr8, CStr16, CString16, Guid, Handle, Result, boot};
+
+pub use uefi_raw::protocol::hii::HiiPackageType;
+pub use uefi_raw::protocol::hii::string::StringBlockType;
+
+/// Maximum length of a single package, including its header.
+const MAX_PACKAGE_LEN: usize = 0x00ff_ffff;
+
+/// Builder for an HII package list.
+///
+/// # Example
+///
+/// ```
+/// use uefi::proto::hii::package::PackageListBuilder;
+/// use uefi::{cstr8, cstr16, guid};
+///
+/// let package_list = PackageListBuilder::new(guid!("3ab3bb4a-f3a2-4ba4-a431-3fc9dc69e9ff"))
+///     .strings(cstr8!("en-US"), &[cstr16!("Hello"), cstr16!("Goodbye")])
+///     .strings(cstr8!("de-DE"), &[cstr16!("Hallo"), cstr16!("Tschüss")])
+///     .build();
+/// ```
+#[derive(Debug)]
+pub struct PackageListBuilder {
+    guid: Guid,
+    packages: Vec<u8>,
+}
+
+impl PackageListBuilder {
+    /// Creates a builder for an empty package list identified by `guid`.
+    #[must_use]
+    pub const fn new(guid: Guid) -> Self {
+        Self {
+            guid,
+            packages: Vec::new(),
+        }
+    }
+
+    /// Adds a package of type `package_type` with the given contents. The
+    /// package header is added automatically and must not be part of
+    /// `data`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the package is larger than 16 MiB, the maximum size that
+    /// can be encoded in the package header.
+    #[must_use]
+    pub fn package(mut self, package_type: HiiPackageType, data: &[u8]) -> Self {
+        let len = mem::size_of::<HiiPackageHeader>() + data.len();
+        assert!(len <= MAX_PACKAGE_LEN, "HII package too large");
+        let header = HiiPackageHeader::new(package_type, len as u32);
+        self.packages
+            .extend_from_slice(&header.length_and_type.to_le_bytes());
+        self.packages.extend_from_slice(data);
+        self
+    }
+
+    /// Adds a string package for `language`, an RFC 4646 language code such
+    /// as `en-US`.
+    ///
+    /// The strings are assigned consecutive string IDs starting at 1, in the
+    /// order given. String packages of different languages in one package
+    /// list should therefore contain the same strings in the same order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the package is larger than 16 MiB.
+    #[must_use]
+    pub fn strings(self, language: &CStr8, strings: &[&CStr16]) -> Self {
+        self.string_package(language.as_bytes(), strings.iter().copied())
+    }
+
+    /// Adds one string package per language of `table`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a package is larger than 16 MiB.
+    #[must_use]
+    pub fn string_table(self, table: &StringTable) -> Self {
+        table
+            .languages
+            .iter()
+            .fold(self, |builder, (language, strings)| {
+                builder.string_package(language, strings.iter().map(|s| &**s))
+            })
+    }
+
+    /// Adds a string package for `language`, which includes the trailing
+    /// null byte.
+    fn string_package<'a>(
+        self,
+        language: &[u8],
+        strings: impl Iterator<Item = &'a CStr16>,
+    ) -> Self {
+        let header_size = mem::offset_of!(HiiStringPackageHeader, language) + language.len();
+
+        // The package header is added by `package`, so only the remaining
+        // fields of the string package header are written here.
+        let mut data = Vec::new();
+        data.extend_from_slice(&(header_size as u32).to_le_bytes());
+        // The string information starts right after the header.
+        data.extend_from_slice(&(header_size as u32).to_le_bytes());
+        // The language window is reserved for SCSU strings and unused here.
+        data.extend_from_slice(&[0; 32]);
+        // No string describes the language name.
+        data.extend_from_slice(&0u16.to_le_bytes());
+        data.extend_from_slice(language);
+
+        for string in strings {
+            data.push(StringBlockType::STRING_UCS2.0);
+            data.extend_from_slice(string.as_bytes());
+        }
+        data.push(StringBlockType::END.0);
+
+        self.package(HiiPackageType::STRINGS, &data)
+    }
+
+    /// Adds a form package containing `ifr`, e.g. built with
+    /// [`FormSetBuilder`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the package is larger than 16 MiB.
+    ///
+    /// [`FormSetBuilder`]: super::form::FormSetBuilder
+    #[must_use]
+    pub fn forms(self, ifr: &[u8]) -> Self {
+        self.package(HiiPackageType::FORMS, ifr)
+    }
+
+    /// Adds a keyboard layout package containing `layouts`.
+    ///
+    /// Once the package list is registered, the layouts can be selected
+    /// with [`HiiDatabase::set_keyboard_layout`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more than 65535 layouts, or a layout can't be
+    /// encoded (see [`KeyboardLayout::to_bytes`]).
+    #[must_use]
+    pub fn keyboard_layouts(self, layouts: &[KeyboardLayout]) -> Self {
+        let count = u16::try_from(layouts.len()).expect("too many keyboard layouts");
+        let mut data = Vec::from(count.to_le_bytes());
+        for layout in layouts {
+            data.extend_from_slice(&layout.to_bytes());
+        }
+        self.package(HiiPackageType::KEYBOARD_LAYOUT, &data)
+    }
+
+    /// Adds a GUID package, containing `data` in a format identified by
+    /// `guid`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the package is larger than 16 MiB.
+    #[must_use]
+    pub fn guid_package(self, guid: Guid, data: &[u8]) -> Self {
+        let mut package = Vec::with_capacity(mem::size_of::<Guid>() + data.len());
+        package.extend_from_slice(&guid.to_bytes());
+        package.extend_from_slice(data);
+        self.package(HiiPackageType::TYPE_GUID, &package)
+    }
+
+    /// Finishes the package list by adding the end package and the package
+    /// list header.
+    ///
+    /// The result can be registered with
+    /// [`HiiDatabase::new_package_list`].
+    ///
+    /// [`HiiDatabase::new_package_list`]: super::database::HiiDatabase::new_package_list
+    #[must_use]
+    pub fn build(self) -> Vec<u8> {
+        let Self { guid, packages } = self.package(HiiPackageType::END, &[]);
+        let len = mem::size_of::<HiiPackageListHeader>() + packages.len();
+
+        let mut package_list = Vec::with_capacity(len);
+        package_list.extend_from_slice(&guid.to_bytes());
+        package_list.extend_from_slice(&(len as u32).to_le_bytes());
+        package_list.extend_from_slice(&packages);
+        package_list
+    }
+
+    /// Finishes the package list and adds it to the HII database.
+    ///
+    /// If `driver_handle` is set, the package list is associated with the
+    /// driver, which is required for forms whose varstores are provided by
+    /// the HII Config Access protocol of the driver.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::NOT_FOUND`]: the HII Database protocol is not available.
+    /// * See [`HiiDatabase::new_package_list`] for other errors.
+    ///
+    /// [`Status::NOT_FOUND`]: crate::Status::NOT_FOUND
+    pub fn register(self, driver_handle: Option<Handle>) -> Result<HiiHandle> {
+        let package_list = self.build();
+        let db_handle = boot::get_handle_for_protocol::<HiiDatabase>()?;
+        let db = boot::open_protocol_exclusive::<HiiDatabase>(db_handle)?;
+        db.new_package_list(&package_list, driver_handle)
+    }
+}
+
+/// Error returned when parsing package lists with [`PackageLists`] or
+/// [`PackageList`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PackageError {
+    /// A package list or package extends past the end of the buffer, or is
+    /// shorter than its header.
+    Truncated {
+        /// Byte offset of the package list or package.
+        offset: usize,
+    },
+    /// A package list doesn't contain an end package.
+    MissingEnd {
+        /// Byte offset of the package list.
+        offset: usize,
+    },
+}
+
+impl Display for PackageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Truncated { offset } => write!(f, "truncated HII package at offset {offset}"),
+            Self::MissingEnd { offset } => {
+                write!(f, "HII package list at offset {offset} has no end package")
+            }
+        }
+    }
+}
+
+impl core::error::Error for PackageError {}
+
+pub(super) fn u16_at(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(data[offset..offset + 2].try_into().unwrap())
+}
+
+pub(super) fn u32_at(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+pub(super) fn guid_at(data: &[u8], offset: usize) -> Guid {
+    Guid::from_bytes(data[offset..offset + 16].try_into().unwrap())
+}
+
+/// Iterator over the package lists in a buffer, such as one returned by
+/// [`HiiDatabase::export_package_lists`].
+///
+/// The iterator stops after returning the first error.
+///
+/// # Example
+///
+/// ```
+/// use uefi::proto::hii::package::{Package, PackageError, PackageLists};
+///
+/// fn languages(dump: &[u8]) -> Result<(), PackageError> {
+///     for list in PackageLists::new(dump) {
+///         let list = list?;
+///         for package in list.packages() {
+///             if let Package::Strings(strings) = package? {
+///                 log::info!("{}: {:?}", list.guid(), strings.language());
+///             }
+///         }
+///     }
+///     Ok(())
+/// }
+/// ```
+///
+/// [`HiiDatabase::export_package_lists`]: super::database::HiiDatabase::export_package_lists
+#[derive(Clone, Debug)]
+pub struct PackageLists<'a> {
+    buf: &'a [u8],
+    offset: usize,
+    done: bool,
+}
+
+impl<'a> PackageLists<'a> {
+    /// Creates an iterator over the package lists in `buf`.
+    #[must_use]
+    pub const fn new(buf: &'a [u8]) -> Self {
+        Self {
+            buf,
+            offset: 0,
+            done: false,
+        }
+    }
+}
+
+impl<'a> Iterator for PackageLists<'a> {
+    type Item = core::result::Result<PackageList<'a>, PackageError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done || self.offset >= self.buf.len() {
+            return None;
+        }
+        let result = PackageList::parse_at(self.buf, self.offset);
+        match &result {
+            Ok(list) => self.offset += list.bytes.len(),
+            Err(_) => self.done = true,
+        }
+        Some(result)
+    }
+}
+
+/// A length-checked package list in a buffer.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PackageList<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> PackageList<'a> {
+    /// Parses the package list at the start of `buf`. Bytes after the end of
+    /// the package list are ignored.
+    ///
+    /// # Errors
+    ///
+    /// * [`PackageError::Truncated`]: the package list header is invalid, or
+    ///   the package list extends past the end of `buf`.
+    pub fn parse(buf: &'a [u8]) -> core::result::Result<Self, PackageError> {
+        Self::parse_at(buf, 0)
+    }
+
+    fn parse_at(buf: &'a [u8], offset: usize) -> core::result::Result<Self, PackageError> {
+        let truncated = PackageError::Truncated { offset };
+        let header_len = mem::size_of::<HiiPackageListHeader>();
+        let header = offset
+            .checked_add(header_len)
+            .and_then(|end| buf.get(offset..end))
+            .ok_or(truncated)?;
+        let len = u32_at(header, 16) as usize;
+        let bytes = offset
+            .checked_add(len)
+            .and_then(|end| buf.get(offset..end))
+            .filter(|_| len >= header_len)
+            .ok_or(truncated)?;
+        Ok(Self { bytes, offset })
+    }
+
+    /// Returns the GUID identifying the package list.
+    #[must_use]
+    pub fn guid(&self) -> Guid {
+        guid_at(self.bytes, 0)
+    }
+
+    /// Returns all bytes of the package list, including the header.
+    #[must_use]
+    pub const fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Returns the byte offset of the package list in the buffer it was
+    /// parsed from.
+    #[must_use]
+    pub const fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns an iterator over the packages, excluding the end package.
+    #[must_use]
+    pub const fn packages(&self) -> Packages<'a> {
+        Packages {
+            list: self.bytes,
+            base: self.offset,
+            offset: mem::size_of::<HiiPackageListHeader>(),
+            done: false,
+        }
+    }
+}
+
+/// Iterator over the packages of a [`PackageList`], excluding the end
+/// package.
+///
+/// The iterator stops after returning the first error.
+#[derive(Clone, Debug)]
+pub struct Packages<'a> {
+    list: &'a [u8],
+    /// Offset of the package list in the buffer, for errors.
+    base: usize,
+    offset: usize,
+    done: bool,
+}
+
+impl<'a> Iterator for Packages<'a> {
+    type Item = core::result::Result<Package<'a>, PackageError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        self.done = true;
+        if self.offset >= self.list.len() {
+            return Some(Err(PackageError::MissingEnd { offset: self.base }));
+        }
+        let truncated = PackageError::Truncated {
+            offset: self.base + self.offset,
+        };
+        let Some(header) = self.list.get(self.offset..self.offset + 4) else {
+            return Some(Err(truncated));
+        };
+        let len = (u32_at(header, 0) & 0x00ff_ffff) as usize;
+        let package_type = HiiPackageType(header[3]);
+        let Some(package) = self
+            .list
+            .get(self.offset..self.offset + len)
+            .filter(|_| len >= 4)
+        else {
+            return Some(Err(truncated));
+        };
+        if package_type == HiiPackageType::END {
+            return None;
+        }
+        let package = Package::parse(package_type, package).ok_or(truncated);
+        self.done = package.is_err();
+        self.offset += len;
+        Some(package)
+    }
+}
+
+/// A package of a [`PackageList`], split by type. Except for
+/// [`Package::Other`], the data excludes the package header.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Package<'a> {
+    /// A GUID package, containing data in a format identified by the GUID.
+    Guid {
+        /// GUID identifying the format of the data.
+        guid: Guid,
+        /// Data following the GUID.
+        data: &'a [u8],
+    },
+    /// A form package, containing IFR opcodes that can be walked with
+    /// [`IfrOps`].
+    ///
+    /// [`IfrOps`]: super::ifr::IfrOps
+    Forms(&'a [u8]),
+    /// A string package.
+    Strings(StringPackage<'a>),
+    /// A font package.
+    Fonts(&'a [u8]),
+    /// An image package.
+    Images(&'a [u8]),
+    /// A simple font package.
+    SimpleFonts(&'a [u8]),
+    /// A device path package, associating the package list with a device.
+    DevicePath(&'a DevicePath),
+    /// A keyboard layout package.
+    KeyboardLayout(&'a [u8]),
+    /// An animation package.
+    Animations(&'a [u8]),
+    /// A package of another type, such as a system-specific package.
+    Other {
+        /// Type of the package.
+        package_type: HiiPackageType,
+        /// All bytes of the package, including the header.
+        bytes: &'a [u8],
+    },
+}
+
+impl<'a> Package<'a> {
+    /// Splits `package`, which includes the header, by type. Returns `None`
+    /// if it's too short for its type.
+    fn parse(package_type: HiiPackageType, package: &'a [u8]) -> Option<Self> {
+        let data = &package[4..];
+        Some(match package_type {
+            HiiPackageType::TYPE_GUID => Self::Guid {
+                guid: guid_at(data.get(..16)?, 0),
+                data: &data[16..],
+            },
+            HiiPackageType::FORMS => Self::Forms(data),
+            HiiPackageType::STRINGS => Self::Strings(StringPackage::parse(package)?),
+            HiiPackageType::FONTS => Self::Fonts(data),
+            HiiPackageType::IMAGES => Self::Images(data),
+            HiiPackageType::SIMPLE_FONTS => Self::SimpleFonts(data),
+            HiiPackageType::DEVICE_PATH => Self::DevicePath(<&DevicePath>::try_from(data).ok()?),
+            HiiPackageType::KEYBOARD_LAYOUT => Self::KeyboardLayout(data),
+            HiiPackageType::ANIMATIONS => Self::Animations(data),
+            package_type => Self::Other {
+                package_type,
+                bytes: package,
+            },
+        })
+    }
+
+    /// Returns the type of the package.
+    #[must_use]
+    pub const fn package_type(&self) -> HiiPackageType {
+        match self {
+            Self::Guid { .. } => HiiPackageType::TYPE_GUID,
+            Self::Forms(_) => HiiPackageType::FORMS,
+            Self::Strings(_) => HiiPackageType::STRINGS,
+            Self::Fonts(_) => HiiPackageType::FONTS,
+            Self::Images(_) => HiiPackageType::IMAGES,
+            Self::SimpleFonts(_) => HiiPackageType::SIMPLE_FONTS,
+            Self::DevicePath(_) => HiiPackageType::DEVICE_PATH,
+            Self::KeyboardLayout(_) => HiiPackageType::KEYBOARD_LAYOUT,
+            Self::Animations(_) => HiiPackageType::ANIMATIONS,
+            Self::Other { package_type, .. } => *package_type,
+        }
+    }
+}
+
+/// A string package, containing the strings of a package list in one
+/// language.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct StringPackage<'a> {
+    bytes: &'a [u8],
+    language: &'a CStr8,
+}
+
+impl<'a> StringPackage<'a> {
+    /// Parses the header of `package`, which includes the package header.
+    pub(super) fn parse(package: &'a [u8]) -> Option<Self> {
+        let language_offset = mem::offset_of!(HiiStringPackageHeader, language);
+        let header_size = package.get(4..8).map(|b| u32_at(b, 0) as usize)?;
+        let info_offset = package.get(8..12).map(|b| u32_at(b, 0) as usize)?;
+        if header_size < language_offset || info_offset > package.len() {
+            return None;
+        }
+        let language = package.get(language_offset..header_size)?;
+        let len = language.iter().position(|&b| b == 0)?;
+        Some(Self {
+            bytes: package,
+            language: CStr8::from_bytes_with_nul(&language[..=len]).ok()?,
+        })
+    }
+
+    /// Returns the RFC 4646 language code of the strings, such as `en-US`.
+    #[must_use]
+    pub const fn language(&self) -> &'a CStr8 {
+        self.language
+    }
+
+    /// Returns the ID of the string naming the language, or 0 if there is
+    /// none.
+    #[must_use]
+    pub fn language_name(&self) -> StringId {
+        u16_at(
+            self.bytes,
+            mem::offset_of!(HiiStringPackageHeader, language_name),
+        )
+    }
+
+    /// Returns an iterator over the string blocks.
+    #[must_use]
+    pub fn blocks(&self) -> StringBlocks<'a> {
+        StringBlocks {
+            package: self.bytes,
+            offset: u32_at(self.bytes, 8) as usize,
+            id: 1,
+            remaining: 0,
+            scsu: false,
+            font: None,
+            done: false,
+        }
+    }
+
+    /// Returns the string `id`, or `None` if the package doesn't contain it
+    /// or it can't be decoded with [`StringText::to_cstring16`]. Blocks
+    /// after a malformed block are ignored.
+    #[must_use]
+    pub fn string(&self, mut id: StringId) -> Option<CString16> {
+        // Duplicates are resolved in a loop rather than recursively, since
+        // the package may come from untrusted data with long chains.
+        loop {
+            let mut blocks = self.blocks().map_while(|block| block.ok());
+            let found = blocks.find_map(|block| match block {
+                StringBlock::String { id: i, text, .. } if i == id => Some(Ok(text)),
+                // Duplicates refer to earlier strings, which rules out cycles.
+                StringBlock::Duplicate { id: i, of } if i == id && of < id => Some(Err(of)),
+                _ => None,
+            })?;
+            match found {
+                Ok(text) => return text.to_cstring16(),
+                Err(of) => id = of,
+            }
+        }
+    }
+
+    /// Decodes all strings. Strings that can't be decoded with
+    /// [`StringText::to_cstring16`] are left out.
+    ///
+    /// # Errors
+    ///
+    /// Fails with the first error of [`StringBlocks`].
+    pub fn strings(&self) -> core::result::Result<BTreeMap<StringId, CString16>, StringBlockError> {
+        let mut strings = BTreeMap::new();
+        for block in self.blocks() {
+            match block? {
+                StringBlock::String { id, text, .. } => {
+                    if let Some(text) = text.to_cstring16() {
+                        strings.insert(id, text);
+                    }
+                }
+                StringBlock::Duplicate { id, of } => {
+                    if let Some(text) = strings.get(&of).cloned() {
+                        strings.insert(id, text);
+                    }
+                }
+                StringBlock::Skip { .. } | StringBlock::Ext { .. } => {}
+            }
+        }
+        Ok(strings)
+    }
+
+    /// Returns all bytes of the package, including the header.
+    #[must_use]
+    pub const fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+}
+
+/// Text of a string in a [`StringPackage`], in the encoding of its block.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StringText<'a> {
+    /// UCS-2 text, as little-endian code units without the null terminator.
+    Ucs2(&'a [u8]),
+    /// SCSU-compressed text, without the null terminator.
+    Scsu(&'a [u8]),
+}
+
+impl StringText<'_> {
+    /// Decodes the text.
+    ///
+    /// SCSU text is only decoded if it doesn't use any tags, i.e. if it
+    /// consists of ASCII and, through the default window, Latin-1
+    /// characters. Returns `None` for other SCSU text, and for UCS-2 text
+    /// containing invalid characters.
+    #[must_use]
+    pub fn to_cstring16(&self) -> Option<CString16> {
+        let mut chars = Vec::new();
+        match self {
+            Self::Ucs2(bytes) => {
+                chars.extend(bytes.chunks_exact(2).map(|c| u16_at(c, 0)));
+            }
+            Self::Scsu(bytes) => {
+                for &b in *bytes {
+                    // Bytes below 0x20 other than these are tags.
+                    if b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r') {
+                        return None;
+                    }
+                    chars.push(b.into());
+                }
+            }
+        }
+        chars.push(0);
+        CString16::try_from(chars).ok()
+    }
+}
+
+/// A block of a [`StringPackage`], returned by [`StringBlocks`]. Blocks
+/// with several strings are split into one [`StringBlock::String`] per
+/// string.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StringBlock<'a> {
+    /// A string.
+    String {
+        /// ID of the string.
+        id: StringId,
+        /// Font of the string, as declared by a [`StringBlockType::FONT`]
+        /// block, if the block specified one.
+        font: Option<u8>,
+        /// Text of the string.
+        text: StringText<'a>,
+    },
+    /// A string that is the same as an earlier string.
+    Duplicate {
+        /// ID of the string.
+        id: StringId,
+        /// ID of the earlier string.
+        of: StringId,
+    },
+    /// A range of string IDs without strings in this package.
+    Skip {
+        /// First skipped ID.
+        first: StringId,
+        /// Number of skipped IDs.
+        count: u16,
+    },
+    /// An extended block, such as a font declaration.
+    Ext {
+        /// Type of the extended block, e.g. [`StringBlockType::FONT`].
+        block_type: StringBlockType,
+        /// Data of the block after its header.
+        data: &'a [u8],
+    },
+}
+
+/// Error returned by [`StringBlocks`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StringBlockError {
+    /// A block extends past the end of the package, or a string is not
+    /// terminated.
+    Truncated {
+        /// Byte offset of the block in the package.
+        offset: usize,
+    },
+    /// A block has an unknown type.
+    UnknownBlock {
+        /// Type of the block.
+        block_type: StringBlockType,
+        /// Byte offset of the block in the package.
+        offset: usize,
+    },
+}
+
+impl Display for StringBlockError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Truncated { offset } => write!(f, "truncated string block at offset {offset}"),
+            Self::UnknownBlock { block_type, offset } => {
+                write!(f, "unknown string block {block_type:?} at offset {offset}")
+            }
+        }
+    }
+}
+
+impl core::error::Error for StringBlockError {}
+
+/// Iterator over the blocks of a [`StringPackage`], up to the end block.
+///
+/// The iterator stops after returning the first error.
+#[derive(Clone, Debug)]
+pub struct StringBlocks<'a> {
+    package: &'a [u8],
+    offset: usize,
+    /// ID of the next string.
+    id: StringId,
+    /// Remaining strings of a block with several strings, with their
+    /// encoding and font.
+    remaining: u16,
+    scsu: bool,
+    font: Option<u8>,
+    done: bool,
+}
+
+impl<'a> StringBlocks<'a> {
+    /// Returns the string at the current offset, and the offset after its
+    /// null terminator.
+    fn text(&self, scsu: bool) -> Option<(StringText<'a>, usize)> {
+        let bytes = self.package.get(self.offset..)?;
+        if scsu {
+            let len = bytes.iter().position(|&b| b == 0)?;
+            Some((StringText::Scsu(&bytes[..len]), self.offset + len + 1))
+        } else {
+            let len = 2 * bytes.chunks_exact(2).position(|c| c == [0, 0])?;
+            Some((StringText::Ucs2(&bytes[..len]), self.offset + len + 2))
+        }
+    }
+
+    fn next_block(&mut self) -> core::result::Result<Option<StringBlock<'a>>, StringBlockError> {
+        let package = self.package;
+        let offset = self.offset;
+        let truncated = StringBlockError::Truncated { offset };
+        let byte = |i: usize| package.get(offset + i).copied().ok_or(truncated);
+        let word = |i: usize| {
+            package
+                .get(offset + i..offset + i + 2)
+                .map(|b| u16_at(b, 0))
+                .ok_or(truncated)
+        };
+
+        if self.remaining == 0 {
+            let block_type = StringBlockType(byte(0)?);
+            // Sets up the strings of a string block: the header size, the
+            // number of strings, the encoding, and the font.
+            let (header_len, count, scsu, font) = match block_type {
+                StringBlockType::END => return Ok(None),
+                StringBlockType::STRING_SCSU => (1, 1, true, None),
+                StringBlockType::STRING_SCSU_FONT => (2, 1, true, Some(byte(1)?)),
+                StringBlockType::STRINGS_SCSU => (3, word(1)?, true, None),
+                StringBlockType::STRINGS_SCSU_FONT => (4, word(2)?, true, Some(byte(1)?)),
+                StringBlockType::STRING_UCS2 => (1, 1, false, None),
+                StringBlockType::STRING_UCS2_FONT => (2, 1, false, Some(byte(1)?)),
+                StringBlockType::STRINGS_UCS2 => (3, word(1)?, false, None),
+                StringBlockType::STRINGS_UCS2_FONT => (4, word(2)?, false, Some(byte(1)?)),
+                StringBlockType::DUPLICATE => {
+                    let block = StringBlock::Duplicate {
+                        id: self.id,
+                        of: word(1)?,
+                    };
+                    self.id = self.id.wrapping_add(1);
+                    self.offset += 3;
+                    return Ok(Some(block));
+                }
+                StringBlockType::SKIP1 | StringBlockType::SKIP2 => {
+                    let (count, len) = if block_type == StringBlockType::SKIP1 {
+                        (byte(1)?.into(), 2)
+                    } else {
+                        (word(1)?, 3)
+                    };
+                    let block = StringBlock::Skip {
+                        first: self.id,
+                        count,
+                    };
+                    self.id = self.id.wrapping_add(count);
+                    self.offset += len;
+                    return Ok(Some(block));
+                }
+                StringBlockType::EXT1 | StringBlockType::EXT2 | StringBlockType::EXT4 => {
+                    let (len, header_len) = match block_type {
+                        StringBlockType::EXT1 => (usize::from(byte(2)?), 3),
+                        StringBlockType::EXT2 => (usize::from(word(2)?), 4),
+                        _ => {
+                            let len = package.get(offset + 2..offset + 6).ok_or(truncated)?;
+                            (u32_at(len, 0) as usize, 6)
+                        }
+                    };
+                    let data = package
+                        .get(offset + header_len..offset + len)
+                        .ok_or(truncated)?;
+                    self.offset += len;
+                    return Ok(Some(StringBlock::Ext {
+                        block_type: StringBlockType(byte(1)?),
+                        data,
+                    }));
+                }
+                block_type => {
+                    return Err(StringBlockError::UnknownBlock { block_type, offset });
+                }
+            };
+            self.offset += header_len;
+            self.remaining = count;
+            self.scsu = scsu;
+            self.font = font;
+            if count == 0 {
+                return self.next_block();
+            }
+        }
+
+        let (text, next) = self.text(self.scsu).ok_or(truncated)?;
+        let block = StringBlock::String {
+            id: self.id,
+            font: self.font,
+            text,
+        };
+        self.id = self.id.wrapping_add(1);
+        self.remaining -= 1;
+        self.offset = next;
+        Ok(Some(block))
+    }
+}
+
+impl<'a> Iterator for StringBlocks<'a> {
+    type Item = core::result::Result<StringBlock<'a>, StringBlockError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let block = self.next_block().transpose();
+        self.done = !matches!(block, Some(Ok(_)));
+        block
+    }
+}
+
+/// Translated strings, parsed from an INI-style source with one section per
+/// language:
+///
+/// ```ini
+/// [en-US]
+/// TITLE = Example settings
+/// ENABLE = Enable feature
+///
+/// [de-DE]
+/// TITLE = Beispieleinstellungen
+/// ```
+///
+/// The first section defines the string keys. Each key gets a [`StringId`],
+/// starting at 1 in the order of the first section. Other sections may
+/// only use these keys; strings they don't translate fall back to the first
+/// language. The syntax is described in the [`ini`] module.
+///
+/// Tables can also be built in code with [`StringTable::add`].
+///
+/// The strings are added to a package list with
+/// [`PackageListBuilder::string_table`], or registered in their own package
+/// list with [`StringTable::register`].
+///
+/// # Example
+///
+/// ```
+/// use uefi::cstr16;
+/// use uefi::proto::hii::package::StringTable;
+///
+/// let mut table = StringTable::parse("[en-US]\nTITLE = Settings\n[de]\nTITLE = Einstellungen\n")
+///     .unwrap();
+/// assert_eq!(table.id("TITLE"), Some(1));
+///
+/// let id = table.add("HELP", &[("en-US", cstr16!("Help")), ("de", cstr16!("Hilfe"))]);
+/// assert_eq!(id, 2);
+/// ```
+///
+/// [`ini`]: crate::config::ini
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct StringTable {
+    keys: Vec<String>,
+    /// Pairs of the null-terminated language code and the strings, in the
+    /// order of `keys`.
+    languages: Vec<(Vec<u8>, Vec<CString16>)>,
+}
+
+impl StringTable {
+    /// Parses `text`.
+    ///
+    /// # Errors
+    ///
+    /// * [`StringTableError::Parse`]: the text is malformed, or a key is
+    ///   defined twice in a section.
+    /// * [`StringTableError::MissingLanguage`]: a string is defined outside
+    ///   of a section.
+    /// * [`StringTableError::UnknownKey`]: a key is not defined in the first
+    ///   section.
+    /// * [`StringTableError::InvalidChar`]: a string can't be encoded as
+    ///   UCS-2.
+    pub fn parse(text: &str) -> core::result::Result<Self, StringTableError> {
+        let mut table = Self::default();
+        // Pairs of language index and ID of the strings translated so far,
+        // excluding the first language.
+        let mut translated = Vec::new();
+        let ini = Ini::new(text);
+        for entry in ini.entries() {
+            let entry = entry.map_err(StringTableError::Parse)?;
+            if entry.section.is_empty() {
+                return Err(StringTableError::MissingLanguage { line: entry.line });
+            }
+            let string = CString16::try_from(entry.value)
+                .map_err(|_| StringTableError::InvalidChar { line: entry.line })?;
+
+            let index = table.language_index(entry.section);
+
+            let duplicate = Err(StringTableError::Parse(ParseError {
+                line: entry.line,
+                kind: ParseErrorKind::DuplicateKey,
+            }));
+            if index == 0 {
+                if table.id(entry.key).is_some() {
+                    return duplicate;
+                }
+                table.keys.push(entry.key.into());
+                table.languages[0].1.push(string);
+            } else {
+                let id = table
+                    .id(entry.key)
+                    .ok_or(StringTableError::UnknownKey { line: entry.line })?;
+                if translated.contains(&(index, id)) {
+                    return duplicate;
+                }
+                translated.push((index, id));
+                table.languages[index].1[usize::from(id - 1)] = string;
+            }
+        }
+        Ok(table)
+    }
+
+    /// Adds the string `key` with its `translations`, pairs of a language
+    /// code and the string in that language, and returns its
+    /// [`StringId`]. If `key` already exists, its translations are
+    /// updated.
+    ///
+    /// Languages without a translation of a new key use the first
+    /// translation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `translations` is empty or the table has more than 65535
+    /// strings.
+    pub fn add(&mut self, key: &str, translations: &[(&str, &CStr16)]) -> StringId {
+        let (_, fallback) = translations.first().expect("no translations");
+        let index = match self.keys.iter().position(|k| k == key) {
+            Some(index) => index,
+            None => {
+                self.keys.push(key.into());
+                for (_, strings) in &mut self.languages {
+                    strings.push((*fallback).into());
+                }
+                self.keys.len() - 1
+            }
+        };
+        for (language, string) in translations {
+            let language = self.language_index(language);
+            self.languages[language].1[index] = (*string).into();
+        }
+        StringId::try_from(index + 1).expect("too many strings")
+    }
+
+    /// Returns the index of `language` in `languages`, adding it if it
+    /// doesn't exist yet.
+    fn language_index(&mut self, language: &str) -> usize {
+        let mut code = Vec::with_capacity(language.len() + 1);
+        code.extend_from_slice(language.as_bytes());
+        code.push(0);
+        if let Some(index) = self.languages.iter().position(|(l, _)| *l == code) {
+            return index;
+        }
+        // Strings that are not translated fall back to the first language.
+        let strings = match self.languages.first() {
+            Some((_, strings)) => strings.clone(),
+            None => alloc::vec![CString16::new(); self.keys.len()],
+        };
+        self.languages.push((code, strings));
+        self.languages.len() - 1
+    }
+
+    /// Registers the strings in a new package list with `guid`, which
+    /// contains one string package per language. The strings can then be
+    /// retrieved with the returned handle and the IDs of [`Self::id`].
+    ///
+    /// # Errors
+    ///
+    /// See [`PackageListBuilder::register`].
+    pub fn register(&self, guid: Guid) -> Result<HiiHandle> {
+        PackageListBuilder::new(guid)
+            .string_table(self)
+            .register(None)
+    }
+
+    /// Returns the [`StringId`] of `key`.
+    #[must_use]
+    pub fn id(&self, key: &str) -> Option<StringId> {
+        let index = self.keys.iter().position(|k| k == key)?;
+        StringId::try_from(index + 1).ok()
+    }
+
+    /// Returns the string `key` in `language`.
+    #[must_use]
+    pub fn get(&self, language: &str, key: &str) -> Option<&CStr16> {
+        let id = self.id(key)?;
+        let (_, strings) = self
+            .languages
+            .iter()
+            .find(|(l, _)| &l[..l.len() - 1] == language.as_bytes())?;
+        strings.get(usize::from(id - 1)).map(|s| &**s)
+    }
+
+    /// Returns an iterator over the language codes, in the order of the
+    /// source.
+    pub fn languages(&self) -> impl Iterator<Item = &str> {
+        self.languages
+            .iter()
+            // OK to unwrap: the language was copied from a `&str`.
+            .map(|(l, _)| core::str::from_utf8(&l[..l.len() - 1]).unwrap())
+    }
+}
+
+/// Error returned by [`StringTable::parse`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StringTableError {
+    /// The source is malformed.
+    Parse(ParseError),
+    /// A string is defined outside of a language section.
+    MissingLanguage {
+        /// One-based line number of the string.
+        line: usize,
+    },
+    /// A key is not defined in the first language section.
+    UnknownKey {
+        /// One-based line number of the string.
+        line: usize,
+    },
+    /// A string contains a character that can't be encoded as UCS-2.
+    InvalidChar {
+        /// One-based line number of the string.
+        line: usize,
+    },
+}
+
+impl Display for StringTableError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(err) => write!(f, "{err}"),
+            Self::MissingLanguage { line } => {
+                write!(f, "line {line}: string outside of a language section")
+            }
+            Self::UnknownKey { line } => {
+                write!(f, "line {line}: key not defined for the first language")
+            }
+            Self::InvalidChar { line } => write!(f, "line {line}: invalid UCS-2 character"),
+        }
+    }
+}
+
+impl core::error::Error for StringTableError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cstr8, cstr16, guid};
+
+    #[test]
+    fn test_empty_package_list() {
+        let guid = guid!("3ab3bb4a-f3a2-4ba4-a431-3fc9dc69e9ff");
+        let package_list = PackageListBuilder::new(guid).build();
+        assert_eq!(package_list.len(), 24);
+        assert_eq!(&package_list[..16], &guid.to_bytes());
+        assert_eq!(&package_list[16..20], &24u32.to_le_bytes());
+        // End package: length 4, type 0xdf.
+        assert_eq!(&package_list[20..], &[4, 0, 0, 0xdf]);
+    }
+
+    #[test]
+    fn test_string_package() {
+        let package_list = PackageListBuilder::new(Guid::ZERO)
+            .strings(cstr8!("en"), &[cstr16!("Hi"), cstr16!("")])
+            .build();
+
+        let package = &package_list[20..];
+        let header_size = 4 + 4 + 4 + 32 + 2 + 3;
+        let package_len = header_size + (1 + 6) + (1 + 2) + 1;
+        assert_eq!(
+            &package[..4],
+            &(package_len as u32 | 0x0400_0000).to_le_bytes()
+        );
+        assert_eq!(&package[4..8], &(header_size as u32).to_le_bytes());
+        assert_eq!(&package[8..12], &(header_size as u32).to_le_bytes());
+        assert_eq!(&package[46..header_size], b"en\0");
+        assert_eq!(
+            &package[header_size..package_len],
+            &[0x14, b'H', 0, b'i', 0, 0, 0, 0x14, 0, 0, 0]
+        );
+        assert_eq!(&package[package_len..], &[4, 0, 0, 0xdf]);
+        assert_eq!(package_list.len(), 20 + package_len + 4);
+    }
+
+    #[test]
+    fn test_guid_package() {
+        let guid = guid!("3ab3bb4a-f3a2-4ba4-a431-3fc9dc69e9ff");
+        let package_list = PackageListBuilder::new(Guid::ZERO)
+            .guid_package(guid, &[1, 2])
+            .build();
+        let package = &package_list[20..];
+        assert_eq!(&package[..4], &[22, 0, 0, 0x01]);
+        assert_eq!(&package[4..20], &guid.to_bytes());
+        assert_eq!(&package[20..22], &[1, 2]);
+    }
+
+    #[test]
+    fn test_parse_packages() {
+        let guid = guid!("3ab3bb4a-f3a2-4ba4-a431-3fc9dc69e9ff");
+        let first = PackageListBuilder::new(guid)
+            .strings(cstr8!("en-US"), &[cstr16!("Hi")])
+            .forms(&[0x0e, 0x82])
+            .guid_package(guid, &[1, 2])
+            .package(HiiPackageType(0xe0), &[3])
+            .build();
+        let second = PackageListBuilder::new(Guid::ZERO).build();
+        let mut dump = first.clone();
+        dump.extend(&second);
+
+        let lists = PackageLists::new(&dump)
+            .collect::<core::result::Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(lists.len(), 2);
+        assert_eq!(lists[0].guid(), guid);
+        assert_eq!(lists[0].bytes(), first);
+        assert_eq!(lists[1].offset(), first.len());
+        assert_eq!(lists[1].packages().count(), 0);
+
+        let packages = lists[0]
+            .packages()
+            .collect::<core::result::Result<Vec<_>, _>>()
+            .unwrap();
+        let Package::Strings(strings) = packages[0] else {
+            panic!("not a string package: {:?}", packages[0]);
+        };
+        assert_eq!(strings.language(), cstr8!("en-US"));
+        assert_eq!(strings.language_name(), 0);
+        assert_eq!(strings.string(1), Some(cstr16!("Hi").into()));
+        assert_eq!(packages[1], Package::Forms(&[0x0e, 0x82]));
+        assert_eq!(
+            packages[2],
+            Package::Guid {
+                guid,
+                data: &[1, 2]
+            }
+        );
+        assert_eq!(packages[3].package_type(), HiiPackageType(0xe0));
+        assert_eq!(packages.len(), 4);
+    }
+
+    #[test]
+    fn test_parse_packages_errors() {
+        let list = PackageListBuilder::new(Guid::ZERO).forms(&[0; 4]).build();
+        assert_eq!(
+            PackageList::parse(&list[..list.len() - 1]),
+            Err(PackageError::Truncated { offset: 0 })
+        );
+
+        // Offsets whose end overflows.
+        assert_eq!(
+            PackageList::parse_at(&list, usize::MAX - 4),
+            Err(PackageError::Truncated {
+                offset: usize::MAX - 4
+            })
+        );
+
+        // Without the end package.
+        let mut list = list[..list.len() - 4].to_vec();
+        let len = list.len() as u32;
+        list[16..20].copy_from_slice(&len.to_le_bytes());
+        let list = PackageList::parse(&list).unwrap();
+        let mut packages = list.packages();
+        assert!(matches!(packages.next(), Some(Ok(Package::Forms(_)))));
+        assert_eq!(
+            packages.next(),
+            Some(Err(PackageError::MissingEnd { offset: 0 }))
+        );
+        assert_eq!(packages.next(), None);
+
+        // Package extends past the end of the list.
+        let mut list = PackageListBuilder::new(Guid::ZERO).build();
+        list[20] = 8;
+        let list = PackageList::parse(&list).unwrap();
+        assert_eq!(
+            list.packages().next(),
+            Some(Err(PackageError::Truncated { offset: 20 }))
+        );
+
+        // Device path package with a node of length 0.
+        let list = PackageListBuilder::new(Guid::ZERO)
+            .package(HiiPackageType::DEVICE_PATH, &[4, 0x15, 0, 0])
+            .build();
+        assert_eq!(list.len(), 32);
+        let list = PackageList::parse(&list).unwrap();
+        assert_eq!(
+            list.packages().next(),
+            Some(Err(PackageError::Truncated { offset: 20 }))
+        );
+
+        // String package without language.
+        let list = PackageListBuilder::new(Guid::ZERO)
+            .package(HiiPackageType::STRINGS, &[0; 8])
+            .build();
+        let list = PackageList::parse(&list).unwrap();
+        assert_eq!(
+            list.packages().next(),
+            Some(Err(PackageError::Truncated { offset: 20 }))
+        );
+    }
+
+    #[test]
+    fn test_string_blocks() {
+        let mut data = Vec::new();
+        data.extend_from_slice(&49u32.to_le_bytes());
+        data.extend_from_slice(&49u32.to_le_bytes());
+        data.extend_from_slice(&[0; 32]);
+        data.extend_from_slice(&3u16.to_le_bytes());
+        data.extend_from_slice(b"en\0");
+        // Two UCS-2 strings.
+        data.extend_from_slice(&[0x16, 2, 0, b'A', 0, 0, 0, b'B', 0, 0, 0]);
+        // Skip two IDs, and duplicate the first string.
+        data.extend_from_slice(&[0x22, 2, 0x20, 1, 0]);
+        // Font declaration.
+        data.extend_from_slice(&[0x31, 0x40, 7, 0, 1, 2, 3]);
+        // SCSU strings with a font, and with a tag.
+        data.extend_from_slice(&[0x11, 1, b'C', b'a', b'f', 0xe9, 0]);
+        data.extend_from_slice(&[0x10, 0x01, b'x', 0]);
+        data.push(0);
+        let list = PackageListBuilder::new(Guid::ZERO)
+            .package(HiiPackageType::STRINGS, &data)
+            .build();
+        let list = PackageList::parse(&list).unwrap();
+        let Some(Ok(Package::Strings(package))) = list.packages().next() else {
+            panic!("no string package");
+        };
+        assert_eq!(package.language(), cstr8!("en"));
+        assert_eq!(package.language_name(), 3);
+
+        let blocks = package
+            .blocks()
+            .collect::<core::result::Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            blocks,
+            [
+                StringBlock::String {
+                    id: 1,
+                    font: None,
+                    text: StringText::Ucs2(&[b'A', 0])
+                },
+                StringBlock::String {
+                    id: 2,
+                    font: None,
+                    text: StringText::Ucs2(&[b'B', 0])
+                },
+                StringBlock::Skip { first: 3, count: 2 },
+                StringBlock::Duplicate { id: 5, of: 1 },
+                StringBlock::Ext {
+                    block_type: StringBlockType::FONT,
+                    data: &[1, 2, 3]
+                },
+                StringBlock::String {
+                    id: 6,
+                    font: Some(1),
+                    text: StringText::Scsu(b"Caf\xe9")
+                },
+                StringBlock::String {
+                    id: 7,
+                    font: None,
+                    text: StringText::Scsu(&[0x01, b'x'])
+                },
+            ]
+        );
+
+        assert_eq!(package.string(2), Some(cstr16!("B").into()));
+        assert_eq!(package.string(3), None);
+        assert_eq!(package.string(5), Some(cstr16!("A").into()));
+        assert_eq!(package.string(6), Some(cstr16!("Café").into()));
+        assert_eq!(package.string(7), None);
+        let strings = package.strings().unwrap();
+        assert_eq!(strings.keys().copied().collect::<Vec<_>>(), [1, 2, 5, 6]);
+    }
+
+    #[test]
+    fn test_string_duplicate_chain() {
+        let mut data = Vec::new();
+        data.extend_from_slice(&47u32.to_le_bytes());
+        data.extend_from_slice(&47u32.to_le_bytes());
+        data.extend_from_slice(&[0; 34]);
+        data.push(0);
+        data.extend_from_slice(&[0x14, b'A', 0, 0, 0]);
+        // Each string duplicates the previous one.
+        for of in 1..=2000u16 {
+            data.push(0x20);
+            data.extend_from_slice(&of.to_le_bytes());
+        }
+        data.push(0);
+        let list = PackageListBuilder::new(Guid::ZERO)
+            .package(HiiPackageType::STRINGS, &data)
+            .build();
+        let list = PackageList::parse(&list).unwrap();
+        let Some(Ok(Package::Strings(package))) = list.packages().next() else {
+            panic!("no string package");
+        };
+        assert_eq!(package.string(2001), Some(cstr16!("A").into()));
+        assert_eq!(package.strings().unwrap().len(), 2001);
+    }
+
+    #[test]
+    fn test_string_blocks_errors() {
+        let package = |blocks: &[u8]| {
+            let mut data = Vec::new();
+            data.extend_from_slice(&47u32.to_le_bytes());
+            data.extend_from_slice(&47u32.to_le_bytes());
+            data.extend_from_slice(&[0; 34]);
+            data.push(0);
+            data.extend_from_slice(blocks);
+            let mut package = 0u32.to_le_bytes().to_vec();
+            package.extend_from_slice(&data);
+            package
+        };
+
+        let bytes = package(&[0x14, b'A', 0, 0]);
+        let strings = StringPackage::parse(&bytes).unwrap();
+        assert_eq!(
+            strings.blocks().collect::<Vec<_>>(),
+            [Err(StringBlockError::Truncated { offset: 47 })]
+        );
+
+        let bytes = package(&[0x14, b'A', 0, 0, 0, 0x50, 0]);
+        let strings = StringPackage::parse(&bytes).unwrap();
+        assert_eq!(strings.string(1), Some(cstr16!("A").into()));
+        assert_eq!(
+            strings.strings(),
+            Err(StringBlockError::UnknownBlock {
+                block_type: StringBlockType(0x50),
+                offset: 52
+            })
+        );
+    }
+
+    #[test]
+    fn test_string_table() {
+        let table = StringTable::parse(
+            "[en-US]\nTITLE = Settings\nENABLE = Enable\n\n[de-DE]\nENABLE = Aktivieren\n",
+        )
+        .unwrap();
+        assert_eq!(table.id("TITLE"), Some(1));
+        assert_eq!(table.id("ENABLE"), Some(2));
+        assert_eq!(table.id("MISSING"), None);
+        assert_eq!(table.languages().collect::<Vec<_>>(), ["en-US", "de-DE"]);
+        assert_eq!(table.get("de-DE", "ENABLE"), Some(cstr16!("Aktivieren")));
+        // Falls back to the first language.
+        assert_eq!(table.get("de-DE", "TITLE"), Some(cstr16!("Settings")));
+
+        // Same package list as when adding the strings individually.
+        assert_eq!(
+            PackageListBuilder::new(Guid::ZERO)
+                .string_table(&table)
+                .build(),
+            PackageListBuilder::new(Guid::ZERO)
+                .strings(cstr8!("en-US"), &[cstr16!("Settings"), cstr16!("Enable")])
+                .strings(
+                    cstr8!("de-DE"),
+                    &[cstr16!("Settings"), cstr16!("Aktivieren")]
+                )
+                .build()
+        );
+    }
+
+    #[test]
+    fn test_string_table_add() {
+        let mut table = StringTable::default();
+        assert_eq!(table.add("TITLE", &[("en-US", cstr16!("Settings"))]), 1);
+        assert_eq!(
+            table.add(
+                "ENABLE",
+                &[
+                    ("en-US", cstr16!("Enable")),
+                    ("de-DE", cstr16!("Aktivieren"))
+                ]
+            ),
+            2
+        );
+        // A new language falls back to the first one.
+        assert_eq!(table.get("de-DE", "TITLE"), Some(cstr16!("Settings")));
+        // Updating a key keeps its ID.
+        assert_eq!(
+            table.add("TITLE", &[("de-DE", cstr16!("Einstellungen"))]),
+            1
+        );
+        assert_eq!(table.get("de-DE", "TITLE"), Some(cstr16!("Einstellungen")));
+        assert_eq!(table.get("en-US", "TITLE"), Some(cstr16!("Settings")));
+
+        assert_eq!(
+            table,
+            StringTable::parse(
+                "[en-US]\nTITLE = Settings\nENABLE = Enable\n\
+                 [de-DE]\nTITLE = Einstellungen\nENABLE = Aktivieren\n"
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_string_table_errors() {
+        assert_eq!(
+            StringTable::parse("KEY = value\n"),
+            Err(StringTableError::MissingLanguage { line: 1 })
+        );
+        assert_eq!(
+            StringTable::parse("[en]\nA = a\n[de]\nB = b\n"),
+            Err(StringTableError::UnknownKey { line: 4 })
+        );
+        assert_eq!(
+            StringTable::parse("[en]\nA = a\n[de]\nA = b\nA = c\n"),
+            Err(StringTableError::Parse(ParseError {
+                line: 5,
+                kind: ParseErrorKind::DuplicateKey
+            }))
+        );
+        assert_eq!(
+            StringTable::parse("[en]\nA = 😀\n"),
+            Err(StringTableError::InvalidChar { line: 2 })
+        );
+    }
+}
@@ -0,0 +1,146 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! HII Popup protocol.
+
+use uefi_macros::unsafe_protocol;
+use uefi_raw::protocol::hii::popup::HiiPopupProtocol;
+
+use super::database::HiiDatabase;
+use super::package::PackageListBuilder;
+use super::{HiiHandle, StringId};
+use crate::{CStr16, Guid, Result, StatusExt, boot, cstr8, guid};
+
+pub use uefi_raw::protocol::hii::popup::{HiiPopupSelection, HiiPopupStyle, HiiPopupType};
+
+/// GUID of the package lists registered for [`PopupMessage::Text`].
+const TEXT_PACKAGE_LIST_GUID: Guid = guid!("8f6f3a2c-64e5-4f27-9b0e-5d0c6a7e14b3");
+
+/// The message shown by [`HiiPopup::show_popup`].
+#[derive(Clone, Copy, Debug)]
+pub enum PopupMessage<'a> {
+    /// A string of a package list in the HII database.
+    Id {
+        /// The package list containing the string.
+        package_list: HiiHandle,
+        /// The ID of the string.
+        id: StringId,
+    },
+    /// A string that is added to the HII database while the popup is shown.
+    Text(&'a CStr16),
+}
+
+impl<'a> From<&'a CStr16> for PopupMessage<'a> {
+    fn from(text: &'a CStr16) -> Self {
+        Self::Text(text)
+    }
+}
+
+/// The HII Popup Protocol.
+///
+/// # UEFI Spec Description
+///
+/// This protocol provides services to display a popup window. The protocol
+/// is typically produced by the forms browser and consumed by a driver
+/// callback handler.
+///
+/// Applications can use it for simple dialogs, like confirming an action:
+///
+/// ```no_run
+/// use uefi::boot;
+/// use uefi::cstr16;
+/// use uefi::proto::hii::popup::{HiiPopup, HiiPopupSelection, HiiPopupStyle, HiiPopupType};
+///
+/// # fn main() -> uefi::Result {
+/// let popup = boot::open_protocol_exclusive::<HiiPopup>(
+///     boot::get_handle_for_protocol::<HiiPopup>()?,
+/// )?;
+/// let selection = popup.show_popup(
+///     HiiPopupStyle::WARNING,
+///     HiiPopupType::YES_NO,
+///     cstr16!("Reset all settings to defaults?").into(),
+/// )?;
+/// if selection == HiiPopupSelection::YES {
+///     // ...
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+#[repr(transparent)]
+#[unsafe_protocol(HiiPopupProtocol::GUID)]
+pub struct HiiPopup(HiiPopupProtocol);
+
+impl HiiPopup {
+    /// Returns the revision of the protocol.
+    #[must_use]
+    pub const fn revision(&self) -> u64 {
+        self.0.revision
+    }
+
+    /// Shows a popup with `message` in the given style, and waits until the
+    /// user selects one of the choices of `popup_type`.
+    ///
+    /// A [`PopupMessage::Text`] is added to the HII database in a temporary
+    /// package list, which is removed again before returning.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::INVALID_PARAMETER`]: `popup_style` or `popup_type` is
+    ///   invalid, or the message was not found.
+    /// * [`Status::NOT_FOUND`]: the HII Database protocol is not available,
+    ///   for a [`PopupMessage::Text`].
+    /// * See [`HiiDatabase::new_package_list`] and
+    ///   [`HiiDatabase::remove_package_list`] for errors of a
+    ///   [`PopupMessage::Text`].
+    ///
+    /// [`Status::INVALID_PARAMETER`]: crate::Status::INVALID_PARAMETER
+    /// [`Status::NOT_FOUND`]: crate::Status::NOT_FOUND
+    pub fn show_popup(
+        &self,
+        popup_style: HiiPopupStyle,
+        popup_type: HiiPopupType,
+        message: PopupMessage<'_>,
+    ) -> Result<HiiPopupSelection> {
+        match message {
+            PopupMessage::Id { package_list, id } => {
+                self.create_popup(popup_style, popup_type, package_list, id)
+            }
+            PopupMessage::Text(text) => {
+                let db =
+                    boot::open_protocol_exclusive::<HiiDatabase>(boot::get_handle_for_protocol::<
+                        HiiDatabase,
+                    >()?)?;
+                let package_list = PackageListBuilder::new(TEXT_PACKAGE_LIST_GUID)
+                    .strings(cstr8!("en-US"), &[text])
+                    .build();
+                let package_list = db.new_package_list(&package_list, None)?;
+                let selection = self.create_popup(popup_style, popup_type, package_list, 1);
+                let removed = db.remove_package_list(package_list);
+                let selection = selection?;
+                removed?;
+                Ok(selection)
+            }
+        }
+    }
+
+    fn create_popup(
+        &self,
+        popup_style: HiiPopupStyle,
+        popup_type: HiiPopupType,
+        package_list: HiiHandle,
+        message: StringId,
+    ) -> Result<HiiPopupSelection> {
+        let mut selection = HiiPopupSelection::OK;
+        unsafe {
+            (self.0.create_popup)(
+                &self.0,
+                popup_style,
+                popup_type,
+                package_list.as_ptr(),
+                message,
+                &mut selection,
+            )
+        }
+        .to_result_with_val(|| selection)
+    }
+}
@@ -0,0 +1,1129 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Analysis of the setup questions in an HII database.
+//!
+//! Firmware settings are described by IFR forms: each question of a form
+//! has a prompt, a range of values or a list of options, defaults, and a
+//! location in a varstore, usually a UEFI variable. [`Settings::parse`]
+//! collects this information from a dump of the HII database, such as the
+//! result of [`HiiDatabase::export_all_raw`], with the strings resolved in
+//! one language. Knowing the variable, offset and size of a question is
+//! enough to read or change the setting without the setup browser.
+//! [`Settings::settings`] joins each stored question with its varstore.
+//!
+//! ```no_run
+//! use uefi::proto::hii::database::HiiDatabase;
+//! use uefi::proto::hii::settings::Settings;
+//! use uefi::{boot, println};
+//!
+//! # fn example() -> uefi::Result {
+//! let handle = boot::get_handle_for_protocol::<HiiDatabase>()?;
+//! let db = boot::open_protocol_exclusive::<HiiDatabase>(handle)?;
+//! let dump = db.export_all_raw()?;
+//!
+//! let settings = Settings::parse(&dump, "en-US").expect("invalid HII database");
+//! for setting in settings.find("Boot Mode") {
+//!     println!(
+//!         "{}: offset {:#x}, {} bytes",
+//!         setting.varstore.name, setting.question.offset, setting.question.size
+//!     );
+//! }
+//! // List all settings, similar to IFRExtractor.
+//! println!("{settings}");
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`HiiDatabase::export_all_raw`]: super::database::HiiDatabase::export_all_raw
+
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+use uefi_raw::protocol::hii::ifr::{
+    IfrCheckboxFlags, IfrDefaultStore, IfrNumericFlags, IfrOneOfOptionFlags, IfrOpCode,
+    IfrQuestionFlags, IfrType,
+};
+use uefi_raw::protocol::hii::{FormId, QuestionId, StringId, VarstoreId};
+
+use super::expression::{self, EvalError, IfrValue, QuestionValues};
+use super::ifr::{IfrError, IfrOps};
+use super::package::{
+    Package, PackageError, PackageList, PackageLists, StringBlock, StringPackage, guid_at, u16_at,
+    u32_at,
+};
+use crate::Guid;
+use crate::runtime::VariableAttributes;
+
+/// The formsets of an HII database.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Settings {
+    /// Formsets of all package lists, in the order of the dump.
+    pub formsets: Vec<FormSetInfo>,
+}
+
+impl Settings {
+    /// Parses `dump`, a sequence of HII package lists, and resolves strings
+    /// in `language`, an RFC 4646 language code such as `en-US`. Package
+    /// lists without strings in `language` use their first string package.
+    ///
+    /// # Errors
+    ///
+    /// See [`SettingsError`].
+    pub fn parse(dump: &[u8], language: &str) -> Result<Self, SettingsError> {
+        let mut settings = Self::default();
+        for list in PackageLists::new(dump) {
+            parse_package_list(dump, &list?, language, &mut settings.formsets)?;
+        }
+        Ok(settings)
+    }
+
+    /// Returns all questions with their formset.
+    pub fn questions(&self) -> impl Iterator<Item = (&FormSetInfo, &QuestionInfo)> {
+        self.formsets.iter().flat_map(|formset| {
+            formset
+                .forms
+                .iter()
+                .flat_map(|form| form.questions.iter())
+                .map(move |question| (formset, question))
+        })
+    }
+
+    /// Returns the questions stored in a varstore, i.e. the settings that
+    /// can be changed by writing the varstore.
+    pub fn settings(&self) -> impl Iterator<Item = Setting<'_>> {
+        self.formsets.iter().flat_map(|formset| {
+            formset.forms.iter().flat_map(move |form| {
+                form.questions.iter().filter_map(move |question| {
+                    let varstore = formset.varstore(question.varstore?)?;
+                    Some(Setting {
+                        formset,
+                        form,
+                        varstore,
+                        question,
+                    })
+                })
+            })
+        })
+    }
+
+    /// Returns the settings whose prompt is `prompt`. Several questions may
+    /// share a prompt, e.g. the same option of different devices.
+    pub fn find<'a>(&'a self, prompt: &'a str) -> impl Iterator<Item = Setting<'a>> {
+        self.settings()
+            .filter(move |setting| setting.question.prompt.as_deref() == Some(prompt))
+    }
+}
+
+impl Display for Settings {
+    /// Lists the settings of each formset, similar to the output of
+    /// IFRExtractor.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for formset in &self.formsets {
+            writeln!(
+                f,
+                "Formset \"{}\" {{{}}}",
+                formset.title.as_deref().unwrap_or_default(),
+                formset.guid
+            )?;
+            for setting in self
+                .settings()
+                .filter(|setting| core::ptr::eq(setting.formset, formset))
+            {
+                writeln!(f, "  {setting}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An IFR formset.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FormSetInfo {
+    /// GUID of the package list containing the formset.
+    pub package_list_guid: Guid,
+    /// GUID of the formset.
+    pub guid: Guid,
+    /// Class GUIDs, e.g. [`IfrFormSet::PLATFORM_SETUP_FORMSET_GUID`].
+    ///
+    /// [`IfrFormSet::PLATFORM_SETUP_FORMSET_GUID`]: uefi_raw::protocol::hii::ifr::IfrFormSet::PLATFORM_SETUP_FORMSET_GUID
+    pub class_guids: Vec<Guid>,
+    /// Title of the formset.
+    pub title: Option<String>,
+    /// Help text of the formset.
+    pub help: Option<String>,
+    /// Default stores with their names. The standard and manufacturing
+    /// stores may be used without being declared.
+    pub default_stores: Vec<(u16, Option<String>)>,
+    /// Varstores declared by the formset.
+    pub varstores: Vec<VarstoreInfo>,
+    /// Forms of the formset.
+    pub forms: Vec<FormInfo>,
+}
+
+impl FormSetInfo {
+    /// Returns the varstore with ID `id`.
+    #[must_use]
+    pub fn varstore(&self, id: VarstoreId) -> Option<&VarstoreInfo> {
+        self.varstores.iter().find(|varstore| varstore.id == id)
+    }
+}
+
+/// Kind of a [`VarstoreInfo`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum VarstoreKind {
+    /// A buffer accessed through the Config Access protocol of the driver.
+    Buffer,
+    /// A UEFI variable.
+    Efi {
+        /// Attributes of the variable.
+        attributes: VariableAttributes,
+    },
+    /// Name/value pairs accessed through the Config Access protocol. Each
+    /// question is stored in a separate value, named by the string in
+    /// [`QuestionInfo::offset`].
+    NameValue,
+}
+
+/// A varstore declared by a formset.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VarstoreInfo {
+    /// ID of the varstore in the formset.
+    pub id: VarstoreId,
+    /// Kind of the varstore.
+    pub kind: VarstoreKind,
+    /// Vendor GUID of the varstore.
+    pub guid: Guid,
+    /// Name of the varstore, empty for name/value varstores.
+    pub name: String,
+    /// Size of the varstore in bytes, zero for name/value varstores.
+    pub size: u16,
+}
+
+/// A form of a formset.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FormInfo {
+    /// ID of the form in the formset.
+    pub id: FormId,
+    /// Title of the form.
+    pub title: Option<String>,
+    /// Questions of the form, in the order they appear.
+    pub questions: Vec<QuestionInfo>,
+}
+
+/// Kind of a [`QuestionInfo`], given by its opcode.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum QuestionKind {
+    /// A boolean stored in one byte.
+    Checkbox,
+    /// An integer with a range.
+    Numeric,
+    /// An integer with a list of options.
+    OneOf,
+    /// A UCS-2 string.
+    String,
+    /// A password, stored as UCS-2 string.
+    Password,
+    /// An array of values of the options.
+    OrderedList,
+    /// A date: year (`u16`), month and day (`u8`).
+    Date,
+    /// A time: hour, minute and second (`u8`).
+    Time,
+    /// A button triggering a callback.
+    Action,
+    /// A link to another form.
+    Ref,
+}
+
+/// Kind of a [`Condition`], given by its opcode.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ConditionKind {
+    /// `suppressif`: the question is hidden.
+    SuppressIf,
+    /// `grayoutif`: the question is shown but can't be changed.
+    GrayOutIf,
+    /// `disableif`: the question is ignored, as if it wasn't in the form.
+    DisableIf,
+}
+
+/// A condition scope enclosing a question.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Condition {
+    /// Kind of the condition.
+    pub kind: ConditionKind,
+    /// The IFR expression of the condition, which can be evaluated with
+    /// [`expression::evaluate`].
+    pub expression: Vec<u8>,
+}
+
+/// Range of a numeric question.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct NumericRange {
+    /// Minimum value.
+    pub min: u64,
+    /// Maximum value.
+    pub max: u64,
+    /// Step between values, or zero if any value is allowed.
+    pub step: u64,
+}
+
+/// An option of a one-of or ordered list question.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OptionInfo {
+    /// Value stored for the option.
+    pub value: u64,
+    /// Text of the option.
+    pub text: Option<String>,
+    /// Flags of the option, marking the standard and manufacturing
+    /// defaults.
+    pub flags: IfrOneOfOptionFlags,
+}
+
+/// A question of a form.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct QuestionInfo {
+    /// ID of the question in the formset.
+    pub id: QuestionId,
+    /// Kind of the question.
+    pub kind: QuestionKind,
+    /// Prompt of the question.
+    pub prompt: Option<String>,
+    /// Help text of the question.
+    pub help: Option<String>,
+    /// Flags of the question.
+    pub flags: IfrQuestionFlags,
+    /// Varstore of the question, or `None` if the question is not stored.
+    pub varstore: Option<VarstoreId>,
+    /// Byte offset of the question in its varstore. For name/value
+    /// varstores, this is the string ID of the name of the value instead.
+    pub offset: u16,
+    /// Size of the value of the question in bytes.
+    pub size: u16,
+    /// Range of numeric and one-of questions.
+    pub range: Option<NumericRange>,
+    /// Options of one-of and ordered list questions.
+    pub options: Vec<OptionInfo>,
+    /// Default values of the question, as pairs of a default store ID and
+    /// a value.
+    pub defaults: Vec<(u16, u64)>,
+    /// Form a ref question links to, if it is in the same formset.
+    pub target_form: Option<FormId>,
+    /// Conditions of the scopes enclosing the question, outermost first.
+    pub conditions: Vec<Condition>,
+}
+
+impl QuestionInfo {
+    /// Returns the default value in the default store `default_id`, such as
+    /// [`IfrDefaultStore::STANDARD`].
+    ///
+    /// Defaults given with a default opcode take precedence over the
+    /// default flags of options and checkboxes.
+    #[must_use]
+    pub fn default_value(&self, default_id: u16) -> Option<u64> {
+        self.defaults
+            .iter()
+            .find(|(id, _)| *id == default_id)
+            .map(|(_, value)| *value)
+    }
+
+    /// Returns the option with `value`.
+    #[must_use]
+    pub fn option(&self, value: u64) -> Option<&OptionInfo> {
+        self.options.iter().find(|option| option.value == value)
+    }
+
+    /// Returns whether the question is hidden for the question values
+    /// `values`, i.e. a `suppressif` or `disableif` condition evaluates to
+    /// true. Conditions evaluating to [`IfrValue::Undefined`] don't apply.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a condition is malformed, see [`EvalError`].
+    pub fn is_suppressed(&self, values: &dyn QuestionValues) -> Result<bool, EvalError> {
+        self.any_condition(values, |kind| kind != ConditionKind::GrayOutIf)
+    }
+
+    /// Returns whether the question is read-only for the question values
+    /// `values`, i.e. a `grayoutif` condition evaluates to true.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a condition is malformed, see [`EvalError`].
+    pub fn is_grayed_out(&self, values: &dyn QuestionValues) -> Result<bool, EvalError> {
+        self.any_condition(values, |kind| kind == ConditionKind::GrayOutIf)
+    }
+
+    fn any_condition(
+        &self,
+        values: &dyn QuestionValues,
+        f: impl Fn(ConditionKind) -> bool,
+    ) -> Result<bool, EvalError> {
+        for condition in self.conditions.iter().filter(|c| f(c.kind)) {
+            if expression::evaluate(&condition.expression, values)? == IfrValue::Bool(true) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// A question stored in a varstore, returned by [`Settings::settings`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Setting<'a> {
+    /// Formset of the question.
+    pub formset: &'a FormSetInfo,
+    /// Form of the question.
+    pub form: &'a FormInfo,
+    /// Varstore of the question.
+    pub varstore: &'a VarstoreInfo,
+    /// The question.
+    pub question: &'a QuestionInfo,
+}
+
+impl Display for Setting<'_> {
+    /// Formats the location, kind, prompt, range and standard default of
+    /// the setting on one line, followed by its options with one line each.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let (varstore, question) = (self.varstore, self.question);
+        if varstore.kind == VarstoreKind::NameValue {
+            write!(f, "{{{}}} name {:#x}", varstore.guid, question.offset)?;
+        } else {
+            write!(
+                f,
+                "{} {{{}}} offset {:#x}",
+                varstore.name, varstore.guid, question.offset
+            )?;
+        }
+        write!(
+            f,
+            ", {} byte(s): {:?} \"{}\"",
+            question.size,
+            question.kind,
+            question.prompt.as_deref().unwrap_or_default()
+        )?;
+        if let Some(range) = question.range {
+            write!(
+                f,
+                ", min {:#x}, max {:#x}, step {:#x}",
+                range.min, range.max, range.step
+            )?;
+        }
+        if let Some(default) = question.default_value(IfrDefaultStore::STANDARD) {
+            write!(f, ", default {default:#x}")?;
+        }
+        for option in &question.options {
+            write!(
+                f,
+                "\n    {:#x} \"{}\"",
+                option.value,
+                option.text.as_deref().unwrap_or_default()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Error returned by [`Settings::parse`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SettingsError {
+    /// A package list, package, or opcode extends past the end of its
+    /// container, or has an invalid length.
+    Truncated {
+        /// Byte offset in the dump.
+        offset: usize,
+    },
+    /// An end opcode without matching scope, or a scope that isn't closed
+    /// at the end of a form package.
+    UnbalancedScope {
+        /// Byte offset in the dump.
+        offset: usize,
+    },
+}
+
+impl Display for SettingsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Truncated { offset } => write!(f, "truncated data at offset {offset}"),
+            Self::UnbalancedScope { offset } => {
+                write!(f, "unbalanced IFR scope at offset {offset}")
+            }
+        }
+    }
+}
+
+impl core::error::Error for SettingsError {}
+
+impl From<PackageError> for SettingsError {
+    fn from(err: PackageError) -> Self {
+        match err {
+            PackageError::Truncated { offset } | PackageError::MissingEnd { offset } => {
+                Self::Truncated { offset }
+            }
+        }
+    }
+}
+
+/// Reads a little-endian integer of `size` bytes, which must be in bounds.
+fn uint_at(data: &[u8], offset: usize, size: usize) -> u64 {
+    let mut buf = [0; 8];
+    buf[..size].copy_from_slice(&data[offset..offset + size]);
+    u64::from_le_bytes(buf)
+}
+
+/// Returns the null-terminated ASCII string at `offset`.
+fn ascii_at(data: &[u8], offset: usize) -> String {
+    data.get(offset..)
+        .unwrap_or_default()
+        .iter()
+        .take_while(|&&b| b != 0)
+        .map(|&b| char::from(b))
+        .collect()
+}
+
+/// Size of an IFR value of type `ty`, if it is an integer.
+const fn type_size(ty: IfrType) -> Option<usize> {
+    match ty {
+        IfrType::NUM_SIZE_8 | IfrType::BOOLEAN => Some(1),
+        IfrType::NUM_SIZE_16 => Some(2),
+        IfrType::NUM_SIZE_32 => Some(4),
+        IfrType::NUM_SIZE_64 => Some(8),
+        _ => None,
+    }
+}
+
+/// Adds the formsets of the form packages of `list`, which was parsed from
+/// `dump`.
+fn parse_package_list(
+    dump: &[u8],
+    list: &PackageList<'_>,
+    language: &str,
+    formsets: &mut Vec<FormSetInfo>,
+) -> Result<(), SettingsError> {
+    let mut string_packages = Vec::new();
+    let mut form_packages = Vec::new();
+    for package in list.packages() {
+        match package? {
+            Package::Strings(strings) => string_packages.push(strings),
+            Package::Forms(ifr) => form_packages.push(ifr),
+            _ => {}
+        }
+    }
+
+    let strings = string_packages
+        .iter()
+        .find(|strings| language_matches(strings, language))
+        .or_else(|| string_packages.first())
+        .map(parse_strings)
+        .unwrap_or_default();
+
+    for ifr in form_packages {
+        // The IFR is a subslice of `dump`; its offset is used for errors.
+        let offset = ifr.as_ptr().addr() - dump.as_ptr().addr();
+        let mut parser = FormParser {
+            strings: &strings,
+            package_list_guid: list.guid(),
+            formsets,
+            question: None,
+            option_size: 1,
+            conditions: Vec::new(),
+        };
+        parser.parse(ifr, offset)?;
+    }
+    Ok(())
+}
+
+/// Returns whether the language of `strings` is `language`, ignoring case.
+fn language_matches(strings: &StringPackage<'_>, language: &str) -> bool {
+    let bytes = strings.language().as_bytes();
+    bytes[..bytes.len() - 1].eq_ignore_ascii_case(language.as_bytes())
+}
+
+/// Decodes the strings of a string package. Decoding stops at the first
+/// malformed or unknown block, keeping the strings before it.
+fn parse_strings(package: &StringPackage<'_>) -> BTreeMap<StringId, String> {
+    let mut strings = BTreeMap::new();
+    for block in package.blocks().map_while(|block| block.ok()) {
+        match block {
+            StringBlock::String { id, text, .. } => {
+                if let Some(text) = text.to_cstring16() {
+                    strings.insert(id, text.to_string());
+                }
+            }
+            StringBlock::Duplicate { id, of } => {
+                if let Some(text) = strings.get(&of).cloned() {
+                    strings.insert(id, text);
+                }
+            }
+            StringBlock::Skip { .. } | StringBlock::Ext { .. } => {}
+        }
+    }
+    strings
+}
+
+/// Returns the kind of a condition opcode.
+const fn condition_kind(op_code: IfrOpCode) -> Option<ConditionKind> {
+    match op_code {
+        IfrOpCode::SUPPRESS_IF => Some(ConditionKind::SuppressIf),
+        IfrOpCode::GRAY_OUT_IF => Some(ConditionKind::GrayOutIf),
+        IfrOpCode::DISABLE_IF => Some(ConditionKind::DisableIf),
+        _ => None,
+    }
+}
+
+/// State of the IFR walk over the form packages of one package list.
+struct FormParser<'a> {
+    strings: &'a BTreeMap<StringId, String>,
+    package_list_guid: Guid,
+    formsets: &'a mut Vec<FormSetInfo>,
+    /// The question whose scope is open, with its scope depth.
+    question: Option<(QuestionInfo, usize)>,
+    /// Size of the option values of the current question.
+    option_size: u16,
+    /// Open condition scopes outside of questions, with their scope depth
+    /// and whether their expression is still being read.
+    conditions: Vec<(Condition, usize, bool)>,
+}
+
+impl FormParser<'_> {
+    fn string(&self, id: StringId) -> Option<String> {
+        self.strings.get(&id).cloned()
+    }
+
+    fn parse(&mut self, ifr: &[u8], base: usize) -> Result<(), SettingsError> {
+        for op in IfrOps::new(ifr) {
+            let op = op.map_err(|err| match err {
+                IfrError::Truncated { offset } => SettingsError::Truncated {
+                    offset: base + offset,
+                },
+                IfrError::UnbalancedScope { offset } => SettingsError::UnbalancedScope {
+                    offset: base + offset,
+                },
+            })?;
+            // Depth of the scope opened by the opcode, or closed by an end.
+            let inner = op.depth() + 1;
+
+            // The expression of a condition is formed by the expression
+            // opcodes at the start of its scope, including the scopes they
+            // open.
+            if let Some((condition, depth, reading @ true)) = self.conditions.last_mut() {
+                let op_code = op.op_code();
+                if op.depth() > *depth
+                    || (op.depth() == *depth
+                        && (op_code == IfrOpCode::END || expression::is_expression_opcode(op_code)))
+                {
+                    condition.expression.extend_from_slice(op.bytes());
+                    continue;
+                }
+                *reading = false;
+            }
+
+            if op.op_code() == IfrOpCode::END {
+                if matches!(self.question, Some((_, d)) if d == inner) {
+                    self.finish_question();
+                } else if matches!(self.conditions.last(), Some((_, d, _)) if *d == inner) {
+                    self.conditions.pop();
+                }
+            } else if let Some(kind) =
+                condition_kind(op.op_code()).filter(|_| op.scope() && self.question.is_none())
+            {
+                let condition = Condition {
+                    kind,
+                    expression: Vec::new(),
+                };
+                self.conditions.push((condition, inner, true));
+            } else {
+                self.opcode(op.op_code(), op.bytes())
+                    .ok_or(SettingsError::Truncated {
+                        offset: base + op.offset(),
+                    })?;
+                if op.scope() {
+                    if let Some((_, d @ 0)) = &mut self.question {
+                        *d = inner;
+                    }
+                } else if matches!(self.question, Some((_, 0))) {
+                    self.finish_question();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the current question to the current form.
+    fn finish_question(&mut self) {
+        let Some((mut question, _)) = self.question.take() else {
+            return;
+        };
+        if question.kind == QuestionKind::OrderedList {
+            // The size was set to the number of containers, each of which
+            // holds the value of an option.
+            question.size *= self.option_size;
+        }
+        if let Some(form) = self
+            .formsets
+            .last_mut()
+            .and_then(|formset| formset.forms.last_mut())
+        {
+            form.questions.push(question);
+        }
+    }
+
+    /// Handles an opcode other than END. Returns `None` if it is too short.
+    fn opcode(&mut self, op_code: IfrOpCode, op: &[u8]) -> Option<()> {
+        match op_code {
+            IfrOpCode::FORM_SET => {
+                op.get(..23)?;
+                let class_count = usize::from(op[22] & 0x3);
+                let class_guids = (0..class_count)
+                    .map(|i| op.get(23 + 16 * i..39 + 16 * i).map(|b| guid_at(b, 0)))
+                    .collect::<Option<_>>()?;
+                self.formsets.push(FormSetInfo {
+                    package_list_guid: self.package_list_guid,
+                    guid: guid_at(op, 2),
+                    class_guids,
+                    title: self.string(u16_at(op, 18)),
+                    help: self.string(u16_at(op, 20)),
+                    default_stores: Vec::new(),
+                    varstores: Vec::new(),
+                    forms: Vec::new(),
+                });
+            }
+            IfrOpCode::FORM | IfrOpCode::FORM_MAP => {
+                op.get(..4)?;
+                // A form map has no title of its own; use the title of its
+                // first method.
+                let title = op.get(4..6).and_then(|b| self.string(u16_at(b, 0)));
+                let form = FormInfo {
+                    id: u16_at(op, 2),
+                    title,
+                    questions: Vec::new(),
+                };
+                self.formsets.last_mut()?.forms.push(form);
+            }
+            IfrOpCode::DEFAULTSTORE => {
+                op.get(..6)?;
+                let store = (u16_at(op, 4), self.string(u16_at(op, 2)));
+                self.formsets.last_mut()?.default_stores.push(store);
+            }
+            IfrOpCode::VARSTORE => {
+                op.get(..22)?;
+                let varstore = VarstoreInfo {
+                    id: u16_at(op, 18),
+                    kind: VarstoreKind::Buffer,
+                    guid: guid_at(op, 2),
+                    name: ascii_at(op, 22),
+                    size: u16_at(op, 20),
+                };
+                self.formsets.last_mut()?.varstores.push(varstore);
+            }
+            IfrOpCode::VARSTORE_EFI => {
+                op.get(..26)?;
+                let varstore = VarstoreInfo {
+                    id: u16_at(op, 2),
+                    kind: VarstoreKind::Efi {
+                        attributes: VariableAttributes::from_bits_retain(u32_at(op, 20)),
+                    },
+                    guid: guid_at(op, 4),
+                    name: ascii_at(op, 26),
+                    size: u16_at(op, 24),
+                };
+                self.formsets.last_mut()?.varstores.push(varstore);
+            }
+            IfrOpCode::VARSTORE_NAME_VALUE => {
+                op.get(..20)?;
+                let varstore = VarstoreInfo {
+                    id: u16_at(op, 2),
+                    kind: VarstoreKind::NameValue,
+                    guid: guid_at(op, 4),
+                    name: String::new(),
+                    size: 0,
+                };
+                self.formsets.last_mut()?.varstores.push(varstore);
+            }
+            IfrOpCode::CHECKBOX => {
+                let mut question = self.question(QuestionKind::Checkbox, op, 1)?;
+                let flags = IfrCheckboxFlags::from_bits_retain(*op.get(13)?);
+                for (flag, store) in [
+                    (IfrCheckboxFlags::DEFAULT, IfrDefaultStore::STANDARD),
+                    (
+                        IfrCheckboxFlags::DEFAULT_MFG,
+                        IfrDefaultStore::MANUFACTURING,
+                    ),
+                ] {
+                    question.defaults.push((store, flags.contains(flag).into()));
+                }
+                self.question = Some((question, 0));
+            }
+            IfrOpCode::NUMERIC | IfrOpCode::ONE_OF => {
+                let flags = *op.get(13)?;
+                let size = 1 << (flags & IfrNumericFlags::SIZE_MASK);
+                let kind = if op_code == IfrOpCode::NUMERIC {
+                    QuestionKind::Numeric
+                } else {
+                    QuestionKind::OneOf
+                };
+                let mut question = self.question(kind, op, size as u16)?;
+                op.get(..14 + 3 * size)?;
+                question.range = Some(NumericRange {
+                    min: uint_at(op, 14, size),
+                    max: uint_at(op, 14 + size, size),
+                    step: uint_at(op, 14 + 2 * size, size),
+                });
+                self.question = Some((question, 0));
+            }
+            IfrOpCode::STRING => {
+                let max_len = *op.get(14)?;
+                let question = self.question(QuestionKind::String, op, u16::from(max_len) * 2)?;
+                self.question = Some((question, 0));
+            }
+            IfrOpCode::PASSWORD => {
+                let max_len = u16_at(op.get(..17)?, 15);
+                let question = self.question(QuestionKind::Password, op, max_len * 2)?;
+                self.question = Some((question, 0));
+            }
+            IfrOpCode::ORDERED_LIST => {
+                let containers = *op.get(13)?;
+                let question = self.question(QuestionKind::OrderedList, op, containers.into())?;
+                self.question = Some((question, 0));
+            }
+            IfrOpCode::DATE => {
+                let question = self.question(QuestionKind::Date, op, 4)?;
+                self.question = Some((question, 0));
+            }
+            IfrOpCode::TIME => {
+                let question = self.question(QuestionKind::Time, op, 3)?;
+                self.question = Some((question, 0));
+            }
+            IfrOpCode::ACTION => {
+                let question = self.question(QuestionKind::Action, op, 0)?;
+                self.question = Some((question, 0));
+            }
+            IfrOpCode::REF => {
+                let mut question = self.question(QuestionKind::Ref, op, 0)?;
+                // Longer variants may link to another formset.
+                if op.len() <= 17 {
+                    question.target_form = Some(u16_at(op.get(..15)?, 13)).filter(|&id| id != 0);
+                }
+                self.question = Some((question, 0));
+            }
+            IfrOpCode::ONE_OF_OPTION => {
+                op.get(..6)?;
+                let flags = IfrOneOfOptionFlags::from_bits_retain(op[4]);
+                let ty = IfrType(op[5]);
+                let text = self.string(u16_at(op, 2));
+                let Some((question, _)) = &mut self.question else {
+                    return Some(());
+                };
+                let Some(size) = type_size(ty) else {
+                    return Some(());
+                };
+                op.get(..6 + size)?;
+                let value = uint_at(op, 6, size);
+                for (flag, store) in [
+                    (IfrOneOfOptionFlags::DEFAULT, IfrDefaultStore::STANDARD),
+                    (
+                        IfrOneOfOptionFlags::DEFAULT_MFG,
+                        IfrDefaultStore::MANUFACTURING,
+                    ),
+                ] {
+                    if flags.contains(flag) && question.default_value(store).is_none() {
+                        question.defaults.push((store, value));
+                    }
+                }
+                self.option_size = size as u16;
+                question.options.push(OptionInfo { value, text, flags });
+            }
+            IfrOpCode::DEFAULT => {
+                op.get(..5)?;
+                let default_id = u16_at(op, 2);
+                let Some((question, _)) = &mut self.question else {
+                    return Some(());
+                };
+                // Defaults of other types, or given by an expression, are
+                // not evaluated.
+                let Some(size) = type_size(IfrType(op[4])) else {
+                    return Some(());
+                };
+                op.get(..5 + size)?;
+                let value = uint_at(op, 5, size);
+                question.defaults.retain(|(id, _)| *id != default_id);
+                question.defaults.push((default_id, value));
+            }
+            _ => {}
+        }
+        Some(())
+    }
+
+    /// Creates a question from the question header of `op`.
+    fn question(&mut self, kind: QuestionKind, op: &[u8], size: u16) -> Option<QuestionInfo> {
+        op.get(..13)?;
+        self.option_size = 1;
+        let varstore = u16_at(op, 8);
+        Some(QuestionInfo {
+            id: u16_at(op, 6),
+            kind,
+            prompt: self.string(u16_at(op, 2)),
+            help: self.string(u16_at(op, 4)),
+            flags: IfrQuestionFlags::from_bits_retain(op[12]),
+            varstore: (varstore != 0).then_some(varstore),
+            offset: u16_at(op, 10),
+            size,
+            range: None,
+            options: Vec::new(),
+            defaults: Vec::new(),
+            target_form: None,
+            conditions: self
+                .conditions
+                .iter()
+                .map(|(condition, _, _)| condition.clone())
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::hii::form::{FormSetBuilder, NumericSize, Question};
+    use crate::proto::hii::package::PackageListBuilder;
+    use crate::{cstr8, cstr16, guid};
+
+    const GUID: Guid = guid!("d6e5b6a9-0a0d-4a4b-9a7f-9d3b6f0c6e61");
+    const LIST_GUID: Guid = guid!("3ab3bb4a-f3a2-4ba4-a431-3fc9dc69e9ff");
+    const ATTRIBUTES: VariableAttributes = VariableAttributes::NON_VOLATILE
+        .union(VariableAttributes::BOOTSERVICE_ACCESS)
+        .union(VariableAttributes::RUNTIME_ACCESS);
+
+    fn dump() -> Vec<u8> {
+        let ifr = FormSetBuilder::new(GUID, 1, 2)
+            .varstore_efi(1, GUID, cstr8!("Setup"), ATTRIBUTES, 4)
+            .form(1, 3, |form| {
+                form.checkbox(Question::new(1, 4, 2).varstore(1, 0), true)
+                    .numeric(
+                        Question::new(2, 5, 2).varstore(1, 2),
+                        NumericSize::U16,
+                        1..=100,
+                        1,
+                        10,
+                    )
+                    .one_of(
+                        Question::new(3, 6, 2).varstore(1, 1),
+                        NumericSize::U8,
+                        &[(7, 0), (8, 1)],
+                        1,
+                    );
+            })
+            .form(2, 2, |form| {
+                form.goto(Question::new(4, 3, 2), 1);
+            })
+            .build();
+        let strings = [
+            cstr16!("Setup"),
+            cstr16!("Help"),
+            cstr16!("Main"),
+            cstr16!("Enable"),
+            cstr16!("Level"),
+            cstr16!("Mode"),
+            cstr16!("Off"),
+            cstr16!("On"),
+        ];
+        let mut dump = PackageListBuilder::new(LIST_GUID)
+            .strings(cstr8!("en-US"), &strings)
+            .strings(cstr8!("de-DE"), &[cstr16!("Einrichtung")])
+            .forms(&ifr)
+            .build();
+        // A second, empty package list.
+        dump.extend(PackageListBuilder::new(GUID).build());
+        dump
+    }
+
+    #[test]
+    fn test_parse() {
+        let settings = Settings::parse(&dump(), "en-us").unwrap();
+        assert_eq!(settings.formsets.len(), 1);
+        let formset = &settings.formsets[0];
+        assert_eq!(formset.package_list_guid, LIST_GUID);
+        assert_eq!(formset.guid, GUID);
+        assert_eq!(formset.title.as_deref(), Some("Setup"));
+        assert_eq!(
+            formset.varstores,
+            [VarstoreInfo {
+                id: 1,
+                kind: VarstoreKind::Efi {
+                    attributes: ATTRIBUTES
+                },
+                guid: GUID,
+                name: "Setup".into(),
+                size: 4,
+            }]
+        );
+        assert_eq!(formset.forms.len(), 2);
+        assert_eq!(formset.forms[0].title.as_deref(), Some("Main"));
+
+        let questions: Vec<_> = settings.questions().map(|(_, q)| q).collect();
+        assert_eq!(questions.len(), 4);
+
+        let checkbox = questions[0];
+        assert_eq!(checkbox.kind, QuestionKind::Checkbox);
+        assert_eq!(checkbox.prompt.as_deref(), Some("Enable"));
+        assert_eq!(
+            (checkbox.varstore, checkbox.offset, checkbox.size),
+            (Some(1), 0, 1)
+        );
+        assert_eq!(checkbox.default_value(IfrDefaultStore::STANDARD), Some(1));
+
+        let numeric = questions[1];
+        assert_eq!(numeric.kind, QuestionKind::Numeric);
+        assert_eq!((numeric.offset, numeric.size), (2, 2));
+        assert_eq!(
+            numeric.range,
+            Some(NumericRange {
+                min: 1,
+                max: 100,
+                step: 1
+            })
+        );
+        assert_eq!(numeric.default_value(IfrDefaultStore::STANDARD), Some(10));
+
+        let one_of = questions[2];
+        assert_eq!(one_of.kind, QuestionKind::OneOf);
+        assert_eq!((one_of.offset, one_of.size), (1, 1));
+        assert_eq!(one_of.options.len(), 2);
+        assert_eq!(one_of.option(1).unwrap().text.as_deref(), Some("On"));
+        assert_eq!(one_of.default_value(IfrDefaultStore::STANDARD), Some(1));
+
+        let goto = questions[3];
+        assert_eq!(goto.kind, QuestionKind::Ref);
+        assert_eq!(goto.prompt.as_deref(), Some("Main"));
+        assert_eq!(goto.target_form, Some(1));
+    }
+
+    #[test]
+    fn test_settings() {
+        let settings = Settings::parse(&dump(), "en-US").unwrap();
+        // The goto question is not stored.
+        let all: Vec<_> = settings.settings().collect();
+        assert_eq!(all.len(), 3);
+        assert!(all.iter().all(|setting| setting.varstore.name == "Setup"));
+        assert!(all.iter().all(|setting| setting.form.id == 1));
+
+        let level = settings.find("Level").next().unwrap();
+        assert_eq!(level.question.kind, QuestionKind::Numeric);
+        assert_eq!(level.varstore.guid, GUID);
+        assert!(settings.find("Main").next().is_none());
+
+        assert_eq!(
+            settings.to_string(),
+            "Formset \"Setup\" {d6e5b6a9-0a0d-4a4b-9a7f-9d3b6f0c6e61}\n\
+             \x20 Setup {d6e5b6a9-0a0d-4a4b-9a7f-9d3b6f0c6e61} offset 0x0, 1 byte(s): Checkbox \"Enable\", default 0x1\n\
+             \x20 Setup {d6e5b6a9-0a0d-4a4b-9a7f-9d3b6f0c6e61} offset 0x2, 2 byte(s): Numeric \"Level\", min 0x1, max 0x64, step 0x1, default 0xa\n\
+             \x20 Setup {d6e5b6a9-0a0d-4a4b-9a7f-9d3b6f0c6e61} offset 0x1, 1 byte(s): OneOf \"Mode\", min 0x0, max 0x1, step 0x0, default 0x1\n\
+             \x20   0x0 \"Off\"\n\
+             \x20   0x1 \"On\"\n"
+        );
+    }
+
+    #[test]
+    fn test_conditions() {
+        // EQ_ID_VAL q1 == 0
+        let disabled = [0x12, 6, 1, 0, 0, 0];
+        let ifr = FormSetBuilder::new(GUID, 1, 2)
+            .varstore_efi(1, GUID, cstr8!("Setup"), ATTRIBUTES, 4)
+            .form(1, 3, |form| {
+                form.checkbox(Question::new(1, 4, 2).varstore(1, 0), true)
+                    .suppress_if(&disabled, |form| {
+                        form.gray_out_if(&[0x46, 2], |form| {
+                            form.checkbox(Question::new(2, 4, 2).varstore(1, 1), false);
+                        });
+                    })
+                    .checkbox(Question::new(3, 4, 2).varstore(1, 2), false);
+            })
+            .build();
+        let dump = PackageListBuilder::new(LIST_GUID).forms(&ifr).build();
+        let settings = Settings::parse(&dump, "en-US").unwrap();
+        let questions: Vec<_> = settings.questions().map(|(_, q)| q).collect();
+        assert_eq!(questions.len(), 3);
+        assert!(questions[0].conditions.is_empty());
+        assert!(questions[2].conditions.is_empty());
+        assert_eq!(
+            questions[1].conditions,
+            [
+                Condition {
+                    kind: ConditionKind::SuppressIf,
+                    expression: disabled.into(),
+                },
+                Condition {
+                    kind: ConditionKind::GrayOutIf,
+                    expression: alloc::vec![0x46, 2],
+                },
+            ]
+        );
+
+        let mut values = BTreeMap::new();
+        values.insert(1, IfrValue::Uint(0));
+        assert_eq!(questions[1].is_suppressed(&values), Ok(true));
+        assert_eq!(questions[1].is_grayed_out(&values), Ok(true));
+        values.insert(1, IfrValue::Uint(1));
+        assert_eq!(questions[1].is_suppressed(&values), Ok(false));
+        assert_eq!(questions[0].is_grayed_out(&values), Ok(false));
+    }
+
+    #[test]
+    fn test_language() {
+        let settings = Settings::parse(&dump(), "de-DE").unwrap();
+        let formset = &settings.formsets[0];
+        assert_eq!(formset.title.as_deref(), Some("Einrichtung"));
+        assert_eq!(formset.help, None);
+
+        // Unknown languages use the first string package.
+        let settings = Settings::parse(&dump(), "fr-FR").unwrap();
+        assert_eq!(settings.formsets[0].title.as_deref(), Some("Setup"));
+    }
+
+    #[test]
+    fn test_strings() {
+        let mut package = alloc::vec![0; 4];
+        package.extend_from_slice(&49u32.to_le_bytes());
+        package.extend_from_slice(&49u32.to_le_bytes());
+        package.extend_from_slice(&[0; 34]);
+        package.extend_from_slice(b"en\0");
+        package.extend_from_slice(&[0x22, 2]); // SKIP1: 2
+        package.extend_from_slice(&[0x12, 2, 0]); // STRINGS_SCSU: 2
+        package.extend_from_slice(b"ab\0cd\0");
+        package.extend_from_slice(&[0x20, 3, 0]); // DUPLICATE 3
+        package.extend_from_slice(&[0x30, 0x40, 4, 0]); // EXT1, skipped
+        package.extend_from_slice(&[0x14, b'x', 0, 0, 0]); // STRING_UCS2
+        package.push(0);
+
+        let package = StringPackage::parse(&package).unwrap();
+        assert!(language_matches(&package, "EN"));
+        assert!(!language_matches(&package, "en-US"));
+        let strings = parse_strings(&package);
+        assert_eq!(
+            strings.into_iter().collect::<Vec<_>>(),
+            [
+                (3, "ab".into()),
+                (4, "cd".into()),
+                (5, "ab".into()),
+                (6, "x".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut dump = dump();
+        dump.truncate(dump.len() - 1);
+        assert!(matches!(
+            Settings::parse(&dump, "en-US"),
+            Err(SettingsError::Truncated { .. })
+        ));
+
+        // Form package with a single END opcode.
+        let dump = PackageListBuilder::new(GUID).forms(&[0x29, 2]).build();
+        assert_eq!(
+            Settings::parse(&dump, "en-US"),
+            Err(SettingsError::UnbalancedScope { offset: 24 })
+        );
+    }
+}
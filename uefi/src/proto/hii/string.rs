@@ -0,0 +1,171 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! HII String protocol.
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ptr;
+use uefi_macros::unsafe_protocol;
+use uefi_raw::protocol::hii::string::HiiStringProtocol;
+
+use super::{HiiHandle, StringId};
+use crate::{CStr8, CStr16, CString16, Error, Result, Status, StatusExt};
+
+/// The HII String Protocol.
+///
+/// # UEFI Spec Description
+///
+/// Interfaces which manipulate string data.
+#[derive(Debug)]
+#[repr(transparent)]
+#[unsafe_protocol(HiiStringProtocol::GUID)]
+pub struct HiiString(HiiStringProtocol);
+
+impl HiiString {
+    /// Adds a new string to the string package of `package_list` for
+    /// `language` and returns its ID.
+    ///
+    /// The string is also added to the string packages of all other
+    /// languages in the package list.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::NOT_FOUND`]: the package list could not be found.
+    /// * [`Status::OUT_OF_RESOURCES`]: could not add the string.
+    pub fn new_string(
+        &self,
+        package_list: HiiHandle,
+        language: &CStr8,
+        string: &CStr16,
+    ) -> Result<StringId> {
+        let mut string_id = 0;
+        unsafe {
+            (self.0.new_string)(
+                &self.0,
+                package_list.as_ptr(),
+                &mut string_id,
+                language.as_ptr().cast(),
+                ptr::null(),
+                string.as_ptr().cast(),
+                ptr::null(),
+            )
+        }
+        .to_result_with_val(|| string_id)
+    }
+
+    /// Gets the string with ID `string_id` in `language` from
+    /// `package_list`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::NOT_FOUND`]: the string ID is not in the package list.
+    /// * [`Status::INVALID_LANGUAGE`]: the package list does not contain
+    ///   strings for `language`.
+    pub fn get_string(
+        &self,
+        language: &CStr8,
+        package_list: HiiHandle,
+        string_id: StringId,
+    ) -> Result<CString16> {
+        let mut size = 0;
+        let status = unsafe {
+            (self.0.get_string)(
+                &self.0,
+                language.as_ptr().cast(),
+                package_list.as_ptr(),
+                string_id,
+                ptr::null_mut(),
+                &mut size,
+                ptr::null_mut(),
+            )
+        };
+        match status {
+            Status::BUFFER_TOO_SMALL => {}
+            // An empty buffer can't hold the null terminator.
+            Status::SUCCESS => return Err(Status::PROTOCOL_ERROR.into()),
+            status => return Err(status.into()),
+        }
+
+        let mut buf: Vec<u16> = vec![0; size.div_ceil(2)];
+        unsafe {
+            (self.0.get_string)(
+                &self.0,
+                language.as_ptr().cast(),
+                package_list.as_ptr(),
+                string_id,
+                buf.as_mut_ptr(),
+                &mut size,
+                ptr::null_mut(),
+            )
+        }
+        .to_result()?;
+
+        // Truncate after the first null character.
+        let len = buf
+            .iter()
+            .position(|c| *c == 0)
+            .map_or(buf.len(), |nul| nul + 1);
+        buf.truncate(len);
+        CString16::try_from(buf).map_err(|_| Error::from(Status::COMPROMISED_DATA))
+    }
+
+    /// Changes the string with ID `string_id` in `language` in
+    /// `package_list`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::NOT_FOUND`]: the string ID or package list could not be
+    ///   found.
+    pub fn set_string(
+        &self,
+        package_list: HiiHandle,
+        string_id: StringId,
+        language: &CStr8,
+        string: &CStr16,
+    ) -> Result {
+        unsafe {
+            (self.0.set_string)(
+                &self.0,
+                package_list.as_ptr(),
+                string_id,
+                language.as_ptr().cast(),
+                string.as_ptr().cast(),
+                ptr::null(),
+            )
+        }
+        .to_result()
+    }
+
+    /// Returns the languages supported by `package_list`, as a
+    /// semicolon-separated list of RFC 4646 language codes (for example
+    /// `"en-US;fr-FR"`).
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::NOT_FOUND`]: the package list could not be found.
+    pub fn languages(&self, package_list: HiiHandle) -> Result<String> {
+        let mut size = 0;
+        let status = unsafe {
+            (self.0.get_languages)(&self.0, package_list.as_ptr(), ptr::null_mut(), &mut size)
+        };
+        match status {
+            Status::BUFFER_TOO_SMALL => {}
+            // An empty buffer can't hold the null terminator.
+            Status::SUCCESS => return Err(Status::PROTOCOL_ERROR.into()),
+            status => return Err(status.into()),
+        }
+
+        let mut buf: Vec<u8> = vec![0; size];
+        unsafe {
+            (self.0.get_languages)(&self.0, package_list.as_ptr(), buf.as_mut_ptr(), &mut size)
+        }
+        .to_result()?;
+
+        Ok(buf
+            .iter()
+            .take_while(|c| **c != 0)
+            .map(|c| char::from(*c))
+            .collect())
+    }
+}
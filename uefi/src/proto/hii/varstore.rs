@@ -0,0 +1,192 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Typed varstores for HII forms.
+//!
+//! The values of the questions of a form are stored in a varstore, a buffer
+//! in which each question has a byte offset. Implementing [`Varstore`] for a
+//! `#[repr(C)]` struct, usually with `#[derive(Varstore)]`, gives access to
+//! the offsets of its fields, which can be used for the storage of questions
+//! instead of hand-written offsets:
+//!
+//! ```
+//! use uefi::proto::hii::varstore::Varstore;
+//!
+//! #[derive(Varstore)]
+//! #[repr(C)]
+//! struct Config {
+//!     enabled: u8,
+//!     level: u8,
+//!     timeout: u16,
+//! }
+//!
+//! assert_eq!(Config::FIELDS.timeout.offset(), 2);
+//! assert_eq!(Config::FIELDS.timeout.size(), 2);
+//! ```
+//!
+//! With the `alloc` feature, a field is bound to a question with
+//! `Question::field` of the `form` module, and the size of the varstore is
+//! [`Varstore::SIZE`].
+
+use core::fmt::{self, Debug, Formatter};
+use core::marker::PhantomData;
+use core::{mem, slice};
+
+pub use uefi_macros::Varstore;
+
+/// Types that can be stored in a [`Varstore`].
+///
+/// # Safety
+///
+/// The type must not contain padding, and any bit pattern must be a valid
+/// value of the type.
+pub unsafe trait VarstoreValue: Copy {}
+
+// SAFETY: integers have no padding and no invalid bit patterns.
+unsafe impl VarstoreValue for u8 {}
+unsafe impl VarstoreValue for u16 {}
+unsafe impl VarstoreValue for u32 {}
+unsafe impl VarstoreValue for u64 {}
+unsafe impl VarstoreValue for i8 {}
+unsafe impl VarstoreValue for i16 {}
+unsafe impl VarstoreValue for i32 {}
+unsafe impl VarstoreValue for i64 {}
+// SAFETY: arrays have no padding between their elements.
+unsafe impl<T: VarstoreValue, const N: usize> VarstoreValue for [T; N] {}
+
+/// A `#[repr(C)]` struct used as the varstore of a form.
+///
+/// Use `#[derive(Varstore)]` to implement this trait. The derive macro
+/// checks that the struct is `#[repr(C)]`, has no padding, and that all
+/// fields implement [`VarstoreValue`]. It generates a struct with a
+/// [`VarstoreField`] for each field of the varstore, named like the
+/// varstore with a `Fields` suffix.
+///
+/// # Safety
+///
+/// The type must not contain padding, and any bit pattern must be a valid
+/// value of the type. The offsets in [`FIELDS`] must be the offsets of the
+/// corresponding fields.
+///
+/// [`FIELDS`]: Self::FIELDS
+pub unsafe trait Varstore: Sized {
+    /// Struct with a [`VarstoreField`] for each field.
+    type Fields;
+
+    /// The fields of the varstore.
+    const FIELDS: Self::Fields;
+
+    /// Size of the varstore in bytes.
+    const SIZE: u16;
+
+    /// Returns the contents of the varstore as bytes, e.g. to answer an
+    /// `ExtractConfig` request.
+    fn as_bytes(&self) -> &[u8] {
+        // SAFETY: the type has no padding.
+        unsafe { slice::from_raw_parts(core::ptr::from_ref(self).cast(), mem::size_of::<Self>()) }
+    }
+
+    /// Returns the contents of the varstore as mutable bytes, e.g. to apply
+    /// a `RouteConfig` request.
+    fn as_bytes_mut(&mut self) -> &mut [u8] {
+        // SAFETY: the type has no padding and any bit pattern is valid.
+        unsafe {
+            slice::from_raw_parts_mut(core::ptr::from_mut(self).cast(), mem::size_of::<Self>())
+        }
+    }
+}
+
+/// The offset and size of a field of type `T` in a [`Varstore`].
+pub struct VarstoreField<T> {
+    offset: u16,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> VarstoreField<T> {
+    /// Creates a field at byte `offset`.
+    ///
+    /// This is used by `#[derive(Varstore)]`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field doesn't fit in a varstore, which is limited to
+    /// 64 KiB.
+    #[must_use]
+    pub const fn new(offset: usize) -> Self {
+        assert!(
+            offset + mem::size_of::<T>() <= u16::MAX as usize,
+            "varstore field out of range"
+        );
+        Self {
+            offset: offset as u16,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the byte offset of the field.
+    #[must_use]
+    pub const fn offset(&self) -> u16 {
+        self.offset
+    }
+
+    /// Returns the size of the field in bytes.
+    #[must_use]
+    pub const fn size(&self) -> u16 {
+        mem::size_of::<T>() as u16
+    }
+}
+
+impl<T> Clone for VarstoreField<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for VarstoreField<T> {}
+
+impl<T> Debug for VarstoreField<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VarstoreField")
+            .field("offset", &self.offset)
+            .field("size", &self.size())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Copy, Varstore)]
+    #[repr(C)]
+    struct Config {
+        enabled: u8,
+        level: u8,
+        timeout: u16,
+        name: [u16; 4],
+    }
+
+    #[test]
+    fn test_varstore_fields() {
+        assert_eq!(Config::SIZE, 12);
+        assert_eq!(Config::FIELDS.enabled.offset(), 0);
+        assert_eq!(Config::FIELDS.level.offset(), 1);
+        assert_eq!(Config::FIELDS.timeout.offset(), 2);
+        assert_eq!(Config::FIELDS.name.offset(), 4);
+        assert_eq!(Config::FIELDS.name.size(), 8);
+    }
+
+    #[test]
+    fn test_varstore_bytes() {
+        let mut config = Config {
+            enabled: 1,
+            level: 2,
+            timeout: 0x0403,
+            name: [0; 4],
+        };
+        assert_eq!(&config.as_bytes()[..4], &[1, 2, 3, 4]);
+
+        let offset = usize::from(Config::FIELDS.level.offset());
+        config.as_bytes_mut()[offset] = 5;
+        assert_eq!(config.level, 5);
+    }
+}
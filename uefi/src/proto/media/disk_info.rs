@@ -2,18 +2,21 @@
 
 //! DiskInfo protocol.
 
-use crate::StatusExt;
+use crate::util::trim_ascii_field;
+use crate::{Status, StatusExt};
 use uefi_macros::unsafe_protocol;
 use uefi_raw::protocol::disk::DiskInfoProtocol;
-
-#[cfg(doc)]
-use crate::Status;
+#[cfg(feature = "alloc")]
+use {
+    crate::mem::make_boxed, crate::proto::ata::identify::AtaIdentifyData,
+    crate::proto::nvme::identify::NvmeNamespaceData, alloc::boxed::Box,
+};
 
 /// Enum representing the interface type of the disk.
 ///
 /// This protocol abstracts various disk interfaces, including IDE, USB, AHCI, NVME, and more.
 /// Unknown indicates an unrecognized or not yet implemented interface type.
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 pub enum DiskInfoInterface {
     /// Unrecognized or unsupported interface.
     Unknown,
@@ -42,6 +45,72 @@ pub struct SenseDataInfo {
     pub number: u8,
 }
 
+/// Standard SCSI INQUIRY data, returned by [`DiskInfo::inquiry_data`].
+///
+/// Besides SCSI disks, this is also returned by USB mass storage, UFS, and
+/// ATAPI devices.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct InquiryData {
+    /// Bytes 0 to 35 of the INQUIRY data.
+    data: [u8; Self::SIZE],
+}
+
+impl InquiryData {
+    /// Size of the standard INQUIRY data in bytes. Devices may return more.
+    pub const SIZE: usize = 36;
+
+    /// Creates the INQUIRY data from the bytes returned by the device.
+    /// Returns `None` if there are less than [`Self::SIZE`] bytes.
+    #[must_use]
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let data = bytes.get(..Self::SIZE)?.try_into().ok()?;
+        Some(Self { data })
+    }
+
+    /// Returns the peripheral device type, e.g. 0 for a disk or 5 for a
+    /// CD/DVD device.
+    #[must_use]
+    pub const fn peripheral_device_type(&self) -> u8 {
+        self.data[0] & 0x1f
+    }
+
+    /// Returns the peripheral qualifier. 0 means that a device is connected.
+    #[must_use]
+    pub const fn peripheral_qualifier(&self) -> u8 {
+        self.data[0] >> 5
+    }
+
+    /// Returns whether the medium is removable.
+    #[must_use]
+    pub const fn is_removable(&self) -> bool {
+        self.data[1] & 0x80 != 0
+    }
+
+    /// Returns the version of the SCSI standard the device conforms to.
+    #[must_use]
+    pub const fn version(&self) -> u8 {
+        self.data[2]
+    }
+
+    /// Returns the vendor identification, without padding.
+    #[must_use]
+    pub fn vendor_id(&self) -> &str {
+        trim_ascii_field(&self.data[8..16])
+    }
+
+    /// Returns the product identification, without padding.
+    #[must_use]
+    pub fn product_id(&self) -> &str {
+        trim_ascii_field(&self.data[16..32])
+    }
+
+    /// Returns the product revision level, without padding.
+    #[must_use]
+    pub fn revision(&self) -> &str {
+        trim_ascii_field(&self.data[32..36])
+    }
+}
+
 /// Structure containing information about the physical device location on the bus.
 ///
 /// This is not supported by all interface types.
@@ -135,6 +204,81 @@ impl DiskInfo {
         }
     }
 
+    /// Performs an inquiry command on the disk device, and returns the
+    /// standard INQUIRY data.
+    ///
+    /// # Errors
+    /// - [`Status::NOT_FOUND`] The device does not support this data class.
+    /// - [`Status::DEVICE_ERROR`] An error occurred while reading the InquiryData from the device.
+    /// - [`Status::BAD_BUFFER_SIZE`] The device returned less than [`InquiryData::SIZE`] bytes.
+    pub fn inquiry_data(&self) -> crate::Result<InquiryData> {
+        // Room for the vendor specific parameters that some devices return.
+        let mut bfr = [0; 96];
+        let len = self.inquiry(&mut bfr)?;
+        InquiryData::parse(&bfr[..len]).ok_or_else(|| Status::BAD_BUFFER_SIZE.into())
+    }
+
+    /// Performs an identify command on the disk device, and returns the
+    /// identification data in a buffer of the right size.
+    ///
+    /// The format depends on the [`interface`]: ATA devices (IDE, AHCI)
+    /// return the 512 bytes of IDENTIFY DEVICE data, and NVMe devices the
+    /// 4096 bytes of Identify Namespace data.
+    ///
+    /// # Errors
+    /// - [`Status::NOT_FOUND`] The device does not support this data class.
+    /// - [`Status::DEVICE_ERROR`] An error occurred while reading the IdentifyData from the device.
+    ///
+    /// [`interface`]: Self::interface
+    #[cfg(feature = "alloc")]
+    #[allow(clippy::extra_unused_lifetimes)] // false positive, it is used
+    pub fn identify_data<'a>(&self) -> crate::Result<Box<[u8]>> {
+        let fetch_data_fn = |buf: &'a mut [u8]| {
+            let mut len = u32::try_from(buf.len()).unwrap_or(u32::MAX);
+            let status = unsafe { (self.0.identify)(&self.0, buf.as_mut_ptr().cast(), &mut len) };
+            status
+                .to_result_with_err(|_| Some(len as usize))
+                .map(|_| buf)
+        };
+        make_boxed::<[u8], _>(fetch_data_fn)
+    }
+
+    /// Performs an identify command on an ATA disk device (IDE, AHCI), and
+    /// decodes the IDENTIFY DEVICE data.
+    ///
+    /// # Errors
+    /// - [`Status::UNSUPPORTED`] The disk is not an ATA device.
+    /// - [`Status::NOT_FOUND`] The device does not support this data class.
+    /// - [`Status::DEVICE_ERROR`] An error occurred while reading the IdentifyData from the device.
+    #[cfg(feature = "alloc")]
+    pub fn ata_identify_data(&self) -> crate::Result<AtaIdentifyData> {
+        if !matches!(
+            self.interface(),
+            DiskInfoInterface::IDE | DiskInfoInterface::AHCI
+        ) {
+            return Err(Status::UNSUPPORTED.into());
+        }
+        let mut bfr = [0; AtaIdentifyData::SIZE];
+        let len = self.identify(&mut bfr)?;
+        AtaIdentifyData::parse(&bfr[..len]).ok_or_else(|| Status::DEVICE_ERROR.into())
+    }
+
+    /// Performs an identify command on an NVMe disk device, and decodes the
+    /// Identify Namespace data of its namespace.
+    ///
+    /// # Errors
+    /// - [`Status::UNSUPPORTED`] The disk is not an NVMe device.
+    /// - [`Status::NOT_FOUND`] The device does not support this data class.
+    /// - [`Status::DEVICE_ERROR`] An error occurred while reading the IdentifyData from the device.
+    #[cfg(feature = "alloc")]
+    pub fn nvme_namespace_data(&self) -> crate::Result<NvmeNamespaceData> {
+        if self.interface() != DiskInfoInterface::NVME {
+            return Err(Status::UNSUPPORTED.into());
+        }
+        let bfr = self.identify_data()?;
+        NvmeNamespaceData::parse(&bfr).ok_or_else(|| Status::DEVICE_ERROR.into())
+    }
+
     /// Retrieves sense data from the disk device.
     ///
     /// # Arguments
@@ -184,3 +328,29 @@ impl DiskInfo {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inquiry_data() {
+        let mut bytes = [0; 40];
+        bytes[0] = 0x05;
+        bytes[1] = 0x80;
+        bytes[2] = 0x06;
+        bytes[8..16].copy_from_slice(b"uefi-rs ");
+        bytes[16..32].copy_from_slice(b"ExtScsiPassThru ");
+        bytes[32..36].copy_from_slice(b"1.0\0");
+        let inquiry = InquiryData::parse(&bytes).unwrap();
+        assert_eq!(inquiry.peripheral_device_type(), 5);
+        assert_eq!(inquiry.peripheral_qualifier(), 0);
+        assert!(inquiry.is_removable());
+        assert_eq!(inquiry.version(), 6);
+        assert_eq!(inquiry.vendor_id(), "uefi-rs");
+        assert_eq!(inquiry.product_id(), "ExtScsiPassThru");
+        assert_eq!(inquiry.revision(), "1.0");
+
+        assert_eq!(InquiryData::parse(&bytes[..35]), None);
+    }
+}
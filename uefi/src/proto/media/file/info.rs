@@ -6,6 +6,7 @@ use crate::runtime::Time;
 use crate::{CStr16, Char16, Guid, Identify};
 use core::ffi::c_void;
 use core::fmt::{self, Display, Formatter};
+use core::ops::Deref;
 use core::ptr;
 use ptr_meta::Pointee;
 
@@ -210,6 +211,12 @@ impl FileInfo {
         self.file_size
     }
 
+    /// Changes the file size, e.g. to truncate a file with `set_info()`.
+    #[cfg(feature = "alloc")]
+    pub(crate) const fn set_file_size(&mut self, file_size: u64) {
+        self.file_size = file_size;
+    }
+
     /// Physical space consumed by the file on the file system volume
     #[must_use]
     pub const fn physical_size(&self) -> u64 {
@@ -277,6 +284,39 @@ impl InfoInternal for FileInfo {
 
 impl FileProtocolInfo for FileInfo {}
 
+/// Read-only view of a [`FileInfo`], returned by [`File::file_info`].
+///
+/// Unlike the `&mut FileInfo` returned by [`File::get_info`], the view is
+/// [`Copy`], so it can be handed to several helpers that only inspect the
+/// file. It dereferences to [`FileInfo`] for the getters.
+///
+/// [`File::file_info`]: super::File::file_info
+/// [`File::get_info`]: super::File::get_info
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FileInfoView<'a>(&'a FileInfo);
+
+impl<'a> FileInfoView<'a> {
+    /// Creates a view of `info`.
+    #[must_use]
+    pub const fn new(info: &'a FileInfo) -> Self {
+        Self(info)
+    }
+
+    /// Returns the viewed [`FileInfo`], with the lifetime of the view.
+    #[must_use]
+    pub const fn info(self) -> &'a FileInfo {
+        self.0
+    }
+}
+
+impl Deref for FileInfoView<'_> {
+    type Target = FileInfo;
+
+    fn deref(&self) -> &FileInfo {
+        self.0
+    }
+}
+
 /// System volume information
 ///
 /// May only be obtained on the root directory's file handle.
@@ -25,10 +25,10 @@ use {crate::mem::make_boxed, alloc::boxed::Box};
 
 pub use dir::Directory;
 pub use info::{
-    FileInfo, FileInfoCreationError, FileProtocolInfo, FileSystemInfo, FileSystemVolumeLabel,
-    FromUefi,
+    FileInfo, FileInfoCreationError, FileInfoView, FileProtocolInfo, FileSystemInfo,
+    FileSystemVolumeLabel, FromUefi,
 };
-pub use regular::RegularFile;
+pub use regular::{RegularFile, SeekFrom};
 pub use uefi_raw::protocol::file_system::FileAttribute;
 
 /// Common interface to `FileHandle`, `RegularFile`, and `Directory`.
@@ -147,6 +147,22 @@ pub trait File: Sized {
         )
     }
 
+    /// Queries the [`FileInfo`] of the file into `buffer`, and returns a
+    /// read-only view of it.
+    ///
+    /// # Errors
+    ///
+    /// See [`get_info`].
+    ///
+    /// [`get_info`]: Self::get_info
+    fn file_info<'buf>(
+        &mut self,
+        buffer: &'buf mut [u8],
+    ) -> Result<FileInfoView<'buf>, Option<usize>> {
+        self.get_info::<FileInfo>(buffer)
+            .map(|info| FileInfoView::new(info))
+    }
+
     /// Sets some information about a file
     ///
     /// There are various restrictions on the information that may be modified using this method.
@@ -1,5 +1,7 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+#[cfg(feature = "alloc")]
+use super::FileInfo;
 use super::{File, FileHandle, FileInternal};
 use crate::{Error, Result, Status, StatusExt};
 
@@ -133,6 +135,96 @@ impl RegularFile {
     pub fn set_position(&mut self, position: u64) -> Result {
         unsafe { (self.imp().set_position)(self.imp(), position) }.to_result()
     }
+
+    /// Seeks to an offset relative to the start, end, or current position
+    /// of the file, and returns the new absolute position.
+    ///
+    /// Positions are 64-bit on all targets, so files larger than 4 GiB are
+    /// fully addressable on 32-bit platforms too.
+    ///
+    /// Seeking past the end of the file is allowed and doesn't change the
+    /// file. A following write grows the file up to the new position; the
+    /// contents of the gap are up to the file system driver and should not
+    /// be relied upon. The FAT driver, for example, doesn't support sparse
+    /// files and allocates the whole gap, which can fail with
+    /// [`Status::VOLUME_FULL`].
+    ///
+    /// If the new position is invalid, the position is unchanged.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::INVALID_PARAMETER`]: the new position would be negative
+    ///   or overflow.
+    /// * [`Status::DEVICE_ERROR`]
+    pub fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(position) => {
+                self.set_position(position)?;
+                // `END_OF_FILE` moves to the end of the file, which is not
+                // at that position.
+                return if position == Self::END_OF_FILE {
+                    self.get_position()
+                } else {
+                    Ok(position)
+                };
+            }
+            SeekFrom::End(offset) => {
+                // The size of the file is only known by moving to its end,
+                // so move back if the new position is invalid.
+                let current = self.get_position()?;
+                let position = self
+                    .set_position(Self::END_OF_FILE)
+                    .and_then(|()| self.get_position())
+                    .and_then(|end| offset_position(end, offset));
+                if position.is_err() {
+                    // Keep the original error if this fails too.
+                    let _ = self.set_position(current);
+                }
+                position?
+            }
+            SeekFrom::Current(offset) => offset_position(self.get_position()?, offset)?,
+        };
+        self.set_position(position)?;
+        Ok(position)
+    }
+
+    /// Truncates or extends the file to `len` bytes.
+    ///
+    /// The current position is not changed. When the file is extended, the
+    /// contents of the new part are up to the file system driver, as for
+    /// writes past the end of the file described in [`seek`].
+    ///
+    /// # Errors
+    ///
+    /// See [`File::get_boxed_info`] and [`File::set_info`]. The file must
+    /// have been opened for writing.
+    ///
+    /// [`seek`]: Self::seek
+    #[cfg(feature = "alloc")]
+    pub fn set_len(&mut self, len: u64) -> Result {
+        let mut info = self.get_boxed_info::<FileInfo>()?;
+        info.set_file_size(len);
+        self.set_info(&*info)
+    }
+}
+
+/// Returns `base` moved by `offset`, or [`Status::INVALID_PARAMETER`] if
+/// that is negative, overflows, or is [`RegularFile::END_OF_FILE`].
+fn offset_position(base: u64, offset: i64) -> Result<u64> {
+    base.checked_add_signed(offset)
+        .filter(|&position| position != RegularFile::END_OF_FILE)
+        .ok_or_else(|| Status::INVALID_PARAMETER.into())
+}
+
+/// Position for [`RegularFile::seek`], like `std::io::SeekFrom`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum SeekFrom {
+    /// An absolute position, in bytes from the start of the file.
+    Start(u64),
+    /// An offset from the end of the file.
+    End(i64),
+    /// An offset from the current position.
+    Current(i64),
 }
 
 impl File for RegularFile {
@@ -191,10 +283,14 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Guid;
     use alloc::rc::Rc;
     use alloc::vec;
     use alloc::vec::Vec;
     use core::cell::RefCell;
+    use core::ffi::c_void;
+    use core::ptr;
+    use uefi_raw::protocol::file_system::{FileAttribute, FileProtocolRevision, FileProtocolV1};
 
     #[derive(Default)]
     struct TestFile {
@@ -275,4 +371,152 @@ mod tests {
         assert_eq!(read_chunked(&mut buffer, 10, read), Ok(0));
         assert_eq!(buffer, [0; 10]);
     }
+
+    /// A file without data, of which only the position and size are
+    /// tracked.
+    #[repr(C)]
+    struct SizedFile {
+        protocol: FileProtocolV1,
+        position: u64,
+        size: u64,
+    }
+
+    impl SizedFile {
+        fn new(size: u64) -> Self {
+            Self {
+                protocol: FileProtocolV1 {
+                    revision: FileProtocolRevision::REVISION_1,
+                    open: stub_open,
+                    close: stub_close,
+                    delete: stub_delete,
+                    read: stub_read,
+                    write: stub_write,
+                    get_position: sized_get_position,
+                    set_position: sized_set_position,
+                    get_info: stub_get_info,
+                    set_info: stub_set_info,
+                    flush: stub_flush,
+                },
+                position: 0,
+                size,
+            }
+        }
+    }
+
+    /// Test seeking in a file larger than 4 GiB.
+    #[test]
+    fn test_seek() {
+        let size = 5 << 30;
+        let mut sized = SizedFile::new(size);
+        let mut file =
+            unsafe { RegularFile::new(FileHandle::new(ptr::from_mut(&mut sized).cast())) };
+
+        assert_eq!(file.seek(SeekFrom::End(-4)), Ok(size - 4));
+        assert_eq!(file.seek(SeekFrom::Current(-(4 << 30))), Ok((1 << 30) - 4));
+        assert_eq!(file.seek(SeekFrom::End(-(5 << 30))), Ok(0));
+        assert_eq!(file.seek(SeekFrom::Current(size as i64 + 1)), Ok(size + 1));
+        assert_eq!(
+            file.seek(SeekFrom::Start(RegularFile::END_OF_FILE)),
+            Ok(size)
+        );
+        assert_eq!(file.get_position(), Ok(size));
+
+        // Invalid positions leave the position unchanged.
+        file.seek(SeekFrom::Start(8)).unwrap();
+        assert_eq!(
+            file.seek(SeekFrom::End(-(size as i64) - 1))
+                .unwrap_err()
+                .status(),
+            Status::INVALID_PARAMETER
+        );
+        assert_eq!(file.get_position(), Ok(8));
+        assert_eq!(
+            file.seek(SeekFrom::Current(-9)).unwrap_err().status(),
+            Status::INVALID_PARAMETER
+        );
+        assert_eq!(file.get_position(), Ok(8));
+        file.set_position(u64::MAX - 1).unwrap();
+        assert_eq!(
+            file.seek(SeekFrom::Current(1)).unwrap_err().status(),
+            Status::INVALID_PARAMETER
+        );
+        assert_eq!(file.get_position(), Ok(u64::MAX - 1));
+    }
+
+    unsafe extern "efiapi" fn sized_get_position(
+        this: *const FileProtocolV1,
+        position: *mut u64,
+    ) -> Status {
+        unsafe { *position = (*this.cast::<SizedFile>()).position };
+        Status::SUCCESS
+    }
+
+    unsafe extern "efiapi" fn sized_set_position(
+        this: *mut FileProtocolV1,
+        position: u64,
+    ) -> Status {
+        let file = unsafe { &mut *this.cast::<SizedFile>() };
+        file.position = if position == RegularFile::END_OF_FILE {
+            file.size
+        } else {
+            position
+        };
+        Status::SUCCESS
+    }
+
+    const extern "efiapi" fn stub_open(
+        _this: *mut FileProtocolV1,
+        _new_handle: *mut *mut FileProtocolV1,
+        _filename: *const uefi_raw::Char16,
+        _open_mode: uefi_raw::protocol::file_system::FileMode,
+        _attributes: FileAttribute,
+    ) -> Status {
+        Status::UNSUPPORTED
+    }
+
+    const extern "efiapi" fn stub_close(_this: *mut FileProtocolV1) -> Status {
+        Status::SUCCESS
+    }
+
+    const extern "efiapi" fn stub_delete(_this: *mut FileProtocolV1) -> Status {
+        Status::UNSUPPORTED
+    }
+
+    const extern "efiapi" fn stub_read(
+        _this: *mut FileProtocolV1,
+        _buffer_size: *mut usize,
+        _buffer: *mut c_void,
+    ) -> Status {
+        Status::UNSUPPORTED
+    }
+
+    const extern "efiapi" fn stub_write(
+        _this: *mut FileProtocolV1,
+        _buffer_size: *mut usize,
+        _buffer: *const c_void,
+    ) -> Status {
+        Status::UNSUPPORTED
+    }
+
+    const extern "efiapi" fn stub_get_info(
+        _this: *mut FileProtocolV1,
+        _information_type: *const Guid,
+        _buffer_size: *mut usize,
+        _buffer: *mut c_void,
+    ) -> Status {
+        Status::UNSUPPORTED
+    }
+
+    const extern "efiapi" fn stub_set_info(
+        _this: *mut FileProtocolV1,
+        _information_type: *const Guid,
+        _buffer_size: usize,
+        _buffer: *const c_void,
+    ) -> Status {
+        Status::UNSUPPORTED
+    }
+
+    const extern "efiapi" fn stub_flush(_this: *mut FileProtocolV1) -> Status {
+        Status::UNSUPPORTED
+    }
 }
@@ -59,6 +59,7 @@ pub mod tcg;
 pub mod usb;
 
 mod boot_policy;
+mod provider;
 
 pub use boot_policy::BootPolicy;
 pub use uefi_macros::unsafe_protocol;
@@ -31,8 +31,8 @@ pub struct Http(HttpProtocol);
 
 impl Http {
     /// Receive HTTP Protocol configuration.
-    pub fn get_mode_data(&mut self, config_data: &mut HttpConfigData) -> uefi::Result<()> {
-        let status = unsafe { (self.0.get_mode_data)(&mut self.0, config_data) };
+    pub fn get_mode_data(&self, config_data: &mut HttpConfigData) -> uefi::Result<()> {
+        let status = unsafe { (self.0.get_mode_data)(&self.0, config_data) };
         match status {
             Status::SUCCESS => Ok(()),
             _ => Err(status.into()),
@@ -54,13 +54,14 @@ impl Ip4Config2 {
     }
 
     /// Get configuration data.  It is recommended to type-specific get_* helpers instead of calling this directly.
-    pub fn get_data(&mut self, data_type: Ip4Config2DataType) -> uefi::Result<Vec<u8>> {
+    pub fn get_data(&self, data_type: Ip4Config2DataType) -> uefi::Result<Vec<u8>> {
+        let this: *const _ = &self.0;
         let mut data_size = 0;
 
         // call #1: figure return buffer size
         let status = unsafe {
             let null = core::ptr::null_mut();
-            (self.0.get_data)(&mut self.0, data_type, &mut data_size, null)
+            (self.0.get_data)(this.cast_mut(), data_type, &mut data_size, null)
         };
         if status != Status::BUFFER_TOO_SMALL {
             return Err(status.into());
@@ -70,7 +71,7 @@ impl Ip4Config2 {
         let mut data = vec![0; data_size];
         let status = unsafe {
             let data_ptr = data.as_mut_ptr().cast::<c_void>();
-            (self.0.get_data)(&mut self.0, data_type, &mut data_size, data_ptr)
+            (self.0.get_data)(this.cast_mut(), data_type, &mut data_size, data_ptr)
         };
         match status {
             Status::SUCCESS => Ok(data),
@@ -85,7 +86,7 @@ impl Ip4Config2 {
     }
 
     /// Get current interface configuration.
-    pub fn get_interface_info(&mut self) -> uefi::Result<Ip4Config2InterfaceInfo> {
+    pub fn get_interface_info(&self) -> uefi::Result<Ip4Config2InterfaceInfo> {
         let data = self.get_data(Ip4Config2DataType::INTERFACE_INFO)?;
         let info: &Ip4Config2InterfaceInfo =
             unsafe { &*(data.as_ptr().cast::<Ip4Config2InterfaceInfo>()) };
@@ -0,0 +1,331 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Diagnostics for bring-up and manufacturing tests.
+
+use super::{NetworkState, ReceiveFlags, SimpleNetwork};
+use crate::{Result, Status, boot};
+use core::time::Duration;
+
+#[cfg(feature = "alloc")]
+use {
+    crate::Event,
+    crate::boot::{EventType, TimerTrigger, Tpl},
+    alloc::boxed::Box,
+    core::cell::UnsafeCell,
+    core::ffi::c_void,
+    core::fmt::{self, Debug, Formatter},
+    core::ptr::NonNull,
+};
+
+/// Ether type of the frames sent by [`SimpleNetwork::loopback_test`]: the
+/// IEEE 802 local experimental Ether type.
+pub const LOOPBACK_ETHER_TYPE: u16 = 0x88b5;
+
+/// Size of the frame buffers of [`SimpleNetwork::loopback_test`], enough
+/// for a full Ethernet frame.
+const FRAME_SIZE: usize = 1536;
+
+/// How thoroughly [`SimpleNetwork::reset_with_level`] resets an adapter.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum ResetLevel {
+    /// Reset the adapter and reinitialize it with the parameters of the
+    /// previous [`initialize`].
+    ///
+    /// [`initialize`]: SimpleNetwork::initialize
+    Quick,
+    /// Like [`Quick`], but with extended verification of the adapter,
+    /// which may take much longer.
+    ///
+    /// [`Quick`]: Self::Quick
+    ExtendedVerification,
+    /// Shut the adapter down and initialize it again, releasing and
+    /// reallocating its buffers. Any extra buffers requested by the
+    /// previous [`initialize`] are not requested again.
+    ///
+    /// [`initialize`]: SimpleNetwork::initialize
+    Reinitialize,
+    /// Like [`Reinitialize`], then also restore the permanent station
+    /// address, if it can be changed, and reset the statistics, if
+    /// supported.
+    ///
+    /// [`Reinitialize`]: Self::Reinitialize
+    Factory,
+}
+
+impl SimpleNetwork {
+    /// Resets the adapter at the given `level`. The adapter must be
+    /// initialized.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::NOT_STARTED`]: the adapter is not initialized.
+    /// * [`Status::DEVICE_ERROR`]: the adapter could not be reset.
+    pub fn reset_with_level(&self, level: ResetLevel) -> Result {
+        match level {
+            ResetLevel::Quick => self.reset(false),
+            ResetLevel::ExtendedVerification => self.reset(true),
+            ResetLevel::Reinitialize => {
+                self.shutdown()?;
+                self.initialize(0, 0)
+            }
+            ResetLevel::Factory => {
+                self.reset_with_level(ResetLevel::Reinitialize)?;
+                if self.mode().mac_address_changeable.into() {
+                    self.station_address(true, None)?;
+                }
+                match self.reset_statistics() {
+                    Err(err) if err.status() == Status::UNSUPPORTED => Ok(()),
+                    result => result,
+                }
+            }
+        }
+    }
+
+    /// Returns whether a cable is connected, or `None` if the adapter
+    /// can't detect it.
+    ///
+    /// The media state is refreshed by reading the interrupt status first.
+    ///
+    /// # Errors
+    ///
+    /// See [`get_interrupt_status`].
+    ///
+    /// [`get_interrupt_status`]: Self::get_interrupt_status
+    pub fn link_status(&self) -> Result<Option<bool>> {
+        self.get_interrupt_status()?;
+        let mode = self.mode();
+        Ok(bool::from(mode.media_present_supported).then(|| mode.media_present.into()))
+    }
+
+    /// Sends `payload` to the station's own address and waits up to
+    /// `timeout` for it to be received back, returning whether it was.
+    ///
+    /// The Simple Network Protocol can't put the adapter in loopback mode,
+    /// so the frame must be looped back outside the adapter, e.g. by a
+    /// loopback plug. The frames have the [`LOOPBACK_ETHER_TYPE`]. Unicast
+    /// reception is enabled for the duration of the test.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::NOT_STARTED`]: the adapter is not initialized.
+    /// * [`Status::BUFFER_TOO_SMALL`]: `payload` is larger than the maximum
+    ///   packet size of the adapter, or than a full Ethernet frame.
+    /// * [`Status::DEVICE_ERROR`]: the frame could not be sent or received.
+    pub fn loopback_test(&self, payload: &[u8], timeout: Duration) -> Result<bool> {
+        let mode = self.mode();
+        if mode.state != NetworkState::INITIALIZED {
+            return Err(Status::NOT_STARTED.into());
+        }
+        let header_size = mode.media_header_size as usize;
+        let frame_size = header_size + payload.len();
+        if payload.len() > mode.max_packet_size as usize || frame_size > FRAME_SIZE {
+            return Err(Status::BUFFER_TOO_SMALL.into());
+        }
+
+        let unicast_enabled = ReceiveFlags::from_bits_retain(mode.receive_filter_setting)
+            .contains(ReceiveFlags::UNICAST);
+        if !unicast_enabled {
+            self.receive_filters(ReceiveFlags::UNICAST, ReceiveFlags::empty(), false, None)?;
+        }
+        let result = self.send_and_wait(header_size, payload, timeout);
+        if !unicast_enabled {
+            self.receive_filters(ReceiveFlags::empty(), ReceiveFlags::UNICAST, false, None)?;
+        }
+        result
+    }
+
+    /// Sends the loopback frame and polls for it until `timeout`.
+    fn send_and_wait(&self, header_size: usize, payload: &[u8], timeout: Duration) -> Result<bool> {
+        let mut frame = [0; FRAME_SIZE];
+        let frame_size = header_size + payload.len();
+        frame[header_size..frame_size].copy_from_slice(payload);
+        self.transmit(
+            header_size,
+            &frame[..frame_size],
+            None,
+            Some(self.mode().current_address),
+            Some(LOOPBACK_ETHER_TYPE),
+        )?;
+
+        let mut received = [0; FRAME_SIZE];
+        let polls = timeout.as_millis().max(1);
+        for _ in 0..polls {
+            // Recycle the sent frame, so that the transmit queue doesn't
+            // fill up.
+            self.get_recycled_transmit_buffer_status()?;
+            let mut protocol = 0;
+            match self.receive(&mut received, None, None, None, Some(&mut protocol)) {
+                // The adapter may pad short frames.
+                Ok(size)
+                    if protocol == LOOPBACK_ETHER_TYPE
+                        && received[..size].get(header_size..frame_size) == Some(payload) =>
+                {
+                    return Ok(true);
+                }
+                Ok(_) => continue,
+                Err(err) if err.status() == Status::NOT_READY => {
+                    boot::stall(Duration::from_millis(1));
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// Tracks changes of the link state across readings.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Copy, Debug, Default)]
+struct LinkTracker {
+    up: Option<bool>,
+}
+
+#[cfg(feature = "alloc")]
+impl LinkTracker {
+    /// Records a reading, returning whether it differs from the previous
+    /// one. The first reading is always a change.
+    const fn update(&mut self, up: bool) -> bool {
+        let changed = !matches!(self.up, Some(last) if last == up);
+        self.up = Some(up);
+        changed
+    }
+}
+
+/// Watches the link state of a [`SimpleNetwork`] adapter, calling a
+/// callback when a cable is connected or disconnected.
+///
+/// A timer event polls the adapter with [`SimpleNetwork::link_status`]. The
+/// callback is called from the timer's notification function at
+/// [`Tpl::CALLBACK`], once with the initial state and then on every change.
+///
+/// Dropping the watcher closes the timer event.
+///
+/// # Example
+///
+/// ```no_run
+/// use core::time::Duration;
+/// use uefi::proto::network::snp::{LinkWatcher, SimpleNetwork};
+///
+/// # fn example(snp: &SimpleNetwork) -> uefi::Result {
+/// let watcher = LinkWatcher::new(snp, Duration::from_millis(500), |up| {
+///     log::info!("link {}", if up { "up" } else { "down" });
+/// })?;
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "alloc")]
+pub struct LinkWatcher<'a> {
+    timer: Event,
+    shared: Box<UnsafeCell<Shared<'a>>>,
+}
+
+/// State shared by a [`LinkWatcher`] and its timer's notification function.
+/// Only accessed at [`Tpl::CALLBACK`].
+#[cfg(feature = "alloc")]
+struct Shared<'a> {
+    snp: &'a SimpleNetwork,
+    tracker: LinkTracker,
+    callback: Box<dyn FnMut(bool) + 'a>,
+}
+
+#[cfg(feature = "alloc")]
+impl<'a> LinkWatcher<'a> {
+    /// Starts watching the link state of `snp` every `interval`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::UNSUPPORTED`]: the adapter can't detect whether a cable
+    ///   is connected.
+    /// * [`Status::OUT_OF_RESOURCES`]: the timer event could not be created.
+    pub fn new(
+        snp: &'a SimpleNetwork,
+        interval: Duration,
+        callback: impl FnMut(bool) + 'a,
+    ) -> Result<Self> {
+        if !bool::from(snp.mode().media_present_supported) {
+            return Err(Status::UNSUPPORTED.into());
+        }
+        let shared = Box::new(UnsafeCell::new(Shared {
+            snp,
+            tracker: LinkTracker::default(),
+            callback: Box::new(callback),
+        }));
+        let context = NonNull::from(&*shared).cast();
+        // SAFETY: the notification function only touches the shared state,
+        // which lives until the event is closed.
+        let timer = unsafe {
+            boot::create_event(
+                EventType::TIMER | EventType::NOTIFY_SIGNAL,
+                Tpl::CALLBACK,
+                Some(poll_notify),
+                Some(context),
+            )?
+        };
+        let period = u64::try_from(interval.as_nanos() / 100).unwrap_or(u64::MAX);
+        if let Err(err) = boot::set_timer(&timer, TimerTrigger::Periodic(period.max(1))) {
+            let _ = boot::close_event(timer);
+            return Err(err);
+        }
+        Ok(Self { timer, shared })
+    }
+
+    /// Returns the last link state seen, or `None` before the first poll.
+    #[must_use]
+    pub fn link_up(&self) -> Option<bool> {
+        // SAFETY: raising to the notification function's TPL keeps it from
+        // running while the shared state is borrowed.
+        let _tpl = unsafe { boot::raise_tpl(Tpl::CALLBACK) };
+        // SAFETY: see above.
+        unsafe { (*self.shared.get()).tracker.up }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Drop for LinkWatcher<'_> {
+    fn drop(&mut self) {
+        // SAFETY: the event is not used again.
+        let timer = unsafe { self.timer.unsafe_clone() };
+        let _ = boot::close_event(timer);
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Debug for LinkWatcher<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LinkWatcher")
+            .field("timer", &self.timer)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Notification function of the watcher's timer event.
+#[cfg(feature = "alloc")]
+unsafe extern "efiapi" fn poll_notify(_event: Event, context: Option<NonNull<c_void>>) {
+    if let Some(context) = context {
+        // SAFETY: the context is the shared state, which outlives the event.
+        // The watcher raises the TPL while borrowing it, so this has
+        // exclusive access.
+        let shared = unsafe { &mut *context.cast::<UnsafeCell<Shared>>().as_ref().get() };
+        if let Ok(Some(up)) = shared.snp.link_status() {
+            if shared.tracker.update(up) {
+                (shared.callback)(up);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_link_tracker() {
+        let mut tracker = LinkTracker::default();
+        assert!(tracker.update(false));
+        assert!(!tracker.update(false));
+        assert!(tracker.update(true));
+        assert!(!tracker.update(true));
+        assert_eq!(tracker.up, Some(true));
+        assert!(tracker.update(false));
+    }
+}
@@ -23,6 +23,11 @@ pub use uefi_raw::protocol::network::snp::{
     InterruptStatus, NetworkMode, NetworkState, NetworkStatistics, ReceiveFlags,
 };
 
+mod diag;
+#[cfg(feature = "alloc")]
+pub use diag::LinkWatcher;
+pub use diag::{LOOPBACK_ETHER_TYPE, ResetLevel};
+
 /// Simple Network [`Protocol`].
 ///
 /// [`Protocol`]: uefi::proto::Protocol
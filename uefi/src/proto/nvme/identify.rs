@@ -0,0 +1,442 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! NVMe Identify Controller and Identify Namespace data.
+
+use crate::util::trim_ascii_field;
+use core::fmt::{self, Debug, Formatter};
+
+/// The opcode of the NVMe Identify admin command.
+pub const NVME_ADMIN_IDENTIFY: u8 = 0x06;
+
+/// Identify command CNS value (CDW10) for the Identify Namespace data
+/// structure.
+pub const NVME_IDENTIFY_NAMESPACE: u32 = 0;
+
+/// Identify command CNS value (CDW10) for the Identify Controller data
+/// structure.
+pub const NVME_IDENTIFY_CONTROLLER: u32 = 1;
+
+bitflags::bitflags! {
+    /// Optional admin commands supported by an NVMe controller (OACS).
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct NvmeAdminCommands: u16 {
+        /// Security Send and Security Receive.
+        const SECURITY = 1 << 0;
+        /// Format NVM.
+        const FORMAT_NVM = 1 << 1;
+        /// Firmware Commit and Firmware Image Download.
+        const FIRMWARE = 1 << 2;
+        /// Namespace Management and Namespace Attachment.
+        const NAMESPACE_MANAGEMENT = 1 << 3;
+        /// Device Self-test.
+        const SELF_TEST = 1 << 4;
+        /// Directives.
+        const DIRECTIVES = 1 << 5;
+        /// NVMe-MI Send and NVMe-MI Receive.
+        const NVME_MI = 1 << 6;
+        /// Virtualization Management.
+        const VIRTUALIZATION = 1 << 7;
+        /// Doorbell Buffer Config.
+        const DOORBELL_BUFFER_CONFIG = 1 << 8;
+        /// Get LBA Status.
+        const GET_LBA_STATUS = 1 << 9;
+    }
+}
+
+bitflags::bitflags! {
+    /// Optional NVM commands supported by an NVMe controller (ONCS).
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct NvmeOptionalCommands: u16 {
+        /// Compare.
+        const COMPARE = 1 << 0;
+        /// Write Uncorrectable.
+        const WRITE_UNCORRECTABLE = 1 << 1;
+        /// Dataset Management, e.g. to deallocate (TRIM) blocks.
+        const DATASET_MANAGEMENT = 1 << 2;
+        /// Write Zeroes.
+        const WRITE_ZEROES = 1 << 3;
+        /// The Save field of Set Features and the Select field of Get
+        /// Features.
+        const SAVE_FEATURES = 1 << 4;
+        /// Reservations.
+        const RESERVATIONS = 1 << 5;
+        /// The Timestamp feature.
+        const TIMESTAMP = 1 << 6;
+        /// Verify.
+        const VERIFY = 1 << 7;
+    }
+}
+
+/// Reads a little-endian integer of `N` bytes at `offset`.
+pub(super) fn le<const N: usize>(data: &[u8], offset: usize) -> u128 {
+    data[offset..offset + N]
+        .iter()
+        .rev()
+        .fold(0, |value, &b| (value << 8) | u128::from(b))
+}
+
+/// Decoded NVMe Identify Controller data, returned by
+/// [`NvmeNamespace::identify_controller`].
+///
+/// [`NvmeNamespace::identify_controller`]: super::pass_thru::NvmeNamespace::identify_controller
+#[derive(Clone)]
+pub struct NvmeControllerData {
+    data: [u8; Self::SIZE],
+}
+
+impl NvmeControllerData {
+    /// Size of the Identify data structures in bytes.
+    pub const SIZE: usize = 4096;
+
+    /// Decodes the Identify Controller data returned by a controller.
+    /// Returns `None` if there are less than [`Self::SIZE`] bytes.
+    #[must_use]
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let data = bytes.get(..Self::SIZE)?.try_into().ok()?;
+        Some(Self { data })
+    }
+
+    /// Returns the raw data.
+    #[must_use]
+    pub const fn bytes(&self) -> &[u8; Self::SIZE] {
+        &self.data
+    }
+
+    /// Returns the PCI vendor ID.
+    #[must_use]
+    pub fn vendor_id(&self) -> u16 {
+        le::<2>(&self.data, 0) as u16
+    }
+
+    /// Returns the PCI subsystem vendor ID.
+    #[must_use]
+    pub fn subsystem_vendor_id(&self) -> u16 {
+        le::<2>(&self.data, 2) as u16
+    }
+
+    /// Returns the serial number, without padding.
+    #[must_use]
+    pub fn serial_number(&self) -> &str {
+        trim_ascii_field(&self.data[4..24])
+    }
+
+    /// Returns the model number, without padding.
+    #[must_use]
+    pub fn model_number(&self) -> &str {
+        trim_ascii_field(&self.data[24..64])
+    }
+
+    /// Returns the firmware revision, without padding.
+    #[must_use]
+    pub fn firmware_revision(&self) -> &str {
+        trim_ascii_field(&self.data[64..72])
+    }
+
+    /// Returns the IEEE OUI identifier of the vendor.
+    #[must_use]
+    pub fn ieee_oui(&self) -> u32 {
+        le::<3>(&self.data, 73) as u32
+    }
+
+    /// Returns the controller ID.
+    #[must_use]
+    pub fn controller_id(&self) -> u16 {
+        le::<2>(&self.data, 78) as u16
+    }
+
+    /// Returns the NVMe version supported by the controller as major,
+    /// minor, and tertiary version, or `None` for controllers older than
+    /// NVMe 1.2, which don't report it.
+    #[must_use]
+    pub fn version(&self) -> Option<(u16, u8, u8)> {
+        let version = le::<4>(&self.data, 80) as u32;
+        (version != 0).then_some(((version >> 16) as u16, (version >> 8) as u8, version as u8))
+    }
+
+    /// Returns the optional admin commands supported by the controller.
+    #[must_use]
+    pub fn admin_commands(&self) -> NvmeAdminCommands {
+        NvmeAdminCommands::from_bits_retain(le::<2>(&self.data, 256) as u16)
+    }
+
+    /// Returns the optional NVM commands supported by the controller.
+    #[must_use]
+    pub fn optional_commands(&self) -> NvmeOptionalCommands {
+        NvmeOptionalCommands::from_bits_retain(le::<2>(&self.data, 520) as u16)
+    }
+
+    /// Returns the number of firmware slots.
+    #[must_use]
+    pub const fn firmware_slots(&self) -> u8 {
+        (self.data[260] >> 1) & 0x7
+    }
+
+    /// Returns the warning composite temperature threshold in Kelvin, or
+    /// `None` if it is not reported.
+    #[must_use]
+    pub fn warning_temperature(&self) -> Option<u16> {
+        Some(le::<2>(&self.data, 266) as u16).filter(|&t| t != 0)
+    }
+
+    /// Returns the critical composite temperature threshold in Kelvin, or
+    /// `None` if it is not reported.
+    #[must_use]
+    pub fn critical_temperature(&self) -> Option<u16> {
+        Some(le::<2>(&self.data, 268) as u16).filter(|&t| t != 0)
+    }
+
+    /// Returns the total NVM capacity in bytes, or `None` if the controller
+    /// doesn't support namespace management.
+    #[must_use]
+    pub fn total_capacity(&self) -> Option<u128> {
+        Some(le::<16>(&self.data, 280)).filter(|&c| c != 0)
+    }
+
+    /// Returns the unallocated NVM capacity in bytes, or `None` if the
+    /// controller doesn't support namespace management.
+    #[must_use]
+    pub fn unallocated_capacity(&self) -> Option<u128> {
+        self.total_capacity()?;
+        Some(le::<16>(&self.data, 296))
+    }
+
+    /// Returns the number of namespaces.
+    #[must_use]
+    pub fn namespace_count(&self) -> u32 {
+        le::<4>(&self.data, 516) as u32
+    }
+
+    /// Returns whether a volatile write cache is present.
+    #[must_use]
+    pub const fn has_volatile_write_cache(&self) -> bool {
+        self.data[525] & 1 != 0
+    }
+}
+
+impl Debug for NvmeControllerData {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NvmeControllerData")
+            .field("model_number", &self.model_number())
+            .field("serial_number", &self.serial_number())
+            .field("firmware_revision", &self.firmware_revision())
+            .field("version", &self.version())
+            .field("namespace_count", &self.namespace_count())
+            .finish_non_exhaustive()
+    }
+}
+
+/// An LBA format supported by an NVMe namespace.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct NvmeLbaFormat {
+    /// Number of metadata bytes per block.
+    pub metadata_size: u16,
+    /// Block size in bytes.
+    pub block_size: u64,
+    /// Relative performance, from 0 (best) to 3 (degraded).
+    pub relative_performance: u8,
+}
+
+/// Decoded NVMe Identify Namespace data, returned by
+/// [`NvmeNamespace::identify`] or [`DiskInfo::nvme_namespace_data`].
+///
+/// [`NvmeNamespace::identify`]: super::pass_thru::NvmeNamespace::identify
+/// [`DiskInfo::nvme_namespace_data`]: crate::proto::media::disk_info::DiskInfo::nvme_namespace_data
+#[derive(Clone)]
+pub struct NvmeNamespaceData {
+    data: [u8; Self::SIZE],
+}
+
+impl NvmeNamespaceData {
+    /// Size of the Identify data structures in bytes.
+    pub const SIZE: usize = 4096;
+
+    /// Decodes the Identify Namespace data returned by a controller.
+    /// Returns `None` if there are less than [`Self::SIZE`] bytes.
+    #[must_use]
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let data = bytes.get(..Self::SIZE)?.try_into().ok()?;
+        Some(Self { data })
+    }
+
+    /// Returns the raw data.
+    #[must_use]
+    pub const fn bytes(&self) -> &[u8; Self::SIZE] {
+        &self.data
+    }
+
+    /// Returns the size of the namespace in blocks.
+    #[must_use]
+    pub fn size(&self) -> u64 {
+        le::<8>(&self.data, 0) as u64
+    }
+
+    /// Returns the number of blocks that may be allocated.
+    #[must_use]
+    pub fn capacity(&self) -> u64 {
+        le::<8>(&self.data, 8) as u64
+    }
+
+    /// Returns the number of allocated blocks.
+    #[must_use]
+    pub fn utilization(&self) -> u64 {
+        le::<8>(&self.data, 16) as u64
+    }
+
+    /// Returns whether the namespace supports thin provisioning.
+    #[must_use]
+    pub const fn is_thin_provisioned(&self) -> bool {
+        self.data[24] & 1 != 0
+    }
+
+    /// Returns the LBA format with index `index`, or `None` if the
+    /// namespace doesn't support it.
+    #[must_use]
+    pub fn lba_format(&self, index: usize) -> Option<NvmeLbaFormat> {
+        if index > usize::from(self.data[25]) || index >= 64 {
+            return None;
+        }
+        let format = le::<4>(&self.data, 128 + index * 4) as u32;
+        let block_size_log2 = (format >> 16) as u8;
+        Some(NvmeLbaFormat {
+            metadata_size: format as u16,
+            // Shifts of 64 or more are invalid formats.
+            block_size: 1u64.checked_shl(u32::from(block_size_log2))?,
+            relative_performance: ((format >> 24) & 0x3) as u8,
+        })
+    }
+
+    /// Returns the index of the LBA format the namespace is formatted with.
+    #[must_use]
+    pub fn formatted_lba_format_index(&self) -> usize {
+        let flbas = self.data[26];
+        usize::from(flbas & 0xf) | (usize::from((flbas >> 5) & 0x3) << 4)
+    }
+
+    /// Returns the LBA format the namespace is formatted with.
+    #[must_use]
+    pub fn formatted_lba_format(&self) -> Option<NvmeLbaFormat> {
+        self.lba_format(self.formatted_lba_format_index())
+    }
+
+    /// Returns the block size in bytes.
+    #[must_use]
+    pub fn block_size(&self) -> Option<u64> {
+        self.formatted_lba_format().map(|format| format.block_size)
+    }
+
+    /// Returns the size of the namespace in bytes.
+    #[must_use]
+    pub fn size_in_bytes(&self) -> Option<u64> {
+        self.size().checked_mul(self.block_size()?)
+    }
+
+    /// Returns the namespace globally unique identifier, or `None` if it is
+    /// not reported.
+    #[must_use]
+    pub fn nguid(&self) -> Option<[u8; 16]> {
+        let nguid: [u8; 16] = self.data[104..120].try_into().unwrap();
+        (nguid != [0; 16]).then_some(nguid)
+    }
+
+    /// Returns the IEEE extended unique identifier, or `None` if it is not
+    /// reported.
+    #[must_use]
+    pub fn eui64(&self) -> Option<[u8; 8]> {
+        let eui64: [u8; 8] = self.data[120..128].try_into().unwrap();
+        (eui64 != [0; 8]).then_some(eui64)
+    }
+}
+
+impl Debug for NvmeNamespaceData {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NvmeNamespaceData")
+            .field("size", &self.size())
+            .field("capacity", &self.capacity())
+            .field("utilization", &self.utilization())
+            .field("formatted_lba_format", &self.formatted_lba_format())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_controller() {
+        let mut bytes = vec![0; 4096];
+        bytes[0..2].copy_from_slice(&0x1b36u16.to_le_bytes());
+        bytes[4..24].copy_from_slice(b"uefi-rsNvmePassThru ");
+        bytes[24..64].fill(b' ');
+        bytes[24..34].copy_from_slice(b"QEMU NVMe ");
+        bytes[64..72].copy_from_slice(b"8.0.0   ");
+        bytes[73..76].copy_from_slice(&[0x00, 0x54, 0x52]);
+        bytes[80..84].copy_from_slice(&0x0001_0400u32.to_le_bytes());
+        bytes[256..258].copy_from_slice(&0x000au16.to_le_bytes());
+        bytes[266..268].copy_from_slice(&343u16.to_le_bytes());
+        bytes[268..270].copy_from_slice(&373u16.to_le_bytes());
+        bytes[516..520].copy_from_slice(&256u32.to_le_bytes());
+        bytes[520..522].copy_from_slice(&0x0014u16.to_le_bytes());
+        bytes[525] = 1;
+
+        let data = NvmeControllerData::parse(&bytes).unwrap();
+        assert_eq!(data.vendor_id(), 0x1b36);
+        assert_eq!(data.serial_number(), "uefi-rsNvmePassThru");
+        assert_eq!(data.model_number(), "QEMU NVMe");
+        assert_eq!(data.firmware_revision(), "8.0.0");
+        assert_eq!(data.ieee_oui(), 0x52_5400);
+        assert_eq!(data.version(), Some((1, 4, 0)));
+        assert_eq!(
+            data.admin_commands(),
+            NvmeAdminCommands::FORMAT_NVM | NvmeAdminCommands::NAMESPACE_MANAGEMENT
+        );
+        assert_eq!(
+            data.optional_commands(),
+            NvmeOptionalCommands::DATASET_MANAGEMENT | NvmeOptionalCommands::SAVE_FEATURES
+        );
+        assert_eq!(data.warning_temperature(), Some(343));
+        assert_eq!(data.critical_temperature(), Some(373));
+        assert_eq!(data.total_capacity(), None);
+        assert_eq!(data.namespace_count(), 256);
+        assert!(data.has_volatile_write_cache());
+
+        assert!(NvmeControllerData::parse(&bytes[..4095]).is_none());
+    }
+
+    #[test]
+    fn test_namespace() {
+        let mut bytes = vec![0; 4096];
+        bytes[0..8].copy_from_slice(&0x20_0000u64.to_le_bytes());
+        bytes[8..16].copy_from_slice(&0x20_0000u64.to_le_bytes());
+        bytes[16..24].copy_from_slice(&0x1000u64.to_le_bytes());
+        // Two LBA formats, formatted with the second.
+        bytes[25] = 1;
+        bytes[26] = 1;
+        bytes[120..128].copy_from_slice(&[0x52, 0x54, 0, 0x12, 0x34, 0x56, 0x78, 0x9a]);
+        bytes[128..132].copy_from_slice(&(9u32 << 16).to_le_bytes());
+        bytes[132..136].copy_from_slice(&((1u32 << 24) | (12 << 16) | 8).to_le_bytes());
+
+        let data = NvmeNamespaceData::parse(&bytes).unwrap();
+        assert_eq!(data.size(), 0x20_0000);
+        assert_eq!(data.capacity(), 0x20_0000);
+        assert_eq!(data.utilization(), 0x1000);
+        assert_eq!(data.formatted_lba_format_index(), 1);
+        assert_eq!(
+            data.formatted_lba_format(),
+            Some(NvmeLbaFormat {
+                metadata_size: 8,
+                block_size: 4096,
+                relative_performance: 1,
+            })
+        );
+        assert_eq!(data.lba_format(0).unwrap().block_size, 512);
+        assert_eq!(data.lba_format(2), None);
+        assert_eq!(data.size_in_bytes(), Some(0x20_0000 * 4096));
+        assert_eq!(data.nguid(), None);
+        assert_eq!(
+            data.eui64(),
+            Some([0x52, 0x54, 0, 0x12, 0x34, 0x56, 0x78, 0x9a])
+        );
+    }
+}
@@ -11,7 +11,9 @@ use uefi_raw::protocol::nvme::{
     NvmExpressCommand, NvmExpressCommandCdwValidity, NvmExpressPassThruCommandPacket,
 };
 
+pub mod identify;
 pub mod pass_thru;
+pub mod smart;
 
 /// Represents the completion status of an NVMe command.
 ///
@@ -2,13 +2,19 @@
 
 //! NVM Express Pass Thru Protocol.
 
-use super::{NvmeRequest, NvmeResponse};
+use super::identify::{
+    NVME_ADMIN_IDENTIFY, NVME_IDENTIFY_CONTROLLER, NVME_IDENTIFY_NAMESPACE, NvmeControllerData,
+    NvmeNamespaceData,
+};
+use super::smart::{NVME_ADMIN_GET_LOG_PAGE, NVME_LOG_SMART, NvmeSmartLog};
+use super::{NvmeQueueType, NvmeRequest, NvmeRequestBuilder, NvmeResponse};
 use crate::StatusExt;
 use crate::mem::{AlignedBuffer, PoolAllocation};
 use crate::proto::device_path::PoolDevicePathNode;
 use core::alloc::LayoutError;
 use core::cell::UnsafeCell;
 use core::ptr::{self, NonNull};
+use core::time::Duration;
 use uefi_macros::unsafe_protocol;
 use uefi_raw::Status;
 use uefi_raw::protocol::device_path::DevicePathProtocol;
@@ -25,6 +31,9 @@ pub type NvmePassThruMode = uefi_raw::protocol::nvme::NvmExpressPassThruMode;
 /// Namespace IDs are used to target specific namespaces on an NVMe device for commands.
 pub type NvmeNamespaceId = u32;
 
+/// Namespace ID addressing all namespaces of a controller.
+const NVME_NSID_BROADCAST: NvmeNamespaceId = 0xffff_ffff;
+
 /// NVMe Pass Thru Protocol.
 ///
 /// One protocol instance corresponds to one NVMe controller
@@ -147,6 +156,90 @@ impl NvmeNamespace<'_> {
         }
     }
 
+    /// Sends an Identify Controller admin command, and decodes the returned
+    /// data. This is usually sent to the [`NvmePassThru::controller`]
+    /// namespace.
+    ///
+    /// # Errors
+    /// - [`Status::OUT_OF_RESOURCES`] The transfer buffer could not be allocated.
+    /// - [`Status::DEVICE_ERROR`] The controller returned less than [`NvmeControllerData::SIZE`] bytes.
+    /// - See [`NvmeNamespace::execute_command`] for other errors.
+    pub fn identify_controller(&mut self) -> crate::Result<NvmeControllerData> {
+        let data = self.admin_data(
+            NVME_ADMIN_IDENTIFY,
+            NVME_IDENTIFY_CONTROLLER,
+            NvmeControllerData::SIZE,
+            NvmeControllerData::parse,
+        )?;
+        data.ok_or_else(|| Status::DEVICE_ERROR.into())
+    }
+
+    /// Sends an Identify Namespace admin command for this namespace
+    /// (Namespace ID ≥ 1), and decodes the returned data.
+    ///
+    /// # Errors
+    /// - [`Status::OUT_OF_RESOURCES`] The transfer buffer could not be allocated.
+    /// - [`Status::DEVICE_ERROR`] The controller returned less than [`NvmeNamespaceData::SIZE`] bytes.
+    /// - See [`NvmeNamespace::execute_command`] for other errors.
+    pub fn identify(&mut self) -> crate::Result<NvmeNamespaceData> {
+        let data = self.admin_data(
+            NVME_ADMIN_IDENTIFY,
+            NVME_IDENTIFY_NAMESPACE,
+            NvmeNamespaceData::SIZE,
+            NvmeNamespaceData::parse,
+        )?;
+        data.ok_or_else(|| Status::DEVICE_ERROR.into())
+    }
+
+    /// Reads the SMART / Health Information log page. When this is sent to
+    /// the [`NvmePassThru::controller`] namespace, the controller-wide
+    /// information is requested; per-namespace information is optional.
+    ///
+    /// # Errors
+    /// - [`Status::OUT_OF_RESOURCES`] The transfer buffer could not be allocated.
+    /// - [`Status::DEVICE_ERROR`] The controller returned less than [`NvmeSmartLog::SIZE`] bytes.
+    /// - See [`NvmeNamespace::execute_command`] for other errors.
+    pub fn smart_log(&mut self) -> crate::Result<NvmeSmartLog> {
+        // CDW10 holds the number of dwords to read (zero-based) and the
+        // log page identifier.
+        let dwords = (NvmeSmartLog::SIZE / 4 - 1) as u32;
+        // Controller-wide log pages are requested with the broadcast NSID.
+        let mut namespace = NvmeNamespace {
+            proto: self.proto,
+            namespace_id: match self.namespace_id {
+                0 => NVME_NSID_BROADCAST,
+                namespace_id => namespace_id,
+            },
+        };
+        let data = namespace.admin_data(
+            NVME_ADMIN_GET_LOG_PAGE,
+            (dwords << 16) | u32::from(NVME_LOG_SMART),
+            NvmeSmartLog::SIZE,
+            NvmeSmartLog::parse,
+        )?;
+        data.ok_or_else(|| Status::DEVICE_ERROR.into())
+    }
+
+    /// Sends the admin command `opcode` with `cdw10`, reading `len` bytes,
+    /// and decodes the returned data with `parse`.
+    fn admin_data<T>(
+        &mut self,
+        opcode: u8,
+        cdw10: u32,
+        len: usize,
+        parse: impl FnOnce(&[u8]) -> Option<T>,
+    ) -> crate::Result<Option<T>> {
+        let io_align = unsafe { (*(*self.proto.get()).mode).io_align.max(1) };
+        let request = NvmeRequestBuilder::new(io_align, opcode, NvmeQueueType::ADMIN)
+            .with_timeout(Duration::from_secs(3))
+            .with_cdw10(cdw10)
+            .with_transfer_buffer(len)
+            .map_err(|_| Status::OUT_OF_RESOURCES)?
+            .build();
+        let response = self.execute_command(request)?;
+        Ok(response.transfer_buffer().and_then(parse))
+    }
+
     /// Sends an NVM Express command to this namespace (Namespace ID ≥ 1).
     ///
     /// # Arguments
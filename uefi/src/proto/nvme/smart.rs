@@ -0,0 +1,228 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! NVMe SMART / Health Information log page.
+
+use super::identify::le;
+use core::fmt::{self, Debug, Formatter};
+
+/// The opcode of the NVMe Get Log Page admin command.
+pub const NVME_ADMIN_GET_LOG_PAGE: u8 = 0x02;
+
+/// Log page identifier of the SMART / Health Information log page.
+pub const NVME_LOG_SMART: u8 = 0x02;
+
+bitflags::bitflags! {
+    /// Critical warnings reported in the [`NvmeSmartLog`].
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct NvmeCriticalWarning: u8 {
+        /// The available spare capacity is below its threshold.
+        const SPARE = 1 << 0;
+        /// A temperature is outside of its thresholds.
+        const TEMPERATURE = 1 << 1;
+        /// Reliability is degraded by media errors or internal errors.
+        const RELIABILITY = 1 << 2;
+        /// All media is read-only.
+        const READ_ONLY = 1 << 3;
+        /// The volatile memory backup device has failed.
+        const VOLATILE_BACKUP = 1 << 4;
+        /// The persistent memory region is read-only.
+        const PMR_READ_ONLY = 1 << 5;
+    }
+}
+
+/// Decoded SMART / Health Information log page, returned by
+/// [`NvmeNamespace::smart_log`].
+///
+/// [`NvmeNamespace::smart_log`]: super::pass_thru::NvmeNamespace::smart_log
+#[derive(Clone)]
+pub struct NvmeSmartLog {
+    data: [u8; Self::SIZE],
+}
+
+impl NvmeSmartLog {
+    /// Size of the log page in bytes.
+    pub const SIZE: usize = 512;
+
+    /// Decodes the log page returned by a controller. Returns `None` if
+    /// there are less than [`Self::SIZE`] bytes.
+    #[must_use]
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let data = bytes.get(..Self::SIZE)?.try_into().ok()?;
+        Some(Self { data })
+    }
+
+    /// Returns the raw data.
+    #[must_use]
+    pub const fn bytes(&self) -> &[u8; Self::SIZE] {
+        &self.data
+    }
+
+    /// Returns the critical warnings.
+    #[must_use]
+    pub const fn critical_warning(&self) -> NvmeCriticalWarning {
+        NvmeCriticalWarning::from_bits_retain(self.data[0])
+    }
+
+    /// Returns the composite temperature in Kelvin.
+    #[must_use]
+    pub fn composite_temperature(&self) -> u16 {
+        le::<2>(&self.data, 1) as u16
+    }
+
+    /// Returns the remaining spare capacity in percent.
+    #[must_use]
+    pub const fn available_spare(&self) -> u8 {
+        self.data[3]
+    }
+
+    /// Returns the threshold of [`Self::available_spare`] in percent.
+    #[must_use]
+    pub const fn available_spare_threshold(&self) -> u8 {
+        self.data[4]
+    }
+
+    /// Returns the estimate of the life used in percent. This may exceed
+    /// 100, and saturates at 255.
+    #[must_use]
+    pub const fn percentage_used(&self) -> u8 {
+        self.data[5]
+    }
+
+    /// Returns the number of 512-byte data units read, in thousands.
+    #[must_use]
+    pub fn data_units_read(&self) -> u128 {
+        le::<16>(&self.data, 32)
+    }
+
+    /// Returns the number of 512-byte data units written, in thousands.
+    #[must_use]
+    pub fn data_units_written(&self) -> u128 {
+        le::<16>(&self.data, 48)
+    }
+
+    /// Returns the number of read commands completed.
+    #[must_use]
+    pub fn host_read_commands(&self) -> u128 {
+        le::<16>(&self.data, 64)
+    }
+
+    /// Returns the number of write commands completed.
+    #[must_use]
+    pub fn host_write_commands(&self) -> u128 {
+        le::<16>(&self.data, 80)
+    }
+
+    /// Returns the time the controller was busy with I/O commands, in
+    /// minutes.
+    #[must_use]
+    pub fn controller_busy_time(&self) -> u128 {
+        le::<16>(&self.data, 96)
+    }
+
+    /// Returns the number of power cycles.
+    #[must_use]
+    pub fn power_cycles(&self) -> u128 {
+        le::<16>(&self.data, 112)
+    }
+
+    /// Returns the number of power-on hours.
+    #[must_use]
+    pub fn power_on_hours(&self) -> u128 {
+        le::<16>(&self.data, 128)
+    }
+
+    /// Returns the number of unsafe shutdowns.
+    #[must_use]
+    pub fn unsafe_shutdowns(&self) -> u128 {
+        le::<16>(&self.data, 144)
+    }
+
+    /// Returns the number of unrecovered data integrity errors.
+    #[must_use]
+    pub fn media_errors(&self) -> u128 {
+        le::<16>(&self.data, 160)
+    }
+
+    /// Returns the number of error information log entries.
+    #[must_use]
+    pub fn error_log_entries(&self) -> u128 {
+        le::<16>(&self.data, 176)
+    }
+
+    /// Returns the time the composite temperature was above the warning
+    /// threshold, in minutes.
+    #[must_use]
+    pub fn warning_temperature_time(&self) -> u32 {
+        le::<4>(&self.data, 192) as u32
+    }
+
+    /// Returns the time the composite temperature was above the critical
+    /// threshold, in minutes.
+    #[must_use]
+    pub fn critical_temperature_time(&self) -> u32 {
+        le::<4>(&self.data, 196) as u32
+    }
+
+    /// Returns the temperature of sensor `index` (0 to 7) in Kelvin, or
+    /// `None` if it is not implemented.
+    #[must_use]
+    pub fn temperature_sensor(&self, index: usize) -> Option<u16> {
+        if index >= 8 {
+            return None;
+        }
+        Some(le::<2>(&self.data, 200 + index * 2) as u16).filter(|&t| t != 0)
+    }
+}
+
+impl Debug for NvmeSmartLog {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NvmeSmartLog")
+            .field("critical_warning", &self.critical_warning())
+            .field("composite_temperature", &self.composite_temperature())
+            .field("available_spare", &self.available_spare())
+            .field("percentage_used", &self.percentage_used())
+            .field("power_on_hours", &self.power_on_hours())
+            .field("media_errors", &self.media_errors())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_smart_log() {
+        let mut bytes = [0; 512];
+        bytes[0] = 0x04;
+        bytes[1..3].copy_from_slice(&308u16.to_le_bytes());
+        bytes[3] = 90;
+        bytes[4] = 10;
+        bytes[5] = 7;
+        bytes[48..56].copy_from_slice(&0x1_0000_0000u64.to_le_bytes());
+        bytes[112] = 42;
+        bytes[128..130].copy_from_slice(&1000u16.to_le_bytes());
+        bytes[160] = 2;
+        bytes[196] = 5;
+        bytes[202..204].copy_from_slice(&310u16.to_le_bytes());
+
+        let log = NvmeSmartLog::parse(&bytes).unwrap();
+        assert_eq!(log.critical_warning(), NvmeCriticalWarning::RELIABILITY);
+        assert_eq!(log.composite_temperature(), 308);
+        assert_eq!(log.available_spare(), 90);
+        assert_eq!(log.available_spare_threshold(), 10);
+        assert_eq!(log.percentage_used(), 7);
+        assert_eq!(log.data_units_read(), 0);
+        assert_eq!(log.data_units_written(), 0x1_0000_0000);
+        assert_eq!(log.power_cycles(), 42);
+        assert_eq!(log.power_on_hours(), 1000);
+        assert_eq!(log.media_errors(), 2);
+        assert_eq!(log.warning_temperature_time(), 0);
+        assert_eq!(log.critical_temperature_time(), 5);
+        assert_eq!(log.temperature_sensor(0), None);
+        assert_eq!(log.temperature_sensor(1), Some(310));
+        assert_eq!(log.temperature_sensor(8), None);
+
+        assert!(NvmeSmartLog::parse(&bytes[..511]).is_none());
+    }
+}
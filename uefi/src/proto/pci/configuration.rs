@@ -2,6 +2,11 @@
 
 //! Pci root bus resource configuration descriptor parsing.
 
+use core::ops::RangeInclusive;
+use uefi_raw::protocol::pci::resource::{
+    EndTagDescriptor, QwordAddressSpaceDescriptor as RawDescriptor, ResourceType,
+};
+
 /// Represents the type of resource described by a QWORD Address Space Descriptor.
 /// This corresponds to the `resource_type` field at offset 0x03 in the descriptor.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -39,7 +44,7 @@ impl From<u8> for ResourceRangeType {
 
 /// Represents a parsed QWORD Address Space Descriptor from UEFI.
 /// This structure describes a decoded resource range for a PCI root bridge.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct QwordAddressSpaceDescriptor {
     /// Type of resource: Memory, I/O, Bus, or Unknown.
     pub resource_range_type: ResourceRangeType,
@@ -61,6 +66,172 @@ pub struct QwordAddressSpaceDescriptor {
     pub address_length: u64,
 }
 
+impl From<ResourceRangeType> for u8 {
+    fn from(value: ResourceRangeType) -> Self {
+        match value {
+            ResourceRangeType::Memory => 0,
+            ResourceRangeType::Io => 1,
+            ResourceRangeType::Bus => 2,
+            ResourceRangeType::Unknown(other) => other,
+        }
+    }
+}
+
+impl QwordAddressSpaceDescriptor {
+    /// Size of an encoded descriptor in bytes, including its tag and length.
+    pub const SIZE: usize = 3 + RawDescriptor::LENGTH as usize;
+
+    /// Decodes a descriptor from the start of `bytes`.
+    ///
+    /// Returns `None` if `bytes` doesn't start with a complete QWORD Address
+    /// Space Descriptor.
+    #[must_use]
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let bfr = bytes.get(..Self::SIZE)?;
+        if bfr[0] != RawDescriptor::TAG
+            || u16::from_le_bytes([bfr[1], bfr[2]]) != RawDescriptor::LENGTH
+        {
+            return None;
+        }
+        let qword = |offset: usize| u64::from_le_bytes(bfr[offset..offset + 8].try_into().unwrap());
+        Some(Self {
+            resource_range_type: ResourceRangeType::from(bfr[0x03]),
+            general_flags: bfr[0x04],
+            type_specific_flags: bfr[0x05],
+            granularity: qword(0x06),
+            address_min: qword(0x0E),
+            address_max: qword(0x16),
+            translation_offset: qword(0x1E),
+            address_length: qword(0x26),
+        })
+    }
+
+    /// Encodes the descriptor, e.g. to return resource padding from a PCI
+    /// hot plug controller driver.
+    #[must_use]
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let raw = RawDescriptor::from(self);
+        // SAFETY: the raw descriptor is packed and has no padding.
+        unsafe { core::mem::transmute::<RawDescriptor, [u8; Self::SIZE]>(raw) }
+    }
+
+    /// Returns the range of addresses or bus numbers.
+    #[must_use]
+    pub const fn range(&self) -> RangeInclusive<u64> {
+        self.address_min..=self.address_max
+    }
+
+    /// Returns whether the descriptor describes prefetchable memory.
+    #[must_use]
+    pub fn is_prefetchable(&self) -> bool {
+        self.resource_range_type == ResourceRangeType::Memory
+            && self.type_specific_flags & RawDescriptor::MEMORY_PREFETCHABLE
+                == RawDescriptor::MEMORY_PREFETCHABLE
+    }
+}
+
+impl From<&QwordAddressSpaceDescriptor> for RawDescriptor {
+    fn from(value: &QwordAddressSpaceDescriptor) -> Self {
+        Self {
+            tag: Self::TAG,
+            length: Self::LENGTH,
+            resource_type: ResourceType(value.resource_range_type.into()),
+            general_flags: value.general_flags,
+            type_specific_flags: value.type_specific_flags,
+            address_space_granularity: value.granularity,
+            address_range_minimum: value.address_min,
+            address_range_maximum: value.address_max,
+            address_translation_offset: value.translation_offset,
+            address_length: value.address_length,
+        }
+    }
+}
+
+impl From<&RawDescriptor> for QwordAddressSpaceDescriptor {
+    fn from(value: &RawDescriptor) -> Self {
+        Self {
+            resource_range_type: ResourceRangeType::from(value.resource_type.0),
+            general_flags: value.general_flags,
+            type_specific_flags: value.type_specific_flags,
+            granularity: value.address_space_granularity,
+            address_min: value.address_range_minimum,
+            address_max: value.address_range_maximum,
+            translation_offset: value.address_translation_offset,
+            address_length: value.address_length,
+        }
+    }
+}
+
+/// Iterator over the QWORD Address Space Descriptors in a list of ACPI
+/// resource descriptors.
+///
+/// Other descriptors are skipped. Iteration stops at the End Tag
+/// descriptor, or at the first descriptor that doesn't fit into the buffer.
+#[derive(Clone, Debug)]
+pub struct ResourceDescriptorIter<'a> {
+    bfr: &'a [u8],
+}
+
+impl<'a> ResourceDescriptorIter<'a> {
+    /// Creates an iterator over the descriptors in `bfr`.
+    #[must_use]
+    pub const fn new(bfr: &'a [u8]) -> Self {
+        Self { bfr }
+    }
+}
+
+impl Iterator for ResourceDescriptorIter<'_> {
+    type Item = QwordAddressSpaceDescriptor;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let &tag = self.bfr.first()?;
+            if tag == EndTagDescriptor::TAG {
+                self.bfr = &[];
+                return None;
+            }
+            let Some(len) = descriptor_len(self.bfr).filter(|&len| len <= self.bfr.len()) else {
+                self.bfr = &[];
+                return None;
+            };
+            let descriptor = &self.bfr[..len];
+            self.bfr = &self.bfr[len..];
+            if tag == RawDescriptor::TAG {
+                return QwordAddressSpaceDescriptor::from_bytes(descriptor);
+            }
+        }
+    }
+}
+
+/// Returns the length of the ACPI resource descriptor at the start of `bfr`.
+///
+/// Large descriptors have bit 7 of the tag set and store their length in the
+/// following two bytes; small descriptors store it in the low three bits of
+/// the tag.
+fn descriptor_len(bfr: &[u8]) -> Option<usize> {
+    let &tag = bfr.first()?;
+    if tag & 0x80 != 0 {
+        let len = u16::from_le_bytes([*bfr.get(1)?, *bfr.get(2)?]);
+        Some(3 + usize::from(len))
+    } else {
+        Some(1 + usize::from(tag & 0x07))
+    }
+}
+
+/// Encodes `descriptors` as list terminated by an End Tag descriptor.
+#[cfg(feature = "alloc")]
+#[must_use]
+pub fn build(descriptors: &[QwordAddressSpaceDescriptor]) -> alloc::vec::Vec<u8> {
+    let mut bfr = alloc::vec::Vec::with_capacity(
+        descriptors.len() * QwordAddressSpaceDescriptor::SIZE + size_of::<EndTagDescriptor>(),
+    );
+    for descriptor in descriptors {
+        bfr.extend_from_slice(&descriptor.to_bytes());
+    }
+    bfr.extend_from_slice(&[EndTagDescriptor::TAG, 0]);
+    bfr
+}
+
 /// Parses a list of QWORD Address Space Descriptors from a raw memory region.
 /// Stops when it encounters an End Tag descriptor (type 0x79).
 #[cfg(feature = "alloc")]
@@ -68,48 +239,22 @@ pub(crate) fn parse(
     base: *const core::ffi::c_void,
 ) -> alloc::vec::Vec<QwordAddressSpaceDescriptor> {
     use alloc::slice;
-    use alloc::vec::Vec;
-    const PCI_RESTBL_QWORDADDRSPEC_TAG: u8 = 0x8a;
-    const PCI_RESTBL_END_TAG: u8 = 0x79;
 
     let base: *const u8 = base.cast();
 
     // Phase 1: determine total length
     let mut offset = 0;
     loop {
-        let tag = unsafe { core::ptr::read(base.add(offset)) };
-        offset += match tag {
-            PCI_RESTBL_QWORDADDRSPEC_TAG => 3 + 0x2B,
-            PCI_RESTBL_END_TAG => break,
-            _ => panic!("{tag}"), // Unknown tag - bailing
-        };
-    }
-
-    // Phase 2: parse descriptors from resource table
-    let mut bfr: &[u8] = unsafe { slice::from_raw_parts(base, offset) };
-    let mut descriptors = Vec::new();
-    while !bfr.is_empty() {
-        match bfr[0] {
-            PCI_RESTBL_QWORDADDRSPEC_TAG => {
-                let descriptor = QwordAddressSpaceDescriptor {
-                    resource_range_type: ResourceRangeType::from(bfr[0x03]),
-                    general_flags: bfr[0x04],
-                    type_specific_flags: bfr[0x05],
-                    granularity: u64::from_le_bytes(bfr[0x06..0x06 + 8].try_into().unwrap()),
-                    address_min: u64::from_le_bytes(bfr[0x0E..0x0E + 8].try_into().unwrap()),
-                    address_max: u64::from_le_bytes(bfr[0x16..0x16 + 8].try_into().unwrap()),
-                    translation_offset: u64::from_le_bytes(bfr[0x1E..0x1E + 8].try_into().unwrap()),
-                    address_length: u64::from_le_bytes(bfr[0x26..0x26 + 8].try_into().unwrap()),
-                };
-                descriptors.push(descriptor);
-
-                bfr = &bfr[3 + 0x2B..];
-            }
-            _ => break,
+        let header = unsafe { slice::from_raw_parts(base.add(offset), 3) };
+        if header[0] == EndTagDescriptor::TAG {
+            break;
         }
+        offset += descriptor_len(header).unwrap();
     }
 
-    descriptors
+    // Phase 2: parse descriptors from resource table
+    let bfr: &[u8] = unsafe { slice::from_raw_parts(base, offset) };
+    ResourceDescriptorIter::new(bfr).collect()
 }
 
 #[cfg(test)]
@@ -143,4 +288,67 @@ mod tests {
         assert_eq!(cnt_io, 1);
         assert_eq!(cnt_bus, 1);
     }
+
+    #[test]
+    fn roundtrip() {
+        let descriptors = [
+            super::QwordAddressSpaceDescriptor {
+                resource_range_type: ResourceRangeType::Memory,
+                general_flags: 0,
+                type_specific_flags: 0x06,
+                granularity: 64,
+                address_min: 0,
+                address_max: 0x1f_ffff,
+                translation_offset: 0,
+                address_length: 0x20_0000,
+            },
+            super::QwordAddressSpaceDescriptor {
+                resource_range_type: ResourceRangeType::Bus,
+                general_flags: 0,
+                type_specific_flags: 0,
+                granularity: 0,
+                address_min: 0,
+                address_max: 0,
+                translation_offset: 0,
+                address_length: 2,
+            },
+        ];
+        let bfr = super::build(&descriptors);
+        assert_eq!(bfr.len(), 2 * super::QwordAddressSpaceDescriptor::SIZE + 2);
+        assert_eq!(&bfr[..3], &[0x8a, 0x2b, 0]);
+        assert_eq!(&bfr[bfr.len() - 2..], &[0x79, 0]);
+
+        let parsed: alloc::vec::Vec<_> = super::ResourceDescriptorIter::new(&bfr).collect();
+        assert_eq!(parsed, descriptors);
+        assert!(parsed[0].is_prefetchable());
+        assert_eq!(parsed[0].range(), 0..=0x1f_ffff);
+        assert!(!parsed[1].is_prefetchable());
+    }
+
+    #[test]
+    fn skip_other_descriptors() {
+        let descriptor = super::QwordAddressSpaceDescriptor {
+            resource_range_type: ResourceRangeType::Io,
+            general_flags: 0,
+            type_specific_flags: 0,
+            granularity: 0,
+            address_min: 0x1000,
+            address_max: 0x1fff,
+            translation_offset: 0,
+            address_length: 0x1000,
+        };
+        // Small IRQ descriptor, large DWORD descriptor, QWORD descriptor.
+        let mut bfr = alloc::vec![0x22, 0x01, 0x00];
+        bfr.extend_from_slice(&[0x87, 0x17, 0x00]);
+        bfr.extend_from_slice(&[0; 0x17]);
+        bfr.extend_from_slice(&super::build(core::slice::from_ref(&descriptor)));
+
+        let parsed: alloc::vec::Vec<_> = super::ResourceDescriptorIter::new(&bfr).collect();
+        assert_eq!(parsed, [descriptor]);
+        let parsed = super::parse(bfr.as_ptr().cast());
+        assert_eq!(parsed.len(), 1);
+
+        // Truncated descriptors end the iteration.
+        assert_eq!(super::ResourceDescriptorIter::new(&bfr[..10]).count(), 0);
+    }
 }
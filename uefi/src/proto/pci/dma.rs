@@ -0,0 +1,400 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! DMA buffers shared between the CPU and PCI bus masters.
+//!
+//! The PCI I/O protocol may implement DMA with bounce buffers, e.g. when the
+//! device can't reach the memory of the buffer or when an IOMMU is used.
+//! Memory the CPU and the device access concurrently, such as descriptor
+//! rings, must therefore be a [`DmaBuffer`], which is allocated and mapped as
+//! a common buffer. Other memory is mapped for a single transfer with a
+//! [`DmaMapping`]: the data for the device is copied to the bounce buffer when
+//! the mapping is created, and the data written by the device is copied back
+//! when it is unmapped.
+
+use super::io::PciIo;
+use crate::mem::memory_map::MemoryType;
+use crate::{Result, Status, StatusExt};
+use core::ffi::c_void;
+use core::fmt::{self, Debug, Formatter};
+use core::marker::PhantomData;
+use core::ptr::{self, NonNull};
+use core::slice;
+use uefi_raw::protocol::pci::io::PciIoProtocolOperation;
+use uefi_raw::table::boot::{AllocateType, PAGE_SIZE};
+
+/// Attribute allowing bus masters to access addresses above 4 GiB.
+const ATTRIBUTE_DUAL_ADDRESS_CYCLE: u64 = 0x8000;
+
+/// Direction of the transfer done with a [`DmaMapping`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DmaDirection {
+    /// The device reads from the buffer.
+    ToDevice,
+    /// The device writes to the buffer.
+    FromDevice,
+}
+
+/// Range of device addresses a bus master can access.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum DmaAddressing {
+    /// The device only supports 32-bit addresses, so the buffer is
+    /// allocated below 4 GiB.
+    #[default]
+    Below4GiB,
+    /// The device supports 64-bit addresses. The dual address cycle
+    /// attribute must be enabled on the device.
+    Any,
+}
+
+/// A buffer allocated for DMA, and mapped as a common buffer for the bus
+/// master behind a [`PciIo`].
+///
+/// The buffer is allocated with the PCI I/O protocol's `AllocateBuffer`,
+/// which satisfies the device's alignment and addressing constraints, then
+/// mapped with `Map` as a common buffer, so that the CPU and the device see
+/// the same memory for as long as the buffer lives. Both the CPU address,
+/// accessible through the slice views, and the device address to program
+/// into the device are kept. Dropping the buffer unmaps then frees it.
+///
+/// The device must be done with the buffer before it is dropped.
+pub struct DmaBuffer<'a> {
+    pci_io: &'a PciIo,
+    host: NonNull<u8>,
+    len: usize,
+    pages: usize,
+    device_address: u64,
+    mapping: *mut c_void,
+}
+
+impl<'a> DmaBuffer<'a> {
+    /// Allocates and maps a zeroed buffer of `len` bytes.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::OUT_OF_RESOURCES`]: the buffer could not be allocated, or
+    ///   only part of it could be mapped.
+    /// * [`Status::UNSUPPORTED`]: the device doesn't support the addressing
+    ///   mode.
+    /// * [`Status::DEVICE_ERROR`]: the buffer could not be mapped.
+    pub fn new(pci_io: &'a PciIo, len: usize, addressing: DmaAddressing) -> Result<Self> {
+        let proto = pci_io.raw();
+        let pages = len.div_ceil(PAGE_SIZE).max(1);
+        let attributes = match addressing {
+            DmaAddressing::Below4GiB => 0,
+            DmaAddressing::Any => ATTRIBUTE_DUAL_ADDRESS_CYCLE,
+        };
+        let mut host: *const c_void = ptr::null();
+        // SAFETY: `host` is a valid pointer for the allocated address.
+        unsafe {
+            (proto.allocate_buffer)(
+                proto,
+                AllocateType::ANY_PAGES,
+                MemoryType::BOOT_SERVICES_DATA,
+                pages,
+                &mut host,
+                attributes,
+            )
+        }
+        .to_result()?;
+        let host = NonNull::new(host.cast_mut().cast::<u8>()).ok_or(Status::OUT_OF_RESOURCES)?;
+        // SAFETY: the allocation spans `pages` pages.
+        unsafe { host.write_bytes(0, pages * PAGE_SIZE) };
+
+        // SAFETY: the allocation is valid for `len` bytes, and stays mapped
+        // until it is unmapped by `drop`.
+        let mapped = unsafe {
+            map(
+                pci_io,
+                PciIoProtocolOperation::BUS_MASTER_COMMON_BUFFER,
+                host.as_ptr(),
+                len,
+            )
+        };
+        let (device_address, mapping) = match mapped {
+            Ok(mapped) => mapped,
+            Err(err) => {
+                // SAFETY: the buffer was allocated above with `pages` pages.
+                let _ = unsafe { (proto.free_buffer)(proto, pages, host.as_ptr().cast()) };
+                return Err(err);
+            }
+        };
+
+        Ok(Self {
+            pci_io,
+            host,
+            len,
+            pages,
+            device_address,
+            mapping,
+        })
+    }
+
+    /// Returns the address of the buffer as seen by the device.
+    #[must_use]
+    pub const fn device_address(&self) -> u64 {
+        self.device_address
+    }
+
+    /// Returns the size of the buffer in bytes.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the buffer is empty.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the buffer contents.
+    ///
+    /// Call [`flush`] after the device wrote to the buffer.
+    ///
+    /// [`flush`]: Self::flush
+    #[must_use]
+    pub const fn as_slice(&self) -> &[u8] {
+        // SAFETY: the buffer is valid for `len` bytes while `self` lives.
+        unsafe { slice::from_raw_parts(self.host.as_ptr(), self.len) }
+    }
+
+    /// Returns the buffer contents mutably.
+    #[must_use]
+    pub const fn as_mut_slice(&mut self) -> &mut [u8] {
+        // SAFETY: the buffer is valid for `len` bytes while `self` lives.
+        unsafe { slice::from_raw_parts_mut(self.host.as_ptr(), self.len) }
+    }
+
+    /// Flushes posted writes of the device to the buffer, so that they are
+    /// visible to the CPU.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::DEVICE_ERROR`]: the writes could not be flushed.
+    pub fn flush(&mut self) -> Result {
+        flush(self.pci_io)
+    }
+}
+
+impl Drop for DmaBuffer<'_> {
+    fn drop(&mut self) {
+        let proto = self.pci_io.raw();
+        // SAFETY: the mapping and the allocation were created by `new` and
+        // are not used after this. The caller ensures that the device is
+        // done with the buffer.
+        unsafe {
+            let _ = (proto.unmap)(proto, self.mapping);
+            let _ = (proto.free_buffer)(proto, self.pages, self.host.as_ptr().cast());
+        }
+    }
+}
+
+impl Debug for DmaBuffer<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DmaBuffer")
+            .field("host", &self.host)
+            .field("device_address", &self.device_address)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+/// A buffer mapped for a single transfer by the bus master behind a
+/// [`PciIo`].
+///
+/// The buffer is borrowed for as long as it is mapped, as the CPU must not
+/// access it during the transfer. For [`DmaDirection::ToDevice`], the data
+/// is passed to the device when the mapping is created. For
+/// [`DmaDirection::FromDevice`], the data written by the device is only
+/// visible in the buffer once it is unmapped, with [`unmap`] or by dropping
+/// the mapping.
+///
+/// The device must be done with the transfer before the mapping is
+/// unmapped.
+///
+/// [`unmap`]: Self::unmap
+pub struct DmaMapping<'a> {
+    pci_io: &'a PciIo,
+    device_address: u64,
+    len: usize,
+    mapping: *mut c_void,
+    direction: DmaDirection,
+    _buffer: PhantomData<&'a mut [u8]>,
+}
+
+impl<'a> DmaMapping<'a> {
+    /// Maps `buffer` for the device to read from it.
+    ///
+    /// # Errors
+    ///
+    /// See [`from_device`].
+    ///
+    /// [`from_device`]: Self::from_device
+    pub fn to_device(pci_io: &'a PciIo, buffer: &'a [u8]) -> Result<Self> {
+        // SAFETY: the buffer is valid for its length, and is borrowed until
+        // the mapping is dropped. The device only reads from it.
+        unsafe {
+            Self::new(
+                pci_io,
+                PciIoProtocolOperation::BUS_MASTER_READ,
+                buffer.as_ptr().cast_mut(),
+                buffer.len(),
+                DmaDirection::ToDevice,
+            )
+        }
+    }
+
+    /// Maps `buffer` for the device to write to it.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::OUT_OF_RESOURCES`]: only part of the buffer could be
+    ///   mapped, or no bounce buffer could be allocated.
+    /// * [`Status::UNSUPPORTED`]: the buffer can't be mapped for the device.
+    /// * [`Status::DEVICE_ERROR`]: the buffer could not be mapped.
+    pub fn from_device(pci_io: &'a PciIo, buffer: &'a mut [u8]) -> Result<Self> {
+        // SAFETY: the buffer is valid for its length, and is mutably
+        // borrowed until the mapping is dropped.
+        unsafe {
+            Self::new(
+                pci_io,
+                PciIoProtocolOperation::BUS_MASTER_WRITE,
+                buffer.as_mut_ptr(),
+                buffer.len(),
+                DmaDirection::FromDevice,
+            )
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `host` must be valid for `len` bytes for as long as `'a`.
+    unsafe fn new(
+        pci_io: &'a PciIo,
+        operation: PciIoProtocolOperation,
+        host: *mut u8,
+        len: usize,
+        direction: DmaDirection,
+    ) -> Result<Self> {
+        // SAFETY: ensured by the caller.
+        let (device_address, mapping) = unsafe { map(pci_io, operation, host, len) }?;
+        Ok(Self {
+            pci_io,
+            device_address,
+            len,
+            mapping,
+            direction,
+            _buffer: PhantomData,
+        })
+    }
+
+    /// Returns the address of the buffer as seen by the device.
+    #[must_use]
+    pub const fn device_address(&self) -> u64 {
+        self.device_address
+    }
+
+    /// Returns the size of the buffer in bytes.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the buffer is empty.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the direction the buffer was mapped for.
+    #[must_use]
+    pub const fn direction(&self) -> DmaDirection {
+        self.direction
+    }
+
+    /// Unmaps the buffer, which completes the transfer. For
+    /// [`DmaDirection::FromDevice`], the data written by the device is then
+    /// in the buffer.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::DEVICE_ERROR`]: the data could not be committed to the
+    ///   buffer.
+    pub fn unmap(self) -> Result {
+        let this = core::mem::ManuallyDrop::new(self);
+        // SAFETY: the mapping is not used after this.
+        unsafe { this.unmap_raw() }
+    }
+
+    /// # Safety
+    ///
+    /// The mapping must not be used after this.
+    unsafe fn unmap_raw(&self) -> Result {
+        let proto = self.pci_io.raw();
+        // SAFETY: the mapping was created by `new`, and ensured by the
+        // caller not to be used again.
+        unsafe { (proto.unmap)(proto, self.mapping) }.to_result()
+    }
+}
+
+impl Drop for DmaMapping<'_> {
+    fn drop(&mut self) {
+        // SAFETY: the mapping is not used after this.
+        let _ = unsafe { self.unmap_raw() };
+    }
+}
+
+impl Debug for DmaMapping<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DmaMapping")
+            .field("device_address", &self.device_address)
+            .field("len", &self.len)
+            .field("direction", &self.direction)
+            .finish()
+    }
+}
+
+/// Maps `len` bytes at `host` for `operation`, returning the device address
+/// and the mapping. Fails with [`Status::OUT_OF_RESOURCES`] if only part of
+/// the buffer could be mapped.
+///
+/// # Safety
+///
+/// `host` must be valid for `len` bytes until the mapping is unmapped.
+unsafe fn map(
+    pci_io: &PciIo,
+    operation: PciIoProtocolOperation,
+    host: *mut u8,
+    len: usize,
+) -> Result<(u64, *mut c_void)> {
+    let proto = pci_io.raw();
+    let mut mapped = len;
+    let mut device_address = 0;
+    let mut mapping = ptr::null_mut();
+    // SAFETY: ensured by the caller.
+    let status = unsafe {
+        (proto.map)(
+            proto,
+            operation,
+            host.cast(),
+            &mut mapped,
+            &mut device_address,
+            &mut mapping,
+        )
+    };
+    match status {
+        Status::SUCCESS if mapped < len => {
+            // SAFETY: the mapping was just created and is not used again.
+            let _ = unsafe { (proto.unmap)(proto, mapping) };
+            Err(Status::OUT_OF_RESOURCES.into())
+        }
+        status => status.to_result_with_val(|| (device_address, mapping)),
+    }
+}
+
+/// Flushes posted writes of the device to system memory.
+fn flush(pci_io: &PciIo) -> Result {
+    let proto = ptr::from_ref(pci_io.raw()).cast_mut();
+    // SAFETY: flushing doesn't modify the protocol interface.
+    unsafe { ((*proto).flush)(proto) }.to_result()
+}
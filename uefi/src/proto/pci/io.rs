@@ -0,0 +1,75 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! PCI I/O protocol.
+
+use super::dma::{DmaAddressing, DmaBuffer};
+use super::option_rom::OptionRom;
+use super::{FullPciIoAddress, PciIoAddress};
+use crate::StatusExt;
+use core::slice;
+use uefi_macros::unsafe_protocol;
+use uefi_raw::protocol::pci::io::PciIoProtocol;
+
+/// Protocol that provides access to a PCI controller.
+///
+/// # UEFI Spec Description
+/// Provides the basic Memory, I/O, PCI configuration, and DMA interfaces that
+/// are used to abstract accesses to PCI controllers.
+#[derive(Debug)]
+#[repr(transparent)]
+#[unsafe_protocol(PciIoProtocol::GUID)]
+pub struct PciIo(PciIoProtocol);
+
+impl PciIo {
+    /// Returns the underlying raw protocol.
+    pub(crate) const fn raw(&self) -> &PciIoProtocol {
+        &self.0
+    }
+
+    /// Returns the location of the PCI controller.
+    pub fn location(&self) -> crate::Result<FullPciIoAddress> {
+        let (mut segment, mut bus, mut dev, mut fun) = (0, 0, 0, 0);
+        unsafe { (self.0.get_location)(&self.0, &mut segment, &mut bus, &mut dev, &mut fun) }
+            .to_result_with_val(|| {
+                FullPciIoAddress::new(
+                    segment as u32,
+                    PciIoAddress::new(bus as u8, dev as u8, fun as u8),
+                )
+            })
+    }
+
+    /// Returns the copy of the option ROM of the PCI controller, or `None` if
+    /// it has no option ROM.
+    ///
+    /// The copy is made by the PCI bus driver, and remains valid as long as
+    /// the protocol is installed.
+    #[must_use]
+    pub const fn rom_image(&self) -> Option<&[u8]> {
+        if self.0.rom_image.is_null() || self.0.rom_size == 0 {
+            return None;
+        }
+        Some(unsafe { slice::from_raw_parts(self.0.rom_image.cast(), self.0.rom_size as usize) })
+    }
+
+    /// Returns the option ROM of the PCI controller, or `None` if it has no
+    /// option ROM. See [`Self::rom_image`].
+    #[must_use]
+    pub fn option_rom(&self) -> Option<OptionRom<'_>> {
+        self.rom_image().map(OptionRom::new)
+    }
+
+    /// Allocates a zeroed buffer of `len` bytes, and maps it as a common
+    /// buffer for DMA by the device.
+    ///
+    /// See [`DmaBuffer::new`].
+    ///
+    /// # Errors
+    /// See [`DmaBuffer::new`].
+    pub fn dma_buffer(
+        &self,
+        len: usize,
+        addressing: DmaAddressing,
+    ) -> crate::Result<DmaBuffer<'_>> {
+        DmaBuffer::new(self, len, addressing)
+    }
+}
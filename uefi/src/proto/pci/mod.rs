@@ -7,8 +7,11 @@ use core::cmp::Ordering;
 use uefi_raw::protocol::pci::root_bridge::PciRootBridgeIoProtocolWidth;
 
 pub mod configuration;
+pub mod dma;
 #[cfg(feature = "alloc")]
 mod enumeration;
+pub mod io;
+pub mod option_rom;
 pub mod root_bridge;
 
 /// IO Address for PCI/register IO operations
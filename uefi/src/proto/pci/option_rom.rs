@@ -0,0 +1,390 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! PCI option ROM images.
+//!
+//! An option ROM contains one or more images, e.g. a legacy BIOS image
+//! followed by an EFI driver. Each image starts with a PCI expansion ROM
+//! header and contains a PCI data structure describing the image. The
+//! option ROM of a PCI controller is available from
+//! [`PciIo::option_rom`].
+//!
+//! [`PciIo::option_rom`]: super::io::PciIo::option_rom
+
+use core::fmt::{self, Debug, Formatter};
+use uefi_raw::newtype_enum;
+
+/// Signature at the start of every image of an option ROM.
+pub const PCI_EXPANSION_ROM_SIGNATURE: u16 = 0xaa55;
+
+/// Signature of the PCI data structure of an image.
+pub const PCI_DATA_STRUCTURE_SIGNATURE: [u8; 4] = *b"PCIR";
+
+/// Signature of the EFI expansion ROM header.
+pub const EFI_PCI_EXPANSION_ROM_SIGNATURE: u32 = 0x0ef1;
+
+/// Images are sized in units of 512 bytes.
+const ROM_UNIT: usize = 512;
+
+/// Size of the PCI data structure, up to and including the indicator.
+const PCI_DATA_STRUCTURE_SIZE: usize = 0x16;
+
+/// Last-image bit of the indicator in the PCI data structure.
+const LAST_IMAGE_INDICATOR: u8 = 0x80;
+
+newtype_enum! {
+    /// Type of the code of an option ROM image.
+    pub enum RomCodeType: u8 => {
+        /// Intel x86, PC-AT compatible (legacy BIOS).
+        PC_AT = 0x00,
+        /// Open Firmware standard for PCI.
+        OPEN_FIRMWARE = 0x01,
+        /// Hewlett-Packard PA RISC.
+        PA_RISC = 0x02,
+        /// EFI image.
+        EFI = 0x03,
+    }
+}
+
+newtype_enum! {
+    /// Compression of the PE/COFF image in an EFI option ROM image.
+    pub enum EfiRomCompression: u16 => {
+        /// The image is not compressed.
+        NONE = 0x0000,
+        /// The image is compressed with the EFI compression algorithm.
+        EFI = 0x0001,
+    }
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        bytes.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+/// An option ROM, consisting of one or more [`RomImage`]s.
+#[derive(Clone, Copy)]
+pub struct OptionRom<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> OptionRom<'a> {
+    /// Creates an option ROM from its raw bytes.
+    #[must_use]
+    pub const fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    /// Returns the raw bytes.
+    #[must_use]
+    pub const fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Returns an iterator over the images.
+    ///
+    /// Like firmware does when loading an option ROM, the iteration stops
+    /// after the image marked as last, or at the first image with an
+    /// invalid header.
+    #[must_use]
+    pub const fn images(&self) -> RomImages<'a> {
+        RomImages {
+            rom: self.bytes,
+            offset: 0,
+            done: false,
+        }
+    }
+
+    /// Returns an iterator over the EFI images.
+    pub fn efi_images(&self) -> impl Iterator<Item = EfiRomImage<'a>> + use<'a> {
+        self.images().filter_map(|image| image.efi())
+    }
+}
+
+impl Debug for OptionRom<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.images()).finish()
+    }
+}
+
+/// Iterator over the images of an [`OptionRom`].
+#[derive(Clone, Debug)]
+pub struct RomImages<'a> {
+    rom: &'a [u8],
+    offset: usize,
+    done: bool,
+}
+
+impl<'a> Iterator for RomImages<'a> {
+    type Item = RomImage<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let image = RomImage::parse(self.rom, self.offset);
+        match &image {
+            Some(image) if !image.is_last() => self.offset += image.bytes.len(),
+            _ => self.done = true,
+        }
+        image
+    }
+}
+
+/// An image of an [`OptionRom`].
+#[derive(Clone, Copy)]
+pub struct RomImage<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    pcir: usize,
+}
+
+impl<'a> RomImage<'a> {
+    /// Parses the image at `offset` of `rom`. Returns `None` if the headers
+    /// are invalid or the image exceeds the ROM.
+    #[must_use]
+    pub fn parse(rom: &'a [u8], offset: usize) -> Option<Self> {
+        let bytes = rom.get(offset..)?;
+        if read_u16(bytes, 0x00)? != PCI_EXPANSION_ROM_SIGNATURE {
+            return None;
+        }
+        let pcir = usize::from(read_u16(bytes, 0x18)?);
+        if bytes.get(pcir..pcir + 4)? != PCI_DATA_STRUCTURE_SIGNATURE {
+            return None;
+        }
+        let len = usize::from(read_u16(bytes, pcir + 0x10)?) * ROM_UNIT;
+        let bytes = bytes.get(..len)?;
+        if pcir + PCI_DATA_STRUCTURE_SIZE > len {
+            return None;
+        }
+        Some(Self {
+            bytes,
+            offset,
+            pcir,
+        })
+    }
+
+    /// Returns the raw bytes of the image.
+    #[must_use]
+    pub const fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Returns the offset of the image in the option ROM.
+    #[must_use]
+    pub const fn offset(&self) -> usize {
+        self.offset
+    }
+
+    const fn pcir_u16(&self, offset: usize) -> u16 {
+        u16::from_le_bytes([
+            self.bytes[self.pcir + offset],
+            self.bytes[self.pcir + offset + 1],
+        ])
+    }
+
+    /// Returns the vendor ID of the devices supported by the image.
+    #[must_use]
+    pub const fn vendor_id(&self) -> u16 {
+        self.pcir_u16(0x04)
+    }
+
+    /// Returns the device ID of the devices supported by the image.
+    #[must_use]
+    pub const fn device_id(&self) -> u16 {
+        self.pcir_u16(0x06)
+    }
+
+    /// Returns the class code, with the base class in bits 16 to 23, the
+    /// subclass in bits 8 to 15 and the programming interface in bits 0 to 7.
+    #[must_use]
+    pub fn class_code(&self) -> u32 {
+        let class = &self.bytes[self.pcir + 0x0d..self.pcir + 0x10];
+        u32::from_le_bytes([class[0], class[1], class[2], 0])
+    }
+
+    /// Returns the revision of the PCI data structure.
+    #[must_use]
+    pub const fn revision(&self) -> u8 {
+        self.bytes[self.pcir + 0x0c]
+    }
+
+    /// Returns the vendor-defined revision of the code.
+    #[must_use]
+    pub const fn code_revision(&self) -> u16 {
+        self.pcir_u16(0x12)
+    }
+
+    /// Returns the type of the code.
+    #[must_use]
+    pub const fn code_type(&self) -> RomCodeType {
+        RomCodeType(self.bytes[self.pcir + 0x14])
+    }
+
+    /// Returns whether this is the last image of the option ROM.
+    #[must_use]
+    pub const fn is_last(&self) -> bool {
+        self.bytes[self.pcir + 0x15] & LAST_IMAGE_INDICATOR != 0
+    }
+
+    /// Returns the image as EFI image, or `None` if it is not an EFI image.
+    #[must_use]
+    pub fn efi(&self) -> Option<EfiRomImage<'a>> {
+        if self.code_type() != RomCodeType::EFI {
+            return None;
+        }
+        let signature = u32::from_le_bytes(self.bytes.get(0x04..0x08)?.try_into().ok()?);
+        (signature == EFI_PCI_EXPANSION_ROM_SIGNATURE).then_some(EfiRomImage { image: *self })
+    }
+}
+
+impl Debug for RomImage<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RomImage")
+            .field("offset", &self.offset)
+            .field("len", &self.bytes.len())
+            .field("vendor_id", &self.vendor_id())
+            .field("device_id", &self.device_id())
+            .field("class_code", &self.class_code())
+            .field("code_type", &self.code_type())
+            .field("is_last", &self.is_last())
+            .finish()
+    }
+}
+
+/// An EFI image of an [`OptionRom`], containing a PE/COFF image.
+#[derive(Clone, Copy)]
+pub struct EfiRomImage<'a> {
+    image: RomImage<'a>,
+}
+
+impl<'a> EfiRomImage<'a> {
+    /// Returns the option ROM image.
+    #[must_use]
+    pub const fn rom_image(&self) -> &RomImage<'a> {
+        &self.image
+    }
+
+    const fn header_u16(&self, offset: usize) -> u16 {
+        u16::from_le_bytes([self.image.bytes[offset], self.image.bytes[offset + 1]])
+    }
+
+    /// Returns the PE/COFF subsystem of the image, e.g. 11 for a boot service
+    /// driver.
+    #[must_use]
+    pub const fn subsystem(&self) -> u16 {
+        self.header_u16(0x08)
+    }
+
+    /// Returns the PE/COFF machine type of the image, e.g. `0x8664` for x64.
+    #[must_use]
+    pub const fn machine_type(&self) -> u16 {
+        self.header_u16(0x0a)
+    }
+
+    /// Returns the compression of the PE/COFF image.
+    #[must_use]
+    pub const fn compression(&self) -> EfiRomCompression {
+        EfiRomCompression(self.header_u16(0x0c))
+    }
+
+    /// Returns the PE/COFF image, which is compressed according to
+    /// [`Self::compression`]. Returns `None` if the image exceeds the option
+    /// ROM image.
+    #[must_use]
+    pub fn pe_image(&self) -> Option<&'a [u8]> {
+        let size = usize::from(self.header_u16(0x02)) * ROM_UNIT;
+        let start = usize::from(self.header_u16(0x16));
+        self.image.bytes.get(start..size)
+    }
+}
+
+impl Debug for EfiRomImage<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EfiRomImage")
+            .field("image", &self.image)
+            .field("subsystem", &self.subsystem())
+            .field("machine_type", &self.machine_type())
+            .field("compression", &self.compression())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes an image header at the start of `image`.
+    fn image(image: &mut [u8], code_type: u8, last: bool) {
+        image[0..2].copy_from_slice(&PCI_EXPANSION_ROM_SIGNATURE.to_le_bytes());
+        image[0x18..0x1a].copy_from_slice(&0x40u16.to_le_bytes());
+        let len = (image.len() / ROM_UNIT) as u16;
+        let pcir = &mut image[0x40..0x40 + 0x18];
+        pcir[0..4].copy_from_slice(&PCI_DATA_STRUCTURE_SIGNATURE);
+        pcir[0x04..0x06].copy_from_slice(&0x1af4u16.to_le_bytes());
+        pcir[0x06..0x08].copy_from_slice(&0x1000u16.to_le_bytes());
+        pcir[0x0a..0x0c].copy_from_slice(&0x18u16.to_le_bytes());
+        pcir[0x0c] = 3;
+        pcir[0x0d..0x10].copy_from_slice(&[0x00, 0x00, 0x02]);
+        pcir[0x10..0x12].copy_from_slice(&len.to_le_bytes());
+        pcir[0x12..0x14].copy_from_slice(&0x0102u16.to_le_bytes());
+        pcir[0x14] = code_type;
+        pcir[0x15] = if last { LAST_IMAGE_INDICATOR } else { 0 };
+    }
+
+    #[test]
+    fn test_option_rom() {
+        let mut rom = [0u8; 3 * ROM_UNIT];
+        image(&mut rom[..ROM_UNIT], RomCodeType::PC_AT.0, false);
+        let efi = &mut rom[ROM_UNIT..];
+        image(efi, RomCodeType::EFI.0, true);
+        efi[0x02..0x04].copy_from_slice(&2u16.to_le_bytes());
+        efi[0x04..0x08].copy_from_slice(&EFI_PCI_EXPANSION_ROM_SIGNATURE.to_le_bytes());
+        efi[0x08..0x0a].copy_from_slice(&11u16.to_le_bytes());
+        efi[0x0a..0x0c].copy_from_slice(&0x8664u16.to_le_bytes());
+        efi[0x0c..0x0e].copy_from_slice(&1u16.to_le_bytes());
+        efi[0x16..0x18].copy_from_slice(&0x60u16.to_le_bytes());
+        efi[0x60..0x62].copy_from_slice(b"MZ");
+
+        let rom = OptionRom::new(&rom);
+        let mut images = rom.images();
+        let legacy = images.next().unwrap();
+        assert_eq!(legacy.offset(), 0);
+        assert_eq!(legacy.bytes().len(), ROM_UNIT);
+        assert_eq!(legacy.vendor_id(), 0x1af4);
+        assert_eq!(legacy.device_id(), 0x1000);
+        assert_eq!(legacy.class_code(), 0x02_0000);
+        assert_eq!(legacy.revision(), 3);
+        assert_eq!(legacy.code_revision(), 0x0102);
+        assert_eq!(legacy.code_type(), RomCodeType::PC_AT);
+        assert!(!legacy.is_last());
+        assert!(legacy.efi().is_none());
+
+        let last = images.next().unwrap();
+        assert_eq!(last.offset(), ROM_UNIT);
+        assert!(last.is_last());
+        assert!(images.next().is_none());
+
+        let efi = rom.efi_images().next().unwrap();
+        assert_eq!(efi.rom_image().offset(), ROM_UNIT);
+        assert_eq!(efi.subsystem(), 11);
+        assert_eq!(efi.machine_type(), 0x8664);
+        assert_eq!(efi.compression(), EfiRomCompression::EFI);
+        let pe = efi.pe_image().unwrap();
+        assert_eq!(pe.len(), 2 * ROM_UNIT - 0x60);
+        assert!(pe.starts_with(b"MZ"));
+    }
+
+    #[test]
+    fn test_option_rom_invalid() {
+        let mut rom = [0u8; 2 * ROM_UNIT];
+        image(&mut rom[..ROM_UNIT], RomCodeType::PC_AT.0, false);
+        // The second image has no signature, which ends the iteration.
+        assert_eq!(OptionRom::new(&rom).images().count(), 1);
+
+        // An image that exceeds the ROM is rejected.
+        image(&mut rom, RomCodeType::PC_AT.0, true);
+        assert!(RomImage::parse(&rom[..ROM_UNIT], 0).is_none());
+        assert_eq!(OptionRom::new(&rom).images().count(), 1);
+        assert_eq!(OptionRom::new(&[]).images().count(), 0);
+    }
+}
@@ -15,6 +15,7 @@
 
 use crate::data_types::Event;
 use crate::proto::unsafe_protocol;
+use crate::sync::ApContext;
 use crate::{Result, Status, StatusExt};
 use bitflags::bitflags;
 use core::ffi::c_void;
@@ -223,6 +224,41 @@ impl MpServices {
         .to_result()
     }
 
+    /// Runs `f` on all enabled APs, blocking until all of them finished.
+    ///
+    /// If `single_thread` is true, the APs run `f` one after another,
+    /// otherwise simultaneously. `f` must not call boot services; use the
+    /// primitives in [`uefi::sync`] to share state between the processors.
+    ///
+    /// [`uefi::sync`]: crate::sync
+    pub fn run_on_aps<F>(&self, single_thread: bool, f: &F, timeout: Option<Duration>) -> Result
+    where
+        F: Fn(&ApContext) + Sync,
+    {
+        let argument = ptr::from_ref(f).cast_mut().cast();
+        self.startup_all_aps(single_thread, ap_trampoline::<F>, argument, None, timeout)
+    }
+
+    /// Runs `f` on a specific AP, blocking until it finished.
+    ///
+    /// `f` must not call boot services; use the primitives in [`uefi::sync`]
+    /// to share state between the processors.
+    ///
+    /// [`uefi::sync`]: crate::sync
+    pub fn run_on_ap<F>(&self, processor_number: usize, f: &F, timeout: Option<Duration>) -> Result
+    where
+        F: Fn(&ApContext) + Sync,
+    {
+        let argument = ptr::from_ref(f).cast_mut().cast();
+        self.startup_this_ap(
+            processor_number,
+            ap_trampoline::<F>,
+            argument,
+            None,
+            timeout,
+        )
+    }
+
     /// Switches the requested AP to be the BSP from that point onward.
     pub fn switch_bsp(&self, processor_number: usize, enable_old_bsp: bool) -> Result {
         (self.switch_bsp)(self, processor_number, enable_old_bsp).to_result()
@@ -256,3 +292,12 @@ impl MpServices {
         (self.who_am_i)(self, &mut processor_number).to_result_with_val(|| processor_number)
     }
 }
+
+/// Calls the closure passed to [`MpServices::run_on_aps`] or
+/// [`MpServices::run_on_ap`] on an AP.
+extern "efiapi" fn ap_trampoline<F: Fn(&ApContext) + Sync>(argument: *mut c_void) {
+    // SAFETY: `argument` points to the closure, which outlives the blocking
+    // call, and this is running on an AP.
+    let (f, context) = unsafe { (&*argument.cast::<F>(), ApContext::new()) };
+    f(&context);
+}
@@ -0,0 +1,43 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Helpers for protocols implemented in Rust, whose interface is stored in a
+//! `static` provider.
+//!
+//! A provider is a `repr(C)` struct whose first field is the raw protocol
+//! structure, followed by the data used by its functions. The provider is
+//! installed by passing a pointer to that field, and the functions get the
+//! provider back by casting the `this` pointer they receive.
+
+use crate::{Guid, Handle, Result, boot};
+use core::ptr;
+
+/// Installs `interface` for the protocol `guid` on `handle`, or on a new
+/// handle if `handle` is `None`, and returns the handle.
+pub(crate) fn install_static<T>(
+    handle: Option<Handle>,
+    guid: &Guid,
+    interface: &'static T,
+) -> Result<Handle> {
+    // SAFETY: the interface is valid for the lifetime of the program, and
+    // the caller passes the protocol structure identified by `guid`.
+    unsafe { boot::install_protocol_interface(handle, guid, ptr::from_ref(interface).cast()) }
+}
+
+/// Uninstalls `interface`, previously installed with [`install_static`],
+/// for the protocol `guid` from `handle`.
+pub(crate) fn uninstall_static<T>(handle: Handle, guid: &Guid, interface: &'static T) -> Result {
+    // SAFETY: the firmware only removes `interface` if it is installed on
+    // `handle`; it is never freed, so it remains valid for agents that still
+    // hold it if the uninstall fails.
+    unsafe { boot::uninstall_protocol_interface(handle, guid, ptr::from_ref(interface).cast()) }
+}
+
+/// Returns the provider whose protocol structure `this` points to.
+///
+/// # Safety
+///
+/// `this` must point to the first field of a `repr(C)` provider of type
+/// `P`, such as one installed with [`install_static`].
+pub(crate) const unsafe fn provider_from_protocol<'a, P, T>(this: *const T) -> &'a P {
+    unsafe { &*this.cast::<P>() }
+}
@@ -3,6 +3,7 @@
 //! EFI Shell Protocol v2.2
 
 use crate::proto::unsafe_protocol;
+use crate::runtime::VariableAttributes;
 use crate::{CStr16, Char16, Error, Result, Status, StatusExt};
 
 use core::marker::PhantomData;
@@ -119,6 +120,52 @@ impl Shell {
         }
     }
 
+    /// Gets the value and attributes of the specified environment variable.
+    ///
+    /// This requires version 2.2 or newer of the shell protocol.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The environment variable name of which to retrieve the
+    ///   value.
+    ///
+    /// # Returns
+    ///
+    /// * `(<env_value>, <attributes>)` - &CStr16 containing the value of the
+    ///   environment variable and its attributes.
+    ///   [`VariableAttributes::NON_VOLATILE`] is set for non-volatile
+    ///   variables.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::NOT_FOUND`]: the environment variable does not exist.
+    /// * [`Status::UNSUPPORTED`]: the shell protocol is older than 2.2; use
+    ///   [`var`] instead.
+    ///
+    /// [`var`]: ShellVarProvider::var
+    pub fn var_ex(&self, name: &CStr16) -> Result<(&CStr16, VariableAttributes)> {
+        if !self.is_at_least(2, 2) {
+            return Err(Status::UNSUPPORTED.into());
+        }
+        let name_ptr: *const Char16 = name.as_ptr();
+        let mut attributes = 0;
+        let var_val = unsafe { (self.0.get_env_ex)(name_ptr.cast(), &mut attributes) };
+        if var_val.is_null() {
+            Err(Status::NOT_FOUND.into())
+        } else {
+            let attributes = VariableAttributes::from_bits_retain(attributes);
+            unsafe { Ok((CStr16::from_ptr(var_val.cast()), attributes)) }
+        }
+    }
+
+    /// Returns whether the version of the protocol is at least
+    /// `major.minor`. Functions added in later versions are missing from
+    /// older protocol structures.
+    const fn is_at_least(&self, major: u32, minor: u32) -> bool {
+        self.0.major_version > major
+            || (self.0.major_version == major && self.0.minor_version >= minor)
+    }
+
     /// Gets an iterator over the names of all environment variables
     ///
     /// # Returns
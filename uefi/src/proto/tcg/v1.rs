@@ -345,7 +345,8 @@ pub struct StatusCheck<'a> {
 impl Tcg {
     /// Get information about the protocol and TPM device, as well as
     /// the TPM event log.
-    pub fn status_check(&mut self) -> Result<StatusCheck<'_>> {
+    pub fn status_check(&self) -> Result<StatusCheck<'_>> {
+        let this: *const _ = &self.0;
         let mut protocol_capability = TcgBootServiceCapability::default();
         let mut feature_flags = 0;
         let mut event_log_location = 0;
@@ -353,7 +354,7 @@ impl Tcg {
 
         let status = unsafe {
             (self.0.status_check)(
-                &mut self.0,
+                this.cast_mut(),
                 &mut protocol_capability,
                 &mut feature_flags,
                 &mut event_log_location,
@@ -555,24 +555,26 @@ pub struct Tcg(Tcg2Protocol);
 
 impl Tcg {
     /// Get information about the protocol and TPM device.
-    pub fn get_capability(&mut self) -> Result<BootServiceCapability> {
+    pub fn get_capability(&self) -> Result<BootServiceCapability> {
+        let this: *const _ = &self.0;
         let mut capability = BootServiceCapability::default();
         unsafe {
-            (self.0.get_capability)(&mut self.0, ptr::from_mut(&mut capability).cast())
+            (self.0.get_capability)(this.cast_mut(), ptr::from_mut(&mut capability).cast())
                 .to_result_with_val(|| capability)
         }
     }
 
     /// Get the V1 event log. This provides events in the same format as a V1
     /// TPM, so all events use SHA-1 hashes.
-    pub fn get_event_log_v1(&mut self) -> Result<v1::EventLog<'_>> {
+    pub fn get_event_log_v1(&self) -> Result<v1::EventLog<'_>> {
+        let this: *const _ = &self.0;
         let mut location = 0;
         let mut last_entry = 0;
         let mut truncated = 0;
 
         let status = unsafe {
             (self.0.get_event_log)(
-                &mut self.0,
+                this.cast_mut(),
                 EventLogFormat::TCG_1_2,
                 &mut location,
                 &mut last_entry,
@@ -594,14 +596,15 @@ impl Tcg {
     }
 
     /// Get the V2 event log. This format allows for a flexible list of hash types.
-    pub fn get_event_log_v2(&mut self) -> Result<EventLog<'_>> {
+    pub fn get_event_log_v2(&self) -> Result<EventLog<'_>> {
+        let this: *const _ = &self.0;
         let mut location = 0;
         let mut last_entry = 0;
         let mut truncated = 0;
 
         let status = unsafe {
             (self.0.get_event_log)(
-                &mut self.0,
+                this.cast_mut(),
                 EventLogFormat::TCG_2,
                 &mut location,
                 &mut last_entry,
@@ -680,10 +683,12 @@ impl Tcg {
 
     /// Get a bitmap of the active PCR banks. Each bank corresponds to a hash
     /// algorithm.
-    pub fn get_active_pcr_banks(&mut self) -> Result<HashAlgorithm> {
+    pub fn get_active_pcr_banks(&self) -> Result<HashAlgorithm> {
+        let this: *const _ = &self.0;
         let mut active_pcr_banks = Tcg2HashAlgorithmBitmap::empty();
 
-        let status = unsafe { (self.0.get_active_pcr_banks)(&mut self.0, &mut active_pcr_banks) };
+        let status =
+            unsafe { (self.0.get_active_pcr_banks)(this.cast_mut(), &mut active_pcr_banks) };
 
         status.to_result_with_val(|| HashAlgorithm::from_bits_retain(active_pcr_banks.bits()))
     }
@@ -705,13 +710,14 @@ impl Tcg {
     /// * `0x00000001..=0x00000FFF`: TPM error code
     /// * `0xfffffff0`: The operation was canceled by the user or timed out
     /// * `0xfffffff1`: Firmware error
-    pub fn get_result_of_set_active_pcr_banks(&mut self) -> Result<Option<u32>> {
+    pub fn get_result_of_set_active_pcr_banks(&self) -> Result<Option<u32>> {
+        let this: *const _ = &self.0;
         let mut operation_present = 0;
         let mut response = 0;
 
         let status = unsafe {
             (self.0.get_result_of_set_active_pcr_banks)(
-                &mut self.0,
+                this.cast_mut(),
                 &mut operation_present,
                 &mut response,
             )
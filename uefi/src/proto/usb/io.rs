@@ -219,54 +219,64 @@ impl UsbIo {
 
     /// Returns information about USB devices, including the device's class, subclass, and number
     /// of configurations.
-    pub fn device_descriptor(&mut self) -> Result<DeviceDescriptor> {
+    pub fn device_descriptor(&self) -> Result<DeviceDescriptor> {
+        let this: *const _ = &self.0;
         let mut device_descriptor = unsafe { core::mem::zeroed() };
 
-        unsafe { (self.0.get_device_descriptor)(&mut self.0, &mut device_descriptor) }
+        unsafe { (self.0.get_device_descriptor)(this.cast_mut(), &mut device_descriptor) }
             .to_result_with_val(|| device_descriptor)
     }
 
     /// Returns information about the active configuration of the USB device.
-    pub fn config_descriptor(&mut self) -> Result<ConfigDescriptor> {
+    pub fn config_descriptor(&self) -> Result<ConfigDescriptor> {
+        let this: *const _ = &self.0;
         let mut config_descriptor = unsafe { core::mem::zeroed() };
 
-        unsafe { (self.0.get_config_descriptor)(&mut self.0, &mut config_descriptor) }
+        unsafe { (self.0.get_config_descriptor)(this.cast_mut(), &mut config_descriptor) }
             .to_result_with_val(|| config_descriptor)
     }
 
     /// Returns information about the interface of the USB device.
-    pub fn interface_descriptor(&mut self) -> Result<InterfaceDescriptor> {
+    pub fn interface_descriptor(&self) -> Result<InterfaceDescriptor> {
+        let this: *const _ = &self.0;
         let mut interface_descriptor = unsafe { core::mem::zeroed() };
 
-        unsafe { (self.0.get_interface_descriptor)(&mut self.0, &mut interface_descriptor) }
+        unsafe { (self.0.get_interface_descriptor)(this.cast_mut(), &mut interface_descriptor) }
             .to_result_with_val(|| interface_descriptor)
     }
 
     /// Returns information about the interface of the USB device.
-    pub fn endpoint_descriptor(&mut self, endpoint: u8) -> Result<EndpointDescriptor> {
+    pub fn endpoint_descriptor(&self, endpoint: u8) -> Result<EndpointDescriptor> {
+        let this: *const _ = &self.0;
         let mut endpoint_descriptor = unsafe { core::mem::zeroed() };
 
-        unsafe { (self.0.get_endpoint_descriptor)(&mut self.0, endpoint, &mut endpoint_descriptor) }
-            .to_result_with_val(|| endpoint_descriptor)
+        unsafe {
+            (self.0.get_endpoint_descriptor)(this.cast_mut(), endpoint, &mut endpoint_descriptor)
+        }
+        .to_result_with_val(|| endpoint_descriptor)
     }
 
     /// Returns the string associated with `string_id` in the language associated with `lang_id`.
-    pub fn string_descriptor(&mut self, lang_id: u16, string_id: u8) -> Result<PoolString> {
+    pub fn string_descriptor(&self, lang_id: u16, string_id: u8) -> Result<PoolString> {
+        let this: *const _ = &self.0;
         let mut string_ptr = core::ptr::null_mut();
 
-        unsafe { (self.0.get_string_descriptor)(&mut self.0, lang_id, string_id, &mut string_ptr) }
-            .to_result()?;
+        unsafe {
+            (self.0.get_string_descriptor)(this.cast_mut(), lang_id, string_id, &mut string_ptr)
+        }
+        .to_result()?;
         unsafe { PoolString::new(string_ptr.cast::<Char16>()) }
     }
 
     /// Returns all of the language ID codes that the USB device supports.
-    pub fn supported_languages(&mut self) -> Result<&[u16]> {
+    pub fn supported_languages(&self) -> Result<&[u16]> {
+        let this: *const _ = &self.0;
         let mut lang_id_table_ptr = core::ptr::null_mut();
         let mut lang_id_table_size = 0;
 
         unsafe {
             (self.0.get_supported_languages)(
-                &mut self.0,
+                this.cast_mut(),
                 &mut lang_id_table_ptr,
                 &mut lang_id_table_size,
             )
@@ -0,0 +1,289 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Workarounds for known firmware bugs.
+//!
+//! Some firmware implementations deviate from the UEFI specification in ways
+//! that the wrappers of this crate can work around. Workarounds that cost
+//! something on conforming firmware are only applied if the corresponding
+//! [`Quirks`] flag is active. Quirks are activated:
+//!
+//! * automatically, if the system manufacturer and product name in the
+//!   SMBIOS tables match an entry of the built-in list or one added with
+//!   [`register`],
+//! * manually with [`enable`], e.g. from a command line option.
+//!
+//! [`disable`] turns off a quirk even if it matches the system.
+//!
+//! ```no_run
+//! use uefi::quirks::{self, QuirkEntry, Quirks};
+//!
+//! quirks::register(QuirkEntry {
+//!     manufacturer: "Example Corp.",
+//!     product: "Laptop 3000",
+//!     quirks: Quirks::BROKEN_TIMER_EVENTS,
+//! });
+//! if let Some(system) = quirks::system_id() {
+//!     log::info!("Running on {system}, quirks: {:?}", quirks::active());
+//! }
+//! ```
+
+use crate::smbios::Structures;
+use crate::sync::{OnceCell, SpinLock};
+use crate::table::cfg::ConfigTableEntry;
+use crate::{system, table};
+use alloc::string::String;
+use alloc::vec::Vec;
+use bitflags::bitflags;
+use core::fmt::{self, Display, Formatter};
+use core::slice;
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+bitflags! {
+    /// Known firmware bugs with a workaround in this crate.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    #[repr(transparent)]
+    pub struct Quirks: u32 {
+        /// `GetNextVariableName` fails with `BUFFER_TOO_SMALL` without
+        /// updating the required size. [`runtime::variable_keys`] doubles
+        /// the name buffer and tries again.
+        ///
+        /// [`runtime::variable_keys`]: crate::runtime::variable_keys
+        const VARIABLE_NAME_SIZE_NOT_UPDATED = 1 << 0;
+
+        /// Timer events are not signaled reliably at `TPL_APPLICATION`.
+        /// [`time::delay`] uses the `Stall` boot service instead.
+        ///
+        /// [`time::delay`]: crate::time::delay
+        const BROKEN_TIMER_EVENTS = 1 << 1;
+    }
+}
+
+/// Quirks of the systems matching a manufacturer and product name.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct QuirkEntry {
+    /// System manufacturer in the SMBIOS System Information, compared
+    /// case-insensitively.
+    pub manufacturer: &'static str,
+    /// Prefix of the product name in the SMBIOS System Information, compared
+    /// case-insensitively. An empty prefix matches all products of the
+    /// manufacturer.
+    pub product: &'static str,
+    /// Quirks of the matching systems.
+    pub quirks: Quirks,
+}
+
+impl QuirkEntry {
+    /// Returns whether the entry matches `system`.
+    #[must_use]
+    pub fn matches(&self, system: &SystemId) -> bool {
+        let product = system.product.as_bytes();
+        system.manufacturer.eq_ignore_ascii_case(self.manufacturer)
+            && product.len() >= self.product.len()
+            && product[..self.product.len()].eq_ignore_ascii_case(self.product.as_bytes())
+    }
+}
+
+/// Built-in list of systems with known quirks.
+const KNOWN_QUIRKS: &[QuirkEntry] = &[QuirkEntry {
+    manufacturer: "TOSHIBA",
+    product: "Satellite Pro R50-B",
+    quirks: Quirks::VARIABLE_NAME_SIZE_NOT_UPDATED,
+}];
+
+/// Identity of the system, read from the SMBIOS System Information
+/// structure.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SystemId {
+    /// System manufacturer.
+    pub manufacturer: String,
+    /// Product name.
+    pub product: String,
+}
+
+impl Display for SystemId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.manufacturer, self.product)
+    }
+}
+
+static SYSTEM_ID: OnceCell<Option<SystemId>> = OnceCell::new();
+static REGISTERED: SpinLock<Vec<QuirkEntry>> = SpinLock::new(Vec::new());
+static DETECTED: AtomicU32 = AtomicU32::new(0);
+static DETECTED_VALID: AtomicBool = AtomicBool::new(false);
+static ENABLED: AtomicU32 = AtomicU32::new(0);
+static DISABLED: AtomicU32 = AtomicU32::new(0);
+
+/// Returns the identity of the system from the SMBIOS tables, or `None` if
+/// the firmware provides no SMBIOS tables or they lack the System
+/// Information structure.
+///
+/// The tables are only read on the first call after the system table was
+/// set.
+pub fn system_id() -> Option<&'static SystemId> {
+    table::system_table_raw()?;
+    SYSTEM_ID.get_or_init(read_system_id).as_ref()
+}
+
+/// Adds `entry` to the list of systems with known quirks.
+pub fn register(entry: QuirkEntry) {
+    REGISTERED.lock().push(entry);
+    DETECTED_VALID.store(false, Ordering::Release);
+}
+
+/// Activates `quirks` regardless of the system.
+pub fn enable(quirks: Quirks) {
+    DISABLED.fetch_and(!quirks.bits(), Ordering::AcqRel);
+    ENABLED.fetch_or(quirks.bits(), Ordering::AcqRel);
+}
+
+/// Deactivates `quirks` regardless of the system.
+pub fn disable(quirks: Quirks) {
+    ENABLED.fetch_and(!quirks.bits(), Ordering::AcqRel);
+    DISABLED.fetch_or(quirks.bits(), Ordering::AcqRel);
+}
+
+/// Returns the active quirks.
+#[must_use]
+pub fn active() -> Quirks {
+    let enabled = Quirks::from_bits_retain(ENABLED.load(Ordering::Acquire));
+    let disabled = Quirks::from_bits_retain(DISABLED.load(Ordering::Acquire));
+    (detected() | enabled) - disabled
+}
+
+/// Returns whether all of `quirks` are active.
+#[must_use]
+pub fn is_active(quirks: Quirks) -> bool {
+    active().contains(quirks)
+}
+
+/// Returns the quirks of all entries matching the system.
+fn detected() -> Quirks {
+    if DETECTED_VALID.load(Ordering::Acquire) {
+        return Quirks::from_bits_retain(DETECTED.load(Ordering::Acquire));
+    }
+    if table::system_table_raw().is_none() {
+        return Quirks::empty();
+    }
+    let quirks = system_id().map_or(Quirks::empty(), |system| {
+        KNOWN_QUIRKS
+            .iter()
+            .chain(REGISTERED.lock().iter())
+            .filter(|entry| entry.matches(system))
+            .fold(Quirks::empty(), |quirks, entry| quirks | entry.quirks)
+    });
+    DETECTED.store(quirks.bits(), Ordering::Release);
+    DETECTED_VALID.store(true, Ordering::Release);
+    quirks
+}
+
+/// Finds the SMBIOS structure table in the configuration table, preferring
+/// the SMBIOS 3 entry point.
+fn smbios_table() -> Option<&'static [u8]> {
+    let (smbios3, smbios) = system::with_config_table(|entries| {
+        let find = |guid| {
+            entries
+                .iter()
+                .find(|entry| entry.guid == guid)
+                .map(|entry| entry.address.cast::<u8>())
+        };
+        (
+            find(ConfigTableEntry::SMBIOS3_GUID),
+            find(ConfigTableEntry::SMBIOS_GUID),
+        )
+    });
+
+    // SAFETY: the entry points are valid per the UEFI specification, and
+    // point to the structure table.
+    unsafe {
+        if let Some(entry) = smbios3.filter(|entry| !entry.is_null()) {
+            let entry = slice::from_raw_parts(entry, 24);
+            if entry.starts_with(b"_SM3_") {
+                let len = u32::from_le_bytes(entry[12..16].try_into().unwrap());
+                let address = u64::from_le_bytes(entry[16..24].try_into().unwrap());
+                return Some(slice::from_raw_parts(address as *const u8, len as usize));
+            }
+        }
+        if let Some(entry) = smbios.filter(|entry| !entry.is_null()) {
+            let entry = slice::from_raw_parts(entry, 31);
+            if entry.starts_with(b"_SM_") && entry[16..21] == *b"_DMI_" {
+                let len = u16::from_le_bytes(entry[22..24].try_into().unwrap());
+                let address = u32::from_le_bytes(entry[24..28].try_into().unwrap());
+                return Some(slice::from_raw_parts(address as *const u8, len.into()));
+            }
+        }
+    }
+    None
+}
+
+fn read_system_id() -> Option<SystemId> {
+    parse_system_id(smbios_table()?)
+}
+
+/// Finds the System Information structure (type 1) in the SMBIOS structure
+/// `table`, and returns its manufacturer and product name.
+fn parse_system_id(table: &[u8]) -> Option<SystemId> {
+    let system = Structures::new(table)
+        .map_while(Result::ok)
+        .find(|structure| structure.ty() == Structures::SYSTEM_INFORMATION)?;
+    let formatted = system.formatted();
+    if formatted.len() < 6 {
+        return None;
+    }
+    let string = |index: u8| {
+        system
+            .string(index)
+            .map(|s| String::from_utf8_lossy(s).trim().into())
+            .unwrap_or_default()
+    };
+    Some(SystemId {
+        manufacturer: string(formatted[4]),
+        product: string(formatted[5]),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rustfmt::skip]
+    const TABLE: &[u8] = &[
+        // Type 0 with one string.
+        0, 4, 0, 0, b'B', b'I', b'O', b'S', 0, 0,
+        // Type 1 with manufacturer and product name.
+        1, 8, 1, 0, 1, 2, 0, 0,
+        b'Q', b'E', b'M', b'U', b' ', 0,
+        b'S', b't', b'a', b'n', b'd', b'a', b'r', b'd', b' ', b'P', b'C', 0, 0,
+        // End of table, without strings.
+        127, 4, 2, 0, 0, 0,
+    ];
+
+    #[test]
+    fn test_parse_system_id() {
+        let system = parse_system_id(TABLE).unwrap();
+        assert_eq!(system.manufacturer, "QEMU");
+        assert_eq!(system.product, "Standard PC");
+        assert_eq!(parse_system_id(&TABLE[..10]), None);
+        assert_eq!(parse_system_id(&TABLE[..20]), None);
+    }
+
+    #[test]
+    fn test_matches() {
+        let system = SystemId {
+            manufacturer: "TOSHIBA".into(),
+            product: "Satellite Pro R50-B-12P".into(),
+        };
+        assert!(KNOWN_QUIRKS[0].matches(&system));
+        let entry = QuirkEntry {
+            manufacturer: "toshiba",
+            product: "",
+            quirks: Quirks::BROKEN_TIMER_EVENTS,
+        };
+        assert!(entry.matches(&system));
+        let entry = QuirkEntry {
+            manufacturer: "TOSHIBA",
+            product: "Satellite Pro R50-C",
+            quirks: Quirks::BROKEN_TIMER_EVENTS,
+        };
+        assert!(!entry.matches(&system));
+    }
+}
@@ -0,0 +1,182 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Coarse classification of status codes. See [`ErrorKind`].
+
+use super::{Error, Status};
+use core::fmt::{self, Debug, Display, Formatter};
+
+/// General category of an error, independent of UEFI.
+///
+/// The variants mirror the most common kinds of `std::io::ErrorKind`, so
+/// that code shared between host tools and UEFI applications can handle
+/// errors uniformly regardless of their origin. A [`Status`] is classified
+/// with [`From`], or with [`Error::kind`] for errors.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// An entity was not found, e.g. a file, protocol or variable.
+    NotFound,
+    /// The operation lacked the necessary privileges, or was rejected by
+    /// a security policy.
+    PermissionDenied,
+    /// An entity already exists, or was already started.
+    AlreadyExists,
+    /// The operation needs to be retried later.
+    WouldBlock,
+    /// A parameter was incorrect.
+    InvalidInput,
+    /// Data was not valid for the operation, e.g. corrupted or failing a
+    /// checksum.
+    InvalidData,
+    /// The operation timed out, or the device didn't respond.
+    TimedOut,
+    /// The underlying storage is read-only.
+    ReadOnlyFilesystem,
+    /// The underlying storage is full.
+    StorageFull,
+    /// The operation is not supported.
+    Unsupported,
+    /// The end of a file or medium was reached prematurely.
+    UnexpectedEof,
+    /// Memory or another resource could not be allocated.
+    OutOfMemory,
+    /// A provided buffer is too small for the result.
+    BufferTooSmall,
+    /// The operation was interrupted or aborted.
+    Interrupted,
+    /// The device or network failed.
+    DeviceError,
+    /// A status that doesn't fit another kind, including warnings.
+    Other,
+}
+
+impl ErrorKind {
+    /// Returns a short description of the kind.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::NotFound => "entity not found",
+            Self::PermissionDenied => "permission denied",
+            Self::AlreadyExists => "entity already exists",
+            Self::WouldBlock => "operation would block",
+            Self::InvalidInput => "invalid input parameter",
+            Self::InvalidData => "invalid data",
+            Self::TimedOut => "timed out",
+            Self::ReadOnlyFilesystem => "read-only filesystem or storage medium",
+            Self::StorageFull => "no storage space",
+            Self::Unsupported => "unsupported",
+            Self::UnexpectedEof => "unexpected end of file",
+            Self::OutOfMemory => "out of memory",
+            Self::BufferTooSmall => "buffer too small",
+            Self::Interrupted => "operation interrupted",
+            Self::DeviceError => "device error",
+            Self::Other => "other error",
+        }
+    }
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<Status> for ErrorKind {
+    fn from(status: Status) -> Self {
+        match status {
+            Status::NOT_FOUND | Status::NO_MAPPING | Status::NO_MEDIA => Self::NotFound,
+            Status::ACCESS_DENIED | Status::SECURITY_VIOLATION => Self::PermissionDenied,
+            Status::ALREADY_STARTED | Status::IP_ADDRESS_CONFLICT => Self::AlreadyExists,
+            Status::NOT_READY | Status::NOT_STARTED => Self::WouldBlock,
+            Status::INVALID_PARAMETER | Status::BAD_BUFFER_SIZE | Status::INVALID_LANGUAGE => {
+                Self::InvalidInput
+            }
+            Status::VOLUME_CORRUPTED
+            | Status::CRC_ERROR
+            | Status::COMPROMISED_DATA
+            | Status::INCOMPATIBLE_VERSION
+            | Status::LOAD_ERROR => Self::InvalidData,
+            Status::TIMEOUT | Status::NO_RESPONSE => Self::TimedOut,
+            Status::WRITE_PROTECTED => Self::ReadOnlyFilesystem,
+            Status::VOLUME_FULL => Self::StorageFull,
+            Status::UNSUPPORTED => Self::Unsupported,
+            Status::END_OF_FILE | Status::END_OF_MEDIA => Self::UnexpectedEof,
+            Status::OUT_OF_RESOURCES => Self::OutOfMemory,
+            Status::BUFFER_TOO_SMALL | Status::WARN_BUFFER_TOO_SMALL => Self::BufferTooSmall,
+            Status::ABORTED | Status::MEDIA_CHANGED => Self::Interrupted,
+            Status::DEVICE_ERROR
+            | Status::ICMP_ERROR
+            | Status::TFTP_ERROR
+            | Status::PROTOCOL_ERROR
+            | Status::HTTP_ERROR => Self::DeviceError,
+            _ => Self::Other,
+        }
+    }
+}
+
+impl<Data: Debug> Error<Data> {
+    /// Returns the general category of the error's status.
+    #[must_use]
+    pub fn kind(&self) -> ErrorKind {
+        self.status().into()
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<ErrorKind> for std::io::ErrorKind {
+    fn from(kind: ErrorKind) -> Self {
+        match kind {
+            ErrorKind::NotFound => Self::NotFound,
+            ErrorKind::PermissionDenied => Self::PermissionDenied,
+            ErrorKind::AlreadyExists => Self::AlreadyExists,
+            ErrorKind::WouldBlock => Self::WouldBlock,
+            ErrorKind::InvalidInput => Self::InvalidInput,
+            ErrorKind::InvalidData => Self::InvalidData,
+            ErrorKind::TimedOut => Self::TimedOut,
+            ErrorKind::ReadOnlyFilesystem => Self::ReadOnlyFilesystem,
+            ErrorKind::StorageFull => Self::StorageFull,
+            ErrorKind::Unsupported => Self::Unsupported,
+            ErrorKind::UnexpectedEof => Self::UnexpectedEof,
+            ErrorKind::OutOfMemory => Self::OutOfMemory,
+            ErrorKind::Interrupted => Self::Interrupted,
+            ErrorKind::BufferTooSmall | ErrorKind::DeviceError | ErrorKind::Other => Self::Other,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<Data: Debug + Send + Sync + 'static> From<Error<Data>> for std::io::Error {
+    fn from(err: Error<Data>) -> Self {
+        Self::new(err.kind().into(), err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_kind() {
+        assert_eq!(ErrorKind::from(Status::NOT_FOUND), ErrorKind::NotFound);
+        assert_eq!(
+            ErrorKind::from(Status::WRITE_PROTECTED),
+            ErrorKind::ReadOnlyFilesystem
+        );
+        assert_eq!(ErrorKind::from(Status::WARN_STALE_DATA), ErrorKind::Other);
+        assert_eq!(
+            Error::from(Status::OUT_OF_RESOURCES).kind(),
+            ErrorKind::OutOfMemory
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn io_error() {
+        let err = std::io::Error::from(Error::from(Status::END_OF_FILE));
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+        assert_eq!(
+            std::io::ErrorKind::from(ErrorKind::DeviceError),
+            std::io::ErrorKind::Other
+        );
+    }
+}
@@ -12,6 +12,10 @@ pub use error::Error;
 mod status;
 pub use status::{Status, StatusExt};
 
+/// Classification of status codes into general error kinds
+mod kind;
+pub use kind::ErrorKind;
+
 /// Return type of most UEFI functions. Both success and error payloads are optional.
 ///
 /// Almost all UEFI operations provide a status code as an output which
@@ -0,0 +1,465 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Tracking of which runtime services may still be called.
+//!
+//! After [`exit_boot_services`], the firmware is allowed to free the code of
+//! runtime services that it doesn't support at runtime; it lists the ones
+//! that remain in the [`RtPropertiesTable`]. After
+//! [`set_virtual_address_map`], the address map can't be changed again.
+//!
+//! The wrappers in [`runtime`] consult the state tracked here instead of
+//! calling a service that is no longer callable, and fail with
+//! [`RuntimeError::Unavailable`] describing why. The state is only updated
+//! by the wrappers in this crate, so exiting boot services through the raw
+//! function pointers bypasses it.
+//!
+//! [`exit_boot_services`]: crate::boot::exit_boot_services
+//! [`runtime`]: crate::runtime
+//! [`set_virtual_address_map`]: super::set_virtual_address_map
+
+use super::{RtPropertiesTable, RuntimeServicesSupported};
+use crate::table::cfg::ConfigTableEntry;
+use crate::{Error, Status, system, table};
+use core::fmt::{self, Debug, Display, Formatter};
+use core::sync::atomic::{AtomicU8, AtomicU32, Ordering};
+
+static PHASE: AtomicU8 = AtomicU8::new(Phase::BootServices as u8);
+static SUPPORTED_AT_RUNTIME: AtomicU32 = AtomicU32::new(RuntimeServicesSupported::all().bits());
+
+/// Phase of the firmware lifetime, as far as runtime services are
+/// concerned.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[repr(u8)]
+pub enum Phase {
+    /// Boot services are active; the firmware decides which runtime
+    /// services succeed.
+    BootServices = 0,
+    /// Boot services were exited; only the runtime services listed in the
+    /// [`RtPropertiesTable`] are callable.
+    Runtime = 1,
+    /// The firmware was switched to virtual addressing with
+    /// [`set_virtual_address_map`].
+    ///
+    /// [`set_virtual_address_map`]: super::set_virtual_address_map
+    Virtual = 2,
+}
+
+/// A runtime service.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum RuntimeService {
+    /// `GetTime`.
+    GetTime,
+    /// `SetTime`.
+    SetTime,
+    /// `GetWakeupTime`.
+    GetWakeupTime,
+    /// `SetWakeupTime`.
+    SetWakeupTime,
+    /// `GetVariable`.
+    GetVariable,
+    /// `GetNextVariableName`.
+    GetNextVariableName,
+    /// `SetVariable`.
+    SetVariable,
+    /// `SetVirtualAddressMap`.
+    SetVirtualAddressMap,
+    /// `ConvertPointer`.
+    ConvertPointer,
+    /// `GetNextHighMonotonicCount`.
+    GetNextHighMonotonicCount,
+    /// `ResetSystem`.
+    ResetSystem,
+    /// `UpdateCapsule`.
+    UpdateCapsule,
+    /// `QueryCapsuleCapabilities`.
+    QueryCapsuleCapabilities,
+    /// `QueryVariableInfo`.
+    QueryVariableInfo,
+}
+
+impl RuntimeService {
+    /// Returns the flag of the service in the [`RtPropertiesTable`].
+    #[must_use]
+    pub const fn flag(self) -> RuntimeServicesSupported {
+        match self {
+            Self::GetTime => RuntimeServicesSupported::GET_TIME,
+            Self::SetTime => RuntimeServicesSupported::SET_TIME,
+            Self::GetWakeupTime => RuntimeServicesSupported::GET_WAKEUP_TIME,
+            Self::SetWakeupTime => RuntimeServicesSupported::SET_WAKEUP_TIME,
+            Self::GetVariable => RuntimeServicesSupported::GET_VARIABLE,
+            Self::GetNextVariableName => RuntimeServicesSupported::GET_NEXT_VARIABLE_NAME,
+            Self::SetVariable => RuntimeServicesSupported::SET_VARIABLE,
+            Self::SetVirtualAddressMap => RuntimeServicesSupported::SET_VIRTUAL_ADDRESS_MAP,
+            Self::ConvertPointer => RuntimeServicesSupported::CONVERT_POINTER,
+            Self::GetNextHighMonotonicCount => {
+                RuntimeServicesSupported::GET_NEXT_HIGH_MONOTONIC_COUNT
+            }
+            Self::ResetSystem => RuntimeServicesSupported::RESET_SYSTEM,
+            Self::UpdateCapsule => RuntimeServicesSupported::UPDATE_CAPSULE,
+            Self::QueryCapsuleCapabilities => RuntimeServicesSupported::QUERY_CAPSULE_CAPABILITIES,
+            Self::QueryVariableInfo => RuntimeServicesSupported::QUERY_VARIABLE_INFO,
+        }
+    }
+}
+
+/// Error returned when calling a runtime service that is no longer
+/// callable.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Unavailable {
+    /// The service that was called.
+    pub service: RuntimeService,
+    /// The phase in which it was called.
+    pub phase: Phase,
+}
+
+impl Display for Unavailable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let when = match self.phase {
+            Phase::BootServices => "while boot services are active",
+            Phase::Runtime => "after exiting boot services",
+            Phase::Virtual => "after switching to virtual addressing",
+        };
+        write!(
+            f,
+            "runtime service {:?} is unavailable {when}",
+            self.service
+        )
+    }
+}
+
+impl core::error::Error for Unavailable {}
+
+/// Error returned by the wrappers of runtime services in [`runtime`].
+///
+/// It converts to an [`Error`] with the same status and data, so `?` works
+/// in functions returning [`Result`](crate::Result). An [`Unavailable`]
+/// service converts to [`Status::UNSUPPORTED`], the status the
+/// specification requires of unsupported runtime services, with the default
+/// error data.
+///
+/// [`runtime`]: crate::runtime
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RuntimeError<Data: Debug = ()> {
+    /// The service is no longer callable, and was not called.
+    Unavailable(Unavailable),
+    /// The service failed.
+    Firmware(Error<Data>),
+}
+
+impl<Data: Debug> RuntimeError<Data> {
+    /// Returns the status of the error, [`Status::UNSUPPORTED`] if the
+    /// service is unavailable.
+    pub const fn status(&self) -> Status {
+        match self {
+            Self::Unavailable(_) => Status::UNSUPPORTED,
+            Self::Firmware(err) => err.status(),
+        }
+    }
+
+    /// Returns the data of the error, or `None` if the service is
+    /// unavailable.
+    #[must_use]
+    pub const fn data(&self) -> Option<&Data> {
+        match self {
+            Self::Unavailable(_) => None,
+            Self::Firmware(err) => Some(err.data()),
+        }
+    }
+
+    /// Returns why the service is unavailable, or `None` if it failed.
+    #[must_use]
+    pub const fn unavailable(&self) -> Option<Unavailable> {
+        match self {
+            Self::Unavailable(err) => Some(*err),
+            Self::Firmware(_) => None,
+        }
+    }
+
+    /// Transforms the data of the error to `()`.
+    #[must_use]
+    pub const fn to_err_without_payload(&self) -> RuntimeError {
+        match self {
+            Self::Unavailable(err) => RuntimeError::Unavailable(*err),
+            Self::Firmware(err) => RuntimeError::Firmware(err.to_err_without_payload()),
+        }
+    }
+}
+
+impl<Data: Debug> Display for RuntimeError<Data> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unavailable(err) => Display::fmt(err, f),
+            Self::Firmware(err) => Display::fmt(err, f),
+        }
+    }
+}
+
+impl<Data: Debug> core::error::Error for RuntimeError<Data> {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::Unavailable(err) => Some(err),
+            Self::Firmware(_) => None,
+        }
+    }
+}
+
+impl<Data: Debug> From<Unavailable> for RuntimeError<Data> {
+    fn from(err: Unavailable) -> Self {
+        Self::Unavailable(err)
+    }
+}
+
+impl<Data: Debug> From<Error<Data>> for RuntimeError<Data> {
+    fn from(err: Error<Data>) -> Self {
+        Self::Firmware(err)
+    }
+}
+
+impl From<Status> for RuntimeError {
+    fn from(status: Status) -> Self {
+        Self::Firmware(status.into())
+    }
+}
+
+impl<Data: Debug + Default> From<RuntimeError<Data>> for Error<Data> {
+    fn from(err: RuntimeError<Data>) -> Self {
+        match err {
+            RuntimeError::Unavailable(_) => Self::new(Status::UNSUPPORTED, Data::default()),
+            RuntimeError::Firmware(err) => err,
+        }
+    }
+}
+
+/// Result of the wrappers of runtime services in [`runtime`].
+///
+/// [`runtime`]: crate::runtime
+pub type RuntimeResult<Output = (), ErrData = ()> =
+    core::result::Result<Output, RuntimeError<ErrData>>;
+
+/// Returns the current phase.
+#[must_use]
+pub fn phase() -> Phase {
+    match PHASE.load(Ordering::Acquire) {
+        0 => Phase::BootServices,
+        1 => Phase::Runtime,
+        _ => Phase::Virtual,
+    }
+}
+
+/// Returns the runtime services that may be called in the current phase.
+#[must_use]
+pub fn available() -> RuntimeServicesSupported {
+    let at_runtime =
+        RuntimeServicesSupported::from_bits_retain(SUPPORTED_AT_RUNTIME.load(Ordering::Acquire));
+    available_in(phase(), at_runtime)
+}
+
+/// Returns whether `service` may be called in the current phase.
+#[must_use]
+pub fn is_available(service: RuntimeService) -> bool {
+    available().contains(service.flag())
+}
+
+/// Checks that `service` may be called in the current phase.
+///
+/// # Errors
+///
+/// Returns [`Unavailable`] if the service is not callable anymore.
+pub fn check(service: RuntimeService) -> Result<(), Unavailable> {
+    let at_runtime =
+        RuntimeServicesSupported::from_bits_retain(SUPPORTED_AT_RUNTIME.load(Ordering::Acquire));
+    check_in(service, phase(), at_runtime)
+}
+
+/// Checks that `service` may be called in `phase`, given the services the
+/// firmware supports after exiting boot services.
+const fn check_in(
+    service: RuntimeService,
+    phase: Phase,
+    at_runtime: RuntimeServicesSupported,
+) -> Result<(), Unavailable> {
+    if available_in(phase, at_runtime).contains(service.flag()) {
+        Ok(())
+    } else {
+        Err(Unavailable { service, phase })
+    }
+}
+
+/// Returns the services callable in `phase`, given the services the
+/// firmware supports after exiting boot services.
+const fn available_in(
+    phase: Phase,
+    at_runtime: RuntimeServicesSupported,
+) -> RuntimeServicesSupported {
+    match phase {
+        Phase::BootServices => RuntimeServicesSupported::all(),
+        Phase::Runtime => at_runtime,
+        // The address map can only be set once, and pointers can only be
+        // converted while it is being set.
+        Phase::Virtual => at_runtime.difference(
+            RuntimeServicesSupported::SET_VIRTUAL_ADDRESS_MAP
+                .union(RuntimeServicesSupported::CONVERT_POINTER),
+        ),
+    }
+}
+
+/// Returns the services supported after exiting boot services according to
+/// `table`, or `None` if the table is malformed.
+fn parse_rt_properties(table: &RtPropertiesTable) -> Option<RuntimeServicesSupported> {
+    (table.version >= RtPropertiesTable::VERSION && usize::from(table.length) >= size_of_val(table))
+        .then_some(table.runtime_services_supported)
+}
+
+/// Reads the [`RtPropertiesTable`] from the configuration table. All
+/// services are assumed to be supported if the firmware doesn't install it.
+pub(crate) fn read_rt_properties() -> RuntimeServicesSupported {
+    if table::system_table_raw().is_none() {
+        return RuntimeServicesSupported::all();
+    }
+    let address = system::with_config_table(|entries| {
+        entries
+            .iter()
+            .find(|entry| entry.guid == ConfigTableEntry::RT_PROPERTIES_GUID)
+            .map(|entry| entry.address)
+    });
+    let Some(address) = address.filter(|address| !address.is_null()) else {
+        return RuntimeServicesSupported::all();
+    };
+    let table = address.cast::<RtPropertiesTable>();
+    // SAFETY: the firmware installed an `EFI_RT_PROPERTIES_TABLE` under
+    // this GUID; its header is present in every version.
+    let length = unsafe { (&raw const (*table).length).read_unaligned() };
+    if usize::from(length) < size_of::<RtPropertiesTable>() {
+        return RuntimeServicesSupported::all();
+    }
+    // SAFETY: the table is at least as large as `RtPropertiesTable`.
+    let table = unsafe { table.read_unaligned() };
+    parse_rt_properties(&table).unwrap_or(RuntimeServicesSupported::all())
+}
+
+/// Records that boot services were exited, after which only `supported`
+/// services may be called.
+pub(crate) fn mark_boot_services_exited(supported: RuntimeServicesSupported) {
+    SUPPORTED_AT_RUNTIME.store(supported.bits(), Ordering::Release);
+    PHASE.store(Phase::Runtime as u8, Ordering::Release);
+}
+
+/// Records that the firmware was switched to virtual addressing.
+pub(crate) fn mark_virtual() {
+    PHASE.store(Phase::Virtual as u8, Ordering::Release);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_available_in() {
+        let at_runtime = RuntimeServicesSupported::GET_VARIABLE
+            | RuntimeServicesSupported::SET_VIRTUAL_ADDRESS_MAP
+            | RuntimeServicesSupported::RESET_SYSTEM;
+
+        assert_eq!(
+            available_in(Phase::BootServices, RuntimeServicesSupported::empty()),
+            RuntimeServicesSupported::all()
+        );
+        assert_eq!(available_in(Phase::Runtime, at_runtime), at_runtime);
+        assert_eq!(
+            available_in(Phase::Virtual, at_runtime),
+            RuntimeServicesSupported::GET_VARIABLE | RuntimeServicesSupported::RESET_SYSTEM
+        );
+    }
+
+    #[test]
+    fn test_parse_rt_properties() {
+        let mut table = RtPropertiesTable {
+            version: RtPropertiesTable::VERSION,
+            length: 8,
+            runtime_services_supported: RuntimeServicesSupported::GET_TIME,
+        };
+        assert_eq!(
+            parse_rt_properties(&table),
+            Some(RuntimeServicesSupported::GET_TIME)
+        );
+
+        table.length = 4;
+        assert_eq!(parse_rt_properties(&table), None);
+
+        table.length = 8;
+        table.version = 0;
+        assert_eq!(parse_rt_properties(&table), None);
+    }
+
+    #[test]
+    fn test_unavailable() {
+        let err = Unavailable {
+            service: RuntimeService::SetVariable,
+            phase: Phase::Runtime,
+        };
+        assert_eq!(
+            err.to_string(),
+            "runtime service SetVariable is unavailable after exiting boot services"
+        );
+        let err = RuntimeError::<Option<usize>>::from(err);
+        assert_eq!(err.status(), Status::UNSUPPORTED);
+        assert_eq!(err.data(), None);
+        assert_eq!(Error::from(err).split(), (Status::UNSUPPORTED, None));
+    }
+
+    #[test]
+    fn test_check_in() {
+        let at_runtime = RuntimeServicesSupported::GET_TIME;
+
+        assert_eq!(
+            check_in(RuntimeService::SetVariable, Phase::BootServices, at_runtime),
+            Ok(())
+        );
+        assert_eq!(
+            check_in(RuntimeService::GetTime, Phase::Runtime, at_runtime),
+            Ok(())
+        );
+
+        let expected = Unavailable {
+            service: RuntimeService::SetVariable,
+            phase: Phase::Runtime,
+        };
+        let err = check_in(RuntimeService::SetVariable, Phase::Runtime, at_runtime).unwrap_err();
+        assert_eq!(err, expected);
+
+        // The error reaches callers of the wrappers through `?`.
+        fn wrapper(at_runtime: RuntimeServicesSupported) -> RuntimeResult {
+            check_in(RuntimeService::SetVariable, Phase::Runtime, at_runtime)?;
+            Ok(())
+        }
+        let err = wrapper(at_runtime).unwrap_err();
+        assert_eq!(err, RuntimeError::Unavailable(expected));
+        assert_eq!(err.unavailable(), Some(expected));
+        assert_eq!(err.status(), Status::UNSUPPORTED);
+        assert_eq!(
+            err.to_string(),
+            "runtime service SetVariable is unavailable after exiting boot services"
+        );
+
+        assert_eq!(
+            check_in(
+                RuntimeService::SetVirtualAddressMap,
+                Phase::Virtual,
+                RuntimeServicesSupported::all()
+            ),
+            Err(Unavailable {
+                service: RuntimeService::SetVirtualAddressMap,
+                phase: Phase::Virtual,
+            })
+        );
+    }
+
+    #[test]
+    fn test_flag() {
+        assert_eq!(
+            RuntimeService::QueryVariableInfo.flag(),
+            RuntimeServicesSupported::QUERY_VARIABLE_INFO
+        );
+        assert_eq!(RuntimeService::GetNextVariableName.flag().bits(), 0x20);
+    }
+}
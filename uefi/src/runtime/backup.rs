@@ -0,0 +1,406 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Backup and restore of UEFI variables.
+//!
+//! [`dump`] reads all variables, or those selected by a filter, into
+//! [`SavedVariable`]s. These can be serialized with [`to_bytes`], e.g. to be
+//! written to a file, and read back with [`from_bytes`]. [`restore`] writes
+//! them back to variable storage, skipping variables that would be unsafe to
+//! overwrite unless explicitly allowed with [`RestoreOptions`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! use uefi::fs::FileSystem;
+//! use uefi::runtime::backup::{self, RestoreOptions};
+//! use uefi::runtime::VariableVendor;
+//! use uefi::{boot, cstr16};
+//!
+//! # fn example() -> Result<(), Box<dyn core::error::Error>> {
+//! let fs = boot::get_image_file_system(boot::image_handle())?;
+//! let mut fs = FileSystem::new(fs);
+//!
+//! // Back up all vendor-specific variables.
+//! let vars = backup::dump(|key| key.vendor != VariableVendor::GLOBAL_VARIABLE)?;
+//! fs.write(cstr16!("vars.bin"), backup::to_bytes(&vars))?;
+//!
+//! // Restore them later.
+//! let vars = backup::from_bytes(&fs.read(cstr16!("vars.bin"))?)?;
+//! let summary = backup::restore(&vars, RestoreOptions::default())?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Format
+//!
+//! All integers are little-endian. The data starts with a header:
+//!
+//! | Offset | Size | Description                      |
+//! |--------|------|----------------------------------|
+//! | 0      | 8    | Magic value `UEFIVARS`           |
+//! | 8      | 4    | Format version, currently 1      |
+//! | 12     | 4    | Number of variables              |
+//!
+//! It is followed by one record per variable:
+//!
+//! | Offset | Size | Description                              |
+//! |--------|------|------------------------------------------|
+//! | 0      | 16   | Vendor GUID                              |
+//! | 16     | 4    | Attributes                               |
+//! | 20     | 4    | Size of the name in bytes (`n`)          |
+//! | 24     | 4    | Size of the data in bytes (`d`)          |
+//! | 28     | `n`  | Name as null-terminated UCS-2            |
+//! | 28+`n` | `d`  | Data                                     |
+
+use super::{RuntimeResult, VariableAttributes, VariableKey, VariableVendor};
+use crate::{CString16, Guid, Status};
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+
+/// Magic value at the start of serialized variables.
+const MAGIC: [u8; 8] = *b"UEFIVARS";
+
+/// Current version of the format.
+const VERSION: u32 = 1;
+
+/// Size of the header of each serialized variable.
+const RECORD_HEADER_SIZE: usize = 28;
+
+/// Attributes of variables that can only be written with a signed payload.
+const AUTHENTICATED: VariableAttributes = VariableAttributes::AUTHENTICATED_WRITE_ACCESS
+    .union(VariableAttributes::TIME_BASED_AUTHENTICATED_WRITE_ACCESS)
+    .union(VariableAttributes::ENHANCED_AUTHENTICATED_ACCESS);
+
+/// A variable read by [`dump`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SavedVariable {
+    /// Name and vendor of the variable.
+    pub key: VariableKey,
+    /// Attributes of the variable.
+    pub attributes: VariableAttributes,
+    /// Contents of the variable.
+    pub data: Vec<u8>,
+}
+
+/// Reads all variables whose key is accepted by `filter`.
+///
+/// Variables that are deleted while the variables are read are skipped.
+///
+/// # Errors
+///
+/// * [`Status::DEVICE_ERROR`]: variables could not be read due to a hardware
+///   error.
+/// * [`Status::SECURITY_VIOLATION`]: a variable could not be read due to an
+///   authentication error.
+/// * [`Status::UNSUPPORTED`]: this platform does not support variable storage
+///   after exiting boot services.
+pub fn dump(mut filter: impl FnMut(&VariableKey) -> bool) -> RuntimeResult<Vec<SavedVariable>> {
+    let mut vars = Vec::new();
+    for key in super::variable_keys() {
+        let key = key?;
+        if !filter(&key) {
+            continue;
+        }
+        match super::get_variable_boxed(&key.name, &key.vendor) {
+            Ok((data, attributes)) => vars.push(SavedVariable {
+                key,
+                attributes,
+                data: data.into_vec(),
+            }),
+            Err(err) if err.status() == Status::NOT_FOUND => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(vars)
+}
+
+/// Serializes `vars` in the format described in the [module documentation].
+///
+/// [module documentation]: self#format
+#[must_use]
+pub fn to_bytes(vars: &[SavedVariable]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&MAGIC);
+    out.extend_from_slice(&VERSION.to_le_bytes());
+    out.extend_from_slice(&(vars.len() as u32).to_le_bytes());
+    for var in vars {
+        let name = var.key.name.to_u16_slice_with_nul();
+        out.extend_from_slice(&var.key.vendor.0.to_bytes());
+        out.extend_from_slice(&var.attributes.bits().to_le_bytes());
+        out.extend_from_slice(&(size_of_val(name) as u32).to_le_bytes());
+        out.extend_from_slice(&(var.data.len() as u32).to_le_bytes());
+        for c in name {
+            out.extend_from_slice(&c.to_le_bytes());
+        }
+        out.extend_from_slice(&var.data);
+    }
+    out
+}
+
+/// Error returned by [`from_bytes`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BackupParseError {
+    /// The data does not start with the expected magic value.
+    InvalidMagic,
+    /// The format version is not supported.
+    UnsupportedVersion(u32),
+    /// The data ends in the middle of a variable.
+    Truncated,
+    /// A variable name is not a valid null-terminated UCS-2 string.
+    InvalidName,
+}
+
+impl Display for BackupParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidMagic => write!(f, "not a variable backup"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported variable backup version {version}")
+            }
+            Self::Truncated => write!(f, "variable backup is truncated"),
+            Self::InvalidName => write!(f, "invalid variable name in backup"),
+        }
+    }
+}
+
+impl core::error::Error for BackupParseError {}
+
+/// Reads `len` bytes from the start of `bytes` and advances it.
+const fn take<'a>(
+    bytes: &mut &'a [u8],
+    len: usize,
+) -> core::result::Result<&'a [u8], BackupParseError> {
+    if bytes.len() < len {
+        return Err(BackupParseError::Truncated);
+    }
+    let (head, tail) = bytes.split_at(len);
+    *bytes = tail;
+    Ok(head)
+}
+
+/// Reads a little-endian `u32` from the start of `bytes` and advances it.
+fn take_u32(bytes: &mut &[u8]) -> core::result::Result<u32, BackupParseError> {
+    // OK to unwrap: the slice has the right length.
+    Ok(u32::from_le_bytes(take(bytes, 4)?.try_into().unwrap()))
+}
+
+/// Parses variables serialized with [`to_bytes`].
+///
+/// # Errors
+///
+/// See [`BackupParseError`].
+pub fn from_bytes(mut bytes: &[u8]) -> core::result::Result<Vec<SavedVariable>, BackupParseError> {
+    let bytes = &mut bytes;
+    if take(bytes, MAGIC.len())? != MAGIC {
+        return Err(BackupParseError::InvalidMagic);
+    }
+    let version = take_u32(bytes)?;
+    if version != VERSION {
+        return Err(BackupParseError::UnsupportedVersion(version));
+    }
+    let count = take_u32(bytes)? as usize;
+
+    // Don't trust `count` for the allocation size.
+    let mut vars = Vec::with_capacity(count.min(bytes.len() / RECORD_HEADER_SIZE));
+    for _ in 0..count {
+        // OK to unwrap: the slice has the right length.
+        let vendor = Guid::from_bytes(take(bytes, 16)?.try_into().unwrap());
+        let attributes = VariableAttributes::from_bits_retain(take_u32(bytes)?);
+        let name_size = take_u32(bytes)? as usize;
+        let data_size = take_u32(bytes)? as usize;
+        let name = take(bytes, name_size)?;
+        if name_size % 2 != 0 {
+            return Err(BackupParseError::InvalidName);
+        }
+        let name: Vec<u16> = name
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        let name = CString16::try_from(name).map_err(|_| BackupParseError::InvalidName)?;
+        let data = take(bytes, data_size)?.to_vec();
+        vars.push(SavedVariable {
+            key: VariableKey {
+                vendor: VariableVendor(vendor),
+                name,
+            },
+            attributes,
+            data,
+        });
+    }
+    Ok(vars)
+}
+
+/// Controls which variables are written by [`restore`].
+///
+/// By default, only non-volatile variables outside of the
+/// [`GLOBAL_VARIABLE`] namespace are restored. Variables with authenticated
+/// write access are never restored, since their contents as returned by the
+/// firmware don't include the signature required to write them.
+///
+/// [`GLOBAL_VARIABLE`]: VariableVendor::GLOBAL_VARIABLE
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct RestoreOptions {
+    /// Also restore variables in the [`GLOBAL_VARIABLE`] namespace, such as
+    /// `BootOrder` and the `Boot####` load options. Writing invalid values
+    /// to these variables can make the system unbootable.
+    ///
+    /// [`GLOBAL_VARIABLE`]: VariableVendor::GLOBAL_VARIABLE
+    pub global: bool,
+    /// Also restore variables without the
+    /// [`NON_VOLATILE`](VariableAttributes::NON_VOLATILE) attribute. These
+    /// are usually created by the firmware at boot and are often read-only.
+    pub volatile: bool,
+}
+
+/// Reason why [`restore`] skipped a variable.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SkipReason {
+    /// The variable has authenticated write access.
+    Authenticated,
+    /// The variable is in the [`GLOBAL_VARIABLE`] namespace, and
+    /// [`RestoreOptions::global`] is not set.
+    ///
+    /// [`GLOBAL_VARIABLE`]: VariableVendor::GLOBAL_VARIABLE
+    Global,
+    /// The variable is volatile, and [`RestoreOptions::volatile`] is not set.
+    Volatile,
+    /// The variable is in the [`IMAGE_SECURITY_DATABASE`] namespace, which
+    /// holds the Secure Boot signature databases.
+    ///
+    /// [`IMAGE_SECURITY_DATABASE`]: VariableVendor::IMAGE_SECURITY_DATABASE
+    SecurityDatabase,
+}
+
+impl SavedVariable {
+    /// Returns why [`restore`] would skip this variable with `options`, or
+    /// `None` if it would be written.
+    #[must_use]
+    pub fn skip_reason(&self, options: &RestoreOptions) -> Option<SkipReason> {
+        if self.attributes.intersects(AUTHENTICATED) {
+            Some(SkipReason::Authenticated)
+        } else if self.key.vendor == VariableVendor::IMAGE_SECURITY_DATABASE {
+            Some(SkipReason::SecurityDatabase)
+        } else if self.key.vendor == VariableVendor::GLOBAL_VARIABLE && !options.global {
+            Some(SkipReason::Global)
+        } else if !self.attributes.contains(VariableAttributes::NON_VOLATILE) && !options.volatile {
+            Some(SkipReason::Volatile)
+        } else {
+            None
+        }
+    }
+}
+
+/// Result of a successful [`restore`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RestoreSummary {
+    /// Number of variables written.
+    pub restored: usize,
+    /// Variables that were not written, and why.
+    pub skipped: Vec<(VariableKey, SkipReason)>,
+}
+
+/// Writes `vars` to variable storage, overwriting existing variables with
+/// the same key.
+///
+/// Variables are checked with [`SavedVariable::skip_reason`] before being
+/// written, and skipped variables are listed in the returned summary.
+///
+/// # Errors
+///
+/// Writing stops at the first variable that fails to be written. See
+/// [`set_variable`] for possible errors.
+///
+/// [`set_variable`]: super::set_variable
+pub fn restore(vars: &[SavedVariable], options: RestoreOptions) -> RuntimeResult<RestoreSummary> {
+    let mut summary = RestoreSummary::default();
+    for var in vars {
+        if let Some(reason) = var.skip_reason(&options) {
+            log::debug!("not restoring {}: {reason:?}", var.key);
+            summary.skipped.push((var.key.clone(), reason));
+            continue;
+        }
+        super::set_variable(&var.key.name, &var.key.vendor, var.attributes, &var.data)?;
+        summary.restored += 1;
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cstr16, guid};
+
+    fn var(vendor: VariableVendor, attributes: VariableAttributes, data: &[u8]) -> SavedVariable {
+        SavedVariable {
+            key: VariableKey {
+                vendor,
+                name: cstr16!("Var").into(),
+            },
+            attributes,
+            data: data.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let vendor = VariableVendor(guid!("01234567-89ab-cdef-0123-456789abcdef"));
+        let vars = [
+            var(vendor, VariableAttributes::NON_VOLATILE, &[1, 2, 3]),
+            var(
+                VariableVendor::GLOBAL_VARIABLE,
+                VariableAttributes::empty(),
+                &[],
+            ),
+        ];
+        let bytes = to_bytes(&vars);
+        assert_eq!(&bytes[..8], b"UEFIVARS");
+        assert_eq!(bytes.len(), 16 + 2 * (RECORD_HEADER_SIZE + 8) + 3);
+        assert_eq!(from_bytes(&bytes).unwrap(), vars);
+
+        assert_eq!(
+            from_bytes(&bytes[..bytes.len() - 1]),
+            Err(BackupParseError::Truncated)
+        );
+        assert_eq!(from_bytes(b"UEFIVARX"), Err(BackupParseError::InvalidMagic));
+        let mut bad_version = bytes.clone();
+        bad_version[8] = 2;
+        assert_eq!(
+            from_bytes(&bad_version),
+            Err(BackupParseError::UnsupportedVersion(2))
+        );
+    }
+
+    #[test]
+    fn test_skip_reason() {
+        let vendor = VariableVendor(guid!("01234567-89ab-cdef-0123-456789abcdef"));
+        let nv = VariableAttributes::NON_VOLATILE | VariableAttributes::BOOTSERVICE_ACCESS;
+        let default = RestoreOptions::default();
+        let all = RestoreOptions {
+            global: true,
+            volatile: true,
+        };
+
+        assert_eq!(var(vendor, nv, &[]).skip_reason(&default), None);
+        assert_eq!(
+            var(VariableVendor::GLOBAL_VARIABLE, nv, &[]).skip_reason(&default),
+            Some(SkipReason::Global)
+        );
+        assert_eq!(
+            var(VariableVendor::GLOBAL_VARIABLE, nv, &[]).skip_reason(&all),
+            None
+        );
+        assert_eq!(
+            var(vendor, VariableAttributes::BOOTSERVICE_ACCESS, &[]).skip_reason(&default),
+            Some(SkipReason::Volatile)
+        );
+        let authenticated = nv | VariableAttributes::TIME_BASED_AUTHENTICATED_WRITE_ACCESS;
+        assert_eq!(
+            var(vendor, authenticated, &[]).skip_reason(&all),
+            Some(SkipReason::Authenticated)
+        );
+        assert_eq!(
+            var(VariableVendor::IMAGE_SECURITY_DATABASE, nv, &[]).skip_reason(&all),
+            Some(SkipReason::SecurityDatabase)
+        );
+    }
+}
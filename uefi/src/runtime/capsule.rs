@@ -0,0 +1,411 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! JSON capsules and capsule results.
+//!
+//! JSON capsules pass vendor-defined JSON configuration data to the
+//! firmware. [`JsonCapsule`] builds them, to be passed to
+//! [`update_capsule`] or written to a file for capsule-on-disk delivery.
+//! After the firmware processed a capsule, possibly across a reset, the
+//! outcome can be read with [`last_capsule_result`] or [`capsule_result`].
+//! JSON data provided through the configuration table is read with
+//! [`json_config_data`].
+//!
+//! The parsing functions only work on byte slices, so they can also be used
+//! by host tools with the `std` feature.
+//!
+//! [`update_capsule`]: super::update_capsule
+
+use super::{CapsuleFlags, CapsuleHeader, Time, VariableVendor};
+use crate::table::cfg::ConfigTableEntry;
+use crate::{CStr16, CString16, Guid, Result, Status, system};
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+pub use uefi_raw::capsule::{
+    CapsuleResultVariableHeader, CapsuleResultVariableJson, JsonCapsuleConfigData,
+    JsonCapsuleHeader,
+};
+
+/// Size of [`CapsuleHeader`] in bytes.
+const CAPSULE_HEADER_SIZE: usize = 28;
+/// Size of [`JsonCapsuleHeader`] in bytes, without the payload.
+const JSON_HEADER_SIZE: usize = 12;
+/// Size of [`CapsuleResultVariableHeader`] in bytes.
+const RESULT_HEADER_SIZE: usize = 48;
+
+/// Error returned when parsing capsule data.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CapsuleParseError {
+    /// The data ends before a length it contains.
+    Truncated,
+    /// The capsule or result is not of the expected type.
+    UnexpectedGuid(Guid),
+    /// The structure version is not supported.
+    UnsupportedVersion(u32),
+}
+
+impl Display for CapsuleParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Truncated => write!(f, "capsule data is truncated"),
+            Self::UnexpectedGuid(guid) => write!(f, "unexpected capsule GUID {guid}"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported capsule structure version {version}")
+            }
+        }
+    }
+}
+
+impl core::error::Error for CapsuleParseError {}
+
+/// Reads `len` bytes from the start of `bytes` and advances it.
+const fn take<'a>(
+    bytes: &mut &'a [u8],
+    len: usize,
+) -> core::result::Result<&'a [u8], CapsuleParseError> {
+    if bytes.len() < len {
+        return Err(CapsuleParseError::Truncated);
+    }
+    let (head, tail) = bytes.split_at(len);
+    *bytes = tail;
+    Ok(head)
+}
+
+/// Reads a little-endian `u32` from the start of `bytes` and advances it.
+fn take_u32(bytes: &mut &[u8]) -> core::result::Result<u32, CapsuleParseError> {
+    // OK to unwrap: the slice has the right length.
+    Ok(u32::from_le_bytes(take(bytes, 4)?.try_into().unwrap()))
+}
+
+/// Reads a GUID from the start of `bytes` and advances it.
+fn take_guid(bytes: &mut &[u8]) -> core::result::Result<Guid, CapsuleParseError> {
+    // OK to unwrap: the slice has the right length.
+    Ok(Guid::from_bytes(take(bytes, 16)?.try_into().unwrap()))
+}
+
+/// Checks a structure version against the supported one.
+const fn check_version(
+    version: u32,
+    supported: u32,
+) -> core::result::Result<(), CapsuleParseError> {
+    if version == supported {
+        Ok(())
+    } else {
+        Err(CapsuleParseError::UnsupportedVersion(version))
+    }
+}
+
+/// A JSON capsule (`EFI_JSON_CAPSULE_ID_GUID`).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct JsonCapsule {
+    /// Vendor-defined identifier, reported back in the capsule's
+    /// [`JsonCapsuleResult`].
+    pub capsule_id: u32,
+    /// Flags of the capsule header.
+    pub flags: CapsuleFlags,
+    /// JSON payload.
+    pub payload: Vec<u8>,
+}
+
+impl JsonCapsule {
+    /// Creates a capsule with `payload` and no flags.
+    #[must_use]
+    pub fn new(capsule_id: u32, payload: impl Into<Vec<u8>>) -> Self {
+        Self {
+            capsule_id,
+            flags: CapsuleFlags::empty(),
+            payload: payload.into(),
+        }
+    }
+
+    /// Returns the capsule, starting with its [`CapsuleHeader`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the payload is larger than 4 GiB.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let payload_len = u32::try_from(self.payload.len()).expect("JSON payload is too large");
+        let image_size = u32::try_from(CAPSULE_HEADER_SIZE + JSON_HEADER_SIZE)
+            .ok()
+            .and_then(|size| size.checked_add(payload_len))
+            .expect("JSON payload is too large");
+
+        let mut bytes = Vec::with_capacity(image_size as usize);
+        bytes.extend_from_slice(&CapsuleHeader::JSON_GUID.to_bytes());
+        bytes.extend_from_slice(&(CAPSULE_HEADER_SIZE as u32).to_le_bytes());
+        bytes.extend_from_slice(&self.flags.bits().to_le_bytes());
+        bytes.extend_from_slice(&image_size.to_le_bytes());
+        bytes.extend_from_slice(&JsonCapsuleHeader::VERSION.to_le_bytes());
+        bytes.extend_from_slice(&self.capsule_id.to_le_bytes());
+        bytes.extend_from_slice(&payload_len.to_le_bytes());
+        bytes.extend_from_slice(&self.payload);
+        bytes
+    }
+
+    /// Parses a capsule built with [`to_bytes`].
+    ///
+    /// # Errors
+    ///
+    /// See [`CapsuleParseError`].
+    ///
+    /// [`to_bytes`]: Self::to_bytes
+    pub fn from_bytes(mut bytes: &[u8]) -> core::result::Result<Self, CapsuleParseError> {
+        let bytes = &mut bytes;
+        let guid = take_guid(bytes)?;
+        if guid != CapsuleHeader::JSON_GUID {
+            return Err(CapsuleParseError::UnexpectedGuid(guid));
+        }
+        let header_size = take_u32(bytes)? as usize;
+        let flags = CapsuleFlags::from_bits_retain(take_u32(bytes)?);
+        let _image_size = take_u32(bytes)?;
+        // Skip fields of a larger capsule header.
+        take(bytes, header_size.saturating_sub(CAPSULE_HEADER_SIZE))?;
+
+        check_version(take_u32(bytes)?, JsonCapsuleHeader::VERSION)?;
+        let capsule_id = take_u32(bytes)?;
+        let payload_len = take_u32(bytes)? as usize;
+        let payload = take(bytes, payload_len)?.to_vec();
+        Ok(Self {
+            capsule_id,
+            flags,
+            payload,
+        })
+    }
+}
+
+/// Parses a [`JsonCapsuleConfigData`], returning the JSON data of its
+/// items.
+///
+/// # Errors
+///
+/// See [`CapsuleParseError`].
+pub fn parse_json_config_data(
+    mut bytes: &[u8],
+) -> core::result::Result<Vec<&[u8]>, CapsuleParseError> {
+    let bytes = &mut bytes;
+    check_version(take_u32(bytes)?, JsonCapsuleConfigData::VERSION)?;
+    let total_len = take_u32(bytes)? as usize;
+    let mut list = take(bytes, total_len.saturating_sub(8))?;
+    let mut items = Vec::new();
+    while !list.is_empty() {
+        let len = take_u32(&mut list)? as usize;
+        items.push(take(&mut list, len)?);
+    }
+    Ok(items)
+}
+
+/// Returns the items of the [`JsonCapsuleConfigData`] installed in the
+/// configuration table under `guid`, which is one of
+/// [`JsonCapsuleHeader::CONFIG_DATA_TABLE_GUID`],
+/// [`JsonCapsuleHeader::DATA_TABLE_GUID`], and
+/// [`JsonCapsuleHeader::RESULT_TABLE_GUID`].
+///
+/// Returns `None` if there is no such table, or if it is malformed.
+#[must_use]
+pub fn json_config_data(guid: &Guid) -> Option<Vec<Vec<u8>>> {
+    let address = system::with_config_table(|entries| {
+        entries
+            .iter()
+            .find(|entry: &&ConfigTableEntry| entry.guid == *guid)
+            .map(|entry| entry.address)
+    })?;
+    let header = address.cast::<u8>();
+    if header.is_null() {
+        return None;
+    }
+    // SAFETY: the firmware installed a `JsonCapsuleConfigData` under this
+    // GUID, which starts with its version and total length.
+    let total_len = unsafe { header.add(4).cast::<u32>().read_unaligned() } as usize;
+    // SAFETY: the table is `total_len` bytes long.
+    let bytes = unsafe { core::slice::from_raw_parts(header, total_len) };
+    let items = parse_json_config_data(bytes).ok()?;
+    Some(items.into_iter().map(<[u8]>::to_vec).collect())
+}
+
+/// Result of processing a capsule, stored by the firmware in a
+/// `Capsule####` variable.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CapsuleResult {
+    /// GUID of the processed capsule.
+    pub capsule_guid: Guid,
+    /// Time the capsule was processed.
+    pub processed: Time,
+    /// Result of processing the capsule.
+    pub status: Status,
+    /// Data specific to the type of capsule, following the
+    /// [`CapsuleResultVariableHeader`].
+    pub data: Vec<u8>,
+}
+
+impl CapsuleResult {
+    /// Parses the contents of a `Capsule####` variable.
+    ///
+    /// # Errors
+    ///
+    /// See [`CapsuleParseError`].
+    pub fn from_bytes(mut bytes: &[u8]) -> core::result::Result<Self, CapsuleParseError> {
+        let all = bytes;
+        let bytes = &mut bytes;
+        let total_size = take_u32(bytes)? as usize;
+        if total_size < RESULT_HEADER_SIZE || total_size > all.len() {
+            return Err(CapsuleParseError::Truncated);
+        }
+        let _reserved = take_u32(bytes)?;
+        let capsule_guid = take_guid(bytes)?;
+        let time = take(bytes, 16)?;
+        // OK to unwrap: the slice has the right length.
+        let processed = Time(uefi_raw::time::Time {
+            year: u16::from_le_bytes([time[0], time[1]]),
+            month: time[2],
+            day: time[3],
+            hour: time[4],
+            minute: time[5],
+            second: time[6],
+            pad1: 0,
+            nanosecond: u32::from_le_bytes(time[8..12].try_into().unwrap()),
+            time_zone: i16::from_le_bytes([time[12], time[13]]),
+            daylight: super::Daylight::from_bits_retain(time[14]),
+            pad2: 0,
+        });
+        let status = take(bytes, 8)?;
+        // OK to unwrap: the slice has the right length.
+        let status = Status(u64::from_le_bytes(status.try_into().unwrap()) as usize);
+        Ok(Self {
+            capsule_guid,
+            processed,
+            status,
+            data: all[RESULT_HEADER_SIZE..total_size].to_vec(),
+        })
+    }
+
+    /// Returns the result of a JSON capsule, or `None` if the capsule is of
+    /// another type or its result data is malformed.
+    #[must_use]
+    pub fn json(&self) -> Option<JsonCapsuleResult> {
+        if self.capsule_guid != CapsuleHeader::JSON_GUID {
+            return None;
+        }
+        let bytes = &mut self.data.as_slice();
+        check_version(take_u32(bytes).ok()?, CapsuleResultVariableJson::VERSION).ok()?;
+        let capsule_id = take_u32(bytes).ok()?;
+        let resp_len = take_u32(bytes).ok()? as usize;
+        let response = take(bytes, resp_len).ok()?.to_vec();
+        Some(JsonCapsuleResult {
+            capsule_id,
+            response,
+        })
+    }
+}
+
+/// Result data of a JSON capsule (`EFI_CAPSULE_RESULT_VARIABLE_JSON`).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct JsonCapsuleResult {
+    /// `capsule_id` of the processed [`JsonCapsule`].
+    pub capsule_id: u32,
+    /// JSON response of the firmware.
+    pub response: Vec<u8>,
+}
+
+/// Reads the `Capsule####` variable with the given index.
+///
+/// # Errors
+///
+/// * [`Status::NOT_FOUND`]: there is no result with this index.
+/// * [`Status::VOLUME_CORRUPTED`]: the variable is malformed.
+/// * See [`get_variable_boxed`] for other errors.
+///
+/// [`get_variable_boxed`]: super::get_variable_boxed
+pub fn capsule_result(index: u16) -> Result<CapsuleResult> {
+    let name = CString16::try_from(alloc::format!("Capsule{index:04X}").as_str())
+        .map_err(|_| Status::INVALID_PARAMETER)?;
+    read_result(&name)
+}
+
+/// Reads the result of the most recently processed capsule, pointed to by
+/// the `CapsuleLast` variable.
+///
+/// # Errors
+///
+/// * [`Status::NOT_FOUND`]: no capsule was processed.
+/// * [`Status::VOLUME_CORRUPTED`]: the variables are malformed.
+/// * See [`get_variable_boxed`] for other errors.
+///
+/// [`get_variable_boxed`]: super::get_variable_boxed
+pub fn last_capsule_result() -> Result<CapsuleResult> {
+    let (last, _) = super::get_variable_boxed(crate::cstr16!("CapsuleLast"), &vendor())?;
+    let name: Vec<u16> = last
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .take_while(|&c| c != 0)
+        .chain([0])
+        .collect();
+    let name = CString16::try_from(name).map_err(|_| Status::VOLUME_CORRUPTED)?;
+    read_result(&name)
+}
+
+/// Vendor of the capsule result variables.
+const fn vendor() -> VariableVendor {
+    VariableVendor(CapsuleResultVariableHeader::VENDOR_GUID)
+}
+
+fn read_result(name: &CStr16) -> Result<CapsuleResult> {
+    let (data, _) = super::get_variable_boxed(name, &vendor())?;
+    CapsuleResult::from_bytes(&data).map_err(|_| Status::VOLUME_CORRUPTED.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn json_capsule() {
+        let capsule = JsonCapsule::new(7, *br#"{"a":1}"#);
+        let bytes = capsule.to_bytes();
+        assert_eq!(bytes.len(), 28 + 12 + 7);
+        assert_eq!(&bytes[24..28], &47u32.to_le_bytes());
+        assert_eq!(JsonCapsule::from_bytes(&bytes), Ok(capsule));
+        assert_eq!(
+            JsonCapsule::from_bytes(&bytes[..45]),
+            Err(CapsuleParseError::Truncated)
+        );
+    }
+
+    #[test]
+    fn config_data() {
+        let mut bytes = vec![1, 0, 0, 0, 19, 0, 0, 0];
+        bytes.extend([2, 0, 0, 0, b'{', b'}']);
+        bytes.extend([1, 0, 0, 0, b'1']);
+        assert_eq!(
+            parse_json_config_data(&bytes),
+            Ok(vec![&b"{}"[..], &b"1"[..]])
+        );
+        bytes[0] = 2;
+        assert_eq!(
+            parse_json_config_data(&bytes),
+            Err(CapsuleParseError::UnsupportedVersion(2))
+        );
+    }
+
+    #[test]
+    fn result() {
+        let mut bytes = vec![0; RESULT_HEADER_SIZE];
+        bytes[8..24].copy_from_slice(&CapsuleHeader::JSON_GUID.to_bytes());
+        bytes[24..26].copy_from_slice(&2024u16.to_le_bytes());
+        bytes[40..48].copy_from_slice(&(Status::ABORTED.0 as u64).to_le_bytes());
+        bytes.extend([1, 0, 0, 0, 7, 0, 0, 0, 2, 0, 0, 0, b'o', b'k']);
+        let len = bytes.len() as u32;
+        bytes[..4].copy_from_slice(&len.to_le_bytes());
+
+        let result = CapsuleResult::from_bytes(&bytes).unwrap();
+        assert_eq!(result.status, Status::ABORTED);
+        assert_eq!(result.processed.year(), 2024);
+        assert_eq!(
+            result.json(),
+            Some(JsonCapsuleResult {
+                capsule_id: 7,
+                response: b"ok".to_vec(),
+            })
+        );
+    }
+}
@@ -0,0 +1,242 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::data_types::FromSliceWithNulError;
+use crate::proto::device_path::{ByteConversionError, DevicePath};
+use crate::{CStr16, CString16};
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+
+bitflags::bitflags! {
+    /// Attributes of a [`LoadOption`].
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    #[repr(transparent)]
+    pub struct LoadOptionAttributes: u32 {
+        /// The option is active; the boot manager only tries active
+        /// options.
+        const ACTIVE = 0x0000_0001;
+        /// The driver of a `Driver####` option requires reconnecting all
+        /// drivers after it loads.
+        const FORCE_RECONNECT = 0x0000_0002;
+        /// The option is hidden from menus of the boot manager.
+        const HIDDEN = 0x0000_0008;
+        /// The option is an application, rather than an option to boot
+        /// the OS.
+        const CATEGORY_APP = 0x0000_0100;
+    }
+}
+
+/// Error returned by [`LoadOption::parse`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LoadOptionError {
+    /// The option ends before the file path list.
+    Truncated,
+    /// The description is not a valid null-terminated UCS-2 string.
+    InvalidDescription(FromSliceWithNulError),
+    /// The file path list doesn't start with a valid device path.
+    InvalidFilePathList,
+}
+
+impl Display for LoadOptionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Truncated => write!(f, "truncated load option"),
+            Self::InvalidDescription(err) => write!(f, "invalid description: {err}"),
+            Self::InvalidFilePathList => write!(f, "invalid file path list"),
+        }
+    }
+}
+
+impl core::error::Error for LoadOptionError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::InvalidDescription(err) => Some(err),
+            Self::Truncated | Self::InvalidFilePathList => None,
+        }
+    }
+}
+
+/// A load option, the content of a `Boot####`, `Driver####` or
+/// `SysPrep####` variable (`EFI_LOAD_OPTION`).
+///
+/// [`parse`] and [`to_bytes`] only operate on bytes, so they also work on
+/// the host, e.g. on variables read from `/sys/firmware/efi/efivars`.
+///
+/// [`parse`]: Self::parse
+/// [`to_bytes`]: Self::to_bytes
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LoadOption {
+    /// Attributes of the option.
+    pub attributes: LoadOptionAttributes,
+    /// Description of the option, shown by the boot manager.
+    pub description: CString16,
+    /// Device paths of the option. The first one is the device path of the
+    /// image to load; the others are optional and their meaning is
+    /// specific to the OS.
+    pub file_path_list: Vec<u8>,
+    /// Data passed to the image as its load options.
+    pub optional_data: Vec<u8>,
+}
+
+impl LoadOption {
+    /// Creates an option loading the image at `file_path`.
+    #[must_use]
+    pub fn new(
+        attributes: LoadOptionAttributes,
+        description: &CStr16,
+        file_path: &DevicePath,
+    ) -> Self {
+        Self {
+            attributes,
+            description: description.into(),
+            file_path_list: file_path.as_bytes().to_vec(),
+            optional_data: Vec::new(),
+        }
+    }
+
+    /// Parses the content of a load option variable.
+    ///
+    /// # Errors
+    ///
+    /// * [`LoadOptionError::Truncated`]: `bytes` are too short.
+    /// * [`LoadOptionError::InvalidDescription`]: the description is
+    ///   invalid.
+    /// * [`LoadOptionError::InvalidFilePathList`]: the file path list
+    ///   doesn't start with a valid device path.
+    pub fn parse(bytes: &[u8]) -> Result<Self, LoadOptionError> {
+        // Attributes, file path list length, then the null-terminated
+        // description, the file path list and the optional data.
+        let header = bytes.get(..6).ok_or(LoadOptionError::Truncated)?;
+        let attributes = u32::from_le_bytes(header[..4].try_into().unwrap());
+        let file_path_list_len = usize::from(u16::from_le_bytes([header[4], header[5]]));
+
+        let description_len = bytes[6..]
+            .chunks_exact(2)
+            .position(|c| c == [0, 0])
+            .ok_or(LoadOptionError::Truncated)?
+            + 1;
+        let description: Vec<u16> = bytes[6..]
+            .chunks_exact(2)
+            .take(description_len)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        let description =
+            CString16::try_from(description).map_err(LoadOptionError::InvalidDescription)?;
+
+        let start = 6 + 2 * description_len;
+        let file_path_list = bytes
+            .get(start..start + file_path_list_len)
+            .ok_or(LoadOptionError::Truncated)?;
+        <&DevicePath>::try_from(file_path_list)
+            .map_err(|_| LoadOptionError::InvalidFilePathList)?;
+
+        Ok(Self {
+            attributes: LoadOptionAttributes::from_bits_retain(attributes),
+            description,
+            file_path_list: file_path_list.to_vec(),
+            optional_data: bytes[start + file_path_list_len..].to_vec(),
+        })
+    }
+
+    /// Returns the device path of the image to load, the first one of the
+    /// file path list.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file path list doesn't start with a valid
+    /// device path, which can only happen if it was modified after
+    /// [`parse`](Self::parse).
+    pub fn file_path(&self) -> Result<&DevicePath, ByteConversionError> {
+        <&DevicePath>::try_from(self.file_path_list.as_slice())
+    }
+
+    /// Returns the content of the load option variable.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file path list is larger than 64 KiB.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let file_path_list_len =
+            u16::try_from(self.file_path_list.len()).expect("file path list is too large");
+        let description = self.description.as_bytes();
+
+        let mut bytes = Vec::with_capacity(
+            6 + description.len() + self.file_path_list.len() + self.optional_data.len(),
+        );
+        bytes.extend_from_slice(&self.attributes.bits().to_le_bytes());
+        bytes.extend_from_slice(&file_path_list_len.to_le_bytes());
+        bytes.extend_from_slice(description);
+        bytes.extend_from_slice(&self.file_path_list);
+        bytes.extend_from_slice(&self.optional_data);
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cstr16;
+
+    /// Firmware volume file node, then the end node.
+    #[rustfmt::skip]
+    const FILE_PATH: &[u8] = &[
+        0x04, 0x06, 20, 0,
+        0xdc, 0x5b, 0xc2, 0xee, 0xf2, 0x67, 0x95, 0x4d,
+        0xb1, 0xd5, 0xf8, 0x1b, 0x20, 0x39, 0xd1, 0x1d,
+        0x7f, 0xff, 4, 0,
+    ];
+
+    #[test]
+    fn test_round_trip() {
+        let file_path = <&DevicePath>::try_from(FILE_PATH).unwrap();
+        let mut option = LoadOption::new(
+            LoadOptionAttributes::ACTIVE | LoadOptionAttributes::CATEGORY_APP,
+            cstr16!("UEFI Boot Menu"),
+            file_path,
+        );
+        option.optional_data = b"data".to_vec();
+
+        let bytes = option.to_bytes();
+        assert_eq!(bytes[..6], [0x01, 0x01, 0, 0, 24, 0]);
+        assert_eq!(bytes.len(), 6 + 30 + 24 + 4);
+        let parsed = LoadOption::parse(&bytes).unwrap();
+        assert_eq!(parsed, option);
+        assert_eq!(parsed.file_path().unwrap(), file_path);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let option = LoadOption::new(
+            LoadOptionAttributes::ACTIVE,
+            cstr16!("Boot"),
+            <&DevicePath>::try_from(FILE_PATH).unwrap(),
+        );
+        let bytes = option.to_bytes();
+        assert_eq!(
+            LoadOption::parse(&bytes[..5]),
+            Err(LoadOptionError::Truncated)
+        );
+        assert_eq!(
+            LoadOption::parse(&bytes[..12]),
+            Err(LoadOptionError::Truncated)
+        );
+        assert_eq!(
+            LoadOption::parse(&bytes[..bytes.len() - 1]),
+            Err(LoadOptionError::Truncated)
+        );
+
+        let mut bytes = bytes;
+        // Make the first node extend past the file path list.
+        bytes[18] = 200;
+        assert_eq!(
+            LoadOption::parse(&bytes),
+            Err(LoadOptionError::InvalidFilePathList)
+        );
+
+        // A node of length 0 in the file path list.
+        assert_eq!(
+            LoadOption::parse(&[1, 0, 0, 0, 4, 0, 0, 0, 4, 0x15, 0, 0]),
+            Err(LoadOptionError::InvalidFilePathList)
+        );
+    }
+}
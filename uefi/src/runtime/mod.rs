@@ -6,56 +6,85 @@
 //! services. Note that various restrictions apply when calling runtime services
 //! functions after exiting boot services; see the "Calling Convention" section
 //! of the UEFI specification for details.
+//!
+//! Wrappers for services that are no longer callable, because boot services
+//! were exited or the address map was already set, fail with
+//! [`Status::UNSUPPORTED`] instead of calling into the firmware; see the
+//! [`availability`] module.
 
 use crate::data_types::PhysicalAddress;
-use crate::table::{self, Revision};
-use crate::{CStr16, Error, Result, Status, StatusExt};
+use crate::table;
+use crate::{CStr16, Status, StatusExt};
 use core::fmt::{self, Debug, Display, Formatter};
 use core::ptr::{self, NonNull};
+use core::time::Duration;
 use uefi_raw::table::boot::MemoryDescriptor;
 
 #[cfg(feature = "alloc")]
 use {
     crate::CString16,
+    crate::Error,
     crate::Guid,
     crate::mem::make_boxed,
+    crate::quirks::{self, Quirks},
     alloc::borrow::ToOwned,
     alloc::boxed::Box,
     alloc::{vec, vec::Vec},
 };
 
+pub mod availability;
+#[cfg(feature = "alloc")]
+pub mod backup;
+#[cfg(feature = "alloc")]
+pub mod capsule;
+#[cfg(feature = "alloc")]
+mod load_option;
+pub mod post_ebs;
+mod reboot;
+#[cfg(feature = "alloc")]
+mod transaction;
+
+#[cfg(feature = "alloc")]
+pub use load_option::{LoadOption, LoadOptionAttributes, LoadOptionError};
+#[cfg(feature = "alloc")]
+pub use reboot::reboot_to_boot_menu;
+pub use reboot::{
+    RebootError, os_indications_supported, reboot_to_firmware_setup, request_os_indications,
+};
+#[cfg(feature = "alloc")]
+pub use transaction::{TransactionError, VariableTransaction};
+
+pub use availability::{RuntimeError, RuntimeResult, RuntimeService, Unavailable};
+pub use post_ebs::RuntimePostEbs;
 pub use uefi_raw::capsule::{CapsuleBlockDescriptor, CapsuleFlags, CapsuleHeader};
 pub use uefi_raw::table::runtime::{
-    ResetType, TimeCapabilities, VariableAttributes, VariableVendor,
+    OsIndications, ResetType, RtPropertiesTable, RuntimeServicesSupported, TimeCapabilities,
+    VariableAttributes, VariableVendor,
 };
 pub use uefi_raw::time::Daylight;
 
+#[track_caller]
 fn runtime_services_raw_panicking() -> NonNull<uefi_raw::table::runtime::RuntimeServices> {
-    let st = table::system_table_raw_panicking();
-    // SAFETY: valid per requirements of `set_system_table`.
-    let st = unsafe { st.as_ref() };
-    NonNull::new(st.runtime_services).expect("runtime services are not active")
+    RuntimePostEbs::current_panicking().as_ptr()
 }
 
 /// Query the current time and date information.
-pub fn get_time() -> Result<Time> {
-    let rt = runtime_services_raw_panicking();
-    let rt = unsafe { rt.as_ref() };
-
-    let mut time = Time::invalid();
-    let time_ptr: *mut Time = &mut time;
-    unsafe { (rt.get_time)(time_ptr.cast(), ptr::null_mut()) }.to_result_with_val(|| time)
+pub fn get_time() -> RuntimeResult<Time> {
+    RuntimePostEbs::current_panicking().get_time()
 }
 
 /// Query the current time and date information and the RTC capabilities.
-pub fn get_time_and_caps() -> Result<(Time, TimeCapabilities)> {
+pub fn get_time_and_caps() -> RuntimeResult<(Time, TimeCapabilities)> {
+    availability::check(RuntimeService::GetTime)?;
     let rt = runtime_services_raw_panicking();
     let rt = unsafe { rt.as_ref() };
 
     let mut time = Time::invalid();
     let time_ptr: *mut Time = &mut time;
     let mut caps = TimeCapabilities::default();
-    unsafe { (rt.get_time)(time_ptr.cast(), &mut caps) }.to_result_with_val(|| (time, caps))
+    unsafe { (rt.get_time)(time_ptr.cast(), &mut caps) }
+        .to_result_with_val(|| (time, caps))
+        .map_err(Into::into)
 }
 
 /// Sets the current local time and date information
@@ -67,12 +96,103 @@ pub fn get_time_and_caps() -> Result<(Time, TimeCapabilities)> {
 ///
 /// Undefined behavior could happen if multiple tasks try to
 /// use this function at the same time without synchronisation.
-pub unsafe fn set_time(time: &Time) -> Result {
+pub unsafe fn set_time(time: &Time) -> RuntimeResult {
+    availability::check(RuntimeService::SetTime)?;
     let rt = runtime_services_raw_panicking();
     let rt = unsafe { rt.as_ref() };
 
     let time: *const Time = time;
-    unsafe { (rt.set_time)(time.cast()) }.to_result()
+    unsafe { (rt.set_time)(time.cast()) }
+        .to_result()
+        .map_err(Into::into)
+}
+
+/// Wakeup alarm state returned by [`get_wakeup_time`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct WakeupTime {
+    /// Whether the alarm is enabled.
+    pub enabled: bool,
+    /// Whether the alarm signal is pending and requires acknowledgement.
+    pub pending: bool,
+    /// The time the alarm is set to.
+    pub time: Time,
+}
+
+/// Queries the current wakeup alarm clock setting.
+///
+/// # Errors
+///
+/// * [`Status::DEVICE_ERROR`]: the wakeup time could not be retrieved due to
+///   a hardware error.
+/// * [`Status::UNSUPPORTED`]: the platform does not support a wakeup alarm.
+pub fn get_wakeup_time() -> RuntimeResult<WakeupTime> {
+    availability::check(RuntimeService::GetWakeupTime)?;
+    let rt = runtime_services_raw_panicking();
+    let rt = unsafe { rt.as_ref() };
+
+    let mut enabled = 0;
+    let mut pending = 0;
+    let mut time = Time::invalid();
+    let time_ptr: *mut Time = &mut time;
+    unsafe { (rt.get_wakeup_time)(&mut enabled, &mut pending, time_ptr.cast()) }
+        .to_result_with_val(|| WakeupTime {
+            enabled: enabled != 0,
+            pending: pending != 0,
+            time,
+        })
+        .map_err(Into::into)
+}
+
+/// Sets the wakeup alarm clock to `time`, or disables it if `time` is
+/// `None`.
+///
+/// When the alarm fires, the system is powered on if it is in a sleep or
+/// off state.
+///
+/// # Safety
+///
+/// Undefined behavior could happen if multiple tasks try to
+/// use this function at the same time without synchronisation.
+///
+/// # Errors
+///
+/// * [`Status::INVALID_PARAMETER`]: `time` is out of range.
+/// * [`Status::DEVICE_ERROR`]: the wakeup time could not be set due to a
+///   hardware error.
+/// * [`Status::UNSUPPORTED`]: the platform does not support a wakeup alarm.
+pub unsafe fn set_wakeup_time(time: Option<&Time>) -> RuntimeResult {
+    availability::check(RuntimeService::SetWakeupTime)?;
+    let rt = runtime_services_raw_panicking();
+    let rt = unsafe { rt.as_ref() };
+
+    let enable = u8::from(time.is_some());
+    let time: *const Time = time.map_or(ptr::null(), ptr::from_ref);
+    unsafe { (rt.set_wakeup_time)(enable, time.cast()) }
+        .to_result()
+        .map_err(Into::into)
+}
+
+/// Sets the wakeup alarm clock to `duration` from now, according to the
+/// current time returned by [`get_time`]. Returns the time the alarm was
+/// set to.
+///
+/// # Safety
+///
+/// Undefined behavior could happen if multiple tasks try to
+/// use this function at the same time without synchronisation.
+///
+/// # Errors
+///
+/// * [`Status::INVALID_PARAMETER`]: the resulting time is after the year 9999.
+/// * [`Status::DEVICE_ERROR`]: the time could not be retrieved or the wakeup
+///   time could not be set due to a hardware error.
+/// * [`Status::UNSUPPORTED`]: the platform does not support a wakeup alarm.
+pub unsafe fn set_wakeup_after(duration: Duration) -> RuntimeResult<Time> {
+    let time = get_time()?
+        .checked_add(duration)
+        .ok_or(Status::INVALID_PARAMETER)?;
+    unsafe { set_wakeup_time(Some(&time)) }?;
+    Ok(time)
 }
 
 /// Checks if a variable exists.
@@ -87,7 +207,8 @@ pub unsafe fn set_time(time: &Time) -> Result {
 ///   authentication error.
 /// * [`Status::UNSUPPORTED`]: this platform does not support variable storage
 ///   after exiting boot services.
-pub fn variable_exists(name: &CStr16, vendor: &VariableVendor) -> Result<bool> {
+pub fn variable_exists(name: &CStr16, vendor: &VariableVendor) -> RuntimeResult<bool> {
+    availability::check(RuntimeService::GetVariable)?;
     let rt = runtime_services_raw_panicking();
     let rt = unsafe { rt.as_ref() };
 
@@ -112,7 +233,7 @@ pub fn variable_exists(name: &CStr16, vendor: &VariableVendor) -> Result<bool> {
         // status will never be SUCCESS.
         Status::BUFFER_TOO_SMALL => Ok(true),
         Status::NOT_FOUND => Ok(false),
-        _ => Err(Error::from(status)),
+        _ => Err(status.into()),
     }
 }
 
@@ -136,27 +257,8 @@ pub fn get_variable<'buf>(
     name: &CStr16,
     vendor: &VariableVendor,
     buf: &'buf mut [u8],
-) -> Result<(&'buf mut [u8], VariableAttributes), Option<usize>> {
-    let rt = runtime_services_raw_panicking();
-    let rt = unsafe { rt.as_ref() };
-
-    let mut attributes = VariableAttributes::empty();
-    let mut data_size = buf.len();
-    let status = unsafe {
-        (rt.get_variable)(
-            name.as_ptr().cast(),
-            &vendor.0,
-            &mut attributes,
-            &mut data_size,
-            buf.as_mut_ptr(),
-        )
-    };
-
-    match status {
-        Status::SUCCESS => Ok((&mut buf[..data_size], attributes)),
-        Status::BUFFER_TOO_SMALL => Err(Error::new(status, Some(data_size))),
-        _ => Err(Error::new(status, None)),
-    }
+) -> RuntimeResult<(&'buf mut [u8], VariableAttributes), Option<usize>> {
+    RuntimePostEbs::current_panicking().get_variable(name, vendor, buf)
 }
 
 /// Gets the contents and attributes of a variable.
@@ -173,18 +275,24 @@ pub fn get_variable<'buf>(
 pub fn get_variable_boxed(
     name: &CStr16,
     vendor: &VariableVendor,
-) -> Result<(Box<[u8]>, VariableAttributes)> {
+) -> RuntimeResult<(Box<[u8]>, VariableAttributes)> {
+    // Report an unavailable service before `make_boxed` flattens the error.
+    availability::check(RuntimeService::GetVariable)?;
     let mut out_attr = VariableAttributes::empty();
     let get_var = |buf| {
-        get_variable(name, vendor, buf).map(|(val, attr)| {
-            // `make_boxed` expects only a DST value to be returned (`val` in
-            // this case), so smuggle the `attr` value out via a separate
-            // variable.
-            out_attr = attr;
-            val
-        })
+        get_variable(name, vendor, buf)
+            .map(|(val, attr)| {
+                // `make_boxed` expects only a DST value to be returned (`val` in
+                // this case), so smuggle the `attr` value out via a separate
+                // variable.
+                out_attr = attr;
+                val
+            })
+            .map_err(Error::from)
     };
-    make_boxed(get_var).map(|val| (val, out_attr))
+    make_boxed(get_var)
+        .map(|val| (val, out_attr))
+        .map_err(Into::into)
 }
 
 /// Gets each variable key (name and vendor) one at a time.
@@ -216,23 +324,8 @@ pub fn get_variable_boxed(
 pub fn get_next_variable_key(
     name: &mut [u16],
     vendor: &mut VariableVendor,
-) -> Result<(), Option<usize>> {
-    let rt = runtime_services_raw_panicking();
-    let rt = unsafe { rt.as_ref() };
-
-    let mut name_size_in_bytes = size_of_val(name);
-
-    let status = unsafe {
-        (rt.get_next_variable_name)(&mut name_size_in_bytes, name.as_mut_ptr(), &mut vendor.0)
-    };
-    match status {
-        Status::SUCCESS => Ok(()),
-        Status::BUFFER_TOO_SMALL => Err(Error::new(
-            status,
-            Some(name_size_in_bytes / size_of::<u16>()),
-        )),
-        _ => Err(Error::new(status, None)),
-    }
+) -> RuntimeResult<(), Option<usize>> {
+    RuntimePostEbs::current_panicking().get_next_variable_key(name, vendor)
 }
 
 /// Get an iterator over all UEFI variables.
@@ -246,7 +339,7 @@ pub fn variable_keys() -> VariableKeys {
 
 /// Iterator over all UEFI variables.
 ///
-/// Each iteration yields a <code>Result<`[`VariableKey`]`></code>. Error values:
+/// Each iteration yields a <code>RuntimeResult<`[`VariableKey`]`></code>. Error values:
 ///
 /// * [`Status::DEVICE_ERROR`]: variable could not be read due to a hardware error.
 /// * [`Status::UNSUPPORTED`]: this platform does not support variable storage
@@ -266,7 +359,8 @@ impl VariableKeys {
         // initialize it. A Toshiba Satellite Pro R50-B-12P was found
         // to not correctly update the VariableNameSize passed into
         // GetNextVariableName and starting with a large buffer works
-        // around this issue.
+        // around this issue for most names. Longer names are handled by
+        // `Quirks::VARIABLE_NAME_SIZE_NOT_UPDATED`.
         let name = vec![0; 512];
 
         Self {
@@ -280,9 +374,9 @@ impl VariableKeys {
 
 #[cfg(feature = "alloc")]
 impl Iterator for VariableKeys {
-    type Item = Result<VariableKey>;
+    type Item = RuntimeResult<VariableKey>;
 
-    fn next(&mut self) -> Option<Result<VariableKey>> {
+    fn next(&mut self) -> Option<RuntimeResult<VariableKey>> {
         if self.is_done {
             return None;
         }
@@ -290,10 +384,18 @@ impl Iterator for VariableKeys {
         let mut result = get_next_variable_key(&mut self.name, &mut self.vendor);
 
         // If the name buffer was too small, resize it to be big enough and call
-        // `get_next_variable_key` again.
+        // `get_next_variable_key` again. Firmware with a known bug doesn't
+        // report the required size, so guess it by doubling the buffer.
         if let Err(err) = &result {
-            if let Some(required_size) = err.data() {
-                self.name.resize(*required_size, 0u16);
+            if let Some(&Some(required_size)) = err.data() {
+                let required_size = if required_size <= self.name.len()
+                    && quirks::is_active(Quirks::VARIABLE_NAME_SIZE_NOT_UPDATED)
+                {
+                    self.name.len() * 2
+                } else {
+                    required_size
+                };
+                self.name.resize(required_size, 0u16);
                 result = get_next_variable_key(&mut self.name, &mut self.vendor);
             }
         }
@@ -351,20 +453,8 @@ pub fn set_variable(
     vendor: &VariableVendor,
     attributes: VariableAttributes,
     data: &[u8],
-) -> Result {
-    let rt = runtime_services_raw_panicking();
-    let rt = unsafe { rt.as_ref() };
-
-    unsafe {
-        (rt.set_variable)(
-            name.as_ptr().cast(),
-            &vendor.0,
-            attributes,
-            data.len(),
-            data.as_ptr(),
-        )
-        .to_result()
-    }
+) -> RuntimeResult {
+    RuntimePostEbs::current_panicking().set_variable(name, vendor, attributes, data)
 }
 
 /// Deletes a UEFI variable.
@@ -378,7 +468,7 @@ pub fn set_variable(
 /// * [`Status::NOT_FOUND`]: attempted to delete a non-existent variable.
 /// * [`Status::UNSUPPORTED`]: this platform does not support variable storage
 ///   after exiting boot services.
-pub fn delete_variable(name: &CStr16, vendor: &VariableVendor) -> Result {
+pub fn delete_variable(name: &CStr16, vendor: &VariableVendor) -> RuntimeResult {
     set_variable(name, vendor, VariableAttributes::empty(), &[])
 }
 
@@ -394,24 +484,8 @@ pub fn delete_variable(name: &CStr16, vendor: &VariableVendor) -> Result {
 /// * [`Status::INVALID_PARAMETER`]: invalid combination of variable attributes.
 /// * [`Status::UNSUPPORTED`]: the combination of variable attributes is not
 ///   supported on this platform, or the UEFI version is less than 2.0.
-pub fn query_variable_info(attributes: VariableAttributes) -> Result<VariableStorageInfo> {
-    let rt = runtime_services_raw_panicking();
-    let rt = unsafe { rt.as_ref() };
-
-    if rt.header.revision < Revision::EFI_2_00 {
-        return Err(Status::UNSUPPORTED.into());
-    }
-
-    let mut info = VariableStorageInfo::default();
-    unsafe {
-        (rt.query_variable_info)(
-            attributes,
-            &mut info.maximum_variable_storage_size,
-            &mut info.remaining_variable_storage_size,
-            &mut info.maximum_variable_size,
-        )
-        .to_result_with_val(|| info)
-    }
+pub fn query_variable_info(attributes: VariableAttributes) -> RuntimeResult<VariableStorageInfo> {
+    RuntimePostEbs::current_panicking().query_variable_info(attributes)
 }
 
 /// Passes capsules to the firmware.
@@ -434,7 +508,8 @@ pub fn query_variable_info(attributes: VariableAttributes) -> Result<VariableSto
 pub fn update_capsule(
     capsule_header_array: &[&CapsuleHeader],
     capsule_block_descriptors: &[CapsuleBlockDescriptor],
-) -> Result {
+) -> RuntimeResult {
+    availability::check(RuntimeService::UpdateCapsule)?;
     let rt = runtime_services_raw_panicking();
     let rt = unsafe { rt.as_ref() };
 
@@ -445,6 +520,7 @@ pub fn update_capsule(
             capsule_block_descriptors.as_ptr() as PhysicalAddress,
         )
         .to_result()
+        .map_err(Into::into)
     }
 }
 
@@ -462,7 +538,10 @@ pub fn update_capsule(
 /// * [`Status::UNSUPPORTED`]: either the capsule type is not supported by this
 ///   platform, or the platform does not support capsule updates after exiting
 ///   boot services.
-pub fn query_capsule_capabilities(capsule_header_array: &[&CapsuleHeader]) -> Result<CapsuleInfo> {
+pub fn query_capsule_capabilities(
+    capsule_header_array: &[&CapsuleHeader],
+) -> RuntimeResult<CapsuleInfo> {
+    availability::check(RuntimeService::QueryCapsuleCapabilities)?;
     let rt = runtime_services_raw_panicking();
     let rt = unsafe { rt.as_ref() };
 
@@ -475,6 +554,7 @@ pub fn query_capsule_capabilities(capsule_header_array: &[&CapsuleHeader]) -> Re
             &mut info.reset_type,
         )
         .to_result_with_val(|| info)
+        .map_err(Into::into)
     }
 }
 
@@ -491,15 +571,13 @@ pub fn query_capsule_capabilities(capsule_header_array: &[&CapsuleHeader]) -> Re
 /// [`Guid`] that indicates the type of reset to perform.
 ///
 /// This function never returns.
+///
+/// # Panics
+///
+/// Panics if `ResetSystem` is not available after exiting boot services;
+/// see [`availability`].
 pub fn reset(reset_type: ResetType, status: Status, data: Option<&[u8]>) -> ! {
-    let rt = runtime_services_raw_panicking();
-    let rt = unsafe { rt.as_ref() };
-
-    let (size, data) = data
-        .map(|data| (data.len(), data.as_ptr()))
-        .unwrap_or((0, ptr::null()));
-
-    unsafe { (rt.reset_system)(reset_type, status, size, data) }
+    RuntimePostEbs::current_panicking().reset(reset_type, status, data)
 }
 
 /// Changes the runtime addressing mode of EFI firmware from physical to
@@ -509,7 +587,8 @@ pub fn reset(reset_type: ResetType, status: Status, data: Option<&[u8]>) -> ! {
 /// to a new virtual address and provide it for this function.
 ///
 /// If successful, this function will call [`set_system_table`] with
-/// `new_system_table_virtual_addr`.
+/// `new_system_table_virtual_addr`, and translate the table set by
+/// [`post_ebs::set_runtime_services`] to its virtual address in `map`.
 ///
 /// [`set_system_table`]: table::set_system_table
 ///
@@ -528,7 +607,8 @@ pub fn reset(reset_type: ResetType, status: Status, data: Option<&[u8]>) -> ! {
 pub unsafe fn set_virtual_address_map(
     map: &mut [MemoryDescriptor],
     new_system_table_virtual_addr: *const uefi_raw::table::system::SystemTable,
-) -> Result {
+) -> RuntimeResult {
+    availability::check(RuntimeService::SetVirtualAddressMap)?;
     let rt = runtime_services_raw_panicking();
     let rt = unsafe { rt.as_ref() };
 
@@ -543,8 +623,11 @@ pub unsafe fn set_virtual_address_map(
     unsafe { (rt.set_virtual_address_map)(map_size, entry_size, entry_version, map_ptr) }
         .to_result()?;
 
-    // Update the global system table pointer.
+    // Update the global system table pointer, and the runtime services
+    // table pointer if one was set.
     unsafe { table::set_system_table(new_system_table_virtual_addr) };
+    post_ebs::convert_runtime_services(map);
+    availability::mark_virtual();
 
     Ok(())
 }
@@ -768,6 +851,75 @@ impl Time {
     pub const fn daylight(&self) -> Daylight {
         self.0.daylight
     }
+
+    /// Returns the time `duration` after `self`, with the same time zone
+    /// and daylight savings time information.
+    ///
+    /// Returns `None` if `self` is not valid or if the result is after the
+    /// year 9999. Leap seconds and daylight savings time transitions are not
+    /// taken into account.
+    #[must_use]
+    pub fn checked_add(&self, duration: Duration) -> Option<Self> {
+        self.is_valid().ok()?;
+
+        let nanos = self.nanosecond() + duration.subsec_nanos();
+        let seconds_of_day = u64::from(self.hour()) * 3600
+            + u64::from(self.minute()) * 60
+            + u64::from(self.second());
+        let seconds = seconds_of_day
+            .checked_add(duration.as_secs())?
+            .checked_add(u64::from(nanos / 1_000_000_000))?;
+
+        let days = days_from_civil(self.year(), self.month(), self.day())
+            .checked_add(i64::try_from(seconds / 86400).ok()?)?;
+        let (year, month, day) = civil_from_days(days)?;
+        let seconds_of_day = seconds % 86400;
+
+        Self::new(TimeParams {
+            year,
+            month,
+            day,
+            hour: (seconds_of_day / 3600) as u8,
+            minute: (seconds_of_day / 60 % 60) as u8,
+            second: (seconds_of_day % 60) as u8,
+            nanosecond: nanos % 1_000_000_000,
+            time_zone: self.time_zone(),
+            daylight: self.daylight(),
+        })
+        .ok()
+    }
+}
+
+/// Returns the number of days since 1970-01-01 of a date in the proleptic
+/// Gregorian calendar.
+///
+/// Based on <https://howardhinnant.github.io/date_algorithms.html#days_from_civil>.
+const fn days_from_civil(year: u16, month: u8, day: u8) -> i64 {
+    let year = year as i64 - (month <= 2) as i64;
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Inverse of [`days_from_civil`]. Returns `None` if the year doesn't fit
+/// in a `u16`.
+///
+/// Based on <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+fn civil_from_days(days: i64) -> Option<(u16, u8, u8)> {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u8;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    Some((u16::try_from(year).ok()?, month, day))
 }
 
 impl Debug for Time {
@@ -927,3 +1079,59 @@ pub struct CapsuleInfo {
     /// The type of reset required for the capsule update.
     pub reset_type: ResetType,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> Time {
+        Time::new(TimeParams {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+            nanosecond: 0,
+            time_zone: None,
+            daylight: Daylight::empty(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_days_from_civil() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11017);
+        assert_eq!(civil_from_days(11017), Some((2000, 3, 1)));
+        assert_eq!(civil_from_days(-25567), Some((1900, 1, 1)));
+    }
+
+    #[test]
+    fn test_time_checked_add() {
+        let t = time(2023, 12, 31, 23, 59, 30);
+        assert_eq!(
+            t.checked_add(Duration::from_secs(45)),
+            Some(time(2024, 1, 1, 0, 0, 15))
+        );
+        // Leap day.
+        assert_eq!(
+            time(2024, 2, 28, 12, 0, 0).checked_add(Duration::from_secs(86400)),
+            Some(time(2024, 2, 29, 12, 0, 0))
+        );
+        assert_eq!(
+            time(2100, 2, 28, 12, 0, 0).checked_add(Duration::from_secs(86400)),
+            Some(time(2100, 3, 1, 12, 0, 0))
+        );
+        // Nanosecond carry.
+        let t = time(2024, 1, 1, 0, 0, 0)
+            .checked_add(Duration::new(0, 999_999_999))
+            .unwrap();
+        assert_eq!(t.checked_add(Duration::from_nanos(2)).unwrap().second(), 1);
+        assert_eq!(
+            time(9999, 12, 31, 23, 59, 59).checked_add(Duration::from_secs(1)),
+            None
+        );
+        assert_eq!(Time::invalid().checked_add(Duration::ZERO), None);
+    }
+}
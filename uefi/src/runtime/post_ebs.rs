@@ -0,0 +1,374 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Runtime services after exiting boot services.
+//!
+//! Once the OS calls `SetVirtualAddressMap`, the physical addresses of the
+//! system table and runtime services table are no longer valid. Kernels and
+//! runtime drivers can keep using the variable and time services by:
+//!
+//! * calling [`register_virtual_address_change`] while boot services are
+//!   active, so that the pointers used by this crate are converted with
+//!   `ConvertPointer` when the address map is set. This is what runtime
+//!   drivers do; their code must be loaded in runtime memory.
+//! * creating a [`RuntimePostEbs`] handle from the virtual address of the
+//!   runtime services table, and passing it to [`set_runtime_services`] if
+//!   the functions of the [`runtime`] module should use it as well. This is
+//!   how a kernel that set the map itself passes it to this crate.
+//!
+//! [`runtime`]: super
+
+use super::availability::{self, RuntimeResult, RuntimeService};
+use super::{ResetType, Time, VariableAttributes, VariableStorageInfo, VariableVendor};
+use crate::boot::{self, EventType, Tpl};
+use crate::table::{self, Revision};
+use crate::{CStr16, Error, Event, Result, Status, StatusExt};
+use core::ffi::c_void;
+use core::ptr::{self, NonNull};
+use core::sync::atomic::{AtomicPtr, Ordering};
+use uefi_raw::table::boot::{MemoryDescriptor, PAGE_SIZE};
+use uefi_raw::table::runtime::RuntimeServices;
+use uefi_raw::table::system::SystemTable;
+
+/// Runtime services table used instead of the one of the system table, set
+/// by [`set_runtime_services`] and [`register_virtual_address_change`].
+static RUNTIME_SERVICES: AtomicPtr<RuntimeServices> = AtomicPtr::new(ptr::null_mut());
+
+/// Handle to the runtime services table, valid in the current addressing
+/// mode.
+///
+/// The functions of the [`runtime`] module use the handle returned by
+/// [`current`]. After `SetVirtualAddressMap`, a handle created from the
+/// virtual address of the table with [`new`] can be used directly, or
+/// registered with [`set_runtime_services`], unless
+/// [`register_virtual_address_change`] converted it.
+///
+/// # Example
+///
+/// ```no_run
+/// use core::ptr::NonNull;
+/// use uefi::runtime::post_ebs::RuntimePostEbs;
+/// use uefi::runtime::VariableVendor;
+/// use uefi::cstr16;
+///
+/// # fn example(virtual_rt: NonNull<uefi_raw::table::runtime::RuntimeServices>) -> uefi::Result {
+/// // SAFETY: `virtual_rt` is the runtime services table, mapped at the
+/// // virtual address passed to `SetVirtualAddressMap`.
+/// let rt = unsafe { RuntimePostEbs::new(virtual_rt) };
+/// // Optionally, use it for the functions of the `runtime` module too.
+/// // SAFETY: `virtual_rt` stays mapped, and runtime services are not used
+/// // concurrently.
+/// unsafe { uefi::runtime::post_ebs::set_runtime_services(rt) };
+/// let time = rt.get_time()?;
+/// let mut buf = [0; 8];
+/// let (data, _) = rt
+///     .get_variable(cstr16!("Timeout"), &VariableVendor::GLOBAL_VARIABLE, &mut buf)
+///     .map_err(|err| err.to_err_without_payload())?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`current`]: Self::current
+/// [`new`]: Self::new
+/// [`runtime`]: super
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RuntimePostEbs(NonNull<RuntimeServices>);
+
+impl RuntimePostEbs {
+    /// Creates a handle to the runtime services table at `rt`.
+    ///
+    /// This does not change the table used by the [`runtime`] module; see
+    /// [`set_runtime_services`].
+    ///
+    /// # Safety
+    ///
+    /// `rt` must point to the runtime services table in the current
+    /// addressing mode, and stay valid for as long as the handle is used.
+    ///
+    /// [`runtime`]: super
+    #[must_use]
+    pub const unsafe fn new(rt: NonNull<RuntimeServices>) -> Self {
+        Self(rt)
+    }
+
+    /// Returns the handle used by the [`runtime`] module: the one set by
+    /// [`set_runtime_services`] or [`register_virtual_address_change`],
+    /// otherwise the table of the system table.
+    ///
+    /// Returns `None` if there is neither, e.g. because the system table
+    /// was not set.
+    ///
+    /// [`runtime`]: super
+    #[must_use]
+    pub fn current() -> Option<Self> {
+        if let Some(rt) = NonNull::new(RUNTIME_SERVICES.load(Ordering::Acquire)) {
+            return Some(Self(rt));
+        }
+        let st = table::system_table_raw()?;
+        // SAFETY: valid per requirements of `set_system_table`.
+        let st = unsafe { st.as_ref() };
+        NonNull::new(st.runtime_services).map(Self)
+    }
+
+    /// Like [`current`], but panics if there is no runtime services table.
+    ///
+    /// [`current`]: Self::current
+    #[track_caller]
+    pub(super) fn current_panicking() -> Self {
+        Self::current().expect("runtime services are not active")
+    }
+
+    /// Returns the pointer to the runtime services table.
+    #[must_use]
+    pub const fn as_ptr(&self) -> NonNull<RuntimeServices> {
+        self.0
+    }
+
+    /// Queries the current time and date information. See [`get_time`](super::get_time).
+    ///
+    /// # Errors
+    ///
+    /// See [`get_time`](super::get_time).
+    pub fn get_time(&self) -> RuntimeResult<Time> {
+        availability::check(RuntimeService::GetTime)?;
+        let rt = unsafe { self.0.as_ref() };
+
+        let mut time = Time::invalid();
+        let time_ptr: *mut Time = &mut time;
+        unsafe { (rt.get_time)(time_ptr.cast(), ptr::null_mut()) }
+            .to_result_with_val(|| time)
+            .map_err(Into::into)
+    }
+
+    /// Gets the contents and attributes of a variable. See [`get_variable`](super::get_variable).
+    ///
+    /// # Errors
+    ///
+    /// See [`get_variable`](super::get_variable).
+    pub fn get_variable<'buf>(
+        &self,
+        name: &CStr16,
+        vendor: &VariableVendor,
+        buf: &'buf mut [u8],
+    ) -> RuntimeResult<(&'buf mut [u8], VariableAttributes), Option<usize>> {
+        availability::check(RuntimeService::GetVariable)?;
+        let rt = unsafe { self.0.as_ref() };
+
+        let mut attributes = VariableAttributes::empty();
+        let mut data_size = buf.len();
+        let status = unsafe {
+            (rt.get_variable)(
+                name.as_ptr().cast(),
+                &vendor.0,
+                &mut attributes,
+                &mut data_size,
+                buf.as_mut_ptr(),
+            )
+        };
+
+        match status {
+            Status::SUCCESS => Ok((&mut buf[..data_size], attributes)),
+            Status::BUFFER_TOO_SMALL => Err(Error::new(status, Some(data_size)).into()),
+            _ => Err(Error::new(status, None).into()),
+        }
+    }
+
+    /// Gets the next variable key. See [`get_next_variable_key`](super::get_next_variable_key).
+    ///
+    /// # Errors
+    ///
+    /// See [`get_next_variable_key`](super::get_next_variable_key).
+    pub fn get_next_variable_key(
+        &self,
+        name: &mut [u16],
+        vendor: &mut VariableVendor,
+    ) -> RuntimeResult<(), Option<usize>> {
+        availability::check(RuntimeService::GetNextVariableName)?;
+        let rt = unsafe { self.0.as_ref() };
+
+        let mut name_size_in_bytes = size_of_val(name);
+
+        let status = unsafe {
+            (rt.get_next_variable_name)(&mut name_size_in_bytes, name.as_mut_ptr(), &mut vendor.0)
+        };
+        match status {
+            Status::SUCCESS => Ok(()),
+            Status::BUFFER_TOO_SMALL => {
+                Err(Error::new(status, Some(name_size_in_bytes / size_of::<u16>())).into())
+            }
+            _ => Err(Error::new(status, None).into()),
+        }
+    }
+
+    /// Sets the value of a variable. See [`set_variable`](super::set_variable).
+    ///
+    /// # Errors
+    ///
+    /// See [`set_variable`](super::set_variable).
+    pub fn set_variable(
+        &self,
+        name: &CStr16,
+        vendor: &VariableVendor,
+        attributes: VariableAttributes,
+        data: &[u8],
+    ) -> RuntimeResult {
+        availability::check(RuntimeService::SetVariable)?;
+        let rt = unsafe { self.0.as_ref() };
+
+        unsafe {
+            (rt.set_variable)(
+                name.as_ptr().cast(),
+                &vendor.0,
+                attributes,
+                data.len(),
+                data.as_ptr(),
+            )
+            .to_result()
+            .map_err(Into::into)
+        }
+    }
+
+    /// Gets information about variable storage. See [`query_variable_info`](super::query_variable_info).
+    ///
+    /// # Errors
+    ///
+    /// See [`query_variable_info`](super::query_variable_info).
+    pub fn query_variable_info(
+        &self,
+        attributes: VariableAttributes,
+    ) -> RuntimeResult<VariableStorageInfo> {
+        availability::check(RuntimeService::QueryVariableInfo)?;
+        let rt = unsafe { self.0.as_ref() };
+
+        if rt.header.revision < Revision::EFI_2_00 {
+            return Err(Status::UNSUPPORTED.into());
+        }
+
+        let mut info = VariableStorageInfo::default();
+        unsafe {
+            (rt.query_variable_info)(
+                attributes,
+                &mut info.maximum_variable_storage_size,
+                &mut info.remaining_variable_storage_size,
+                &mut info.maximum_variable_size,
+            )
+            .to_result_with_val(|| info)
+            .map_err(Into::into)
+        }
+    }
+
+    /// Resets the computer. See [`reset`](super::reset).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ResetSystem` is not available; see [`availability`].
+    pub fn reset(&self, reset_type: ResetType, status: Status, data: Option<&[u8]>) -> ! {
+        if let Err(err) = availability::check(RuntimeService::ResetSystem) {
+            panic!("{err}");
+        }
+        let rt = unsafe { self.0.as_ref() };
+
+        let (size, data) = data
+            .map(|data| (data.len(), data.as_ptr()))
+            .unwrap_or((0, ptr::null()));
+
+        unsafe { (rt.reset_system)(reset_type, status, size, data) }
+    }
+}
+
+/// Sets the runtime services table used by the functions of the [`runtime`]
+/// module, instead of the one of the system table.
+///
+/// A kernel that called `SetVirtualAddressMap` itself uses this to pass the
+/// virtual address of the table. [`runtime::set_virtual_address_map`]
+/// converts the table set here, so calling it again is only needed if the
+/// map was set some other way.
+///
+/// # Safety
+///
+/// `rt` must stay valid for the rest of the program, and no other thread
+/// may be calling runtime services through the [`runtime`] module.
+///
+/// [`runtime`]: super
+/// [`runtime::set_virtual_address_map`]: super::set_virtual_address_map
+pub unsafe fn set_runtime_services(rt: RuntimePostEbs) {
+    RUNTIME_SERVICES.store(rt.0.as_ptr(), Ordering::Release);
+}
+
+/// Translates the runtime services table set by [`set_runtime_services`] or
+/// [`register_virtual_address_change`] to its virtual address in `map`,
+/// after a successful `SetVirtualAddressMap`.
+///
+/// If the table is not in `map`, it is cleared so that the table of the
+/// (converted) system table is used instead.
+pub(super) fn convert_runtime_services(map: &[MemoryDescriptor]) {
+    let rt = RUNTIME_SERVICES.load(Ordering::Acquire);
+    if rt.is_null() {
+        return;
+    }
+    let phys = rt as u64;
+    let virt = map
+        .iter()
+        .find(|desc| {
+            let len = desc.page_count * PAGE_SIZE as u64;
+            (desc.phys_start..desc.phys_start + len).contains(&phys)
+        })
+        .map_or(ptr::null_mut(), |desc| {
+            (desc.virt_start + (phys - desc.phys_start)) as *mut RuntimeServices
+        });
+    RUNTIME_SERVICES.store(virt, Ordering::Release);
+}
+
+/// Converts the pointers to the system table and runtime services table
+/// used by this crate when the OS calls `SetVirtualAddressMap`, so that
+/// the functions of the [`runtime`] module keep working afterwards.
+///
+/// This must be called while boot services are active. The returned event
+/// must not be closed.
+///
+/// # Safety
+///
+/// The notification function runs in the context of `SetVirtualAddressMap`,
+/// so the code of the executable must be in runtime memory, i.e. it must be
+/// a runtime driver.
+///
+/// # Errors
+///
+/// * [`Status::UNSUPPORTED`]: the system table was not set.
+/// * [`Status::OUT_OF_RESOURCES`]: the event could not be allocated.
+///
+/// [`runtime`]: super
+pub unsafe fn register_virtual_address_change() -> Result<Event> {
+    let rt = RuntimePostEbs::current().ok_or(Status::UNSUPPORTED)?;
+    RUNTIME_SERVICES.store(rt.0.as_ptr(), Ordering::Release);
+    unsafe {
+        boot::create_event(
+            EventType::SIGNAL_VIRTUAL_ADDRESS_CHANGE,
+            Tpl::NOTIFY,
+            Some(convert_pointers),
+            None,
+        )
+    }
+}
+
+/// Notification function of the event created by
+/// [`register_virtual_address_change`].
+unsafe extern "efiapi" fn convert_pointers(_event: Event, _context: Option<NonNull<c_void>>) {
+    let rt = RUNTIME_SERVICES.load(Ordering::Acquire);
+    // SAFETY: set by `register_virtual_address_change`, and still mapped at
+    // its physical address while the map is being set.
+    let convert_pointer = unsafe { (*rt).convert_pointer };
+
+    let mut st = table::system_table_raw().map_or(ptr::null(), |st| st.as_ptr().cast_const());
+    let mut rt = rt.cast_const();
+    // SAFETY: both pointers point to runtime memory. Nothing can be done if
+    // a conversion fails.
+    unsafe {
+        if !st.is_null() {
+            let _ = convert_pointer(0, ptr::from_mut(&mut st).cast());
+            table::set_system_table(st.cast::<SystemTable>());
+        }
+        let _ = convert_pointer(0, ptr::from_mut(&mut rt).cast());
+    }
+    RUNTIME_SERVICES.store(rt.cast_mut(), Ordering::Release);
+    availability::mark_virtual();
+}
@@ -0,0 +1,227 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use super::{
+    OsIndications, ResetType, RuntimeError, RuntimeResult, VariableAttributes, VariableVendor,
+};
+use crate::{CStr16, Status, cstr16};
+use core::convert::Infallible;
+use core::fmt::{self, Display, Formatter};
+
+#[cfg(feature = "alloc")]
+use {
+    super::LoadOption, crate::Guid, crate::proto::device_path::DevicePathNodeEnum,
+    alloc::string::ToString,
+};
+
+const OS_INDICATIONS: &CStr16 = cstr16!("OsIndications");
+
+/// Attributes of the `OsIndications` and `BootNext` variables.
+const ATTRIBUTES: VariableAttributes = VariableAttributes::NON_VOLATILE
+    .union(VariableAttributes::BOOTSERVICE_ACCESS)
+    .union(VariableAttributes::RUNTIME_ACCESS);
+
+/// File GUID of EDK2's boot manager menu application, the default of
+/// `PcdBootManagerMenuFile`.
+#[cfg(feature = "alloc")]
+const BOOT_MANAGER_MENU_FILE: Guid = crate::guid!("eec25bdc-67f2-4d95-b1d5-f81b2039d11d");
+
+/// Error returned by [`reboot_to_firmware_setup`] and
+/// [`reboot_to_boot_menu`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RebootError {
+    /// The firmware doesn't support the request.
+    Unsupported,
+    /// Reading or writing a variable failed.
+    Variable(RuntimeError),
+}
+
+impl Display for RebootError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unsupported => write!(f, "not supported by the firmware"),
+            Self::Variable(err) => write!(f, "failed to access variable: {err}"),
+        }
+    }
+}
+
+impl core::error::Error for RebootError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::Unsupported => None,
+            Self::Variable(err) => Some(err),
+        }
+    }
+}
+
+impl From<RuntimeError> for RebootError {
+    fn from(err: RuntimeError) -> Self {
+        Self::Variable(err)
+    }
+}
+
+/// Reads a little-endian integer variable of up to 8 bytes, returning 0 if
+/// it doesn't exist.
+fn read_u64(name: &CStr16) -> RuntimeResult<u64> {
+    let mut buf = [0; 8];
+    match super::get_variable(name, &VariableVendor::GLOBAL_VARIABLE, &mut buf) {
+        Ok((data, _)) => Ok(data
+            .iter()
+            .rev()
+            .fold(0, |value, byte| (value << 8) | u64::from(*byte))),
+        Err(err) if err.status() == Status::NOT_FOUND => Ok(0),
+        Err(err) => Err(err.to_err_without_payload()),
+    }
+}
+
+/// Returns the requests of the `OsIndications` variable that the firmware
+/// supports, read from the `OsIndicationsSupported` variable. The result
+/// is empty if the variable doesn't exist.
+///
+/// # Errors
+///
+/// See [`get_variable`](super::get_variable).
+pub fn os_indications_supported() -> RuntimeResult<OsIndications> {
+    read_u64(cstr16!("OsIndicationsSupported")).map(OsIndications::from_bits_retain)
+}
+
+/// Requests `indications` for the next boot, in addition to those already
+/// set in the `OsIndications` variable.
+///
+/// # Errors
+///
+/// * [`RebootError::Unsupported`]: the firmware doesn't support all of
+///   `indications`, see [`os_indications_supported`].
+/// * [`RebootError::Variable`]: reading or writing a variable failed.
+pub fn request_os_indications(indications: OsIndications) -> core::result::Result<(), RebootError> {
+    if !os_indications_supported()?.contains(indications) {
+        return Err(RebootError::Unsupported);
+    }
+    let current = read_u64(OS_INDICATIONS)?;
+    let value = current | indications.bits();
+    super::set_variable(
+        OS_INDICATIONS,
+        &VariableVendor::GLOBAL_VARIABLE,
+        ATTRIBUTES,
+        &value.to_le_bytes(),
+    )?;
+    Ok(())
+}
+
+/// Reboots into the setup of the firmware, using the
+/// [`OsIndications::BOOT_TO_FW_UI`] request. Only returns on error.
+///
+/// # Errors
+///
+/// * [`RebootError::Unsupported`]: the firmware doesn't support booting
+///   to its setup.
+/// * [`RebootError::Variable`]: reading or writing a variable failed.
+pub fn reboot_to_firmware_setup() -> core::result::Result<Infallible, RebootError> {
+    request_os_indications(OsIndications::BOOT_TO_FW_UI)?;
+    super::reset(ResetType::COLD, Status::SUCCESS, None)
+}
+
+/// Reboots into the boot menu of the firmware, by setting `BootNext` to its
+/// load option. Only returns on error.
+///
+/// There is no standard request for the boot menu; this works on firmware
+/// based on EDK2, where the boot menu is a hidden `Boot####` option
+/// launching the `BootManagerMenuApp` file.
+///
+/// # Errors
+///
+/// * [`RebootError::Unsupported`]: the firmware has no boot menu option.
+/// * [`RebootError::Variable`]: reading or writing a variable failed.
+#[cfg(feature = "alloc")]
+pub fn reboot_to_boot_menu() -> core::result::Result<Infallible, RebootError> {
+    let index = boot_menu_option()?.ok_or(RebootError::Unsupported)?;
+    super::set_variable(
+        cstr16!("BootNext"),
+        &VariableVendor::GLOBAL_VARIABLE,
+        ATTRIBUTES,
+        &index.to_le_bytes(),
+    )?;
+    super::reset(ResetType::COLD, Status::SUCCESS, None)
+}
+
+/// Returns the index of the `Boot####` option of the boot menu.
+#[cfg(feature = "alloc")]
+fn boot_menu_option() -> RuntimeResult<Option<u16>> {
+    for key in super::variable_keys() {
+        let key = key?;
+        if key.vendor != VariableVendor::GLOBAL_VARIABLE {
+            continue;
+        }
+        let Some(index) = boot_option_index(&key.name) else {
+            continue;
+        };
+        let (option, _) = super::get_variable_boxed(&key.name, &VariableVendor::GLOBAL_VARIABLE)?;
+        if is_boot_menu(&option) {
+            return Ok(Some(index));
+        }
+    }
+    Ok(None)
+}
+
+/// Returns the index of a `Boot####` variable name.
+#[cfg(feature = "alloc")]
+fn boot_option_index(name: &CStr16) -> Option<u16> {
+    let name = name.to_string();
+    let hex = name.strip_prefix("Boot")?;
+    if hex.len() != 4 || !hex.bytes().all(|b| matches!(b, b'0'..=b'9' | b'A'..=b'F')) {
+        return None;
+    }
+    u16::from_str_radix(hex, 16).ok()
+}
+
+/// Returns whether the load option `option` launches the boot menu.
+#[cfg(feature = "alloc")]
+fn is_boot_menu(option: &[u8]) -> bool {
+    let Ok(option) = LoadOption::parse(option) else {
+        return false;
+    };
+    let Ok(path) = option.file_path() else {
+        return false;
+    };
+    path.node_iter().any(|node| {
+        matches!(
+            node.as_enum(),
+            Ok(DevicePathNodeEnum::MediaPiwgFirmwareFile(file))
+                if file.data() == BOOT_MANAGER_MENU_FILE.to_bytes()
+        )
+    })
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_boot_option_index() {
+        assert_eq!(boot_option_index(cstr16!("Boot000A")), Some(10));
+        assert_eq!(boot_option_index(cstr16!("BootOrder")), None);
+        assert_eq!(boot_option_index(cstr16!("Boot000a")), None);
+        assert_eq!(boot_option_index(cstr16!("Boot00001")), None);
+    }
+
+    #[test]
+    fn test_is_boot_menu() {
+        let option = |file: Guid| {
+            let mut path = Vec::new();
+            // Firmware volume file node, then the end node.
+            path.extend_from_slice(&[0x04, 0x06, 20, 0]);
+            path.extend_from_slice(&file.to_bytes());
+            path.extend_from_slice(&[0x7f, 0xff, 4, 0]);
+
+            let mut option = Vec::new();
+            option.extend_from_slice(&9u32.to_le_bytes());
+            option.extend_from_slice(&(path.len() as u16).to_le_bytes());
+            option.extend_from_slice(cstr16!("UEFI Boot Menu").as_bytes());
+            option.extend_from_slice(&path);
+            option
+        };
+        assert!(is_boot_menu(&option(BOOT_MANAGER_MENU_FILE)));
+        assert!(!is_boot_menu(&option(Guid::ZERO)));
+        assert!(!is_boot_menu(&option(BOOT_MANAGER_MENU_FILE)[..30]));
+    }
+}
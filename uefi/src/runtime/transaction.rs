@@ -0,0 +1,419 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Batches of variable writes that are rolled back on failure.
+
+use super::{VariableAttributes, VariableKey, VariableVendor};
+use crate::{CStr16, Error, Status};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+
+/// A write recorded in a [`VariableTransaction`].
+#[derive(Clone, Debug)]
+struct PendingWrite {
+    key: VariableKey,
+    /// New attributes and contents, or `None` to delete the variable.
+    value: Option<(VariableAttributes, Box<[u8]>)>,
+}
+
+/// A batch of variable writes that is applied as a whole.
+///
+/// Writes are recorded with [`set`] and [`delete`], and applied in order by
+/// [`commit`]. Before each variable is written, its previous value is read.
+/// If any write fails, the variables written so far are restored to their
+/// previous values, in reverse order.
+///
+/// This reduces the risk of leaving related variables in an inconsistent
+/// state, such as a `BootOrder` that refers to a missing `Boot####` option.
+/// It is not atomic: a reset during [`commit`] may still leave some of the
+/// variables written.
+///
+/// # Example
+///
+/// ```no_run
+/// use uefi::runtime::{VariableAttributes, VariableTransaction, VariableVendor};
+/// use uefi::cstr16;
+///
+/// # fn example(load_option: &[u8], boot_order: &[u8]) -> Result<(), uefi::runtime::TransactionError> {
+/// let attributes = VariableAttributes::NON_VOLATILE
+///     | VariableAttributes::BOOTSERVICE_ACCESS
+///     | VariableAttributes::RUNTIME_ACCESS;
+/// let vendor = VariableVendor::GLOBAL_VARIABLE;
+///
+/// let mut transaction = VariableTransaction::new();
+/// transaction
+///     .set(cstr16!("Boot0005"), &vendor, attributes, load_option)
+///     .set(cstr16!("BootOrder"), &vendor, attributes, boot_order);
+/// transaction.commit()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`set`]: Self::set
+/// [`delete`]: Self::delete
+/// [`commit`]: Self::commit
+#[derive(Clone, Debug, Default)]
+pub struct VariableTransaction {
+    writes: Vec<PendingWrite>,
+}
+
+impl VariableTransaction {
+    /// Creates an empty transaction.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { writes: Vec::new() }
+    }
+
+    /// Records setting a variable to `data` with `attributes`.
+    pub fn set(
+        &mut self,
+        name: &CStr16,
+        vendor: &VariableVendor,
+        attributes: VariableAttributes,
+        data: &[u8],
+    ) -> &mut Self {
+        self.writes.push(PendingWrite {
+            key: VariableKey {
+                vendor: *vendor,
+                name: name.into(),
+            },
+            value: Some((attributes, data.into())),
+        });
+        self
+    }
+
+    /// Records deleting a variable. Deleting a variable that doesn't exist
+    /// is not an error.
+    pub fn delete(&mut self, name: &CStr16, vendor: &VariableVendor) -> &mut Self {
+        self.writes.push(PendingWrite {
+            key: VariableKey {
+                vendor: *vendor,
+                name: name.into(),
+            },
+            value: None,
+        });
+        self
+    }
+
+    /// Returns the number of recorded writes.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.writes.len()
+    }
+
+    /// Returns whether no writes were recorded.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.writes.is_empty()
+    }
+
+    /// Applies all recorded writes, rolling back on failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`TransactionError`] if reading the previous value of a
+    /// variable or writing a variable fails. See [`get_variable`] and
+    /// [`set_variable`] for possible errors.
+    ///
+    /// [`get_variable`]: super::get_variable
+    /// [`set_variable`]: super::set_variable
+    pub fn commit(self) -> Result<(), TransactionError> {
+        self.commit_to(&mut Runtime)
+    }
+
+    fn commit_to(self, store: &mut impl VariableStore) -> Result<(), TransactionError> {
+        let mut previous = Vec::with_capacity(self.writes.len());
+        for (index, write) in self.writes.iter().enumerate() {
+            // Only variables that were written are restored; a failed write
+            // leaves the variable unchanged.
+            let result = read(store, &write.key).and_then(|value| {
+                apply(store, &write.key, write.value.as_ref())?;
+                previous.push((&write.key, value));
+                Ok(())
+            });
+            if let Err(error) = result {
+                let rollback_errors = previous
+                    .iter()
+                    .rev()
+                    .filter_map(|(key, value)| {
+                        let value = value.as_ref().map(|(attr, data)| (*attr, &**data));
+                        rollback(store, key, value)
+                            .err()
+                            .map(|err| ((*key).clone(), err))
+                    })
+                    .collect();
+                return Err(TransactionError {
+                    index,
+                    key: write.key.clone(),
+                    error,
+                    rollback_errors,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Variable services used by [`VariableTransaction::commit`], so that the
+/// rollback can be tested without firmware.
+trait VariableStore {
+    fn get(&mut self, key: &VariableKey) -> crate::Result<(Box<[u8]>, VariableAttributes)>;
+
+    fn set(
+        &mut self,
+        key: &VariableKey,
+        attributes: VariableAttributes,
+        data: &[u8],
+    ) -> crate::Result;
+}
+
+/// The variables of the firmware.
+struct Runtime;
+
+impl VariableStore for Runtime {
+    fn get(&mut self, key: &VariableKey) -> crate::Result<(Box<[u8]>, VariableAttributes)> {
+        super::get_variable_boxed(&key.name, &key.vendor).map_err(Into::into)
+    }
+
+    fn set(
+        &mut self,
+        key: &VariableKey,
+        attributes: VariableAttributes,
+        data: &[u8],
+    ) -> crate::Result {
+        super::set_variable(&key.name, &key.vendor, attributes, data).map_err(Into::into)
+    }
+}
+
+/// Reads the current value of a variable, `None` if it doesn't exist.
+fn read(
+    store: &mut impl VariableStore,
+    key: &VariableKey,
+) -> crate::Result<Option<(VariableAttributes, Box<[u8]>)>> {
+    match store.get(key) {
+        Ok((data, attributes)) => Ok(Some((attributes, data))),
+        Err(err) if err.status() == Status::NOT_FOUND => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Sets or deletes a variable.
+fn apply(
+    store: &mut impl VariableStore,
+    key: &VariableKey,
+    value: Option<&(VariableAttributes, Box<[u8]>)>,
+) -> crate::Result {
+    match value {
+        Some((attributes, data)) => store.set(key, *attributes, data),
+        None => delete(store, key),
+    }
+}
+
+/// Deletes a variable, ignoring whether it exists.
+fn delete(store: &mut impl VariableStore, key: &VariableKey) -> crate::Result {
+    match store.set(key, VariableAttributes::empty(), &[]) {
+        Err(err) if err.status() == Status::NOT_FOUND => Ok(()),
+        result => result,
+    }
+}
+
+/// Restores the previous value of a variable.
+fn rollback(
+    store: &mut impl VariableStore,
+    key: &VariableKey,
+    value: Option<(VariableAttributes, &[u8])>,
+) -> crate::Result {
+    let Some((attributes, data)) = value else {
+        return delete(store, key);
+    };
+    store.set(key, attributes, data).or_else(|_| {
+        // A variable can't be overwritten with different attributes, so
+        // delete it first.
+        delete(store, key)?;
+        store.set(key, attributes, data)
+    })
+}
+
+/// Error returned by [`VariableTransaction::commit`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TransactionError {
+    /// Index of the write that failed, in the order the writes were
+    /// recorded.
+    pub index: usize,
+    /// Variable that failed to be read or written.
+    pub key: VariableKey,
+    /// The error that caused the transaction to fail.
+    pub error: Error,
+    /// Variables that could not be restored to their previous value. If this
+    /// is empty, the rollback was complete.
+    pub rollback_errors: Vec<(VariableKey, Error)>,
+}
+
+impl Display for TransactionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to write variable {}: {}",
+            self.key.name,
+            self.error.status()
+        )?;
+        if !self.rollback_errors.is_empty() {
+            write!(
+                f,
+                " ({} variables could not be restored)",
+                self.rollback_errors.len()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl core::error::Error for TransactionError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CString16, cstr16};
+    use alloc::collections::BTreeMap;
+    use alloc::string::ToString;
+
+    const NV: VariableAttributes = VariableAttributes::NON_VOLATILE;
+    const BS: VariableAttributes = VariableAttributes::BOOTSERVICE_ACCESS;
+
+    /// In-memory variables that, like firmware, can't be overwritten with
+    /// different attributes.
+    #[derive(Default)]
+    struct Store {
+        vars: BTreeMap<VariableKey, (VariableAttributes, Box<[u8]>)>,
+        /// Number of writes that succeed before all writes fail.
+        writes_left: Option<usize>,
+        /// Variables that can't be written.
+        read_only: Vec<CString16>,
+    }
+
+    impl Store {
+        fn insert(&mut self, name: &CStr16, attributes: VariableAttributes, data: &[u8]) {
+            self.vars.insert(key(name), (attributes, data.into()));
+        }
+
+        fn value(&self, name: &CStr16) -> Option<(VariableAttributes, &[u8])> {
+            let (attributes, data) = self.vars.get(&key(name))?;
+            Some((*attributes, data))
+        }
+    }
+
+    impl VariableStore for Store {
+        fn get(&mut self, key: &VariableKey) -> crate::Result<(Box<[u8]>, VariableAttributes)> {
+            let (attributes, data) = self.vars.get(key).ok_or(Status::NOT_FOUND)?;
+            Ok((data.clone(), *attributes))
+        }
+
+        fn set(
+            &mut self,
+            key: &VariableKey,
+            attributes: VariableAttributes,
+            data: &[u8],
+        ) -> crate::Result {
+            if self.read_only.contains(&key.name) || self.writes_left == Some(0) {
+                return Err(Status::WRITE_PROTECTED.into());
+            }
+            if let Some(left) = &mut self.writes_left {
+                *left -= 1;
+            }
+            if data.is_empty() {
+                return match self.vars.remove(key) {
+                    Some(_) => Ok(()),
+                    None => Err(Status::NOT_FOUND.into()),
+                };
+            }
+            match self.vars.get(key) {
+                Some((old, _)) if *old != attributes => Err(Status::INVALID_PARAMETER.into()),
+                _ => {
+                    self.vars.insert(key.clone(), (attributes, data.into()));
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    fn key(name: &CStr16) -> VariableKey {
+        VariableKey {
+            vendor: VariableVendor::GLOBAL_VARIABLE,
+            name: name.into(),
+        }
+    }
+
+    #[test]
+    fn test_commit() {
+        let vendor = VariableVendor::GLOBAL_VARIABLE;
+        let mut store = Store::default();
+        store.insert(cstr16!("A"), NV, &[1]);
+        store.insert(cstr16!("C"), NV, &[3]);
+
+        let mut transaction = VariableTransaction::new();
+        transaction
+            .set(cstr16!("A"), &vendor, NV, &[10])
+            .set(cstr16!("B"), &vendor, NV, &[20])
+            .delete(cstr16!("C"), &vendor)
+            .delete(cstr16!("D"), &vendor);
+        assert_eq!(transaction.len(), 4);
+        transaction.commit_to(&mut store).unwrap();
+
+        assert_eq!(store.value(cstr16!("A")), Some((NV, &[10][..])));
+        assert_eq!(store.value(cstr16!("B")), Some((NV, &[20][..])));
+        assert_eq!(store.value(cstr16!("C")), None);
+    }
+
+    #[test]
+    fn test_rollback() {
+        let vendor = VariableVendor::GLOBAL_VARIABLE;
+        let mut store = Store::default();
+        store.insert(cstr16!("A"), NV, &[1]);
+        store.insert(cstr16!("C"), NV, &[3]);
+        store.read_only.push(cstr16!("D").into());
+
+        // `A` is rewritten with other attributes, `B` is created, `C` is
+        // deleted, and the write of `D` fails.
+        let mut transaction = VariableTransaction::new();
+        transaction
+            .delete(cstr16!("A"), &vendor)
+            .set(cstr16!("A"), &vendor, BS, &[10])
+            .set(cstr16!("B"), &vendor, NV, &[20])
+            .delete(cstr16!("C"), &vendor)
+            .set(cstr16!("D"), &vendor, NV, &[40]);
+        let err = transaction.commit_to(&mut store).unwrap_err();
+
+        assert_eq!(err.index, 4);
+        assert_eq!(err.key, key(cstr16!("D")));
+        assert_eq!(err.error.status(), Status::WRITE_PROTECTED);
+        assert_eq!(err.rollback_errors, []);
+        assert_eq!(store.value(cstr16!("A")), Some((NV, &[1][..])));
+        assert_eq!(store.value(cstr16!("B")), None);
+        assert_eq!(store.value(cstr16!("C")), Some((NV, &[3][..])));
+        assert_eq!(store.value(cstr16!("D")), None);
+    }
+
+    #[test]
+    fn test_rollback_errors() {
+        let vendor = VariableVendor::GLOBAL_VARIABLE;
+        let mut store = Store::default();
+        store.insert(cstr16!("A"), NV, &[1]);
+        store.writes_left = Some(1);
+
+        let mut transaction = VariableTransaction::new();
+        transaction
+            .set(cstr16!("A"), &vendor, NV, &[10])
+            .set(cstr16!("B"), &vendor, NV, &[20]);
+        let err = transaction.commit_to(&mut store).unwrap_err();
+
+        assert_eq!(err.index, 1);
+        assert_eq!(err.key, key(cstr16!("B")));
+        assert_eq!(err.rollback_errors.len(), 1);
+        assert_eq!(err.rollback_errors[0].0, key(cstr16!("A")));
+        assert_eq!(
+            err.to_string(),
+            "failed to write variable B: WRITE_PROTECTED (1 variables could not be restored)"
+        );
+        assert_eq!(store.value(cstr16!("A")), Some((NV, &[10][..])));
+    }
+}
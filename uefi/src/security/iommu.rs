@@ -0,0 +1,927 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! IOMMU description tables: the Intel VT-d DMAR table and the AMD-Vi IVRS
+//! table.
+//!
+//! Both tables list the IOMMUs of the platform and the devices behind each
+//! of them. Devices not covered by any IOMMU can access all memory with DMA,
+//! so pre-boot tools can use these tables to verify the DMA protection
+//! coverage before loading an OS:
+//!
+//! ```no_run
+//! use uefi::security::iommu::Dmar;
+//!
+//! if let Some(dmar) = Dmar::find() {
+//!     for unit in dmar.remapping_units() {
+//!         log::info!("VT-d unit at {:#x}", unit.register_base);
+//!     }
+//!     // Device 00:1f.0 on segment 0.
+//!     if dmar.remapping_unit_for(0, 0, 0x1f, 0).is_none() {
+//!         log::warn!("00:1f.0 is not behind an IOMMU");
+//!     }
+//! }
+//! ```
+
+use super::{ACPI_HEADER_SIZE, find_acpi_table};
+use core::fmt::{self, Debug, Formatter};
+use uefi_raw::newtype_enum;
+
+fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        bytes.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        bytes.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(
+        bytes.get(offset..offset + 8)?.try_into().ok()?,
+    ))
+}
+
+/// Returns the body of the ACPI table in `bytes` if it has `signature`,
+/// or `None` if the table is invalid.
+fn table_body<'a>(bytes: &'a [u8], signature: &[u8; 4], body: usize) -> Option<&'a [u8]> {
+    if bytes.get(..4)? != signature {
+        return None;
+    }
+    let len = read_u32(bytes, 4)? as usize;
+    bytes.get(..len).filter(|_| len >= body)
+}
+
+bitflags::bitflags! {
+    /// Flags of the [`Dmar`] table.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct DmarFlags: u8 {
+        /// Interrupt remapping is supported.
+        const INTR_REMAP = 1 << 0;
+        /// The firmware requests the OS not to enable x2APIC mode.
+        const X2APIC_OPT_OUT = 1 << 1;
+        /// The firmware requests the OS to keep DMA protection enabled.
+        const DMA_CTRL_PLATFORM_OPT_IN = 1 << 2;
+    }
+}
+
+newtype_enum! {
+    /// Type of a [`DeviceScope`].
+    pub enum DeviceScopeType: u8 => {
+        /// A PCI endpoint device.
+        PCI_ENDPOINT = 0x01,
+        /// A PCI-PCI bridge and all devices behind it.
+        PCI_SUB_HIERARCHY = 0x02,
+        /// An I/O APIC.
+        IOAPIC = 0x03,
+        /// An MSI capable HPET.
+        HPET = 0x04,
+        /// An ACPI namespace device.
+        ACPI_NAMESPACE_DEVICE = 0x05,
+    }
+}
+
+/// A device scope of a [`Dmar`] remapping structure, identifying a device
+/// by the path through the PCI hierarchy from a root bus.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct DeviceScope<'a> {
+    /// Type of the device.
+    pub scope_type: DeviceScopeType,
+    /// I/O APIC ID, HPET number or ACPI device number, depending on the
+    /// type.
+    pub enumeration_id: u8,
+    /// Bus number of the first element of the path.
+    pub start_bus: u8,
+    path: &'a [u8],
+}
+
+impl<'a> DeviceScope<'a> {
+    /// Returns the path as pairs of PCI device and function numbers. The
+    /// first pair is on the start bus, each following pair is on the
+    /// secondary bus of the bridge before it.
+    pub fn path(&self) -> impl Iterator<Item = (u8, u8)> + use<'a> {
+        self.path.chunks_exact(2).map(|pair| (pair[0], pair[1]))
+    }
+
+    /// Returns whether the scope is the PCI device `bus:dev.fun` on the
+    /// start bus.
+    fn is_device(&self, bus: u8, dev: u8, fun: u8) -> bool {
+        self.start_bus == bus && self.path == [dev, fun]
+    }
+}
+
+impl Debug for DeviceScope<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeviceScope")
+            .field("scope_type", &self.scope_type)
+            .field("enumeration_id", &self.enumeration_id)
+            .field("start_bus", &self.start_bus)
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+/// Iterator over the [`DeviceScope`]s of a remapping structure. Stops at
+/// the first truncated scope.
+#[derive(Clone, Debug)]
+pub struct DeviceScopes<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Iterator for DeviceScopes<'a> {
+    type Item = DeviceScope<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let len = usize::from(*self.bytes.get(1)?);
+        let Some(scope) = self.bytes.get(..len).filter(|_| len >= 6) else {
+            self.bytes = &[];
+            return None;
+        };
+        self.bytes = &self.bytes[len..];
+        Some(DeviceScope {
+            scope_type: DeviceScopeType(scope[0]),
+            enumeration_id: scope[4],
+            start_bus: scope[5],
+            path: &scope[6..],
+        })
+    }
+}
+
+/// A DMA remapping hardware unit definition (DRHD): a VT-d remapping unit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RemappingUnit<'a> {
+    /// Whether the unit covers all devices of the segment that are not
+    /// covered by other units (`INCLUDE_PCI_ALL`).
+    pub include_pci_all: bool,
+    /// PCI segment of the devices.
+    pub segment: u16,
+    /// Base address of the registers of the unit.
+    pub register_base: u64,
+    scopes: &'a [u8],
+}
+
+impl<'a> RemappingUnit<'a> {
+    /// Returns the devices covered by the unit. If
+    /// [`include_pci_all`](Self::include_pci_all) is set, these are only
+    /// the I/O APICs and HPETs.
+    #[must_use]
+    pub const fn device_scopes(&self) -> DeviceScopes<'a> {
+        DeviceScopes { bytes: self.scopes }
+    }
+}
+
+/// A reserved memory region reporting (RMRR): memory that devices may
+/// access with DMA, e.g. for USB legacy emulation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReservedMemory<'a> {
+    /// PCI segment of the devices.
+    pub segment: u16,
+    /// Base address of the region.
+    pub base: u64,
+    /// Address of the last byte of the region.
+    pub limit: u64,
+    scopes: &'a [u8],
+}
+
+impl<'a> ReservedMemory<'a> {
+    /// Returns the devices that access the region.
+    #[must_use]
+    pub const fn device_scopes(&self) -> DeviceScopes<'a> {
+        DeviceScopes { bytes: self.scopes }
+    }
+}
+
+/// A root port ATS capability reporting (ATSR): root ports supporting
+/// address translation services.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RootPortAts<'a> {
+    /// Whether all root ports of the segment support ATS.
+    pub all_ports: bool,
+    /// PCI segment of the root ports.
+    pub segment: u16,
+    scopes: &'a [u8],
+}
+
+impl<'a> RootPortAts<'a> {
+    /// Returns the root ports supporting ATS, unless
+    /// [`all_ports`](Self::all_ports) is set.
+    #[must_use]
+    pub const fn device_scopes(&self) -> DeviceScopes<'a> {
+        DeviceScopes { bytes: self.scopes }
+    }
+}
+
+/// A remapping structure of the [`Dmar`] table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DmarStructure<'a> {
+    /// A DMA remapping hardware unit definition (DRHD).
+    RemappingUnit(RemappingUnit<'a>),
+    /// A reserved memory region reporting (RMRR).
+    ReservedMemory(ReservedMemory<'a>),
+    /// A root port ATS capability reporting (ATSR).
+    RootPortAts(RootPortAts<'a>),
+    /// A remapping hardware static affinity (RHSA) structure.
+    StaticAffinity {
+        /// Base address of the registers of the remapping unit.
+        register_base: u64,
+        /// NUMA proximity domain of the remapping unit.
+        proximity_domain: u32,
+    },
+    /// An ACPI name-space device declaration (ANDD).
+    AcpiNamespaceDevice {
+        /// Number of the device, referenced by device scopes.
+        device_number: u8,
+        /// Fully qualified ACPI object name of the device.
+        name: &'a [u8],
+    },
+    /// Another structure.
+    Other {
+        /// Type of the structure.
+        structure_type: u16,
+        /// The raw structure, including its header.
+        bytes: &'a [u8],
+    },
+}
+
+impl<'a> DmarStructure<'a> {
+    fn parse(bytes: &'a [u8]) -> Option<Self> {
+        let structure_type = read_u16(bytes, 0)?;
+        let scopes = |offset| bytes.get(offset..).unwrap_or_default();
+        Some(match structure_type {
+            0 => Self::RemappingUnit(RemappingUnit {
+                include_pci_all: bytes.get(4)? & 1 != 0,
+                segment: read_u16(bytes, 6)?,
+                register_base: read_u64(bytes, 8)?,
+                scopes: scopes(16),
+            }),
+            1 => Self::ReservedMemory(ReservedMemory {
+                segment: read_u16(bytes, 6)?,
+                base: read_u64(bytes, 8)?,
+                limit: read_u64(bytes, 16)?,
+                scopes: scopes(24),
+            }),
+            2 => Self::RootPortAts(RootPortAts {
+                all_ports: bytes.get(4)? & 1 != 0,
+                segment: read_u16(bytes, 6)?,
+                scopes: scopes(8),
+            }),
+            3 => Self::StaticAffinity {
+                register_base: read_u64(bytes, 8)?,
+                proximity_domain: read_u32(bytes, 16)?,
+            },
+            4 => {
+                let name = bytes.get(8..)?;
+                let len = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+                Self::AcpiNamespaceDevice {
+                    device_number: *bytes.get(7)?,
+                    name: &name[..len],
+                }
+            }
+            _ => Self::Other {
+                structure_type,
+                bytes,
+            },
+        })
+    }
+}
+
+/// Iterator over the [`DmarStructure`]s of a [`Dmar`] table. Stops at the
+/// first truncated structure.
+#[derive(Clone, Debug)]
+pub struct DmarStructures<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Iterator for DmarStructures<'a> {
+    type Item = DmarStructure<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let len = usize::from(read_u16(self.bytes, 2)?);
+        let structure = self
+            .bytes
+            .get(..len)
+            .filter(|_| len >= 4)
+            .and_then(DmarStructure::parse);
+        self.bytes = if structure.is_some() {
+            &self.bytes[len..]
+        } else {
+            &[]
+        };
+        structure
+    }
+}
+
+/// The DMA remapping reporting (DMAR) table, describing Intel VT-d.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Dmar<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Dmar<'a> {
+    /// Offset of the remapping structures.
+    const STRUCTURES: usize = ACPI_HEADER_SIZE + 12;
+
+    /// Parses a DMAR table, including its ACPI header. Returns `None` if the
+    /// signature or length is invalid.
+    #[must_use]
+    pub fn parse(bytes: &'a [u8]) -> Option<Self> {
+        let bytes = table_body(bytes, b"DMAR", Self::STRUCTURES)?;
+        Some(Self { bytes })
+    }
+
+    /// Returns the DMAR table of the firmware, or `None` if there is none.
+    #[must_use]
+    pub fn find() -> Option<Dmar<'static>> {
+        Dmar::parse(find_acpi_table(*b"DMAR")?)
+    }
+
+    /// Returns the raw table, including its ACPI header.
+    #[must_use]
+    pub const fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Returns the maximum DMA physical address width in bits.
+    ///
+    /// The table stores the width minus one in a byte, so a malformed table
+    /// can report up to 256 bits.
+    #[must_use]
+    pub const fn host_address_width(&self) -> u16 {
+        self.bytes[ACPI_HEADER_SIZE] as u16 + 1
+    }
+
+    /// Returns the flags of the table.
+    #[must_use]
+    pub const fn flags(&self) -> DmarFlags {
+        DmarFlags::from_bits_retain(self.bytes[ACPI_HEADER_SIZE + 1])
+    }
+
+    /// Returns the remapping structures.
+    #[must_use]
+    pub fn structures(&self) -> DmarStructures<'a> {
+        DmarStructures {
+            bytes: &self.bytes[Self::STRUCTURES..],
+        }
+    }
+
+    /// Returns the remapping units.
+    pub fn remapping_units(&self) -> impl Iterator<Item = RemappingUnit<'a>> + use<'a> {
+        self.structures().filter_map(|structure| match structure {
+            DmarStructure::RemappingUnit(unit) => Some(unit),
+            _ => None,
+        })
+    }
+
+    /// Returns the remapping unit translating DMA of the PCI device
+    /// `bus:dev.fun` in `segment`, or `None` if it is not covered.
+    ///
+    /// A device is covered by a unit listing it as an endpoint directly on
+    /// the start bus, or else by the unit of the segment with
+    /// [`include_pci_all`](RemappingUnit::include_pci_all). Scopes with
+    /// longer paths, i.e. behind bridges, are not resolved, as that
+    /// requires the bus numbers assigned to the bridges.
+    #[must_use]
+    pub fn remapping_unit_for(
+        &self,
+        segment: u16,
+        bus: u8,
+        dev: u8,
+        fun: u8,
+    ) -> Option<RemappingUnit<'a>> {
+        let units = || {
+            self.remapping_units()
+                .filter(move |unit| unit.segment == segment)
+        };
+        units()
+            .find(|unit| {
+                unit.device_scopes().any(|scope| {
+                    scope.scope_type == DeviceScopeType::PCI_ENDPOINT
+                        && scope.is_device(bus, dev, fun)
+                })
+            })
+            .or_else(|| units().find(|unit| unit.include_pci_all))
+    }
+}
+
+impl Debug for Dmar<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Dmar")
+            .field("host_address_width", &self.host_address_width())
+            .field("flags", &self.flags())
+            .field("structures", &List(self.structures()))
+            .finish()
+    }
+}
+
+/// Formats the items of an iterator as list.
+struct List<I>(I);
+
+impl<I: Clone + Iterator<Item: Debug>> Debug for List<I> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.0.clone()).finish()
+    }
+}
+
+newtype_enum! {
+    /// Type of an [`IvhdEntry`].
+    pub enum IvhdEntryType: u8 => {
+        /// All devices.
+        ALL = 0x01,
+        /// The device with the device ID.
+        SELECT = 0x02,
+        /// The first device of a range.
+        START_OF_RANGE = 0x03,
+        /// The last device of a range.
+        END_OF_RANGE = 0x04,
+        /// A device whose requests use the device ID in the extra data.
+        ALIAS_SELECT = 0x42,
+        /// The first device of a range, whose requests use the device ID in
+        /// the extra data.
+        ALIAS_START_OF_RANGE = 0x43,
+        /// A device with extended settings in the extra data.
+        EXTENDED_SELECT = 0x46,
+        /// The first device of a range with extended settings in the extra
+        /// data.
+        EXTENDED_START_OF_RANGE = 0x47,
+        /// An I/O APIC or HPET, identified in the extra data.
+        SPECIAL = 0x48,
+        /// An ACPI device, identified by the HID, CID and UID in the extra
+        /// data.
+        ACPI_HID = 0xf0,
+    }
+}
+
+/// A device entry of an [`Ivhd`] block, describing the devices behind an
+/// IOMMU.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IvhdEntry<'a> {
+    /// Type of the entry.
+    pub entry_type: IvhdEntryType,
+    /// Device ID: the PCI bus number in bits 8 to 15, the device number in
+    /// bits 3 to 7 and the function number in bits 0 to 2. Ignored for
+    /// [`IvhdEntryType::ALL`].
+    pub device_id: u16,
+    /// Settings of the device table entries of the devices.
+    pub data_setting: u8,
+    /// The data after the first four bytes of the entry, if any.
+    pub extra: &'a [u8],
+}
+
+/// Iterator over the [`IvhdEntry`]s of an [`Ivhd`] block. Stops at the first
+/// truncated or unknown variable-length entry.
+#[derive(Clone, Debug)]
+pub struct IvhdEntries<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Iterator for IvhdEntries<'a> {
+    type Item = IvhdEntry<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry_type = *self.bytes.first()?;
+        let len = match entry_type {
+            0x00..=0x3f => Some(4),
+            0x40..=0x7f => Some(8),
+            // The UID length is at offset 21.
+            0xf0 => self.bytes.get(21).map(|&uid_len| 22 + usize::from(uid_len)),
+            _ => None,
+        };
+        let Some(entry) = len.and_then(|len| self.bytes.get(..len)) else {
+            self.bytes = &[];
+            return None;
+        };
+        self.bytes = &self.bytes[entry.len()..];
+        Some(IvhdEntry {
+            entry_type: IvhdEntryType(entry_type),
+            device_id: u16::from_le_bytes([entry[1], entry[2]]),
+            data_setting: entry[3],
+            extra: &entry[4..],
+        })
+    }
+}
+
+/// An I/O virtualization hardware definition (IVHD) block: an AMD-Vi IOMMU.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Ivhd<'a> {
+    /// Type of the block: `0x10`, `0x11` or `0x40`. The later types report
+    /// extended features.
+    pub block_type: u8,
+    /// Flags of the block.
+    pub flags: u8,
+    /// Device ID of the IOMMU itself.
+    pub device_id: u16,
+    /// Offset of the IOMMU capability block in the PCI configuration space.
+    pub capability_offset: u16,
+    /// Base address of the registers of the IOMMU.
+    pub base_address: u64,
+    /// PCI segment of the devices.
+    pub segment: u16,
+    /// IOMMU information, such as the MSI number.
+    pub info: u16,
+    entries: &'a [u8],
+}
+
+impl<'a> Ivhd<'a> {
+    /// Returns the device entries.
+    #[must_use]
+    pub const fn entries(&self) -> IvhdEntries<'a> {
+        IvhdEntries {
+            bytes: self.entries,
+        }
+    }
+
+    /// Returns whether the IOMMU translates DMA of the device with
+    /// `device_id` in its segment, according to the device entries.
+    #[must_use]
+    pub fn covers(&self, device_id: u16) -> bool {
+        let mut range_start = None;
+        for entry in self.entries() {
+            match entry.entry_type {
+                IvhdEntryType::ALL => return true,
+                IvhdEntryType::SELECT
+                | IvhdEntryType::ALIAS_SELECT
+                | IvhdEntryType::EXTENDED_SELECT
+                    if entry.device_id == device_id =>
+                {
+                    return true;
+                }
+                IvhdEntryType::START_OF_RANGE
+                | IvhdEntryType::ALIAS_START_OF_RANGE
+                | IvhdEntryType::EXTENDED_START_OF_RANGE => range_start = Some(entry.device_id),
+                IvhdEntryType::END_OF_RANGE => {
+                    if range_start
+                        .is_some_and(|start| (start..=entry.device_id).contains(&device_id))
+                    {
+                        return true;
+                    }
+                    range_start = None;
+                }
+                _ => {}
+            }
+        }
+        false
+    }
+}
+
+newtype_enum! {
+    /// Type of an [`Ivmd`] block.
+    pub enum IvmdType: u8 => {
+        /// The memory block applies to all devices.
+        ALL = 0x20,
+        /// The memory block applies to the device with the device ID.
+        SELECT = 0x21,
+        /// The memory block applies to the devices from the device ID to
+        /// the device ID in the auxiliary data.
+        RANGE = 0x22,
+    }
+}
+
+/// An I/O virtualization memory definition (IVMD) block: memory that devices
+/// may access with DMA, or must not access.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Ivmd {
+    /// Type of the block.
+    pub block_type: IvmdType,
+    /// Flags of the block, e.g. whether the memory is readable or writable
+    /// by the devices.
+    pub flags: u8,
+    /// Device ID of the first device the block applies to.
+    pub device_id: u16,
+    /// Device ID of the last device for [`IvmdType::RANGE`].
+    pub aux_data: u16,
+    /// Start address of the memory.
+    pub start: u64,
+    /// Length of the memory in bytes.
+    pub length: u64,
+}
+
+/// A block of the [`Ivrs`] table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IvrsBlock<'a> {
+    /// An I/O virtualization hardware definition (IVHD).
+    Ivhd(Ivhd<'a>),
+    /// An I/O virtualization memory definition (IVMD).
+    Ivmd(Ivmd),
+    /// Another block.
+    Other {
+        /// Type of the block.
+        block_type: u8,
+        /// The raw block, including its header.
+        bytes: &'a [u8],
+    },
+}
+
+impl<'a> IvrsBlock<'a> {
+    fn parse(bytes: &'a [u8]) -> Option<Self> {
+        let block_type = bytes[0];
+        Some(match block_type {
+            0x10 | 0x11 | 0x40 => {
+                let entries = if block_type == 0x10 { 24 } else { 40 };
+                Self::Ivhd(Ivhd {
+                    block_type,
+                    flags: bytes[1],
+                    device_id: read_u16(bytes, 4)?,
+                    capability_offset: read_u16(bytes, 6)?,
+                    base_address: read_u64(bytes, 8)?,
+                    segment: read_u16(bytes, 16)?,
+                    info: read_u16(bytes, 18)?,
+                    entries: bytes.get(entries..)?,
+                })
+            }
+            0x20..=0x22 => Self::Ivmd(Ivmd {
+                block_type: IvmdType(block_type),
+                flags: bytes[1],
+                device_id: read_u16(bytes, 4)?,
+                aux_data: read_u16(bytes, 6)?,
+                start: read_u64(bytes, 16)?,
+                length: read_u64(bytes, 24)?,
+            }),
+            _ => Self::Other { block_type, bytes },
+        })
+    }
+}
+
+/// Iterator over the [`IvrsBlock`]s of an [`Ivrs`] table. Stops at the first
+/// truncated block.
+#[derive(Clone, Debug)]
+pub struct IvrsBlocks<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Iterator for IvrsBlocks<'a> {
+    type Item = IvrsBlock<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let len = usize::from(read_u16(self.bytes, 2)?);
+        let block = self
+            .bytes
+            .get(..len)
+            .filter(|_| len >= 4)
+            .and_then(IvrsBlock::parse);
+        self.bytes = if block.is_some() {
+            &self.bytes[len..]
+        } else {
+            &[]
+        };
+        block
+    }
+}
+
+/// The I/O virtualization reporting structure (IVRS), describing AMD-Vi.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Ivrs<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Ivrs<'a> {
+    /// Offset of the blocks.
+    const BLOCKS: usize = ACPI_HEADER_SIZE + 12;
+
+    /// Parses an IVRS table, including its ACPI header. Returns `None` if the
+    /// signature or length is invalid.
+    #[must_use]
+    pub fn parse(bytes: &'a [u8]) -> Option<Self> {
+        let bytes = table_body(bytes, b"IVRS", Self::BLOCKS)?;
+        Some(Self { bytes })
+    }
+
+    /// Returns the IVRS table of the firmware, or `None` if there is none.
+    #[must_use]
+    pub fn find() -> Option<Ivrs<'static>> {
+        Ivrs::parse(find_acpi_table(*b"IVRS")?)
+    }
+
+    /// Returns the raw table, including its ACPI header.
+    #[must_use]
+    pub const fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Returns the I/O virtualization information, e.g. the supported
+    /// address sizes.
+    #[must_use]
+    pub fn iv_info(&self) -> u32 {
+        u32::from_le_bytes(
+            self.bytes[ACPI_HEADER_SIZE..ACPI_HEADER_SIZE + 4]
+                .try_into()
+                .unwrap(),
+        )
+    }
+
+    /// Returns the blocks.
+    #[must_use]
+    pub fn blocks(&self) -> IvrsBlocks<'a> {
+        IvrsBlocks {
+            bytes: &self.bytes[Self::BLOCKS..],
+        }
+    }
+
+    /// Returns the IOMMUs. Firmware may describe an IOMMU with several
+    /// IVHD types; use [`Ivhd::block_type`] to pick one.
+    pub fn iommus(&self) -> impl Iterator<Item = Ivhd<'a>> + use<'a> {
+        self.blocks().filter_map(|block| match block {
+            IvrsBlock::Ivhd(ivhd) => Some(ivhd),
+            _ => None,
+        })
+    }
+
+    /// Returns the IOMMU translating DMA of the PCI device `bus:dev.fun` in
+    /// `segment`, or `None` if it is not covered.
+    #[must_use]
+    pub fn iommu_for(&self, segment: u16, bus: u8, dev: u8, fun: u8) -> Option<Ivhd<'a>> {
+        let device_id = (u16::from(bus) << 8) | (u16::from(dev & 0x1f) << 3) | u16::from(fun & 0x7);
+        self.iommus()
+            .find(|ivhd| ivhd.segment == segment && ivhd.covers(device_id))
+    }
+}
+
+impl Debug for Ivrs<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Ivrs")
+            .field("iv_info", &self.iv_info())
+            .field("blocks", &List(self.blocks()))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::format;
+
+    /// Writes bytes into a table buffer.
+    struct Writer<const N: usize> {
+        buf: [u8; N],
+        len: usize,
+    }
+
+    impl<const N: usize> Writer<N> {
+        /// Starts a table with `signature`, followed by `info` and reserved
+        /// bytes up to the first structure.
+        fn new(signature: &[u8; 4], info: &[u8]) -> Self {
+            let mut writer = Self {
+                buf: [0; N],
+                len: ACPI_HEADER_SIZE + 12,
+            };
+            writer.buf[..4].copy_from_slice(signature);
+            writer.buf[ACPI_HEADER_SIZE..ACPI_HEADER_SIZE + info.len()].copy_from_slice(info);
+            writer
+        }
+
+        fn put(&mut self, bytes: &[u8]) -> &mut Self {
+            self.buf[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+            self.len += bytes.len();
+            self
+        }
+
+        fn finish(&mut self) -> &[u8] {
+            let len = (self.len as u32).to_le_bytes();
+            self.buf[4..8].copy_from_slice(&len);
+            &self.buf[..self.len]
+        }
+    }
+
+    #[test]
+    fn test_dmar() {
+        let mut writer = Writer::<256>::new(b"DMAR", &[38, 0x05]);
+        // DRHD for 00:02.0.
+        writer
+            .put(&[0, 0, 24, 0, 0, 0, 0, 0])
+            .put(&0xfed9_0000u64.to_le_bytes())
+            .put(&[1, 8, 0, 0, 0, 0, 0x02, 0x00]);
+        // DRHD with INCLUDE_PCI_ALL and an I/O APIC.
+        writer
+            .put(&[0, 0, 24, 0, 1, 0, 0, 0])
+            .put(&0xfed9_1000u64.to_le_bytes())
+            .put(&[3, 8, 0, 0, 2, 0xf0, 0x1f, 0x00]);
+        // RMRR for 00:14.0.
+        writer
+            .put(&[1, 0, 32, 0, 0, 0, 0, 0])
+            .put(&0x3e2e_0000u64.to_le_bytes())
+            .put(&0x3e2f_ffffu64.to_le_bytes())
+            .put(&[1, 8, 0, 0, 0, 0, 0x14, 0x00]);
+        // ANDD.
+        writer.put(&[4, 0, 16, 0, 0, 0, 0, 1]).put(b"\\_SB.UA\0");
+        // Unknown structure.
+        writer.put(&[9, 0, 4, 0]);
+
+        let dmar = Dmar::parse(writer.finish()).unwrap();
+        assert_eq!(dmar.host_address_width(), 39);
+        assert_eq!(
+            dmar.flags(),
+            DmarFlags::INTR_REMAP | DmarFlags::DMA_CTRL_PLATFORM_OPT_IN
+        );
+        assert_eq!(dmar.structures().count(), 5);
+
+        let units: [_; 2] = core::array::from_fn({
+            let mut units = dmar.remapping_units();
+            move |_| units.next().unwrap()
+        });
+        assert!(!units[0].include_pci_all);
+        assert_eq!(units[0].register_base, 0xfed9_0000);
+        let scope = units[0].device_scopes().next().unwrap();
+        assert_eq!(scope.scope_type, DeviceScopeType::PCI_ENDPOINT);
+        assert!(scope.path().eq([(2, 0)]));
+        assert!(units[1].include_pci_all);
+        let ioapic = units[1].device_scopes().next().unwrap();
+        assert_eq!(ioapic.scope_type, DeviceScopeType::IOAPIC);
+        assert_eq!((ioapic.enumeration_id, ioapic.start_bus), (2, 0xf0));
+
+        assert_eq!(dmar.remapping_unit_for(0, 0, 2, 0), Some(units[0]));
+        assert_eq!(dmar.remapping_unit_for(0, 0, 0x1f, 3), Some(units[1]));
+        assert_eq!(dmar.remapping_unit_for(1, 0, 2, 0), None);
+
+        let mut structures = dmar.structures().skip(2);
+        let Some(DmarStructure::ReservedMemory(rmrr)) = structures.next() else {
+            panic!("expected RMRR");
+        };
+        assert_eq!((rmrr.base, rmrr.limit), (0x3e2e_0000, 0x3e2f_ffff));
+        assert!(rmrr.device_scopes().next().unwrap().path().eq([(0x14, 0)]));
+        assert_eq!(
+            structures.next(),
+            Some(DmarStructure::AcpiNamespaceDevice {
+                device_number: 1,
+                name: b"\\_SB.UA",
+            })
+        );
+        assert!(matches!(
+            structures.next(),
+            Some(DmarStructure::Other {
+                structure_type: 9,
+                ..
+            })
+        ));
+
+        assert!(Dmar::parse(b"DMAR").is_none());
+        assert!(Dmar::parse(&writer.finish()[..40]).is_none());
+
+        // The largest width that fits in the table.
+        let mut wide = Writer::<256>::new(b"DMAR", &[0xff, 0]);
+        let dmar = Dmar::parse(wide.finish()).unwrap();
+        assert_eq!(dmar.host_address_width(), 256);
+        assert!(format!("{dmar:?}").contains("host_address_width: 256"));
+    }
+
+    #[test]
+    fn test_ivrs() {
+        let mut writer = Writer::<256>::new(b"IVRS", &0x0020_3043u32.to_le_bytes());
+        // IVHD with a select entry for 00:02.0, a range of bus 1, a special
+        // entry, and an ACPI HID entry.
+        writer
+            .put(&[0x10, 0xb0, 68, 0, 0x02, 0, 0x40, 0])
+            .put(&0xfeb8_0000u64.to_le_bytes())
+            .put(&[0, 0, 0, 0, 0, 0, 0, 0])
+            .put(&[0x02, 0x10, 0x00, 0])
+            .put(&[0x03, 0x00, 0x01, 0])
+            .put(&[0x04, 0xff, 0x01, 0])
+            .put(&[0x48, 0, 0, 0xd7, 0, 0xa0, 0, 1])
+            .put(&[0xf0, 0xa5, 0, 0x40])
+            .put(b"AMDI0020")
+            .put(&[0; 8])
+            .put(&[2, 2, b'I', b'D']);
+        // IVMD for 00:14.0.
+        writer
+            .put(&[0x21, 0x07, 32, 0, 0xa0, 0, 0, 0])
+            .put(&[0; 8])
+            .put(&0x9d00_0000u64.to_le_bytes())
+            .put(&0x1000u64.to_le_bytes());
+
+        let ivrs = Ivrs::parse(writer.finish()).unwrap();
+        assert_eq!(ivrs.iv_info(), 0x0020_3043);
+        assert_eq!(ivrs.blocks().count(), 2);
+
+        let iommu = ivrs.iommus().next().unwrap();
+        assert_eq!(iommu.base_address, 0xfeb8_0000);
+        assert_eq!(iommu.device_id, 0x0002);
+        assert_eq!(iommu.capability_offset, 0x40);
+        let entries: [_; 5] = core::array::from_fn({
+            let mut entries = iommu.entries();
+            move |_| entries.next().unwrap()
+        });
+        assert_eq!(entries[0].entry_type, IvhdEntryType::SELECT);
+        assert_eq!(entries[3].entry_type, IvhdEntryType::SPECIAL);
+        assert_eq!(entries[3].extra, [0, 0xa0, 0, 1]);
+        assert_eq!(entries[4].entry_type, IvhdEntryType::ACPI_HID);
+        assert_eq!(&entries[4].extra[..8], b"AMDI0020");
+        assert!(iommu.entries().nth(5).is_none());
+
+        assert_eq!(ivrs.iommu_for(0, 0, 2, 0), Some(iommu));
+        assert_eq!(ivrs.iommu_for(0, 1, 5, 1), Some(iommu));
+        assert_eq!(ivrs.iommu_for(0, 0, 0x14, 0), None);
+        assert_eq!(ivrs.iommu_for(1, 0, 2, 0), None);
+
+        assert_eq!(
+            ivrs.blocks().nth(1),
+            Some(IvrsBlock::Ivmd(Ivmd {
+                block_type: IvmdType::SELECT,
+                flags: 0x07,
+                device_id: 0x00a0,
+                aux_data: 0,
+                start: 0x9d00_0000,
+                length: 0x1000,
+            }))
+        );
+    }
+}
@@ -0,0 +1,375 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Auditing the security posture of the firmware.
+//!
+//! [`audit`] collects the security-relevant state of the platform into a
+//! [`SecurityReport`]: whether Secure Boot is enforced, which TPM and PCR
+//! banks are available, whether the firmware describes an IOMMU for DMA
+//! protection, and whether boot services memory is protected against
+//! execution. This is the information compliance check tools typically
+//! verify before trusting a platform:
+//!
+//! ```no_run
+//! use uefi::security;
+//!
+//! let report = security::audit();
+//! if !report.secure_boot.is_enforced() {
+//!     log::warn!("Secure Boot is not enforced");
+//! }
+//! log::info!("{report}");
+//! ```
+
+use self::iommu::{Dmar, DmarFlags, Ivrs};
+use crate::boot::{self, AllocateType, OpenProtocolAttributes, OpenProtocolParams};
+use crate::mem::memory_map::{MemoryAttribute, MemoryType};
+use crate::proto::security::MemoryProtection;
+use crate::proto::tcg::{HashAlgorithm, v1, v2};
+use crate::runtime::{self, VariableVendor};
+use crate::table::cfg::ConfigTableEntry;
+use crate::{CStr16, cstr16, system};
+use core::fmt::{self, Display, Formatter};
+use core::{ptr, slice};
+
+pub mod iommu;
+
+/// Size of the header of an ACPI system description table.
+const ACPI_HEADER_SIZE: usize = 36;
+
+/// State of the Secure Boot global variables. A variable is `None` if it
+/// is not set, e.g. because the firmware does not support Secure Boot.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SecureBootState {
+    /// The `SecureBoot` variable: whether Secure Boot is enabled.
+    pub secure_boot: Option<bool>,
+    /// The `SetupMode` variable: whether no platform key is enrolled.
+    pub setup_mode: Option<bool>,
+    /// The `AuditMode` variable: whether image verification failures are
+    /// only logged.
+    pub audit_mode: Option<bool>,
+    /// The `DeployedMode` variable: whether the platform is in deployed mode.
+    pub deployed_mode: Option<bool>,
+    /// The `VendorKeys` variable: whether the enrolled keys are the
+    /// unmodified keys of the platform vendor.
+    pub vendor_keys: Option<bool>,
+}
+
+impl SecureBootState {
+    /// Reads the Secure Boot state from the global variables.
+    #[must_use]
+    pub fn read() -> Self {
+        Self {
+            secure_boot: read_bool(cstr16!("SecureBoot")),
+            setup_mode: read_bool(cstr16!("SetupMode")),
+            audit_mode: read_bool(cstr16!("AuditMode")),
+            deployed_mode: read_bool(cstr16!("DeployedMode")),
+            vendor_keys: read_bool(cstr16!("VendorKeys")),
+        }
+    }
+
+    /// Returns whether Secure Boot verifies images, i.e. whether it is
+    /// enabled and neither in setup mode nor in audit mode.
+    #[must_use]
+    pub fn is_enforced(&self) -> bool {
+        self.secure_boot == Some(true)
+            && self.setup_mode != Some(true)
+            && self.audit_mode != Some(true)
+    }
+}
+
+/// Version of the TPM specification implemented by the firmware.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TpmVersion {
+    /// TPM 1.2, through the [`v1::Tcg`] protocol.
+    V1_2,
+    /// TPM 2.0, through the [`v2::Tcg`] protocol.
+    V2_0,
+}
+
+/// State of the TPM.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TpmState {
+    /// Version of the TPM.
+    pub version: TpmVersion,
+    /// Whether the TPM device is present.
+    pub present: bool,
+    /// Manufacturer ID of the TPM, only reported by TPM 2.0.
+    pub manufacturer_id: Option<u32>,
+    /// Hash algorithms supported for PCR banks.
+    pub supported_pcr_banks: HashAlgorithm,
+    /// Hash algorithms of the active PCR banks.
+    pub active_pcr_banks: HashAlgorithm,
+}
+
+impl TpmState {
+    /// Reads the state of the TPM, preferring TPM 2.0. Returns `None` if
+    /// the firmware provides no TCG protocol.
+    #[must_use]
+    pub fn read() -> Option<Self> {
+        if let Some(capability) =
+            get_protocol::<v2::Tcg>().and_then(|tcg| tcg.get_capability().ok())
+        {
+            return Some(Self {
+                version: TpmVersion::V2_0,
+                present: capability.tpm_present(),
+                manufacturer_id: Some(capability.manufacturer_id),
+                supported_pcr_banks: capability.hash_algorithm_bitmap,
+                active_pcr_banks: capability.active_pcr_banks,
+            });
+        }
+        let tcg = get_protocol::<v1::Tcg>()?;
+        let capability = tcg.status_check().ok()?.protocol_capability;
+        Some(Self {
+            version: TpmVersion::V1_2,
+            present: capability.tpm_present() && !capability.tpm_deactivated(),
+            manufacturer_id: None,
+            supported_pcr_banks: capability.hash_algorithm(),
+            active_pcr_banks: capability.hash_algorithm(),
+        })
+    }
+}
+
+/// Indicators of DMA protection by an IOMMU, from the ACPI tables. See
+/// [`iommu`] for the details of the tables.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct DmaProtection {
+    /// Whether a DMAR table describes Intel VT-d remapping units.
+    pub dmar: bool,
+    /// Whether the DMAR table requests the OS to enable DMA protection
+    /// (the `DMA_CTRL_PLATFORM_OPT_IN_FLAG`).
+    pub dmar_platform_opt_in: bool,
+    /// Whether an IVRS table describes AMD-Vi IOMMUs.
+    pub ivrs: bool,
+}
+
+impl DmaProtection {
+    /// Reads the DMA protection indicators from the ACPI tables.
+    #[must_use]
+    pub fn read() -> Self {
+        let dmar = Dmar::find();
+        Self {
+            dmar: dmar.is_some(),
+            dmar_platform_opt_in: dmar
+                .is_some_and(|dmar| dmar.flags().contains(DmarFlags::DMA_CTRL_PLATFORM_OPT_IN)),
+            ivrs: Ivrs::find().is_some(),
+        }
+    }
+
+    /// Returns whether the firmware describes an IOMMU.
+    #[must_use]
+    pub const fn has_iommu(&self) -> bool {
+        self.dmar || self.ivrs
+    }
+}
+
+/// State of the protection of memory against execution.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct MemoryProtectionState {
+    /// Whether the firmware provides the [`MemoryProtection`] protocol.
+    pub memory_attribute_protocol: bool,
+    /// Whether the firmware publishes the memory attributes table, which
+    /// describes the protection of runtime services memory.
+    pub memory_attributes_table: bool,
+    /// Whether newly allocated boot services data is not executable, or
+    /// `None` if it could not be determined.
+    pub boot_services_data_nx: Option<bool>,
+}
+
+impl MemoryProtectionState {
+    /// Reads the memory protection state.
+    #[must_use]
+    pub fn read() -> Self {
+        let protocol = get_protocol::<MemoryProtection>();
+        Self {
+            memory_attribute_protocol: protocol.is_some(),
+            memory_attributes_table: system::with_config_table(|tables| {
+                tables
+                    .iter()
+                    .any(|entry| entry.guid == ConfigTableEntry::MEMORY_ATTRIBUTES_GUID)
+            }),
+            boot_services_data_nx: protocol.and_then(|protocol| {
+                let page =
+                    boot::allocate_pages(AllocateType::AnyPages, MemoryType::BOOT_SERVICES_DATA, 1)
+                        .ok()?;
+                let start = page.as_ptr() as u64;
+                let attributes = protocol.get_memory_attributes(start..start + 4096);
+                // SAFETY: the page was allocated above and is not used.
+                let _ = unsafe { boot::free_pages(page, 1) };
+                Some(attributes.ok()?.contains(MemoryAttribute::EXECUTE_PROTECT))
+            }),
+        }
+    }
+}
+
+/// The security posture of the firmware, returned by [`audit`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SecurityReport {
+    /// State of Secure Boot.
+    pub secure_boot: SecureBootState,
+    /// State of the TPM, or `None` if there is no TCG protocol.
+    pub tpm: Option<TpmState>,
+    /// DMA protection indicators.
+    pub dma_protection: DmaProtection,
+    /// State of memory protection.
+    pub memory_protection: MemoryProtectionState,
+}
+
+impl Display for SecurityReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let flag = |value: Option<bool>| match value {
+            Some(true) => "yes",
+            Some(false) => "no",
+            None => "unknown",
+        };
+        let secure_boot = &self.secure_boot;
+        writeln!(
+            f,
+            "Secure Boot: {} (enabled: {}, setup mode: {}, audit mode: {}, deployed mode: {})",
+            if secure_boot.is_enforced() {
+                "enforced"
+            } else {
+                "not enforced"
+            },
+            flag(secure_boot.secure_boot),
+            flag(secure_boot.setup_mode),
+            flag(secure_boot.audit_mode),
+            flag(secure_boot.deployed_mode),
+        )?;
+        match &self.tpm {
+            Some(tpm) => writeln!(
+                f,
+                "TPM: {:?}, present: {}, active PCR banks: {:?}",
+                tpm.version,
+                flag(Some(tpm.present)),
+                tpm.active_pcr_banks
+            )?,
+            None => writeln!(f, "TPM: none")?,
+        }
+        let dma = &self.dma_protection;
+        writeln!(
+            f,
+            "DMA protection: DMAR: {}, platform opt-in: {}, IVRS: {}",
+            flag(Some(dma.dmar)),
+            flag(Some(dma.dmar_platform_opt_in)),
+            flag(Some(dma.ivrs)),
+        )?;
+        let memory = &self.memory_protection;
+        writeln!(
+            f,
+            "Memory protection: attribute protocol: {}, attributes table: {}, boot services data NX: {}",
+            flag(Some(memory.memory_attribute_protocol)),
+            flag(Some(memory.memory_attributes_table)),
+            flag(memory.boot_services_data_nx),
+        )
+    }
+}
+
+/// Audits the security posture of the firmware.
+///
+/// Missing protocols, variables and tables are reported as such rather
+/// than as errors, so this never fails. Boot services must be active.
+#[must_use]
+pub fn audit() -> SecurityReport {
+    SecurityReport {
+        secure_boot: SecureBootState::read(),
+        tpm: TpmState::read(),
+        dma_protection: DmaProtection::read(),
+        memory_protection: MemoryProtectionState::read(),
+    }
+}
+
+/// Reads a one-byte boolean global variable.
+fn read_bool(name: &CStr16) -> Option<bool> {
+    let mut buf = [0; 1];
+    let (data, _) = runtime::get_variable(name, &VariableVendor::GLOBAL_VARIABLE, &mut buf).ok()?;
+    Some(data.first()? == &1)
+}
+
+/// Opens the first instance of `P` without affecting drivers managing it.
+fn get_protocol<P: crate::proto::ProtocolPointer + ?Sized>() -> Option<boot::ScopedProtocol<P>> {
+    let handle = boot::get_handle_for_protocol::<P>().ok()?;
+    // SAFETY: the protocol is only used briefly, while the handle is not
+    // expected to go away.
+    unsafe {
+        boot::open_protocol::<P>(
+            OpenProtocolParams {
+                handle,
+                agent: boot::image_handle(),
+                controller: None,
+            },
+            OpenProtocolAttributes::GetProtocol,
+        )
+    }
+    .ok()
+}
+
+/// Returns the ACPI table with `signature`, listed in the XSDT (or the RSDT
+/// for ACPI 1.0) of the RSDP in the configuration table.
+fn find_acpi_table(signature: [u8; 4]) -> Option<&'static [u8]> {
+    let (rsdp, acpi2) = system::with_config_table(|tables| {
+        let find = |guid| tables.iter().find(|entry| entry.guid == guid);
+        find(ConfigTableEntry::ACPI2_GUID)
+            .map(|entry| (entry.address.cast::<u8>(), true))
+            .or_else(|| {
+                find(ConfigTableEntry::ACPI_GUID).map(|entry| (entry.address.cast(), false))
+            })
+    })?;
+    // SAFETY: the firmware publishes a valid RSDP, and the tables it points
+    // to are identity mapped while boot services are active.
+    unsafe {
+        if rsdp.is_null() || slice::from_raw_parts(rsdp, 8) != b"RSD PTR " {
+            return None;
+        }
+        let (sdt, entry_size) = if acpi2 && rsdp.add(15).read() >= 2 {
+            (ptr::read_unaligned(rsdp.add(24).cast::<u64>()), 8)
+        } else {
+            (
+                u64::from(ptr::read_unaligned(rsdp.add(16).cast::<u32>())),
+                4,
+            )
+        };
+        let sdt = acpi_table(sdt)?;
+        sdt[ACPI_HEADER_SIZE..]
+            .chunks_exact(entry_size)
+            .filter_map(|entry| {
+                let mut address = [0; 8];
+                address[..entry_size].copy_from_slice(entry);
+                acpi_table(u64::from_le_bytes(address))
+            })
+            .find(|table| table[..4] == signature)
+    }
+}
+
+/// Returns the ACPI table at physical `address`.
+///
+/// # Safety
+///
+/// `address` must be null or point to a valid ACPI table.
+unsafe fn acpi_table(address: u64) -> Option<&'static [u8]> {
+    let table = address as usize as *const u8;
+    if table.is_null() {
+        return None;
+    }
+    let len = unsafe { ptr::read_unaligned(table.add(4).cast::<u32>()) } as usize;
+    (len >= ACPI_HEADER_SIZE).then(|| unsafe { slice::from_raw_parts(table, len) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secure_boot_enforced() {
+        let mut state = SecureBootState {
+            secure_boot: Some(true),
+            setup_mode: Some(false),
+            ..Default::default()
+        };
+        assert!(state.is_enforced());
+        state.audit_mode = Some(true);
+        assert!(!state.is_enforced());
+        state.audit_mode = None;
+        state.setup_mode = Some(true);
+        assert!(!state.is_enforced());
+        assert!(!SecureBootState::default().is_enforced());
+    }
+}
@@ -0,0 +1,227 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Parsing of the SMBIOS structure table.
+//!
+//! The structure table describes the hardware of the system as a list of
+//! structures. Each structure starts with its type, length and handle,
+//! followed by the rest of its formatted area, then by a set of
+//! null-terminated strings ending with an additional null. Fields of the
+//! formatted area refer to the strings by their index, starting at 1.
+//!
+//! [`Structures`] only operates on the bytes of the table, so it also
+//! works on the host, e.g. on a dump of `/sys/firmware/dmi/tables/DMI`:
+//!
+//! ```
+//! use uefi::smbios::Structures;
+//!
+//! # let table: &[u8] = &[1, 6, 0, 0, 1, 0, b'Q', b'E', b'M', b'U', 0, 0];
+//! for structure in Structures::new(table) {
+//!     let structure = structure.unwrap();
+//!     if structure.ty() == Structures::SYSTEM_INFORMATION {
+//!         let manufacturer = structure.string(structure.formatted()[4]);
+//!         assert_eq!(manufacturer, Some(&b"QEMU"[..]));
+//!     }
+//! }
+//! ```
+
+use core::fmt::{self, Display, Formatter};
+
+/// Error returned when iterating over an invalid structure table.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SmbiosError {
+    /// The length of a structure is less than the size of its header.
+    InvalidLength {
+        /// Offset of the structure in the table.
+        offset: usize,
+    },
+    /// A structure extends past the end of the table.
+    Truncated {
+        /// Offset of the structure in the table.
+        offset: usize,
+    },
+}
+
+impl Display for SmbiosError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidLength { offset } => {
+                write!(f, "invalid length of the structure at offset {offset:#x}")
+            }
+            Self::Truncated { offset } => {
+                write!(f, "truncated structure at offset {offset:#x}")
+            }
+        }
+    }
+}
+
+impl core::error::Error for SmbiosError {}
+
+/// A structure of the SMBIOS structure table.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Structure<'a> {
+    formatted: &'a [u8],
+    strings: &'a [u8],
+}
+
+impl<'a> Structure<'a> {
+    /// Returns the type of the structure.
+    #[must_use]
+    pub const fn ty(&self) -> u8 {
+        self.formatted[0]
+    }
+
+    /// Returns the handle of the structure.
+    #[must_use]
+    pub const fn handle(&self) -> u16 {
+        u16::from_le_bytes([self.formatted[2], self.formatted[3]])
+    }
+
+    /// Returns the formatted area of the structure, including its header.
+    /// Offsets in the area match those of the SMBIOS specification.
+    #[must_use]
+    pub const fn formatted(&self) -> &'a [u8] {
+        self.formatted
+    }
+
+    /// Returns the string `index` of the structure, without its null
+    /// terminator. Returns `None` for index 0, which means that the field
+    /// has no string, or if there is no such string.
+    #[must_use]
+    pub fn string(&self, index: u8) -> Option<&'a [u8]> {
+        let index = usize::from(index.checked_sub(1)?);
+        if self.strings.is_empty() {
+            return None;
+        }
+        self.strings.split(|&b| b == 0).nth(index)
+    }
+}
+
+/// Iterator over the structures of an SMBIOS structure table.
+///
+/// Iteration ends after the end-of-table structure (type 127), or at the
+/// end of the table. After returning an error, the iterator returns
+/// `None`.
+#[derive(Clone, Debug)]
+pub struct Structures<'a> {
+    rest: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Structures<'a> {
+    /// Type of the System Information structure.
+    pub const SYSTEM_INFORMATION: u8 = 1;
+    /// Type of the End-of-Table structure.
+    pub const END_OF_TABLE: u8 = 127;
+
+    /// Creates an iterator over the structures of `table`.
+    #[must_use]
+    pub const fn new(table: &'a [u8]) -> Self {
+        Self {
+            rest: table,
+            offset: 0,
+        }
+    }
+
+    fn parse(&self) -> Result<(Structure<'a>, usize), SmbiosError> {
+        let offset = self.offset;
+        let len = usize::from(*self.rest.get(1).ok_or(SmbiosError::Truncated { offset })?);
+        if len < 4 {
+            return Err(SmbiosError::InvalidLength { offset });
+        }
+        let formatted = self
+            .rest
+            .get(..len)
+            .ok_or(SmbiosError::Truncated { offset })?;
+        // The strings end with two nulls; a structure without strings has
+        // just the two nulls.
+        let strings_len = self.rest[len..]
+            .windows(2)
+            .position(|w| w == [0, 0])
+            .ok_or(SmbiosError::Truncated { offset })?;
+        let strings = &self.rest[len..len + strings_len];
+        Ok((Structure { formatted, strings }, len + strings_len + 2))
+    }
+}
+
+impl<'a> Iterator for Structures<'a> {
+    type Item = Result<Structure<'a>, SmbiosError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        match self.parse() {
+            Ok((structure, size)) => {
+                self.rest = if structure.ty() == Self::END_OF_TABLE {
+                    &[]
+                } else {
+                    &self.rest[size..]
+                };
+                self.offset += size;
+                Some(Ok(structure))
+            }
+            Err(err) => {
+                self.rest = &[];
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rustfmt::skip]
+    const TABLE: &[u8] = &[
+        // Type 0 with one string.
+        0, 4, 0, 0, b'B', b'I', b'O', b'S', 0, 0,
+        // Type 1 with two strings.
+        1, 8, 1, 0, 1, 2, 0, 0,
+        b'Q', b'E', b'M', b'U', 0,
+        b'P', b'C', 0, 0,
+        // End of table, without strings.
+        127, 4, 2, 0, 0, 0,
+        // Ignored.
+        0xff,
+    ];
+
+    #[test]
+    fn test_structures() {
+        let mut structures = Structures::new(TABLE).map(Result::unwrap);
+
+        let bios = structures.next().unwrap();
+        assert_eq!(bios.ty(), 0);
+        assert_eq!(bios.string(1), Some(&b"BIOS"[..]));
+
+        let system = structures.next().unwrap();
+        assert_eq!(system.ty(), Structures::SYSTEM_INFORMATION);
+        assert_eq!(system.handle(), 1);
+        assert_eq!(system.formatted(), &TABLE[10..18]);
+        assert_eq!(system.string(0), None);
+        assert_eq!(system.string(2), Some(&b"PC"[..]));
+        assert_eq!(system.string(3), None);
+
+        let end = structures.next().unwrap();
+        assert_eq!(end.ty(), Structures::END_OF_TABLE);
+        assert_eq!(end.string(1), None);
+        assert_eq!(structures.next(), None);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let mut structures = Structures::new(&TABLE[..20]);
+        assert!(structures.next().unwrap().is_ok());
+        assert_eq!(
+            structures.next(),
+            Some(Err(SmbiosError::Truncated { offset: 10 }))
+        );
+        assert_eq!(structures.next(), None);
+
+        let mut structures = Structures::new(&[0, 3, 0, 0, 0]);
+        assert_eq!(
+            structures.next(),
+            Some(Err(SmbiosError::InvalidLength { offset: 0 }))
+        );
+    }
+}
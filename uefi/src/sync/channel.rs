@@ -0,0 +1,316 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Lock-free single-producer single-consumer channel.
+
+use crate::{Event, ResultExt, boot};
+use core::cell::UnsafeCell;
+use core::ffi::c_void;
+use core::fmt::{self, Debug, Formatter};
+use core::hint;
+use core::mem::MaybeUninit;
+use core::ptr;
+use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+
+/// A bounded single-producer single-consumer channel holding up to `N`
+/// values.
+///
+/// The channel is meant for passing data from event notification functions
+/// running at [`Tpl::CALLBACK`] or [`Tpl::NOTIFY`] to code running at
+/// [`Tpl::APPLICATION`], such as received keys or network packets. It is
+/// lock-free, so neither side needs to raise the TPL: a notification
+/// function may interrupt the consumer at any point without corrupting the
+/// channel, and without the consumer being able to block the notification
+/// function.
+///
+/// The channel is split into a [`Producer`] and a [`Consumer`] with
+/// [`split`]. Since each of them needs to be borrowed mutably to use it,
+/// there can only be one of each; in particular, a producer must not be
+/// shared by notification functions running at different TPLs, since the one
+/// at the higher TPL could interrupt the other one.
+///
+/// If a wakeup event is set with [`set_wakeup_event`], the producer signals
+/// it after sending a value, so that [`Consumer::recv`] can wait for it
+/// instead of polling.
+///
+/// ```no_run
+/// use core::ffi::c_void;
+/// use core::ptr::NonNull;
+/// use uefi::sync::{Channel, Producer};
+/// use uefi::{Event, boot};
+///
+/// static CHANNEL: Channel<u32, 16> = Channel::new();
+///
+/// extern "efiapi" fn notify(_event: Event, context: Option<NonNull<c_void>>) {
+///     let producer = unsafe { context.unwrap().cast::<Producer<u32, 16>>().as_mut() };
+///     // The value is dropped if the consumer falls behind.
+///     let _ = producer.try_send(42);
+/// }
+///
+/// # fn main() -> uefi::Result {
+/// let (mut producer, mut consumer) = CHANNEL.split().unwrap();
+/// let wakeup =
+///     unsafe { boot::create_event(boot::EventType::empty(), boot::Tpl::CALLBACK, None, None)? };
+/// CHANNEL.set_wakeup_event(Some(wakeup));
+/// // Register `notify` with a pointer to `producer` as context...
+/// let value = consumer.recv()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Tpl::APPLICATION`]: crate::boot::Tpl::APPLICATION
+/// [`Tpl::CALLBACK`]: crate::boot::Tpl::CALLBACK
+/// [`Tpl::NOTIFY`]: crate::boot::Tpl::NOTIFY
+/// [`set_wakeup_event`]: Self::set_wakeup_event
+/// [`split`]: Self::split
+pub struct Channel<T, const N: usize> {
+    slots: [UnsafeCell<MaybeUninit<T>>; N],
+    /// Number of values received, only written by the consumer.
+    head: AtomicUsize,
+    /// Number of values sent, only written by the producer.
+    tail: AtomicUsize,
+    split: AtomicBool,
+    wakeup: AtomicPtr<c_void>,
+}
+
+// SAFETY: each slot is accessed by either the producer or the consumer,
+// handed over through `head` and `tail` with release/acquire ordering.
+unsafe impl<T: Send, const N: usize> Send for Channel<T, N> {}
+// SAFETY: see above.
+unsafe impl<T: Send, const N: usize> Sync for Channel<T, N> {}
+
+impl<T, const N: usize> Channel<T, N> {
+    /// Creates an empty channel without a wakeup event.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            slots: [const { UnsafeCell::new(MaybeUninit::uninit()) }; N],
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            split: AtomicBool::new(false),
+            wakeup: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+
+    /// Returns the producer and consumer of the channel. Returns `None` if
+    /// the channel was already split.
+    pub fn split(&self) -> Option<(Producer<'_, T, N>, Consumer<'_, T, N>)> {
+        if self.split.swap(true, Ordering::AcqRel) {
+            None
+        } else {
+            Some((Producer { channel: self }, Consumer { channel: self }))
+        }
+    }
+
+    /// Sets the event signaled after each sent value, returning the
+    /// previous one.
+    ///
+    /// The event must not have a notification function if it is waited on
+    /// with [`Consumer::recv`]. The channel doesn't close the event; the
+    /// caller must do so once the channel is no longer used.
+    pub fn set_wakeup_event(&self, event: Option<Event>) -> Option<Event> {
+        let new = event.map_or(ptr::null_mut(), |event| event.as_ptr());
+        let prev = self.wakeup.swap(new, Ordering::AcqRel);
+        // SAFETY: the pointer is either null or came from an `Event`.
+        unsafe { Event::from_ptr(prev) }
+    }
+
+    /// Returns the number of values in the channel.
+    pub fn len(&self) -> usize {
+        let tail = self.tail.load(Ordering::Acquire);
+        tail.wrapping_sub(self.head.load(Ordering::Acquire))
+    }
+
+    /// Returns whether the channel is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the maximum number of values in the channel.
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns a clone of the wakeup event, if any.
+    fn wakeup_event(&self) -> Option<Event> {
+        // SAFETY: the pointer is either null or came from an `Event`.
+        unsafe { Event::from_ptr(self.wakeup.load(Ordering::Acquire)) }
+    }
+}
+
+impl<T, const N: usize> Default for Channel<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Drop for Channel<T, N> {
+    fn drop(&mut self) {
+        let tail = *self.tail.get_mut();
+        let mut head = *self.head.get_mut();
+        while head != tail {
+            // SAFETY: slots between head and tail are initialized.
+            unsafe { self.slots[head % N].get_mut().assume_init_drop() };
+            head = head.wrapping_add(1);
+        }
+    }
+}
+
+impl<T, const N: usize> Debug for Channel<T, N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Channel")
+            .field("len", &self.len())
+            .field("capacity", &N)
+            .finish()
+    }
+}
+
+/// Sending half of a [`Channel`].
+#[derive(Debug)]
+pub struct Producer<'a, T, const N: usize> {
+    channel: &'a Channel<T, N>,
+}
+
+impl<T, const N: usize> Producer<'_, T, N> {
+    /// Sends `value`, then signals the wakeup event if there is one.
+    /// Returns `value` as error if the channel is full.
+    ///
+    /// This doesn't block and may be called at any TPL up to
+    /// [`Tpl::NOTIFY`].
+    ///
+    /// [`Tpl::NOTIFY`]: crate::boot::Tpl::NOTIFY
+    pub fn try_send(&mut self, value: T) -> Result<(), T> {
+        let channel = self.channel;
+        let tail = channel.tail.load(Ordering::Relaxed);
+        if tail.wrapping_sub(channel.head.load(Ordering::Acquire)) >= N {
+            return Err(value);
+        }
+        // SAFETY: the slot is free and only the producer writes to it.
+        unsafe { (*channel.slots[tail % N].get()).write(value) };
+        channel.tail.store(tail.wrapping_add(1), Ordering::Release);
+
+        if let Some(event) = channel.wakeup_event() {
+            // Signaling can only fail for an invalid event.
+            let _ = boot::signal_event(&event);
+        }
+        Ok(())
+    }
+
+    /// Returns whether the channel is full.
+    #[must_use]
+    pub fn is_full(&self) -> bool {
+        self.channel.len() >= N
+    }
+}
+
+/// Receiving half of a [`Channel`].
+#[derive(Debug)]
+pub struct Consumer<'a, T, const N: usize> {
+    channel: &'a Channel<T, N>,
+}
+
+impl<T, const N: usize> Consumer<'_, T, N> {
+    /// Receives the oldest value, or returns `None` if the channel is empty.
+    pub fn try_recv(&mut self) -> Option<T> {
+        let channel = self.channel;
+        let head = channel.head.load(Ordering::Relaxed);
+        if channel.tail.load(Ordering::Acquire) == head {
+            return None;
+        }
+        // SAFETY: the slot was initialized by the producer, and is not
+        // touched by it again until `head` is advanced.
+        let value = unsafe { (*channel.slots[head % N].get()).assume_init_read() };
+        channel.head.store(head.wrapping_add(1), Ordering::Release);
+        Some(value)
+    }
+
+    /// Receives the oldest value, waiting until there is one.
+    ///
+    /// With a wakeup event, this waits for the event with
+    /// [`boot::wait_for_event`], which must be called at
+    /// [`Tpl::APPLICATION`]. Otherwise, this spins until a value arrives.
+    ///
+    /// [`Tpl::APPLICATION`]: crate::boot::Tpl::APPLICATION
+    pub fn recv(&mut self) -> crate::Result<T> {
+        loop {
+            if let Some(value) = self.try_recv() {
+                return Ok(value);
+            }
+            match self.channel.wakeup_event() {
+                Some(event) => {
+                    boot::wait_for_event(&mut [event]).discard_errdata()?;
+                }
+                None => hint::spin_loop(),
+            }
+        }
+    }
+
+    /// Returns an iterator receiving the values currently in the channel.
+    pub fn try_iter(&mut self) -> impl Iterator<Item = T> + '_ {
+        core::iter::from_fn(|| self.try_recv())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::rc::Rc;
+    use std::thread;
+    use std::vec::Vec;
+
+    #[test]
+    fn send_recv() {
+        let channel = Channel::<u32, 4>::new();
+        let (mut producer, mut consumer) = channel.split().unwrap();
+        assert!(channel.split().is_none());
+
+        assert_eq!(consumer.try_recv(), None);
+        for i in 0..4 {
+            producer.try_send(i).unwrap();
+        }
+        assert!(producer.is_full());
+        assert_eq!(producer.try_send(4), Err(4));
+        assert_eq!(channel.len(), 4);
+
+        assert_eq!(consumer.try_recv(), Some(0));
+        producer.try_send(4).unwrap();
+        assert_eq!(consumer.try_iter().collect::<Vec<_>>(), [1, 2, 3, 4]);
+        assert!(channel.is_empty());
+    }
+
+    #[test]
+    fn drop_values() {
+        let value = Rc::new(());
+        {
+            let channel = Channel::<_, 4>::new();
+            let (mut producer, mut consumer) = channel.split().unwrap();
+            for _ in 0..3 {
+                producer.try_send(Rc::clone(&value)).unwrap();
+            }
+            consumer.try_recv();
+            assert_eq!(Rc::strong_count(&value), 3);
+        }
+        assert_eq!(Rc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn threads() {
+        static CHANNEL: Channel<usize, 8> = Channel::new();
+        let (mut producer, mut consumer) = CHANNEL.split().unwrap();
+        let sender = thread::spawn(move || {
+            for i in 0..1_000 {
+                let mut value = i;
+                while let Err(v) = producer.try_send(value) {
+                    value = v;
+                    thread::yield_now();
+                }
+            }
+        });
+        for i in 0..1_000 {
+            assert_eq!(consumer.recv(), Ok(i));
+        }
+        sender.join().unwrap();
+    }
+}
@@ -0,0 +1,461 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Synchronization primitives usable on application processors.
+//!
+//! Code running on an application processor (AP) started through
+//! [`MpServices`] must not call boot services: they are not reentrant and
+//! may only be used from the bootstrap processor (BSP). This rules out
+//! anything built on events or on [`boot::raise_tpl`], so the primitives in
+//! this module are implemented purely with atomics and busy waiting:
+//!
+//! * [`SpinLock`]: a mutual exclusion lock.
+//! * [`OnceCell`]: a cell that is initialized at most once.
+//! * [`Barrier`]: lets a fixed number of processors wait for each other.
+//! * [`Channel`]: a lock-free queue for passing values from event
+//!   notification functions to the main loop.
+//!
+//! [`MpServices::run_on_aps`] and [`MpServices::run_on_ap`] run a closure
+//! on APs. The closure receives an [`ApContext`] to mark the code as running
+//! on an AP, and must be [`Sync`], which keeps it from capturing state that
+//! is only safe to use from the BSP, such as a [`ScopedProtocol`].
+//!
+//! ```no_run
+//! use uefi::boot;
+//! use uefi::proto::pi::mp::MpServices;
+//! use uefi::sync::{ApContext, SpinLock};
+//!
+//! # fn main() -> uefi::Result {
+//! let handle = boot::get_handle_for_protocol::<MpServices>()?;
+//! let mp = boot::open_protocol_exclusive::<MpServices>(handle)?;
+//!
+//! let visited = SpinLock::new(0);
+//! mp.run_on_aps(false, &|_: &ApContext| *visited.lock() += 1, None)?;
+//! log::info!("{} APs ran the closure", visited.into_inner());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`MpServices`]: crate::proto::pi::mp::MpServices
+//! [`MpServices::run_on_aps`]: crate::proto::pi::mp::MpServices::run_on_aps
+//! [`MpServices::run_on_ap`]: crate::proto::pi::mp::MpServices::run_on_ap
+//! [`ScopedProtocol`]: crate::boot::ScopedProtocol
+//! [`boot::raise_tpl`]: crate::boot::raise_tpl
+
+mod channel;
+
+pub use channel::{Channel, Consumer, Producer};
+
+use core::cell::UnsafeCell;
+use core::fmt::{self, Debug, Formatter};
+use core::hint;
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+
+/// Marker for code running on an application processor.
+///
+/// An `ApContext` is passed to closures run by
+/// [`MpServices::run_on_aps`] and [`MpServices::run_on_ap`]. Functions meant
+/// to be called from APs can take it as an argument to document that they
+/// don't use boot services. This is only a convention: the marker doesn't
+/// prevent calling boot services, and nothing checks that it is passed.
+///
+/// The marker is neither [`Send`] nor [`Sync`], so it can't escape the
+/// processor it was created on.
+///
+/// [`MpServices::run_on_aps`]: crate::proto::pi::mp::MpServices::run_on_aps
+/// [`MpServices::run_on_ap`]: crate::proto::pi::mp::MpServices::run_on_ap
+#[derive(Debug)]
+pub struct ApContext {
+    _not_send: PhantomData<*const ()>,
+}
+
+impl ApContext {
+    /// Creates the marker.
+    ///
+    /// # Safety
+    ///
+    /// The caller must be running on an application processor, or otherwise
+    /// uphold that code receiving the marker may not call boot services.
+    #[must_use]
+    pub const unsafe fn new() -> Self {
+        Self {
+            _not_send: PhantomData,
+        }
+    }
+}
+
+/// A mutual exclusion lock that spins while waiting.
+///
+/// Unlike locks built on TPLs, the lock can be used from application
+/// processors. It isn't reentrant: locking it again on the same processor
+/// while holding the guard deadlocks.
+pub struct SpinLock<T: ?Sized> {
+    locked: AtomicBool,
+    value: UnsafeCell<T>,
+}
+
+// SAFETY: the lock only hands out access to the value to one processor at
+// a time.
+unsafe impl<T: ?Sized + Send> Send for SpinLock<T> {}
+// SAFETY: see above.
+unsafe impl<T: ?Sized + Send> Sync for SpinLock<T> {}
+
+impl<T> SpinLock<T> {
+    /// Creates an unlocked lock holding `value`.
+    pub const fn new(value: T) -> Self {
+        Self {
+            locked: AtomicBool::new(false),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    /// Consumes the lock, returning the value.
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+impl<T: ?Sized> SpinLock<T> {
+    /// Acquires the lock, spinning until it is available.
+    pub fn lock(&self) -> SpinLockGuard<'_, T> {
+        loop {
+            if let Some(guard) = self.try_lock() {
+                return guard;
+            }
+            while self.locked.load(Ordering::Relaxed) {
+                hint::spin_loop();
+            }
+        }
+    }
+
+    /// Acquires the lock if it is available.
+    pub fn try_lock(&self) -> Option<SpinLockGuard<'_, T>> {
+        self.locked
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .ok()
+            .map(|_| SpinLockGuard { lock: self })
+    }
+
+    /// Returns whether the lock is currently held.
+    pub fn is_locked(&self) -> bool {
+        self.locked.load(Ordering::Relaxed)
+    }
+
+    /// Returns a mutable reference to the value. No locking is needed since
+    /// the lock is borrowed mutably.
+    pub const fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+}
+
+impl<T: Default> Default for SpinLock<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: ?Sized + Debug> Debug for SpinLock<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("SpinLock");
+        match self.try_lock() {
+            Some(guard) => d.field("value", &&*guard),
+            None => d.field("value", &format_args!("<locked>")),
+        };
+        d.finish()
+    }
+}
+
+/// Guard returned by [`SpinLock::lock`], releasing the lock when dropped.
+#[must_use = "the lock is released immediately if the guard is unused"]
+pub struct SpinLockGuard<'a, T: ?Sized> {
+    lock: &'a SpinLock<T>,
+}
+
+impl<T: ?Sized> Deref for SpinLockGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: the guard holds the lock.
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for SpinLockGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: the guard holds the lock.
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for SpinLockGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.locked.store(false, Ordering::Release);
+    }
+}
+
+impl<T: ?Sized + Debug> Debug for SpinLockGuard<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&**self, f)
+    }
+}
+
+const UNINIT: u8 = 0;
+const RUNNING: u8 = 1;
+const INIT: u8 = 2;
+
+/// A cell that is initialized at most once.
+///
+/// If several processors try to initialize the cell at the same time, one
+/// of them runs its initializer while the others spin until it is done. If
+/// the initializer panics, the cell is left uninitialized, and a waiting
+/// processor runs its own initializer instead.
+pub struct OnceCell<T> {
+    state: AtomicU8,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+// SAFETY: the value is only written once, before `state` is set to `INIT`
+// with release ordering, and only read after observing `INIT`.
+unsafe impl<T: Send> Send for OnceCell<T> {}
+// SAFETY: see above. `T: Send` is needed because any processor may
+// initialize the value, `T: Sync` because all processors share it.
+unsafe impl<T: Send + Sync> Sync for OnceCell<T> {}
+
+impl<T> OnceCell<T> {
+    /// Creates an uninitialized cell.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicU8::new(UNINIT),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// Returns the value, or `None` if the cell is not initialized yet.
+    pub fn get(&self) -> Option<&T> {
+        if self.state.load(Ordering::Acquire) == INIT {
+            // SAFETY: the value was initialized.
+            Some(unsafe { (*self.value.get()).assume_init_ref() })
+        } else {
+            None
+        }
+    }
+
+    /// Initializes the cell with `value`. If the cell was already
+    /// initialized, or is being initialized, `value` is returned as error.
+    pub fn set(&self, value: T) -> Result<(), T> {
+        let mut value = Some(value);
+        self.try_init(&mut Some(|| value.take().unwrap()));
+        value.map_or(Ok(()), Err)
+    }
+
+    /// Returns the value, initializing the cell with `f` if needed.
+    ///
+    /// If another processor is initializing the cell, this spins until it is
+    /// done, or runs `f` if the other initializer panicked.
+    pub fn get_or_init(&self, f: impl FnOnce() -> T) -> &T {
+        let mut f = Some(f);
+        loop {
+            self.try_init(&mut f);
+            if let Some(value) = self.get() {
+                return value;
+            }
+            hint::spin_loop();
+        }
+    }
+
+    /// Consumes the cell, returning the value if it was initialized.
+    pub fn into_inner(self) -> Option<T> {
+        let mut this = core::mem::ManuallyDrop::new(self);
+        if *this.state.get_mut() == INIT {
+            // SAFETY: the value was initialized, and is not dropped again
+            // since `this` is not dropped.
+            Some(unsafe { this.value.get_mut().assume_init_read() })
+        } else {
+            None
+        }
+    }
+
+    /// Takes and runs `f` and stores its result if the cell is
+    /// uninitialized.
+    fn try_init(&self, f: &mut Option<impl FnOnce() -> T>) {
+        if f.is_none()
+            || self
+                .state
+                .compare_exchange(UNINIT, RUNNING, Ordering::Acquire, Ordering::Acquire)
+                .is_err()
+        {
+            return;
+        }
+        /// Resets the state if the initializer panics.
+        struct Reset<'a>(&'a AtomicU8);
+        impl Drop for Reset<'_> {
+            fn drop(&mut self) {
+                self.0.store(UNINIT, Ordering::Release);
+            }
+        }
+        let reset = Reset(&self.state);
+        let value = f.take().unwrap()();
+        core::mem::forget(reset);
+        // SAFETY: the `RUNNING` state grants exclusive access.
+        unsafe { (*self.value.get()).write(value) };
+        self.state.store(INIT, Ordering::Release);
+    }
+}
+
+impl<T> Default for OnceCell<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for OnceCell<T> {
+    fn drop(&mut self) {
+        if *self.state.get_mut() == INIT {
+            // SAFETY: the value was initialized.
+            unsafe { self.value.get_mut().assume_init_drop() };
+        }
+    }
+}
+
+impl<T: Debug> Debug for OnceCell<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("OnceCell").field(&self.get()).finish()
+    }
+}
+
+/// Lets a fixed number of processors wait until all of them reached the
+/// same point.
+///
+/// The barrier can be reused: once all processors passed it, it is ready
+/// for the next round.
+#[derive(Debug)]
+pub struct Barrier {
+    parties: usize,
+    arrived: AtomicUsize,
+    generation: AtomicUsize,
+}
+
+impl Barrier {
+    /// Creates a barrier for `parties` processors.
+    #[must_use]
+    pub const fn new(parties: usize) -> Self {
+        Self {
+            parties,
+            arrived: AtomicUsize::new(0),
+            generation: AtomicUsize::new(0),
+        }
+    }
+
+    /// Spins until all processors called `wait`.
+    ///
+    /// Returns `true` on exactly one processor per round, the last one to
+    /// arrive.
+    pub fn wait(&self) -> bool {
+        let generation = self.generation.load(Ordering::Acquire);
+        if self.arrived.fetch_add(1, Ordering::AcqRel) + 1 >= self.parties {
+            self.arrived.store(0, Ordering::Relaxed);
+            self.generation
+                .store(generation.wrapping_add(1), Ordering::Release);
+            true
+        } else {
+            while self.generation.load(Ordering::Acquire) == generation {
+                hint::spin_loop();
+            }
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+    use std::vec::Vec;
+
+    #[test]
+    fn spin_lock() {
+        let lock = Arc::new(SpinLock::new(0));
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let lock = Arc::clone(&lock);
+                thread::spawn(move || {
+                    for _ in 0..1000 {
+                        *lock.lock() += 1;
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(*lock.lock(), 4000);
+
+        let guard = lock.lock();
+        assert!(lock.is_locked());
+        assert!(lock.try_lock().is_none());
+        drop(guard);
+        assert!(lock.try_lock().is_some());
+    }
+
+    #[test]
+    fn once_cell() {
+        let cell = OnceCell::new();
+        assert_eq!(cell.get(), None);
+        assert_eq!(*cell.get_or_init(|| 1), 1);
+        assert_eq!(*cell.get_or_init(|| 2), 1);
+        assert_eq!(cell.set(3), Err(3));
+        assert_eq!(cell.into_inner(), Some(1));
+
+        let cell = Arc::new(OnceCell::new());
+        let threads: Vec<_> = (0..4)
+            .map(|i| {
+                let cell = Arc::clone(&cell);
+                thread::spawn(move || *cell.get_or_init(|| i))
+            })
+            .collect();
+        let values: Vec<_> = threads.into_iter().map(|t| t.join().unwrap()).collect();
+        assert!(values.iter().all(|&v| v == values[0]));
+    }
+
+    #[test]
+    fn once_cell_panic() {
+        let cell = OnceCell::new();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            cell.get_or_init(|| panic!("init failed"));
+        }));
+        assert!(result.is_err());
+        assert_eq!(cell.get(), None);
+        assert_eq!(*cell.get_or_init(|| 1), 1);
+    }
+
+    #[test]
+    fn barrier() {
+        let barrier = Arc::new(Barrier::new(4));
+        let count = Arc::new(AtomicUsize::new(0));
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let barrier = Arc::clone(&barrier);
+                let count = Arc::clone(&count);
+                thread::spawn(move || {
+                    let mut leaders = 0;
+                    for round in 1..=3 {
+                        count.fetch_add(1, Ordering::SeqCst);
+                        leaders += usize::from(barrier.wait());
+                        // All threads of this round arrived.
+                        assert!(count.load(Ordering::SeqCst) >= round * 4);
+                        barrier.wait();
+                    }
+                    leaders
+                })
+            })
+            .collect();
+        let leaders: usize = threads.into_iter().map(|t| t.join().unwrap()).sum();
+        assert_eq!(leaders, 3);
+    }
+}
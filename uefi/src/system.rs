@@ -12,8 +12,18 @@
 use crate::proto::console::text::{Input, Output};
 use crate::table::cfg::ConfigTableEntry;
 use crate::table::{self, Revision};
-use crate::{CStr16, Char16};
-use core::slice;
+use crate::{CStr16, Char16, Handle, boot, cstr16};
+use core::ffi::c_void;
+use core::{mem, slice};
+use uefi_raw::table::Header;
+#[cfg(feature = "alloc")]
+use {
+    crate::boot::{OpenProtocolAttributes, OpenProtocolParams},
+    crate::proto::device_path::DevicePath,
+    crate::runtime::{self, VariableAttributes, VariableVendor},
+    alloc::boxed::Box,
+    uefi_raw::protocol::console::SimpleTextOutputProtocol,
+};
 
 /// Get the firmware vendor string.
 #[must_use]
@@ -165,6 +175,303 @@ where
     f(stderr)
 }
 
+/// One of the standard consoles of the system table.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Console {
+    /// The console input device, `ConIn`.
+    Stdin,
+    /// The console output device, `ConOut`.
+    Stdout,
+    /// The standard error device, `StdErr`.
+    Stderr,
+}
+
+impl Console {
+    /// Returns the name of the global variable holding the device path of
+    /// the console's device: `ConIn`, `ConOut`, or `ErrOut`.
+    #[must_use]
+    pub const fn variable_name(self) -> &'static CStr16 {
+        match self {
+            Self::Stdin => cstr16!("ConIn"),
+            Self::Stdout => cstr16!("ConOut"),
+            Self::Stderr => cstr16!("ErrOut"),
+        }
+    }
+}
+
+/// Replaces the handle and protocol of `console` in the system table and
+/// returns the previous values. The CRC of the system table is updated.
+///
+/// # Safety
+///
+/// `protocol` must point to a valid protocol of the console's type until it
+/// is replaced again, and no references to the previous protocol obtained
+/// through the system table may be in use.
+///
+/// # Panics
+///
+/// This function will panic if called after exiting boot services.
+pub(crate) unsafe fn swap_console(
+    console: Console,
+    handle: uefi_raw::Handle,
+    protocol: *mut c_void,
+) -> (uefi_raw::Handle, *mut c_void) {
+    let st = table::system_table_raw_panicking().as_ptr();
+    // SAFETY: valid per requirements of `set_system_table`.
+    unsafe {
+        assert!(
+            !(*st).boot_services.is_null(),
+            "boot services are not active"
+        );
+        let previous = match console {
+            Console::Stdin => (
+                mem::replace(&mut (*st).stdin_handle, handle),
+                mem::replace(&mut (*st).stdin, protocol.cast()).cast(),
+            ),
+            Console::Stdout => (
+                mem::replace(&mut (*st).stdout_handle, handle),
+                mem::replace(&mut (*st).stdout, protocol.cast()).cast(),
+            ),
+            Console::Stderr => (
+                mem::replace(&mut (*st).stderr_handle, handle),
+                mem::replace(&mut (*st).stderr, protocol.cast()).cast(),
+            ),
+        };
+        update_crc32(&raw mut (*st).header);
+        previous
+    }
+}
+
+/// Replaces the stdout handle and protocol in the system table and returns
+/// the previous values. See [`swap_console`].
+#[cfg(feature = "alloc")]
+pub(crate) unsafe fn swap_stdout(
+    handle: uefi_raw::Handle,
+    stdout: *mut SimpleTextOutputProtocol,
+) -> (uefi_raw::Handle, *mut SimpleTextOutputProtocol) {
+    // SAFETY: forwarded to the caller.
+    let (handle, stdout) = unsafe { swap_console(Console::Stdout, handle, stdout.cast()) };
+    (handle, stdout.cast())
+}
+
+/// Replaces stdin in the system table with `input`, installed on `handle`,
+/// until the returned guard is dropped.
+///
+/// This is meant for console interposers, e.g. to inject scripted input.
+///
+/// # Safety
+///
+/// `input` must stay valid until the guard is dropped, and the guard must
+/// not be leaked. No references obtained through [`with_stdin`] may be in use
+/// when the guard is created or dropped.
+///
+/// # Panics
+///
+/// This function will panic if called after exiting boot services.
+pub unsafe fn replace_stdin(handle: Handle, input: *mut Input) -> ConsoleGuard {
+    // SAFETY: forwarded to the caller.
+    unsafe { ConsoleGuard::new(Console::Stdin, handle, input.cast()) }
+}
+
+/// Replaces stdout in the system table with `output`, installed on
+/// `handle`, until the returned guard is dropped.
+///
+/// This is meant for console interposers, e.g. to capture or mirror output.
+///
+/// # Safety
+///
+/// `output` must stay valid until the guard is dropped, and the guard must
+/// not be leaked. No references obtained through [`with_stdout`] may be in
+/// use when the guard is created or dropped.
+///
+/// # Panics
+///
+/// This function will panic if called after exiting boot services.
+pub unsafe fn replace_stdout(handle: Handle, output: *mut Output) -> ConsoleGuard {
+    // SAFETY: forwarded to the caller.
+    unsafe { ConsoleGuard::new(Console::Stdout, handle, output.cast()) }
+}
+
+/// Replaces stderr in the system table with `output`, installed on
+/// `handle`, until the returned guard is dropped.
+///
+/// # Safety
+///
+/// `output` must stay valid until the guard is dropped, and the guard must
+/// not be leaked. No references obtained through [`with_stderr`] may be in
+/// use when the guard is created or dropped.
+///
+/// # Panics
+///
+/// This function will panic if called after exiting boot services.
+pub unsafe fn replace_stderr(handle: Handle, output: *mut Output) -> ConsoleGuard {
+    // SAFETY: forwarded to the caller.
+    unsafe { ConsoleGuard::new(Console::Stderr, handle, output.cast()) }
+}
+
+/// Guard returned by [`replace_stdin`], [`replace_stdout`], and
+/// [`replace_stderr`], restoring the previous console when dropped.
+///
+/// Guards of the same console must be dropped in the reverse order of their
+/// creation. Dropping a guard after exiting boot services does nothing.
+#[derive(Debug)]
+#[must_use]
+pub struct ConsoleGuard {
+    console: Console,
+    previous_handle: uefi_raw::Handle,
+    previous_protocol: *mut c_void,
+    /// Previous contents and attributes of the console's variable, if it was
+    /// changed with [`set_device_variable`].
+    ///
+    /// [`set_device_variable`]: Self::set_device_variable
+    #[cfg(feature = "alloc")]
+    previous_variable: Option<Option<(Box<[u8]>, VariableAttributes)>>,
+}
+
+impl ConsoleGuard {
+    /// # Safety
+    ///
+    /// See [`swap_console`].
+    unsafe fn new(console: Console, handle: Handle, protocol: *mut c_void) -> Self {
+        // SAFETY: forwarded to the caller.
+        let (previous_handle, previous_protocol) =
+            unsafe { swap_console(console, handle.as_ptr(), protocol) };
+        Self {
+            console,
+            previous_handle,
+            previous_protocol,
+            #[cfg(feature = "alloc")]
+            previous_variable: None,
+        }
+    }
+
+    /// Returns the replaced console.
+    #[must_use]
+    pub const fn console(&self) -> Console {
+        self.console
+    }
+
+    /// Returns the handle of the previous console, if any.
+    #[must_use]
+    pub fn previous_handle(&self) -> Option<Handle> {
+        // SAFETY: the handle came from the system table.
+        unsafe { Handle::from_ptr(self.previous_handle) }
+    }
+
+    /// Sets the console's global variable (see [`Console::variable_name`])
+    /// to the device path of the new console's handle. The previous value is
+    /// restored when the guard is dropped.
+    ///
+    /// Firmware reads these variables to find the console devices, and
+    /// operating system loaders may pass them on. Since the variables are
+    /// non-volatile, the change persists if the system resets before the
+    /// guard is dropped.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::UNSUPPORTED`]: the new console's handle has no device
+    ///   path.
+    /// * See [`runtime::set_variable`] for other errors.
+    ///
+    /// [`Status::UNSUPPORTED`]: crate::Status::UNSUPPORTED
+    #[cfg(feature = "alloc")]
+    pub fn set_device_variable(&mut self) -> crate::Result {
+        let name = self.console.variable_name();
+        let vendor = VariableVendor::GLOBAL_VARIABLE;
+        let handle = console_handle(self.console).ok_or(crate::Status::UNSUPPORTED)?;
+        // SAFETY: the device path is only read while the protocol is open.
+        let path = unsafe {
+            boot::open_protocol::<DevicePath>(
+                OpenProtocolParams {
+                    handle,
+                    agent: boot::image_handle(),
+                    controller: None,
+                },
+                OpenProtocolAttributes::GetProtocol,
+            )
+        }
+        .map_err(|_| crate::Status::UNSUPPORTED)?;
+
+        let previous = match runtime::get_variable_boxed(name, &vendor) {
+            Ok(previous) => Some(previous),
+            Err(err) if err.status() == crate::Status::NOT_FOUND => None,
+            Err(err) => return Err(err.into()),
+        };
+        let attributes = previous.as_ref().map_or(
+            VariableAttributes::NON_VOLATILE
+                | VariableAttributes::BOOTSERVICE_ACCESS
+                | VariableAttributes::RUNTIME_ACCESS,
+            |(_, attributes)| *attributes,
+        );
+        runtime::set_variable(name, &vendor, attributes, path.as_bytes())?;
+        if self.previous_variable.is_none() {
+            self.previous_variable = Some(previous);
+        }
+        Ok(())
+    }
+
+    /// Restores the previous console. This is equivalent to dropping the
+    /// guard.
+    pub fn restore(self) {}
+}
+
+impl Drop for ConsoleGuard {
+    fn drop(&mut self) {
+        // After exiting boot services, the console protocols and variable
+        // services of the guard can no longer be used.
+        if !boot::are_boot_services_active() {
+            return;
+        }
+
+        // SAFETY: the previous console was valid before it was replaced.
+        unsafe { swap_console(self.console, self.previous_handle, self.previous_protocol) };
+
+        #[cfg(feature = "alloc")]
+        if let Some(previous) = self.previous_variable.take() {
+            let name = self.console.variable_name();
+            let vendor = VariableVendor::GLOBAL_VARIABLE;
+            // Errors are ignored since they can't be propagated from `drop`.
+            let _ = match previous {
+                Some((data, attributes)) => runtime::set_variable(name, &vendor, attributes, &data),
+                None => runtime::delete_variable(name, &vendor),
+            };
+        }
+    }
+}
+
+/// Returns the handle of `console` in the system table.
+#[cfg(feature = "alloc")]
+fn console_handle(console: Console) -> Option<Handle> {
+    let st = table::system_table_raw_panicking();
+    // SAFETY: valid per requirements of `set_system_table`.
+    let st = unsafe { st.as_ref() };
+    let handle = match console {
+        Console::Stdin => st.stdin_handle,
+        Console::Stdout => st.stdout_handle,
+        Console::Stderr => st.stderr_handle,
+    };
+    // SAFETY: the handle came from the system table.
+    unsafe { Handle::from_ptr(handle) }
+}
+
+/// Recalculates the CRC of the table starting with `header`, e.g. the
+/// system table or the boot services table.
+///
+/// # Safety
+///
+/// `header` must point to the header of a valid table of `header.size`
+/// bytes, and boot services must be active.
+pub(crate) unsafe fn update_crc32(header: *mut Header) {
+    unsafe {
+        (*header).crc = 0;
+        let bytes = slice::from_raw_parts(header.cast::<u8>(), (*header).size as usize);
+        // Calculating a CRC only fails for invalid parameters.
+        if let Ok(crc) = boot::calculate_crc32(bytes) {
+            (*header).crc = crc;
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
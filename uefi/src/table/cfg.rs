@@ -84,6 +84,12 @@ impl ConfigTableEntry {
     /// The properties table is used to provide additional info
     /// about the UEFI implementation.
     pub const PROPERTIES_TABLE_GUID: Guid = guid!("880aaca3-4adc-4a04-9079-b747340825e5");
+
+    /// Entry pointing to the [`RtPropertiesTable`], which lists the runtime
+    /// services supported after exiting boot services.
+    ///
+    /// [`RtPropertiesTable`]: crate::runtime::RtPropertiesTable
+    pub const RT_PROPERTIES_GUID: Guid = uefi_raw::table::runtime::RtPropertiesTable::GUID;
 }
 
 /// Entry pointing to the old ACPI 1 RSDP.
@@ -0,0 +1,494 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! coreboot tables.
+//!
+//! When UEFI runs as a payload of coreboot, e.g. with EDK2's
+//! `UefiPayloadPkg`, the firmware was initialized by coreboot, which
+//! describes the platform in its own tables: the memory map, the serial
+//! console, the framebuffer set up for the payload, the coreboot version,
+//! and more. These tables are not part of the UEFI configuration table; like
+//! the payload itself, [`CorebootTable::find`] looks for them in low memory.
+//!
+//! ```no_run
+//! use uefi::table::coreboot::CorebootTable;
+//!
+//! // SAFETY: the low memory is mapped, see `CorebootTable::find`.
+//! if let Some(table) = unsafe { CorebootTable::find() } {
+//!     log::info!("coreboot {}", table.version().unwrap_or("unknown"));
+//!     if let Some(serial) = table.serial() {
+//!         log::info!("serial port at {:#x}, {} baud", serial.base, serial.baud);
+//!     }
+//! }
+//! ```
+//!
+//! See <https://github.com/coreboot/coreboot/blob/main/src/commonlib/include/commonlib/coreboot_tables.h>.
+
+use core::fmt::{self, Debug, Formatter};
+use core::slice;
+use uefi_raw::newtype_enum;
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        bytes.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(
+        bytes.get(offset..offset + 8)?.try_into().ok()?,
+    ))
+}
+
+/// Computes the 16-bit one's complement checksum used by coreboot, the
+/// same as the IP header checksum. Data including its valid checksum sums
+/// to 0.
+fn ip_checksum(bytes: &[u8]) -> u16 {
+    let mut sum = 0u32;
+    for (i, byte) in bytes.iter().enumerate() {
+        let value = u32::from(*byte) << (8 * (i & 1));
+        sum += value;
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+newtype_enum! {
+    /// Tag of a coreboot table [`Record`].
+    pub enum RecordTag: u32 => {
+        /// Unused record.
+        UNUSED = 0x00,
+        /// Memory map, see [`CorebootTable::memory`].
+        MEMORY = 0x01,
+        /// Mainboard vendor and part number.
+        MAINBOARD = 0x03,
+        /// coreboot version string.
+        VERSION = 0x04,
+        /// Extra version string.
+        EXTRA_VERSION = 0x05,
+        /// Build string.
+        BUILD = 0x06,
+        /// Compile time string.
+        COMPILE_TIME = 0x07,
+        /// Serial console, see [`CorebootTable::serial`].
+        SERIAL = 0x0f,
+        /// Console types.
+        CONSOLE = 0x10,
+        /// Pointer to the actual table, see [`CorebootTable::forward`].
+        FORWARD = 0x11,
+        /// Framebuffer, see [`CorebootTable::framebuffer`].
+        FRAMEBUFFER = 0x12,
+        /// Address of the CBMEM console buffer.
+        CBMEM_CONSOLE = 0x17,
+        /// Address of the ACPI RSDP.
+        ACPI_RSDP = 0x43,
+    }
+}
+
+/// A record of a [`CorebootTable`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Record<'a> {
+    /// Tag of the record.
+    pub tag: RecordTag,
+    /// Data of the record, following the tag and size.
+    pub data: &'a [u8],
+}
+
+/// Iterator over the [`Record`]s of a [`CorebootTable`]. Stops at the first
+/// truncated record.
+#[derive(Clone, Debug)]
+pub struct Records<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Iterator for Records<'a> {
+    type Item = Record<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let tag = read_u32(self.bytes, 0)?;
+        let size = read_u32(self.bytes, 4)? as usize;
+        let Some(record) = self.bytes.get(..size).filter(|_| size >= 8) else {
+            self.bytes = &[];
+            return None;
+        };
+        self.bytes = &self.bytes[size..];
+        Some(Record {
+            tag: RecordTag(tag),
+            data: &record[8..],
+        })
+    }
+}
+
+newtype_enum! {
+    /// Type of a [`MemoryRange`].
+    pub enum MemoryRangeType: u32 => {
+        /// Usable RAM.
+        RAM = 1,
+        /// Reserved memory.
+        RESERVED = 2,
+        /// ACPI tables, reclaimable after they have been read.
+        ACPI = 3,
+        /// ACPI non-volatile storage.
+        NVS = 4,
+        /// Memory with errors.
+        UNUSABLE = 5,
+        /// Memory reserved by the vendor.
+        VENDOR_RESERVED = 6,
+        /// coreboot tables.
+        TABLE = 16,
+    }
+}
+
+/// A range of the coreboot memory map.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoryRange {
+    /// Physical start address.
+    pub start: u64,
+    /// Size in bytes.
+    pub size: u64,
+    /// Type of the memory.
+    pub ty: MemoryRangeType,
+}
+
+newtype_enum! {
+    /// Type of a [`SerialPort`].
+    pub enum SerialPortType: u32 => {
+        /// Registers in I/O port space.
+        IO_MAPPED = 1,
+        /// Registers in memory space.
+        MEMORY_MAPPED = 2,
+    }
+}
+
+/// The serial console used by coreboot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SerialPort {
+    /// Type of the registers.
+    pub ty: SerialPortType,
+    /// I/O port or physical address of the registers.
+    pub base: u32,
+    /// Baud rate.
+    pub baud: u32,
+    /// Distance between registers in bytes.
+    pub register_width: u32,
+    /// Frequency of the UART input clock in Hz.
+    pub input_hertz: u32,
+}
+
+/// Position and size in bits of a color component of a [`Framebuffer`]
+/// pixel.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ColorMask {
+    /// Position of the least significant bit.
+    pub position: u8,
+    /// Number of bits.
+    pub size: u8,
+}
+
+/// The linear framebuffer set up by coreboot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Framebuffer {
+    /// Physical address of the framebuffer.
+    pub address: u64,
+    /// Width in pixels.
+    pub width: u32,
+    /// Height in pixels.
+    pub height: u32,
+    /// Bytes per line.
+    pub stride: u32,
+    /// Bits per pixel.
+    pub bits_per_pixel: u8,
+    /// Red component.
+    pub red: ColorMask,
+    /// Green component.
+    pub green: ColorMask,
+    /// Blue component.
+    pub blue: ColorMask,
+    /// Reserved bits.
+    pub reserved: ColorMask,
+}
+
+/// A coreboot table, starting with an `LBIO` header.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct CorebootTable<'a> {
+    bytes: &'a [u8],
+    header_len: usize,
+}
+
+impl<'a> CorebootTable<'a> {
+    /// Signature of the table header.
+    pub const SIGNATURE: [u8; 4] = *b"LBIO";
+
+    const HEADER_SIZE: usize = 24;
+
+    /// Memory ranges searched by [`Self::find`].
+    const SEARCH_RANGES: [(usize, usize); 2] = [(0x10, 0x1000), (0xf_0000, 0x10_0000)];
+
+    /// Parses a table, including its header. Returns `None` if the
+    /// signature, a length or a checksum is invalid.
+    #[must_use]
+    pub fn parse(bytes: &'a [u8]) -> Option<Self> {
+        if bytes.get(..4)? != Self::SIGNATURE {
+            return None;
+        }
+        let header_len = read_u32(bytes, 4)? as usize;
+        let header = bytes
+            .get(..header_len)
+            .filter(|_| header_len >= Self::HEADER_SIZE)?;
+        if ip_checksum(header) != 0 {
+            return None;
+        }
+        let table_len = read_u32(header, 12)? as usize;
+        let bytes = bytes.get(..header_len.checked_add(table_len)?)?;
+        if u32::from(ip_checksum(&bytes[header_len..])) != read_u32(header, 16)? {
+            return None;
+        }
+        Some(Self { bytes, header_len })
+    }
+
+    /// Reads the table at the physical `address`, without following a
+    /// [forward record](Self::forward).
+    ///
+    /// # Safety
+    ///
+    /// The header at `address` and, if it has a valid signature, the table
+    /// it describes must be mapped and remain unchanged.
+    #[must_use]
+    pub unsafe fn from_address(address: usize) -> Option<CorebootTable<'static>> {
+        let header = unsafe { slice::from_raw_parts(address as *const u8, Self::HEADER_SIZE) };
+        if header[..4] != Self::SIGNATURE {
+            return None;
+        }
+        let len = read_u32(header, 4)?.checked_add(read_u32(header, 12)?)?;
+        CorebootTable::parse(unsafe { slice::from_raw_parts(address as *const u8, len as usize) })
+    }
+
+    /// Searches the coreboot table in low memory, where coreboot places it
+    /// on x86, and follows its [forward record](Self::forward). Returns
+    /// `None` if the firmware is not running on coreboot.
+    ///
+    /// # Safety
+    ///
+    /// The ranges `0x0..0x1000` and `0xf0000..0x100000` must be mapped. This
+    /// is the case on x86, unless the firmware unmapped the first page to
+    /// detect null pointer accesses.
+    #[must_use]
+    pub unsafe fn find() -> Option<CorebootTable<'static>> {
+        if !cfg!(any(target_arch = "x86", target_arch = "x86_64")) {
+            return None;
+        }
+        let table = Self::SEARCH_RANGES.iter().find_map(|&(start, end)| {
+            (start..end)
+                .step_by(16)
+                .find_map(|address| unsafe { Self::from_address(address) })
+        })?;
+        match table.forward() {
+            Some(forward) => unsafe { Self::from_address(usize::try_from(forward).ok()?) },
+            None => Some(table),
+        }
+    }
+
+    /// Returns the raw table, including its header.
+    #[must_use]
+    pub const fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Returns the records of the table.
+    #[must_use]
+    pub fn records(&self) -> Records<'a> {
+        Records {
+            bytes: &self.bytes[self.header_len..],
+        }
+    }
+
+    /// Returns the first record with `tag`.
+    #[must_use]
+    pub fn record(&self, tag: RecordTag) -> Option<Record<'a>> {
+        self.records().find(|record| record.tag == tag)
+    }
+
+    /// Returns the string of the record with `tag`, e.g.
+    /// [`RecordTag::BUILD`].
+    #[must_use]
+    pub fn string(&self, tag: RecordTag) -> Option<&'a str> {
+        let data = self.record(tag)?.data;
+        let len = data.iter().position(|b| *b == 0).unwrap_or(data.len());
+        core::str::from_utf8(&data[..len]).ok()
+    }
+
+    /// Returns the coreboot version.
+    #[must_use]
+    pub fn version(&self) -> Option<&'a str> {
+        self.string(RecordTag::VERSION)
+    }
+
+    /// Returns the physical address of the actual table, if this is the
+    /// small table in low memory pointing to it.
+    #[must_use]
+    pub fn forward(&self) -> Option<u64> {
+        read_u64(self.record(RecordTag::FORWARD)?.data, 0)
+    }
+
+    /// Returns the memory map.
+    pub fn memory(&self) -> impl Iterator<Item = MemoryRange> + use<'a> {
+        let data = self.record(RecordTag::MEMORY).map_or(&[][..], |r| r.data);
+        data.chunks_exact(20).map(|range| MemoryRange {
+            start: read_u64(range, 0).unwrap_or(0),
+            size: read_u64(range, 8).unwrap_or(0),
+            ty: MemoryRangeType(read_u32(range, 16).unwrap_or(0)),
+        })
+    }
+
+    /// Returns the serial console.
+    #[must_use]
+    pub fn serial(&self) -> Option<SerialPort> {
+        let data = self.record(RecordTag::SERIAL)?.data;
+        Some(SerialPort {
+            ty: SerialPortType(read_u32(data, 0)?),
+            base: read_u32(data, 4)?,
+            baud: read_u32(data, 8)?,
+            register_width: read_u32(data, 12)?,
+            input_hertz: read_u32(data, 16)?,
+        })
+    }
+
+    /// Returns the framebuffer.
+    #[must_use]
+    pub fn framebuffer(&self) -> Option<Framebuffer> {
+        let data = self.record(RecordTag::FRAMEBUFFER)?.data;
+        let masks = data.get(21..29)?;
+        let mask = |i: usize| ColorMask {
+            position: masks[2 * i],
+            size: masks[2 * i + 1],
+        };
+        Some(Framebuffer {
+            address: read_u64(data, 0)?,
+            width: read_u32(data, 8)?,
+            height: read_u32(data, 12)?,
+            stride: read_u32(data, 16)?,
+            bits_per_pixel: data[20],
+            red: mask(0),
+            green: mask(1),
+            blue: mask(2),
+            reserved: mask(3),
+        })
+    }
+}
+
+impl Debug for CorebootTable<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CorebootTable")
+            .field("version", &self.version())
+            .field("records", &self.records().count())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes `record` with `tag` at `offset` of `buf`, returning the
+    /// offset after it.
+    fn record(buf: &mut [u8], offset: usize, tag: u32, data: &[u8]) -> usize {
+        let size = (8 + data.len()).next_multiple_of(4);
+        buf[offset..offset + 4].copy_from_slice(&tag.to_le_bytes());
+        buf[offset + 4..offset + 8].copy_from_slice(&(size as u32).to_le_bytes());
+        buf[offset + 8..offset + 8 + data.len()].copy_from_slice(data);
+        offset + size
+    }
+
+    fn table(buf: &mut [u8]) -> usize {
+        let mut memory = [0; 40];
+        memory[8..16].copy_from_slice(&0x9_f000u64.to_le_bytes());
+        memory[16] = 1;
+        memory[20..28].copy_from_slice(&0xf_0000u64.to_le_bytes());
+        memory[28..36].copy_from_slice(&0x1_0000u64.to_le_bytes());
+        memory[36] = 2;
+        let mut serial = [0; 20];
+        serial[0] = 1;
+        serial[4..8].copy_from_slice(&0x3f8u32.to_le_bytes());
+        serial[8..12].copy_from_slice(&115_200u32.to_le_bytes());
+        serial[12] = 1;
+        serial[16..20].copy_from_slice(&1_843_200u32.to_le_bytes());
+        let mut framebuffer = [0; 29];
+        framebuffer[..8].copy_from_slice(&0xc000_0000u64.to_le_bytes());
+        framebuffer[8..10].copy_from_slice(&1024u16.to_le_bytes());
+        framebuffer[12..14].copy_from_slice(&768u16.to_le_bytes());
+        framebuffer[16..18].copy_from_slice(&4096u16.to_le_bytes());
+        framebuffer[20..29].copy_from_slice(&[32, 16, 8, 8, 8, 0, 8, 24, 8]);
+
+        let mut end = record(buf, 24, 0x01, &memory);
+        end = record(buf, end, 0x0f, &serial);
+        end = record(buf, end, 0x12, &framebuffer);
+        end = record(buf, end, 0x04, b"4.22\0");
+
+        let table_checksum = ip_checksum(&buf[24..end]);
+        buf[..4].copy_from_slice(b"LBIO");
+        buf[4] = 24;
+        buf[12..16].copy_from_slice(&((end - 24) as u32).to_le_bytes());
+        buf[16..18].copy_from_slice(&table_checksum.to_le_bytes());
+        buf[20] = 4;
+        let header_checksum = ip_checksum(&buf[..24]);
+        buf[8..10].copy_from_slice(&header_checksum.to_le_bytes());
+        end
+    }
+
+    #[test]
+    fn test_coreboot_table() {
+        let mut buf = [0; 160];
+        let len = table(&mut buf);
+        let table = CorebootTable::parse(&buf).unwrap();
+        assert_eq!(table.bytes().len(), len);
+        assert_eq!(table.records().count(), 4);
+        assert_eq!(table.version(), Some("4.22"));
+        assert_eq!(table.forward(), None);
+
+        let mut memory = table.memory();
+        assert_eq!(
+            memory.next(),
+            Some(MemoryRange {
+                start: 0,
+                size: 0x9_f000,
+                ty: MemoryRangeType::RAM
+            })
+        );
+        assert_eq!(memory.next().unwrap().ty, MemoryRangeType::RESERVED);
+        assert_eq!(memory.next(), None);
+
+        assert_eq!(
+            table.serial(),
+            Some(SerialPort {
+                ty: SerialPortType::IO_MAPPED,
+                base: 0x3f8,
+                baud: 115_200,
+                register_width: 1,
+                input_hertz: 1_843_200,
+            })
+        );
+
+        let framebuffer = table.framebuffer().unwrap();
+        assert_eq!(
+            (framebuffer.width, framebuffer.height, framebuffer.stride),
+            (1024, 768, 4096)
+        );
+        assert_eq!(framebuffer.bits_per_pixel, 32);
+        assert_eq!(
+            framebuffer.red,
+            ColorMask {
+                position: 16,
+                size: 8
+            }
+        );
+        assert_eq!(framebuffer.reserved.position, 24);
+    }
+
+    #[test]
+    fn test_invalid_table() {
+        let mut buf = [0; 160];
+        table(&mut buf);
+        buf[30] ^= 1;
+        assert_eq!(CorebootTable::parse(&buf), None);
+        assert_eq!(CorebootTable::parse(&buf[..20]), None);
+    }
+}
@@ -0,0 +1,488 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Interposing boot services.
+//!
+//! Research and diagnostic tools often need to observe or filter calls that
+//! other images make to boot services, e.g. to log every image that gets
+//! loaded, or to run code right before the OS loader exits boot services.
+//! This is done by replacing function pointers in the boot services table,
+//! and recalculating the table's CRC so that consumers validating it don't
+//! reject it.
+//!
+//! [`Hook`] replaces any function pointer of the table with an
+//! `extern "efiapi"` function, and restores the original one when dropped.
+//! [`hook_load_image`] and [`hook_exit_boot_services`] install ready-made
+//! trampolines calling plain Rust callbacks before forwarding to the
+//! original service.
+//!
+//! ```no_run
+//! use uefi::table::hook;
+//! use uefi::{Handle, Status};
+//!
+//! fn on_exit_boot_services(image: Handle) -> Status {
+//!     log::info!("{image:?} is exiting boot services");
+//!     Status::SUCCESS
+//! }
+//!
+//! # fn main() -> uefi::Result {
+//! // SAFETY: the callback doesn't use boot services beyond what is allowed
+//! // at this point.
+//! let hook = unsafe { hook::hook_exit_boot_services(on_exit_boot_services)? };
+//! // Start the OS loader...
+//! // Dropping the hook removes it again.
+//! drop(hook);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! All of this is inherently unsafe: other images may have cached the
+//! original function pointers, or may hook the same services themselves.
+//! Hooks must be removed in the reverse order of their installation.
+
+use super::system_table_raw_panicking;
+use crate::boot::{self, Tpl};
+use crate::proto::device_path::{DevicePath, FfiDevicePath};
+use crate::sync::SpinLock;
+use crate::{Handle, Result, Status, system};
+use core::fmt::{self, Debug, Formatter};
+use core::slice;
+use uefi_raw::protocol::device_path::DevicePathProtocol;
+use uefi_raw::table::boot::BootServices;
+use uefi_raw::{Boolean, Handle as RawHandle};
+
+/// Returns the boot services table.
+///
+/// # Panics
+///
+/// Panics if boot services are not active.
+fn boot_services() -> *mut BootServices {
+    let st = system_table_raw_panicking();
+    // SAFETY: valid per requirements of `set_system_table`.
+    let bs = unsafe { st.as_ref() }.boot_services;
+    assert!(!bs.is_null(), "boot services are not active");
+    bs
+}
+
+/// A replaced function pointer of the boot services table, restored when
+/// dropped.
+///
+/// Dropping the hook after exiting boot services does nothing, as the table
+/// is no longer used.
+///
+/// `F` is the type of the function pointer, e.g.
+/// `unsafe extern "efiapi" fn(Handle, usize) -> Status` for
+/// `exit_boot_services`.
+pub struct Hook<F: Copy + PartialEq + 'static> {
+    field: fn(&mut BootServices) -> &mut F,
+    original: F,
+    replacement: F,
+}
+
+impl<F: Copy + PartialEq + 'static> Hook<F> {
+    /// Replaces the function pointer returned by `field` with
+    /// `replacement`, and updates the table's CRC.
+    ///
+    /// The table is modified at [`Tpl::HIGH_LEVEL`], so that notification
+    /// functions never see it half-updated.
+    ///
+    /// # Safety
+    ///
+    /// `replacement` must have the exact semantics the firmware's callers
+    /// expect from the service, usually by forwarding to [`original`]. The
+    /// hook must not be leaked if `replacement` depends on state that doesn't
+    /// outlive it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if boot services are not active.
+    ///
+    /// [`original`]: Self::original
+    pub unsafe fn install(field: fn(&mut BootServices) -> &mut F, replacement: F) -> Self {
+        let original = unsafe { Self::swap(field, replacement) };
+        Self {
+            field,
+            original,
+            replacement,
+        }
+    }
+
+    /// Returns the function pointer that was replaced.
+    #[must_use]
+    pub const fn original(&self) -> F {
+        self.original
+    }
+
+    /// Returns whether the hook is still the one installed in the table, i.e.
+    /// it was not hooked again on top of it. Returns `false` after exiting
+    /// boot services.
+    #[must_use]
+    pub fn is_active(&self) -> bool {
+        // SAFETY: the table is only read, while boot services are active.
+        boot::are_boot_services_active()
+            && *(self.field)(unsafe { &mut *boot_services() }) == self.replacement
+    }
+
+    /// Restores the original function pointer. This is equivalent to
+    /// dropping the hook.
+    pub fn unhook(self) {}
+
+    /// Swaps the function pointer, returning the previous one.
+    unsafe fn swap(field: fn(&mut BootServices) -> &mut F, value: F) -> F {
+        let bs = boot_services();
+        // SAFETY: the TPL is restored when the guard is dropped.
+        let _tpl = unsafe { boot::raise_tpl(Tpl::HIGH_LEVEL) };
+        // SAFETY: the table is valid while boot services are active.
+        unsafe {
+            let previous = core::mem::replace(field(&mut *bs), value);
+            system::update_crc32(&raw mut (*bs).header);
+            previous
+        }
+    }
+}
+
+impl<F: Copy + PartialEq + 'static> Drop for Hook<F> {
+    fn drop(&mut self) {
+        if !boot::are_boot_services_active() {
+            return;
+        }
+        // If another hook was installed on top of this one, restoring the
+        // original would remove it as well, so leave the table alone.
+        if self.is_active() {
+            // SAFETY: the original function pointer was valid before.
+            unsafe { Self::swap(self.field, self.original) };
+        } else {
+            log::warn!("boot services hook was replaced, not removing it");
+        }
+    }
+}
+
+impl<F: Copy + PartialEq + 'static> Debug for Hook<F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Hook")
+            .field("active", &self.is_active())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Type of the `load_image` boot service.
+pub type LoadImageFn = unsafe extern "efiapi" fn(
+    Boolean,
+    RawHandle,
+    *const DevicePathProtocol,
+    *const u8,
+    usize,
+    *mut RawHandle,
+) -> Status;
+
+/// Type of the `exit_boot_services` boot service.
+pub type ExitBootServicesFn = unsafe extern "efiapi" fn(RawHandle, usize) -> Status;
+
+/// Arguments of a `LoadImage` call, passed to the callback of
+/// [`hook_load_image`].
+#[derive(Debug)]
+pub struct LoadImageCall<'a> {
+    /// Whether the image is loaded as part of the boot selection.
+    pub boot_policy: bool,
+    /// Image loading the new image.
+    pub parent_image_handle: Option<Handle>,
+    /// Device path of the image, if any.
+    pub device_path: Option<&'a DevicePath>,
+    /// Contents of the image, if loaded from memory.
+    pub source_buffer: Option<&'a [u8]>,
+}
+
+/// State of the trampoline of [`hook_load_image`] or
+/// [`hook_exit_boot_services`].
+#[derive(Debug)]
+struct Trampoline<C, F> {
+    /// Callback of the installed [`CallbackHook`], if any.
+    callback: Option<C>,
+    /// Service the trampoline forwards to, if the trampoline is in the
+    /// table.
+    original: Option<F>,
+}
+
+impl<C: Copy, F: Copy> Trampoline<C, F> {
+    const fn new() -> Self {
+        Self {
+            callback: None,
+            original: None,
+        }
+    }
+
+    /// Returns the callback and the service to forward to.
+    ///
+    /// The state is read at [`Tpl::HIGH_LEVEL`], so that a notification
+    /// function calling the hooked service can't interrupt a caller holding
+    /// the lock and spin forever.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the trampoline is not installed.
+    fn get(lock: &SpinLock<Self>) -> (Option<C>, F) {
+        let _tpl = raise_tpl_if_active();
+        let state = lock.lock();
+        (
+            state.callback,
+            state.original.expect("trampoline called without a state"),
+        )
+    }
+
+    /// Installs `trampoline` in the boot services table with `callback`.
+    ///
+    /// The state is set before the trampoline is installed, at
+    /// [`Tpl::HIGH_LEVEL`], so that a call from a notification function
+    /// always finds it.
+    ///
+    /// # Safety
+    ///
+    /// See [`Hook::install`].
+    unsafe fn install(
+        lock: &'static SpinLock<Self>,
+        field: fn(&mut BootServices) -> &mut F,
+        trampoline: F,
+        callback: C,
+        clear: fn(bool),
+    ) -> Result<CallbackHook<F>>
+    where
+        F: PartialEq + 'static,
+    {
+        // SAFETY: the TPL is restored when the guard is dropped.
+        let _tpl = unsafe { boot::raise_tpl(Tpl::HIGH_LEVEL) };
+        let mut state = lock.lock();
+        if state.callback.is_some() {
+            return Err(Status::ALREADY_STARTED.into());
+        }
+        state.callback = Some(callback);
+        if state.original.is_some() {
+            // The trampoline of a previous hook is still in the table, below
+            // a hook of another image, so reuse it.
+            return Ok(CallbackHook { hook: None, clear });
+        }
+        // SAFETY: the table is valid while boot services are active.
+        state.original = Some(*field(unsafe { &mut *boot_services() }));
+        drop(state);
+        // SAFETY: ensured by the caller.
+        let hook = unsafe { Hook::install(field, trampoline) };
+        Ok(CallbackHook {
+            hook: Some(hook),
+            clear,
+        })
+    }
+
+    /// Clears the state when the [`CallbackHook`] is dropped. The service to
+    /// forward to is only cleared if the trampoline was removed from the
+    /// table.
+    fn clear(lock: &SpinLock<Self>, removed: bool) {
+        let _tpl = raise_tpl_if_active();
+        let mut state = lock.lock();
+        state.callback = None;
+        if removed {
+            state.original = None;
+        }
+    }
+}
+
+/// Raises the TPL to [`Tpl::HIGH_LEVEL`] while boot services are active.
+/// Afterwards, there are no notification functions left to exclude.
+fn raise_tpl_if_active() -> Option<boot::TplGuard> {
+    // SAFETY: the TPL is restored when the guard is dropped.
+    boot::are_boot_services_active().then(|| unsafe { boot::raise_tpl(Tpl::HIGH_LEVEL) })
+}
+
+type LoadImageState = Trampoline<fn(&LoadImageCall) -> Status, LoadImageFn>;
+type ExitBootServicesState = Trampoline<fn(Handle) -> Status, ExitBootServicesFn>;
+
+static LOAD_IMAGE: SpinLock<LoadImageState> = SpinLock::new(Trampoline::new());
+static EXIT_BOOT_SERVICES: SpinLock<ExitBootServicesState> = SpinLock::new(Trampoline::new());
+
+/// Guard of a hook installed with [`hook_load_image`] or
+/// [`hook_exit_boot_services`], removing it when dropped.
+///
+/// If another hook was installed on top of it, the trampoline stays in the
+/// table and forwards calls to the original service without calling the
+/// callback. A later hook of the same service then reuses it. After exiting
+/// boot services, dropping the guard only stops calling the callback.
+#[derive(Debug)]
+pub struct CallbackHook<F: Copy + PartialEq + 'static> {
+    hook: Option<Hook<F>>,
+    clear: fn(bool),
+}
+
+impl<F: Copy + PartialEq + 'static> CallbackHook<F> {
+    /// Removes the hook. This is equivalent to dropping the guard.
+    pub fn unhook(self) {}
+}
+
+impl<F: Copy + PartialEq + 'static> Drop for CallbackHook<F> {
+    fn drop(&mut self) {
+        let removed = self.hook.as_ref().is_some_and(Hook::is_active);
+        drop(self.hook.take());
+        (self.clear)(removed);
+    }
+}
+
+/// Hooks `LoadImage`, calling `callback` before each call.
+///
+/// If `callback` returns an error status, the image is not loaded and the
+/// status is returned to the caller. Otherwise, the call is forwarded to the
+/// original service.
+///
+/// # Errors
+///
+/// * [`Status::ALREADY_STARTED`]: `LoadImage` is already hooked with this
+///   function.
+///
+/// # Safety
+///
+/// `callback` is called at the caller's TPL, and must only use services
+/// allowed there. See also [`Hook::install`].
+///
+/// # Panics
+///
+/// Panics if boot services are not active.
+pub unsafe fn hook_load_image(
+    callback: fn(&LoadImageCall) -> Status,
+) -> Result<CallbackHook<LoadImageFn>> {
+    // SAFETY: ensured by the caller.
+    unsafe {
+        Trampoline::install(
+            &LOAD_IMAGE,
+            |bs| &mut bs.load_image,
+            load_image_trampoline,
+            callback,
+            |removed| Trampoline::clear(&LOAD_IMAGE, removed),
+        )
+    }
+}
+
+unsafe extern "efiapi" fn load_image_trampoline(
+    boot_policy: Boolean,
+    parent_image_handle: RawHandle,
+    device_path: *const DevicePathProtocol,
+    source_buffer: *const u8,
+    source_size: usize,
+    image_handle: *mut RawHandle,
+) -> Status {
+    let (callback, original) = Trampoline::get(&LOAD_IMAGE);
+    let Some(callback) = callback else {
+        // SAFETY: the arguments are passed through unchanged.
+        return unsafe {
+            original(
+                boot_policy,
+                parent_image_handle,
+                device_path,
+                source_buffer,
+                source_size,
+                image_handle,
+            )
+        };
+    };
+    let call = LoadImageCall {
+        boot_policy: boot_policy.into(),
+        // SAFETY: the arguments are valid per the caller's requirements.
+        parent_image_handle: unsafe { Handle::from_ptr(parent_image_handle) },
+        device_path: (!device_path.is_null())
+            .then(|| unsafe { DevicePath::from_ffi_ptr(device_path.cast::<FfiDevicePath>()) }),
+        source_buffer: (!source_buffer.is_null())
+            .then(|| unsafe { slice::from_raw_parts(source_buffer, source_size) }),
+    };
+    let status = callback(&call);
+    if status.is_error() {
+        return status;
+    }
+    unsafe {
+        original(
+            boot_policy,
+            parent_image_handle,
+            device_path,
+            source_buffer,
+            source_size,
+            image_handle,
+        )
+    }
+}
+
+/// Hooks `ExitBootServices`, calling `callback` with the image handle before
+/// each call.
+///
+/// If `callback` returns an error status, boot services are not exited and
+/// the status is returned to the caller. Otherwise, the call is forwarded to
+/// the original service. Note that OS loaders usually retry with a new
+/// memory map key if exiting fails, so `callback` may run more than once.
+///
+/// # Errors
+///
+/// * [`Status::ALREADY_STARTED`]: `ExitBootServices` is already hooked with
+///   this function.
+///
+/// # Safety
+///
+/// `callback` must not change the memory map, e.g. by allocating memory,
+/// since the caller already obtained the map key. See also
+/// [`Hook::install`].
+///
+/// # Panics
+///
+/// Panics if boot services are not active.
+pub unsafe fn hook_exit_boot_services(
+    callback: fn(Handle) -> Status,
+) -> Result<CallbackHook<ExitBootServicesFn>> {
+    // SAFETY: ensured by the caller.
+    unsafe {
+        Trampoline::install(
+            &EXIT_BOOT_SERVICES,
+            |bs| &mut bs.exit_boot_services,
+            exit_boot_services_trampoline,
+            callback,
+            |removed| Trampoline::clear(&EXIT_BOOT_SERVICES, removed),
+        )
+    }
+}
+
+unsafe extern "efiapi" fn exit_boot_services_trampoline(
+    image_handle: RawHandle,
+    map_key: usize,
+) -> Status {
+    let (callback, original) = Trampoline::get(&EXIT_BOOT_SERVICES);
+    // SAFETY: the handle is valid per the caller's requirements.
+    if let (Some(callback), Some(handle)) = (callback, unsafe { Handle::from_ptr(image_handle) }) {
+        let status = callback(handle);
+        if status.is_error() {
+            return status;
+        }
+    }
+    unsafe { original(image_handle, map_key) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const extern "efiapi" fn exit_boot_services(_image: RawHandle, _map_key: usize) -> Status {
+        Status::SUCCESS
+    }
+
+    /// Without a system table, as after exiting boot services, dropping a
+    /// hook leaves the table alone.
+    #[test]
+    fn test_drop_without_boot_services() {
+        let hook: Hook<ExitBootServicesFn> = Hook {
+            field: |bs| &mut bs.exit_boot_services,
+            original: exit_boot_services,
+            replacement: exit_boot_services,
+        };
+        assert!(!hook.is_active());
+        drop(hook);
+
+        let hook = CallbackHook {
+            hook: Some(Hook {
+                field: |bs| &mut bs.exit_boot_services,
+                original: exit_boot_services,
+                replacement: exit_boot_services,
+            }),
+            clear: |removed| assert!(!removed),
+        };
+        drop(hook);
+    }
+}
@@ -3,6 +3,8 @@
 //! Standard UEFI tables.
 
 pub mod cfg;
+pub mod coreboot;
+pub mod hook;
 
 mod header;
 
@@ -0,0 +1,207 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Delays choosing the best mechanism for the current TPL.
+
+use crate::ResultExt;
+use crate::boot::{self, EventType, TimerTrigger, Tpl};
+use core::time::Duration;
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+use {crate::sync::ApContext, core::sync::atomic::AtomicU64, core::sync::atomic::Ordering};
+
+/// Delays shorter than this use [`boot::stall`] even at
+/// [`Tpl::APPLICATION`], since timer events only fire on timer ticks.
+const MIN_TIMER_DELAY: Duration = Duration::from_millis(10);
+
+/// Mechanism used by [`delay`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DelayMethod {
+    /// Waiting for a timer event, which lets the firmware run other
+    /// notification functions in the meantime.
+    TimerEvent,
+    /// The [`boot::stall`] boot service.
+    Stall,
+    /// Spinning on the time stamp counter, calibrated with [`calibrate`].
+    BusyWait,
+}
+
+/// Returns the current TPL.
+///
+/// The TPL can't be queried directly, so this raises it to
+/// [`Tpl::HIGH_LEVEL`] and restores it immediately.
+#[must_use]
+pub fn current_tpl() -> Tpl {
+    // SAFETY: the TPL is restored right away.
+    unsafe { boot::raise_tpl(Tpl::HIGH_LEVEL) }.old_tpl()
+}
+
+/// Returns the mechanism [`delay`] uses for `duration` at `tpl`.
+#[must_use]
+pub fn delay_method(duration: Duration, tpl: Tpl) -> DelayMethod {
+    if tpl < Tpl::CALLBACK && duration >= MIN_TIMER_DELAY && !broken_timer_events() {
+        DelayMethod::TimerEvent
+    } else if tpl >= Tpl::HIGH_LEVEL && tsc_frequency().is_some() {
+        DelayMethod::BusyWait
+    } else {
+        DelayMethod::Stall
+    }
+}
+
+/// Returns whether [`Quirks::BROKEN_TIMER_EVENTS`] is active.
+///
+/// [`Quirks::BROKEN_TIMER_EVENTS`]: crate::quirks::Quirks::BROKEN_TIMER_EVENTS
+#[cfg(feature = "alloc")]
+fn broken_timer_events() -> bool {
+    crate::quirks::is_active(crate::quirks::Quirks::BROKEN_TIMER_EVENTS)
+}
+
+#[cfg(not(feature = "alloc"))]
+const fn broken_timer_events() -> bool {
+    false
+}
+
+/// Delays execution for `duration`, with the mechanism best suited to the
+/// current TPL:
+///
+/// * At [`Tpl::APPLICATION`], waits for a timer event, so notification
+///   functions keep running. Short delays use [`boot::stall`] instead,
+///   since timer events are only as precise as the firmware's timer tick,
+///   and so do all delays if [`Quirks::BROKEN_TIMER_EVENTS`] is active.
+/// * At [`Tpl::CALLBACK`] and [`Tpl::NOTIFY`], uses [`boot::stall`].
+/// * At [`Tpl::HIGH_LEVEL`], where some firmware implements
+///   [`boot::stall`] with a timer that no longer advances, spins on the
+///   time stamp counter if it was calibrated with [`calibrate`], and falls
+///   back to [`boot::stall`] otherwise.
+///
+/// Use [`delay_on_ap`] on application processors, where boot services are
+/// not available.
+///
+/// [`Quirks::BROKEN_TIMER_EVENTS`]: crate::quirks::Quirks::BROKEN_TIMER_EVENTS
+pub fn delay(duration: Duration) {
+    match delay_method(duration, current_tpl()) {
+        DelayMethod::TimerEvent => {
+            if wait_for_timer(duration).is_err() {
+                boot::stall(duration);
+            }
+        }
+        DelayMethod::Stall => boot::stall(duration),
+        DelayMethod::BusyWait => busy_wait(duration),
+    }
+}
+
+/// Waits for a one-shot timer event.
+fn wait_for_timer(duration: Duration) -> crate::Result {
+    // SAFETY: the event has no notification function.
+    let event = unsafe { boot::create_event(EventType::TIMER, Tpl::APPLICATION, None, None)? };
+    let ticks = u64::try_from(duration.as_nanos() / 100).unwrap_or(u64::MAX);
+    // SAFETY: the event is only closed once, below.
+    let mut events = [unsafe { event.unsafe_clone() }];
+    let result = boot::set_timer(&event, TimerTrigger::Relative(ticks)).and_then(|()| {
+        boot::wait_for_event(&mut events)
+            .discard_errdata()
+            .map(|_| ())
+    });
+    let _ = boot::close_event(event);
+    result
+}
+
+/// Calibrated time stamp counter frequency in Hz, zero if not calibrated.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+static TSC_FREQUENCY: AtomicU64 = AtomicU64::new(0);
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub(crate) fn read_tsc() -> u64 {
+    #[cfg(target_arch = "x86")]
+    use core::arch::x86::_rdtsc;
+    #[cfg(target_arch = "x86_64")]
+    use core::arch::x86_64::_rdtsc;
+
+    // SAFETY: all x86 CPUs capable of running UEFI have the instruction.
+    unsafe { _rdtsc() }
+}
+
+/// Calibrates the time stamp counter against [`boot::stall`], and returns
+/// its frequency in Hz.
+///
+/// This must be called at [`Tpl::APPLICATION`] on the bootstrap processor,
+/// before [`delay`] can busy-wait at [`Tpl::HIGH_LEVEL`] or
+/// [`delay_on_ap`] can be used. It takes about 10 milliseconds. On
+/// architectures without a time stamp counter, this returns `None`.
+pub fn calibrate() -> Option<u64> {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        const PERIOD: Duration = Duration::from_millis(10);
+
+        let start = read_tsc();
+        boot::stall(PERIOD);
+        let cycles = read_tsc().wrapping_sub(start);
+        let frequency = u64::try_from(u128::from(cycles) * 1_000_000_000 / PERIOD.as_nanos())
+            .unwrap_or(u64::MAX);
+        TSC_FREQUENCY.store(frequency, Ordering::Relaxed);
+        Some(frequency)
+    }
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+    None
+}
+
+/// Returns the time stamp counter frequency in Hz measured by
+/// [`calibrate`], or `None` if it wasn't called yet.
+#[must_use]
+pub fn tsc_frequency() -> Option<u64> {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        Some(TSC_FREQUENCY.load(Ordering::Relaxed)).filter(|&frequency| frequency != 0)
+    }
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+    None
+}
+
+/// Spins on the time stamp counter for `duration`.
+fn busy_wait(duration: Duration) {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    if let Some(frequency) = tsc_frequency() {
+        let cycles = u128::from(frequency) * duration.as_nanos() / 1_000_000_000;
+        let cycles = u64::try_from(cycles).unwrap_or(u64::MAX);
+        let start = read_tsc();
+        while read_tsc().wrapping_sub(start) < cycles {
+            core::hint::spin_loop();
+        }
+        return;
+    }
+    boot::stall(duration);
+}
+
+/// Delays execution on an application processor for `duration` by spinning
+/// on the time stamp counter.
+///
+/// # Panics
+///
+/// Panics if the time stamp counter was not calibrated with [`calibrate`]
+/// on the bootstrap processor beforehand.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub fn delay_on_ap(_context: &ApContext, duration: Duration) {
+    assert!(
+        tsc_frequency().is_some(),
+        "time::calibrate must be called before delaying on an AP"
+    );
+    busy_wait(duration);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn method() {
+        let long = Duration::from_millis(100);
+        let short = Duration::from_micros(100);
+        assert_eq!(
+            delay_method(long, Tpl::APPLICATION),
+            DelayMethod::TimerEvent
+        );
+        assert_eq!(delay_method(short, Tpl::APPLICATION), DelayMethod::Stall);
+        assert_eq!(delay_method(long, Tpl::CALLBACK), DelayMethod::Stall);
+        assert_eq!(delay_method(long, Tpl::NOTIFY), DelayMethod::Stall);
+        assert_eq!(delay_method(long, Tpl::HIGH_LEVEL), DelayMethod::Stall);
+    }
+}
@@ -0,0 +1,20 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Timers, timeouts, and delays.
+//!
+//! * [`delay`]: delays execution with the mechanism best suited to the
+//!   current TPL.
+//! * [`TimerWheel`]: multiplexes many [`Timeout`]s onto a single firmware
+//!   timer event.
+
+mod delay;
+#[cfg(feature = "alloc")]
+mod wheel;
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub use delay::delay_on_ap;
+#[cfg(all(feature = "alloc", any(target_arch = "x86", target_arch = "x86_64")))]
+pub(crate) use delay::read_tsc;
+pub use delay::{DelayMethod, calibrate, current_tpl, delay, delay_method, tsc_frequency};
+#[cfg(feature = "alloc")]
+pub use wheel::{Timeout, TimerWheel};
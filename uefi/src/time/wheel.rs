@@ -0,0 +1,317 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Timer wheel multiplexing timeouts onto one timer event.
+
+use crate::boot::{self, EventType, TimerTrigger, Tpl};
+use crate::{Event, Result};
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cell::UnsafeCell;
+use core::ffi::c_void;
+use core::fmt::{self, Debug, Formatter};
+use core::future::Future;
+use core::pin::Pin;
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::{Context, Poll, Waker};
+use core::time::Duration;
+
+/// Number of slots in the wheel. Timeouts further in the future than this
+/// many ticks stay in their slot for more than one revolution.
+const SLOTS: usize = 256;
+
+/// A scheduler multiplexing any number of [`Timeout`]s onto a single
+/// periodic timer event.
+///
+/// Firmware only provides a limited number of events, so creating one timer
+/// event per timeout doesn't scale to e.g. one timeout per network
+/// connection. The wheel instead advances on each tick of one periodic
+/// timer, and expires the timeouts whose deadline passed. Timeouts are
+/// therefore rounded up to a whole number of ticks.
+///
+/// The timer's notification function runs at [`Tpl::CALLBACK`]. The wheel
+/// raises the TPL to [`Tpl::CALLBACK`] while touching state shared with it,
+/// so it must not be used from code running above that TPL.
+///
+/// Dropping the wheel closes the timer event. Timeouts that didn't expire by
+/// then never will.
+pub struct TimerWheel {
+    event: Event,
+    tick: Duration,
+    wheel: Box<UnsafeCell<Wheel>>,
+}
+
+impl TimerWheel {
+    /// Creates a wheel advancing every `tick`, which is rounded to the
+    /// 100ns resolution of firmware timers.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::OUT_OF_RESOURCES`]: the timer event could not be created.
+    ///
+    /// [`Status::OUT_OF_RESOURCES`]: crate::Status::OUT_OF_RESOURCES
+    pub fn new(tick: Duration) -> Result<Self> {
+        let wheel = Box::new(UnsafeCell::new(Wheel::new()));
+        let context = NonNull::from(&*wheel).cast();
+        // SAFETY: the notification function only touches the wheel, which
+        // lives until the event is closed.
+        let event = unsafe {
+            boot::create_event(
+                EventType::TIMER | EventType::NOTIFY_SIGNAL,
+                Tpl::CALLBACK,
+                Some(tick_notify),
+                Some(context),
+            )?
+        };
+        let period = u64::try_from(tick.as_nanos() / 100)
+            .unwrap_or(u64::MAX)
+            .max(1);
+        if let Err(err) = boot::set_timer(&event, TimerTrigger::Periodic(period)) {
+            let _ = boot::close_event(event);
+            return Err(err);
+        }
+        Ok(Self { event, tick, wheel })
+    }
+
+    /// Returns the tick period.
+    #[must_use]
+    pub const fn tick(&self) -> Duration {
+        self.tick
+    }
+
+    /// Starts a timeout expiring after `duration`, rounded up to at least
+    /// one tick.
+    #[must_use]
+    pub fn timeout(&self, duration: Duration) -> Timeout {
+        let ticks = duration
+            .as_nanos()
+            .div_ceil(self.tick.as_nanos().max(1))
+            .clamp(1, u128::from(u64::MAX));
+        let shared = Arc::new(Shared::default());
+        self.with_wheel(|wheel| wheel.insert(ticks as u64, Arc::clone(&shared)));
+        Timeout { shared }
+    }
+
+    /// Returns the number of pending timeouts, including cancelled ones that
+    /// were not removed yet.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.with_wheel(|wheel| wheel.len())
+    }
+
+    /// Returns whether there are no pending timeouts.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Runs `f` on the wheel with the timer notification blocked.
+    fn with_wheel<R>(&self, f: impl FnOnce(&mut Wheel) -> R) -> R {
+        // SAFETY: raising to the notification function's TPL keeps it from
+        // running while the wheel is borrowed.
+        let _tpl = unsafe { boot::raise_tpl(Tpl::CALLBACK) };
+        // SAFETY: see above.
+        f(unsafe { &mut *self.wheel.get() })
+    }
+}
+
+impl Drop for TimerWheel {
+    fn drop(&mut self) {
+        // SAFETY: the event is not used again.
+        let event = unsafe { self.event.unsafe_clone() };
+        let _ = boot::close_event(event);
+    }
+}
+
+impl Debug for TimerWheel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TimerWheel")
+            .field("event", &self.event)
+            .field("tick", &self.tick)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Notification function of the wheel's timer event.
+unsafe extern "efiapi" fn tick_notify(_event: Event, context: Option<NonNull<c_void>>) {
+    if let Some(context) = context {
+        // SAFETY: the context is the wheel, which outlives the event. The
+        // main code raises the TPL while borrowing it, so this has exclusive
+        // access.
+        let wheel = unsafe { &mut *context.cast::<UnsafeCell<Wheel>>().as_ref().get() };
+        wheel.advance();
+    }
+}
+
+/// State shared by a [`Timeout`] and the wheel.
+#[derive(Debug, Default)]
+struct Shared {
+    expired: AtomicBool,
+    cancelled: AtomicBool,
+    /// Waker of the task polling the timeout. Only accessed at
+    /// [`Tpl::CALLBACK`].
+    waker: UnsafeCell<Option<Waker>>,
+}
+
+// SAFETY: the waker is only accessed at `Tpl::CALLBACK`, which serializes
+// the accesses on the bootstrap processor.
+unsafe impl Sync for Shared {}
+// SAFETY: see above.
+unsafe impl Send for Shared {}
+
+impl Shared {
+    fn expire(&self) {
+        self.expired.store(true, Ordering::Release);
+        // SAFETY: the wheel calls this at `Tpl::CALLBACK`.
+        if let Some(waker) = unsafe { (*self.waker.get()).take() } {
+            waker.wake();
+        }
+    }
+}
+
+/// A timeout started with [`TimerWheel::timeout`].
+///
+/// The timeout can be polled with [`is_expired`], or awaited since it
+/// implements [`Future`]. The waker is woken from the timer's notification
+/// function at [`Tpl::CALLBACK`], so it must be safe to wake at that TPL.
+///
+/// Dropping the timeout cancels it.
+///
+/// [`is_expired`]: Self::is_expired
+#[derive(Debug)]
+pub struct Timeout {
+    shared: Arc<Shared>,
+}
+
+impl Timeout {
+    /// Returns whether the timeout expired.
+    #[must_use]
+    pub fn is_expired(&self) -> bool {
+        self.shared.expired.load(Ordering::Acquire)
+    }
+
+    /// Cancels the timeout. The wheel drops it when reaching its slot.
+    pub fn cancel(self) {}
+}
+
+impl Drop for Timeout {
+    fn drop(&mut self) {
+        self.shared.cancelled.store(true, Ordering::Release);
+    }
+}
+
+impl Future for Timeout {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.is_expired() {
+            return Poll::Ready(());
+        }
+        {
+            // SAFETY: see `TimerWheel::with_wheel`.
+            let _tpl = unsafe { boot::raise_tpl(Tpl::CALLBACK) };
+            // SAFETY: the waker is only accessed at `Tpl::CALLBACK`.
+            let waker = unsafe { &mut *self.shared.waker.get() };
+            match waker {
+                Some(waker) if waker.will_wake(cx.waker()) => {}
+                _ => *waker = Some(cx.waker().clone()),
+            }
+        }
+        // Check again in case the timeout expired before the waker was set.
+        if self.is_expired() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// A pending timeout in the wheel.
+#[derive(Debug)]
+struct Entry {
+    deadline: u64,
+    shared: Arc<Shared>,
+}
+
+/// The wheel itself, independent of the timer event.
+#[derive(Debug)]
+struct Wheel {
+    now: u64,
+    slots: Vec<Vec<Entry>>,
+}
+
+impl Wheel {
+    fn new() -> Self {
+        Self {
+            now: 0,
+            slots: (0..SLOTS).map(|_| Vec::new()).collect(),
+        }
+    }
+
+    /// Inserts a timeout expiring `ticks` ticks from now.
+    fn insert(&mut self, ticks: u64, shared: Arc<Shared>) {
+        let deadline = self.now.saturating_add(ticks);
+        self.slots[(deadline % SLOTS as u64) as usize].push(Entry { deadline, shared });
+    }
+
+    /// Advances the wheel by one tick, expiring the timeouts in the new
+    /// slot whose deadline was reached.
+    fn advance(&mut self) {
+        self.now += 1;
+        let now = self.now;
+        self.slots[(now % SLOTS as u64) as usize].retain(|entry| {
+            if entry.shared.cancelled.load(Ordering::Acquire) {
+                false
+            } else if entry.deadline <= now {
+                entry.shared.expire();
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    fn len(&self) -> usize {
+        self.slots.iter().map(Vec::len).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn insert(wheel: &mut Wheel, ticks: u64) -> Arc<Shared> {
+        let shared = Arc::new(Shared::default());
+        wheel.insert(ticks, Arc::clone(&shared));
+        shared
+    }
+
+    #[test]
+    fn expire() {
+        let mut wheel = Wheel::new();
+        let short = insert(&mut wheel, 1);
+        let long = insert(&mut wheel, SLOTS as u64 + 2);
+        let cancelled = insert(&mut wheel, 2);
+        cancelled.cancelled.store(true, Ordering::Release);
+        assert_eq!(wheel.len(), 3);
+
+        wheel.advance();
+        assert!(short.expired.load(Ordering::Acquire));
+        assert!(!long.expired.load(Ordering::Acquire));
+        assert_eq!(wheel.len(), 2);
+
+        wheel.advance();
+        assert!(!cancelled.expired.load(Ordering::Acquire));
+        assert_eq!(wheel.len(), 1);
+
+        // The long timeout survives its first pass through the slot.
+        for _ in 2..SLOTS + 1 {
+            wheel.advance();
+        }
+        assert!(!long.expired.load(Ordering::Acquire));
+        wheel.advance();
+        assert!(long.expired.load(Ordering::Acquire));
+        assert_eq!(wheel.len(), 0);
+    }
+}
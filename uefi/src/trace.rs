@@ -0,0 +1,441 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Lightweight tracing for profiling boot-time performance.
+//!
+//! Tracing records named [spans] of time, [instant] events, and [counter]
+//! values, timestamped with a high-resolution [`Clock`]. Recording is
+//! started with [`start`] and stopped with [`stop`], which returns the
+//! recorded [`Trace`]. The trace can be written in the Chrome trace-event
+//! format, e.g. to a serial port or a file, and viewed in Perfetto or
+//! `chrome://tracing`.
+//!
+//! ```no_run
+//! use uefi::proto::console::serial::Serial;
+//! use uefi::{boot, trace};
+//!
+//! # fn load_drivers() {}
+//! # fn example() -> uefi::Result {
+//! trace::start(trace::Clock::detect()?, 1024);
+//! {
+//!     let _span = trace::span("load drivers");
+//!     load_drivers();
+//! }
+//! let trace = trace::stop().unwrap();
+//!
+//! let handle = boot::get_handle_for_protocol::<Serial>()?;
+//! let mut serial = boot::open_protocol_exclusive::<Serial>(handle)?;
+//! trace.write_chrome_json(&mut *serial).unwrap();
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Events are stored in a buffer allocated by [`start`], so recording
+//! doesn't allocate and can be done at any TPL and on application
+//! processors. Events that don't fit in the buffer are dropped and counted
+//! in [`Trace::dropped`], as are events recorded while another processor,
+//! or code interrupted by an event notification, is recording an event.
+//!
+//! [spans]: span
+//! [instant]: instant
+//! [counter]: counter
+
+use crate::boot;
+use crate::proto::misc::Timestamp;
+use crate::sync::{SpinLock, SpinLockGuard};
+use crate::{Result, Status};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// Function reading the timestamp counter of the Timestamp protocol.
+type TimestampFn = unsafe extern "efiapi" fn() -> u64;
+
+#[derive(Clone, Copy, Debug)]
+enum Source {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    Tsc,
+    Timestamp(TimestampFn),
+}
+
+/// A monotonic high-resolution clock used to timestamp trace events.
+#[derive(Clone, Copy, Debug)]
+pub struct Clock {
+    source: Source,
+    frequency: u64,
+}
+
+impl Clock {
+    /// Returns a clock reading the time stamp counter, if it was calibrated
+    /// with [`time::calibrate`].
+    ///
+    /// [`time::calibrate`]: crate::time::calibrate
+    #[must_use]
+    pub fn tsc() -> Option<Self> {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            crate::time::tsc_frequency().map(|frequency| Self {
+                source: Source::Tsc,
+                frequency,
+            })
+        }
+        #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+        None
+    }
+
+    /// Returns a clock reading the counter of the [`Timestamp`] protocol.
+    ///
+    /// The counter is assumed not to roll over while tracing; check the
+    /// `end_value` of [`Timestamp::get_properties`] for short counters.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::NOT_FOUND`]: the protocol is not available.
+    /// * [`Status::UNSUPPORTED`]: the counter frequency is unknown.
+    pub fn timestamp_protocol() -> Result<Self> {
+        let handle = boot::get_handle_for_protocol::<Timestamp>()?;
+        let timestamp = boot::open_protocol_exclusive::<Timestamp>(handle)?;
+        let properties = timestamp.get_properties()?;
+        if properties.frequency == 0 {
+            return Err(Status::UNSUPPORTED.into());
+        }
+        // The function is used after closing the protocol, which is fine as
+        // long as the driver providing it isn't unloaded.
+        let get_timestamp = timestamp_fn(&timestamp);
+        Ok(Self {
+            source: Source::Timestamp(get_timestamp),
+            frequency: properties.frequency,
+        })
+    }
+
+    /// Returns the best available clock: the time stamp counter, which is
+    /// calibrated if needed, or else the [`Timestamp`] protocol.
+    ///
+    /// # Errors
+    ///
+    /// * [`Status::UNSUPPORTED`]: no clock is available.
+    pub fn detect() -> Result<Self> {
+        if let Some(clock) = Self::tsc() {
+            return Ok(clock);
+        }
+        if crate::time::calibrate().is_some() {
+            if let Some(clock) = Self::tsc() {
+                return Ok(clock);
+            }
+        }
+        Self::timestamp_protocol().map_err(|_| Status::UNSUPPORTED.into())
+    }
+
+    /// Returns the current value of the clock, in ticks.
+    #[must_use]
+    pub fn now(&self) -> u64 {
+        match self.source {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Source::Tsc => crate::time::read_tsc(),
+            // SAFETY: the function comes from the Timestamp protocol.
+            Source::Timestamp(get_timestamp) => unsafe { get_timestamp() },
+        }
+    }
+
+    /// Returns the frequency of the clock, in Hz.
+    #[must_use]
+    pub const fn frequency(&self) -> u64 {
+        self.frequency
+    }
+}
+
+fn timestamp_fn(timestamp: &Timestamp) -> TimestampFn {
+    // SAFETY: `Timestamp` is a transparent wrapper of the raw protocol.
+    let raw = unsafe {
+        &*core::ptr::from_ref(timestamp).cast::<uefi_raw::protocol::misc::TimestampProtocol>()
+    };
+    raw.get_timestamp
+}
+
+/// What happened at a trace event.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EventKind {
+    /// A span lasting the given number of ticks.
+    Span {
+        /// Duration of the span, in ticks.
+        duration: u64,
+    },
+    /// A point in time.
+    Instant,
+    /// A new value of a counter.
+    Counter {
+        /// The value.
+        value: i64,
+    },
+}
+
+/// A recorded trace event.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Event {
+    /// Name of the event.
+    pub name: &'static str,
+    /// Ticks since tracing started.
+    pub timestamp: u64,
+    /// Kind of the event.
+    pub kind: EventKind,
+}
+
+/// State of an active recording.
+#[derive(Debug)]
+struct Recording {
+    clock: Clock,
+    start: u64,
+    events: Vec<Event>,
+    dropped: usize,
+}
+
+static RECORDING: SpinLock<Option<Recording>> = SpinLock::new(None);
+
+/// Number of events dropped because [`RECORDING`] was locked.
+static CONTENDED: AtomicUsize = AtomicUsize::new(0);
+
+/// Starts recording trace events, with room for `capacity` events. A
+/// recording that is already in progress is discarded.
+///
+/// This must be called at a TPL where memory can be allocated.
+pub fn start(clock: Clock, capacity: usize) {
+    let recording = Recording {
+        clock,
+        start: clock.now(),
+        events: Vec::with_capacity(capacity),
+        dropped: 0,
+    };
+    *RECORDING.lock() = Some(recording);
+    CONTENDED.store(0, Ordering::Relaxed);
+}
+
+/// Stops recording, and returns the recorded trace, or `None` if tracing
+/// wasn't started.
+pub fn stop() -> Option<Trace> {
+    let recording = RECORDING.lock().take()?;
+    Some(Trace {
+        frequency: recording.clock.frequency,
+        events: recording.events,
+        dropped: recording.dropped + CONTENDED.swap(0, Ordering::Relaxed),
+    })
+}
+
+/// Returns whether events are being recorded.
+#[must_use]
+pub fn is_enabled() -> bool {
+    // Don't wait for the lock, in case this interrupted a recording.
+    RECORDING
+        .try_lock()
+        .is_none_or(|recording| recording.is_some())
+}
+
+/// Locks the recording without waiting, so that recording from an event
+/// notification function can't deadlock.
+fn try_lock() -> Option<SpinLockGuard<'static, Option<Recording>>> {
+    let guard = RECORDING.try_lock();
+    if guard.is_none() {
+        CONTENDED.fetch_add(1, Ordering::Relaxed);
+    }
+    guard
+}
+
+/// Returns the ticks since the start of the recording, if any.
+fn now() -> Option<u64> {
+    let recording = try_lock()?;
+    let recording = recording.as_ref()?;
+    Some(recording.clock.now().wrapping_sub(recording.start))
+}
+
+/// Records an event, if recording.
+fn record(name: &'static str, timestamp: u64, kind: EventKind) {
+    if let Some(recording) = try_lock().as_deref_mut().and_then(Option::as_mut) {
+        if recording.events.len() < recording.events.capacity() {
+            recording.events.push(Event {
+                name,
+                timestamp,
+                kind,
+            });
+        } else {
+            recording.dropped += 1;
+        }
+    }
+}
+
+/// Starts a span called `name`, which ends when the returned guard is
+/// dropped. Spans can be nested.
+#[must_use = "the span ends when the guard is dropped"]
+pub fn span(name: &'static str) -> Span {
+    Span { name, start: now() }
+}
+
+/// Records an instant event called `name`.
+pub fn instant(name: &'static str) {
+    if let Some(timestamp) = now() {
+        record(name, timestamp, EventKind::Instant);
+    }
+}
+
+/// Records a new `value` of the counter called `name`, e.g. the amount of
+/// allocated memory.
+pub fn counter(name: &'static str, value: i64) {
+    if let Some(timestamp) = now() {
+        record(name, timestamp, EventKind::Counter { value });
+    }
+}
+
+/// A span started with [`span`], recorded when dropped.
+#[derive(Debug)]
+pub struct Span {
+    name: &'static str,
+    /// Start of the span, `None` if not recording.
+    start: Option<u64>,
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        if let (Some(start), Some(end)) = (self.start, now()) {
+            let duration = end.saturating_sub(start);
+            record(self.name, start, EventKind::Span { duration });
+        }
+    }
+}
+
+/// A recorded trace, returned by [`stop`].
+#[derive(Clone, Debug)]
+pub struct Trace {
+    frequency: u64,
+    events: Vec<Event>,
+    dropped: usize,
+}
+
+impl Trace {
+    /// Returns the recorded events, in the order in which they ended.
+    #[must_use]
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// Returns the number of events that were dropped because the buffer
+    /// was full.
+    #[must_use]
+    pub const fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// Returns the frequency of the clock the events were timestamped with,
+    /// in Hz.
+    #[must_use]
+    pub const fn frequency(&self) -> u64 {
+        self.frequency
+    }
+
+    /// Writes `ticks` as microseconds with three decimals.
+    fn write_micros(&self, out: &mut impl Write, ticks: u64) -> fmt::Result {
+        let nanos = u128::from(ticks) * 1_000_000_000 / u128::from(self.frequency.max(1));
+        write!(out, "{}.{:03}", nanos / 1000, nanos % 1000)
+    }
+
+    /// Writes the trace as JSON in the Chrome trace-event format.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to `out` fails.
+    pub fn write_chrome_json(&self, out: &mut impl Write) -> fmt::Result {
+        out.write_str("{\"traceEvents\":[")?;
+        for (i, event) in self.events.iter().enumerate() {
+            if i > 0 {
+                out.write_char(',')?;
+            }
+            out.write_str("{\"name\":")?;
+            write_json_string(out, event.name)?;
+            let phase = match event.kind {
+                EventKind::Span { .. } => "X",
+                EventKind::Instant => "i",
+                EventKind::Counter { .. } => "C",
+            };
+            write!(out, ",\"ph\":\"{phase}\",\"pid\":1,\"tid\":1,\"ts\":")?;
+            self.write_micros(out, event.timestamp)?;
+            match event.kind {
+                EventKind::Span { duration } => {
+                    out.write_str(",\"dur\":")?;
+                    self.write_micros(out, duration)?;
+                }
+                EventKind::Instant => out.write_str(",\"s\":\"g\"")?,
+                EventKind::Counter { value } => write!(out, ",\"args\":{{\"value\":{value}}}")?,
+            }
+            out.write_char('}')?;
+        }
+        out.write_str("],\"displayTimeUnit\":\"ms\"}")
+    }
+
+    /// Returns the trace as JSON in the Chrome trace-event format, e.g. to
+    /// write it to a file.
+    #[must_use]
+    pub fn to_chrome_json(&self) -> String {
+        let mut json = String::new();
+        // OK to unwrap: writing to a string doesn't fail.
+        self.write_chrome_json(&mut json).unwrap();
+        json
+    }
+}
+
+/// Writes `s` as a JSON string literal.
+fn write_json_string(out: &mut impl Write, s: &str) -> fmt::Result {
+    out.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => out.write_str("\\\"")?,
+            '\\' => out.write_str("\\\\")?,
+            c if c < ' ' => write!(out, "\\u{:04x}", u32::from(c))?,
+            c => out.write_char(c)?,
+        }
+    }
+    out.write_char('"')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chrome_json() {
+        let trace = Trace {
+            frequency: 2_000_000,
+            events: alloc::vec![
+                Event {
+                    name: "inner \"quoted\"",
+                    timestamp: 3,
+                    kind: EventKind::Span { duration: 4000 },
+                },
+                Event {
+                    name: "mark",
+                    timestamp: 2001,
+                    kind: EventKind::Instant,
+                },
+                Event {
+                    name: "pages",
+                    timestamp: 2_000_000,
+                    kind: EventKind::Counter { value: -5 },
+                },
+            ],
+            dropped: 0,
+        };
+        assert_eq!(
+            trace.to_chrome_json(),
+            concat!(
+                r#"{"traceEvents":["#,
+                r#"{"name":"inner \"quoted\"","ph":"X","pid":1,"tid":1,"ts":1.500,"dur":2000.000},"#,
+                r#"{"name":"mark","ph":"i","pid":1,"tid":1,"ts":1000.500,"s":"g"},"#,
+                r#"{"name":"pages","ph":"C","pid":1,"tid":1,"ts":1000000.000,"args":{"value":-5}}"#,
+                r#"],"displayTimeUnit":"ms"}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_json_string() {
+        let mut s = String::new();
+        write_json_string(&mut s, "a\\b\n").unwrap();
+        assert_eq!(s, r#""a\\b\u000a""#);
+    }
+}
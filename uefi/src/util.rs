@@ -33,6 +33,15 @@ pub fn opt_nonnull_to_ptr<T>(opt: Option<NonNull<T>>) -> *mut T {
     opt.map(NonNull::as_ptr).unwrap_or(ptr::null_mut())
 }
 
+/// Returns a fixed-size ASCII field of device data, such as a model number,
+/// without its space or null padding. Invalid fields are returned as an
+/// empty string.
+pub fn trim_ascii_field(bytes: &[u8]) -> &str {
+    core::str::from_utf8(bytes)
+        .unwrap_or_default()
+        .trim_matches([' ', '\0'])
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -50,7 +50,10 @@ impl Package {
 pub enum Feature {
     // `uefi` features.
     Alloc,
+    Std,
     GlobalAllocator,
+    AllocTrace,
+    LeakCheck,
     LogDebugcon,
     Logger,
     Unstable,
@@ -70,7 +73,10 @@ impl Feature {
     fn as_str(&self) -> &'static str {
         match self {
             Self::Alloc => "alloc",
+            Self::Std => "std",
             Self::GlobalAllocator => "global_allocator",
+            Self::AllocTrace => "alloc_trace",
+            Self::LeakCheck => "leak_check",
             Self::LogDebugcon => "log-debugcon",
             Self::Logger => "logger",
             Self::Unstable => "unstable",
@@ -92,6 +98,8 @@ impl Feature {
             Package::Uefi => vec![
                 Self::Alloc,
                 Self::GlobalAllocator,
+                Self::AllocTrace,
+                Self::LeakCheck,
                 Self::LogDebugcon,
                 Self::Logger,
                 Self::Unstable,
@@ -117,12 +125,17 @@ impl Feature {
     /// - `include_unstable` - add all functionality behind the `unstable` feature
     /// - `runtime_features` - add all functionality that effect the runtime of Rust
     pub fn more_code(include_unstable: bool, runtime_features: bool) -> Vec<Self> {
-        let mut base_features = vec![Self::Alloc, Self::LogDebugcon, Self::Logger];
+        let mut base_features = vec![
+            Self::Alloc,
+            Self::AllocTrace,
+            Self::LogDebugcon,
+            Self::Logger,
+        ];
         if include_unstable {
             base_features.extend([Self::Unstable])
         }
         if runtime_features {
-            base_features.extend([Self::GlobalAllocator])
+            base_features.extend([Self::GlobalAllocator, Self::LeakCheck])
         }
         base_features
     }
@@ -387,19 +400,19 @@ mod tests {
     fn test_comma_separated_features() {
         assert_eq!(
             Feature::comma_separated_string(&Feature::more_code(false, false)),
-            "alloc,log-debugcon,logger"
+            "alloc,alloc_trace,log-debugcon,logger"
         );
         assert_eq!(
             Feature::comma_separated_string(&Feature::more_code(false, true)),
-            "alloc,log-debugcon,logger,global_allocator"
+            "alloc,alloc_trace,log-debugcon,logger,global_allocator,leak_check"
         );
         assert_eq!(
             Feature::comma_separated_string(&Feature::more_code(true, false)),
-            "alloc,log-debugcon,logger,unstable"
+            "alloc,alloc_trace,log-debugcon,logger,unstable"
         );
         assert_eq!(
             Feature::comma_separated_string(&Feature::more_code(true, true)),
-            "alloc,log-debugcon,logger,unstable,global_allocator"
+            "alloc,alloc_trace,log-debugcon,logger,unstable,global_allocator,leak_check"
         );
     }
 
@@ -215,10 +215,13 @@ fn run_host_tests(test_opt: &TestOpt) -> Result<()> {
     }
 
     // Run uefi-rs and uefi-macros tests with `unstable` feature.
+    // Some tests may behave differently depending on the unstable feature.
+    let mut features = Feature::more_code(*test_opt.unstable, false);
+    // The host tests also cover the standard library integration.
+    features.push(Feature::Std);
     let cargo = Cargo {
         action: CargoAction::Test,
-        // Some tests may behave differently depending on the unstable feature.
-        features: Feature::more_code(*test_opt.unstable, false),
+        features,
         packages,
         release: false,
         // Use the host target so that tests can run without a VM.